@@ -44,6 +44,10 @@ pub fn handle(
         }
     }
 
+    if params.assertions.assert_integer_only {
+        crate::utils::check_integer_only(model)?;
+    }
+
     if let Some(path) = sub_matches.value_of("nnef") {
         let nnef = super::nnef(&matches);
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
@@ -56,6 +60,18 @@ pub fn handle(
         }
     }
 
+    if let Some(path) = sub_matches.value_of("nnef-zstd") {
+        let nnef = super::nnef(&matches);
+        if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
+            rename_outputs(&mut typed, sub_matches)?;
+            let file = std::fs::File::create(path)?;
+            let encoder = zstd::stream::write::Encoder::new(file, 0)?;
+            nnef.write_to_tar(&typed, encoder).context("Writting model to tar")?.finish()?;
+        } else {
+            bail!("Only typed model can be dumped")
+        }
+    }
+
     if let Some(path) = sub_matches.value_of("nnef-tar") {
         let nnef = super::nnef(&matches);
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
@@ -80,7 +80,7 @@ fn main() -> tract_core::anyhow::Result<()> {
         .arg(arg!(verbose: -v ... "Sets the level of verbosity."))
         .arg(arg!([model] "Sets the model to use"))
         .arg(arg!(-f --format [format]
-                  "Hint the model format ('kaldi', 'onnx', 'nnef' or 'tf') instead of guess from extension."))
+                  "Hint the model format ('kaldi', 'onnx', 'nnef', 'tf' or 'tf-saved-model') instead of guess from extension."))
         .arg(Arg::new("input").long("input").short('i').multiple_occurrences(true).takes_value(true).long_help(
                   "Set input shape and type (@file.pb or @file.npz:thing.npy or 3x4xi32)."))
 
@@ -95,13 +95,16 @@ fn main() -> tract_core::anyhow::Result<()> {
 
         .arg(arg!(--"onnx-test-data-set" [data_set] "Use onnx-test data-set as input (expect test_data_set_N dir with input_X.pb, etc. inside)"))
         .arg(arg!(--"onnx-ignore-output-shapes" "Ignore output shapes from model (workaround for pytorch export bug with mask axes)"))
+        .arg(arg!(--"onnx-shared-initializers" [min_bytes] "Share initializers bigger than min_bytes with the model buffer instead of copying them"))
 
         .arg(arg!(--"input-node" [node] ... "Override input nodes names (auto-detects otherwise)."))
         .arg(arg!(--"output-node" [node] ... "Override output nodes name (auto-detects otherwise)."))
 
         .arg(arg!(--"tf-initializer-output-node" [node] "Set an initializer node"))
+        .arg(arg!(--"tf-signature" [signature] "Signature to load from a SavedModel directory (default: serving_default)"))
 
         .arg(arg!(--"override-fact" [fact] "Override a fact."))
+        .arg(arg!(--assume [assumption] ... "Declare an assumption on a symbol (S>=1, B<=32, S%8==0)."))
 
         .arg(arg!(--"analyse-fail-fast" "Stop analyse at first error."))
         .arg(arg!(--recursive "Apply to sub graphes"))
@@ -282,6 +285,12 @@ fn dump_subcommand<'a>() -> clap::Command<'a> {
             .long("nnef")
             .help("Dump the network in NNEF format (as a tar.gz file)"),
             )
+        .arg(
+            Arg::new("nnef-zstd")
+            .takes_value(true)
+            .long("nnef-zstd")
+            .help("Dump the network in NNEF format (as a tar.zst file)"),
+            )
         .arg(
             Arg::new("nnef-graph")
             .takes_value(true)
@@ -341,6 +350,11 @@ fn assertions_options(command: clap::Command) -> clap::Command {
             .long("assert-op-count")
             .help("Specified operator must appear exactly the specified number of times. This argument can appear multiple times."),
             )
+        .arg(
+            Arg::new("assert-integer-only")
+            .long("assert-integer-only")
+            .help("Check no floating point operation remains in the model, listing the offending nodes otherwise."),
+            )
 }
 
 fn benchlimits_options(command: clap::Command) -> clap::Command {
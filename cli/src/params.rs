@@ -147,6 +147,8 @@ impl Parameters {
             } else if location.path().extension().map(|s| s == "raw" || s == "txt").unwrap_or(false)
             {
                 "kaldi"
+            } else if location.is_dir() && location.path().join("saved_model.pb").exists() {
+                "tf-saved-model"
             } else if location.is_dir()
                 || location.path().to_string_lossy().ends_with(".tar")
                 || location.path().to_string_lossy().ends_with(".tar.gz")
@@ -221,6 +223,9 @@ impl Parameters {
                 if matches.is_present("onnx-ignore-output-shapes") {
                     onnx = onnx.with_ignore_output_shapes(true);
                 }
+                if let Some(min) = matches.value_of("onnx-shared-initializers") {
+                    onnx = onnx.with_shared_initializers(Some(min.parse()?));
+                }
                 info_usage("loaded framework (onnx)", probe);
                 let graph = onnx.proto_model_for_read(&mut *location.read()?)?;
                 info_usage("proto model loaded", probe);
@@ -260,6 +265,17 @@ impl Parameters {
                     (SomeGraphDef::NoGraphDef, Box::new(model_and_ext.0), Some(model_and_ext.1))
                 }
             }
+            #[cfg(feature = "tf")]
+            "tf-saved-model" => {
+                let tf = tract_tensorflow::tensorflow();
+                info_usage("loaded framework (tf)", probe);
+                let signature = matches
+                    .value_of("tf-signature")
+                    .unwrap_or(tract_tensorflow::saved_model::DEFAULT_SIGNATURE);
+                let model = tf.model_for_saved_model_dir(location.path(), signature)?;
+                info_usage("saved model loaded", probe);
+                (SomeGraphDef::NoGraphDef, Box::new(model), Option::<TfExt>::None)
+            }
             _ => bail!(
                 "Format {} not supported. You may need to recompile tract with the right features.",
                 format
@@ -708,6 +724,12 @@ impl Parameters {
     #[allow(unused_variables)]
     /// Parses the command-line arguments.
     pub fn from_clap(matches: &clap::ArgMatches, probe: Option<&Probe>) -> CliResult<Parameters> {
+        if let Some(assumptions) = matches.values_of("assume") {
+            for assumption in assumptions {
+                let (symbol, assumption) = Assumption::parse(assumption)?;
+                symbol.assume(assumption);
+            }
+        }
         let (filename, onnx_tc) = Self::disco_model(matches)?;
         let (mut graph, mut raw_model, tf_model_extensions) =
             Self::load_model(matches, probe, &filename)?;
@@ -925,6 +947,7 @@ pub struct Assertions {
     pub assert_outputs: Vec<Option<Arc<Tensor>>>,
     pub assert_output_facts: Option<Vec<InferenceFact>>,
     pub assert_op_count: Option<Vec<(String, usize)>>,
+    pub assert_integer_only: bool,
 }
 
 impl Assertions {
@@ -992,12 +1015,20 @@ impl Assertions {
                 })
                 .flatten();
 
-            Ok(Assertions { assert_outputs, assert_output_facts, assert_op_count })
+            let assert_integer_only = sub.is_present("assert-integer-only");
+
+            Ok(Assertions {
+                assert_outputs,
+                assert_output_facts,
+                assert_op_count,
+                assert_integer_only,
+            })
         } else {
             Ok(Assertions {
                 assert_outputs: vec![None; output_names.len()],
                 assert_output_facts: None,
                 assert_op_count: None,
+                assert_integer_only: false,
             })
         }
     }
@@ -55,6 +55,10 @@ pub fn handle(
         }
     }
 
+    if params.assertions.assert_integer_only {
+        crate::utils::check_integer_only(&*params.tract_model)?;
+    }
+
     Ok(())
 }
 
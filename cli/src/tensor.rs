@@ -11,8 +11,11 @@ use tract_hir::internal::*;
 fn parse_dt(dt: &str) -> CliResult<DatumType> {
     Ok(match dt.to_lowercase().as_ref() {
         "f16" => DatumType::F16,
+        "bf16" => DatumType::BF16,
         "f32" => DatumType::F32,
         "f64" => DatumType::F64,
+        "f8e4m3" => DatumType::F8E4M3,
+        "f8e5m2" => DatumType::F8E5M2,
         "i8" => DatumType::I8,
         "i16" => DatumType::I16,
         "i32" => DatumType::I32,
@@ -23,7 +26,7 @@ fn parse_dt(dt: &str) -> CliResult<DatumType> {
         "u64" => DatumType::U64,
         "tdim" => DatumType::TDim,
         _ => bail!(
-            "Type of the input should be f16, f32, f64, i8, i16, i16, i32, u8, u16, u32, u64, TDim."
+            "Type of the input should be f16, bf16, f32, f64, i8, i16, i16, i32, u8, u16, u32, u64, TDim."
             ),
     })
 }
@@ -338,7 +341,13 @@ pub fn retrieve_or_make_inputs(
         } else if allow_random {
             let fact = tract.outlet_typedfact(*input)?;
             warn_once(format!("Using random input for input called {:?}: {:?}", name, fact));
-            tmp.push(vec![crate::tensor::tensor_for_fact(&fact, None)?]);
+            let range = tract.properties().get(&format!("{}{}", INPUT_RANGE_PROPERTY_PREFIX, name));
+            if let Some(range) = range {
+                info!("Using range {:?} from model properties for input called {}", range, name);
+                tmp.push(vec![crate::tensor::tensor_for_fact_in_range(&fact, range)?]);
+            } else {
+                tmp.push(vec![crate::tensor::tensor_for_fact(&fact, None)?]);
+            }
         } else {
             bail!("Unmatched tensor {}. Fix the input or use \"--allow-random-input\" if this was intended", name);
         }
@@ -394,6 +403,24 @@ pub fn tensor_for_fact(fact: &TypedFact, streaming_dim: Option<usize>) -> CliRes
     ))
 }
 
+/// Generates a random tensor for a fact, uniformly distributed in a `[min, max]` range.
+///
+/// Falls back to `tensor_for_fact` for types that can not be scaled, like bool
+/// or quantized types.
+pub fn tensor_for_fact_in_range(fact: &TypedFact, range: &Tensor) -> CliResult<Tensor> {
+    if fact.konst.is_some() || !(fact.datum_type.is_float() || fact.datum_type.is_integer()) {
+        return tensor_for_fact(fact, None);
+    }
+    let range = range.cast_to::<f32>()?;
+    let range = range.as_slice::<f32>()?;
+    if range.len() != 2 {
+        bail!("Expected a [min, max] range, got {:?}", range)
+    }
+    let unit = tensor_for_fact(&TypedFact::dt_shape(f32::datum_type(), fact.shape.clone()), None)?;
+    let scaled = unit.into_array::<f32>()?.mapv(|x| range[0] + x * (range[1] - range[0]));
+    Ok(scaled.into_tensor().cast_to_dt(fact.datum_type)?.into_owned())
+}
+
 /// Generates a random tensor of a given size and type.
 pub fn random(sizes: &[usize], datum_type: DatumType) -> Tensor {
     use std::iter::repeat_with;
@@ -422,8 +449,10 @@ pub fn random(sizes: &[usize], datum_type: DatumType) -> Tensor {
         U8 => make::<u8>(sizes),
         U16 => make::<u16>(sizes),
         F16 => make::<f32>(sizes).cast_to::<f16>().unwrap().into_owned(),
+        BF16 => make::<f32>(sizes).cast_to::<bf16>().unwrap().into_owned(),
         F32 => make::<f32>(sizes),
         F64 => make::<f64>(sizes),
+        F8E4M3 | F8E5M2 => make::<f32>(sizes).cast_to_dt(datum_type).unwrap().into_owned(),
         QU8(_) => make::<u8>(sizes),
         QI8(_) => make::<i8>(sizes),
         QI32(_) => make::<i32>(sizes),
@@ -42,6 +42,13 @@ pub fn check_inferred(got: &[InferenceFact], expected: &[InferenceFact]) -> CliR
     Ok(())
 }
 
+pub fn check_integer_only(model: &dyn Model) -> CliResult<()> {
+    model
+        .downcast_ref::<TypedModel>()
+        .context("Can only check a typed model is integer-only")?
+        .check_integer_only()
+}
+
 pub fn count_op(model: &dyn Model, name: &str) -> CliResult<usize> {
     Ok(model
         .eval_order()
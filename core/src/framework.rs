@@ -25,12 +25,22 @@ where
         self.proto_model_for_read(&mut r)
     }
 
+    /// Parse a proto model from an in-memory buffer.
+    fn proto_model_for_bytes(&self, bytes: &[u8]) -> TractResult<ProtoModel> {
+        self.proto_model_for_read(&mut &*bytes)
+    }
+
     /// Read a model from a reader
     fn model_for_read(&self, r: &mut dyn Read) -> TractResult<Model> {
         let proto_model = self.proto_model_for_read(r).context("Reading proto model")?;
         self.model_for_proto_model(&proto_model).context("Translating proto model to model")
     }
 
+    /// Build a model from an in-memory buffer.
+    fn model_for_bytes(&self, bytes: &[u8]) -> TractResult<Model> {
+        self.model_for_read(&mut &*bytes)
+    }
+
     /// Build a model from a filename.
     fn model_for_path(&self, p: impl AsRef<Path>) -> TractResult<Model> {
         let mut r = std::fs::File::open(p.as_ref())
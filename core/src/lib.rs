@@ -92,6 +92,7 @@ pub mod prelude {
     pub use num_traits as tract_num_traits;
     pub use tract_data;
     pub use tract_linalg;
+    pub use tract_linalg::multithread::{multithread_tract_scope, set_default_executor, Executor};
 }
 
 /// This prelude is meant for code extending tract (like implementing new ops).
@@ -0,0 +1,458 @@
+//! Post-training quantization.
+//!
+//! A float model is first run over a calibration dataset, recording the
+//! range of every f32 value it computes. The calibration is then used to
+//! rewrite the float convolutions and matrix products with constant weights
+//! as quantized i8 operators: activations are quantized with the calibrated
+//! ranges, asymmetrically, weights symmetrically per output channel.
+//!
+//! Each quantized operator is surrounded by a quantization of its input and
+//! a dequantization of its output, so the model keeps its f32 interface. The
+//! declutter then folds the dequantization/quantization pairs between
+//! successive quantized operators.
+use std::collections::HashMap;
+
+use crate::internal::*;
+use crate::ops::cnn::{ConvUnary, KernelFormat};
+use crate::ops::matmul::mir_quant_unary::QMatMulUnary;
+use crate::ops::matmul::{MatMulQParams, MatMulUnary};
+use crate::ops::quant::{quantize_linear_i8, DequantizeLinearF32};
+
+/// Bins of the histograms of absolute values.
+const BINS: usize = 2048;
+
+/// Quantized levels of the positive half of the i8 range, for the entropy
+/// estimation.
+const LEVELS: usize = 128;
+
+/// How the range of a value is estimated from the calibration samples.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RangeEstimator {
+    /// Observed minimum and maximum.
+    MinMax,
+    /// Observed range, clipped to the given percentile (in ]0, 100]) of the
+    /// absolute values.
+    Percentile(f32),
+    /// Observed range, clipped to the threshold minimizing the KL divergence
+    /// between the distribution of the absolute values and its quantized
+    /// version.
+    Entropy,
+}
+
+/// Running statistics of a value over the calibration samples.
+#[derive(Clone, Debug)]
+struct Observer {
+    min: f32,
+    max: f32,
+    /// Histogram of the absolute values, over [0, hist_max].
+    hist: Vec<u64>,
+    hist_max: f32,
+}
+
+impl Default for Observer {
+    fn default() -> Observer {
+        Observer { min: 0.0, max: 0.0, hist: vec![0; BINS], hist_max: 0.0 }
+    }
+}
+
+impl Observer {
+    fn observe(&mut self, values: &[f32]) {
+        let values = || values.iter().copied().filter(|x| x.is_finite());
+        for x in values() {
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+        }
+        let abs_max = self.max.max(-self.min);
+        if abs_max == 0.0 {
+            return;
+        }
+        if self.hist_max == 0.0 {
+            self.hist_max = abs_max;
+        }
+        // widen the histogram by merging pairs of bins
+        while abs_max > self.hist_max {
+            for i in 0..BINS / 2 {
+                self.hist[i] = self.hist[2 * i] + self.hist[2 * i + 1];
+            }
+            self.hist[BINS / 2..].iter_mut().for_each(|c| *c = 0);
+            self.hist_max *= 2.0;
+        }
+        for x in values() {
+            let bin = (x.abs() / self.hist_max * BINS as f32) as usize;
+            self.hist[bin.min(BINS - 1)] += 1;
+        }
+    }
+
+    fn range(&self, estimator: RangeEstimator) -> (f32, f32) {
+        let threshold = match estimator {
+            RangeEstimator::MinMax => return (self.min, self.max),
+            _ if self.hist_max == 0.0 => return (self.min, self.max),
+            RangeEstimator::Percentile(p) => self.percentile(p),
+            RangeEstimator::Entropy => self.entropy_threshold(),
+        };
+        (self.min.max(-threshold), self.max.min(threshold))
+    }
+
+    fn bin_width(&self) -> f32 {
+        self.hist_max / BINS as f32
+    }
+
+    fn percentile(&self, p: f32) -> f32 {
+        let total: u64 = self.hist.iter().sum();
+        let target = (total as f64 * p as f64 / 100.0).ceil() as u64;
+        let mut count = 0;
+        for (bin, c) in self.hist.iter().enumerate() {
+            count += c;
+            if count >= target {
+                return (bin + 1) as f32 * self.bin_width();
+            }
+        }
+        self.hist_max
+    }
+
+    fn entropy_threshold(&self) -> f32 {
+        let mut best = (f64::INFINITY, BINS);
+        for i in LEVELS..=BINS {
+            // reference distribution: the first i bins, outliers clipped in the last one
+            let mut p: Vec<f64> = self.hist[..i].iter().map(|&c| c as f64).collect();
+            p[i - 1] += self.hist[i..].iter().sum::<u64>() as f64;
+            // candidate: the first i bins merged in LEVELS levels, spread back
+            // over the non-empty bins
+            let mut q = vec![0f64; i];
+            for level in 0..LEVELS {
+                let bins = level * i / LEVELS..(level + 1) * i / LEVELS;
+                let sum: u64 = self.hist[bins.clone()].iter().sum();
+                let non_empty = self.hist[bins.clone()].iter().filter(|&&c| c > 0).count();
+                for j in bins {
+                    if self.hist[j] > 0 {
+                        q[j] = sum as f64 / non_empty as f64;
+                    }
+                }
+            }
+            let divergence = kl_divergence(&p, &q);
+            if divergence < best.0 {
+                best = (divergence, i);
+            }
+        }
+        best.1 as f32 * self.bin_width()
+    }
+}
+
+/// KL divergence of q from p. Empty bins of q are smoothed, so that a
+/// distribution clipping a few outliers is not ruled out.
+fn kl_divergence(p: &[f64], q: &[f64]) -> f64 {
+    const EPSILON: f64 = 1e-4;
+    let p_sum: f64 = p.iter().sum();
+    let q_sum: f64 = q.iter().sum();
+    let mut divergence = 0.0;
+    for (p, q) in p.iter().zip(q) {
+        if *p > 0.0 {
+            let (p, q) = (p / p_sum, (q / q_sum).max(EPSILON));
+            divergence += p * (p / q).ln();
+        }
+    }
+    divergence
+}
+
+/// Calibrated ranges of the f32 values of a model, by outlet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Calibration {
+    pub ranges: HashMap<OutletId, (f32, f32)>,
+}
+
+impl TypedModel {
+    /// Runs the model over the dataset, and estimates the range of every
+    /// f32 value it computes.
+    pub fn calibrate<I>(&self, dataset: I, estimator: RangeEstimator) -> TractResult<Calibration>
+    where
+        I: IntoIterator<Item = TVec<Tensor>>,
+    {
+        if let RangeEstimator::Percentile(p) = estimator {
+            ensure!(p > 0.0 && p <= 100.0, "Percentile must be in ]0, 100], got {}", p);
+        }
+        let plan = SimplePlan::new(self)?;
+        let mut state = SimpleState::new(&plan)?;
+        let mut observers: HashMap<OutletId, Observer> = HashMap::new();
+        let mut samples = 0;
+        for inputs in dataset {
+            state.run_plan_with_eval(inputs, |session, op_state, node, inputs| {
+                let outputs = crate::plan::eval(session, op_state, node, inputs)?;
+                for (slot, output) in outputs.iter().enumerate() {
+                    if output.datum_type() == f32::datum_type() {
+                        observers
+                            .entry(OutletId::new(node.id, slot))
+                            .or_default()
+                            .observe(output.as_slice::<f32>()?);
+                    }
+                }
+                Ok::<_, anyhow::Error>(outputs)
+            })?;
+            samples += 1;
+        }
+        ensure!(samples > 0, "Empty calibration dataset");
+        let ranges = observers.into_iter().map(|(o, obs)| (o, obs.range(estimator))).collect();
+        Ok(Calibration { ranges })
+    }
+}
+
+/// Asymmetric i8 quantization (scale, zero point) of a range.
+fn activation_qparams((min, max): (f32, f32)) -> (f32, i32) {
+    let (min, max) = (min.min(0.0), max.max(0.0));
+    if max == min {
+        return (1.0, 0);
+    }
+    let scale = (max - min) / 255.0;
+    let zero_point = (-128.0 - min / scale).round().max(-128.0).min(127.0) as i32;
+    (scale, zero_point)
+}
+
+/// Symmetric i8 quantization of weights, with one scale per index of `axis`.
+fn quantize_weights(weights: &Tensor, axis: usize) -> TractResult<(Tensor, Tensor)> {
+    let weights = weights.to_array_view::<f32>()?;
+    let scales: Vec<f32> = weights
+        .axis_iter(tract_ndarray::Axis(axis))
+        .map(|channel| {
+            let max = channel.iter().fold(0f32, |acc, x| acc.max(x.abs()));
+            if max == 0.0 {
+                1.0
+            } else {
+                max / 127.0
+            }
+        })
+        .collect();
+    let mut quantized = weights.mapv(|_| 0i8);
+    for (c, (mut q, w)) in quantized
+        .axis_iter_mut(tract_ndarray::Axis(axis))
+        .zip(weights.axis_iter(tract_ndarray::Axis(axis)))
+        .enumerate()
+    {
+        q.zip_mut_with(&w, |q, w| *q = (w / scales[c]).round().max(-127.0).min(127.0) as i8);
+    }
+    Ok((quantized.into_tensor(), tensor1(&scales)))
+}
+
+/// Quantized i8 operator for a float convolution or matrix product, with
+/// the i8 input and output quantization parameters.
+fn quantized_op(
+    node: &TypedNode,
+    (x_scale, x0): (f32, i32),
+    (y_scale, y0): (f32, i32),
+) -> TractResult<Option<Box<dyn TypedOp>>> {
+    let params = |a_scale: Tensor| MatMulQParams {
+        a0: tensor0(0i32).into(),
+        a_scale: a_scale.into(),
+        b0: tensor0(x0).into(),
+        b_scale: tensor0(x_scale).into(),
+        c0: tensor0(y0).into(),
+        c_scale: tensor0(y_scale).into(),
+    };
+    if let Some(conv) = node.op_as::<ConvUnary>() {
+        if conv.q_params.is_some() || conv.kernel.datum_type() != f32::datum_type() {
+            return Ok(None);
+        }
+        let co = conv.output_channels();
+        let per_channel = conv.kernel_fmt == KernelFormat::OIHW || conv.group == 1;
+        let o_axis = if conv.kernel_fmt == KernelFormat::OIHW { 0 } else { conv.kernel.rank() - 1 };
+        let (kernel, scales) = if per_channel {
+            quantize_weights(&conv.kernel, o_axis)?
+        } else {
+            let (kernel, scale) = quantize_weights(
+                &conv.kernel.clone().into_tensor().into_shape(&[1, conv.kernel.len()])?,
+                0,
+            )?;
+            (kernel.into_shape(conv.kernel.shape())?, scale)
+        };
+        let bias = if let Some(bias) = &conv.bias {
+            if bias.len() != co || bias.datum_type() != f32::datum_type() {
+                return Ok(None);
+            }
+            let bias = bias.as_slice::<f32>()?;
+            let scales = scales.as_slice::<f32>()?;
+            let bias: Vec<i32> = (0..co)
+                .map(|c| (bias[c] / (scales[c % scales.len()] * x_scale)).round() as i32)
+                .collect();
+            Some(rctensor1(&bias))
+        } else {
+            None
+        };
+        Ok(Some(Box::new(ConvUnary {
+            kernel: kernel.into_arc_tensor(),
+            bias,
+            q_params: Some((i8::datum_type(), params(scales))),
+            ..conv.clone()
+        })))
+    } else if let Some(mm) = node.op_as::<MatMulUnary>() {
+        if mm.a.datum_type() != f32::datum_type() || mm.a.rank() != 2 {
+            return Ok(None);
+        }
+        let (a, scales) = quantize_weights(&mm.a, mm.a_trans as usize)?;
+        Ok(Some(Box::new(QMatMulUnary::new(
+            a.into_arc_tensor(),
+            None,
+            mm.a_trans,
+            mm.b_trans,
+            mm.c_trans,
+            i8::datum_type(),
+            params(scales),
+        ))))
+    } else {
+        Ok(None)
+    }
+}
+
+impl Calibration {
+    /// Quantizes the float convolutions and matrix products of `model`
+    /// (the calibrated one) whose input and output ranges are known. The
+    /// result is decluttered.
+    pub fn quantize(&self, model: &TypedModel) -> TractResult<TypedModel> {
+        let mut quantized = model.clone();
+        for node in model.nodes() {
+            if node.inputs.len() != 1
+                || model.outlet_fact(node.inputs[0])?.datum_type != f32::datum_type()
+            {
+                continue;
+            }
+            let (input, output) = match (
+                self.ranges.get(&node.inputs[0]),
+                self.ranges.get(&OutletId::new(node.id, 0)),
+            ) {
+                (Some(input), Some(output)) => (*input, *output),
+                _ => continue,
+            };
+            let (x_scale, x0) = activation_qparams(input);
+            let (y_scale, y0) = activation_qparams(output);
+            let op = if let Some(op) = quantized_op(node, (x_scale, x0), (y_scale, y0))? {
+                op
+            } else {
+                continue;
+            };
+            // patch the quantized model, so a quantized operator feeding this
+            // one is tapped instead of its float version
+            let mut patch = TypedModelPatch::new(format!("Quantize {}", node.name));
+            let wire = patch.tap_model(&quantized, quantized.node(node.id).inputs[0])?;
+            let wire = patch.wire_node(
+                format!("{}.quantize_input", node.name),
+                quantize_linear_i8(x_scale.recip(), x0 as i8),
+                &[wire],
+            )?;
+            let wire = patch.wire_node(format!("{}.quantized", node.name), op, &wire)?;
+            let wire =
+                patch.wire_node(&node.name, DequantizeLinearF32::new(y_scale, y0), &wire)?[0];
+            patch.shunt_outside(&quantized, node.id.into(), wire)?;
+            patch.apply(&mut quantized)?;
+        }
+        quantized.into_decluttered()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+
+    #[test]
+    fn estimate_ranges() {
+        let mut obs = Observer::default();
+        let mut values: Vec<f32> = (0..1000).map(|i| (i % 200) as f32 / 100.0 - 1.0).collect();
+        values.push(50.0);
+        obs.observe(&values[..500]);
+        obs.observe(&values[500..]);
+        assert_eq!(obs.range(RangeEstimator::MinMax), (-1.0, 50.0));
+        let (min, max) = obs.range(RangeEstimator::Percentile(99.0));
+        assert_eq!(min, -1.0);
+        assert!(max > 0.95 && max < 1.1, "{}", max);
+        let (min, max) = obs.range(RangeEstimator::Entropy);
+        assert_eq!(min, -1.0);
+        assert!(max > 0.5 && max < 25.0, "{}", max);
+    }
+
+    fn conv(ci: usize, co: usize) -> TractResult<ConvUnary> {
+        let kernel: Vec<f32> =
+            (0..co * ci * 9).map(|i| ((i * 7) % 11) as f32 / 5.0 - 1.0).collect();
+        let kernel = tensor1(&kernel).into_shape(&[co, ci, 3, 3])?;
+        let pool_spec = PoolSpec::new(
+            DataFormat::NCHW,
+            tvec!(3, 3),
+            PaddingSpec::SameUpper,
+            None,
+            None,
+            Some(co),
+        );
+        let bias: Vec<f32> = (0..co).map(|i| i as f32 / 2.0 - 0.5).collect();
+        Ok(ConvUnary::new(
+            pool_spec,
+            Default::default(),
+            kernel.into_arc_tensor(),
+            1,
+            Some(rctensor1(&bias)),
+            None,
+        ))
+    }
+
+    fn sample(s: usize) -> TVec<Tensor> {
+        let x: Vec<f32> = (0..108).map(|i| (((i + s) * 5) % 13) as f32 / 6.0 - 1.0).collect();
+        tvec!(tensor1(&x).into_shape(&[1, 3, 6, 6]).unwrap())
+    }
+
+    fn check_quantized(
+        model: TypedModel,
+        calibration: &Calibration,
+        quantized: TypedModel,
+    ) -> TractResult<()> {
+        let (min, max) = calibration.ranges[&model.output_outlets()?[0]];
+        let expected = model.into_runnable()?.run(sample(1))?;
+        let found = quantized.into_optimized()?.into_runnable()?.run(sample(1))?;
+        let tolerance = (max - min) / 255.0 * 8.0;
+        let expected = expected[0].as_slice::<f32>()?;
+        for (f, e) in found[0].as_slice::<f32>()?.iter().zip(expected) {
+            assert!((f - e).abs() <= tolerance, "{} vs {}", f, e);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn quantize_calibrated_model() -> TractResult<()> {
+        let conv = conv(3, 4)?;
+        let a: Vec<f32> = (0..5 * 4).map(|i| ((i * 3) % 7) as f32 / 3.0 - 1.0).collect();
+        let a = tensor1(&a).into_shape(&[5, 4])?.into_arc_tensor();
+        let mm = MatMulUnary { a, a_trans: false, b_trans: false, c_trans: false };
+        let mut model = TypedModel::default();
+        let wire = model.add_source("input", f32::fact(&[1, 3, 6, 6]))?;
+        let wire = model.wire_node("conv", conv, &[wire])?;
+        let reshape = AxisOp::Reshape(
+            0,
+            tvec!(1.into(), 4.into(), 6.into(), 6.into()),
+            tvec!(4.into(), 36.into()),
+        );
+        let wire = model.wire_node("reshape", reshape, &wire)?;
+        let wire = model.wire_node("mm", mm, &wire)?;
+        model.set_output_outlets(&wire)?;
+
+        let calibration = model.calibrate((0..4).map(sample), RangeEstimator::MinMax)?;
+        let quantized = calibration.quantize(&model)?;
+        assert!(quantized
+            .nodes()
+            .iter()
+            .any(|n| n.op_as::<ConvUnary>().map(|c| c.q_params.is_some()) == Some(true)));
+        assert!(quantized.nodes().iter().any(|n| n.op_is::<QMatMulUnary>()));
+        check_quantized(model, &calibration, quantized)
+    }
+
+    #[test]
+    fn quantize_chained_convolutions() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let wire = model.add_source("input", f32::fact(&[1, 3, 6, 6]))?;
+        let wire = model.wire_node("conv.0", conv(3, 4)?, &[wire])?;
+        let wire = model.wire_node("conv.1", conv(4, 2)?, &wire)?;
+        model.set_output_outlets(&wire)?;
+
+        let calibration = model.calibrate((0..4).map(sample), RangeEstimator::MinMax)?;
+        let quantized = calibration.quantize(&model)?;
+        let convs: Vec<&ConvUnary> =
+            quantized.nodes().iter().filter_map(|n| n.op_as::<ConvUnary>()).collect();
+        assert_eq!(convs.len(), 2);
+        assert!(convs.iter().all(|c| c.q_params.is_some()));
+        check_quantized(model, &calibration, quantized)
+    }
+}
@@ -0,0 +1,170 @@
+//! Static cost estimation of a model: floating point operations, parameters
+//! and activations, per node and for the whole model.
+//!
+//! Measures are symbolic when the model shapes are: evaluate them with the
+//! actual symbol values to compare models, or to balance a partitioning.
+use crate::internal::*;
+use crate::ops::konst::Const;
+
+/// Estimated cost of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeCost {
+    pub node: usize,
+    pub name: String,
+    pub op: String,
+    /// Arithmetic operations: two per multiply-accumulate, one per division.
+    pub flops: TDim,
+    /// Bytes of the constants: the tensor of a Const node, or the weights
+    /// embedded in an operator.
+    pub param_bytes: TDim,
+    /// Bytes of the inputs read by the node.
+    pub input_bytes: TDim,
+    /// Bytes of the outputs written by the node, zero for a Const node.
+    pub output_bytes: TDim,
+}
+
+impl NodeCost {
+    /// Operations per byte read or written by the node (parameters, inputs
+    /// and outputs), for the given symbol values.
+    pub fn arithmetic_intensity(&self, symbols: &SymbolValues) -> TractResult<f64> {
+        let bytes = self.param_bytes.clone() + &self.input_bytes + &self.output_bytes;
+        intensity(&self.flops, &bytes, symbols)
+    }
+}
+
+/// Totals of a `CostReport`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostTotals {
+    pub flops: TDim,
+    pub param_bytes: TDim,
+    /// Bytes of all the values computed by the model, counting each one once.
+    pub activation_bytes: TDim,
+}
+
+impl CostTotals {
+    /// Operations per byte of parameters and activations, for the given
+    /// symbol values.
+    pub fn arithmetic_intensity(&self, symbols: &SymbolValues) -> TractResult<f64> {
+        intensity(&self.flops, &(self.param_bytes.clone() + &self.activation_bytes), symbols)
+    }
+}
+
+/// Costs of the nodes of a model, in node order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostReport {
+    pub nodes: Vec<NodeCost>,
+}
+
+impl CostReport {
+    pub fn total(&self) -> CostTotals {
+        let mut total =
+            CostTotals { flops: 0.to_dim(), param_bytes: 0.to_dim(), activation_bytes: 0.to_dim() };
+        for node in &self.nodes {
+            total.flops += &node.flops;
+            total.param_bytes += &node.param_bytes;
+            total.activation_bytes += &node.output_bytes;
+        }
+        total
+    }
+
+    /// FLOPs of each node relative to the most expensive one, between 0.0
+    /// and 1.0, for the given symbol values. Meant to color a rendering of
+    /// the model, see `DotOptions::heat`.
+    pub fn flops_heat(&self, symbols: &SymbolValues) -> TractResult<HashMap<usize, f64>> {
+        let flops = self
+            .nodes
+            .iter()
+            .map(|n| Ok((n.node, n.flops.eval(symbols).to_i64()? as f64)))
+            .collect::<TractResult<Vec<(usize, f64)>>>()?;
+        let max = flops.iter().map(|f| f.1).fold(0.0, f64::max);
+        Ok(flops.into_iter().map(|(n, f)| (n, if max > 0.0 { f / max } else { 0.0 })).collect())
+    }
+}
+
+fn intensity(flops: &TDim, bytes: &TDim, symbols: &SymbolValues) -> TractResult<f64> {
+    let flops = flops.eval(symbols).to_i64()?;
+    let bytes = bytes.eval(symbols).to_i64()?;
+    Ok(if bytes == 0 { 0.0 } else { flops as f64 / bytes as f64 })
+}
+
+fn fact_bytes(fact: &TypedFact) -> TDim {
+    fact.shape.volume() * fact.datum_type.size_of()
+}
+
+impl TypedModel {
+    /// Estimates the cost of every node of the model, from the cost reported
+    /// by the operators and the facts of their inputs and outputs.
+    pub fn cost(&self) -> TractResult<CostReport> {
+        let mut nodes = vec![];
+        for node in self.nodes() {
+            let (inputs, outputs) = self.node_facts(node.id)?;
+            let mut cost = NodeCost {
+                node: node.id,
+                name: node.name.clone(),
+                op: node.op().name().to_string(),
+                flops: 0.to_dim(),
+                param_bytes: 0.to_dim(),
+                input_bytes: inputs.iter().map(|f| fact_bytes(f)).sum(),
+                output_bytes: 0.to_dim(),
+            };
+            if node.op_is::<Const>() {
+                cost.param_bytes = outputs.iter().map(|f| fact_bytes(f)).sum();
+            } else {
+                cost.output_bytes = outputs.iter().map(|f| fact_bytes(f)).sum();
+                for (c, count) in
+                    node.op.cost(&inputs).with_context(|| format!("Costing {}", node))?
+                {
+                    match c {
+                        Cost::FMA(_) => cost.flops += count * 2,
+                        Cost::Div(_) => cost.flops += count,
+                        Cost::Params(dt) => cost.param_bytes += count * dt.size_of(),
+                        Cost::Buffer(_) => (),
+                    }
+                }
+            }
+            nodes.push(cost);
+        }
+        Ok(CostReport { nodes })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::matmul::MatMulUnary;
+
+    #[test]
+    fn matmul_cost() -> TractResult<()> {
+        let s = Symbol::new('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("b", f32::fact(&[8.to_dim(), s.to_dim()]))?;
+        let a = Tensor::zero::<f32>(&[4, 8])?.into_arc_tensor();
+        let op = MatMulUnary { a, a_trans: false, b_trans: false, c_trans: false };
+        let mm = model.wire_node("mm", op, &[source])?;
+        let bias = model.add_const("bias", Tensor::zero::<f32>(&[4, 1])?)?;
+        let add = model.wire_node("add", crate::ops::math::add::bin_typed(), &[mm[0], bias])?;
+        model.set_output_outlets(&add)?;
+
+        let report = model.cost()?;
+        let mm = &report.nodes[model.node_id_by_name("mm")?];
+        assert_eq!(mm.flops, s.to_dim() * 64);
+        assert_eq!(mm.param_bytes, 128.to_dim());
+        assert_eq!(mm.input_bytes, s.to_dim() * 32);
+        assert_eq!(mm.output_bytes, s.to_dim() * 16);
+        let bias = &report.nodes[model.node_id_by_name("bias")?];
+        assert_eq!(bias.param_bytes, 16.to_dim());
+        assert_eq!(bias.output_bytes, 0.to_dim());
+
+        let symbols = SymbolValues::default().with(s, 10);
+        assert_eq!(mm.arithmetic_intensity(&symbols)?, 640.0 / (128.0 + 320.0 + 160.0));
+        let total = report.total();
+        assert_eq!(total.param_bytes, 144.to_dim());
+        assert_eq!(total.activation_bytes.eval(&symbols), 640.to_dim());
+        assert!(total.arithmetic_intensity(&symbols)? > 0.0);
+        assert!(total.arithmetic_intensity(&SymbolValues::default()).is_err());
+        let heat = report.flops_heat(&symbols)?;
+        assert_eq!(heat[&model.node_id_by_name("mm")?], 1.0);
+        assert_eq!(heat[&model.node_id_by_name("bias")?], 0.0);
+        Ok(())
+    }
+}
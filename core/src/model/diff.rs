@@ -0,0 +1,209 @@
+//! Comparison of two typed models.
+//!
+//! Nodes are matched by name, so this is meant for models of the same
+//! origin: two exports of the same network, or a model before and after a
+//! transformation keeping the node names. Constants are compared value by
+//! value, to report how much the weights moved.
+use std::collections::HashMap;
+
+use crate::internal::*;
+use crate::ops::konst::Const;
+
+/// Difference of the values of a constant present in both models, with the
+/// same datum type and shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightDelta {
+    pub name: String,
+    pub max_abs_diff: f64,
+    pub mean_abs_diff: f64,
+}
+
+/// Differences between two models, from the point of view of the first one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelDiff {
+    /// Differences in the inputs and outputs of the models.
+    pub interface: Vec<String>,
+    /// Names of the nodes only found in the first model.
+    pub removed: Vec<String>,
+    /// Names of the nodes only found in the second model.
+    pub added: Vec<String>,
+    /// Nodes found in both models, with a different operator, inputs or
+    /// output facts, and the description of the differences.
+    pub changed: Vec<(String, Vec<String>)>,
+    /// Value differences of the numeric constants found in both models.
+    pub weights: Vec<WeightDelta>,
+}
+
+impl ModelDiff {
+    /// True if the models have the same interface, nodes and wiring,
+    /// whatever the values of their constants.
+    pub fn same_structure(&self) -> bool {
+        self.interface.is_empty()
+            && self.removed.is_empty()
+            && self.added.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// The largest difference between the values of a constant of both
+    /// models.
+    pub fn max_weight_delta(&self) -> f64 {
+        self.weights.iter().map(|w| w.max_abs_diff).fold(0.0, f64::max)
+    }
+}
+
+fn outlet_name(model: &TypedModel, outlet: OutletId) -> String {
+    format!("{}:{}", model.node(outlet.node).name, outlet.slot)
+}
+
+fn describe_outlets(model: &TypedModel, outlets: &[OutletId]) -> TractResult<Vec<String>> {
+    outlets
+        .iter()
+        .map(|&o| Ok(format!("{} {:?}", outlet_name(model, o), model.outlet_fact(o)?)))
+        .collect()
+}
+
+fn weight_delta(name: &str, a: &Tensor, b: &Tensor) -> TractResult<Option<WeightDelta>> {
+    if a.datum_type() == String::datum_type() || a.datum_type() == TDim::datum_type() {
+        return Ok(None);
+    }
+    let (a, b) = (a.cast_to::<f64>()?, b.cast_to::<f64>()?);
+    let (a, b) = (a.as_slice::<f64>()?, b.as_slice::<f64>()?);
+    let mut delta = WeightDelta { name: name.to_string(), max_abs_diff: 0.0, mean_abs_diff: 0.0 };
+    for (x, y) in a.iter().zip(b) {
+        let diff = if x == y { 0.0 } else { (x - y).abs() };
+        delta.max_abs_diff = delta.max_abs_diff.max(diff);
+        delta.mean_abs_diff += diff;
+    }
+    if !a.is_empty() {
+        delta.mean_abs_diff /= a.len() as f64;
+    }
+    Ok(Some(delta))
+}
+
+impl TypedModel {
+    /// Compares the model to `other`, matching the nodes by name.
+    pub fn diff(&self, other: &TypedModel) -> TractResult<ModelDiff> {
+        let mut diff = ModelDiff::default();
+        for (what, mine, theirs) in &[
+            ("inputs", self.input_outlets()?, other.input_outlets()?),
+            ("outputs", self.output_outlets()?, other.output_outlets()?),
+        ] {
+            let (mine, theirs) = (describe_outlets(self, mine)?, describe_outlets(other, theirs)?);
+            if mine != theirs {
+                diff.interface.push(format!("{}: {:?} vs {:?}", what, mine, theirs));
+            }
+        }
+        let theirs: HashMap<&str, &TypedNode> =
+            other.nodes().iter().map(|n| (&*n.name, n)).collect();
+        for node in self.nodes() {
+            let their = if let Some(their) = theirs.get(&*node.name) {
+                their
+            } else {
+                diff.removed.push(node.name.clone());
+                continue;
+            };
+            let mut changes = vec![];
+            if node.op().name() != their.op().name() {
+                changes.push(format!("op: {} vs {}", node.op().name(), their.op().name()));
+            } else if node.op().info()? != their.op().info()? {
+                changes.push(format!(
+                    "parameters: {:?} vs {:?}",
+                    node.op().info()?,
+                    their.op().info()?
+                ));
+            }
+            let inputs: Vec<String> = node.inputs.iter().map(|&i| outlet_name(self, i)).collect();
+            let their_inputs: Vec<String> =
+                their.inputs.iter().map(|&i| outlet_name(other, i)).collect();
+            if inputs != their_inputs {
+                changes.push(format!("inputs: {:?} vs {:?}", inputs, their_inputs));
+            }
+            let facts: Vec<(DatumType, &ShapeFact)> =
+                node.outputs.iter().map(|o| (o.fact.datum_type, &o.fact.shape)).collect();
+            let their_facts: Vec<(DatumType, &ShapeFact)> =
+                their.outputs.iter().map(|o| (o.fact.datum_type, &o.fact.shape)).collect();
+            if facts != their_facts {
+                changes.push(format!("outputs: {:?} vs {:?}", facts, their_facts));
+            } else if let (Some(mine), Some(their)) =
+                (node.op_as::<Const>(), their.op_as::<Const>())
+            {
+                if let Some(delta) = weight_delta(&node.name, &mine.0, &their.0)? {
+                    diff.weights.push(delta);
+                } else if mine.0 != their.0 {
+                    changes.push("constant value".to_string());
+                }
+            }
+            if !changes.is_empty() {
+                diff.changed.push((node.name.clone(), changes));
+            }
+        }
+        let mine: std::collections::HashSet<&str> = self.nodes().iter().map(|n| &*n.name).collect();
+        diff.added = other
+            .nodes()
+            .iter()
+            .filter(|n| !mine.contains(&*n.name))
+            .map(|n| n.name.clone())
+            .collect();
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(weights: &[f32], activation: Box<dyn TypedOp>) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let w = model.add_const("w", tensor1(weights))?;
+        let mul = model.wire_node("mul", crate::ops::math::mul::bin_typed(), &[source, w])?;
+        let act = model.wire_node("act", activation, &mul)?;
+        model.set_output_outlets(&act)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn same_model() -> TractResult<()> {
+        let a = model(&[1.0, 2.0], Box::new(crate::ops::math::exp()))?;
+        let diff = a.diff(&a)?;
+        assert!(diff.same_structure());
+        assert_eq!(diff.max_weight_delta(), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn weights_and_structure() -> TractResult<()> {
+        let a = model(&[1.0, 2.0], Box::new(crate::ops::math::exp()))?;
+        let b = model(&[1.0, 2.5], Box::new(crate::ops::math::exp()))?;
+        let diff = a.diff(&b)?;
+        assert!(diff.same_structure());
+        assert_eq!(
+            diff.weights,
+            vec![WeightDelta { name: "w".into(), max_abs_diff: 0.5, mean_abs_diff: 0.25 }]
+        );
+
+        let mut c = model(&[1.0, 2.0], Box::new(crate::ops::math::tanh()))?;
+        let act = c.output_outlets()?[0];
+        c.wire_node("extra", crate::ops::math::abs(), &[act])?;
+        let diff = a.diff(&c)?;
+        assert!(!diff.same_structure());
+        assert!(diff.interface.is_empty());
+        assert_eq!(diff.added, vec!["extra"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0, "act");
+        assert!(diff.changed[0].1[0].starts_with("op: Exp vs Tanh"));
+        Ok(())
+    }
+
+    #[test]
+    fn interface() -> TractResult<()> {
+        let a = model(&[1.0, 2.0], Box::new(crate::ops::math::exp()))?;
+        let mut b = a.clone();
+        b.set_output_outlets(&[OutletId::new(b.node_id_by_name("mul")?, 0)])?;
+        let diff = a.diff(&b)?;
+        assert_eq!(diff.interface.len(), 1);
+        assert!(diff.interface[0].starts_with("outputs:"));
+        Ok(())
+    }
+}
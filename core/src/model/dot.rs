@@ -0,0 +1,111 @@
+//! Graphviz rendering of a model.
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Write};
+
+use crate::internal::*;
+use crate::model::{Fact, Graph};
+
+/// What `Graph::to_dot_with` renders.
+#[derive(Debug, Clone)]
+pub struct DotOptions {
+    /// Label the edges with the facts of the values they carry.
+    pub facts: bool,
+    /// Add the operator annotations (see `Op::info`) to the node labels.
+    pub info: bool,
+    /// Fill the nodes with a shade of red, from white for 0.0 to red for
+    /// 1.0, by node id. See `CostReport::flops_heat`.
+    pub heat: Option<HashMap<usize, f64>>,
+}
+
+impl Default for DotOptions {
+    fn default() -> DotOptions {
+        DotOptions { facts: true, info: true, heat: None }
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl<F, O> Graph<F, O>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    /// Renders the model in the Graphviz DOT language, with the default
+    /// options.
+    pub fn to_dot(&self) -> TractResult<String> {
+        self.to_dot_with(&DotOptions::default())
+    }
+
+    /// Renders the model in the Graphviz DOT language. Model inputs and
+    /// outputs are drawn with a double border.
+    pub fn to_dot_with(&self, options: &DotOptions) -> TractResult<String> {
+        let inputs = self.input_outlets()?;
+        let outputs = self.output_outlets()?;
+        let mut dot = String::new();
+        writeln!(dot, "digraph model {{")?;
+        writeln!(dot, "  node [shape=box, fontname=\"monospace\"];")?;
+        for node in self.nodes() {
+            let mut label = format!("{}\n{}", node.name, node.op().name());
+            if options.info {
+                for info in node.op().info()? {
+                    label.push('\n');
+                    label.push_str(&info);
+                }
+            }
+            let mut attributes = format!("label=\"{}\"", escape(&label));
+            if inputs.iter().chain(outputs.iter()).any(|o| o.node == node.id) {
+                attributes.push_str(", peripheries=2");
+            }
+            if let Some(heat) = options.heat.as_ref().and_then(|h| h.get(&node.id)) {
+                let heat = heat.max(0.0).min(1.0);
+                write!(attributes, ", style=filled, fillcolor=\"0.000 {:.3} 1.000\"", heat)?;
+            }
+            writeln!(dot, "  n{} [{}];", node.id, attributes)?;
+        }
+        for node in self.nodes() {
+            for (slot, output) in node.outputs.iter().enumerate() {
+                for succ in &output.successors {
+                    write!(dot, "  n{} -> n{}", node.id, succ.node)?;
+                    if options.facts {
+                        let mut label = format!("{:?}", output.fact);
+                        if node.outputs.len() > 1 {
+                            label = format!("#{} {}", slot, label);
+                        }
+                        write!(dot, " [label=\"{}\"]", escape(&label))?;
+                    }
+                    writeln!(dot, ";")?;
+                }
+            }
+        }
+        writeln!(dot, "}}")?;
+        Ok(dot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dot() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let exp = model.wire_node("exp \"quoted\"", crate::ops::math::exp(), &neg)?;
+        model.set_output_outlets(&exp)?;
+        let dot = model.to_dot()?;
+        assert!(dot.starts_with("digraph model {\n"));
+        assert!(dot.contains("n0 [label=\"input\\nSource"));
+        assert!(dot.contains("n2 [label=\"exp \\\"quoted\\\"\\nExp\", peripheries=2];"));
+        assert!(dot.contains("n0 -> n1 [label=\"3,F32\"];"));
+        let heat = vec![(1, 0.5)].into_iter().collect();
+        let options = DotOptions { facts: false, heat: Some(heat), ..DotOptions::default() };
+        let dot = model.to_dot_with(&options)?;
+        assert!(dot
+            .contains("n1 [label=\"neg\\nNeg\", style=filled, fillcolor=\"0.000 0.500 1.000\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        Ok(())
+    }
+}
@@ -153,6 +153,50 @@ impl AsRef<[TDim]> for ShapeFact {
     }
 }
 
+/// Known bounds of the values of an integer tensor: they are all in
+/// `[start, end)`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ValueRange {
+    pub start: TDim,
+    pub end: TDim,
+}
+
+impl ValueRange {
+    pub fn new(start: impl ToDim, end: impl ToDim) -> ValueRange {
+        ValueRange { start: start.to_dim(), end: end.to_dim() }
+    }
+
+    /// The range of the values of a non-empty integer tensor.
+    pub fn of_tensor(t: &Tensor) -> Option<ValueRange> {
+        if !(t.datum_type().is_integer() || t.datum_type() == TDim::datum_type()) || t.len() == 0 {
+            return None;
+        }
+        let t = t.cast_to::<i64>().ok()?;
+        let values = t.as_slice::<i64>().ok()?;
+        let min = values.iter().min()?;
+        let max = values.iter().max()?;
+        Some(ValueRange::new(*min, *max + 1))
+    }
+
+    /// Proves all the values are greater or equal to `v`.
+    pub fn at_least(&self, v: &TDim) -> bool {
+        (self.start.clone() - v).low_bound().map(|b| b >= 0).unwrap_or(false)
+    }
+
+    /// Proves all the values are strictly less than `v`.
+    pub fn below(&self, v: &TDim) -> bool {
+        (v.clone() - &self.end).low_bound().map(|b| b >= 0).unwrap_or(false)
+    }
+
+    /// The smallest range including both ranges.
+    pub fn union(&self, other: &ValueRange) -> ValueRange {
+        ValueRange {
+            start: self.start.clone().mini(other.start.clone()),
+            end: self.end.clone().maxi(other.end.clone()),
+        }
+    }
+}
+
 /// Fully determined tensor information for TypedModel.
 #[derive(Clone, PartialEq, Hash)]
 pub struct TypedFact {
@@ -164,6 +208,8 @@ pub struct TypedFact {
     pub konst: Option<Arc<Tensor>>,
     /// optional uniform value
     pub uniform: Option<Arc<Tensor>>,
+    /// optional bounds of integer values
+    pub range: Option<ValueRange>,
 }
 
 impl_dyn_hash!(TypedFact);
@@ -187,14 +233,20 @@ impl TypedFact {
 
     pub fn dt_scalar(datum_type: DatumType) -> TypedFact {
         let foo: &[usize] = &[];
-        TypedFact { datum_type, shape: ShapeFact::from(foo), konst: None, uniform: None }
+        TypedFact {
+            datum_type,
+            shape: ShapeFact::from(foo),
+            konst: None,
+            uniform: None,
+            range: None,
+        }
     }
 
     pub fn dt_shape<S>(datum_type: DatumType, shape: S) -> TypedFact
     where
         S: Into<ShapeFact>,
     {
-        TypedFact { datum_type, shape: shape.into(), konst: None, uniform: None }
+        TypedFact { datum_type, shape: shape.into(), konst: None, uniform: None, range: None }
     }
 
     pub fn rank(&self) -> usize {
@@ -245,6 +297,16 @@ impl TypedFact {
     pub fn without_value(&self) -> Self {
         Self::dt_shape(self.datum_type, self.shape.clone())
     }
+
+    /// Bounds of the values, from the analysis or from the constant value.
+    pub fn value_range(&self) -> Option<ValueRange> {
+        self.range.clone().or_else(|| self.konst.as_deref().and_then(ValueRange::of_tensor))
+    }
+
+    pub fn with_range(mut self, range: Option<ValueRange>) -> Self {
+        self.range = range;
+        self
+    }
 }
 
 impl Fact for TypedFact {
@@ -308,6 +370,7 @@ impl From<Arc<Tensor>> for TypedFact {
             shape: ShapeFact::from_dims(t.shape().iter().map(TDim::from)),
             uniform: t.as_uniform().map(Arc::new),
             konst: Some(t),
+            range: None,
         }
     }
 }
@@ -321,10 +384,14 @@ impl<'a> From<&'a TypedFact> for TypedFact {
 impl fmt::Debug for TypedFact {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self.konst {
-            Some(ref k) => write!(fmt, "{:?}", k),
-            None if self.rank() > 0 => write!(fmt, "{:?},{:?}", self.shape, self.datum_type),
-            None => write!(fmt, "{:?}", self.datum_type),
+            Some(ref k) => write!(fmt, "{:?}", k)?,
+            None if self.rank() > 0 => write!(fmt, "{:?},{:?}", self.shape, self.datum_type)?,
+            None => write!(fmt, "{:?}", self.datum_type)?,
         }
+        if let (None, Some(range)) = (&self.konst, &self.range) {
+            write!(fmt, " in [{},{})", range.start, range.end)?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,404 @@
+//! Reading GGUF weight files, as used in the llama.cpp ecosystem.
+//!
+//! F32, F16 and BF16 tensors are loaded as regular tensors. Q4_0, Q8_0 and Q4_K
+//! tensors are kept in their block-quantized layout: they can either be
+//! dequantized, or fed to a `BlockQuantMatMul` which consumes the blocks
+//! directly.
+//!
+//! GGUF dimensions are listed innermost first: they are reversed here, so a
+//! linear layer weight with k inputs and m outputs is a [m, k] tensor.
+use std::convert::TryInto;
+use std::path::Path;
+
+use crate::internal::*;
+use crate::ops::matmul::BlockQuantMatMul;
+use tract_linalg::block_quant::{BlockQuant, Q4K, Q4_0, Q8_0};
+
+const MAGIC: &[u8] = b"GGUF";
+const DEFAULT_ALIGNMENT: usize = 32;
+
+/// A metadata value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GgufValue {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    String(String),
+    Array(Vec<GgufValue>),
+}
+
+impl GgufValue {
+    /// Integer values, whatever their width.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            GgufValue::U8(v) => Some(*v as u64),
+            GgufValue::U16(v) => Some(*v as u64),
+            GgufValue::U32(v) => Some(*v as u64),
+            GgufValue::U64(v) => Some(*v),
+            GgufValue::I8(v) => (*v).try_into().ok(),
+            GgufValue::I16(v) => (*v).try_into().ok(),
+            GgufValue::I32(v) => (*v).try_into().ok(),
+            GgufValue::I64(v) => (*v).try_into().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let GgufValue::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum GgufTensor {
+    Dense(Tensor),
+    BlockQuant { format: Box<dyn BlockQuant>, shape: TVec<usize>, blocks: Arc<Tensor> },
+}
+
+impl GgufTensor {
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            GgufTensor::Dense(t) => t.shape(),
+            GgufTensor::BlockQuant { shape, .. } => shape,
+        }
+    }
+
+    /// Converts to a regular tensor, dequantizing blocks to f32.
+    pub fn to_tensor(&self) -> TractResult<Tensor> {
+        match self {
+            GgufTensor::Dense(t) => Ok(t.clone()),
+            GgufTensor::BlockQuant { format, shape, blocks } => {
+                let mut tensor = Tensor::zero::<f32>(shape)?;
+                format.dequant_f32(blocks.as_slice::<u8>()?, tensor.as_slice_mut::<f32>()?);
+                Ok(tensor)
+            }
+        }
+    }
+
+    /// Operator computing input.tr(self), for a block-quantized [m, k] tensor.
+    pub fn matmul_op(&self) -> TractResult<BlockQuantMatMul> {
+        match self {
+            GgufTensor::BlockQuant { format, shape, blocks } if shape.len() == 2 => {
+                BlockQuantMatMul::new(format.clone(), shape[0], shape[1], blocks.clone())
+            }
+            _ => bail!("Expected a block-quantized matrix, got {:?}", self),
+        }
+    }
+}
+
+/// The content of a GGUF file.
+#[derive(Clone, Debug)]
+pub struct Gguf {
+    pub version: u32,
+    pub metadata: Vec<(String, GgufValue)>,
+    pub tensors: Vec<(String, GgufTensor)>,
+}
+
+impl Gguf {
+    pub fn read(bytes: &[u8]) -> TractResult<Gguf> {
+        let mut r = Reader { bytes, pos: 0 };
+        ensure!(r.take(4)? == MAGIC, "Not a GGUF file");
+        let version = r.u32()?;
+        ensure!(version == 2 || version == 3, "Unsupported GGUF version {}", version);
+        let tensor_count = r.u64()?;
+        let metadata_count = r.u64()?;
+        let mut metadata = vec![];
+        for _ in 0..metadata_count {
+            let key = r.string()?;
+            let value_type = r.u32()?;
+            let value = r.value(value_type).with_context(|| format!("Reading metadata {}", key))?;
+            metadata.push((key, value));
+        }
+        let mut infos = vec![];
+        for _ in 0..tensor_count {
+            let name = r.string()?;
+            let rank = r.u32()?;
+            let mut shape =
+                (0..rank).map(|_| Ok(r.u64()? as usize)).collect::<TractResult<TVec<usize>>>()?;
+            shape.reverse();
+            let ggml_type = r.u32()?;
+            let offset = r.u64()? as usize;
+            infos.push((name, shape, ggml_type, offset));
+        }
+        let alignment = metadata
+            .iter()
+            .find(|(k, _)| k == "general.alignment")
+            .and_then(|(_, v)| v.as_u64())
+            .map(|a| a as usize)
+            .unwrap_or(DEFAULT_ALIGNMENT);
+        ensure!(alignment > 0, "Invalid GGUF alignment 0");
+        let start = r
+            .pos
+            .checked_add(alignment - 1)
+            .map(|end| end / alignment * alignment)
+            .context("Invalid GGUF alignment")?;
+        let data = bytes.get(start..).context("Truncated data")?;
+        let tensors = infos
+            .into_iter()
+            .map(|(name, shape, ggml_type, offset)| {
+                let tensor = read_tensor(data, &shape, ggml_type, offset)
+                    .with_context(|| format!("Reading tensor {}", name))?;
+                Ok((name, tensor))
+            })
+            .collect::<TractResult<_>>()?;
+        Ok(Gguf { version, metadata, tensors })
+    }
+
+    pub fn read_path(path: impl AsRef<Path>) -> TractResult<Gguf> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("Reading {:?}", path))?;
+        Gguf::read(&bytes)
+    }
+
+    pub fn metadata(&self, key: &str) -> Option<&GgufValue> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn tensor(&self, name: &str) -> Option<&GgufTensor> {
+        self.tensors.iter().find(|(k, _)| k == name).map(|(_, t)| t)
+    }
+
+    /// All tensors as regular tensors, suitable for `TypedModel::bind_weights`.
+    pub fn dequantized_tensors(&self) -> TractResult<Vec<(String, Tensor)>> {
+        self.tensors.iter().map(|(name, t)| Ok((name.clone(), t.to_tensor()?))).collect()
+    }
+}
+
+fn read_tensor(
+    data: &[u8],
+    shape: &[usize],
+    ggml_type: u32,
+    offset: usize,
+) -> TractResult<GgufTensor> {
+    let len = shape
+        .iter()
+        .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+        .with_context(|| format!("Tensor size overflow for shape {:?}", shape))?;
+    let range = |size: Option<usize>| -> TractResult<std::ops::Range<usize>> {
+        let end = size.and_then(|size| offset.checked_add(size)).context("Tensor size overflow")?;
+        Ok(offset..end)
+    };
+    let (format, dense): (Option<Box<dyn BlockQuant>>, _) = match ggml_type {
+        0 => (None, Some(f32::datum_type())),
+        1 => (None, Some(f16::datum_type())),
+        2 => (Some(Box::new(Q4_0)), None),
+        8 => (Some(Box::new(Q8_0)), None),
+        12 => (Some(Box::new(Q4K)), None),
+        30 => (None, Some(bf16::datum_type())),
+        other => bail!("Unsupported GGML type {}", other),
+    };
+    if let Some(dt) = dense {
+        let bytes = data.get(range(len.checked_mul(dt.size_of()))?).context("Truncated data")?;
+        Ok(GgufTensor::Dense(unsafe { Tensor::from_raw_dt(dt, shape, bytes)? }))
+    } else {
+        let format = format.unwrap();
+        let inner = shape.last().copied().unwrap_or(1);
+        ensure!(
+            inner % format.block_len() == 0,
+            "Inner dimension {} is not a multiple of the {} block length",
+            inner,
+            format.name()
+        );
+        let size = (len / format.block_len()).checked_mul(format.block_bytes());
+        let bytes = data.get(range(size)?).context("Truncated data")?;
+        Ok(GgufTensor::BlockQuant { format, shape: shape.into(), blocks: rctensor1(bytes) })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+macro_rules! read_le {
+    ($($name: ident: $t: ty),*) => {
+        $(
+            fn $name(&mut self) -> TractResult<$t> {
+                let bytes = self.take(std::mem::size_of::<$t>())?;
+                Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        )*
+    };
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> TractResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).context("Truncated GGUF header")?;
+        let bytes = self.bytes.get(self.pos..end).context("Truncated GGUF header")?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    read_le!(u8: u8, i8: i8, u16: u16, i16: i16, u32: u32, i32: i32, u64: u64, i64: i64, f32: f32, f64: f64);
+
+    fn string(&mut self) -> TractResult<String> {
+        let len = self.u64()? as usize;
+        Ok(std::str::from_utf8(self.take(len)?)?.to_string())
+    }
+
+    fn value(&mut self, value_type: u32) -> TractResult<GgufValue> {
+        Ok(match value_type {
+            0 => GgufValue::U8(self.u8()?),
+            1 => GgufValue::I8(self.i8()?),
+            2 => GgufValue::U16(self.u16()?),
+            3 => GgufValue::I16(self.i16()?),
+            4 => GgufValue::U32(self.u32()?),
+            5 => GgufValue::I32(self.i32()?),
+            6 => GgufValue::F32(self.f32()?),
+            7 => GgufValue::Bool(self.u8()? != 0),
+            8 => GgufValue::String(self.string()?),
+            9 => {
+                let item_type = self.u32()?;
+                let len = self.u64()?;
+                GgufValue::Array(
+                    (0..len).map(|_| self.value(item_type)).collect::<TractResult<_>>()?,
+                )
+            }
+            10 => GgufValue::U64(self.u64()?),
+            11 => GgufValue::I64(self.i64()?),
+            12 => GgufValue::F64(self.f64()?),
+            other => bail!("Unknown GGUF value type {}", other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn string(s: &str) -> Vec<u8> {
+        let mut v = (s.len() as u64).to_le_bytes().to_vec();
+        v.extend(s.as_bytes());
+        v
+    }
+
+    /// Quantizes exactly, with a scale of 1.
+    fn quant_values() -> Vec<f32> {
+        (0..32).map(|i| if i == 0 { 127.0 } else { i as f32 - 16.0 }).collect()
+    }
+
+    /// A file with a [2, 3] f32 tensor and a Q8_0 [1, 32] tensor.
+    fn file() -> Vec<u8> {
+        let mut f = MAGIC.to_vec();
+        f.extend(3u32.to_le_bytes());
+        f.extend(2u64.to_le_bytes());
+        f.extend(2u64.to_le_bytes());
+        f.extend(string("general.name"));
+        f.extend(8u32.to_le_bytes());
+        f.extend(string("tiny"));
+        f.extend(string("tiny.sizes"));
+        f.extend(9u32.to_le_bytes());
+        f.extend(4u32.to_le_bytes());
+        f.extend(2u64.to_le_bytes());
+        f.extend(2u32.to_le_bytes());
+        f.extend(3u32.to_le_bytes());
+        f.extend(string("dense"));
+        f.extend(2u32.to_le_bytes());
+        f.extend(3u64.to_le_bytes());
+        f.extend(2u64.to_le_bytes());
+        f.extend(0u32.to_le_bytes());
+        f.extend(0u64.to_le_bytes());
+        f.extend(string("quant"));
+        f.extend(2u32.to_le_bytes());
+        f.extend(32u64.to_le_bytes());
+        f.extend(1u64.to_le_bytes());
+        f.extend(8u32.to_le_bytes());
+        f.extend(32u64.to_le_bytes());
+        while f.len() % DEFAULT_ALIGNMENT != 0 {
+            f.push(0);
+        }
+        for i in 0..6 {
+            f.extend((i as f32).to_le_bytes());
+        }
+        f.resize(f.len() + 8, 0);
+        let mut block = [0u8; 34];
+        Q8_0.quant_block_f32(&quant_values(), &mut block);
+        f.extend(block);
+        f
+    }
+
+    #[test]
+    fn read() -> TractResult<()> {
+        let gguf = Gguf::read(&file())?;
+        assert_eq!(gguf.metadata("general.name").and_then(|v| v.as_str()), Some("tiny"));
+        assert_eq!(
+            gguf.metadata("tiny.sizes"),
+            Some(&GgufValue::Array(vec![GgufValue::U32(2), GgufValue::U32(3)]))
+        );
+        assert_eq!(
+            gguf.tensor("dense").unwrap().to_tensor()?,
+            tensor2(&[[0f32, 1.0, 2.0], [3.0, 4.0, 5.0]])
+        );
+        let quant = gguf.tensor("quant").unwrap();
+        assert_eq!(quant.shape(), &[1, 32]);
+        let expected = quant_values();
+        quant.to_tensor()?.close_enough(&tensor1(&expected).into_shape(&[1, 32])?, true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn matmul() -> TractResult<()> {
+        let gguf = Gguf::read(&file())?;
+        let op = gguf.tensor("quant").unwrap().matmul_op()?;
+        assert!(gguf.tensor("dense").unwrap().matmul_op().is_err());
+        let output =
+            op.eval(tvec!(Tensor::from_shape(&[1, 32], &[1f32; 32])?.into_arc_tensor()))?;
+        let expected: f32 =
+            gguf.tensor("quant").unwrap().to_tensor()?.as_slice::<f32>()?.iter().sum();
+        output[0].close_enough(&tensor2(&[[expected]]), true)
+    }
+
+    /// A file with a single tensor, and optionally an alignment.
+    fn single_tensor(alignment: Option<u32>, shape: &[u64], offset: u64) -> Vec<u8> {
+        let mut f = MAGIC.to_vec();
+        f.extend(3u32.to_le_bytes());
+        f.extend(1u64.to_le_bytes());
+        f.extend((alignment.is_some() as u64).to_le_bytes());
+        if let Some(alignment) = alignment {
+            f.extend(string("general.alignment"));
+            f.extend(4u32.to_le_bytes());
+            f.extend(alignment.to_le_bytes());
+        }
+        f.extend(string("t"));
+        f.extend((shape.len() as u32).to_le_bytes());
+        for d in shape {
+            f.extend(d.to_le_bytes());
+        }
+        f.extend(0u32.to_le_bytes());
+        f.extend(offset.to_le_bytes());
+        f.resize(f.len() + 64, 0);
+        f
+    }
+
+    #[test]
+    fn corrupted_header() -> TractResult<()> {
+        Gguf::read(&single_tensor(Some(8), &[2], 0))?;
+        assert!(Gguf::read(&single_tensor(Some(0), &[2], 0)).is_err());
+        assert!(Gguf::read(&single_tensor(None, &[u64::MAX, 2], 0)).is_err());
+        assert!(Gguf::read(&single_tensor(None, &[u64::MAX / 2], 0)).is_err());
+        assert!(Gguf::read(&single_tensor(None, &[2], u64::MAX)).is_err());
+        let mut file = single_tensor(None, &[2], 0);
+        // name length
+        file[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Gguf::read(&file).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn truncated() {
+        let file = file();
+        assert!(Gguf::read(&file[..file.len() - 1]).is_err());
+        assert!(Gguf::read(&file[..40]).is_err());
+    }
+}
@@ -41,8 +41,15 @@ where
     /// model properties
     #[educe(Hash(method = "hash_properties"))]
     pub properties: HashMap<String, Arc<Tensor>>,
+    /// model before optimization, kept to bind weights
+    #[educe(Hash(ignore))]
+    pub unoptimized: Option<Arc<Graph<F, O>>>,
 }
 
+/// Prefix of the model properties declaring the expected value range of an
+/// input: `input_range.<input node name>` holds a `[min, max]` f32 tensor.
+pub const INPUT_RANGE_PROPERTY_PREFIX: &str = "input_range.";
+
 fn hash_outlet_labels<H: std::hash::Hasher>(it: &HashMap<OutletId, String>, state: &mut H) {
     it.iter().sorted().for_each(|ol| ol.hash(state))
 }
@@ -73,6 +80,7 @@ where
             outputs: vec![],
             outlet_labels: HashMap::new(),
             properties: HashMap::new(),
+            unoptimized: None,
         }
     }
 }
@@ -203,6 +211,29 @@ where
         Ok(self)
     }
 
+    /// Get the expected value range of the `ix`-th input, if the model declares one.
+    pub fn input_range(&self, ix: usize) -> TractResult<Option<(f32, f32)>> {
+        let name = &self.node(self.input_outlets()?[ix].node).name;
+        self.properties
+            .get(&format!("{}{}", INPUT_RANGE_PROPERTY_PREFIX, name))
+            .map(|range| {
+                let range = range.cast_to::<f32>()?;
+                let range = range.as_slice::<f32>()?;
+                ensure!(range.len() == 2, "Input range must be [min, max], got {:?}", range);
+                Ok((range[0], range[1]))
+            })
+            .transpose()
+    }
+
+    /// Declare the expected value range of the `ix`-th input.
+    pub fn set_input_range(&mut self, ix: usize, min: f32, max: f32) -> TractResult<()> {
+        ensure!(min <= max, "Invalid input range [{}, {}]", min, max);
+        let name = &self.node(self.input_outlets()?[ix].node).name;
+        self.properties
+            .insert(format!("{}{}", INPUT_RANGE_PROPERTY_PREFIX, name), rctensor1(&[min, max]));
+        Ok(())
+    }
+
     // Outputs
     /// Get model outputs.
     pub fn output_outlets(&self) -> TractResult<&[OutletId]> {
@@ -712,4 +743,16 @@ mod test {
         let mut hasher = std::collections::hash_map::DefaultHasher::default();
         model.hash(&mut hasher);
     }
+
+    #[test]
+    fn input_range() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        model.add_source("source", f32::fact(&[1, 2, 3]))?;
+        assert_eq!(model.input_range(0)?, None);
+        model.set_input_range(0, -1.0, 1.0)?;
+        assert_eq!(model.input_range(0)?, Some((-1.0, 1.0)));
+        assert!(model.properties.contains_key("input_range.source"));
+        assert!(model.set_input_range(0, 1.0, -1.0).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,130 @@
+//! Machine-readable description of a typed model.
+//!
+//! The JSON document lists the model inputs, outputs and properties, every
+//! node with its operator, operator parameters (as reported by `Op::info`),
+//! inputs and output facts, and the edges between nodes. Outlets are
+//! `{"node": id, "slot": ix}` objects, dimensions are integers, or strings
+//! for symbolic ones. Fields are only added to the format: tools should
+//! ignore the ones they do not know, and check `version` otherwise.
+use serde_json::{json, Value};
+
+use crate::internal::*;
+
+/// Version of the JSON format, bumped when a field changes meaning or is
+/// removed.
+pub const JSON_FORMAT_VERSION: usize = 1;
+
+fn outlet(o: &OutletId) -> Value {
+    json!({ "node": o.node, "slot": o.slot })
+}
+
+fn dim(d: &TDim) -> Value {
+    if let Ok(d) = d.to_i64() {
+        json!(d)
+    } else {
+        json!(d.to_string())
+    }
+}
+
+fn fact(f: &TypedFact) -> Value {
+    let mut fact = json!({
+        "datum_type": format!("{:?}", f.datum_type),
+        "shape": f.shape.iter().map(|d| dim(&d)).collect::<Vec<_>>(),
+        "constant": f.konst.is_some(),
+    });
+    if let Some(range) = f.value_range() {
+        fact["range"] = json!([dim(&range.start), dim(&range.end)]);
+    }
+    fact
+}
+
+impl TypedModel {
+    /// Describes the model as a JSON document, see the module documentation
+    /// for the format.
+    pub fn to_json(&self) -> TractResult<Value> {
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        for node in self.nodes() {
+            let mut outputs = vec![];
+            for (slot, output) in node.outputs.iter().enumerate() {
+                let id = OutletId::new(node.id, slot);
+                let mut desc = json!({ "fact": fact(&output.fact) });
+                if let Some(label) = self.outlet_label(id) {
+                    desc["label"] = json!(label);
+                }
+                outputs.push(desc);
+                for succ in &output.successors {
+                    edges.push(json!({
+                        "from": outlet(&id),
+                        "to": { "node": succ.node, "slot": succ.slot },
+                    }));
+                }
+            }
+            nodes.push(json!({
+                "id": node.id,
+                "name": node.name,
+                "op": node.op().name(),
+                "parameters": node.op().info()?,
+                "inputs": node.inputs.iter().map(outlet).collect::<Vec<_>>(),
+                "outputs": outputs,
+            }));
+        }
+        let properties: serde_json::Map<String, Value> = self
+            .properties
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.clone(),
+                    json!({ "datum_type": format!("{:?}", v.datum_type()), "shape": v.shape() }),
+                )
+            })
+            .collect();
+        Ok(json!({
+            "version": JSON_FORMAT_VERSION,
+            "inputs": self.input_outlets()?.iter().map(outlet).collect::<Vec<_>>(),
+            "outputs": self.output_outlets()?.iter().map(outlet).collect::<Vec<_>>(),
+            "properties": properties,
+            "nodes": nodes,
+            "edges": edges,
+        }))
+    }
+
+    /// The JSON description of the model, serialized, see `to_json`.
+    pub fn to_json_string(&self) -> TractResult<String> {
+        Ok(serde_json::to_string_pretty(&self.to_json()?)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json() -> TractResult<()> {
+        let s = Symbol::new('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[s.to_dim(), 2.to_dim()]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        model.set_outlet_label(neg[0], "negated".to_string())?;
+        model.set_output_outlets(&neg)?;
+        model.properties.insert("version".into(), rctensor0(3i64));
+        let json = model.to_json()?;
+        assert_eq!(json["version"], json!(1));
+        assert_eq!(json["inputs"], json!([{ "node": 0, "slot": 0 }]));
+        assert_eq!(json["nodes"][1]["name"], json!("neg"));
+        assert_eq!(json["nodes"][1]["op"], json!("Neg"));
+        assert_eq!(json["nodes"][1]["outputs"][0]["label"], json!("negated"));
+        assert_eq!(
+            json["nodes"][0]["outputs"][0]["fact"],
+            json!({ "datum_type": "F32", "shape": ["S", 2], "constant": false })
+        );
+        assert_eq!(
+            json["edges"],
+            json!([{ "from": { "node": 0, "slot": 0 }, "to": { "node": 1, "slot": 0 } }])
+        );
+        assert_eq!(json["properties"]["version"], json!({ "datum_type": "I64", "shape": [] }));
+        let parsed: Value = serde_json::from_str(&model.to_json_string()?)?;
+        assert_eq!(parsed, json);
+        Ok(())
+    }
+}
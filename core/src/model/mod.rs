@@ -36,13 +36,22 @@
 use std::collections::HashMap;
 use std::str;
 
+pub mod calibration;
+pub mod cost;
+pub mod diff;
+pub mod dot;
 mod fact;
 mod graph;
+pub mod gguf;
+pub mod json;
 mod node;
 pub mod order;
 mod patch;
+pub mod precision;
+mod surgery;
 pub mod translator;
 pub mod typed;
+pub mod weights;
 
 pub use self::fact::*;
 pub use self::graph::*;
@@ -0,0 +1,441 @@
+//! Conversion of models between single and half precision.
+//!
+//! Every floating point tensor of a model in the source precision, weights
+//! and activations, is converted to the target precision. The interface of
+//! the model is kept: inputs are cast after the sources, and outputs before
+//! they are returned. An operator that can not compute in the target
+//! precision keeps its original precision, between casts.
+//!
+//! A `PrecisionPolicy` restricts the conversion to some of the nodes,
+//! selected by operator, by name or by their position in the graph.
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::internal::*;
+use crate::model::translator::Translate;
+use crate::ops::binary::UnaryOp;
+use crate::ops::cast::{cast, Cast};
+use crate::ops::cnn::ConvUnary;
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::konst::Const;
+use crate::ops::matmul::MatMulUnary;
+use crate::ops::source::TypedSource;
+
+/// A set of nodes of a model.
+#[derive(Debug, Clone)]
+pub enum NodeSelector {
+    /// Nodes whose operator has this name, as in `Op::name()`.
+    Op(String),
+    /// Nodes whose name matches the regular expression.
+    Name(Regex),
+    /// Nodes on a path from one of the `inputs` nodes to one of the
+    /// `outputs` nodes, both included.
+    Subgraph { inputs: Vec<String>, outputs: Vec<String> },
+}
+
+impl NodeSelector {
+    pub fn op(name: impl Into<String>) -> NodeSelector {
+        NodeSelector::Op(name.into())
+    }
+
+    pub fn name(regex: &str) -> TractResult<NodeSelector> {
+        Ok(NodeSelector::Name(Regex::new(regex)?))
+    }
+
+    pub fn subgraph(inputs: &[&str], outputs: &[&str]) -> NodeSelector {
+        NodeSelector::Subgraph {
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            outputs: outputs.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Ids of the selected nodes of model.
+    pub fn select(&self, model: &TypedModel) -> TractResult<HashSet<usize>> {
+        match self {
+            NodeSelector::Op(name) => {
+                Ok(model.nodes().iter().filter(|n| n.op.name() == *name).map(|n| n.id).collect())
+            }
+            NodeSelector::Name(regex) => {
+                Ok(model.nodes().iter().filter(|n| regex.is_match(&n.name)).map(|n| n.id).collect())
+            }
+            NodeSelector::Subgraph { inputs, outputs } => {
+                let mut after = HashSet::new();
+                let mut todo = inputs
+                    .iter()
+                    .map(|n| model.node_id_by_name(n))
+                    .collect::<TractResult<Vec<_>>>()?;
+                while let Some(id) = todo.pop() {
+                    if after.insert(id) {
+                        let node = model.node(id);
+                        todo.extend(
+                            node.outputs.iter().flat_map(|o| o.successors.iter().map(|s| s.node)),
+                        );
+                    }
+                }
+                let mut before = HashSet::new();
+                let mut todo = outputs
+                    .iter()
+                    .map(|n| model.node_id_by_name(n))
+                    .collect::<TractResult<Vec<_>>>()?;
+                while let Some(id) = todo.pop() {
+                    if before.insert(id) {
+                        todo.extend(model.node(id).inputs.iter().map(|i| i.node));
+                    }
+                }
+                Ok(after.intersection(&before).copied().collect())
+            }
+        }
+    }
+}
+
+/// Which nodes are converted to the target precision.
+///
+/// Rules are applied in order, so the last rule selecting a node decides.
+/// Nodes selected by no rule follow the default.
+#[derive(Debug, Clone)]
+pub struct PrecisionPolicy {
+    pub default: bool,
+    pub rules: Vec<(NodeSelector, bool)>,
+}
+
+impl Default for PrecisionPolicy {
+    fn default() -> PrecisionPolicy {
+        PrecisionPolicy::convert_all()
+    }
+}
+
+impl PrecisionPolicy {
+    pub fn convert_all() -> PrecisionPolicy {
+        PrecisionPolicy { default: true, rules: vec![] }
+    }
+
+    pub fn keep_all() -> PrecisionPolicy {
+        PrecisionPolicy { default: false, rules: vec![] }
+    }
+
+    /// Converts the selected nodes.
+    pub fn convert(mut self, selector: NodeSelector) -> PrecisionPolicy {
+        self.rules.push((selector, true));
+        self
+    }
+
+    /// Keeps the selected nodes in their original precision.
+    pub fn keep(mut self, selector: NodeSelector) -> PrecisionPolicy {
+        self.rules.push((selector, false));
+        self
+    }
+
+    /// Ids of the nodes of model to convert.
+    pub fn converted(&self, model: &TypedModel) -> TractResult<HashSet<usize>> {
+        let mut converted: HashSet<usize> =
+            if self.default { (0..model.nodes().len()).collect() } else { HashSet::new() };
+        for (selector, convert) in &self.rules {
+            for id in selector.select(model)? {
+                if *convert {
+                    converted.insert(id);
+                } else {
+                    converted.remove(&id);
+                }
+            }
+        }
+        Ok(converted)
+    }
+}
+
+#[derive(Debug, Clone, new)]
+pub struct FloatPrecisionTranslator {
+    pub from: DatumType,
+    pub to: DatumType,
+    #[new(default)]
+    pub policy: PrecisionPolicy,
+}
+
+impl FloatPrecisionTranslator {
+    pub fn with_policy(self, policy: PrecisionPolicy) -> FloatPrecisionTranslator {
+        FloatPrecisionTranslator { policy, ..self }
+    }
+
+    /// Convert model, keeping the datum types of its inputs and outputs.
+    pub fn translate(&self, model: &TypedModel) -> TractResult<TypedModel> {
+        let converted = self.policy.converted(model)?;
+        let mut target = Translation { translator: self, converted }.translate_model(model)?;
+        for (ix, output) in model.output_outlets()?.iter().enumerate() {
+            target.set_output_datum_type(ix, model.outlet_fact(*output)?.datum_type)?;
+        }
+        Ok(target)
+    }
+
+    fn convert(&self, t: &Arc<Tensor>) -> TractResult<Arc<Tensor>> {
+        if t.datum_type() == self.from {
+            Ok(t.cast_to_dt(self.to)?.into_owned().into_arc_tensor())
+        } else {
+            Ok(t.clone())
+        }
+    }
+
+    /// The operator of node, computing in the target precision, or None if
+    /// it has no implementation for it.
+    fn translate_op(&self, node: &TypedNode) -> TractResult<Option<Box<dyn TypedOp>>> {
+        let op: Box<dyn TypedOp> = if let Some(k) = node.op_as::<Const>() {
+            Box::new(Const::new(self.convert(&k.0)?))
+        } else if let Some(op) = node.op_as::<UnaryOp>() {
+            Box::new(UnaryOp::new(op.mini_op.clone(), self.convert(&op.a)?))
+        } else if let Some(op) = node.op_as::<MatMulUnary>() {
+            Box::new(MatMulUnary { a: self.convert(&op.a)?, ..op.clone() })
+        } else if let Some(op) = node.op_as::<ConvUnary>() {
+            if op.q_params.is_some() {
+                return Ok(None);
+            }
+            let bias = op.bias.as_ref().map(|b| self.convert(b)).transpose()?;
+            Box::new(ConvUnary { kernel: self.convert(&op.kernel)?, bias, ..op.clone() })
+        } else if let Some(op) = node.op_as::<Cast>() {
+            Box::new(if op.to == self.from { cast(self.to) } else { op.clone() })
+        } else if let Some(op) = node.op_as::<ElementWiseOp>() {
+            // element-wise operators only fail at eval time on unsupported types
+            let probe = Tensor::zero_dt(self.to, &[1])?.into_arc_tensor();
+            if op.eval(tvec!(probe)).is_err() {
+                return Ok(None);
+            }
+            node.op.clone()
+        } else {
+            node.op.clone()
+        };
+        Ok(Some(op))
+    }
+}
+
+#[derive(Debug)]
+struct Translation<'a> {
+    translator: &'a FloatPrecisionTranslator,
+    converted: HashSet<usize>,
+}
+
+impl<'a> Translation<'a> {
+    /// Casts the inputs of node that are not of the datum type `dt` gives
+    /// them.
+    fn cast_inputs(
+        &self,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+        dt: impl Fn(usize, DatumType) -> DatumType,
+    ) -> TractResult<TVec<OutletId>> {
+        let mut wires = tvec!();
+        for (ix, input) in inputs.iter().enumerate() {
+            let current = target.outlet_fact(*input)?.datum_type;
+            let wanted = dt(ix, current);
+            wires.push(if current != wanted {
+                target.wire_node(format!("{}.cast-{}", node.name, ix), cast(wanted), &[*input])?[0]
+            } else {
+                *input
+            });
+        }
+        Ok(wires)
+    }
+}
+
+impl<'a> Translate<TypedFact, Box<dyn TypedOp>, TypedFact, Box<dyn TypedOp>> for Translation<'a> {
+    fn translate_node(
+        &self,
+        source: &TypedModel,
+        node: &TypedNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let FloatPrecisionTranslator { from, to, .. } = *self.translator;
+        if let Some(op) = node.op_as::<TypedSource>() {
+            return Ok(tvec!(target.add_source(&node.name, op.fact.clone())?));
+        }
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|i| mapping[i]).collect();
+        let op = if self.converted.contains(&node.id) {
+            self.translator.translate_op(node)?
+        } else {
+            None
+        };
+        if let Some(op) = op {
+            // inputs in the source precision are cast to the target one
+            let facts = inputs
+                .iter()
+                .map(|i| {
+                    let fact = target.outlet_fact(*i)?;
+                    if fact.datum_type != from {
+                        Ok(fact.clone())
+                    } else if let Some(k) = &fact.konst {
+                        Ok(TypedFact::from(k.cast_to_dt(to)?.into_owned().into_arc_tensor()))
+                    } else {
+                        Ok(TypedFact::dt_shape(to, fact.shape.clone()))
+                    }
+                })
+                .collect::<TractResult<TVec<_>>>()?;
+            let probe = op.output_facts(&facts.iter().collect::<TVec<_>>());
+            match probe {
+                Ok(_) => {
+                    let wires =
+                        self.cast_inputs(
+                            node,
+                            target,
+                            &inputs,
+                            |_, dt| {
+                                if dt == from {
+                                    to
+                                } else {
+                                    dt
+                                }
+                            },
+                        )?;
+                    return target.wire_node(&node.name, op, &wires);
+                }
+                Err(e) => debug!("Keeping {} in {:?}: {:?}", node, from, e),
+            }
+        }
+        // the original operator, with its original input types
+        let dts = node
+            .inputs
+            .iter()
+            .map(|i| Ok(source.outlet_fact(*i)?.datum_type))
+            .collect::<TractResult<TVec<_>>>()?;
+        let wires = self.cast_inputs(node, target, &inputs, |ix, _| dts[ix])?;
+        target.wire_node(&node.name, node.op.clone(), &wires)
+    }
+}
+
+impl TypedModel {
+    /// Convert the f32 weights and activations of the model to f16, keeping
+    /// the datum types of its inputs and outputs.
+    pub fn into_f16(self) -> TractResult<TypedModel> {
+        FloatPrecisionTranslator::new(f32::datum_type(), f16::datum_type()).translate(&self)
+    }
+
+    /// Convert the f16 weights and activations of the model to f32, keeping
+    /// the datum types of its inputs and outputs.
+    pub fn into_f32(self) -> TractResult<TypedModel> {
+        FloatPrecisionTranslator::new(f16::datum_type(), f32::datum_type()).translate(&self)
+    }
+
+    /// Convert the f32 weights and activations of the nodes selected by
+    /// policy to f16.
+    pub fn into_f16_with_policy(self, policy: PrecisionPolicy) -> TractResult<TypedModel> {
+        FloatPrecisionTranslator::new(f32::datum_type(), f16::datum_type())
+            .with_policy(policy)
+            .translate(&self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::{PaddingSpec, PoolSpec};
+    use crate::ops::math;
+    use crate::ops::nn::{sigmoid, DataFormat};
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[1, 2, 5, 5]))?;
+        let kernel: Vec<f32> = (0..4 * 2 * 9).map(|i| ((i * 7) % 11) as f32 / 10.0 - 0.5).collect();
+        let kernel = tensor1(&kernel).into_shape(&[4, 2, 3, 3])?;
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(3, 3), PaddingSpec::Valid, None, None, Some(4));
+        let bias = rctensor1(&[0.1f32, -0.2, 0.3, 0.0]);
+        let conv =
+            ConvUnary::new(pool_spec, Default::default(), kernel.into(), 1, Some(bias), None);
+        let mut wire = model.wire_node("conv", conv, &[source])?;
+        wire = model.wire_node("add", math::add::unary(rctensor4(&[[[[0.5f32]]]])), &wire)?;
+        wire = model.wire_node("sigmoid", sigmoid(), &wire)?;
+        wire = model.wire_node(
+            "reshape",
+            AxisOp::Reshape(
+                0,
+                tvec!(1.into(), 4.into(), 3.into(), 3.into()),
+                tvec!(4.into(), 9.into()),
+            ),
+            &wire,
+        )?;
+        let a = tensor1(&(0..12).map(|i| i as f32 / 12.0 - 0.5).collect::<Vec<_>>());
+        let matmul =
+            MatMulUnary::new(a.into_shape(&[3, 4])?.into_arc_tensor(), false, false, false);
+        wire = model.wire_node("matmul", matmul, &wire)?;
+        model.set_output_outlets(&wire)?;
+        Ok(model)
+    }
+
+    fn assert_close(found: &Tensor, expected: &Tensor) -> TractResult<()> {
+        let found = found.cast_to::<f32>()?;
+        let found = found.as_slice::<f32>()?;
+        for (a, b) in found.iter().zip(expected.as_slice::<f32>()?) {
+            ensure!((a - b).abs() <= 1e-2 + 1e-2 * b.abs(), "{} != {}", a, b);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn f16_round_trip() -> TractResult<()> {
+        let model = model()?;
+        let input: Vec<f32> = (0..50).map(|i| ((i * 5) % 13) as f32 / 6.0 - 1.0).collect();
+        let input = tensor1(&input).into_shape(&[1, 2, 5, 5])?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+
+        let half = model.clone().into_f16()?;
+        assert_eq!(half.input_fact(0)?.datum_type, f32::datum_type());
+        assert_eq!(half.output_fact(0)?.datum_type, f32::datum_type());
+        let conv = half.node_by_name("conv")?.op_as::<ConvUnary>().unwrap();
+        assert_eq!(conv.kernel.datum_type(), f16::datum_type());
+        assert_eq!(
+            half.outlet_fact(half.node_by_name("add")?.id.into())?.datum_type,
+            f16::datum_type()
+        );
+        // no f16 sigmoid: computed in f32
+        assert_eq!(
+            half.outlet_fact(half.node_by_name("sigmoid")?.id.into())?.datum_type,
+            f32::datum_type()
+        );
+
+        for half in [half.clone(), half.clone().into_optimized()?] {
+            let found = half.into_runnable()?.run(tvec!(input.clone()))?;
+            assert_close(&found[0], &expected[0])?;
+        }
+
+        let single = half.into_f32()?;
+        assert!(single
+            .nodes()
+            .iter()
+            .all(|n| single.outlet_fact(n.id.into()).unwrap().datum_type != f16::datum_type()));
+        let found = single.into_runnable()?.run(tvec!(input))?;
+        assert_close(&found[0], &expected[0])?;
+        Ok(())
+    }
+
+    #[test]
+    fn policy() -> TractResult<()> {
+        let model = model()?;
+        let input: Vec<f32> = (0..50).map(|i| ((i * 3) % 11) as f32 / 5.0 - 1.0).collect();
+        let input = tensor1(&input).into_shape(&[1, 2, 5, 5])?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let dt = |model: &TypedModel, name: &str| -> TractResult<DatumType> {
+            Ok(model.outlet_fact(model.node_by_name(name)?.id.into())?.datum_type)
+        };
+
+        let policy = PrecisionPolicy::convert_all()
+            .keep(NodeSelector::op("ConvUnary"))
+            .keep(NodeSelector::name("^mat")?);
+        let mixed = model.clone().into_f16_with_policy(policy)?;
+        assert_eq!(dt(&mixed, "conv")?, f32::datum_type());
+        assert_eq!(dt(&mixed, "add")?, f16::datum_type());
+        assert_eq!(dt(&mixed, "reshape")?, f16::datum_type());
+        assert_eq!(dt(&mixed, "matmul")?, f32::datum_type());
+        let found = mixed.into_runnable()?.run(tvec!(input.clone()))?;
+        assert_close(&found[0], &expected[0])?;
+
+        let policy =
+            PrecisionPolicy::keep_all().convert(NodeSelector::subgraph(&["add"], &["reshape"]));
+        let mixed = model.into_f16_with_policy(policy)?;
+        assert_eq!(dt(&mixed, "conv")?, f32::datum_type());
+        assert_eq!(dt(&mixed, "add")?, f16::datum_type());
+        assert_eq!(dt(&mixed, "sigmoid")?, f32::datum_type());
+        assert_eq!(dt(&mixed, "reshape")?, f16::datum_type());
+        assert_eq!(dt(&mixed, "matmul")?, f32::datum_type());
+        let found = mixed.into_runnable()?.run(tvec!(input))?;
+        assert_close(&found[0], &expected[0])?;
+        Ok(())
+    }
+}
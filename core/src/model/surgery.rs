@@ -0,0 +1,267 @@
+//! Editing a typed model in place.
+//!
+//! These helpers keep the graph consistent: edges are rewired on both ends,
+//! output outlets and labels follow the values they designate, and the
+//! facts downstream of a change are recomputed. An edit failing to type
+//! check leaves the model untouched.
+//!
+//! Parts of a model can also be extracted as standalone models.
+use std::collections::HashMap;
+
+use crate::internal::*;
+use crate::model::order::eval_order_for_nodes;
+use crate::model::translator::{IntoTranslator, Translate};
+
+impl TypedModel {
+    /// Replaces the operator of `node`, keeping its name and inputs. The new
+    /// operator must have the same number of outputs, but their facts may
+    /// change: the facts of the nodes downstream are updated.
+    pub fn replace_op(&mut self, node: usize, op: impl Into<Box<dyn TypedOp>>) -> TractResult<()> {
+        ensure!(node < self.nodes().len(), "No node #{} in model", node);
+        let op = op.into();
+        self.edit(|model| {
+            let facts = {
+                let inputs = model.node_input_facts(node)?;
+                op.output_facts(&inputs)?
+            };
+            let target = model.node_mut(node);
+            ensure!(
+                facts.len() == target.outputs.len(),
+                "{} has {} outputs, the replacement operator {}",
+                target,
+                target.outputs.len(),
+                facts.len()
+            );
+            target.op = op;
+            for (output, fact) in target.outputs.iter_mut().zip(facts) {
+                output.fact = fact;
+            }
+            Ok(())
+        })
+        .with_context(|| format!("Replacing operator of {}", self.node(node)))
+    }
+
+    /// Inserts a new node computing `op` from `outlet`, between `outlet` and
+    /// its consumers: the successors, model outputs and label of `outlet`
+    /// are moved to the output of the new node, which is returned.
+    pub fn splice(
+        &mut self,
+        outlet: OutletId,
+        name: impl Into<String>,
+        op: impl Into<Box<dyn TypedOp>>,
+    ) -> TractResult<OutletId> {
+        let (name, op) = (name.into(), op.into());
+        let mut wire = outlet;
+        self.edit(|model| {
+            let successors = model.outlet_successors(outlet).to_vec();
+            let wires = model.wire_node(&*name, op, &[outlet])?;
+            ensure!(wires.len() == 1, "Can only splice single output operators");
+            wire = wires[0];
+            for succ in successors {
+                model.add_edge(wire, succ)?;
+            }
+            for o in model.outputs.iter_mut().filter(|o| **o == outlet) {
+                *o = wire;
+            }
+            if let Some(label) = model.outlet_labels.remove(&outlet) {
+                model.set_outlet_label(wire, label)?;
+            }
+            Ok(())
+        })
+        .with_context(|| format!("Splicing {} after {:?}", name, outlet))?;
+        Ok(wire)
+    }
+
+    /// Removes the nodes the outputs of the model do not depend on, keeping
+    /// the inputs of the model even if they are unused. Node ids change: the
+    /// returned map gives the new outlet of every outlet kept.
+    pub fn remove_dead_nodes(&mut self) -> TractResult<HashMap<OutletId, OutletId>> {
+        let (model, mapping) = IntoTranslator.translate_model_with_mappings(self)?;
+        *self = model;
+        Ok(mapping)
+    }
+
+    /// Builds a standalone model computing `outputs` from `inputs`: the
+    /// nodes between them are copied, and every input outlet becomes a
+    /// source, named after its label or its node. Fails if the outputs
+    /// depend on a model input not in `inputs`.
+    pub fn extract(&self, inputs: &[OutletId], outputs: &[OutletId]) -> TractResult<TypedModel> {
+        let mut extracted = TypedModel::default();
+        let mut mapping: HashMap<OutletId, OutletId> = HashMap::new();
+        for &input in inputs {
+            let name = if let Some(label) = self.outlet_label(input) {
+                label.to_string()
+            } else if self.node(input.node).outputs.len() == 1 {
+                self.node(input.node).name.clone()
+            } else {
+                format!("{}:{}", self.node(input.node).name, input.slot)
+            };
+            let source = extracted.add_source(name, self.outlet_fact(input)?.without_value())?;
+            mapping.insert(input, source);
+        }
+        let input_nodes: Vec<usize> = inputs.iter().map(|i| i.node).collect();
+        let output_nodes: Vec<usize> = outputs.iter().map(|o| o.node).collect();
+        for id in eval_order_for_nodes(self.nodes(), &input_nodes, &output_nodes, &[])? {
+            let node = self.node(id);
+            if input_nodes.contains(&id) {
+                continue;
+            }
+            ensure!(
+                !Self::is_source(&node.op),
+                "Extracted outputs depend on input {}, which is not an extracted input",
+                node
+            );
+            let wires = node
+                .inputs
+                .iter()
+                .map(|i| {
+                    mapping.get(i).copied().with_context(|| {
+                        format!("{} uses {:?}, which is not an extracted input", node, i)
+                    })
+                })
+                .collect::<TractResult<TVec<OutletId>>>()?;
+            let wires = extracted.wire_node(&*node.name, node.op.clone(), &wires)?;
+            for (ix, wire) in wires.into_iter().enumerate() {
+                let outlet = OutletId::new(id, ix);
+                if let Some(label) = self.outlet_label(outlet) {
+                    extracted.set_outlet_label(wire, label.to_string())?;
+                }
+                mapping.insert(outlet, wire);
+            }
+        }
+        let outputs = outputs
+            .iter()
+            .map(|o| mapping.get(o).copied().with_context(|| format!("{:?} is not computed", o)))
+            .collect::<TractResult<TVec<OutletId>>>()?;
+        extracted.set_output_outlets(&outputs)?;
+        extracted.properties = self.properties.clone();
+        Ok(extracted)
+    }
+
+    /// Recomputes the output facts of every node from the facts of its
+    /// inputs, in evaluation order, updating the ones whose datum type or
+    /// shape changed. Fails, leaving the model untouched, if an operator
+    /// rejects its inputs.
+    pub fn type_check(&mut self) -> TractResult<()> {
+        self.edit(|_| Ok(()))
+    }
+
+    /// Applies `edit` on a copy of the model, then refreshes its facts, and
+    /// only replaces the model if both succeed.
+    fn edit(&mut self, edit: impl FnOnce(&mut TypedModel) -> TractResult<()>) -> TractResult<()> {
+        let mut model = self.clone();
+        edit(&mut model)?;
+        model.refresh_facts()?;
+        *self = model;
+        Ok(())
+    }
+
+    fn refresh_facts(&mut self) -> TractResult<()> {
+        for id in self.eval_order()? {
+            let node = self.node(id);
+            let facts = {
+                let inputs = self.node_input_facts(id)?;
+                node.op.output_facts(&inputs).with_context(|| format!("Type checking {}", node))?
+            };
+            ensure!(
+                facts.len() == node.outputs.len(),
+                "{} has {} outputs, its operator produces {}",
+                node,
+                node.outputs.len(),
+                facts.len()
+            );
+            for (output, fact) in self.node_mut(id).outputs.iter_mut().zip(facts) {
+                if output.fact.datum_type != fact.datum_type || output.fact.shape != fact.shape {
+                    output.fact = fact;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &neg)?;
+        model.set_outlet_label(exp[0], "output".to_string())?;
+        model.set_output_outlets(&exp)?;
+        Ok(model)
+    }
+
+    fn run(model: &TypedModel) -> TractResult<Arc<Tensor>> {
+        Ok(model.clone().into_runnable()?.run(tvec!(tensor1(&[0f32, 1.0, 2.0])))?.remove(0))
+    }
+
+    #[test]
+    fn replace_op() -> TractResult<()> {
+        let mut model = model()?;
+        let neg = model.node_id_by_name("neg")?;
+        model.replace_op(neg, crate::ops::math::abs())?;
+        assert_eq!(*run(&model)?, tensor1(&[1f32, 1f32.exp(), 2f32.exp()]));
+        model.replace_op(neg, AxisOp::Add(0))?;
+        assert_eq!(model.output_fact(0)?.shape.as_concrete(), Some(&[1, 3][..]));
+        // an axis of size 3 can not be removed: the model is left as it was
+        let before = model.signature();
+        assert!(model.replace_op(neg, AxisOp::Rm(0)).is_err());
+        assert!(model.replace_op(99, crate::ops::math::abs()).is_err());
+        assert_eq!(model.signature(), before);
+        Ok(())
+    }
+
+    #[test]
+    fn splice() -> TractResult<()> {
+        let mut model = model()?;
+        let exp = model.output_outlets()?[0];
+        let wire = model.splice(exp, "recip", crate::ops::math::recip())?;
+        assert_eq!(model.output_outlets()?, &[wire]);
+        assert_eq!(model.find_outlet_label("output"), Some(wire));
+        assert_eq!(*run(&model)?, tensor1(&[1f32, 1f32.exp(), 2f32.exp()]));
+        let source = model.input_outlets()?[0];
+        let abs = model.splice(source, "abs", crate::ops::math::abs())?;
+        assert_eq!(model.outlet_successors(source).len(), 1);
+        assert_eq!(model.node(model.node_id_by_name("neg")?).inputs, vec![abs]);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_dead_nodes() -> TractResult<()> {
+        let mut model = model()?;
+        let source = model.input_outlets()?[0];
+        model.wire_node("dead", crate::ops::math::abs(), &[source])?;
+        let exp = model.output_outlets()?[0];
+        let mapping = model.remove_dead_nodes()?;
+        assert_eq!(model.nodes().len(), 3);
+        assert!(model.node_id_by_name("dead").is_err());
+        assert_eq!(model.output_outlets()?, &[mapping[&exp]]);
+        assert_eq!(*run(&model)?, tensor1(&[1f32, (-1f32).exp(), (-2f32).exp()]));
+        Ok(())
+    }
+
+    #[test]
+    fn extract() -> TractResult<()> {
+        let mut model = model()?;
+        let exp = model.output_outlets()?[0];
+        let recip = model.wire_node("recip", crate::ops::math::recip(), &[exp])?;
+        model.set_output_outlets(&recip)?;
+        let neg = model.node_id_by_name("neg")?;
+        let layer = model.extract(&[neg.into()], &[exp])?;
+        assert_eq!(layer.nodes().len(), 2);
+        assert_eq!(layer.node(layer.input_outlets()?[0].node).name, "neg");
+        assert_eq!(layer.find_outlet_label("output"), Some(layer.output_outlets()?[0]));
+        assert_eq!(*run(&layer)?, tensor1(&[1f32, 1f32.exp(), 2f32.exp()]));
+        let layers = model.extract(&[neg.into()], &recip)?;
+        assert_eq!(layers.nodes().len(), 3);
+        // sum also depends on the model input, through abs
+        let abs =
+            model.wire_node("abs", crate::ops::math::abs(), &model.input_outlets()?.to_vec())?;
+        let sum = model.wire_node("sum", crate::ops::math::add::bin_typed(), &[exp, abs[0]])?;
+        assert!(model.extract(&[neg.into()], &sum).is_err());
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use crate::ops;
 use crate::ops::invariants;
 use crate::optim::OptimizerSession;
 use crate::plan::{SimplePlan, SimpleState};
+use tract_itertools::Itertools;
 
 /// A model with completely determined types and shapes.
 pub type TypedModel = Graph<TypedFact, Box<dyn TypedOp>>;
@@ -88,8 +89,14 @@ impl TypedModel {
     }
 
     pub fn into_optimized(mut self) -> TractResult<TypedModel> {
+        let unoptimized = if self.unoptimized.is_none() && weights::bindable_weights(&self)? {
+            Some(Arc::new(self.clone()))
+        } else {
+            self.unoptimized.take()
+        };
         self.declutter()?;
         self.optimize()?;
+        self.unoptimized = unoptimized;
         Ok(self)
     }
     #[cfg(not(all(debug_assertions, feature = "paranoid_assertions")))]
@@ -169,6 +176,18 @@ impl TypedModel {
         values.translate_model(&self)
     }
 
+    /// Declare assumptions on symbols, and rebuild the model with its
+    /// dimensions simplified accordingly.
+    pub fn with_assumptions(
+        &self,
+        assumptions: impl IntoIterator<Item = (Symbol, Assumption)>,
+    ) -> TractResult<TypedModel> {
+        for (symbol, assumption) in assumptions {
+            symbol.assume(assumption);
+        }
+        self.concretize_dims(&SymbolValues::default())
+    }
+
     /// Translate the graph to locally optimized operators (LIR or MIR ops).
     pub fn optimize(&mut self) -> TractResult<()> {
         crate::optim::Optimizer::codegen().optimize(self)
@@ -177,6 +196,78 @@ impl TypedModel {
     pub fn invariants(&self) -> TractResult<invariants::Invariants> {
         invariants::for_model(self)
     }
+
+    /// Nodes using floating point values, either in their inputs and outputs, or
+    /// in their computations (as reported by their cost).
+    pub fn float_nodes(&self) -> TractResult<Vec<usize>> {
+        let mut nodes = vec![];
+        for node in self.nodes() {
+            let (inputs, outputs) = self.node_facts(node.id)?;
+            if uses_float(&*node.op, &inputs, &outputs)? {
+                nodes.push(node.id);
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Check that the model is integer-only, listing the nodes still using
+    /// floating point values otherwise.
+    pub fn check_integer_only(&self) -> TractResult<()> {
+        let nodes = self.float_nodes()?;
+        if nodes.len() > 0 {
+            bail!(
+                "Model is not integer-only, {} node(s) use floating point values: {}",
+                nodes.len(),
+                nodes.iter().map(|n| self.node(*n).to_string()).join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Make the `ix`-th output of the model a `dt` tensor.
+    ///
+    /// A Cast node is appended to the output if its type does not already
+    /// match, and the output label, if any, is moved to the cast output.
+    pub fn set_output_datum_type(&mut self, output: usize, dt: DatumType) -> TractResult<()> {
+        let outlet = *self
+            .outputs
+            .get(output)
+            .with_context(|| format!("Model has no output #{}", output))?;
+        if self.outlet_fact(outlet)?.datum_type == dt {
+            return Ok(());
+        }
+        let name = if outlet.slot == 0 {
+            format!("{}.cast", self.node(outlet.node).name)
+        } else {
+            format!("{}.{}.cast", self.node(outlet.node).name, outlet.slot)
+        };
+        let cast = self.wire_node(name, ops::cast::cast(dt), &[outlet])?[0];
+        self.outputs[output] = cast;
+        if let Some(label) = self.outlet_labels.remove(&outlet) {
+            self.set_outlet_label(cast, label)?;
+        }
+        Ok(())
+    }
+
+    /// Make the `ix`-th output of the model a `dt` tensor and return `self`.
+    pub fn with_output_datum_type(mut self, output: usize, dt: DatumType) -> TractResult<Self> {
+        self.set_output_datum_type(output, dt)?;
+        Ok(self)
+    }
+}
+
+/// True if the op uses floating point values, in its inputs and outputs, or
+/// in its computations.
+pub(crate) fn uses_float(
+    op: &dyn TypedOp,
+    inputs: &[&TypedFact],
+    outputs: &[&TypedFact],
+) -> TractResult<bool> {
+    Ok(inputs.iter().chain(outputs.iter()).any(|f| f.datum_type.is_float())
+        || op.cost(inputs)?.iter().any(|(c, _)| match c {
+            Cost::FMA(dt) | Cost::Div(dt) => dt.is_float(),
+            _ => false,
+        }))
 }
 
 #[cfg(test)]
@@ -188,4 +279,39 @@ mod test {
         fn is_sync<T: Sync>() {}
         is_sync::<TypedModel>();
     }
+
+    #[test]
+    fn integer_only() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", i8::fact(&[2]))?;
+        let abs = model.wire_node("abs", ops::math::abs(), &[source])?;
+        model.set_output_outlets(&abs)?;
+        model.check_integer_only()?;
+        let cast = model.wire_node("cast", ops::cast::cast(f32::datum_type()), &abs)?;
+        model.set_output_outlets(&cast)?;
+        assert_eq!(model.float_nodes()?, vec!(cast[0].node));
+        assert!(model.check_integer_only().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn output_datum_types() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let neg = model.wire_node("neg", ops::math::neg(), &[source])?[0];
+        let rint = model.wire_node("rint", ops::math::round(), &[source])?[0];
+        model.set_output_outlets(&[neg, rint])?;
+        model.set_outlet_label(rint, "rounded".to_string())?;
+        let model = model
+            .with_output_datum_type(0, f16::datum_type())?
+            .with_output_datum_type(1, i32::datum_type())?
+            .into_optimized()?;
+        assert_eq!(model.output_fact(0)?.datum_type, f16::datum_type());
+        assert_eq!(model.output_fact(1)?.datum_type, i32::datum_type());
+        assert_eq!(model.outlet_label(model.output_outlets()?[1]), Some("rounded"));
+        let outputs = model.into_runnable()?.run(tvec!(tensor1(&[1.5f32, -2.0])))?;
+        assert_eq!(*outputs[0], tensor1(&[-1.5f32, 2.0]).cast_to::<f16>()?.into_owned());
+        assert_eq!(*outputs[1], tensor1(&[2i32, -2]));
+        Ok(())
+    }
 }
@@ -0,0 +1,401 @@
+//! Binding constant tensors by name, from memory or from safetensors files.
+//!
+//! Weights are matched against Const nodes, by node name or by outlet label.
+//!
+//! Constants absorbed by operators (convolution kernels and biases, matrix
+//! product operands, constant operands of element-wise operations) are
+//! matched through the Const node they were taken from, and bound in the
+//! operators too.
+//!
+//! The optimization transforms the weights (packed for the matrix multiplier
+//! kernels, fused in other operators...), so the weights of an optimized
+//! model can only be bound if the model kept a copy of itself before the
+//! optimization, with `BINDABLE_WEIGHTS_PROPERTY`. Binding then happens on the
+//! copy, which is optimized again.
+//!
+//! FP8 weights can be bound to floating point constants: they are dequantized
+//! at binding time, using the `{name}_scale` companion weight if present.
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::Path;
+
+use crate::internal::*;
+use crate::ops::binary::UnaryOp;
+use crate::ops::cnn::conv::ConvUnary;
+use crate::ops::konst::Const;
+use crate::ops::matmul::MatMulUnary;
+
+/// Model property keeping a copy of the model before optimization, so that
+/// its weights can be bound after it. An integer scalar, non-zero to enable
+/// it.
+pub const BINDABLE_WEIGHTS_PROPERTY: &str = "weights.bindable";
+
+pub fn bindable_weights(model: &TypedModel) -> TractResult<bool> {
+    if let Some(prop) = model.properties.get(BINDABLE_WEIGHTS_PROPERTY) {
+        Ok(prop.cast_to_scalar::<i64>()? != 0)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Reads the tensors of a safetensors file, in file order.
+pub fn read_safetensors(bytes: &[u8]) -> TractResult<Vec<(String, Tensor)>> {
+    ensure!(bytes.len() >= 8, "Truncated safetensors header");
+    let header_len: usize = u64::from_le_bytes(bytes[0..8].try_into().unwrap())
+        .try_into()
+        .context("Invalid safetensors header length")?;
+    let header_end = header_len.checked_add(8).context("Invalid safetensors header length")?;
+    let header = bytes.get(8..header_end).context("Truncated safetensors header")?;
+    let data = &bytes[header_end..];
+    let header: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(header).context("Parsing safetensors header")?;
+    let mut tensors = vec![];
+    for (name, desc) in header {
+        if name == "__metadata__" {
+            continue;
+        }
+        let tensor =
+            read_tensor(&desc, data).with_context(|| format!("Reading tensor {}", name))?;
+        tensors.push((name, tensor));
+    }
+    Ok(tensors)
+}
+
+fn read_tensor(desc: &serde_json::Value, data: &[u8]) -> TractResult<Tensor> {
+    let dt = match desc["dtype"].as_str().context("Missing dtype")? {
+        "BOOL" => bool::datum_type(),
+        "U8" => u8::datum_type(),
+        "I8" => i8::datum_type(),
+        "U16" => u16::datum_type(),
+        "I16" => i16::datum_type(),
+        "U32" => u32::datum_type(),
+        "I32" => i32::datum_type(),
+        "U64" => u64::datum_type(),
+        "I64" => i64::datum_type(),
+        "F16" => f16::datum_type(),
+        "BF16" => bf16::datum_type(),
+        "F32" => f32::datum_type(),
+        "F64" => f64::datum_type(),
+        "F8_E4M3" => f8e4m3::datum_type(),
+        "F8_E5M2" => f8e5m2::datum_type(),
+        other => bail!("Unsupported safetensors dtype {}", other),
+    };
+    let shape = desc["shape"]
+        .as_array()
+        .context("Missing shape")?
+        .iter()
+        .map(|d| d.as_u64().map(|d| d as usize).context("Invalid dimension"))
+        .collect::<TractResult<TVec<usize>>>()?;
+    let offsets = desc["data_offsets"]
+        .as_array()
+        .filter(|o| o.len() == 2)
+        .context("Missing data_offsets")?
+        .iter()
+        .map(|o| o.as_u64().map(|o| o as usize).context("Invalid offset"))
+        .collect::<TractResult<TVec<usize>>>()?;
+    let bytes = data.get(offsets[0]..offsets[1]).context("Data offsets out of bounds")?;
+    let expected = shape
+        .iter()
+        .try_fold(dt.size_of(), |acc, d| acc.checked_mul(*d))
+        .with_context(|| format!("Tensor size overflow for {:?} {:?}", dt, shape))?;
+    ensure!(
+        bytes.len() == expected,
+        "Data is {} bytes long, expected {} for {:?} {:?}",
+        bytes.len(),
+        expected,
+        dt,
+        shape
+    );
+    unsafe { Tensor::from_raw_dt(dt, &shape, bytes) }
+}
+
+/// Suffix of the name of the scale of a FP8 weight.
+pub const F8_SCALE_SUFFIX: &str = "_scale";
+
+/// Dequantizes FP8 values to f32, multiplied by scale.
+///
+/// Scale is either a scalar, one value per row (first axis) of values, or
+/// any shape broadcasting to the shape of values.
+pub fn dequantize_f8(values: &Tensor, scale: &Tensor) -> TractResult<Tensor> {
+    ensure!(values.datum_type().is_fp8(), "Expected FP8 values, got {:?}", values.datum_type());
+    let mut scale = scale.cast_to::<f32>()?.into_owned();
+    if scale.rank() == 1 && values.rank() > 1 && scale.len() == values.shape()[0] {
+        let mut shape = tvec!(1; values.rank());
+        shape[0] = scale.len();
+        scale.set_shape(&shape)?;
+    }
+    let mut result = values.cast_to::<f32>()?.into_owned();
+    let scale = scale.to_array_view::<f32>()?;
+    let scale = scale.broadcast(values.shape()).with_context(|| {
+        format!("Can not broadcast scale {:?} to {:?}", scale.shape(), values.shape())
+    })?;
+    let mut view = result.to_array_view_mut::<f32>()?;
+    view *= &scale;
+    Ok(result)
+}
+
+impl TypedModel {
+    /// Replaces the values of constants, matched by node name or outlet label.
+    ///
+    /// Replacement tensors must have the same datum type and shape as the
+    /// original constant, except for FP8 tensors bound to a floating point
+    /// constant, which are dequantized with their scale. Returns the names
+    /// that did not match any constant.
+    ///
+    /// Optimized models are bound through the model they were optimized
+    /// from, if they kept it.
+    pub fn bind_weights(
+        &mut self,
+        weights: impl IntoIterator<Item = (String, Tensor)>,
+    ) -> TractResult<Vec<String>> {
+        if let Some(unoptimized) = &self.unoptimized {
+            let mut unoptimized = (**unoptimized).clone();
+            let unbound = unoptimized.bind_weights(weights)?;
+            *self = unoptimized.into_optimized()?;
+            return Ok(unbound);
+        }
+        let weights: Vec<(String, Tensor)> = weights.into_iter().collect();
+        let scale_names: Vec<String> = weights
+            .iter()
+            .filter(|(_, t)| t.datum_type().is_fp8())
+            .map(|(name, _)| format!("{}{}", name, F8_SCALE_SUFFIX))
+            .collect();
+        let (scales, weights): (Vec<_>, Vec<_>) =
+            weights.into_iter().partition(|(name, _)| scale_names.contains(name));
+        let scales: HashMap<String, Tensor> = scales.into_iter().collect();
+        let mut unbound = vec![];
+        for (name, tensor) in weights {
+            let node = if let Ok(node) = self.node_id_by_name(&name) {
+                Some(node)
+            } else {
+                self.outlet_labels
+                    .iter()
+                    .find(|(_, label)| **label == name)
+                    .map(|(outlet, _)| outlet.node)
+            };
+            let node = if let Some(node) = node.filter(|n| self.node(*n).op_is::<Const>()) {
+                node
+            } else {
+                unbound.push(name);
+                continue;
+            };
+            let current = &self.node(node).op_as::<Const>().unwrap().0;
+            let tensor = if tensor.datum_type().is_fp8() && current.datum_type().is_float() {
+                let scale = scales
+                    .get(&format!("{}{}", name, F8_SCALE_SUFFIX))
+                    .cloned()
+                    .unwrap_or_else(|| tensor0(1f32));
+                dequantize_f8(&tensor, &scale)
+                    .with_context(|| format!("Dequantizing {}", name))?
+                    .cast_to_dt(current.datum_type())?
+                    .into_owned()
+            } else {
+                tensor
+            };
+            ensure!(
+                current.datum_type() == tensor.datum_type() && current.shape() == tensor.shape(),
+                "Can not bind {:?} {:?} to {}, expected {:?} {:?}",
+                tensor.datum_type(),
+                tensor.shape(),
+                name,
+                current.datum_type(),
+                current.shape()
+            );
+            let current = current.clone();
+            let tensor = tensor.into_arc_tensor();
+            for absorbing in 0..self.nodes.len() {
+                if let Some(op) = rebind_absorbed(self.node(absorbing), &current, &tensor) {
+                    self.node_mut(absorbing).op = op;
+                }
+            }
+            let node = self.node_mut(node);
+            node.outputs[0].fact = TypedFact::from(tensor.clone());
+            node.op = Box::new(Const::new(tensor));
+        }
+        Ok(unbound)
+    }
+
+    /// Binds the tensors of a safetensors buffer. See `bind_weights`.
+    pub fn bind_safetensors(&mut self, bytes: &[u8]) -> TractResult<Vec<String>> {
+        self.bind_weights(read_safetensors(bytes)?)
+    }
+
+    /// Binds the tensors of a safetensors file. See `bind_weights`.
+    pub fn bind_safetensors_path(&mut self, path: impl AsRef<Path>) -> TractResult<Vec<String>> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).with_context(|| format!("Reading {:?}", path))?;
+        self.bind_safetensors(&bytes)
+    }
+}
+
+/// Op of node with `new` in place of the constant `old` it absorbed, if any.
+fn rebind_absorbed(
+    node: &TypedNode,
+    old: &Arc<Tensor>,
+    new: &Arc<Tensor>,
+) -> Option<Box<dyn TypedOp>> {
+    let rebind = |t: &Arc<Tensor>| if Arc::ptr_eq(t, old) { new.clone() } else { t.clone() };
+    if let Some(op) = node.op_as::<ConvUnary>() {
+        if Arc::ptr_eq(&op.kernel, old) || op.bias.as_ref().map_or(false, |b| Arc::ptr_eq(b, old)) {
+            let mut op = op.clone();
+            op.kernel = rebind(&op.kernel);
+            op.bias = op.bias.as_ref().map(rebind);
+            return Some(Box::new(op));
+        }
+    } else if let Some(op) = node.op_as::<MatMulUnary>() {
+        if Arc::ptr_eq(&op.a, old) {
+            return Some(Box::new(MatMulUnary { a: new.clone(), ..op.clone() }));
+        }
+    } else if let Some(op) = node.op_as::<UnaryOp>() {
+        if Arc::ptr_eq(&op.a, old) {
+            return Some(Box::new(UnaryOp { a: new.clone(), ..op.clone() }));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn safetensors(tensors: &[(&str, &str, &[usize], &[u8])]) -> Vec<u8> {
+        let mut header = serde_json::Map::new();
+        let mut data = vec![];
+        for (name, dt, shape, bytes) in tensors {
+            let desc = serde_json::json!({
+                "dtype": dt,
+                "shape": shape,
+                "data_offsets": [data.len(), data.len() + bytes.len()],
+            });
+            header.insert(name.to_string(), desc);
+            data.extend_from_slice(bytes);
+        }
+        header.insert("__metadata__".to_string(), serde_json::json!({"format": "pt"}));
+        let header = serde_json::to_vec(&header).unwrap();
+        let mut file = (header.len() as u64).to_le_bytes().to_vec();
+        file.extend(header);
+        file.extend(data);
+        file
+    }
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([2]))?;
+        let bias = model.add_const("bias", tensor1(&[1f32, 2.0]))?;
+        let add = model.wire_node("add", crate::ops::math::add::bin_typed(), &[source, bias])?;
+        model.set_output_outlets(&add)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn swap_weights() -> TractResult<()> {
+        let mut model = model()?;
+        let bytes: Vec<u8> = [10f32, 20.0].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let file = safetensors(&[("bias", "F32", &[2], &bytes), ("extra", "U8", &[1], &[0])]);
+        let unbound = model.bind_safetensors(&file)?;
+        assert_eq!(unbound, vec!["extra".to_string()]);
+        let output = model.into_runnable()?.run(tvec!(tensor1(&[1f32, 1.0])))?;
+        assert_eq!(*output[0], tensor1(&[11f32, 21.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_mismatch() -> TractResult<()> {
+        let mut model = model()?;
+        assert!(model.bind_weights(vec![("bias".to_string(), tensor1(&[1f32]))]).is_err());
+        let bytes: Vec<u8> = [1i32, 2].iter().flat_map(|f| f.to_le_bytes()).collect();
+        assert!(model.bind_safetensors(&safetensors(&[("bias", "I32", &[2], &bytes)])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fp8_weights() -> TractResult<()> {
+        let mut model = model()?;
+        let values = [f8e4m3::from_f32(1.5).0, f8e4m3::from_f32(-0.5).0];
+        let scale: Vec<u8> = [4f32, 2.0].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let file =
+            safetensors(&[("bias", "F8_E4M3", &[2], &values), ("bias_scale", "F32", &[2], &scale)]);
+        let unbound = model.bind_safetensors(&file)?;
+        assert!(unbound.is_empty());
+        let output = model.into_runnable()?.run(tvec!(tensor1(&[1f32, 1.0])))?;
+        assert_eq!(*output[0], tensor1(&[7f32, 0.0]));
+        Ok(())
+    }
+
+    fn conv_matmul_weights(x: f32) -> TractResult<Vec<(String, Tensor)>> {
+        Ok(vec![
+            ("kernel".to_string(), tensor1(&[x, 2.0, -1.0, x]).into_shape(&[2, 2, 1])?),
+            ("w".to_string(), tensor1(&[1.0, x, -x, 2.0]).into_shape(&[1, 2, 2])?),
+            ("bias".to_string(), tensor1(&[x, -x]).into_shape(&[1, 2, 1])?),
+        ])
+    }
+
+    fn conv_matmul(x: f32) -> TractResult<TypedModel> {
+        use crate::ops::cnn::{PaddingSpec, PoolSpec};
+        use crate::ops::matmul::MatMul;
+        use crate::ops::nn::DataFormat;
+        let mut weights = conv_matmul_weights(x)?.into_iter();
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([1, 2, 3]))?;
+        // as translated from a framework, the conv absorbs the kernel constant
+        let (name, kernel) = weights.next().unwrap();
+        let kernel = model.add_const(name, kernel)?;
+        let kernel = model.outlet_fact(kernel)?.konst.clone().unwrap();
+        let pool_spec =
+            PoolSpec::new(DataFormat::NCHW, tvec!(1), PaddingSpec::Valid, None, None, Some(2));
+        let conv = ConvUnary::new(pool_spec, Default::default(), kernel, 1, None, None);
+        let wire = model.wire_node("conv", conv, &[source])?;
+        let (name, w) = weights.next().unwrap();
+        let w = model.add_const(name, w)?;
+        let wire = model.wire_node("mm", MatMul::default(), &[w, wire[0]])?;
+        let (name, bias) = weights.next().unwrap();
+        let bias = model.add_const(name, bias)?;
+        let wire = model.wire_node("add", crate::ops::math::add::bin_typed(), &[wire[0], bias])?;
+        model.set_output_outlets(&wire)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn bind_optimized() -> TractResult<()> {
+        let input = tensor1(&[1f32, 2.0, 3.0, 4.0, 5.0, 6.0]).into_shape(&[1, 2, 3])?;
+        let expected = conv_matmul(3.0)?.into_runnable()?.run(tvec!(input.clone()))?;
+
+        let optimized = conv_matmul(1.0)?.into_optimized()?;
+        assert!(optimized.nodes().iter().all(|n| !n.op_is::<Const>()));
+        assert_eq!(optimized.clone().bind_weights(conv_matmul_weights(3.0)?)?.len(), 3);
+
+        let mut model = conv_matmul(1.0)?;
+        model.properties.insert(BINDABLE_WEIGHTS_PROPERTY.to_string(), rctensor0(1i64));
+        let mut optimized = model.into_optimized()?;
+        assert!(optimized.nodes().iter().all(|n| !n.op_is::<Const>()));
+        assert!(optimized.bind_weights(conv_matmul_weights(3.0)?)?.is_empty());
+        let found = optimized.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn dequantize_per_row() -> TractResult<()> {
+        let values = tensor2(&[[1f32, 2.0], [3.0, 4.0]]).cast_to::<f8e5m2>()?.into_owned();
+        let found = dequantize_f8(&values, &tensor1(&[1f32, 0.5]))?;
+        assert_eq!(found, tensor2(&[[1f32, 2.0], [1.5, 2.0]]));
+        let found = dequantize_f8(&values, &tensor0(0.5f32))?;
+        assert_eq!(found, tensor2(&[[0.5f32, 1.0], [1.5, 2.0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_data() {
+        let file = safetensors(&[("bias", "F32", &[2], &[0; 4])]);
+        assert!(read_safetensors(&file).is_err());
+    }
+
+    #[test]
+    fn corrupted_header() {
+        let mut file = safetensors(&[("bias", "F32", &[2], &[0; 8])]);
+        file[0..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(read_safetensors(&file).is_err());
+        let huge = usize::MAX / 2 + 1;
+        let file = safetensors(&[("bias", "F32", &[huge, 2], &[0; 8])]);
+        assert!(read_safetensors(&file).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::internal::*;
 
-#[derive(Debug, Clone, new, Hash)]
+#[derive(Debug, Clone, new, Hash, PartialEq)]
 pub struct MultiBroadcastTo {
     pub shape: ShapeFact,
 }
@@ -29,6 +29,7 @@ impl Op for MultiBroadcastTo {
 
     op_core_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl EvalOp for MultiBroadcastTo {
@@ -74,6 +75,7 @@ impl TypedOp for MultiBroadcastTo {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
         let mut fact = inputs[0].datum_type.fact(self.shape.clone());
         fact.uniform = inputs[0].uniform.clone();
+        fact.range = inputs[0].value_range();
         Ok(tvec!(fact))
     }
 
@@ -1,7 +1,7 @@
 use crate::internal::*;
 
 /// ConcatSlice: fully decluttered Concat equivalent
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq)]
 pub enum ConcatSlice {
     Const(Arc<Tensor>),
     Var,
@@ -23,7 +23,7 @@ impl ConcatSlice {
     }
 }
 
-#[derive(new, Debug, Clone, Hash)]
+#[derive(new, Debug, Clone, Hash, PartialEq)]
 pub struct TypedConcat {
     pub axis: usize,
     pub slices: TVec<ConcatSlice>,
@@ -64,6 +64,7 @@ impl Op for TypedConcat {
 
     op_core_lir_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl TypedOp for TypedConcat {
@@ -103,6 +104,12 @@ impl TypedOp for TypedConcat {
                 .map(|s| s.shape()[self.axis])
                 .sum::<usize>();
         fact.shape.set(self.axis, dim);
+        let ranges: Option<Vec<ValueRange>> = inputs
+            .iter()
+            .map(|f| f.value_range())
+            .chain(self.slices.iter().filter_map(|s| s.as_const()).map(ValueRange::of_tensor))
+            .collect();
+        fact.range = ranges.and_then(|r| r.into_iter().reduce(|a, b| a.union(&b)));
         Ok(tvec!(fact))
     }
 
@@ -1,7 +1,7 @@
 use crate::internal::*;
 use ndarray::*;
 
-#[derive(Debug, Clone, new, Hash)]
+#[derive(Debug, Clone, new, Hash, PartialEq)]
 pub struct Gather {
     pub axis: usize,
 }
@@ -14,6 +14,7 @@ impl Op for Gather {
 
     op_core_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl Gather {
@@ -35,6 +36,13 @@ impl Gather {
         Ok(output_shape)
     }
 
+    /// Proves the indices are valid for the data, negative indices counting
+    /// from the end of the axis.
+    pub fn indices_in_bounds(&self, data: &TypedFact, indices: &TypedFact) -> bool {
+        let dim = &data.shape[self.axis];
+        indices.value_range().map(|r| r.at_least(&-dim.clone()) && r.below(dim)).unwrap_or(false)
+    }
+
     unsafe fn eval_t<T: Datum>(
         &self,
         data: Arc<Tensor>,
@@ -78,10 +86,27 @@ impl TypedOp for Gather {
     as_op!();
 
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        Ok(tvec!(inputs[0].datum_type.fact(
-            &*self
-                .compute_output_shape(&*inputs[0].shape.to_tvec(), &*inputs[1].shape.to_tvec())?
-        )))
+        if let Some(range) = inputs[1].konst.as_deref().and_then(ValueRange::of_tensor) {
+            // the range of a constant is tight
+            let dim = &inputs[0].shape[self.axis];
+            let proven = |d: TDim| d.low_bound().map(|b| b >= 0).unwrap_or(false);
+            if proven(range.end.clone() - 1 - dim) || proven(-range.start.clone() - 1 - dim) {
+                bail!(
+                    "Gather indices {:?} are out of bounds for axis of size {}",
+                    inputs[1].konst.as_ref().unwrap(),
+                    dim
+                );
+            }
+        }
+        Ok(tvec!(inputs[0]
+            .datum_type
+            .fact(
+                &*self.compute_output_shape(
+                    &*inputs[0].shape.to_tvec(),
+                    &*inputs[1].shape.to_tvec()
+                )?
+            )
+            .with_range(inputs[0].value_range())))
     }
 
     fn declutter(
@@ -90,34 +115,44 @@ impl TypedOp for Gather {
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
         let indices_fact = model.outlet_fact(node.inputs[1])?;
-        if let Some(indices) = indices_fact.konst.as_ref() {
+        // a single index, either constant or pinned by its range
+        let index = if let Some(indices) = indices_fact.konst.as_ref() {
             if indices.len() == 1 {
-                let mut patch = TypedModelPatch::default();
-                let mut wire = patch.tap_model(model, node.inputs[0])?;
-                let index = indices.cast_to_scalar::<i64>()?;
-                let index = if index < 0 {
-                    let data_fact = model.outlet_fact(node.inputs[0])?;
-                    data_fact.shape[self.axis].clone() + index.to_dim()
-                } else {
-                    index.to_dim()
-                };
-                wire = patch.wire_node(
-                    format!("{}.slice", node.name),
-                    crate::ops::array::Slice {
-                        axis: self.axis,
-                        start: index.clone(),
-                        end: index + 1,
-                    },
-                    &[wire],
-                )?[0];
-                wire = patch.wire_node(
-                    format!("{}.rm_axis", node.name),
-                    crate::ops::change_axes::AxisOp::Rm(self.axis),
-                    &[wire],
-                )?[0];
-                patch.shunt_outside(model, node.id.into(), wire)?;
-                return Ok(Some(patch));
+                Some(indices.cast_to_scalar::<i64>()?)
+            } else {
+                None
             }
+        } else if indices_fact.rank() == 0
+            && self.indices_in_bounds(model.outlet_fact(node.inputs[0])?, indices_fact)
+        {
+            indices_fact
+                .value_range()
+                .filter(|r| (r.end.clone() - &r.start).is_one())
+                .and_then(|r| r.start.to_i64().ok())
+        } else {
+            None
+        };
+        if let Some(index) = index {
+            let mut patch = TypedModelPatch::default();
+            let mut wire = patch.tap_model(model, node.inputs[0])?;
+            let index = if index < 0 {
+                let data_fact = model.outlet_fact(node.inputs[0])?;
+                data_fact.shape[self.axis].clone() + index.to_dim()
+            } else {
+                index.to_dim()
+            };
+            wire = patch.wire_node(
+                format!("{}.slice", node.name),
+                crate::ops::array::Slice { axis: self.axis, start: index.clone(), end: index + 1 },
+                &[wire],
+            )?[0];
+            wire = patch.wire_node(
+                format!("{}.rm_axis", node.name),
+                crate::ops::change_axes::AxisOp::Rm(self.axis),
+                &[wire],
+            )?[0];
+            patch.shunt_outside(model, node.id.into(), wire)?;
+            return Ok(Some(patch));
         }
         Ok(None)
     }
@@ -154,4 +189,15 @@ mod tests {
             assert_eq!(*output.to_scalar::<i64>().unwrap(), idx + 1);
         }
     }
+
+    #[test]
+    fn indices_out_of_bounds() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let data = model.add_source("data", f32::fact(&[3]))?;
+        let indices = model.add_const("indices", tensor1(&[0i64, 4]))?;
+        assert!(model.wire_node("gather", Gather::new(0), &[data, indices]).is_err());
+        let indices = model.add_const("indices-ok", tensor1(&[-3i64, 2]))?;
+        assert!(model.wire_node("gather", Gather::new(0), &[data, indices]).is_ok());
+        Ok(())
+    }
 }
@@ -0,0 +1,213 @@
+use crate::internal::*;
+
+/// Key or value cache of a decoder. Each run appends the input, the entries
+/// of the current step, along `axis` to the entries of the previous runs of
+/// the state, and outputs them all: it replaces feeding the past entries in
+/// and out of the model. `SimpleState::reset_op_states` starts a new
+/// sequence.
+///
+/// Storage for `max_len` entries is allocated at the first run. When the
+/// entries are contiguous (the axes before `axis` all have size 1) and the
+/// output of the previous run has been dropped, the new entries are written
+/// in place, so a run costs O(new entries). Otherwise the cache is copied.
+///
+/// The output length on `axis` is `len`, resolved in the session at each
+/// run. Caches filled together (keys and values) can share it.
+#[derive(Clone, Debug, new, Hash, PartialEq)]
+pub struct KvCache {
+    pub axis: usize,
+    pub max_len: usize,
+    pub len: Symbol,
+}
+
+impl_dyn_hash!(KvCache);
+
+impl Op for KvCache {
+    fn name(&self) -> Cow<str> {
+        "KvCache".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!(
+            "axis: {} max_len: {} len: {}",
+            self.axis,
+            self.max_len,
+            self.len.as_char()
+        )])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for KvCache {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(KvCacheState::default())))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct KvCacheState {
+    cache: Option<Arc<Tensor>>,
+}
+
+impl KvCacheState {
+    /// Count of entries in the cache.
+    pub fn len(&self, op: &KvCache) -> usize {
+        self.cache.as_ref().map(|c| c.shape()[op.axis]).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self, op: &KvCache) -> bool {
+        self.len(op) == 0
+    }
+
+    /// A tensor of `shape`, with room for max_len entries on axis if the
+    /// entries are contiguous.
+    fn allocate(op: &KvCache, dt: DatumType, shape: &[usize]) -> TractResult<Tensor> {
+        let contiguous = shape[..op.axis].iter().all(|d| *d == 1);
+        if contiguous && dt.is_copy() {
+            let mut full: TVec<usize> = shape.into();
+            full[op.axis] = op.max_len;
+            let mut tensor = unsafe { Tensor::uninitialized_dt(dt, &full)? };
+            unsafe { tensor.set_shape_unchecked(shape) };
+            Ok(tensor)
+        } else {
+            unsafe { Tensor::uninitialized_dt(dt, shape) }
+        }
+    }
+}
+
+impl OpState for KvCacheState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        mut inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = op.downcast_ref::<KvCache>().context("Wrong op")?;
+        let input = args_1!(inputs);
+        let past = self.len(op);
+        let len = past + input.shape()[op.axis];
+        ensure!(
+            len <= op.max_len,
+            "KV cache overflow: {} entries for a capacity of {}",
+            len,
+            op.max_len
+        );
+        let mut shape: TVec<usize> = input.shape().into();
+        shape[op.axis] = len;
+        let cache = match self.cache.take() {
+            Some(mut cache) => {
+                ensure!(
+                    cache.datum_type() == input.datum_type()
+                        && cache.rank() == input.rank()
+                        && (0..input.rank())
+                            .all(|ax| ax == op.axis || cache.shape()[ax] == input.shape()[ax]),
+                    "KV cache holds {:?}, can not append {:?}",
+                    cache,
+                    input
+                );
+                let capacity = unsafe { cache.as_bytes().len() };
+                let fits = capacity
+                    >= shape.iter().product::<usize>() * cache.datum_type().size_of()
+                    && shape[..op.axis].iter().all(|d| *d == 1);
+                match Arc::get_mut(&mut cache) {
+                    Some(tensor) if fits => {
+                        unsafe { tensor.set_shape_unchecked(&shape) };
+                        tensor.assign_slice(past..len, &input, .., op.axis)?;
+                        cache
+                    }
+                    _ => {
+                        let mut tensor = Self::allocate(op, input.datum_type(), &shape)?;
+                        tensor.assign_slice(0..past, &cache, .., op.axis)?;
+                        tensor.assign_slice(past..len, &input, .., op.axis)?;
+                        tensor.into_arc_tensor()
+                    }
+                }
+            }
+            None => {
+                let mut tensor = Self::allocate(op, input.datum_type(), &shape)?;
+                tensor.assign_slice(.., &input, .., op.axis)?;
+                tensor.into_arc_tensor()
+            }
+        };
+        session.resolved_symbols[op.len] = Some(len as i64);
+        self.cache = Some(cache.clone());
+        Ok(tvec!(cache))
+    }
+}
+
+impl TypedOp for KvCache {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(self.axis < inputs[0].rank());
+        let mut fact = inputs[0].datum_type.fact(inputs[0].shape.clone());
+        fact.shape.set(self.axis, self.len.into());
+        Ok(tvec!(fact))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let mut shape: TVec<TDim> = inputs[0].shape.to_tvec();
+        shape[self.axis] = self.max_len.into();
+        Ok(tvec!((Cost::Buffer(inputs[0].datum_type), shape.iter().product())))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model(shape: &[usize], axis: usize) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("new", f32::fact(shape))?;
+        let cache =
+            model.wire_node("cache", KvCache::new(axis, 4, Symbol::from('L')), &[source])?;
+        model.set_output_outlets(&cache)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn append_in_place() -> TractResult<()> {
+        let plan = SimplePlan::new(model(&[1, 1, 2], 1)?)?;
+        let mut state = SimpleState::new(&plan)?;
+        let mut ptr = None;
+        for step in 0..4 {
+            let output = state.run(tvec!(tensor3(&[[[step as f32, 10.0 + step as f32]]])))?;
+            assert_eq!(output[0].shape(), &[1, step + 1, 2]);
+            let values = output[0].as_slice::<f32>()?;
+            assert_eq!(&values[2 * step..], &[step as f32, 10.0 + step as f32]);
+            assert_eq!(values[0], 0.0);
+            let p = output[0].as_ptr::<f32>()?;
+            assert_eq!(*ptr.get_or_insert(p), p);
+            assert_eq!(
+                state.session_state.resolved_symbols[Symbol::from('L')],
+                Some(step as i64 + 1)
+            );
+        }
+        assert!(state.run(tvec!(tensor3(&[[[0f32, 0.0]]]))).is_err());
+        state.reset_op_states()?;
+        let output = state.run(tvec!(tensor3(&[[[5f32, 6.0]]])))?;
+        assert_eq!(*output[0], tensor3(&[[[5f32, 6.0]]]));
+        Ok(())
+    }
+
+    #[test]
+    fn append_held_or_inner_axis() -> TractResult<()> {
+        let plan = SimplePlan::new(model(&[2, 1], 1)?)?;
+        let mut state = SimpleState::new(&plan)?;
+        let first = state.run(tvec!(tensor2(&[[1f32], [2.0]])))?;
+        let second = state.run(tvec!(tensor2(&[[3f32], [4.0]])))?;
+        assert_eq!(*first[0], tensor2(&[[1f32], [2.0]]));
+        assert_eq!(*second[0], tensor2(&[[1f32, 3.0], [2.0, 4.0]]));
+        Ok(())
+    }
+}
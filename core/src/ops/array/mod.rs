@@ -5,6 +5,7 @@ pub mod dyn_slice;
 mod gather;
 mod gather_elements;
 mod gather_nd;
+mod kv_cache;
 mod one_hot;
 mod pad;
 mod range;
@@ -20,6 +21,7 @@ pub use self::dyn_slice::DynSlice;
 pub use self::gather::Gather;
 pub use self::gather_elements::GatherElements;
 pub use self::gather_nd::GatherNd;
+pub use self::kv_cache::{KvCache, KvCacheState};
 pub use self::one_hot::OneHot;
 pub use self::pad::{Pad, PadMode};
 pub use self::reshape::FiniteReshape;
@@ -18,6 +18,7 @@ impl Op for OneHot {
 
     op_core!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl TypedOp for OneHot {
@@ -15,7 +15,7 @@ impl Default for PadMode {
     }
 }
 
-#[derive(Debug, Clone, new, Default, Hash)]
+#[derive(Debug, Clone, new, Default, Hash, PartialEq)]
 pub struct Pad {
     pub pads: Vec<(usize, usize)>,
     pub mode: PadMode,
@@ -95,6 +95,7 @@ impl Op for Pad {
 
     op_core_lir_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl EvalOp for Pad {
@@ -119,6 +120,10 @@ impl TypedOp for Pad {
         for (ix, (b, e)) in self.pads.iter().enumerate() {
             fact.shape.set(ix, fact.shape[ix].clone() + *b + *e);
         }
+        if let PadMode::Constant(value) = &self.mode {
+            fact.range =
+                fact.range.take().and_then(|r| ValueRange::of_tensor(value).map(|v| r.union(&v)));
+        }
         Ok(tvec!(fact))
     }
 
@@ -155,7 +160,7 @@ impl TypedOp for Pad {
             }
         }
         if let (InOut::In(0), AxisOp::Add(ix)) = (io, change) {
-            new_op.pads.insert(*ix, (0,0));
+            new_op.pads.insert(*ix, (0, 0));
             return Ok(Some(AxisChangeConsequence::new(
                 model,
                 node,
@@ -90,6 +90,23 @@ impl Range {
         }
     }
 
+    /// Bounds of the generated values.
+    fn value_range(&self) -> Option<ValueRange> {
+        let dt = self.start.datum_type();
+        if !(dt.is_integer() || dt == TDim::datum_type()) {
+            return None;
+        }
+        let dim = |t: &Tensor| -> Option<TDim> {
+            Some(t.cast_to::<TDim>().ok()?.to_scalar::<TDim>().ok()?.clone())
+        };
+        let (start, end, step) = (dim(&self.start)?, dim(&self.end)?, dim(&self.step)?);
+        if step.to_i64().ok()? > 0 {
+            Some(ValueRange { start, end })
+        } else {
+            Some(ValueRange { start: end + 1, end: start + 1 })
+        }
+    }
+
     fn len_for_numbers<T: Datum + AsPrimitive<f64>>(&self) -> TractResult<usize> {
         let start = self.start.to_scalar::<T>()?;
         let end = self.end.to_scalar::<T>()?;
@@ -110,7 +127,7 @@ impl TypedOp for Range {
         } else {
             dispatch_numbers!(Self::len_for_numbers(self.start.datum_type())(self))?.into()
         };
-        Ok(tvec!(self.start.datum_type().fact(&[len])))
+        Ok(tvec!(self.start.datum_type().fact(&[len]).with_range(self.value_range())))
     }
     as_op!();
 }
@@ -1,7 +1,7 @@
 use crate::internal::*;
 use tract_itertools::Itertools;
 
-#[derive(Debug, Clone, new, Default, Hash)]
+#[derive(Debug, Clone, new, Default, Hash, PartialEq)]
 pub struct FiniteReshape {
     pub shape: TVec<usize>,
 }
@@ -17,6 +17,7 @@ impl Op for FiniteReshape {
 
     op_core_lir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl_dyn_hash!(FiniteReshape);
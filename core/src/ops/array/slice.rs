@@ -47,13 +47,46 @@ impl Op for Slice {
 
 impl EvalOp for Slice {
     fn is_stateless(&self) -> bool {
-        true
+        self.start.to_i64().is_ok() && self.end.to_i64().is_ok()
     }
 
-    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        self.eval_with_values(inputs, &SymbolValues::default())
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        if self.is_stateless() {
+            Ok(None)
+        } else {
+            Ok(Some(Box::new(self.clone())))
+        }
+    }
+}
+
+impl OpState for Slice {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        self.eval_with_values(inputs, &session.resolved_symbols)
+    }
+}
+
+impl Slice {
+    fn eval_with_values(
+        &self,
+        mut inputs: TVec<Arc<Tensor>>,
+        values: &SymbolValues,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
         let input = args_1!(inputs);
-        let start = self.start.to_usize()?;
-        let end = self.end.to_usize()?;
+        let start = self.start.eval(values).to_usize()?;
+        let end = self.end.eval(values).to_usize()?;
         if end > input.shape()[self.axis] || start > end {
             bail!("Invalid range {}..{} for slicing {:?} on axis {}", start, end, input, self.axis);
         }
@@ -200,3 +233,40 @@ impl TypedOp for Slice {
 
     as_op!();
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn symbolic_end() -> TractResult<()> {
+        let s = Symbol::from('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[s.to_dim()]))?;
+        let slice = Slice::new(0, 0, 4.to_dim().mini(s.to_dim()));
+        let output = model.wire_node("slice", slice, &[source])?;
+        model.set_output_outlets(&output)?;
+        assert_eq!(model.outlet_fact(output[0])?.shape[0], 4.to_dim().mini(s.to_dim()));
+        let plan = SimplePlan::new(model)?;
+        let output = plan.run(tvec!(tensor1(&[1f32, 2.0])))?;
+        assert_eq!(*output[0], tensor1(&[1f32, 2.0]));
+        let output = plan.run(tvec!(tensor1(&[1f32, 2.0, 3.0, 4.0, 5.0])))?;
+        assert_eq!(*output[0], tensor1(&[1f32, 2.0, 3.0, 4.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn assumed_noop() -> TractResult<()> {
+        let b = Symbol::new('B');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[b.to_dim()]))?;
+        let slice = Slice::new(0, 0, b.to_dim().mini(32.to_dim()));
+        let output = model.wire_node("slice", slice, &[source])?;
+        model.set_output_outlets(&output)?;
+        let model = model.with_assumptions(Some((b, Assumption::AtMost(32))))?;
+        assert_eq!(model.outlet_fact(model.output_outlets()?[0])?.shape[0], b.to_dim());
+        let model = model.into_decluttered()?;
+        assert_eq!(model.nodes().len(), 1);
+        Ok(())
+    }
+}
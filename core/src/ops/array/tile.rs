@@ -1,7 +1,7 @@
 use crate::internal::*;
 use ndarray::*;
 
-#[derive(Debug, Clone, new, Default, Hash)]
+#[derive(Debug, Clone, new, Default, Hash, PartialEq)]
 pub struct Tile {
     pub multipliers: TVec<usize>,
 }
@@ -38,6 +38,7 @@ impl Op for Tile {
 
     op_core_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl EvalOp for Tile {
@@ -50,9 +50,36 @@ pub trait BinMiniOp:
     fn eval_unicast_in_place(&self, a: &Tensor, b: &mut Tensor) -> TractResult<()>;
     fn eval_uniform_in_place(&self, a: &Tensor, b: &mut Tensor) -> TractResult<()>;
     fn eval_out_of_place(&self, c: &mut Tensor, a: &Tensor, b: &Tensor) -> TractResult<()>;
+    /// Computes the result in `a`, `b` having the shape of `a` or a single
+    /// element and no more axes.
+    fn eval_in_a(&self, a: &mut Tensor, b: &Tensor) -> TractResult<()> {
+        let mut c = unsafe { Tensor::uninitialized_dt(a.datum_type(), a.shape())? };
+        self.eval_out_of_place(&mut c, a, b)?;
+        *a = c;
+        Ok(())
+    }
+    /// Whether results of type `dt` can only be computed out of place.
+    #[allow(unused_variables)]
+    fn out_of_place_only(&self, dt: DatumType) -> bool {
+        false
+    }
     fn generic_eval(&self, a: Arc<Tensor>, b: Arc<Tensor>) -> TractResult<Tensor> {
         let c_dt = self.result_datum_type(a.datum_type(), b.datum_type())?;
-        if c_dt == b.datum_type() && a.len() == 1 {
+        if self.out_of_place_only(c_dt) {
+            let c_shape = crate::broadcast::multi_broadcast(&[a.shape(), b.shape()])
+                .ok_or_else(|| format_err!("Can not compute resulting shape"))?;
+            let mut c = unsafe { Tensor::uninitialized_dt(c_dt, &c_shape)? };
+            self.eval_out_of_place(&mut c, a.as_ref(), b.as_ref())?;
+            Ok(c)
+        } else if Arc::strong_count(&b) > 1
+            && Arc::strong_count(&a) == 1
+            && c_dt == a.datum_type()
+            && (a.shape() == b.shape() || (b.len() == 1 && b.rank() <= a.rank()))
+        {
+            let mut a = a.into_tensor();
+            self.eval_in_a(&mut a, &b)?;
+            Ok(a)
+        } else if c_dt == b.datum_type() && a.len() == 1 {
             let mut b = b.into_tensor();
             self.eval_uniform_in_place(&a, &mut b)?;
             Ok(b)
@@ -108,6 +135,10 @@ pub trait BinMiniOp:
     fn as_linalg_binop(&self) -> Option<tract_linalg::mmm::BinOp> {
         None
     }
+    #[allow(unused_variables)]
+    fn same_as(&self, other: &dyn BinMiniOp) -> bool {
+        false
+    }
 }
 dyn_clone::clone_trait_object!(BinMiniOp);
 downcast_rs::impl_downcast!(BinMiniOp);
@@ -132,6 +163,14 @@ impl Op for TypedBinOp {
         self.0.validation()
     }
 
+    fn same_as(&self, other: &dyn Op) -> bool {
+        if let Some(other) = other.downcast_ref::<TypedBinOp>() {
+            self.0.same_as(&*other.0)
+        } else {
+            false
+        }
+    }
+
     op_core_mir!();
     op_as_typed_op!();
 }
@@ -146,6 +185,10 @@ impl EvalOp for TypedBinOp {
         debug_assert_eq!(a.rank(), b.rank());
         Ok(tvec!(self.0.eval(a, b)?.into_arc_tensor()))
     }
+
+    fn eval_in_place_input(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl TypedOp for TypedBinOp {
@@ -153,17 +196,21 @@ impl TypedOp for TypedBinOp {
         if inputs[0].rank() != inputs[1].rank() {
             bail!("Typed ops require rank match. Invalid inputs for {}: {:?}", self.name(), inputs);
         }
-        Ok(tvec!(self.0.result_datum_type(inputs[0].datum_type, inputs[1].datum_type)?.fact(
-            &*crate::broadcast::multi_broadcast(&[
-                &inputs[0].shape.to_tvec(),
-                &inputs[1].shape.to_tvec()
-            ])
-            .ok_or_else(|| format_err!(
-                "Can not broadcast shapes a:{:?} b:{:?}",
-                &inputs[0],
-                &inputs[1]
-            ))?
-        )))
+        let dt = self.0.result_datum_type(inputs[0].datum_type, inputs[1].datum_type)?;
+        let range = bin_value_range(&*self.0, dt, inputs[0].value_range(), inputs[1].value_range());
+        Ok(tvec!(dt
+            .fact(
+                &*crate::broadcast::multi_broadcast(&[
+                    &inputs[0].shape.to_tvec(),
+                    &inputs[1].shape.to_tvec()
+                ])
+                .ok_or_else(|| format_err!(
+                    "Can not broadcast shapes a:{:?} b:{:?}",
+                    &inputs[0],
+                    &inputs[1]
+                ))?
+            )
+            .with_range(range)))
     }
 
     fn change_axes(
@@ -315,6 +362,54 @@ impl TypedOp for TypedBinOp {
     as_op!();
 }
 
+/// Bounds of the result of an integer binary op, from the bounds of its
+/// operands. None if the op may overflow the datum type.
+pub fn bin_value_range(
+    mini_op: &dyn BinMiniOp,
+    dt: DatumType,
+    a: Option<ValueRange>,
+    b: Option<ValueRange>,
+) -> Option<ValueRange> {
+    use crate::ops::math::{Add, Max, Min};
+    if !(dt.is_integer() || dt == TDim::datum_type()) || dt.is_quantized() {
+        return None;
+    }
+    let (a, b) = (a?, b?);
+    if mini_op.is::<Add>() {
+        let range = ValueRange { start: a.start + b.start, end: a.end + b.end - 1 };
+        if dt != TDim::datum_type() {
+            let (min, max) = integer_bounds(dt)?;
+            let low = range.start.low_bound()?;
+            let high = (range.end.clone() - 1).high_bound()?;
+            if low < min || high > max {
+                return None;
+            }
+        }
+        Some(range)
+    } else if mini_op.is::<Min>() {
+        Some(ValueRange { start: a.start.mini(b.start), end: a.end.mini(b.end) })
+    } else if mini_op.is::<Max>() {
+        Some(ValueRange { start: a.start.maxi(b.start), end: a.end.maxi(b.end) })
+    } else {
+        None
+    }
+}
+
+/// Smallest and largest values of an integer type, as far as an i64 goes.
+fn integer_bounds(dt: DatumType) -> Option<(i64, i64)> {
+    match dt {
+        DatumType::I8 => Some((i8::MIN as i64, i8::MAX as i64)),
+        DatumType::I16 => Some((i16::MIN as i64, i16::MAX as i64)),
+        DatumType::I32 => Some((i32::MIN as i64, i32::MAX as i64)),
+        DatumType::I64 => Some((i64::MIN, i64::MAX)),
+        DatumType::U8 => Some((0, u8::MAX as i64)),
+        DatumType::U16 => Some((0, u16::MAX as i64)),
+        DatumType::U32 => Some((0, u32::MAX as i64)),
+        DatumType::U64 => Some((0, i64::MAX)),
+        _ => None,
+    }
+}
+
 fn declutter_bin_to_unary(
     model: &TypedModel,
     node: &TypedNode,
@@ -360,6 +455,14 @@ impl Op for UnaryOp {
         self.mini_op.validation()
     }
 
+    fn same_as(&self, other: &dyn Op) -> bool {
+        if let Some(other) = other.downcast_ref::<UnaryOp>() {
+            self.a == other.a && self.mini_op.same_as(&*other.mini_op)
+        } else {
+            false
+        }
+    }
+
     op_core_lir_mir!();
     op_as_typed_op!();
 }
@@ -373,6 +476,10 @@ impl EvalOp for UnaryOp {
         debug_assert_eq!(self.a.rank(), inputs[0].rank());
         Ok(tvec!(self.mini_op.eval(self.a.clone(), inputs.remove(0))?.into_arc_tensor()))
     }
+
+    fn eval_in_place_input(&self) -> Option<usize> {
+        Some(0)
+    }
 }
 
 impl TypedOp for UnaryOp {
@@ -380,17 +487,26 @@ impl TypedOp for UnaryOp {
         if self.a.rank() != inputs[0].rank() {
             bail!("Rank mismatch: constant: {:?}, input: {:?}", self.a, inputs[0]);
         }
-        Ok(tvec!(self.mini_op.result_datum_type(self.a.datum_type(), inputs[0].datum_type)?.fact(
-            &*crate::broadcast::multi_broadcast(&[
-                &*self.a.shape().iter().map(|d| d.to_dim()).collect::<TVec<_>>(),
-                &*inputs[0].shape.to_tvec()
-            ])
-            .ok_or_else(|| format_err!(
-                "Failed to broadcast {:?} and {:?}",
-                self.a.shape(),
-                inputs[0].shape
-            ))?
-        )))
+        let dt = self.mini_op.result_datum_type(self.a.datum_type(), inputs[0].datum_type)?;
+        let range = bin_value_range(
+            &*self.mini_op,
+            dt,
+            ValueRange::of_tensor(&self.a),
+            inputs[0].value_range(),
+        );
+        Ok(tvec!(dt
+            .fact(
+                &*crate::broadcast::multi_broadcast(&[
+                    &*self.a.shape().iter().map(|d| d.to_dim()).collect::<TVec<_>>(),
+                    &*inputs[0].shape.to_tvec()
+                ])
+                .ok_or_else(|| format_err!(
+                    "Failed to broadcast {:?} and {:?}",
+                    self.a.shape(),
+                    inputs[0].shape
+                ))?
+            )
+            .with_range(range)))
     }
 
     fn invariants(
@@ -509,6 +625,14 @@ impl Op for MergeOpUnicast {
         format!("{}Unicast", self.0.name()).into()
     }
 
+    fn same_as(&self, other: &dyn Op) -> bool {
+        if let Some(other) = other.downcast_ref::<MergeOpUnicast>() {
+            self.0.same_as(&*other.0)
+        } else {
+            false
+        }
+    }
+
     op_core_lir_mir!();
     op_as_typed_op!();
 }
@@ -524,12 +648,22 @@ impl EvalOp for MergeOpUnicast {
         self.0.eval_unicast_in_place(a.as_ref(), &mut b)?;
         Ok(tvec!(b.into_arc_tensor()))
     }
+
+    fn eval_in_place_input(&self) -> Option<usize> {
+        Some(1)
+    }
 }
 
 impl TypedOp for MergeOpUnicast {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
         debug_assert_eq!(inputs[0].shape, inputs[1].shape);
-        Ok(tvec!(inputs[0].clone()))
+        let range = bin_value_range(
+            &*self.0,
+            inputs[0].datum_type,
+            inputs[0].value_range(),
+            inputs[1].value_range(),
+        );
+        Ok(tvec!(inputs[0].without_value().with_range(range)))
     }
 
     fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
@@ -567,6 +701,7 @@ macro_rules! bin_to_super_type {
      $(flip: $flip:expr,)?
      $(linalg: $linalg:ident,)?
      $(out_of_place: $out_of_place:expr,)?
+     $(out_of_place_only: $out_of_place_only:expr,)?
      $(validation: $validation:expr,)?
      $(q: $([$($typ_dt:ident),*] => $cab_dt:expr),* ;)?
      $( [$($typ:ident),*] => $cab:expr),*) => {
@@ -578,6 +713,10 @@ macro_rules! bin_to_super_type {
                 stringify!($Op)
             }
 
+            fn same_as(&self, other: &dyn $crate::ops::binary::BinMiniOp) -> bool {
+                other.downcast_ref::<Self>().is_some()
+            }
+
             fn eval_uniform_in_place(&self, a: &Tensor, b: &mut Tensor) -> TractResult<()> {
                 $(
                     $(if a.datum_type() == $typ::datum_type() {
@@ -657,6 +796,28 @@ macro_rules! bin_to_super_type {
                     bail!("{} does not support {:?} (inplace)", self.name(), a.datum_type());
             }
 
+            fn eval_in_a(&self, a: &mut Tensor, b: &Tensor) -> TractResult<()> {
+                $(
+                    $(if a.datum_type() == $typ::datum_type() && b.datum_type() == $typ::datum_type() {
+                        let cab: fn(&mut $typ, &$typ, &$typ) -> () = $cab;
+                        let b = b.as_slice::<$typ>()?;
+                        let a = a.as_slice_mut::<$typ>()?;
+                        let uniform = b.len() == 1;
+                        for i in 0..a.len() {
+                            let mut c = $typ::default();
+                            cab(&mut c, &a[i], &b[if uniform { 0 } else { i }]);
+                            a[i] = c;
+                        }
+                        return Ok(())
+                    }
+                    )*
+                 )*
+                    let mut c = unsafe { Tensor::uninitialized_dt(a.datum_type(), a.shape())? };
+                    self.eval_out_of_place(&mut c, a, b)?;
+                    *a = c;
+                    Ok(())
+            }
+
             fn eval_out_of_place(&self, c: &mut Tensor, a: &Tensor, b: &Tensor) -> TractResult<()> {
                 $(if $out_of_place(c, a, b)? { return Ok(()) } )?
                     $(
@@ -744,6 +905,11 @@ macro_rules! bin_to_super_type {
                         ($cost)(dt)
                     }
                  )?
+                $(
+                    fn out_of_place_only(&self, dt: DatumType) -> bool {
+                        ($out_of_place_only)(dt)
+                    }
+                 )?
                 $(
                     fn validation(&self) -> Validation {
                         $validation
@@ -782,6 +948,10 @@ macro_rules! bin_to_bool {
                 stringify!($Op)
             }
 
+            fn same_as(&self, other: &dyn $crate::ops::binary::BinMiniOp) -> bool {
+                other.downcast_ref::<Self>().is_some()
+            }
+
             fn eval_uniform_in_place(&self, a: &Tensor, b: &mut Tensor) -> TractResult<()> {
                 $(
                     $(if a.datum_type() == $typ::datum_type() {
@@ -4,7 +4,7 @@ pub fn cast(to: DatumType) -> Cast {
     Cast { to }
 }
 
-#[derive(Debug, Clone, new, Hash)]
+#[derive(Debug, Clone, new, Hash, PartialEq)]
 pub struct Cast {
     pub to: DatumType,
 }
@@ -18,6 +18,7 @@ impl Op for Cast {
 
     op_core!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl EvalOp for Cast {
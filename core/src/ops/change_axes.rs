@@ -455,6 +455,7 @@ impl Op for AxisOp {
 
     op_core_lir_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl_dyn_hash!(AxisOp);
@@ -510,7 +511,7 @@ impl TypedOp for AxisOp {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
         let mut shape = inputs[0].shape.clone();
         self.change_shape(&mut shape, false)?;
-        Ok(tvec!(inputs[0].datum_type.fact(shape)))
+        Ok(tvec!(inputs[0].datum_type.fact(shape).with_range(inputs[0].value_range())))
     }
 
     fn invariants(
@@ -0,0 +1,168 @@
+//! Benchmark-guided selection of the convolution lowering.
+//!
+//! By default, a convolution is lowered according to fixed rules. With the
+//! autotune property set, every lowering applicable to a convolution is timed
+//! on its actual input shape when the model is optimized, and the fastest one
+//! is kept. Timings only depend on the geometry of the convolution, so the
+//! selections are cached for the process.
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::internal::*;
+
+use super::winograd::winograd_tile;
+use super::ConvUnary;
+
+/// Model property enabling the benchmark-guided selection of convolution
+/// lowerings. An integer scalar, non-zero to enable it.
+pub const AUTOTUNE_PROPERTY: &str = "conv.autotune";
+
+/// Timed runs of each candidate, after a warm-up run. The fastest run counts.
+const BENCH_RUNS: usize = 5;
+
+/// Lowering of a convolution.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum ConvAlgorithm {
+    /// Patches materialized by Im2Col, then a matrix product.
+    Im2col,
+    /// A matrix product reading the patches directly in the input (lazy
+    /// im2col).
+    Direct,
+    /// Winograd transforms, for 3x3 stride-1 convolutions.
+    Winograd,
+    /// Per-channel direct convolution, for depth-wise convolutions.
+    DepthWise,
+}
+
+pub fn autotune_enabled(model: &TypedModel) -> TractResult<bool> {
+    if let Some(prop) = model.properties.get(AUTOTUNE_PROPERTY) {
+        Ok(prop.cast_to_scalar::<i64>()? != 0)
+    } else {
+        Ok(false)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SELECTED: Mutex<HashMap<u64, ConvAlgorithm>> = Mutex::new(HashMap::new());
+}
+
+/// The fastest of the `candidates` lowerings of conv, for `input_fact`.
+pub fn fastest(
+    conv: &ConvUnary,
+    model: &TypedModel,
+    input_fact: &TypedFact,
+    candidates: &[ConvAlgorithm],
+) -> TractResult<ConvAlgorithm> {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    conv.pool_spec.hash(&mut hasher);
+    conv.kernel_fmt.hash(&mut hasher);
+    conv.kernel.shape().hash(&mut hasher);
+    conv.kernel.datum_type().hash(&mut hasher);
+    conv.group.hash(&mut hasher);
+    conv.bias.is_some().hash(&mut hasher);
+    input_fact.datum_type.hash(&mut hasher);
+    input_fact.shape.hash(&mut hasher);
+    winograd_tile(model)?.hash(&mut hasher);
+    candidates.hash(&mut hasher);
+    let key = hasher.finish();
+    if let Some(algorithm) = SELECTED.lock().unwrap().get(&key) {
+        return Ok(*algorithm);
+    }
+    let mut best = (candidates[0], Duration::MAX);
+    for &algorithm in candidates {
+        match bench(conv, model, input_fact, algorithm) {
+            Ok(time) => {
+                debug!("{:?} lowering of {:?}: {:?}", algorithm, conv.pool_spec, time);
+                if time < best.1 {
+                    best = (algorithm, time);
+                }
+            }
+            Err(e) => debug!("Failed to bench {:?} lowering: {:?}", algorithm, e),
+        }
+    }
+    SELECTED.lock().unwrap().insert(key, best.0);
+    Ok(best.0)
+}
+
+/// Best run time of the conv lowered with `algorithm`.
+fn bench(
+    conv: &ConvUnary,
+    model: &TypedModel,
+    input_fact: &TypedFact,
+    algorithm: ConvAlgorithm,
+) -> TractResult<Duration> {
+    let mut bench = TypedModel { properties: model.properties.clone(), ..TypedModel::default() };
+    let shape = input_fact.shape.as_concrete().context("Expects a concrete input shape")?;
+    let input = Tensor::zero_dt(input_fact.datum_type, shape)?;
+    let source = bench.add_source("input", input_fact.datum_type.fact(shape))?;
+    let wire = bench.wire_node("conv", conv.clone(), &[source])?;
+    bench.set_output_outlets(&wire)?;
+    let patch = conv.lower(&bench, bench.node(wire[0].node), algorithm)?;
+    patch.apply(&mut bench)?;
+    let plan = SimplePlan::new(bench)?;
+    let mut state = SimpleState::new(&plan)?;
+    state.run(tvec!(input.clone()))?;
+    let mut best = Duration::MAX;
+    for _ in 0..BENCH_RUNS {
+        let start = Instant::now();
+        state.run(tvec!(input.clone()))?;
+        best = best.min(start.elapsed());
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::conv::WINOGRAD_PROPERTY;
+    use crate::ops::cnn::{PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+
+    #[test]
+    fn autotuned_conv() -> TractResult<()> {
+        let kernel: Vec<f32> = (0..16 * 8 * 9).map(|i| ((i * 7) % 11) as f32 / 5.0 - 1.0).collect();
+        let kernel = tensor1(&kernel).into_shape(&[16, 8, 3, 3])?;
+        let pool_spec = PoolSpec::new(
+            DataFormat::NCHW,
+            tvec!(3, 3),
+            PaddingSpec::SameUpper,
+            None,
+            None,
+            Some(16),
+        );
+        let conv =
+            ConvUnary::new(pool_spec, Default::default(), kernel.into_arc_tensor(), 1, None, None);
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[1, 8, 12, 12]))?;
+        let wire = model.wire_node("conv", conv.clone(), &[source])?;
+        model.set_output_outlets(&wire)?;
+        let input: Vec<f32> = (0..8 * 144).map(|i| ((i * 5) % 13) as f32 / 6.0 - 1.0).collect();
+        let input = tensor1(&input).into_shape(&[1, 8, 12, 12])?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+
+        let input_fact = model.outlet_fact(source)?.clone();
+        let candidates = conv.algorithms(&model, &input_fact)?;
+        assert_eq!(&*candidates, &[ConvAlgorithm::Direct, ConvAlgorithm::Im2col]);
+        model.properties.insert(WINOGRAD_PROPERTY.to_string(), rctensor0(2i64));
+        let candidates = conv.algorithms(&model, &input_fact)?;
+        assert_eq!(
+            &*candidates,
+            &[ConvAlgorithm::Winograd, ConvAlgorithm::Direct, ConvAlgorithm::Im2col]
+        );
+        model.properties.insert(AUTOTUNE_PROPERTY.to_string(), rctensor0(1i64));
+        let selected = fastest(&conv, &model, &input_fact, &candidates)?;
+        assert!(candidates.contains(&selected));
+        for &algorithm in &candidates {
+            let patch = conv.lower(&model, model.node(wire[0].node), algorithm)?;
+            let mut lowered = model.clone();
+            patch.apply(&mut lowered)?;
+            let found = lowered.into_runnable()?.run(tvec!(input.clone()))?;
+            found[0].close_enough(&expected[0], true)?;
+        }
+        let found = model.into_optimized()?.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)?;
+        Ok(())
+    }
+}
@@ -37,11 +37,117 @@ impl EvalOp for DepthWise {
     }
 
     fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        if inputs[0].datum_type() == f32::datum_type() && self.is_3x3() {
+            if let Some(output) = self.eval_3x3_f32(&inputs[0])? {
+                return Ok(tvec!(output.into_arc_tensor()));
+            }
+        }
         dispatch_floatlike!(Self::eval_t(inputs[0].datum_type())(self, inputs))
     }
 }
 
 impl DepthWise {
+    fn is_3x3(&self) -> bool {
+        let spec = &self.patch.spec;
+        &*spec.kernel_shape == &[3, 3] && spec.dilations.iter().all(|&d| d == 1)
+    }
+
+    /// Runs the valid zone on tract-linalg depthwise 3x3 kernels, if the
+    /// layout and strides allow it. Border zones go through the generic path.
+    fn eval_3x3_f32(&self, img: &Tensor) -> TractResult<Option<Tensor>> {
+        let strides = &*self.patch.spec.strides;
+        let (is_h, is_w) = (self.input_shape.hw_strides()[0], self.input_shape.hw_strides()[1]);
+        let (os_h, os_w) = (self.output_shape.hw_strides()[0], self.output_shape.hw_strides()[1]);
+        let planar = is_w == 1 && os_w == 1 && strides[1] <= 2;
+        let interleaved = *self.input_shape.c_stride() == 1 && *self.output_shape.c_stride() == 1;
+        let valid = if let Some(valid) = self.patch.valid_zone_id {
+            &self.patch.zones[valid]
+        } else {
+            return Ok(None);
+        };
+        if !planar && !interleaved {
+            return Ok(None);
+        }
+        let dw = (tract_linalg::ops().depthwise_3x3_f32)();
+        let mut output = unsafe { Tensor::uninitialized::<f32>(&*self.output_shape.shape)? };
+        let kernel = self.kernel_chw.as_slice::<f32>()?;
+        let channels = *self.input_shape.c();
+        let packed = if planar {
+            vec![]
+        } else {
+            tract_linalg::depthwise::pack_interleaved_kernel(kernel, channels)
+        };
+        let bias = self.bias.as_slice::<f32>()?;
+        let (pad_h, pad_w) = (self.patch.pad_before[0] as isize, self.patch.pad_before[1] as isize);
+        let (rows, cols) = (valid.output_ranges[0].clone(), valid.output_ranges[1].clone());
+        let c_stride_i = *self.input_shape.c_stride() as isize;
+        let c_stride_o = *self.output_shape.c_stride() as isize;
+        unsafe {
+            for n in 0..*self.input_shape.n().unwrap_or(&1) as isize {
+                let iptr = img
+                    .as_ptr::<f32>()?
+                    .offset(n * *self.input_shape.n_stride().unwrap_or(&0) as isize);
+                let optr = output
+                    .as_ptr_mut::<f32>()?
+                    .offset(n * *self.output_shape.n_stride().unwrap_or(&0) as isize);
+                let input_at = |y: usize, x: usize, ky: usize, kx: usize| {
+                    let iy = (y * strides[0]) as isize - pad_h + ky as isize;
+                    let ix = (x * strides[1]) as isize - pad_w + kx as isize;
+                    iptr.offset(iy * is_h as isize + ix * is_w as isize)
+                };
+                for zone in self.patch.zones.iter().filter(|z| !z.valid) {
+                    self.process_zone(
+                        zone,
+                        c_stride_i,
+                        c_stride_o,
+                        self.kernel_chw.strides()[1],
+                        iptr,
+                        kernel.as_ptr(),
+                        bias.as_ptr(),
+                        optr,
+                    )
+                }
+                if planar {
+                    for c in 0..channels {
+                        let mut k = [0f32; 9];
+                        k.copy_from_slice(&kernel[9 * c..][..9]);
+                        for y in rows.clone() {
+                            let at =
+                                |ky| input_at(y, cols.start, ky, 0).offset(c as isize * c_stride_i);
+                            dw.planar_row(
+                                strides[1],
+                                [at(0), at(1), at(2)],
+                                &k,
+                                bias[c],
+                                optr.offset(
+                                    c as isize * c_stride_o + (y * os_h + cols.start) as isize,
+                                ),
+                                cols.len(),
+                            );
+                        }
+                    }
+                } else {
+                    let mut inputs = [std::ptr::null(); 9];
+                    for y in rows.clone() {
+                        for x in cols.clone() {
+                            for t in 0..9 {
+                                inputs[t] = input_at(y, x, t / 3, t % 3);
+                            }
+                            dw.interleaved_pixel(
+                                &inputs,
+                                &packed,
+                                bias.as_ptr(),
+                                optr.offset((y * os_h + x * os_w) as isize),
+                                channels,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Some(output))
+    }
+
     fn eval_t<T: Datum + Copy + num_traits::Zero + ndarray::LinalgScalar>(
         &self,
         mut inputs: TVec<Arc<Tensor>>,
@@ -429,3 +535,74 @@ unsafe fn process_zone_4<T: Datum + Copy + ndarray::LinalgScalar>(
     }
 }
 */
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::conv::{ConvUnary, KernelFormat};
+    use crate::ops::cnn::{PaddingSpec, PoolSpec};
+    use crate::ops::nn::DataFormat;
+
+    fn check(
+        fmt: DataFormat,
+        padding: PaddingSpec,
+        stride: usize,
+        hw: (usize, usize),
+        c: usize,
+    ) -> TractResult<()> {
+        let kernel = Tensor::from_shape(
+            &[c, 1, 3, 3],
+            &(0..c * 9).map(|i| ((i * 7) % 11) as f32 / 11.0 - 0.5).collect::<Vec<_>>(),
+        )?;
+        let bias = Tensor::from_shape(&[c], &(0..c).map(|i| i as f32).collect::<Vec<_>>())?;
+        let strides = Some(tvec!(stride, stride));
+        let op = ConvUnary::new(
+            PoolSpec::new(fmt, tvec!(3, 3), padding, None, strides, Some(c)),
+            KernelFormat::OIHW,
+            kernel.into_arc_tensor(),
+            c,
+            Some(bias.into_arc_tensor()),
+            None,
+        );
+        let shape = fmt.from_n_c_hw(1, c, &[hw.0, hw.1])?.shape;
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&shape))?;
+        let conv = model.wire_node("conv", op, &[source])?;
+        model.set_output_outlets(&conv)?;
+        let len = shape.iter().product::<usize>();
+        let data = Tensor::from_shape(
+            &shape,
+            &(0..len).map(|i| ((i * 5) % 13) as f32 / 13.0).collect::<Vec<_>>(),
+        )?;
+        let expected = model.clone().into_runnable()?.run(tvec!(data.clone()))?;
+        let optimized = model.into_optimized()?;
+        assert!(optimized.nodes().iter().any(|n| n.op_is::<DepthWise>()));
+        let found = optimized.into_runnable()?.run(tvec!(data))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn planar_s1_same() -> TractResult<()> {
+        check(DataFormat::NCHW, PaddingSpec::SameUpper, 1, (7, 21), 3)
+    }
+
+    #[test]
+    fn planar_s2_same() -> TractResult<()> {
+        check(DataFormat::NCHW, PaddingSpec::SameUpper, 2, (9, 37), 2)
+    }
+
+    #[test]
+    fn planar_s2_valid() -> TractResult<()> {
+        check(DataFormat::CHW, PaddingSpec::Valid, 2, (8, 20), 4)
+    }
+
+    #[test]
+    fn interleaved_s1_same() -> TractResult<()> {
+        check(DataFormat::NHWC, PaddingSpec::SameUpper, 1, (6, 5), 19)
+    }
+
+    #[test]
+    fn interleaved_s2_valid() -> TractResult<()> {
+        check(DataFormat::HWC, PaddingSpec::Valid, 2, (9, 7), 16)
+    }
+}
@@ -1,3 +1,4 @@
+mod autotune;
 mod depth_wise;
 mod im2col;
 mod lazy_im2col;
@@ -7,12 +8,15 @@ pub mod proptest;
 mod proptest_q;
 mod q_sum_b;
 mod unary;
+mod winograd;
 
 use crate::internal::*;
 
+pub use self::autotune::{ConvAlgorithm, AUTOTUNE_PROPERTY};
 pub use self::im2col::Im2Col;
 pub(crate) use self::q_sum_b::QSumB;
 pub use self::unary::ConvUnary;
+pub use self::winograd::{WinogradConv, WINOGRAD_PROPERTY};
 
 #[derive(Debug, Copy, Clone, PartialEq, Hash)]
 pub enum KernelFormat {
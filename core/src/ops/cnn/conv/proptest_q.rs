@@ -57,12 +57,18 @@ impl QConvProblem {
         let n = *self.shape_in.n().clone().unwrap_or(&1);
         let ci_per_g = self.shape_in.c() / self.group;
         let co_per_g = self.co / self.group;
-        let a0 = self.qp.a0.as_static().unwrap().cast_to_scalar::<i32>().unwrap();
+        // a0 and a_scale are per-tensor, or per output channel
+        let per_co = |t: &Tensor, co: usize| if t.len() == 1 { 0 } else { co };
+        let a0 = self.qp.a0.as_static().unwrap().cast_to::<i32>().unwrap();
+        let a0 = |co: usize| a0.as_slice::<i32>().unwrap()[per_co(&a0, co)];
+        let a_scale = self.qp.a_scale.as_static().unwrap().cast_to::<f32>().unwrap();
         let b0 = self.qp.b0.as_static().unwrap().cast_to_scalar::<i32>().unwrap();
         let c0 = self.qp.c0.as_static().unwrap().cast_to_scalar::<i32>().unwrap();
-        let scale = self.qp.c_scale.as_static().unwrap().cast_to_scalar::<f32>().unwrap()
-            / self.qp.a_scale.as_static().unwrap().cast_to_scalar::<f32>().unwrap()
-            / self.qp.b_scale.as_static().unwrap().cast_to_scalar::<f32>().unwrap();
+        let scale = |co: usize| {
+            self.qp.c_scale.as_static().unwrap().cast_to_scalar::<f32>().unwrap()
+                / a_scale.as_slice::<f32>().unwrap()[per_co(&a_scale, co)]
+                / self.qp.b_scale.as_static().unwrap().cast_to_scalar::<f32>().unwrap()
+        };
         let shape_out: TVec<usize> = izip!(self.shape_in.hw_dims(), self.geo_ker())
             .map(|(i, k)| (*i + 1).saturating_sub(*k))
             .collect();
@@ -104,7 +110,7 @@ impl QConvProblem {
                                     }
                                 }
                                 let k = self.kernel[&*kernel_coords] as i32;
-                                temp[&*output_coords] += (k - a0) * (i - b0);
+                                temp[&*output_coords] += (k - a0(co + g * co_per_g)) * (i - b0);
                             }
                         }
                     }
@@ -116,8 +122,9 @@ impl QConvProblem {
             shape[shape_out.c_axis()] = bias.len();
             temp += &bias.clone().into_shape(shape).unwrap();
         }
-        temp.mapv(|i| {
-            (round_ties_to_even(i as f32 / scale as f32) as i32 + c0)
+        ArrayD::from_shape_fn(temp.shape(), |coords| {
+            let scale = scale(coords[shape_out.c_axis()]);
+            (round_ties_to_even(temp[&coords] as f32 / scale as f32) as i32 + c0)
                 .max(std::i8::MIN as i32)
                 .min(std::i8::MAX as i32) as i8
         })
@@ -529,3 +536,44 @@ fn bias_3() {
     .check()
     .unwrap();
 }
+
+#[test]
+fn per_channel_0() {
+    let mut qp = MatMulQParams::noop_static(i8::datum_type());
+    qp.a0 = tensor1(&[1i32, -2, 0]).into();
+    qp.a_scale = tensor1(&[0.5f32, 2.0, 1.0]).into();
+    qp.b0 = tensor0(1i32).into();
+    QConvProblem {
+        shape_in: NCHW.from_n_c_hw(1, 2, &[3]).unwrap(),
+        co: 3,
+        kernel_format: OIHW,
+        group: 1,
+        data: arr3(&[[[1, -3, 2], [0, 4, -1]]]).into_dyn(),
+        kernel: arr3(&[[[2, -1], [0, 3]], [[-4, 1], [2, 0]], [[1, 1], [-1, 5]]]).into_dyn(),
+        bias: None,
+        qp,
+        optim: true,
+    }
+    .check()
+    .unwrap();
+}
+
+#[test]
+fn per_channel_group() {
+    let mut qp = MatMulQParams::noop_static(i8::datum_type());
+    qp.a0 = tensor1(&[0i32, 1, -1, 2]).into();
+    qp.a_scale = tensor1(&[1f32, 0.5, 0.25, 4.0]).into();
+    QConvProblem {
+        shape_in: HWC.from_n_c_hw(1, 2, &[2]).unwrap(),
+        co: 4,
+        kernel_format: OIHW,
+        group: 2,
+        data: arr2(&[[3, -1], [2, 5]]).into_dyn(),
+        kernel: arr3(&[[[1, 2]], [[-3, 0]], [[2, -2]], [[4, 1]]]).into_dyn(),
+        bias: None,
+        qp,
+        optim: false,
+    }
+    .check()
+    .unwrap();
+}
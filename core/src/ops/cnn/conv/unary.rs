@@ -6,8 +6,10 @@ use crate::ops;
 use crate::ops::matmul::mir_quant::wire_offset_u8_as_i8;
 use crate::ops::matmul::mir_quant::QParamKind;
 
+use super::autotune::{self, autotune_enabled, ConvAlgorithm};
 use super::depth_wise::DepthWise;
 use super::im2col::Im2Col;
+use super::winograd::{winograd_tile, WinogradConv};
 use crate::ops::cnn::conv::KernelFormat;
 use crate::ops::cnn::pools::{ConcretePoolGeometry, PoolGeometry, PoolSpec};
 use crate::ops::matmul::lir_unary::{
@@ -44,7 +46,7 @@ impl ConvUnary {
         }
     }
 
-    fn output_channels(&self) -> usize {
+    pub fn output_channels(&self) -> usize {
         let kshape = self.kernel.shape();
         match self.kernel_fmt {
             KernelFormat::OIHW => kshape[0],
@@ -52,6 +54,23 @@ impl ConvUnary {
         }
     }
 
+    fn is_half(&self) -> bool {
+        self.q_params.is_none() && ops::matmul::is_half(self.kernel.datum_type())
+    }
+
+    /// There are no half precision kernels: half precision convolutions are
+    /// computed in single precision, between casts.
+    fn half_as_f32(&self) -> TractResult<ConvUnary> {
+        let to_f32 = |t: &Arc<Tensor>| -> TractResult<Arc<Tensor>> {
+            Ok(t.cast_to::<f32>()?.into_owned().into_arc_tensor())
+        };
+        Ok(ConvUnary {
+            kernel: to_f32(&self.kernel)?,
+            bias: self.bias.as_ref().map(to_f32).transpose()?,
+            ..self.clone()
+        })
+    }
+
     pub fn kernel_as_group_o_ihw(&self) -> TractResult<Arc<Tensor>> {
         self.kernel_fmt.kernel_as_group_o_ihw(
             &self.kernel,
@@ -203,8 +222,8 @@ impl ConvUnary {
             c_dt,
         )?;
 
-        let a0 = params[0];
-        let a_scale = params[1];
+        let mut a0 = params[0];
+        let mut a_scale = params[1];
         let mut b0 = params[2];
         let b_scale = params[3];
         let c0 = params[4];
@@ -215,6 +234,29 @@ impl ConvUnary {
         let (_, m, k, n, mmm) = self.compute_geo(&b_fact)?;
         let output_shape = self.pool_spec.output_shape(&b_fact.shape)?;
 
+        let (mmm_output_shape, c_axis, h_axis) = self.mmm_output_shape(&output_shape)?;
+        let has_n = self.pool_spec.data_format.has_n() as usize;
+        let has_group = (self.group > 1) as usize;
+        let (m_axis, n_axis) = if self.pool_spec.data_format.c_is_last() {
+            (1 + has_group + has_n, has_n)
+        } else {
+            (has_group + has_n, 1 + has_n + has_group)
+        };
+
+        // per output channel kernel params, split by group like the output channels
+        for (param, param_name) in [(&mut a0, "a0"), (&mut a_scale, "a_scale")] {
+            let name = format!("{}.{}", name, param_name);
+            let shape = model.outlet_fact(*param)?.shape.to_tvec();
+            if self.group > 1 && shape.len() == 1 && shape[0] != 1.to_dim() {
+                *param = model.wire_node(
+                    format!("{}.split_group", name),
+                    AxisOp::Reshape(0, shape, tvec!(self.group.to_dim(), m.to_dim())),
+                    &[*param],
+                )?[0];
+            }
+            *param = qmm::wire_per_axis(model, &name, *param, m_axis, mmm_output_shape.len())?;
+        }
+
         let abc_scale = qmm::combine_scales(model, name, a_scale, b_scale, c_scale)?;
 
         let im2col = model.wire_node(
@@ -252,7 +294,6 @@ impl ConvUnary {
         }
 
         let b_dt = model.outlet_fact(b)?.datum_type;
-        let (mmm_output_shape, c_axis, h_axis) = self.mmm_output_shape(&output_shape)?;
         let mut geometry = MatMulGeometry::from(SymbolicMatMulGeometry {
             b_datum_type: b_dt,
             m: m.to_dim(),
@@ -276,13 +317,6 @@ impl ConvUnary {
             c_axis,
             h_axis,
         )?;
-        let has_n = self.pool_spec.data_format.has_n() as usize;
-        let has_group = (self.group > 1) as usize;
-        let (m_axis, n_axis) = if self.pool_spec.data_format.c_is_last() {
-            (1 + has_group + has_n, has_n)
-        } else {
-            (has_group + has_n, 1 + has_n + has_group)
-        };
         let wire = qmm::compensate_zero_points(
             model,
             name,
@@ -578,6 +612,107 @@ impl ConvUnary {
         Ok(Box::new(op))
     }
 
+    /// Winograd transforms to use for this convolution, if it qualifies.
+    fn winograd_for(
+        &self,
+        model: &TypedModel,
+        input_fact: &TypedFact,
+    ) -> TractResult<Option<Box<dyn tract_linalg::winograd::Winograd>>> {
+        if self.q_params.is_some()
+            || input_fact.datum_type != f32::datum_type()
+            || self.kernel.datum_type() != f32::datum_type()
+            || self.group != 1
+            || &*self.pool_spec.kernel_shape != &[3, 3]
+            || self.pool_spec.strides().iter().any(|&s| s != 1)
+            || self.pool_spec.dilations().iter().any(|&d| d != 1)
+            || input_fact.shape.as_concrete().is_none()
+            || self.input_channels() * self.output_channels() < 64
+        {
+            return Ok(None);
+        }
+        Ok(winograd_tile(model)?.and_then(|tile| (tract_linalg::ops().winograd_3x3_f32)(tile)))
+    }
+
+    /// Lowerings applicable to this convolution, preferred one first.
+    pub fn algorithms(
+        &self,
+        model: &TypedModel,
+        input_fact: &TypedFact,
+    ) -> TractResult<TVec<ConvAlgorithm>> {
+        let mut algorithms = tvec!();
+        if self.winograd_for(model, input_fact)?.is_some() {
+            algorithms.push(ConvAlgorithm::Winograd);
+        }
+        if let Some(shape) = input_fact.shape.as_concrete() {
+            if should_use_lazy(
+                &self.pool_spec.data_format.shape(shape.into())?,
+                &self.pool_spec,
+                self.group,
+            ) {
+                algorithms.push(ConvAlgorithm::Direct);
+            }
+            if self.group != 1
+                && self.group == self.output_channels()
+                && self.group == self.input_channels()
+            {
+                algorithms.push(ConvAlgorithm::DepthWise);
+            }
+        }
+        algorithms.push(ConvAlgorithm::Im2col);
+        Ok(algorithms)
+    }
+
+    /// Patch replacing the convolution node by its `algorithm` lowering.
+    pub fn lower(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        algorithm: ConvAlgorithm,
+    ) -> TractResult<TypedModelPatch> {
+        let input_fact = model.outlet_fact(node.inputs[0])?;
+        unsafe {
+            match algorithm {
+                ConvAlgorithm::Winograd => {
+                    let winograd = self
+                        .winograd_for(model, input_fact)?
+                        .context("Winograd lowering is not applicable")?;
+                    let op = WinogradConv::new(
+                        &self.pool_spec,
+                        input_fact.shape.as_concrete().unwrap(),
+                        &*self.kernel_as_group_o_ihw()?,
+                        self.bias.clone(),
+                        winograd,
+                    )?;
+                    TypedModelPatch::single_unary_op(model, node, op)
+                }
+                ConvAlgorithm::Direct => {
+                    let mut patch = TypedModelPatch::new("wire_as_lazy_im2col");
+                    let mut wire = patch.tap_model(model, node.inputs[0])?;
+                    wire = self.wire_as_lazy_im2col(&mut patch, &*node.name, wire)?;
+                    patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
+                    patch.obliterate(node.id)?;
+                    Ok(patch)
+                }
+                ConvAlgorithm::DepthWise => {
+                    let dt = input_fact.datum_type;
+                    let op = dispatch_floatlike!(Self::to_depth_wise(dt)(self, input_fact))
+                        .context("in to_depth_wise")?;
+                    TypedModelPatch::single_unary_op(model, node, op)
+                }
+                ConvAlgorithm::Im2col => {
+                    let mut patch = TypedModelPatch::default();
+                    let wire = patch.tap_model(model, node.inputs[0])?;
+                    let wire = self
+                        .wire_as_im2col_pair(&mut patch, &*node.name, wire)
+                        .context("in wire_as_im2col_pair")?;
+                    patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
+                    patch.obliterate(node.id)?;
+                    Ok(patch)
+                }
+            }
+        }
+    }
+
     fn declutter_stride_slice_to_downsample(
         &self,
         model: &TypedModel,
@@ -725,6 +860,15 @@ impl EvalOp for ConvUnary {
     }
 
     fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        if self.is_half() {
+            let inputs = inputs
+                .iter()
+                .map(|t| Ok(t.cast_to::<f32>()?.into_owned().into_arc_tensor()))
+                .collect::<TractResult<_>>()?;
+            let output = self.half_as_f32()?.eval(inputs)?.remove(0);
+            let output = output.cast_to_dt(self.kernel.datum_type())?.into_owned();
+            return Ok(tvec!(output.into_arc_tensor()));
+        }
         let mut model = TypedModel::default();
 
         let mut wires: TVec<OutletId> = inputs
@@ -986,13 +1130,27 @@ impl TypedOp for ConvUnary {
             return Ok(Some(patch.with_context("kernel-u8-to-i8")));
         }
 
+        if self.is_half() {
+            let mut patch = TypedModelPatch::default();
+            let mut wire = patch.tap_model(model, node.inputs[0])?;
+            wire = patch.wire_node(
+                format!("{}.cast_input", node.name),
+                ops::cast::cast(f32::datum_type()),
+                &[wire],
+            )?[0];
+            wire = patch.wire_node(format!("{}.f32", node.name), self.half_as_f32()?, &[wire])?[0];
+            let cast = ops::cast::cast(self.kernel.datum_type());
+            wire = patch.wire_node(&node.name, cast, &[wire])?[0];
+            patch.shunt_outside(model, node.id.into(), wire)?;
+            return Ok(Some(patch.with_context("half-as-f32")));
+        }
+
         let full_input_shape = model.outlet_fact(node.inputs[0])?.shape.to_tvec();
         let input_fact = model.outlet_fact(node.inputs[0])?;
         let input_shape = self.pool_spec.data_format.shape(&full_input_shape)?;
         let spatial_rank = input_shape.hw_rank();
         let kernel_spatial_shape = &self.kernel.shape()[self.kernel_fmt.h_axis()..][..spatial_rank];
         unsafe {
-            let dt = input_fact.datum_type;
             if self.q_params.is_some() {
                 let mut patch = TypedModelPatch::default();
                 let inputs = node
@@ -1076,41 +1234,14 @@ impl TypedOp for ConvUnary {
                 patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
                 patch.obliterate(node.id)?;
                 return Ok(Some(patch));
-            } else if input_fact
-                .shape
-                .as_concrete()
-                .map(|s| {
-                    should_use_lazy(
-                        &self.pool_spec.data_format.shape(s.into()).unwrap(),
-                        &self.pool_spec,
-                        self.group,
-                    )
-                })
-                .unwrap_or(false)
-            {
-                let mut patch = TypedModelPatch::new("wire_as_lazy_im2col");
-                let mut wire = patch.tap_model(model, node.inputs[0])?;
-                wire = self.wire_as_lazy_im2col(&mut patch, &*node.name, wire)?;
-                patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
-                patch.obliterate(node.id)?;
-                return Ok(Some(patch));
-            } else if self.group != 1
-                && self.group == self.output_channels()
-                && self.group == self.input_channels()
-                && input_fact.shape.as_concrete().is_some()
-            {
-                let op = dispatch_floatlike!(Self::to_depth_wise(dt)(self, &input_fact))
-                    .context("in to_depth_wise")?;
-                return Ok(Some(TypedModelPatch::single_unary_op(model, node, op)?));
             } else {
-                let mut patch = TypedModelPatch::default();
-                let wire = patch.tap_model(model, node.inputs[0])?;
-                let wire = self
-                    .wire_as_im2col_pair(&mut patch, &*node.name, wire)
-                    .context("in wire_as_im2col_pair")?;
-                patch.shunt_outside(model, OutletId::new(node.id, 0), wire)?;
-                patch.obliterate(node.id)?;
-                return Ok(Some(patch));
+                let candidates = self.algorithms(model, input_fact)?;
+                let algorithm = if autotune_enabled(model)? && candidates.len() > 1 {
+                    autotune::fastest(self, model, input_fact, &candidates)?
+                } else {
+                    candidates[0]
+                };
+                return Ok(Some(self.lower(model, node, algorithm)?));
             }
         }
     }
@@ -0,0 +1,346 @@
+use ndarray::*;
+use num_integer::Integer;
+
+use crate::internal::*;
+use crate::ops::cnn::pools::PoolSpec;
+use crate::ops::nn::DataShape;
+
+use tract_linalg::frame::Packer;
+use tract_linalg::mmm::{FusedSpec, MatMatMul};
+use tract_linalg::winograd::Winograd;
+
+/// Model property controlling the Winograd lowering of 3x3 convolutions.
+///
+/// An integer scalar: 2 or 4 pick the output tile size, 0 disables Winograd.
+/// Winograd changes the numerics of the convolutions, so it is only used when
+/// the property is set. F(4x4, 3x3) is faster than F(2x2, 3x3) but
+/// noticeably less accurate.
+pub const WINOGRAD_PROPERTY: &str = "conv.winograd";
+
+/// Output tile size to use for a model, or None if Winograd is not enabled.
+pub fn winograd_tile(model: &TypedModel) -> TractResult<Option<usize>> {
+    if let Some(prop) = model.properties.get(WINOGRAD_PROPERTY) {
+        let tile = prop.cast_to_scalar::<i64>()?;
+        Ok(Some(tile as usize).filter(|&t| t > 0))
+    } else {
+        Ok(None)
+    }
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct WinogradConv {
+    pub input_shape: DataShape,
+    pub output_shape: DataShape,
+    pub pad_before: (usize, usize),
+    /// transformed kernel, one O x I matrix per transformed tile element,
+    /// packed for `mmm`
+    pub packed_kernels: Vec<Arc<Tensor>>,
+    pub bias: Option<Arc<Tensor>>,
+    pub winograd: Box<dyn Winograd>,
+    pub mmm: Box<dyn MatMatMul>,
+}
+
+impl_dyn_hash!(WinogradConv);
+
+impl WinogradConv {
+    /// Builds the op from a kernel in (1, O, I*3*3) form.
+    pub fn new(
+        pool_spec: &PoolSpec,
+        input_full_shape: &[usize],
+        kernel_o_ihw: &Tensor,
+        bias: Option<Arc<Tensor>>,
+        winograd: Box<dyn Winograd>,
+    ) -> TractResult<WinogradConv> {
+        let input_shape = pool_spec.data_format.shape(input_full_shape.into())?;
+        let output_shape = pool_spec.output_shape(input_full_shape)?;
+        let padding = pool_spec.computed_padding(input_shape.hw_dims());
+        let r = winograd.kernel_size();
+        let alpha = winograd.input_tile();
+        let kernel = kernel_o_ihw.to_array_view::<f32>()?.into_shape((
+            *output_shape.c(),
+            *input_shape.c(),
+            r * r,
+        ))?;
+        let mut transformed =
+            Array3::<f32>::zeros((alpha * alpha, *output_shape.c(), *input_shape.c()));
+        let mut buffer = vec![0f32; alpha * alpha];
+        for o in 0..*output_shape.c() {
+            for i in 0..*input_shape.c() {
+                let ker = kernel.slice(s![o, i, ..]);
+                winograd.transform_kernel(ker.as_slice().unwrap(), &mut buffer);
+                for (xi, v) in buffer.iter().enumerate() {
+                    transformed[(xi, o, i)] = *v;
+                }
+            }
+        }
+        let (co, ci) = (*output_shape.c(), *input_shape.c());
+        let (tiles_h, tiles_w) = tiles(&output_shape, winograd.output_tile());
+        let tiles = tiles_h * tiles_w;
+        let mmm = tract_linalg::ops()
+            .mmm(
+                f32::datum_type(),
+                f32::datum_type(),
+                f32::datum_type(),
+                Some(co),
+                Some(ci),
+                Some(tiles),
+            )
+            .context("No f32 matmul")?;
+        let packer = mmm.a_pack();
+        let packed_kernels = transformed
+            .outer_iter()
+            .map(|kernel| Self::pack(&packer, ci, co, kernel.to_owned().into_tensor()))
+            .collect::<TractResult<Vec<_>>>()?;
+        Ok(WinogradConv {
+            input_shape,
+            output_shape,
+            pad_before: (padding[0].pad_before, padding[1].pad_before),
+            packed_kernels,
+            bias,
+            winograd,
+            mmm,
+        })
+    }
+
+    fn pack(packer: &Packer, k: usize, m: usize, kernel: Tensor) -> TractResult<Arc<Tensor>> {
+        unsafe {
+            let mut packed =
+                Tensor::uninitialized_aligned::<f32>(&[packer.len(k, m)], packer.alignment())?;
+            packer.pack(&mut packed.view_mut(), kernel.view(), 1, 0);
+            Ok(packed.into_arc_tensor())
+        }
+    }
+
+    #[inline]
+    fn tiles(&self) -> (usize, usize) {
+        tiles(&self.output_shape, self.winograd.output_tile())
+    }
+}
+
+/// Number of output tiles along the height and width.
+fn tiles(output_shape: &DataShape, m: usize) -> (usize, usize) {
+    let hw = output_shape.hw_dims();
+    (Integer::div_ceil(&hw[0], &m), Integer::div_ceil(&hw[1], &m))
+}
+
+impl Op for WinogradConv {
+    fn name(&self) -> Cow<str> {
+        "WinogradConv".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![
+            format!("{} on {:?}", self.winograd.name(), self.input_shape),
+            format!("Tiles: {:?}", self.tiles()),
+        ])
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_core_lir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for WinogradConv {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.as_slice::<f32>()?;
+        let m = self.winograd.output_tile();
+        let alpha = self.winograd.input_tile();
+        let (tiles_h, tiles_w) = self.tiles();
+        let tiles = tiles_h * tiles_w;
+        let (ih, iw) = (self.input_shape.hw_dims()[0], self.input_shape.hw_dims()[1]);
+        let (oh, ow) = (self.output_shape.hw_dims()[0], self.output_shape.hw_dims()[1]);
+        let (is_h, is_w) = (self.input_shape.hw_strides()[0], self.input_shape.hw_strides()[1]);
+        let (os_h, os_w) = (self.output_shape.hw_strides()[0], self.output_shape.hw_strides()[1]);
+        let (is_c, os_c) = (*self.input_shape.c_stride(), *self.output_shape.c_stride());
+        let (ci, co) = (*self.input_shape.c(), *self.output_shape.c());
+        let bias = self.bias.as_ref().map(|b| b.as_slice::<f32>()).transpose()?;
+
+        let mut output = unsafe { Tensor::uninitialized::<f32>(&self.output_shape.shape)? };
+        let output_slice = output.as_slice_mut::<f32>()?;
+        let mut v = Tensor::zero::<f32>(&[alpha * alpha, ci, tiles])?;
+        let mm = Tensor::zero::<f32>(&[alpha * alpha, co, tiles])?;
+        let mut scratch = unsafe { self.mmm.allocate_scratch_space() };
+        let mut tile = vec![0f32; alpha * alpha];
+        let mut transformed = vec![0f32; alpha * alpha];
+        for n in 0..*self.input_shape.n().unwrap_or(&1) {
+            let input = &input[n * self.input_shape.n_stride().unwrap_or(&0)..];
+            let output = &mut output_slice[n * self.output_shape.n_stride().unwrap_or(&0)..];
+            let mut v_view = v.to_array_view_mut::<f32>()?.into_dimensionality::<Ix3>()?;
+            for c in 0..ci {
+                for ty in 0..tiles_h {
+                    for tx in 0..tiles_w {
+                        for y in 0..alpha {
+                            let iy = (ty * m + y) as isize - self.pad_before.0 as isize;
+                            for x in 0..alpha {
+                                let ix = (tx * m + x) as isize - self.pad_before.1 as isize;
+                                tile[y * alpha + x] = if iy >= 0
+                                    && (iy as usize) < ih
+                                    && ix >= 0
+                                    && (ix as usize) < iw
+                                {
+                                    input[c * is_c + iy as usize * is_h + ix as usize * is_w]
+                                } else {
+                                    0.0
+                                };
+                            }
+                        }
+                        self.winograd.transform_input(&tile, &mut transformed);
+                        for (xi, t) in transformed.iter().enumerate() {
+                            v_view[(xi, c, ty * tiles_w + tx)] = *t;
+                        }
+                    }
+                }
+            }
+            for xi in 0..alpha * alpha {
+                unsafe {
+                    let a = self.mmm.a_packed(4, ci).wrap(&self.packed_kernels[xi].view());
+                    let b = self.mmm.b_late_packing().wrap(&TensorView::at_prefix(&v, &[xi])?)?;
+                    let c = self.mmm.c_view(0, 1).wrap(&TensorView::at_prefix(&mm, &[xi])?);
+                    self.mmm.run_with_scratch_space(
+                        co,
+                        tiles,
+                        &mut *scratch,
+                        &[FusedSpec::AddMatMul { a, b, k: ci }, FusedSpec::Store(c)],
+                    )?;
+                }
+            }
+            let mm = mm.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+            for o in 0..co {
+                let bias = bias.map(|b| b[o]).unwrap_or(0.0);
+                for ty in 0..tiles_h {
+                    for tx in 0..tiles_w {
+                        for xi in 0..alpha * alpha {
+                            tile[xi] = mm[(xi, o, ty * tiles_w + tx)];
+                        }
+                        self.winograd.transform_output(&tile, &mut transformed[..m * m]);
+                        for y in (0..m).filter(|y| ty * m + y < oh) {
+                            for x in (0..m).filter(|x| tx * m + x < ow) {
+                                output[o * os_c + (ty * m + y) * os_h + (tx * m + x) * os_w] =
+                                    transformed[y * m + x] + bias;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for WinogradConv {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        anyhow::ensure!(
+            inputs[0].datum_type == f32::datum_type(),
+            "WinogradConv only supports f32, got {:?}",
+            inputs[0].datum_type
+        );
+        Ok(tvec!(f32::fact(&*self.output_shape.shape)))
+    }
+
+    fn cost(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let (tiles_h, tiles_w) = self.tiles();
+        Ok(tvec!((
+            Cost::FMA(f32::datum_type()),
+            (self.input_shape.n().unwrap_or(&1)
+                * tiles_h
+                * tiles_w
+                * self.winograd.input_tile().pow(2)
+                * self.input_shape.c()
+                * self.output_shape.c())
+            .to_dim()
+        )))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::cnn::conv::{ConvUnary, KernelFormat};
+    use crate::ops::cnn::PaddingSpec;
+    use crate::ops::nn::DataFormat;
+
+    fn conv(
+        fmt: DataFormat,
+        padding: PaddingSpec,
+        input: &[usize],
+        ci: usize,
+        co: usize,
+    ) -> TractResult<(TypedModel, Tensor)> {
+        let kernel = Tensor::from_shape(
+            &[co, ci, 3, 3],
+            &(0..co * ci * 9).map(|i| ((i * 7) % 11) as f32 / 11.0 - 0.5).collect::<Vec<_>>(),
+        )?;
+        let bias = Tensor::from_shape(&[co], &(0..co).map(|i| i as f32).collect::<Vec<_>>())?;
+        let op = ConvUnary::new(
+            PoolSpec::new(fmt, tvec!(3, 3), padding, None, None, Some(co)),
+            KernelFormat::OIHW,
+            kernel.into_arc_tensor(),
+            1,
+            Some(bias.into_arc_tensor()),
+            None,
+        );
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(input))?;
+        let conv = model.wire_node("conv", op, &[source])?;
+        model.set_output_outlets(&conv)?;
+        let len = input.iter().product::<usize>();
+        let data = Tensor::from_shape(
+            input,
+            &(0..len).map(|i| ((i * 5) % 13) as f32 / 13.0).collect::<Vec<_>>(),
+        )?;
+        Ok((model, data))
+    }
+
+    fn check(model: TypedModel, data: Tensor, expect_winograd: bool) -> TractResult<()> {
+        let expected = model.clone().into_runnable()?.run(tvec!(data.clone()))?;
+        let optimized = model.into_optimized()?;
+        assert_eq!(optimized.nodes().iter().any(|n| n.op_is::<WinogradConv>()), expect_winograd);
+        let found = optimized.into_runnable()?.run(tvec!(data))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn nchw_valid() -> TractResult<()> {
+        let (mut model, data) = conv(DataFormat::NCHW, PaddingSpec::Valid, &[1, 8, 7, 6], 8, 8)?;
+        model.properties.insert(WINOGRAD_PROPERTY.to_string(), rctensor0(2i64));
+        check(model, data, true)
+    }
+
+    #[test]
+    fn nhwc_same() -> TractResult<()> {
+        let (mut model, data) =
+            conv(DataFormat::NHWC, PaddingSpec::SameUpper, &[2, 5, 9, 8], 8, 16)?;
+        model.properties.insert(WINOGRAD_PROPERTY.to_string(), rctensor0(2i64));
+        check(model, data, true)
+    }
+
+    #[test]
+    fn opt_in() -> TractResult<()> {
+        let (model, data) = conv(DataFormat::NCHW, PaddingSpec::Valid, &[1, 8, 7, 6], 8, 8)?;
+        check(model, data, false)
+    }
+
+    #[test]
+    fn chw_same_f4x4() -> TractResult<()> {
+        let (mut model, data) = conv(DataFormat::CHW, PaddingSpec::SameUpper, &[8, 11, 10], 8, 8)?;
+        model.properties.insert(WINOGRAD_PROPERTY.to_string(), rctensor0(4i64));
+        check(model, data, true)
+    }
+
+    #[test]
+    fn opt_out() -> TractResult<()> {
+        let (mut model, data) = conv(DataFormat::NCHW, PaddingSpec::Valid, &[1, 8, 7, 6], 8, 8)?;
+        model.properties.insert(WINOGRAD_PROPERTY.to_string(), rctensor0(0i64));
+        check(model, data, false)
+    }
+}
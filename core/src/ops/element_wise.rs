@@ -50,6 +50,10 @@ pub trait ElementWiseMiniOp:
     fn info(&self) -> TractResult<Vec<String>> {
         Ok(vec![])
     }
+    #[allow(unused_variables)]
+    fn same_as(&self, other: &dyn ElementWiseMiniOp) -> bool {
+        false
+    }
 }
 
 impl Hash for Box<dyn ElementWiseMiniOp> {
@@ -78,6 +82,14 @@ impl Op for ElementWiseOp {
         self.0.validation()
     }
 
+    fn same_as(&self, other: &dyn Op) -> bool {
+        if let Some(other) = other.downcast_ref::<ElementWiseOp>() {
+            self.0.same_as(&*other.0)
+        } else {
+            false
+        }
+    }
+
     op_core_lir_mir!();
     op_as_typed_op!();
 }
@@ -103,6 +115,10 @@ impl EvalOp for ElementWiseOp {
             Ok(inputs)
         }
     }
+
+    fn eval_in_place_input(&self) -> Option<usize> {
+        Some(0)
+    }
 }
 
 impl TypedOp for ElementWiseOp {
@@ -183,6 +199,12 @@ macro_rules! element_wise {
             fn name(&self) -> String {
                 format!("{}{}", self.prefix(), stringify!($Op))
             }
+            fn same_as(&self, other: &dyn $crate::ops::element_wise::ElementWiseMiniOp) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .map(|_other| true $($( && &self.$var == &_other.$var)*)?)
+                    .unwrap_or(false)
+            }
             fn eval_in_place(&self, t: &mut Tensor) -> TractResult<()> {
                 $(
                     $(if t.datum_type() == $typ::datum_type() {
@@ -272,6 +294,12 @@ macro_rules! element_wise_oop {
             fn name(&self) -> String {
                 format!("{}{}", self.prefix(), stringify!($Op))
             }
+            fn same_as(&self, other: &dyn $crate::ops::element_wise::ElementWiseMiniOp) -> bool {
+                other
+                    .downcast_ref::<Self>()
+                    .map(|_other| true $($( && &self.$var == &_other.$var)*)?)
+                    .unwrap_or(false)
+            }
             fn output_type(&self, input_type: DatumType) -> Option<DatumType> {
                 $(
                     $(if input_type == $typ::datum_type() {
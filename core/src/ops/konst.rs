@@ -1,6 +1,6 @@
 use crate::internal::*;
 
-#[derive(Debug, Clone, new, Hash)]
+#[derive(Debug, Clone, new, Hash, PartialEq)]
 pub struct Const(pub Arc<Tensor>);
 
 impl_dyn_hash!(Const);
@@ -12,6 +12,7 @@ impl Op for Const {
 
     op_core_mir!();
     op_as_typed_op!();
+    impl_op_same_as!();
 }
 
 impl EvalOp for Const {
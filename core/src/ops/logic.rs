@@ -63,22 +63,22 @@ fn codegen_compare_to_zero(
     Ok(None)
 }
 
-element_wise_oop!(less_than_zero, LessThanZero, [f16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
+element_wise_oop!(less_than_zero, LessThanZero, [f16, bf16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
     xs.iter().zip(ys.iter_mut()).for_each(|(x,y)| *y = *x < num_traits::Zero::zero());
     Ok(())
 });
 
-element_wise_oop!(less_equal_than_zero, LessEqualThanZero, [f16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
+element_wise_oop!(less_equal_than_zero, LessEqualThanZero, [f16, bf16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
     xs.iter().zip(ys.iter_mut()).for_each(|(x,y)| *y = *x <= num_traits::Zero::zero());
     Ok(())
 });
 
-element_wise_oop!(greater_than_zero, GreaterThanZero, [f16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
+element_wise_oop!(greater_than_zero, GreaterThanZero, [f16, bf16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
     xs.iter().zip(ys.iter_mut()).for_each(|(x,y)| *y = *x > num_traits::Zero::zero());
     Ok(())
 });
 
-element_wise_oop!(greater_equal_than_zero, GreaterEqualThanZero, [f16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
+element_wise_oop!(greater_equal_than_zero, GreaterEqualThanZero, [f16, bf16, f32, f64, i8, i16, i32, i64] => bool |_op, xs, ys| {
     xs.iter().zip(ys.iter_mut()).for_each(|(x,y)| *y = *x >= num_traits::Zero::zero());
     Ok(())
 });
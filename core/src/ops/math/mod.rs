@@ -9,13 +9,22 @@ pub use tract_data::prelude::round_ties_to_even;
 use tract_linalg::{ScaleShiftAndRound, Scaler};
 use tract_num_traits::AsPrimitive;
 
+pub use tract_linalg::{Accuracy, Transcendental};
+
+/// Runs `function` over `xs` at the accuracy selected for the current thread
+/// (see `tract_linalg::accuracy::accuracy_scope`), or its default one.
+pub fn transcendental_f32(function: Transcendental, xs: &mut [f32]) -> TractResult<()> {
+    let accuracy = tract_linalg::accuracy::accuracy_for(function);
+    tract_linalg::ops().transcendental_f32(function, accuracy).run(xs)
+}
+
 bin_to_super_type!(add, Add,
                    declutter_unary: declutter_unary_add,
                    flip:commute,
                    linalg: Add,
                    validation: Validation::Rounding,
                    q: [i8, u8, i32, i32] => add_quant;
-                   [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64, TDim] => |c, a, b| *c = a.clone() + b);
+                   [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, bf16, f64, TDim] => |c, a, b| *c = a.clone() + b);
 
 fn add_quant<T>(c: &mut T, a: &T, b: &T, zp: i32, _: f32)
 where
@@ -42,7 +51,7 @@ fn declutter_unary_add(
 bin_to_super_type!(sub, Sub,
     declutter_unary: declutter_unary_sub, flip:flip_sub, linalg:Sub,
     q: [i8, u8, i32, i32] => sub_quant;
-    [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64, TDim] => |c, a, b| *c = a.clone() - b);
+    [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, bf16, f64, TDim] => |c, a, b| *c = a.clone() - b);
 
 fn sub_quant<T>(c: &mut T, a: &T, b: &T, zp: i32, _: f32)
 where
@@ -79,10 +88,9 @@ out_of_place: |c:&mut Tensor, a:&Tensor, b: &Tensor| -> TractResult<bool> {
     if c.datum_type() == TDim::datum_type() &&
         a.datum_type() == TDim::datum_type() && b.datum_type() == TDim::datum_type() {
             let a = a.to_array_view::<TDim>()?;
-            let b = b.cast_to::<i32>()?;
-            let b = b.to_array_view::<i32>()?;
+            let b = b.to_array_view::<TDim>()?;
             let c = c.to_array_view_mut::<TDim>()?;
-            crate::ndarray::Zip::from(c).and_broadcast(a).and_broadcast(b).for_each(|c,a,b| *c = a.clone() * *b);
+            crate::ndarray::Zip::from(c).and_broadcast(a).and_broadcast(b).for_each(|c,a,b| *c = a.clone() * b);
             Ok(true)
         }
     else {
@@ -113,7 +121,7 @@ out_of_place: |c:&mut Tensor, a:&Tensor, b: &Tensor| -> TractResult<bool> {
         }
     }
 },
-[f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64, TDim] => |c, a, b| *c = a.clone() * b
+[f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, bf16, f64, TDim] => |c, a, b| *c = a.clone() * b
 );
 
 bin_to_super_type!(div, Div,
@@ -141,7 +149,8 @@ out_of_place: |c:&mut Tensor, a:&Tensor, b: &Tensor| -> TractResult<bool> {
             Ok(false)
         }
 },
-[f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64] => |c, a, b| *c = a.clone() / b
+out_of_place_only: |dt| dt == TDim::datum_type(),
+[f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, bf16, f64] => |c, a, b| *c = a.clone() / b
 );
 
 bin_to_super_type!(rem, Rem,
@@ -175,16 +184,63 @@ bin_to_super_type!(rem, Rem,
                                Ok(false)
                            }
                    },
-                   [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, f64] => |c, a, b| *c = a.clone() % b);
+                   [f32, i8, i16, i32, i64, u8, u16, u32, u64, f16, bf16, f64] => |c, a, b| *c = a.clone() % b);
 
-bin_to_super_type!(min, Min, flip:commute, linalg:Min,
+bin_to_super_type!(min, Min,
+                   declutter_unary: declutter_unary_min,
+                   flip:commute, linalg:Min,
                    q: [i8, u8, i32] => |c, a, b, _, _| *c = if a < b { *a } else { *b };
                    [f32, f64] => |c,a,b| *c = a.min(*b),
-                   [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.min(b));
-bin_to_super_type!(max, Max, flip:commute, linalg:Max,
+                   [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.min(b),
+                   [TDim] => |c, a, b| *c = a.clone().mini(b.clone()));
+bin_to_super_type!(max, Max,
+                   declutter_unary: declutter_unary_max,
+                   flip:commute, linalg:Max,
                    q: [i8, u8, i32] => |c, a, b, _, _| *c = if a < b { *b } else { *a };
                    [f32, f64] => |c,a,b| *c = a.max(*b),
-                   [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.max(b));
+                   [i8, i16, i32, i64, u8, u16, u32, u64] => |c, a, b| *c = *a.max(b),
+                   [TDim] => |c, a, b| *c = a.clone().maxi(b.clone()));
+
+fn declutter_unary_min(
+    _op: &Min,
+    model: &TypedModel,
+    node: &TypedNode,
+    a: &Arc<Tensor>,
+) -> TractResult<Option<TypedModelPatch>> {
+    declutter_unary_clamp(true, model, node, a)
+}
+
+fn declutter_unary_max(
+    _op: &Max,
+    model: &TypedModel,
+    node: &TypedNode,
+    a: &Arc<Tensor>,
+) -> TractResult<Option<TypedModelPatch>> {
+    declutter_unary_clamp(false, model, node, a)
+}
+
+/// A min (or max) with a constant is a no-op when the input values range
+/// is proven under (or over) every value of the constant.
+fn declutter_unary_clamp(
+    min: bool,
+    model: &TypedModel,
+    node: &TypedNode,
+    a: &Arc<Tensor>,
+) -> TractResult<Option<TypedModelPatch>> {
+    let input = model.outlet_fact(node.inputs[0])?;
+    let output = &node.outputs[0].fact;
+    if input.datum_type != output.datum_type || input.shape != output.shape {
+        return Ok(None);
+    }
+    if let (Some(range), Some(bounds)) = (input.value_range(), ValueRange::of_tensor(a)) {
+        let noop =
+            if min { range.below(&(bounds.start + 1)) } else { range.at_least(&(bounds.end - 1)) };
+        if noop {
+            return Ok(Some(TypedModelPatch::shunt_one_op(model, node)?.with_context("in range")));
+        }
+    }
+    Ok(None)
+}
 
 bin_to_super_type!(pow, Pow,
                    flip: flip_pow,
@@ -290,6 +346,7 @@ fn declutter_bin_div(
     if fact.datum_type == f32::datum_type()
         || fact.datum_type == f64::datum_type()
         || fact.datum_type == f16::datum_type()
+        || fact.datum_type == bf16::datum_type()
     {
         let mut patch = TypedModelPatch::default();
         let num = patch.tap_model(model, node.inputs[0])?;
@@ -379,21 +436,25 @@ fn declutter_unary_flipped_pow(
     Ok(None)
 }
 
-element_wise!(abs, Abs, [i8, i16, i32, i64, f16, f32, i32] => |_, xs| {
+element_wise!(abs, Abs, [i8, i16, i32, i64, f16, bf16, f32, i32] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.abs());
     Ok(())
 };
 q: [i8, u8, i32, i32] => f32::abs);
 
-element_wise!(exp, Exp, [f16, f32, f64] => |_, xs| {
-    xs.iter_mut().for_each(|x| *x = x.exp());
-    Ok(())
-};
+element_wise!(exp, Exp,
+ [f32] => |_, xs| { transcendental_f32(Transcendental::Exp, xs) },
+ [f16, bf16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.exp()); Ok(()) };
 q: [i8, u8, i32, i32] => f32::exp;
 validation: Validation::Rounding
 );
 
-element_wise!(ln, Ln, [f16, f32, f64] => |_, xs| {
+element_wise!(erf, Erf,
+ [f32] => |_, xs| { transcendental_f32(Transcendental::Erf, xs) };
+ validation: Validation::Rounding
+);
+
+element_wise!(ln, Ln, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.ln());
     Ok(())
 };
@@ -401,7 +462,7 @@ q: [i8, u8, i32, i32] => f32::ln;
 validation: Validation::Rounding
 );
 
-element_wise!(square, Square, [f16, f32, f64] => |_, xs| {
+element_wise!(square, Square, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.powi(2));
     Ok(())
 };
@@ -409,7 +470,7 @@ q: [i8, u8, i32, i32] => |f : f32| f.powi(2);
 validation: Validation::Rounding
 );
 
-element_wise!(cube, Cube, [f16, f32, f64] => |_, xs| {
+element_wise!(cube, Cube, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.powi(3));
     Ok(())
 };
@@ -417,7 +478,7 @@ q: [i8, u8, i32, i32] => |f : f32| f.powi(3);
 validation: Validation::Rounding
 );
 
-element_wise!(sqrt, Sqrt, [f16, f32, f64] => |_, xs| {
+element_wise!(sqrt, Sqrt, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.sqrt());
     Ok(())
 };
@@ -425,7 +486,7 @@ q: [i8, u8, i32, i32] => f32::sqrt;
 validation: Validation::Rounding
 );
 
-element_wise!(recip, Recip, [f16, f32, f64] => |_, xs| {
+element_wise!(recip, Recip, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.recip());
     Ok(())
 };
@@ -458,7 +519,7 @@ fn declutter_recip(model: &TypedModel, node: &TypedNode) -> TractResult<Option<T
     Ok(None)
 }
 
-element_wise!(rsqrt, Rsqrt, [f16, f32, f64] => |_, xs| {
+element_wise!(rsqrt, Rsqrt, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.sqrt().recip());
     Ok(())
 };
@@ -466,19 +527,19 @@ q: [i8, u8, i32] => |x : f32| x.sqrt().recip();
 validation: Validation::Rounding
 );
 
-element_wise!(ceil, Ceil, [f16, f32, f64] => |_, xs| {
+element_wise!(ceil, Ceil, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.ceil());
     Ok(())
 };
 q: [i8, u8, i32] => f32::recip);
 
-element_wise!(floor, Floor, [f16, f32, f64] => |_, xs| {
+element_wise!(floor, Floor, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.floor());
     Ok(())
 };
 q: [i8, u8, i32] => f32::floor);
 
-element_wise!(round, Round, [f16, f32, f64] => |_, xs| {
+element_wise!(round, Round, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.round());
     Ok(())
 };
@@ -495,84 +556,84 @@ element_wise!(round_half_to_even, RoundHalfToEven,[ f32] => |_, xs| {
 };
 q: [i8, u8, i32] => round_ties_to_even);
 
-element_wise!(cos, Cos, [f16, f32, f64] => |_, xs| {
+element_wise!(cos, Cos, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.cos());
     Ok(())
 };
 q: [i8, u8, i32] => f32::cos);
 
-element_wise!(sin, Sin, [f16, f32, f64] => |_, xs| {
+element_wise!(sin, Sin, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.sin());
     Ok(())
 };
 q: [i8, u8, i32] => f32::sin);
 
-element_wise!(tan, Tan, [f16, f32, f64] => |_, xs| {
+element_wise!(tan, Tan, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.tan());
     Ok(())
 };
 q: [i8, u8, i32] => f32::tan);
 
-element_wise!(acos, Acos, [f16, f32, f64] => |_, xs| {
+element_wise!(acos, Acos, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.acos());
     Ok(())
 };
 q: [i8, u8, i32] => f32::acos);
 
-element_wise!(asin, Asin, [f16, f32, f64] => |_, xs| {
+element_wise!(asin, Asin, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.asin());
     Ok(())
 };
 q: [i8, u8, i32] => f32::asin);
 
-element_wise!(atan, Atan, [f16, f32, f64] => |_, xs| {
+element_wise!(atan, Atan, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.atan());
     Ok(())
 };
 q: [i8, u8, i32] => f32::atan);
 
-element_wise!(cosh, Cosh, [f16, f32, f64] => |_, xs| {
+element_wise!(cosh, Cosh, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.cosh());
     Ok(())
 };
 q: [i8, u8, i32] => f32::cosh);
 
-element_wise!(sinh, Sinh, [f16, f32, f64] => |_, xs| {
+element_wise!(sinh, Sinh, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.sinh());
     Ok(())
 };
 q: [i8, u8, i32] => f32::sinh);
 
 element_wise!(tanh, Tanh,
- [f32] => |_, xs| { (tract_linalg::ops().tanh_f32)().run(xs) },
- [f16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.tanh()); Ok(()) };
+ [f32] => |_, xs| { transcendental_f32(Transcendental::Tanh, xs) },
+ [f16, bf16, f64] => |_, xs| { xs.iter_mut().for_each(|x| *x = x.tanh()); Ok(()) };
  q: [i8, u8, i32] => f32::tanh;
  cost: |dt| {tvec!((Cost::FMA(dt), 11), (Cost::Div(dt), 1))}
 );
 
-element_wise!(acosh, Acosh, [f16, f32, f64] => |_, xs| {
+element_wise!(acosh, Acosh, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.acosh());
     Ok(())
 };
 q: [i8, u8, i32] => f32::acosh);
-element_wise!(asinh, Asinh, [f16, f32, f64] => |_, xs| {
+element_wise!(asinh, Asinh, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.asinh());
     Ok(())
 };
 q: [i8, u8, i32] => f32::asinh);
-element_wise!(atanh, Atanh, [f16, f32, f64] => |_, xs| {
+element_wise!(atanh, Atanh, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = x.atanh());
     Ok(())
 };
 q: [i8, u8, i32] => f32::atanh);
 
-element_wise!(neg, Neg, [i8, i16, i32, i64, f16, f32, f64, TDim] => |_, xs| {
+element_wise!(neg, Neg, [i8, i16, i32, i64, f16, bf16, f32, f64, TDim] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = -x.clone());
     Ok(())
 };
 q: [i8, u8, i32] => |x: f32| -x);
 
-element_wise!(sign, Sign, [f16, f32, f64] => |_, xs| {
+element_wise!(sign, Sign, [f16, bf16, f32, f64] => |_, xs| {
     xs.iter_mut().for_each(|x| *x = if x.is_zero() { *x } else { x.signum() });
     Ok(())
 };
@@ -597,6 +658,23 @@ mod tests {
         assert_eq!(a.dot(&b), arr2(&[[1., 0.], [3., 0.]]));
     }
 
+    #[test]
+    fn sub_in_left_operand() -> TractResult<()> {
+        let a = tensor1(&[4f32, 5.0, 6.0]).into_arc_tensor();
+        let ptr = a.as_ptr::<f32>()?;
+        let b = rctensor1(&[1f32, 2.0, 3.0]);
+        let c = sub::bin_typed().eval(tvec!(a, b.clone()))?;
+        assert_eq!(c[0], rctensor1(&[3f32, 3.0, 3.0]));
+        assert_eq!(c[0].as_ptr::<f32>()?, ptr);
+        let a = tensor1(&[4f32, 5.0, 6.0]).into_arc_tensor();
+        let ptr = a.as_ptr::<f32>()?;
+        let b = rctensor1(&[1f32]);
+        let c = sub::bin_typed().eval(tvec!(a, b.clone()))?;
+        assert_eq!(c[0], rctensor1(&[3f32, 4.0, 5.0]));
+        assert_eq!(c[0].as_ptr::<f32>()?, ptr);
+        Ok(())
+    }
+
     #[test]
     fn mul_as_shift() -> TractResult<()> {
         let mut model = TypedModel::default();
@@ -629,4 +707,69 @@ mod tests {
         assert!(op.mini_op.downcast_ref::<FlippedShiftRight>().is_some());
         Ok(())
     }
+
+    #[test]
+    fn div_tdim() -> TractResult<()> {
+        let s: TDim = Symbol::from('S').into();
+        let a = rctensor1(&[s.clone() * 8]);
+        let b = rctensor1(&[TDim::from(2)]);
+        let c = div::bin_typed().eval(tvec!(a, b))?;
+        assert_eq!(c[0], rctensor1(&[s * 4]));
+        Ok(())
+    }
+
+    #[test]
+    fn mul_tdim_symbols() -> TractResult<()> {
+        let s: TDim = Symbol::from('S').into();
+        let a = rctensor1(&[TDim::from(2), TDim::from(3)]);
+        let b = rctensor1(&[s.clone(), TDim::from(4)]);
+        let mut c = Tensor::zero::<TDim>(&[2])?;
+        mul::bin_typed().0.eval_out_of_place(&mut c, &a, &b)?;
+        assert_eq!(c, tensor1(&[s.clone() * 2, TDim::from(12)]));
+        let c = mul::bin_typed().eval(tvec!(a, b))?;
+        assert_eq!(c[0], rctensor1(&[s * 2, TDim::from(12)]));
+        Ok(())
+    }
+
+    #[test]
+    fn clamp_in_range() -> TractResult<()> {
+        let s = Symbol::new('S');
+        s.assume(Assumption::AtMost(8));
+        let mut model = TypedModel::default();
+        let range = crate::ops::array::Range::new(
+            tensor0(TDim::from(0)),
+            tensor0(s.to_dim()),
+            tensor0(TDim::from(1)),
+        );
+        let range = model.wire_node("range", range, &[])?;
+        let fact = model.outlet_fact(range[0])?;
+        assert_eq!(fact.range, Some(ValueRange::new(0, s)));
+        let a = model.wire_node("max", max::unary(rctensor1(&[TDim::from(0)])), &range)?;
+        let b = model.wire_node("min", min::unary(rctensor1(&[TDim::from(8)])), &a)?;
+        let c = model.wire_node("floor", min::unary(rctensor1(&[TDim::from(4)])), &b)?;
+        let end = s.to_dim().maxi(1.into()).mini(5.into());
+        assert_eq!(model.outlet_fact(c[0])?.range, Some(ValueRange::new(0, end)));
+        model.set_output_outlets(&c)?;
+        let decluttered = model.into_decluttered()?;
+        assert_eq!(decluttered.nodes().len(), 2);
+        assert!(decluttered.node_by_name("floor").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn clamp_after_overflowing_add() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let table = model.add_const("table", rctensor1(&[100i8, 110, 120]))?;
+        let indices = model.add_source("indices", i64::fact(&[4]))?;
+        let gather = crate::ops::array::Gather::new(0);
+        let values = model.wire_node("gather", gather, &[table, indices])?;
+        assert_eq!(model.outlet_fact(values[0])?.range, Some(ValueRange::new(100, 121)));
+        let a = model.wire_node("add", add::unary(rctensor1(&[100i8])), &values)?;
+        assert_eq!(model.outlet_fact(a[0])?.range, None);
+        let b = model.wire_node("clamp", max::unary(rctensor1(&[0i8])), &a)?;
+        model.set_output_outlets(&b)?;
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered.node_by_name("clamp").is_ok());
+        Ok(())
+    }
 }
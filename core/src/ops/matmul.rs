@@ -1,3 +1,4 @@
+pub mod block_quant;
 pub mod lir_unary;
 pub mod mir;
 pub mod mir_quant;
@@ -10,6 +11,7 @@ use tract_itertools::Itertools;
 use tract_linalg::mmm::FusedSpec;
 use tract_ndarray::prelude::*;
 
+pub use self::block_quant::BlockQuantMatMul;
 pub use self::mir::MatMul;
 pub use self::mir_quant::{MatMulQParams, QMatMul};
 pub use self::mir_unary::MatMulUnary;
@@ -63,6 +65,12 @@ pub fn output_type(input: DatumType) -> DatumType {
     }
 }
 
+/// Half precision floats (f16 and bf16), computed by the single precision
+/// kernels.
+pub(crate) fn is_half(dt: DatumType) -> bool {
+    dt == f16::datum_type() || dt == bf16::datum_type()
+}
+
 pub(super) fn eval(
     a: &Tensor,
     b: &Tensor,
@@ -70,6 +78,11 @@ pub(super) fn eval(
     b_trans: bool,
     c_trans: bool,
 ) -> TractResult<Tensor> {
+    if is_half(a.datum_type()) || is_half(b.datum_type()) {
+        // no half precision kernels, computed in single precision
+        let c = eval(&*a.cast_to::<f32>()?, &*b.cast_to::<f32>()?, a_trans, b_trans, c_trans)?;
+        return Ok(c.cast_to_dt(a.datum_type())?.into_owned());
+    }
     unsafe {
         let rank = a.rank();
         let (m, k, n, c_shape) = compute_shape(a.shape(), b.shape(), a_trans, b_trans, c_trans)?;
@@ -0,0 +1,173 @@
+use crate::internal::*;
+use tract_linalg::block_quant::BlockQuant;
+
+/// Product of a f32 input by the transpose of block-quantized constant
+/// weights.
+///
+/// The input is [.., k], the weights are a m x k matrix stored in `format`
+/// blocks, which is the layout of linear layers in GGUF files. The output is
+/// [.., m]. Weights are never dequantized as a whole.
+#[derive(Debug, Clone, Hash)]
+pub struct BlockQuantMatMul {
+    pub format: Box<dyn BlockQuant>,
+    pub m: usize,
+    pub k: usize,
+    pub blocks: Arc<Tensor>,
+}
+
+impl_dyn_hash!(BlockQuantMatMul);
+
+impl BlockQuantMatMul {
+    pub fn new(
+        format: Box<dyn BlockQuant>,
+        m: usize,
+        k: usize,
+        blocks: Arc<Tensor>,
+    ) -> TractResult<BlockQuantMatMul> {
+        ensure!(
+            k % format.block_len() == 0,
+            "{} needs k to be a multiple of {}, got {}",
+            format.name(),
+            format.block_len(),
+            k
+        );
+        let expected = m * k / format.block_len() * format.block_bytes();
+        ensure!(
+            blocks.datum_type() == u8::datum_type() && blocks.len() == expected,
+            "Expected {} bytes of {} blocks for {}x{}, got {:?}",
+            expected,
+            format.name(),
+            m,
+            k,
+            blocks
+        );
+        Ok(BlockQuantMatMul { format, m, k, blocks })
+    }
+
+    /// Quantizes m x k weights to packed int4 blocks of `block_len` values.
+    pub fn int4(weights: &Tensor, block_len: usize) -> TractResult<BlockQuantMatMul> {
+        ensure!(weights.rank() == 2, "Expected a matrix, got {:?}", weights);
+        let format = Int4Blocks::new(block_len)?;
+        let (m, k) = (weights.shape()[0], weights.shape()[1]);
+        ensure!(k % block_len == 0, "k ({}) must be a multiple of {}", k, block_len);
+        let blocks = format.quant_f32(weights.cast_to::<f32>()?.as_slice::<f32>()?)?;
+        BlockQuantMatMul::new(Box::new(format), m, k, rctensor1(&blocks))
+    }
+}
+
+impl Op for BlockQuantMatMul {
+    fn name(&self) -> Cow<str> {
+        "BlockQuantMatMul".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("{} weights, m: {} k: {}", self.format.name(), self.m, self.k)])
+    }
+
+    fn validation(&self) -> Validation {
+        Validation::Rounding
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for BlockQuantMatMul {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let input = input.cast_to::<f32>()?;
+        let n = input.len() / self.k;
+        let mut shape: TVec<usize> = input.shape().into();
+        *shape.last_mut().unwrap() = self.m;
+        let mut output = unsafe { Tensor::uninitialized::<f32>(&shape)? };
+        self.format.matmul_bt_f32(
+            self.blocks.as_slice::<u8>()?,
+            self.m,
+            self.k,
+            input.as_slice::<f32>()?,
+            n,
+            output.as_slice_mut::<f32>()?,
+        );
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for BlockQuantMatMul {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(
+            inputs[0].rank() > 0 && inputs[0].shape[inputs[0].rank() - 1] == self.k.to_dim(),
+            "BlockQuantMatMul expects an input of shape [.., {}], got {:?}",
+            self.k,
+            inputs[0]
+        );
+        let mut shape = inputs[0].shape.to_tvec();
+        *shape.last_mut().unwrap() = self.m.to_dim();
+        Ok(tvec!(f32::fact(shape)))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let n = inputs[0].shape.iter().take(inputs[0].rank() - 1).product::<TDim>();
+        Ok(tvec!((Cost::FMA(f32::datum_type()), n * self.m * self.k)))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tract_linalg::block_quant::Q8_0;
+
+    #[test]
+    fn q8_0() -> TractResult<()> {
+        let (m, k) = (2, 32);
+        // one 15.875 per block makes a 0.125 scale, so quantization is exact
+        let weights: Vec<f32> = (0..m * k)
+            .map(|i| if i % 32 == 0 { 15.875 } else { (i as f32 - 32.0) / 8.0 })
+            .collect();
+        let mut blocks = vec![0u8; m * 34];
+        for (w, blocks) in weights.chunks(32).zip(blocks.chunks_mut(34)) {
+            Q8_0.quant_block_f32(w, blocks);
+        }
+        let op = BlockQuantMatMul::new(Box::new(Q8_0), m, k, rctensor1(&blocks))?;
+        let input = Tensor::from_shape(&[1, 2, 32], &[[1f32; 32], [0f32; 32]].concat())?;
+
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([1, 2, 32]))?;
+        let output = model.wire_node("mm", op, &[source])?;
+        model.set_output_outlets(&output)?;
+        let output = model.into_runnable()?.run(tvec!(input))?;
+
+        let sums: Vec<f32> = weights.chunks(32).map(|w| w.iter().sum()).collect();
+        let expected = tensor3(&[[[sums[0], sums[1]], [0.0, 0.0]]]);
+        output[0].close_enough(&expected, false)
+    }
+
+    #[test]
+    fn int4() -> TractResult<()> {
+        let (m, k) = (3, 64);
+        // values in [-1, 0.875] make a 0.125 scale, so quantization is exact
+        let weights: Vec<f32> = (0..m * k).map(|i| (i % 16) as f32 / 8.0 - 1.0).collect();
+        let weights = tensor1(&weights).into_shape(&[m, k])?;
+        let op = BlockQuantMatMul::int4(&weights, 32)?;
+        assert_eq!(op.blocks.len(), m * 2 * 19);
+        let input: Vec<f32> = (0..2 * k).map(|i| (i % 7) as f32 - 3.0).collect();
+        let input = tensor1(&input).into_shape(&[2, k])?;
+
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact([2, k]))?;
+        let output = model.wire_node("mm", op, &[source])?;
+        model.set_output_outlets(&output)?;
+        let output = model.into_runnable()?.run(tvec!(input.clone()))?;
+
+        let expected = input
+            .to_array_view::<f32>()?
+            .into_dimensionality::<tract_ndarray::Ix2>()?
+            .dot(&weights.to_array_view::<f32>()?.into_dimensionality::<tract_ndarray::Ix2>()?.t());
+        output[0].close_enough(&expected.into_tensor(), true)
+    }
+}
@@ -256,7 +256,10 @@ fn eval(
             f.push(FusedSpec::AddMatMul {
                 k: geometry.k,
                 a: op.mmm.a_packed(size_of_a, geometry.k).wrap(&pa.view()),
-                b: geometry.b_storage.wrap(&inputs[0].view())?,
+                b: geometry.b_storage.wrap(&TensorView::at_prefix_unchecked(
+                    &inputs[0],
+                    &tvec![0; c_shape.len() - 2],
+                ))?,
             });
             for ix in 0..fused.len() {
                 f.push(fused.get_unchecked(ix).resolve(inputs, &[], &c_storage, c_store));
@@ -160,6 +160,29 @@ mod test {
         c.close_enough(&c_found, true).unwrap();
     }
 
+    #[test]
+    fn bin_bf16() -> TractResult<()> {
+        let a = tensor2(&[[0f32, 1.0, 2.0], [3.0, 4.0, 5.0]]).cast_to::<bf16>()?.into_owned();
+        let b = tensor2(&[[0f32], [1.0], [2.0]]).cast_to::<bf16>()?.into_owned();
+        let c = tensor2(&[[5f32], [14.0]]).cast_to::<bf16>()?.into_owned();
+        let op = MatMul::default();
+        let c_found = op.eval(tvec!(a.clone().into_arc_tensor(), b.into_arc_tensor()))?;
+        assert_eq!(*c_found[0], c);
+
+        let mut model = TypedModel::default();
+        let source = model.add_source("b", bf16::fact(&[3, 1]))?;
+        let wire = model.wire_node(
+            "m",
+            MatMulUnary::new(a.into_arc_tensor(), false, false, false),
+            &[source],
+        )?;
+        model.set_output_outlets(&wire)?;
+        let b = tensor2(&[[0f32], [1.0], [2.0]]).cast_to::<bf16>()?.into_owned();
+        let c_found = model.into_optimized()?.into_runnable()?.run(tvec!(b))?;
+        assert_eq!(*c_found[0], c);
+        Ok(())
+    }
+
     #[test]
     fn batch_input() -> TractResult<()> {
         crate::setup_test_logger();
@@ -187,4 +210,34 @@ mod test {
         model.into_decluttered()?.into_optimized()?.into_runnable()?.run(tvec!(input))?;
         Ok(())
     }
+
+    #[test]
+    fn skinny_products() -> TractResult<()> {
+        let (m, k) = (37, 29);
+        for n in 1..=5 {
+            for &b_trans in &[false, true] {
+                for batch in &[tvec!(), tvec!(2)] {
+                    let mut a_shape = batch.clone();
+                    a_shape.extend([m, k].iter().copied());
+                    let a: Vec<f32> =
+                        (0..a_shape.iter().product()).map(|i| (i % 7) as f32 - 3.0).collect();
+                    let a = tensor1(&a).into_shape(&a_shape)?.into_arc_tensor();
+                    let mut b_shape = batch.clone();
+                    b_shape.extend(if b_trans { [n, k] } else { [k, n] }.iter().copied());
+                    let b: Vec<f32> =
+                        (0..b_shape.iter().product()).map(|i| (i % 5) as f32 - 2.0).collect();
+                    let b = tensor1(&b).into_shape(&b_shape)?;
+                    let mut model = TypedModel::default();
+                    let source = model.add_source("b", f32::fact(&*b_shape))?;
+                    let op = MatMulUnary { a, a_trans: false, b_trans, c_trans: false };
+                    let output = model.wire_node("m", op, &[source])?;
+                    model.set_output_outlets(&output)?;
+                    let expected = model.clone().into_runnable()?.run(tvec!(b.clone()))?;
+                    let found = model.into_optimized()?.into_runnable()?.run(tvec!(b))?;
+                    found[0].close_enough(&expected[0], true)?;
+                }
+            }
+        }
+        Ok(())
+    }
 }
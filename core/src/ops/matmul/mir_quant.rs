@@ -568,7 +568,12 @@ pub(crate) fn wire_matmul_quant(
 
     let k = model.outlet_fact(a)?.shape[rank - 2 + !a_trans as usize].clone();
 
-    let abc_scale = combine_scales(model, name, params[1], params[3], params[5])?;
+    let a0 = wire_per_axis(model, &format!("{}.a0", name), params[0], m_axis, rank)?;
+    let a_scale = wire_per_axis(model, &format!("{}.a_scale", name), params[1], m_axis, rank)?;
+    let b0 = wire_per_axis(model, &format!("{}.b0", name), params[2], n_axis, rank)?;
+    let b_scale = wire_per_axis(model, &format!("{}.b_scale", name), params[3], n_axis, rank)?;
+
+    let abc_scale = combine_scales(model, name, a_scale, b_scale, params[5])?;
 
     let a_i32 =
         model.wire_node(format!("{}.a_as_i32", name), ops::cast::cast(i32::datum_type()), &[a])?[0];
@@ -590,12 +595,36 @@ pub(crate) fn wire_matmul_quant(
     )?[0];
     let sum_b =
         model.wire_node(format!("{}.sum_b_reduced", name), AxisOp::Rm(b_k_axis), &[sum_b])?[0];
-    let result = compensate_zero_points(
-        model, name, result, k, params[0], params[2], sum_a, sum_b, m_axis, n_axis,
-    )?;
+    let result =
+        compensate_zero_points(model, name, result, k, a0, b0, sum_a, sum_b, m_axis, n_axis)?;
     requant(model, name, result, output_type, abc_scale, params[4])
 }
 
+/// Reshapes a per-axis quantization parameter (one value per row or column
+/// of the product) so that it broadcasts along `axis` against a tensor of
+/// rank `rank`. A parameter of rank p > 1 (typically group and channel)
+/// spans the p axes ending at `axis`. Per-tensor parameters are left
+/// untouched.
+pub(crate) fn wire_per_axis(
+    model: &mut TypedModel,
+    name: &str,
+    param: OutletId,
+    axis: usize,
+    rank: usize,
+) -> TractResult<OutletId> {
+    let fact = model.outlet_fact(param)?;
+    let param_rank = fact.rank();
+    if param_rank == 0 || param_rank >= rank || fact.shape.iter().all(|d| d == 1.to_dim()) {
+        return Ok(param);
+    }
+    ensure!(axis + 1 >= param_rank && axis < rank);
+    let mut wire = param;
+    for ax in (0..axis + 1 - param_rank).chain(axis + 1..rank) {
+        wire = model.wire_node(format!("{}.per_axis_{}", name, ax), AxisOp::Add(ax), &[wire])?[0];
+    }
+    Ok(wire)
+}
+
 pub(crate) fn combine_scales(
     model: &mut TypedModel,
     name: &str,
@@ -1372,4 +1401,42 @@ mod test {
             },
         ));
     }
+
+    #[test]
+    fn per_axis_qparams() -> TractResult<()> {
+        use crate::ops::math::round_ties_to_even;
+        let a = arr2(&[[3i8, -2, 5], [0, 7, -4]]);
+        let b = arr2(&[[1i8, -3, 2, 0], [4, 0, -1, 2], [-2, 5, 3, 1]]);
+        let a0 = [1i32, -2];
+        let b0 = [0i32, 1, -1, 2];
+        let b_scale = [0.5f32, 0.25, 2.0, 1.0];
+        let params = MatMulQParams {
+            a0: tensor1(&a0).into(),
+            a_scale: tensor0(0.5f32).into(),
+            b0: tensor1(&b0).into(),
+            b_scale: tensor1(&b_scale).into(),
+            c0: tensor0(3i32).into(),
+            c_scale: tensor0(0.25f32).into(),
+        };
+        let mut model = TypedModel::default();
+        let source = model.add_source("a", i8::fact(&[2, 3]))?;
+        let b_const = model.add_const("b", b.clone().into_tensor())?;
+        let bias = model.add_const("bias", tensor0(0i32))?;
+        let op = QMatMul::new(false, false, false, i8::datum_type(), params);
+        let wire = model.wire_node("qmm", op, &[source, b_const, bias])?;
+        model.set_output_outlets(&wire)?;
+        let expected = Array2::from_shape_fn((2, 4), |(m, n)| {
+            let acc: i32 =
+                (0..3).map(|k| (a[(m, k)] as i32 - a0[m]) * (b[(k, n)] as i32 - b0[n])).sum();
+            let scaled = round_ties_to_even(acc as f32 * 0.5 * b_scale[n] / 0.25) as i32 + 3;
+            scaled.max(i8::MIN as i32).min(i8::MAX as i32) as i8
+        })
+        .into_tensor();
+        let input = tvec!(a.into_tensor());
+        let found = model.clone().into_runnable()?.run(input.clone())?;
+        assert_eq!(*found[0], expected);
+        let found = model.into_optimized()?.into_runnable()?.run(input)?;
+        assert_eq!(*found[0], expected);
+        Ok(())
+    }
 }
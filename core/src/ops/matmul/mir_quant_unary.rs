@@ -2,7 +2,7 @@ use ops::matmul::mir_quant::wire_matmul_quant;
 
 use crate::internal::*;
 use crate::ops;
-use crate::ops::matmul::mir_quant::{combine_scales, requant, wire_offset_u8_as_i8};
+use crate::ops::matmul::mir_quant::{combine_scales, requant, wire_offset_u8_as_i8, wire_per_axis};
 use crate::ops::matmul::*;
 use mir_quant::MatMulQParams;
 use mir_quant::QParamKind;
@@ -297,13 +297,23 @@ impl TypedOp for QMatMulUnary {
                     self.output_type,
                 )?;
 
-                let scale = combine_scales(
+                let rank = node.outputs[0].fact.rank();
+                let a_scale = wire_per_axis(
                     &mut patch,
-                    &node.name,
+                    &format!("{}.a_scale", node.name),
                     params_outlets[1],
+                    rank - 2 + self.c_trans as usize,
+                    rank,
+                )?;
+                let b_scale = wire_per_axis(
+                    &mut patch,
+                    &format!("{}.b_scale", node.name),
                     params_outlets[3],
-                    params_outlets[5],
+                    rank - 1 - self.c_trans as usize,
+                    rank,
                 )?;
+                let scale =
+                    combine_scales(&mut patch, &node.name, a_scale, b_scale, params_outlets[5])?;
                 let c0 = params_outlets[4];
 
                 for (ix, slice) in concat.slices.iter().enumerate() {
@@ -631,7 +641,7 @@ mod test {
     #[test]
     fn scale_big() {
         QMatMulUnaryProblemI8I8I8 {
-            a: arr2(&[[0],[0]]),
+            a: arr2(&[[0], [0]]),
             b: arr2(&[[0, 0]]),
             bias: tensor0(0i32),
             a0: -1,
@@ -158,6 +158,9 @@ impl TypedOp for MatMulUnary {
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
         let b = args_1!(model.node_input_facts(node.id)?);
+        if is_half(self.a.datum_type()) {
+            return Ok(Some(self.wire_half_as_f32(model, node)?));
+        }
         if let Some(b_shape) = b.shape.as_concrete() {
             return Ok(Some(self.new_mat_mul_unary_finite(model, node, &b_shape, b.datum_type)?));
         }
@@ -168,6 +171,29 @@ impl TypedOp for MatMulUnary {
 }
 
 impl MatMulUnary {
+    /// There are no half precision kernels: the product is computed in
+    /// single precision, between casts.
+    fn wire_half_as_f32(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<TypedModelPatch> {
+        let mut patch = TypedModelPatch::default();
+        let mut wire = patch.tap_model(model, node.inputs[0])?;
+        wire = patch.wire_node(
+            format!("{}.cast_input", node.name),
+            crate::ops::cast::cast(f32::datum_type()),
+            &[wire],
+        )?[0];
+        let op =
+            MatMulUnary { a: self.a.cast_to::<f32>()?.into_owned().into_arc_tensor(), ..*self };
+        wire = patch.wire_node(format!("{}.f32", node.name), op, &[wire])?[0];
+        let cast = crate::ops::cast::cast(self.a.datum_type());
+        wire = patch.wire_node(&node.name, cast, &[wire])?[0];
+        patch.shunt_outside(model, node.id.into(), wire)?;
+        Ok(patch.with_context("half-as-f32"))
+    }
+
     fn new_mat_mul_unary_finite(
         &self,
         model: &TypedModel,
@@ -210,18 +236,27 @@ impl MatMulUnary {
                 (pa.into_arc_tensor(), vec![ProtoFusedSpec::Store])
             });
         unsafe {
-            let mut packed_b_shape: TVec<usize> = b_shape[..b_shape.len() - 2].into();
-            packed_b_shape.push(mmm.b_pack().len(k, n));
-            wire = patch.wire_node(
-                format!("{}.pack", &*node.name),
-                super::MatMatMulPack {
-                    packer: mmm.b_pack(),
-                    trans: self.b_trans,
-                    output_shape: packed_b_shape,
-                },
-                &[wire],
-            )?[0];
-            let b_storage = mmm.b_packed(b_dt.size_of(), k);
+            // gemv and skinny kernels can read B in place, when it is already
+            // laid out as their packed panels: no packing, B is streamed
+            let (b_k_axis, b_n_axis) = if self.b_trans { (1, 0) } else { (0, 1) };
+            let b_streaming = mmm.b_pack().single_panel_len(k) == k * mmm.nr()
+                && if self.b_trans { mmm.nr() == 1 } else { n == mmm.nr() };
+            let b_storage = if b_streaming {
+                mmm.b_streaming_with_axes(b_k_axis, b_n_axis)
+            } else {
+                let mut packed_b_shape: TVec<usize> = b_shape[..b_shape.len() - 2].into();
+                packed_b_shape.push(mmm.b_pack().len(k, n));
+                wire = patch.wire_node(
+                    format!("{}.pack", &*node.name),
+                    super::MatMatMulPack {
+                        packer: mmm.b_pack(),
+                        trans: self.b_trans,
+                        output_shape: packed_b_shape,
+                    },
+                    &[wire],
+                )?[0];
+                mmm.b_packed(b_dt.size_of(), k)
+            };
             let rank = c_shape.len();
             let mut strides = natural_strides(&c_shape);
             let mut overrided_shape = c_shape.clone();
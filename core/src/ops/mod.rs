@@ -26,6 +26,7 @@ pub mod logic;
 pub mod math;
 pub mod matmul;
 pub mod nn;
+pub mod opaque;
 pub mod quant;
 pub mod scan;
 pub mod source;
@@ -92,6 +93,12 @@ pub trait EvalOp {
     }
 
     fn is_stateless(&self) -> bool;
+
+    /// Input `eval` writes its output to when it gets it uniquely owned and
+    /// with the output type and shape, instead of allocating a new tensor.
+    fn eval_in_place_input(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// A base operation
@@ -0,0 +1,243 @@
+use crate::internal::*;
+use ndarray::*;
+use tract_linalg::attention::AttentionProblem;
+
+/// Scaled dot product attention: softmax(scale * q.k^T + mask).v over the
+/// two last axes, the others being broadcast batch axes.
+///
+/// Inputs are q [.., q_len, depth], k [.., kv_len, depth] (or
+/// [.., depth, kv_len] if `k_trans`), v [.., kv_len, value_depth], and an
+/// optional additive mask broadcasting to [.., q_len, kv_len]. Each head runs
+/// through the linalg attention kernel, so the scores are never stored.
+#[derive(Clone, Debug, new, Educe, PartialEq)]
+#[educe(Hash)]
+pub struct ScaledDotProductAttention {
+    #[educe(Hash(method = "hash_f32"))]
+    pub scale: f32,
+    pub k_trans: bool,
+}
+
+impl_dyn_hash!(ScaledDotProductAttention);
+
+impl ScaledDotProductAttention {
+    fn geometry<D: DimLike>(&self, q: &[D], k: &[D], v: &[D]) -> TractResult<(D, D, D, D)> {
+        let rank = q.len();
+        ensure!(rank >= 2 && k.len() == rank && v.len() == rank, "Inconsistent attention ranks");
+        let (kv_len, depth) = if self.k_trans {
+            (k[rank - 1].clone(), k[rank - 2].clone())
+        } else {
+            (k[rank - 2].clone(), k[rank - 1].clone())
+        };
+        ensure!(q[rank - 1] == depth, "q and k depth mismatch");
+        ensure!(v[rank - 2] == kv_len, "k and v length mismatch");
+        Ok((q[rank - 2].clone(), kv_len, depth, v[rank - 1].clone()))
+    }
+
+    fn output_shape<D: DimLike>(
+        &self,
+        q: &[D],
+        k: &[D],
+        v: &[D],
+        mask: Option<&[D]>,
+    ) -> TractResult<TVec<D>> {
+        let rank = q.len();
+        let (q_len, kv_len, _, value_depth) = self.geometry(q, k, v)?;
+        let mut batch: TVec<&[D]> = tvec!(&q[..rank - 2], &k[..rank - 2], &v[..rank - 2]);
+        if let Some(mask) = mask {
+            ensure!(mask.len() <= rank, "Attention mask has a higher rank than q");
+            let scores = [q_len.clone(), kv_len];
+            let last = mask.len().min(2);
+            ensure!(
+                crate::broadcast::multi_broadcast(&[&scores[..], &mask[mask.len() - last..]])
+                    .as_deref()
+                    == Some(&scores[..]),
+                "Attention mask does not broadcast to the scores"
+            );
+            batch.push(&mask[..mask.len() - last]);
+        }
+        let mut shape = crate::broadcast::multi_broadcast(&batch)
+            .ok_or_else(|| format_err!("Could not broadcast attention batch axes"))?;
+        shape.push(q_len);
+        shape.push(value_depth);
+        Ok(shape)
+    }
+}
+
+impl Op for ScaledDotProductAttention {
+    fn name(&self) -> Cow<str> {
+        "ScaledDotProductAttention".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("scale: {} k_trans: {}", self.scale, self.k_trans)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    impl_op_same_as!();
+}
+
+impl EvalOp for ScaledDotProductAttention {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let views =
+            inputs.iter().map(|t| t.to_array_view::<f32>()).collect::<TractResult<TVec<_>>>()?;
+        let rank = views[0].ndim();
+        let (q_len, kv_len, depth, value_depth) =
+            self.geometry(views[0].shape(), views[1].shape(), views[2].shape())?;
+        let shape = self.output_shape(
+            views[0].shape(),
+            views[1].shape(),
+            views[2].shape(),
+            views.get(3).map(|m| m.shape()),
+        )?;
+        let batch = &shape[..rank - 2];
+        let k_shape = if self.k_trans { [depth, kv_len] } else { [kv_len, depth] };
+        let q = broadcast(&views[0], batch, &[q_len, depth])?;
+        let k = broadcast(&views[1], batch, &k_shape)?;
+        let v = broadcast(&views[2], batch, &[kv_len, value_depth])?;
+        let mask = views.get(3).map(|m| broadcast(m, batch, &[q_len, kv_len])).transpose()?;
+        let kernel = (tract_linalg::ops().attention_f32)();
+        let mut output = Tensor::zero::<f32>(&shape)?;
+        let mut out = output.to_array_view_mut::<f32>()?;
+        let (mut q_buf, mut k_buf, mut v_buf, mut mask_buf) = (vec![], vec![], vec![], vec![]);
+        let mut out_buf = vec![0f32; q_len * value_depth];
+        for coords in ndarray::indices(batch) {
+            let coords = coords.slice();
+            let head = |t: &ArrayViewD<'_, f32>, buf: &mut Vec<f32>| {
+                let mut view = t.view();
+                for (axis, &c) in coords.iter().enumerate() {
+                    view.collapse_axis(Axis(axis), c);
+                }
+                buf.clear();
+                buf.extend(view.iter().copied());
+            };
+            head(&q, &mut q_buf);
+            head(&v, &mut v_buf);
+            if self.k_trans {
+                let mut view = k.view();
+                for (axis, &c) in coords.iter().enumerate() {
+                    view.collapse_axis(Axis(axis), c);
+                }
+                k_buf.clear();
+                k_buf.extend(view.t().iter().copied());
+            } else {
+                head(&k, &mut k_buf);
+            }
+            if let Some(mask) = &mask {
+                head(mask, &mut mask_buf);
+            }
+            let problem = AttentionProblem {
+                q: &q_buf,
+                k: &k_buf,
+                v: &v_buf,
+                mask: mask.as_ref().map(|_| &*mask_buf),
+                scale: self.scale,
+                q_len,
+                kv_len,
+                depth,
+                value_depth,
+            };
+            kernel.run(&problem, &mut out_buf)?;
+            let mut view = out.view_mut();
+            for (axis, &c) in coords.iter().enumerate() {
+                view.collapse_axis(Axis(axis), c);
+            }
+            view.iter_mut().zip(out_buf.iter()).for_each(|(o, x)| *o = *x);
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+fn broadcast<'a>(
+    t: &'a ArrayViewD<f32>,
+    batch: &[usize],
+    last: &[usize],
+) -> TractResult<ArrayViewD<'a, f32>> {
+    let shape: TVec<usize> = batch.iter().chain(last.iter()).copied().collect();
+    t.broadcast(&*shape)
+        .ok_or_else(|| format_err!("Can not broadcast {:?} to {:?}", t.shape(), shape))
+}
+
+impl TypedOp for ScaledDotProductAttention {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs.len() == 3 || inputs.len() == 4, "Attention expects 3 or 4 inputs");
+        ensure!(
+            inputs.iter().all(|i| i.datum_type == f32::datum_type()),
+            "Attention is only implemented for f32"
+        );
+        let shape = self.output_shape(
+            &inputs[0].shape,
+            &inputs[1].shape,
+            &inputs[2].shape,
+            inputs.get(3).map(|m| &*m.shape),
+        )?;
+        Ok(tvec!(f32::fact(shape)))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let shape = self.output_shape(
+            &inputs[0].shape,
+            &inputs[1].shape,
+            &inputs[2].shape,
+            inputs.get(3).map(|m| &*m.shape),
+        )?;
+        let (q_len, kv_len, depth, value_depth) =
+            self.geometry(&inputs[0].shape, &inputs[1].shape, &inputs[2].shape)?;
+        let batch: TDim = shape.iter().rev().skip(2).product();
+        let scores = batch * q_len * kv_len;
+        Ok(tvec!(
+            (Cost::FMA(f32::datum_type()), scores.clone() * (depth + value_depth)),
+            (Cost::FMA(f32::datum_type()), scores * 6)
+        ))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::matmul::MatMul;
+    use crate::ops::nn::Softmax;
+
+    fn tensor(shape: &[usize], seed: usize) -> Tensor {
+        let len = shape.iter().product::<usize>();
+        let data: Vec<f32> = (0..len).map(|i| (((i + seed) * 7) % 13) as f32 / 6.0 - 1.0).collect();
+        tensor1(&data).into_shape(shape).unwrap()
+    }
+
+    #[test]
+    fn matches_decomposed() -> TractResult<()> {
+        let (q, k, v) =
+            (tensor(&[2, 3, 5, 4], 0), tensor(&[2, 1, 7, 4], 1), tensor(&[2, 1, 7, 6], 2));
+        let mask = tensor(&[1, 1, 5, 7], 3);
+        let mut model = TypedModel::default();
+        let wires = [
+            model.add_source("q", f32::fact(q.shape()))?,
+            model.add_source("k", f32::fact(k.shape()))?,
+            model.add_source("v", f32::fact(v.shape()))?,
+            model.add_source("mask", f32::fact(mask.shape()))?,
+        ];
+        let s = model.wire_node("qk", MatMul::default().with_b_trans(true), &wires[0..2])?;
+        let s = model.wire_node(
+            "scale",
+            crate::ops::math::mul::unary(rctensor4(&[[[[0.5f32]]]])),
+            &s,
+        )?;
+        let s =
+            model.wire_node("mask.add", crate::ops::math::add::bin_typed(), &[s[0], wires[3]])?;
+        let p = model.wire_node("softmax", Softmax::new(tvec!(3), f32::datum_type()), &s)?;
+        let o = model.wire_node("pv", MatMul::default(), &[p[0], wires[2]])?;
+        model.set_output_outlets(&o)?;
+        let inputs = tvec!(q.clone(), k.clone(), v.clone(), mask.clone());
+        let expected = model.into_runnable()?.run(inputs)?;
+
+        let op = ScaledDotProductAttention::new(0.5, false);
+        let found = op.eval(tvec!(q.into(), k.clone().into(), v.into(), mask.into()))?;
+        found[0].close_enough(&expected[0], true)
+    }
+}
@@ -0,0 +1,127 @@
+use crate::internal::*;
+use ndarray::prelude::*;
+use num_traits::Float;
+
+/// Normalizes its input over `axes`: (x - mean) / sqrt(var + epsilon), with
+/// the mean and the biased variance computed over `axes`. The scale and the
+/// offset of the usual layer norm are left to element-wise ops.
+#[derive(Clone, Debug, new, Educe, PartialEq)]
+#[educe(Hash)]
+pub struct LayerNorm {
+    pub axes: TVec<usize>,
+    #[educe(Hash(method = "hash_f32"))]
+    pub epsilon: f32,
+}
+
+impl_dyn_hash!(LayerNorm);
+
+impl Op for LayerNorm {
+    fn name(&self) -> Cow<str> {
+        "LayerNorm".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("axes: {:?} epsilon: {}", self.axes, self.epsilon)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    impl_op_same_as!();
+}
+
+impl EvalOp for LayerNorm {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = match input.datum_type() {
+            DatumType::F16 => self.eval_t::<f16>(input)?,
+            DatumType::BF16 => self.eval_t::<bf16>(input)?,
+            DatumType::F32 => self.eval_t::<f32>(input)?,
+            DatumType::F64 => self.eval_t::<f64>(input)?,
+            dt => bail!("Unsupported type {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl LayerNorm {
+    fn eval_t<T: Float + Datum>(&self, input: Arc<Tensor>) -> TractResult<Tensor> {
+        let epsilon = T::from(self.epsilon).unwrap();
+        let mut output = input.into_tensor().into_array::<T>()?;
+        if self.axes.len() == 1 {
+            for lane in output.lanes_mut(Axis(self.axes[0])) {
+                layer_norm_inner(lane, epsilon);
+            }
+        } else {
+            let mut iterating_shape: TVec<usize> = output.shape().into();
+            for &axis in &self.axes {
+                iterating_shape[axis] = 1;
+            }
+            for coords in tract_ndarray::indices(&*iterating_shape) {
+                let mut view = output.view_mut();
+                for ix in 0..iterating_shape.len() {
+                    if !self.axes.contains(&ix) {
+                        view.collapse_axis(Axis(ix), coords[ix]);
+                    }
+                }
+                layer_norm_inner(view, epsilon);
+            }
+        }
+        Ok(output.into_tensor())
+    }
+}
+
+fn layer_norm_inner<T: Float, D: Dimension>(mut view: ArrayViewMut<T, D>, epsilon: T) {
+    let len = T::from(view.len()).unwrap();
+    let mean = view.iter().fold(T::zero(), |acc, &x| acc + x) / len;
+    let var = view.iter().fold(T::zero(), |acc, &x| acc + (x - mean) * (x - mean)) / len;
+    let scale = (var + epsilon).sqrt().recip();
+    view.mapv_inplace(|x| (x - mean) * scale);
+}
+
+impl TypedOp for LayerNorm {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs[0].datum_type.is_float(), "LayerNorm input must be float");
+        ensure!(self.axes.iter().all(|&axis| axis < inputs[0].rank()));
+        Ok(tvec!(inputs[0].datum_type.fact(inputs[0].shape.clone())))
+    }
+
+    fn invariants(
+        &self,
+        inputs: &[&TypedFact],
+        _outputs: &[&TypedFact],
+    ) -> TractResult<Invariants> {
+        let axes = (0..inputs[0].rank())
+            .filter(|axis| !self.axes.contains(axis))
+            .map(AxisInfo::simple)
+            .collect::<TVec<_>>();
+        Ok(axes.into())
+    }
+
+    fn change_axes(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+        _io: InOut,
+        change: &AxisOp,
+    ) -> TractResult<Option<AxisChangeConsequence>> {
+        let axes: Option<TVec<usize>> =
+            self.axes.iter().map(|axis| change.transform_axis(*axis)).collect();
+        if let Some(axes) = axes {
+            let op = Some(Box::new(LayerNorm { axes, ..self.clone() }) as _);
+            Ok(Some(AxisChangeConsequence::new(model, node, op, change)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let dt = inputs[0].datum_type;
+        Ok(tvec!((Cost::FMA(dt), inputs[0].shape.iter().product::<TDim>() * 3)))
+    }
+
+    as_op!();
+}
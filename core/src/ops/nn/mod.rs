@@ -1,19 +1,65 @@
+mod attention;
 mod data_formats;
+mod layer_norm;
 mod reduce;
+mod rope;
 mod softmax;
 
+pub use self::attention::ScaledDotProductAttention;
 pub use self::data_formats::{BaseDataShape, DataFormat, DataShape, SymDataShape};
+pub use self::layer_norm::LayerNorm;
 pub use self::reduce::{Reduce, Reducer};
+pub use self::rope::{RopeLayout, RopeRotation, RopeScaling, RopeTables, RotaryEmbedding};
 pub use self::softmax::Softmax;
 
 pub use crate::internal::*;
 
 element_wise!(sigmoid, Sigmoid, [f32] => |_, xs| {
-    (tract_linalg::ops().sigmoid_f32)().run(xs)
+    use crate::ops::math::*;
+    transcendental_f32(Transcendental::Sigmoid, xs)
 };
 cost: |dt| {tvec!((Cost::FMA(dt), 11), (Cost::Div(dt), 1))}
 );
 
+element_wise!(silu, Silu, [f32] => |_, xs| {
+    use crate::ops::math::*;
+    let mut sigmoid = xs.to_vec();
+    transcendental_f32(Transcendental::Sigmoid, &mut sigmoid)?;
+    xs.iter_mut().zip(sigmoid.iter()).for_each(|(x, s)| *x *= s);
+    Ok(())
+};
+cost: |dt| {tvec!((Cost::FMA(dt), 12), (Cost::Div(dt), 1))}
+);
+
+element_wise!(gelu, Gelu, [f32] => |_, xs| {
+    use crate::ops::math::*;
+    let mut erf = xs.iter().map(|x| x * std::f32::consts::FRAC_1_SQRT_2).collect::<Vec<_>>();
+    transcendental_f32(Transcendental::Erf, &mut erf)?;
+    xs.iter_mut().zip(erf.iter()).for_each(|(x, e)| *x *= 0.5 * (1.0 + e));
+    Ok(())
+};
+cost: |dt| {tvec!((Cost::FMA(dt), 16), (Cost::Div(dt), 1))}
+);
+
+// Gelu with the tanh approximation:
+// 0.5 * x * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * x^3)))
+element_wise!(gelu_tanh, GeluTanh, [f32] => |_, xs| {
+    use crate::ops::math::*;
+    let mut tanh = xs
+        .iter()
+        .map(|x| GELU_TANH_SCALE * (x + GELU_TANH_CUBE * x * x * x))
+        .collect::<Vec<_>>();
+    transcendental_f32(Transcendental::Tanh, &mut tanh)?;
+    xs.iter_mut().zip(tanh.iter()).for_each(|(x, t)| *x *= 0.5 * (1.0 + t));
+    Ok(())
+};
+cost: |dt| {tvec!((Cost::FMA(dt), 15), (Cost::Div(dt), 1))}
+);
+
+/// sqrt(2 / pi)
+pub const GELU_TANH_SCALE: f32 = 0.797_884_6;
+pub const GELU_TANH_CUBE: f32 = 0.044_715;
+
 element_wise!(leaky_relu, LeakyRelu { #[educe(Hash(method = "hash_f32"))] alpha: f32 },
     [f32] => |op, xs| { xs.iter_mut().for_each(|x| *x *= if *x < 0. { op.alpha } else { 1.0 }); Ok(()) }
 );
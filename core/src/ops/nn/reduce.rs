@@ -59,6 +59,12 @@ impl Reducer {
         let (zp, scale) = input.datum_type().zp_scale();
         Ok(unsafe {
             match self {
+                ArgMax(last) if dt == DatumType::F32 => {
+                    Self::reduce_t(self, axes, &output_shape, input, argmax_f32, *last)
+                }
+                ArgMin(last) if dt == DatumType::F32 => {
+                    Self::reduce_t(self, axes, &output_shape, input, argmin_f32, *last)
+                }
                 ArgMax(last) => {
                     r!(Self::reduce_t(dt)(self, axes, &output_shape, &input, argmax_t, *last))
                 }
@@ -195,6 +201,27 @@ where
         .0 as i64
 }
 
+fn arg_min_max_f32(
+    v: ArrayViewD<f32>,
+    f: impl Fn(&dyn tract_linalg::arg_min_max::ArgMinMax<f32>, &[f32]) -> usize,
+) -> i64 {
+    let kernel = (tract_linalg::ops().arg_min_max_f32)();
+    if let Some(slice) = v.as_slice() {
+        f(&*kernel, slice) as i64
+    } else {
+        let values: Vec<f32> = v.iter().copied().collect();
+        f(&*kernel, &values) as i64
+    }
+}
+
+fn argmax_f32(v: ArrayViewD<f32>, last: bool) -> i64 {
+    arg_min_max_f32(v, |k, s| k.argmax(s, last))
+}
+
+fn argmin_f32(v: ArrayViewD<f32>, last: bool) -> i64 {
+    arg_min_max_f32(v, |k, s| k.argmin(s, last))
+}
+
 fn max_t<'a, T>(v: ArrayViewD<'a, T>, _: ()) -> T
 where
     T: Copy + Datum + num_traits::Bounded + ::std::cmp::PartialOrd,
@@ -314,3 +341,29 @@ impl TypedOp for Reduce {
         Ok(Some(AxisChangeConsequence::new(model, node, op, change)))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arg_min_max_f32_matches_generic() -> TractResult<()> {
+        let values: Vec<f32> = (0..3 * 37).map(|i| ((i * 17) % 23) as f32).collect();
+        let input = Tensor::from_shape(&[3, 37], &values)?;
+        for axis in 0..2 {
+            for last in &[false, true] {
+                for (reducer, f) in &[
+                    (Reducer::ArgMax(*last), argmax_t::<f32> as fn(ArrayViewD<f32>, bool) -> i64),
+                    (Reducer::ArgMin(*last), argmin_t::<f32>),
+                ] {
+                    let found = reducer.reduce(&[axis], &input)?;
+                    let mut shape = tvec!(3, 37);
+                    shape[axis] = 1;
+                    let expected = unsafe { reducer.reduce_t(&[axis], &shape, &input, f, *last) };
+                    assert_eq!(found, expected);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+use crate::internal::*;
+use ndarray::*;
+use num_traits::Float;
+
+/// Share of the last axis that is rotated, the remaining features passing
+/// through unchanged (partial rotary embeddings of GPT-J or GPT-NeoX).
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum RopeRotation {
+    Full,
+    Half,
+    Quarter,
+}
+
+impl RopeRotation {
+    /// Count of rotated features for a last axis of `dim`.
+    pub fn rotated<D: DimLike>(&self, dim: D) -> D {
+        match self {
+            RopeRotation::Full => dim,
+            RopeRotation::Half => dim / 2,
+            RopeRotation::Quarter => dim / 4,
+        }
+    }
+}
+
+/// Pairing of the rotated features.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum RopeLayout {
+    /// The feature i is paired with i + r/2 (`rotate_half`, as in Llama or
+    /// GPT-NeoX).
+    Halves,
+    /// The feature 2i is paired with 2i + 1 (as in GPT-J).
+    Interleaved,
+}
+
+/// Rotary position embedding, applied to the r first features of the last
+/// axis of its first input:
+///
+/// ```text
+/// out[.., :r] = x[.., :r] * cos + rotate(x[.., :r]) * sin
+/// ```
+///
+/// where rotate maps each pair (a, b) of features to (-b, a). The cos and sin
+/// inputs broadcast to [.., r], and hold the angle of each feature (so both
+/// features of a pair get the same value): `RopeTables` builds them.
+#[derive(Clone, Debug, new, Hash, PartialEq)]
+pub struct RotaryEmbedding {
+    pub rotation: RopeRotation,
+    pub layout: RopeLayout,
+}
+
+impl_dyn_hash!(RotaryEmbedding);
+
+impl Op for RotaryEmbedding {
+    fn name(&self) -> Cow<str> {
+        "RotaryEmbedding".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("rotation: {:?} layout: {:?}", self.rotation, self.layout)])
+    }
+
+    op_core_mir!();
+    op_as_typed_op!();
+    impl_op_same_as!();
+}
+
+impl EvalOp for RotaryEmbedding {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let (input, cos, sin) = args_3!(inputs);
+        let output = match input.datum_type() {
+            DatumType::F16 => self.eval_t::<f16>(input, &cos, &sin)?,
+            DatumType::BF16 => self.eval_t::<bf16>(input, &cos, &sin)?,
+            DatumType::F32 => self.eval_t::<f32>(input, &cos, &sin)?,
+            DatumType::F64 => self.eval_t::<f64>(input, &cos, &sin)?,
+            dt => bail!("Unsupported type {:?}", dt),
+        };
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl RotaryEmbedding {
+    fn eval_t<T: Float + Datum>(
+        &self,
+        input: Arc<Tensor>,
+        cos: &Tensor,
+        sin: &Tensor,
+    ) -> TractResult<Tensor> {
+        let mut output = input.into_tensor().into_array::<T>()?;
+        let last = output.ndim() - 1;
+        let rotated = self.rotation.rotated(output.shape()[last]);
+        let mut shape: TVec<usize> = output.shape().into();
+        shape[last] = rotated;
+        let cos = cos.to_array_view::<T>()?;
+        let cos = cos
+            .broadcast(&*shape)
+            .ok_or_else(|| format_err!("Can not broadcast cos {:?} to {:?}", cos.shape(), shape))?;
+        let sin = sin.to_array_view::<T>()?;
+        let sin = sin
+            .broadcast(&*shape)
+            .ok_or_else(|| format_err!("Can not broadcast sin {:?} to {:?}", sin.shape(), shape))?;
+        let mut x = vec![T::zero(); rotated];
+        Zip::from(output.lanes_mut(Axis(last)))
+            .and(cos.lanes(Axis(last)))
+            .and(sin.lanes(Axis(last)))
+            .for_each(|mut lane, cos, sin| {
+                x.iter_mut().zip(lane.iter()).for_each(|(x, l)| *x = *l);
+                for i in 0..rotated {
+                    let pair = match self.layout {
+                        RopeLayout::Halves if i < rotated / 2 => -x[i + rotated / 2],
+                        RopeLayout::Halves => x[i - rotated / 2],
+                        RopeLayout::Interleaved if i % 2 == 0 => -x[i + 1],
+                        RopeLayout::Interleaved => x[i - 1],
+                    };
+                    lane[i] = x[i] * cos[i] + pair * sin[i];
+                }
+            });
+        Ok(output.into_tensor())
+    }
+}
+
+impl TypedOp for RotaryEmbedding {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs.len() == 3, "RotaryEmbedding expects input, cos and sin");
+        let dt = inputs[0].datum_type;
+        ensure!(dt.is_float(), "RotaryEmbedding input must be float");
+        ensure!(inputs[1].datum_type == dt && inputs[2].datum_type == dt);
+        let rank = inputs[0].rank();
+        ensure!(rank >= 1 && inputs[1].rank() <= rank && inputs[2].rank() <= rank);
+        let rotated = self.rotation.rotated(inputs[0].shape[rank - 1].clone());
+        if let Ok(rotated) = rotated.to_usize() {
+            ensure!(
+                rotated % 2 == 0,
+                "RotaryEmbedding must rotate an even count of features, got {}",
+                rotated
+            );
+        }
+        let mut shape: TVec<TDim> = inputs[0].shape.to_tvec();
+        shape[rank - 1] = rotated;
+        for table in &inputs[1..] {
+            let broadcast = crate::broadcast::multi_broadcast(&[&*shape, &*table.shape.to_tvec()]);
+            ensure!(
+                broadcast.as_ref() == Some(&shape),
+                "RotaryEmbedding table {:?} does not broadcast to {:?}",
+                table.shape,
+                shape
+            );
+        }
+        Ok(tvec!(dt.fact(inputs[0].shape.clone())))
+    }
+
+    fn cost(&self, inputs: &[&TypedFact]) -> TractResult<TVec<(Cost, TDim)>> {
+        let dt = inputs[0].datum_type;
+        let rank = inputs[0].rank();
+        let lanes: TDim = inputs[0].shape.iter().take(rank - 1).product();
+        let rotated = self.rotation.rotated(inputs[0].shape[rank - 1].clone());
+        Ok(tvec!((Cost::FMA(dt), lanes * rotated * 2)))
+    }
+
+    as_op!();
+}
+
+/// Scaling of the positions, to extend the context of a model beyond the
+/// length it was trained on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RopeScaling {
+    None,
+    /// Positions are divided by the factor (position interpolation).
+    Linear(f32),
+    /// The base is scaled by factor^(r/(r-2)) ("NTK-aware" scaling).
+    Ntk(f32),
+}
+
+/// Builder of the cos and sin inputs of `RotaryEmbedding`: the feature pair k
+/// of position p is rotated by the angle p.base^(-2k/r).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RopeTables {
+    pub rotated: usize,
+    pub base: f32,
+    pub scaling: RopeScaling,
+    pub layout: RopeLayout,
+}
+
+impl RopeTables {
+    pub fn new(rotated: usize, layout: RopeLayout) -> RopeTables {
+        RopeTables { rotated, base: 10000.0, scaling: RopeScaling::None, layout }
+    }
+
+    pub fn with_base(self, base: f32) -> RopeTables {
+        RopeTables { base, ..self }
+    }
+
+    pub fn with_scaling(self, scaling: RopeScaling) -> RopeTables {
+        RopeTables { scaling, ..self }
+    }
+
+    /// Cos and sin tables for `positions`, both shaped [positions, rotated].
+    pub fn cos_sin(&self, positions: std::ops::Range<usize>) -> TractResult<(Tensor, Tensor)> {
+        ensure!(self.rotated % 2 == 0, "Rotary embeddings need an even count of features");
+        let pairs = self.rotated / 2;
+        let (base, position_scale) = match self.scaling {
+            RopeScaling::None => (self.base as f64, 1.0),
+            RopeScaling::Linear(factor) => (self.base as f64, 1.0 / factor as f64),
+            RopeScaling::Ntk(factor) => {
+                let r = self.rotated as f64;
+                (self.base as f64 * (factor as f64).powf(r / (r - 2.0)), 1.0)
+            }
+        };
+        let len = positions.len();
+        let mut cos = Array2::<f32>::zeros((len, self.rotated));
+        let mut sin = Array2::<f32>::zeros((len, self.rotated));
+        for (row, p) in positions.enumerate() {
+            for k in 0..pairs {
+                let angle =
+                    p as f64 * position_scale * base.powf(-2.0 * k as f64 / self.rotated as f64);
+                let features = match self.layout {
+                    RopeLayout::Halves => [k, k + pairs],
+                    RopeLayout::Interleaved => [2 * k, 2 * k + 1],
+                };
+                for f in features {
+                    cos[(row, f)] = angle.cos() as f32;
+                    sin[(row, f)] = angle.sin() as f32;
+                }
+            }
+        }
+        Ok((cos.into_tensor(), sin.into_tensor()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rotate(x: &[f32], angles: &[f32], pairs: &[(usize, usize)]) -> Vec<f32> {
+        let mut y = x.to_vec();
+        for (&(a, b), angle) in pairs.iter().zip(angles) {
+            y[a] = x[a] * angle.cos() - x[b] * angle.sin();
+            y[b] = x[b] * angle.cos() + x[a] * angle.sin();
+        }
+        y
+    }
+
+    #[test]
+    fn rotate_pairs() -> TractResult<()> {
+        let x: Vec<f32> = (0..8).map(|i| i as f32 - 3.5).collect();
+        let cases = [
+            (RopeRotation::Full, RopeLayout::Halves, vec![(0, 4), (1, 5), (2, 6), (3, 7)]),
+            (RopeRotation::Full, RopeLayout::Interleaved, vec![(0, 1), (2, 3), (4, 5), (6, 7)]),
+            (RopeRotation::Half, RopeLayout::Halves, vec![(0, 2), (1, 3)]),
+            (RopeRotation::Quarter, RopeLayout::Interleaved, vec![(0, 1)]),
+        ];
+        for (rotation, layout, pairs) in cases {
+            let rotated = rotation.rotated(8);
+            let (cos, sin) = RopeTables::new(rotated, layout).with_base(100.0).cos_sin(0..3)?;
+            let op = RotaryEmbedding::new(rotation, layout);
+            let input = tensor1(&x.repeat(3)).into_shape(&[3, 8])?;
+            let output = op.eval(tvec!(input.into(), cos.into(), sin.into()))?;
+            let output = output[0].to_array_view::<f32>()?;
+            for p in 0..3 {
+                let angles: Vec<f32> = (0..pairs.len())
+                    .map(|k| p as f32 * 100f32.powf(-2.0 * k as f32 / rotated as f32))
+                    .collect();
+                let expected = tensor1(&rotate(&x, &angles, &pairs));
+                tensor1(&output.index_axis(Axis(0), p).iter().copied().collect::<Vec<_>>())
+                    .close_enough(&expected, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn scaled_tables() -> TractResult<()> {
+        let tables = RopeTables::new(8, RopeLayout::Halves);
+        let (cos, sin) = tables.clone().with_scaling(RopeScaling::Linear(4.0)).cos_sin(0..8)?;
+        let (cos_ref, sin_ref) = tables.cos_sin(0..2)?;
+        assert_eq!(cos.slice(0, 0, 1)?, cos_ref.slice(0, 0, 1)?);
+        cos.slice(0, 4, 5)?.close_enough(&cos_ref.slice(0, 1, 2)?, true)?;
+        sin.slice(0, 4, 5)?.close_enough(&sin_ref.slice(0, 1, 2)?, true)?;
+        // NTK keeps the highest frequency, and slows down the lowest one
+        let (_, sin_ntk) = tables.clone().with_scaling(RopeScaling::Ntk(4.0)).cos_sin(1..2)?;
+        let (_, sin_ref) = tables.cos_sin(1..2)?;
+        let (sin_ntk, sin_ref) = (sin_ntk.as_slice::<f32>()?, sin_ref.as_slice::<f32>()?);
+        assert_eq!(sin_ntk[0], sin_ref[0]);
+        assert!(sin_ntk[3] < sin_ref[3]);
+        Ok(())
+    }
+}
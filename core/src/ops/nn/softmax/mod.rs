@@ -106,8 +106,15 @@ impl EvalOp for Softmax {
 
         let output = match dt {
             DatumType::F64 => self.eval_t::<f64>(input)?,
+            DatumType::F32 if self.axes.len() == 1 => {
+                self.eval_linalg::<f32>(input, (tract_linalg::ops().softmax_f32)())?
+            }
+            DatumType::F16 if self.axes.len() == 1 => {
+                self.eval_linalg::<f16>(input, (tract_linalg::ops().softmax_f16)())?
+            }
             DatumType::F32 => self.eval_t::<f32>(input)?,
             DatumType::F16 => self.eval_t::<f16>(input)?,
+            DatumType::BF16 => self.eval_t::<bf16>(input)?,
             DatumType::QI8(_) | DatumType::QU8(_) => self.eval_quant_t(input)?,
             dt => bail!("Unsupported type {:?}", dt),
         };
@@ -143,6 +150,28 @@ impl Softmax {
         Ok(tvec!(output.into_arc_tensor()))
     }
 
+    /// Single axis softmax, running each lane through a linalg kernel,
+    /// via a buffer when the lane is not contiguous.
+    fn eval_linalg<T: Datum + Copy>(
+        &self,
+        input: Arc<Tensor>,
+        kernel: Box<dyn tract_linalg::softmax::Softmax<T>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut output = input.into_tensor().into_array::<T>()?;
+        let mut buffer = vec![];
+        for mut lane in output.lanes_mut(Axis(self.axes[0])) {
+            if let Some(slice) = lane.as_slice_mut() {
+                kernel.run(slice)?;
+            } else {
+                buffer.clear();
+                buffer.extend(lane.iter().copied());
+                kernel.run(&mut buffer)?;
+                lane.iter_mut().zip(buffer.iter()).for_each(|(x, y)| *x = *y);
+            }
+        }
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+
     fn eval_quant_t(&self, input: Arc<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
         let mut iterating_shape: TVec<usize> = input.shape().into();
 
@@ -472,6 +501,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_softmax_f32_axes() -> Result<()> {
+        let data = Tensor::from_shape(
+            &[2, 3, 11],
+            &(0..66).map(|i| ((i * 7) % 13) as f32 - 6.0).collect::<Vec<_>>(),
+        )?;
+        for axis in 0..3 {
+            let softmax = Softmax { axes: tvec![axis], output_dt: DatumType::F32 };
+            let found = softmax.eval(tvec!(data.clone().into_arc_tensor()))?;
+            let mut expected = data.clone().into_array::<f32>()?;
+            for lane in expected.lanes_mut(Axis(axis)) {
+                softmax_inner(lane);
+            }
+            found[0].close_enough(&expected.into_tensor(), true)?;
+        }
+        Ok(())
+    }
+
     #[test]
     // We test QU8 -> QU8
     fn test_softmax_trivial_0() -> Result<()> {
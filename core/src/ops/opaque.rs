@@ -0,0 +1,124 @@
+use crate::internal::*;
+
+/// Evaluation function for an `OpaqueOp`, provided by the application.
+pub type OpaqueEval =
+    Arc<dyn Fn(&OpaqueOp, TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> + Send + Sync>;
+
+/// An operator tract does not implement, carried through the model untouched.
+///
+/// Its evaluation is delegated to the function registered for its `kind` in
+/// the session state (see `SimpleState::register_opaque_eval`).
+#[derive(Debug, Clone, Hash)]
+pub struct OpaqueOp {
+    pub kind: String,
+    pub attributes: Vec<(String, Arc<Tensor>)>,
+    pub output_facts: TVec<TypedFact>,
+}
+
+impl_dyn_hash!(OpaqueOp);
+
+impl OpaqueOp {
+    pub fn attribute(&self, name: &str) -> Option<&Arc<Tensor>> {
+        self.attributes.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+    }
+}
+
+impl Op for OpaqueOp {
+    fn name(&self) -> Cow<str> {
+        format!("Opaque({})", self.kind).into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(self.attributes.iter().map(|(k, v)| format!("{}: {:?}", k, v)).collect())
+    }
+
+    op_core!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for OpaqueOp {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(OpaqueOpState)))
+    }
+}
+
+impl TypedOp for OpaqueOp {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(self.output_facts.clone())
+    }
+
+    as_op!();
+}
+
+#[derive(Debug, Clone)]
+struct OpaqueOpState;
+
+impl OpState for OpaqueOpState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = op.downcast_ref::<OpaqueOp>().context("Wrong op")?;
+        let eval = session.opaque_evals.get(&op.kind).with_context(|| {
+            format!("No evaluation function registered for opaque operator {}", op.kind)
+        })?;
+        let outputs = (eval)(op, inputs)?;
+        ensure!(
+            outputs.len() == op.output_facts.len(),
+            "Opaque operator {} produced {} outputs, expected {}",
+            op.kind,
+            outputs.len(),
+            op.output_facts.len()
+        );
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let op = OpaqueOp {
+            kind: "Scale".to_string(),
+            attributes: vec![("factor".to_string(), rctensor0(2f32))],
+            output_facts: tvec!(f32::fact(&[3])),
+        };
+        let scale = model.wire_node("scale", op, &[source])?;
+        model.set_output_outlets(&scale)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn registered_eval() -> TractResult<()> {
+        let plan = SimplePlan::new(model()?.into_optimized()?)?;
+        let mut state = SimpleState::new(&plan)?;
+        state.register_opaque_eval("Scale", |op, inputs| {
+            let factor = *op.attribute("factor").unwrap().to_scalar::<f32>()?;
+            let output = inputs[0].to_array_view::<f32>()?.mapv(|x| x * factor);
+            Ok(tvec!(output.into_arc_tensor()))
+        });
+        let output = state.run(tvec!(tensor1(&[1f32, 2.0, 3.0])))?;
+        assert_eq!(*output[0], tensor1(&[2f32, 4.0, 6.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_eval() -> TractResult<()> {
+        let output = model()?.into_runnable()?.run(tvec!(tensor1(&[1f32, 2.0, 3.0])));
+        assert!(output.is_err());
+        Ok(())
+    }
+}
@@ -124,7 +124,7 @@ impl EvalOp for DequantizeLinearF32 {
 
 impl TypedOp for DequantizeLinearF32 {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        let mut fact = inputs[0].clone();
+        let mut fact = inputs[0].clone().with_range(None);
         fact.datum_type = f32::datum_type();
         Ok(tvec!(fact))
     }
@@ -279,6 +279,10 @@ impl crate::ops::binary::BinMiniOp for Scale {
         "Scale"
     }
 
+    fn same_as(&self, other: &dyn crate::ops::binary::BinMiniOp) -> bool {
+        other.downcast_ref::<Self>().is_some()
+    }
+
     fn result_datum_type(&self, a: DatumType, b: DatumType) -> TractResult<DatumType> {
         if a != f32::datum_type() {
             bail!("Scale left operand must be f32, got {:?}", a);
@@ -0,0 +1,238 @@
+use crate::internal::*;
+use crate::ops::binary::{TypedBinOp, UnaryOp};
+use crate::ops::element_wise::{ElementWiseMiniOp, ElementWiseOp};
+use crate::ops::math::{Add, Cube, Erf, Mul, Tanh};
+use crate::ops::nn::{Gelu, GeluTanh, Sigmoid, Silu, GELU_TANH_CUBE, GELU_TANH_SCALE};
+use crate::optim::OptimizerSession;
+
+/// Recognizes the decomposed activations of exporters without fused
+/// operators, as they look like once decluttered, and replaces them by a
+/// single element-wise op:
+///
+/// ```text
+/// silu:      x * sigmoid(x)
+/// gelu:      x * 0.5 * (1 + erf(x * (1 / sqrt(2))))
+/// gelu_tanh: x * 0.5 * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * cube(x))))
+/// ```
+///
+/// The multiplication by 0.5 may be applied to any of the operands.
+#[derive(Clone, Debug)]
+pub struct FuseActivations;
+
+impl super::TypedPass for FuseActivations {
+    fn reset(&mut self) -> TractResult<()> {
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        _session: &mut OptimizerSession,
+        model: &TypedModel,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        for n in model.eval_order()? {
+            let node = model.node(n);
+            if model.outlet_fact(node.id.into())?.datum_type != f32::datum_type() {
+                continue;
+            }
+            if let Some((input, op)) = silu(model, node).or_else(|| gelu(model, node)) {
+                let mut patch = TypedModelPatch::default();
+                let wire = patch.tap_model(model, input)?;
+                let wire = patch.wire_node(&node.name, ElementWiseOp(op), &[wire])?;
+                patch.shunt_outside(model, node.id.into(), wire[0])?;
+                return Ok(Some(patch));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn silu(model: &TypedModel, node: &TypedNode) -> Option<(OutletId, Box<dyn ElementWiseMiniOp>)> {
+    let (a, b) = bin_mul_inputs(node)?;
+    for (x, sigmoid) in [(a, b), (b, a)] {
+        let sigmoid = model.node(sigmoid.node);
+        if element_wise_is::<Sigmoid>(sigmoid) && sigmoid.inputs[0] == x {
+            return Some((x, Box::new(Silu {})));
+        }
+    }
+    None
+}
+
+fn gelu(model: &TypedModel, node: &TypedNode) -> Option<(OutletId, Box<dyn ElementWiseMiniOp>)> {
+    if let Some((a, b)) = bin_mul_inputs(node) {
+        for (p, q) in [(a, b), (b, a)] {
+            // (x * 0.5) * (1 + f(x))
+            if let Some(x) = scaled(model, p, 0.5) {
+                if let Some((found, op)) = one_plus_gelu(model, q) {
+                    if found == x {
+                        return Some((x, op));
+                    }
+                }
+            }
+            // x * (0.5 * (1 + f(x)))
+            if let Some(q) = scaled(model, q, 0.5) {
+                if let Some((found, op)) = one_plus_gelu(model, q) {
+                    if found == p {
+                        return Some((p, op));
+                    }
+                }
+            }
+        }
+    }
+    // (x * (1 + f(x))) * 0.5
+    let product = scaled(model, node.id.into(), 0.5)?;
+    let (a, b) = bin_mul_inputs(model.node(product.node))?;
+    [(a, b), (b, a)]
+        .iter()
+        .find_map(|&(x, q)| one_plus_gelu(model, q).filter(|(found, _)| *found == x))
+}
+
+/// Matches 1 + erf(x / sqrt(2)) and its tanh approximation.
+fn one_plus_gelu(
+    model: &TypedModel,
+    outlet: OutletId,
+) -> Option<(OutletId, Box<dyn ElementWiseMiniOp>)> {
+    let node = model.node(outlet.node);
+    let add = node.op_as::<UnaryOp>().filter(|op| op.mini_op.is::<Add>())?;
+    if !is_uniform(&add.a, 1.0) {
+        return None;
+    }
+    let f = model.node(node.inputs[0].node);
+    if element_wise_is::<Erf>(f) {
+        let x = scaled(model, f.inputs[0], std::f32::consts::FRAC_1_SQRT_2)?;
+        return Some((x, Box::new(Gelu {})));
+    }
+    if element_wise_is::<Tanh>(f) {
+        let sum = scaled(model, f.inputs[0], GELU_TANH_SCALE)?;
+        let sum = model.node(sum.node);
+        if !sum.op_as::<TypedBinOp>().map(|op| op.0.is::<Add>()).unwrap_or(false) {
+            return None;
+        }
+        for (x, cubed) in [(sum.inputs[0], sum.inputs[1]), (sum.inputs[1], sum.inputs[0])] {
+            if let Some(cube) = scaled(model, cubed, GELU_TANH_CUBE) {
+                let cube = model.node(cube.node);
+                if element_wise_is::<Cube>(cube) && cube.inputs[0] == x {
+                    return Some((x, Box::new(GeluTanh {})));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn bin_mul_inputs(node: &TypedNode) -> Option<(OutletId, OutletId)> {
+    let op = node.op_as::<TypedBinOp>()?;
+    if op.0.is::<Mul>() {
+        Some((node.inputs[0], node.inputs[1]))
+    } else {
+        None
+    }
+}
+
+/// Input of the multiplication by `factor` computed at outlet.
+fn scaled(model: &TypedModel, outlet: OutletId, factor: f32) -> Option<OutletId> {
+    let node = model.node(outlet.node);
+    let mul = node.op_as::<UnaryOp>().filter(|op| op.mini_op.is::<Mul>())?;
+    if is_uniform(&mul.a, factor) {
+        Some(node.inputs[0])
+    } else {
+        None
+    }
+}
+
+fn is_uniform(t: &Tensor, value: f32) -> bool {
+    t.as_uniform()
+        .and_then(|t| t.cast_to_scalar::<f32>().ok())
+        .map(|x| (x - value).abs() <= value.abs() * 1e-4)
+        .unwrap_or(false)
+}
+
+fn element_wise_is<T: ElementWiseMiniOp>(node: &TypedNode) -> bool {
+    node.op_as::<ElementWiseOp>().map(|op| op.0.is::<T>()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+    use crate::ops::nn::sigmoid;
+
+    fn konst(model: &mut TypedModel, name: &str, value: f32) -> TractResult<OutletId> {
+        model.add_const(name, tensor0(value).broadcast_into_rank(2)?)
+    }
+
+    fn check_fused<T: ElementWiseMiniOp>(model: TypedModel) -> TractResult<()> {
+        let input: Vec<f32> = (0..12).map(|i| i as f32 / 2.0 - 3.0).collect();
+        let input = tensor1(&input).into_shape(&[3, 4])?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let decluttered = model.into_decluttered()?;
+        assert_eq!(decluttered.nodes().len(), 2);
+        assert!(element_wise_is::<T>(decluttered.node(decluttered.output_outlets()?[0].node)));
+        let found = decluttered.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    #[test]
+    fn fuse_silu() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[3, 4]))?;
+        let s = model.wire_node("sigmoid", sigmoid(), &[x])?;
+        let y = model.wire_node("y", math::mul::bin_typed(), &[s[0], x])?;
+        model.set_output_outlets(&y)?;
+        check_fused::<Silu>(model)
+    }
+
+    #[test]
+    fn fuse_gelu() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[3, 4]))?;
+        let sqrt2 = konst(&mut model, "sqrt2", 1.414_213_5)?;
+        let one = konst(&mut model, "one", 1.0)?;
+        let half = konst(&mut model, "half", 0.5)?;
+        let w = model.wire_node("div", math::div::bin_typed(), &[x, sqrt2])?;
+        let w = model.wire_node("erf", math::erf(), &w)?;
+        let w = model.wire_node("add", math::add::bin_typed(), &[w[0], one])?;
+        let w = model.wire_node("mul", math::mul::bin_typed(), &[x, w[0]])?;
+        let y = model.wire_node("y", math::mul::bin_typed(), &[w[0], half])?;
+        model.set_output_outlets(&y)?;
+        check_fused::<Gelu>(model)
+    }
+
+    #[test]
+    fn fuse_gelu_tanh() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[3, 4]))?;
+        let three = konst(&mut model, "three", 3.0)?;
+        let cube = konst(&mut model, "cube", 0.044715)?;
+        let scale = konst(&mut model, "scale", (2.0 / std::f32::consts::PI).sqrt())?;
+        let one = konst(&mut model, "one", 1.0)?;
+        let half = konst(&mut model, "half", 0.5)?;
+        let w = model.wire_node("pow", math::pow::bin_typed(), &[x, three])?;
+        let w = model.wire_node("pow.mul", math::mul::bin_typed(), &[cube, w[0]])?;
+        let w = model.wire_node("sum", math::add::bin_typed(), &[x, w[0]])?;
+        let w = model.wire_node("sum.scale", math::mul::bin_typed(), &[w[0], scale])?;
+        let w = model.wire_node("tanh", math::tanh(), &w)?;
+        let w = model.wire_node("add", math::add::bin_typed(), &[one, w[0]])?;
+        let h = model.wire_node("x.half", math::mul::bin_typed(), &[half, x])?;
+        let y = model.wire_node("y", math::mul::bin_typed(), &[h[0], w[0]])?;
+        model.set_output_outlets(&y)?;
+        check_fused::<GeluTanh>(model)
+    }
+
+    #[test]
+    fn keep_other_scales() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[3, 4]))?;
+        let three = konst(&mut model, "three", 3.0)?;
+        let one = konst(&mut model, "one", 1.0)?;
+        let half = konst(&mut model, "half", 0.5)?;
+        let w = model.wire_node("div", math::div::bin_typed(), &[x, three])?;
+        let w = model.wire_node("erf", math::erf(), &w)?;
+        let w = model.wire_node("add", math::add::bin_typed(), &[w[0], one])?;
+        let w = model.wire_node("mul", math::mul::bin_typed(), &[x, w[0]])?;
+        let y = model.wire_node("y", math::mul::bin_typed(), &[w[0], half])?;
+        model.set_output_outlets(&y)?;
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered.nodes().iter().any(|n| element_wise_is::<Erf>(n)));
+        Ok(())
+    }
+}
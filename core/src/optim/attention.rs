@@ -0,0 +1,229 @@
+use crate::internal::*;
+use crate::ops::binary::{TypedBinOp, UnaryOp};
+use crate::ops::math::{Add, Mul};
+use crate::ops::matmul::MatMul;
+use crate::ops::nn::{ScaledDotProductAttention, Softmax};
+use crate::optim::OptimizerSession;
+
+/// Recognizes attention computed with matrix products and a softmax, as it
+/// looks like once decluttered:
+///
+/// ```text
+/// scores = matmul(q, k^T) * scale + mask
+/// out = matmul(softmax(scores, last axis), v)
+/// ```
+///
+/// where the scale and the mask are optional, and replaces it by a single
+/// `ScaledDotProductAttention`.
+#[derive(Clone, Debug)]
+pub struct FuseAttention;
+
+impl super::TypedPass for FuseAttention {
+    fn reset(&mut self) -> TractResult<()> {
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        _session: &mut OptimizerSession,
+        model: &TypedModel,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        for n in model.eval_order()? {
+            let node = model.node(n);
+            if let Some(patch) = fuse(model, node)? {
+                return Ok(Some(patch));
+            }
+        }
+        Ok(None)
+    }
+}
+
+enum Mask {
+    Input(OutletId),
+    Const(Arc<Tensor>),
+}
+
+fn fuse(model: &TypedModel, node: &TypedNode) -> TractResult<Option<TypedModelPatch>> {
+    if !node.op_as::<MatMul>().map(|op| !op.a_trans && !op.b_trans && !op.c_trans).unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    let (probs, v) = (node.inputs[0], node.inputs[1]);
+    let softmax = model.node(probs.node);
+    let rank = model.outlet_fact(probs)?.rank();
+    if !softmax.op_as::<Softmax>().map(|op| *op.axes == [rank - 1]).unwrap_or(false) {
+        return Ok(None);
+    }
+    let scores = softmax.inputs[0];
+    let (scores, mask) = if let Some((scores, mask)) = masked(model, scores) {
+        (scores, Some(mask))
+    } else {
+        (scores, None)
+    };
+    let (scores, scale) = scaled(model, scores)?;
+    let qk = model.node(scores.node);
+    let k_trans = match qk.op_as::<MatMul>() {
+        Some(op) if !op.a_trans && !op.c_trans => !op.b_trans,
+        _ => return Ok(None),
+    };
+    let mut inputs: TVec<OutletId> = tvec!(qk.inputs[0], qk.inputs[1], v);
+    if inputs
+        .iter()
+        .any(|i| model.outlet_fact(*i).map(|f| f.datum_type != f32::datum_type()).unwrap_or(true))
+    {
+        return Ok(None);
+    }
+    if inputs.iter().any(|i| model.outlet_fact(*i).map(|f| f.rank() != rank).unwrap_or(true)) {
+        return Ok(None);
+    }
+    let op = ScaledDotProductAttention::new(scale, k_trans);
+    // MatMul broadcasts its batch axes, and the mask may broadcast the
+    // scores: only fuse if the attention op computes the same shape
+    let mask_fact = match &mask {
+        Some(Mask::Input(mask)) => Some(model.outlet_fact(*mask)?.clone()),
+        Some(Mask::Const(mask)) => Some(TypedFact::from(mask.clone())),
+        None => None,
+    };
+    let mut facts =
+        inputs.iter().map(|i| model.outlet_fact(*i)).collect::<TractResult<TVec<_>>>()?;
+    facts.extend(mask_fact.as_ref());
+    match op.output_facts(&facts) {
+        Ok(output) if output[0].shape == model.outlet_fact(node.id.into())?.shape => (),
+        _ => return Ok(None),
+    }
+    let mut patch = TypedModelPatch::default();
+    for input in &mut inputs {
+        *input = patch.tap_model(model, *input)?;
+    }
+    match mask {
+        Some(Mask::Input(mask)) => inputs.push(patch.tap_model(model, mask)?),
+        Some(Mask::Const(mask)) => {
+            inputs.push(patch.add_const(format!("{}.mask", node.name), mask)?)
+        }
+        None => (),
+    }
+    let wire = patch.wire_node(&node.name, op, &inputs)?;
+    patch.shunt_outside(model, node.id.into(), wire[0])?;
+    Ok(Some(patch))
+}
+
+/// Scores and mask of a masked score.
+fn masked(model: &TypedModel, outlet: OutletId) -> Option<(OutletId, Mask)> {
+    let node = model.node(outlet.node);
+    if let Some(op) = node.op_as::<UnaryOp>() {
+        if op.mini_op.is::<Add>() {
+            return Some((node.inputs[0], Mask::Const(op.a.clone())));
+        }
+    }
+    if node.op_as::<TypedBinOp>().map(|op| op.0.is::<Add>()).unwrap_or(false) {
+        for (scores, mask) in [(node.inputs[0], node.inputs[1]), (node.inputs[1], node.inputs[0])] {
+            if model.node(scaled(model, scores).ok()?.0.node).op_is::<MatMul>() {
+                return Some((scores, Mask::Input(mask)));
+            }
+        }
+    }
+    None
+}
+
+/// Unscaled scores and scale of a scaled score.
+fn scaled(model: &TypedModel, outlet: OutletId) -> TractResult<(OutletId, f32)> {
+    let node = model.node(outlet.node);
+    if let Some(op) = node.op_as::<UnaryOp>() {
+        if op.mini_op.is::<Mul>() {
+            if let Some(scale) = op.a.as_uniform() {
+                return Ok((node.inputs[0], scale.cast_to_scalar::<f32>()?));
+            }
+        }
+    }
+    Ok((outlet, 1.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn decomposed(k_trans: bool) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let q = model.add_source("q", f32::fact(&[2, 5, 8]))?;
+        let k_shape: &[usize] = if k_trans { &[2, 8, 9] } else { &[2, 9, 8] };
+        let k = model.add_source("k", f32::fact(k_shape))?;
+        let v = model.add_source("v", f32::fact(&[2, 9, 3]))?;
+        let qk = MatMul::default().with_b_trans(!k_trans);
+        let s = model.wire_node("qk", qk, &[q, k])?;
+        let sqrt_d = model.add_const("sqrt_d", rctensor3(&[[[8f32.sqrt()]]]))?;
+        let s = model.wire_node("scale", math::div::bin_typed(), &[s[0], sqrt_d])?;
+        let mask: Vec<f32> =
+            (0..45).map(|i| if i % 9 > i / 9 + 4 { f32::NEG_INFINITY } else { 0.0 }).collect();
+        let mask = model.add_const("mask", tensor1(&mask).into_shape(&[1, 5, 9])?)?;
+        let s = model.wire_node("masked", math::add::bin_typed(), &[s[0], mask])?;
+        let p = model.wire_node("softmax", Softmax::new(tvec!(2), f32::datum_type()), &s)?;
+        let o = model.wire_node("pv", MatMul::default(), &[p[0], v])?;
+        model.set_output_outlets(&o)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn fuse_attention() -> TractResult<()> {
+        for &k_trans in &[false, true] {
+            let model = decomposed(k_trans)?;
+            let inputs: TVec<Tensor> = model
+                .input_outlets()?
+                .iter()
+                .enumerate()
+                .map(|(ix, input)| {
+                    let shape = model.outlet_fact(*input)?.shape.as_concrete().unwrap().to_vec();
+                    let len = shape.iter().product();
+                    let data: Vec<f32> =
+                        (0..len).map(|i| (((i + ix) * 5) % 9) as f32 / 4.0 - 1.0).collect();
+                    tensor1(&data).into_shape(&shape)
+                })
+                .collect::<TractResult<_>>()?;
+            let expected = model.clone().into_runnable()?.run(inputs.clone())?;
+            let decluttered = model.into_decluttered()?;
+            let op = decluttered
+                .nodes()
+                .iter()
+                .find_map(|n| n.op_as::<ScaledDotProductAttention>())
+                .unwrap();
+            assert_eq!(op.k_trans, k_trans);
+            assert!((op.scale - 8f32.sqrt().recip()).abs() < 1e-6);
+            assert!(decluttered.nodes().iter().all(|n| !n.op_is::<Softmax>()));
+            let found = decluttered.into_runnable()?.run(inputs)?;
+            found[0].close_enough(&expected[0], true)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn broadcast_batch_axes() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let q = model.add_source("q", f32::fact(&[2, 1, 5, 8]))?;
+        let k = model.add_source("k", f32::fact(&[1, 1, 9, 8]))?;
+        let v = model.add_source("v", f32::fact(&[1, 1, 9, 3]))?;
+        let mask = model.add_source("mask", f32::fact(&[1, 3, 5, 9]))?;
+        let s = model.wire_node("qk", MatMul::default().with_b_trans(true), &[q, k])?;
+        let s = model.wire_node("masked", math::add::bin_typed(), &[s[0], mask])?;
+        let p = model.wire_node("softmax", Softmax::new(tvec!(3), f32::datum_type()), &s)?;
+        let o = model.wire_node("pv", MatMul::default(), &[p[0], v])?;
+        model.set_output_outlets(&o)?;
+        let inputs: TVec<Tensor> = model
+            .input_outlets()?
+            .iter()
+            .enumerate()
+            .map(|(ix, input)| {
+                let shape = model.outlet_fact(*input)?.shape.as_concrete().unwrap().to_vec();
+                let len = shape.iter().product();
+                let data: Vec<f32> =
+                    (0..len).map(|i| (((i + ix) * 5) % 9) as f32 / 4.0 - 1.0).collect();
+                tensor1(&data).into_shape(&shape)
+            })
+            .collect::<TractResult<_>>()?;
+        let expected = model.clone().into_runnable()?.run(inputs.clone())?;
+        assert_eq!(expected[0].shape(), &[2, 3, 5, 3]);
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered.nodes().iter().any(|n| n.op_is::<ScaledDotProductAttention>()));
+        let found = decluttered.into_runnable()?.run(inputs)?;
+        found[0].close_enough(&expected[0], true)
+    }
+}
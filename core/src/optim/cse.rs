@@ -0,0 +1,104 @@
+use crate::internal::*;
+use crate::ops::konst::Const;
+use crate::ops::source::TypedSource;
+use crate::optim::OptimizerSession;
+use std::hash::Hasher;
+
+/// Common subexpression elimination: merges the stateless nodes with the
+/// same operator applied to the same inputs. Chains of duplicates are merged
+/// one node per pass run.
+///
+/// Constants are left alone: equal weights are still distinct named
+/// tensors, which `TypedModel::bind_weights` must be able to bind separately.
+#[derive(Clone, Debug)]
+pub struct Cse;
+
+impl super::TypedPass for Cse {
+    fn reset(&mut self) -> TractResult<()> {
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        _session: &mut OptimizerSession,
+        model: &TypedModel,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let mut patch = TypedModelPatch::default();
+        let outputs = model.output_outlets()?;
+        let mut seen: HashMap<u64, TVec<usize>> = HashMap::default();
+        for n in model.eval_order()? {
+            let node = model.node(n);
+            if node.op_is::<TypedSource>() || node.op_is::<Const>() || !node.op.is_stateless() {
+                continue;
+            }
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            node.op.hash(&mut hasher);
+            node.inputs.hash(&mut hasher);
+            let candidates = seen.entry(hasher.finish()).or_default();
+            let twin = candidates.iter().copied().find(|&c| model.node(c).same_as(node));
+            match twin {
+                Some(twin) if !outputs.iter().any(|o| o.node == n) => {
+                    for slot in 0..node.outputs.len() {
+                        let tap = patch.tap_model(model, OutletId::new(twin, slot))?;
+                        patch.shunt_outside(model, OutletId::new(n, slot), tap)?;
+                    }
+                    patch.obliterate(n)?;
+                }
+                Some(_) => (),
+                None => candidates.push(n),
+            }
+        }
+        Ok(Some(patch).filter(|p| !p.is_empty()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::array::Slice;
+    use crate::ops::cast::cast;
+    use crate::ops::math;
+
+    #[test]
+    fn merge_duplicated_branches() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[4]))?;
+        let mut branches = tvec!();
+        for b in 0..2 {
+            let s = model.wire_node(format!("slice.{}", b), Slice::new(0, 1, 3), &[x])?;
+            let c = model.wire_node(format!("cast.{}", b), cast(f32::datum_type()), &s)?;
+            let one = model.add_const(format!("one.{}", b), rctensor1(&[1f32]))?;
+            let y = model.wire_node(format!("add.{}", b), math::add::bin_typed(), &[c[0], one])?;
+            branches.push(y[0]);
+        }
+        let sum = model.wire_node("sum", math::mul::bin_typed(), &branches)?;
+        model.set_output_outlets(&sum)?;
+        let optimizer = crate::optim::Optimizer::new(vec![Box::new(Cse)]);
+        optimizer.optimize(&mut model)?;
+        assert_eq!(model.nodes().len(), 8);
+        assert!(model.node_by_name("slice.1").is_err());
+        assert!(model.node_by_name("one.0").is_ok());
+        assert!(model.node_by_name("one.1").is_ok());
+        let output = model.into_runnable()?.run(tvec!(tensor1(&[0f32, 1.0, 2.0, 3.0])))?;
+        assert_eq!(*output[0], tensor1(&[4f32, 9.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn keep_different_constants() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[2]))?;
+        let a = model.wire_node("a", math::add::unary(rctensor1(&[1f32, 2.0])), &[x])?;
+        let b = model.wire_node("b", math::add::unary(rctensor1(&[1f32, 3.0])), &[x])?;
+        let c = model.wire_node("c", math::add::unary(rctensor1(&[1f32, 2.0])), &[x])?;
+        let ab = model.wire_node("ab", math::mul::bin_typed(), &[a[0], b[0]])?;
+        let abc = model.wire_node("abc", math::mul::bin_typed(), &[ab[0], c[0]])?;
+        model.set_output_outlets(&abc)?;
+        let optimizer = crate::optim::Optimizer::new(vec![Box::new(Cse)]);
+        optimizer.optimize(&mut model)?;
+        assert!(model.node_by_name("a").is_ok());
+        assert!(model.node_by_name("b").is_ok());
+        assert!(model.node_by_name("c").is_err());
+        Ok(())
+    }
+}
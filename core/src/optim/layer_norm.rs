@@ -0,0 +1,180 @@
+use crate::internal::*;
+use crate::ops::binary::{TypedBinOp, UnaryOp};
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::math::{Add, Mul, Rsqrt, Square, Sub};
+use crate::ops::nn::{LayerNorm, Reduce, Reducer};
+use crate::optim::OptimizerSession;
+
+/// Recognizes the decomposed layer norm of exporters without a fused
+/// operator, as it looks like once decluttered:
+///
+/// ```text
+/// mean = sum(x, axes) * (1 / n)
+/// d = x - mean
+/// y = d * rsqrt(sum(square(d), axes) * (1 / n) + epsilon)
+/// ```
+///
+/// and replaces it by a single `LayerNorm`.
+#[derive(Clone, Debug)]
+pub struct FuseLayerNorm;
+
+impl super::TypedPass for FuseLayerNorm {
+    fn reset(&mut self) -> TractResult<()> {
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        _session: &mut OptimizerSession,
+        model: &TypedModel,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        for n in model.eval_order()? {
+            let node = model.node(n);
+            if let Some((input, op)) = layer_norm(model, node)? {
+                let mut patch = TypedModelPatch::default();
+                let wire = patch.tap_model(model, input)?;
+                let wire = patch.wire_node(&node.name, op, &[wire])?;
+                patch.shunt_outside(model, node.id.into(), wire[0])?;
+                return Ok(Some(patch));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Input and op of the layer norm computed by node, if any.
+fn layer_norm(model: &TypedModel, node: &TypedNode) -> TractResult<Option<(OutletId, LayerNorm)>> {
+    if !node.op_as::<TypedBinOp>().map(|op| op.0.is::<Mul>()).unwrap_or(false) {
+        return Ok(None);
+    }
+    for (d, r) in [(node.inputs[0], node.inputs[1]), (node.inputs[1], node.inputs[0])] {
+        let d_node = model.node(d.node);
+        if !d_node.op_as::<TypedBinOp>().map(|op| op.0.is::<Sub>()).unwrap_or(false) {
+            continue;
+        }
+        let (x, mean) = (d_node.inputs[0], d_node.inputs[1]);
+        let axes = if let Some(axes) = mean_axes(model, mean, x)? { axes } else { continue };
+        let rsqrt = model.node(r.node);
+        if !element_wise_is::<Rsqrt>(rsqrt) {
+            continue;
+        }
+        let add_eps = model.node(rsqrt.inputs[0].node);
+        let epsilon = add_eps
+            .op_as::<UnaryOp>()
+            .filter(|op| op.mini_op.is::<Add>())
+            .and_then(|op| op.a.as_uniform())
+            .map(|eps| eps.cast_to_scalar::<f32>())
+            .transpose()?;
+        let epsilon = if let Some(epsilon) = epsilon { epsilon } else { continue };
+        let var = add_eps.inputs[0];
+        let square = if let Some(square) = mean_input(model, var)? { square } else { continue };
+        let square = model.node(square.node);
+        if !element_wise_is::<Square>(square) || square.inputs[0] != d {
+            continue;
+        }
+        if mean_axes(model, var, square.id.into())? != Some(axes.clone()) {
+            continue;
+        }
+        return Ok(Some((x, LayerNorm::new(axes, epsilon))));
+    }
+    Ok(None)
+}
+
+fn element_wise_is<T: crate::ops::element_wise::ElementWiseMiniOp>(node: &TypedNode) -> bool {
+    node.op_as::<ElementWiseOp>().map(|op| op.0.is::<T>()).unwrap_or(false)
+}
+
+/// The input of a mean (`sum(x, axes) * (1 / n)`) computed at outlet.
+fn mean_input(model: &TypedModel, outlet: OutletId) -> TractResult<Option<OutletId>> {
+    let mul = model.node(outlet.node);
+    if !mul.op_as::<UnaryOp>().map(|op| op.mini_op.is::<Mul>()).unwrap_or(false) {
+        return Ok(None);
+    }
+    let sum = model.node(mul.inputs[0].node);
+    if sum.op_as::<Reduce>().map(|op| op.reducer == Reducer::Sum).unwrap_or(false) {
+        Ok(Some(sum.inputs[0]))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Axes of the mean of `x` computed at outlet, if it is one.
+fn mean_axes(
+    model: &TypedModel,
+    outlet: OutletId,
+    x: OutletId,
+) -> TractResult<Option<TVec<usize>>> {
+    if mean_input(model, outlet)? != Some(x) {
+        return Ok(None);
+    }
+    let mul = model.node(outlet.node);
+    let reduce = model.node(mul.inputs[0].node).op_as::<Reduce>().unwrap();
+    let shape = &model.outlet_fact(x)?.shape;
+    let n = reduce.axes.iter().map(|&axis| shape[axis].to_usize()).product::<TractResult<usize>>();
+    let n = if let Ok(n) = n { n } else { return Ok(None) };
+    let factor = mul.op_as::<UnaryOp>().unwrap().a.as_uniform();
+    let factor =
+        if let Some(factor) = factor { factor.cast_to_scalar::<f32>()? } else { return Ok(None) };
+    if (factor * n as f32 - 1.0).abs() > 1e-5 {
+        return Ok(None);
+    }
+    Ok(Some(reduce.axes.clone()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    fn decomposed(model: &mut TypedModel, x: OutletId, epsilon: f32) -> TractResult<OutletId> {
+        let n = model.outlet_fact(x)?.shape[2].clone();
+        let n = model.add_const("n", tensor0(n.to_usize()? as f32).broadcast_into_rank(3)?)?;
+        let mean = model.wire_node("mean.sum", Reduce::new(tvec!(2), Reducer::Sum), &[x])?;
+        let mean = model.wire_node("mean", math::div::bin_typed(), &[mean[0], n])?;
+        let d = model.wire_node("d", math::sub::bin_typed(), &[x, mean[0]])?;
+        let two = model.add_const("two", tensor0(2f32).broadcast_into_rank(3)?)?;
+        let sq = model.wire_node("sq", math::pow::bin_typed(), &[d[0], two])?;
+        let var = model.wire_node("var.sum", Reduce::new(tvec!(2), Reducer::Sum), &sq)?;
+        let var = model.wire_node("var", math::div::bin_typed(), &[var[0], n])?;
+        let eps = model.add_const("eps", tensor0(epsilon).broadcast_into_rank(3)?)?;
+        let var = model.wire_node("var.eps", math::add::bin_typed(), &[var[0], eps])?;
+        let std = model.wire_node("std", math::sqrt(), &var)?;
+        let y = model.wire_node("y", math::div::bin_typed(), &[d[0], std[0]])?;
+        let gamma = model.add_const("gamma", rctensor3(&[[[1f32, 2.0, 3.0, 4.0]]]))?;
+        let y = model.wire_node("gamma.mul", math::mul::bin_typed(), &[y[0], gamma])?;
+        Ok(y[0])
+    }
+
+    #[test]
+    fn fuse_layer_norm() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[2, 3, 4]))?;
+        let y = decomposed(&mut model, x, 1e-5)?;
+        model.set_output_outlets(&[y])?;
+        let input: Vec<f32> = (0..24).map(|i| ((i * 7) % 11) as f32 / 3.0).collect();
+        let input = tensor1(&input).into_shape(&[2, 3, 4])?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let decluttered = model.into_decluttered()?;
+        let norm = decluttered.nodes().iter().find_map(|n| n.op_as::<LayerNorm>()).unwrap();
+        assert_eq!(norm, &LayerNorm::new(tvec!(2), 1e-5));
+        assert!(decluttered.nodes().iter().all(|n| !n.op_is::<Reduce>()));
+        let found = decluttered.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)?;
+        Ok(())
+    }
+
+    #[test]
+    fn keep_other_reductions() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[2, 3, 4]))?;
+        let y = decomposed(&mut model, x, 1e-5)?;
+        // the variance is divided by 3 instead of 4
+        let var = model.node_by_name("var")?.id;
+        let three = model.add_const("three", tensor0(3f32).broadcast_into_rank(3)?)?;
+        model.node_mut(var).inputs[1] = three;
+        model.set_output_outlets(&[y])?;
+        let decluttered = model.into_decluttered()?;
+        assert!(decluttered.nodes().iter().all(|n| !n.op_is::<LayerNorm>()));
+        Ok(())
+    }
+}
@@ -1,16 +1,27 @@
 use crate::internal::*;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::sync::Mutex;
 use tract_itertools::Itertools;
 
+mod activations;
+mod attention;
 pub mod change_axes;
+mod cse;
+mod layer_norm;
 mod op_optim;
 mod prop_const;
 mod push_split_down;
+mod rope;
 
+use self::activations::FuseActivations;
+use self::attention::FuseAttention;
 use self::change_axes::ChangeAxes;
+use self::cse::Cse;
+use self::layer_norm::FuseLayerNorm;
 use self::prop_const::PropConst;
 use self::push_split_down::PushSplitDown;
+use self::rope::FuseRotaryEmbedding;
 use op_optim::OpOptim;
 
 pub trait TypedPass: Debug + Send + Sync + dyn_clone::DynClone {
@@ -24,6 +35,35 @@ pub trait TypedPass: Debug + Send + Sync + dyn_clone::DynClone {
 
 dyn_clone::clone_trait_object!(TypedPass);
 
+/// Optimizer pipeline a registered pass is added to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Declutter,
+    Codegen,
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTERED_PASSES: Mutex<Vec<(Stage, Box<dyn TypedPass>)>> = Mutex::new(vec![]);
+}
+
+/// Add a pass to every `Optimizer::declutter()` or `Optimizer::codegen()`
+/// built from now on, after the built-in passes and the ones registered
+/// before. This is how `TypedModel::declutter`, `TypedModel::optimize` and
+/// `into_optimized` can be extended with custom fusions.
+pub fn register_pass(stage: Stage, pass: impl TypedPass + 'static) {
+    REGISTERED_PASSES.lock().unwrap().push((stage, Box::new(pass)));
+}
+
+pub fn registered_passes(stage: Stage) -> Vec<Box<dyn TypedPass>> {
+    REGISTERED_PASSES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(s, _)| *s == stage)
+        .map(|(_, p)| p.clone())
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct Optimizer {
     passes: Vec<Box<dyn TypedPass>>,
@@ -31,7 +71,9 @@ pub struct Optimizer {
 }
 
 impl Optimizer {
-    fn passes(passes: Vec<Box<dyn TypedPass>>) -> Optimizer {
+    /// An optimizer running `passes` in order, until none of them changes
+    /// the model.
+    pub fn new(passes: Vec<Box<dyn TypedPass>>) -> Optimizer {
         Optimizer { passes, steps: None }
     }
 
@@ -39,17 +81,57 @@ impl Optimizer {
         Optimizer { steps: Some(steps), ..self }
     }
 
+    /// Names of the passes, as they can be referred to by `with_pass_before`
+    /// and `without_pass`.
+    pub fn pass_names(&self) -> Vec<String> {
+        self.passes.iter().map(|p| format!("{:?}", p)).collect()
+    }
+
+    pub fn with_pass(mut self, pass: impl TypedPass + 'static) -> Optimizer {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn with_pass_before(
+        mut self,
+        name: &str,
+        pass: impl TypedPass + 'static,
+    ) -> TractResult<Optimizer> {
+        let ix = self.pass_index(name)?;
+        self.passes.insert(ix, Box::new(pass));
+        Ok(self)
+    }
+
+    pub fn without_pass(mut self, name: &str) -> TractResult<Optimizer> {
+        let ix = self.pass_index(name)?;
+        self.passes.remove(ix);
+        Ok(self)
+    }
+
+    fn pass_index(&self, name: &str) -> TractResult<usize> {
+        self.pass_names().iter().position(|n| n == name).ok_or_else(|| {
+            format_err!("No pass named {} in optimizer {:?}", name, self.pass_names())
+        })
+    }
+
     pub fn declutter() -> Optimizer {
-        Optimizer::passes(vec![
+        let mut passes: Vec<Box<dyn TypedPass>> = vec![
             Box::new(PropConst),
             Box::new(OpOptim("declutter", TypedOp::declutter_with_session, 0)),
             Box::new(PushSplitDown),
+            Box::new(Cse),
             Box::new(ChangeAxes),
-        ])
+            Box::new(FuseLayerNorm),
+            Box::new(FuseActivations),
+            Box::new(FuseAttention),
+            Box::new(FuseRotaryEmbedding),
+        ];
+        passes.extend(registered_passes(Stage::Declutter));
+        Optimizer::new(passes)
     }
 
     pub fn codegen() -> Optimizer {
-        Optimizer::passes(vec![
+        let mut passes: Vec<Box<dyn TypedPass>> = vec![
             Box::new(PropConst),
             Box::new(OpOptim(
                 "codegen",
@@ -63,7 +145,9 @@ impl Optimizer {
                 |op, _session, model, node| TypedOp::fuse(op, model, node),
                 0,
             )),
-        ])
+        ];
+        passes.extend(registered_passes(Stage::Codegen));
+        Optimizer::new(passes)
     }
 
     pub fn optimize(&self, model: &mut TypedModel) -> TractResult<()> {
@@ -159,3 +243,69 @@ impl<'o> OptimizerSession<'o> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    /// Moves the nodes named after the tests to a "done" name.
+    #[derive(Debug, Clone)]
+    struct Rename;
+
+    impl TypedPass for Rename {
+        fn reset(&mut self) -> TractResult<()> {
+            Ok(())
+        }
+
+        fn next(
+            &mut self,
+            _session: &mut OptimizerSession,
+            model: &TypedModel,
+        ) -> TractResult<Option<TypedModelPatch>> {
+            for &id in &model.eval_order()? {
+                let node = model.node(id);
+                if node.name.starts_with("registered_pass.") && !node.name.ends_with(".done") {
+                    let mut patch = TypedModelPatch::default();
+                    let x = patch.tap_model(model, node.inputs[0])?;
+                    let name = format!("{}.done", node.name);
+                    let y = patch.wire_node(name, node.op.clone(), &[x])?;
+                    patch.shunt_outside(model, node.id.into(), y[0])?;
+                    return Ok(Some(patch));
+                }
+            }
+            Ok(None)
+        }
+    }
+
+    fn model(name: &str) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[3]))?;
+        let y = model.wire_node(name, math::neg(), &[x])?;
+        model.set_output_outlets(&y)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn custom_pipeline() -> TractResult<()> {
+        let optimizer = Optimizer::declutter().with_pass_before("ChangeAxes", Rename)?;
+        assert_eq!(optimizer.pass_names()[4], "Rename");
+        let mut model = model("registered_pass.custom")?;
+        optimizer.optimize(&mut model)?;
+        assert!(model.node_by_name("registered_pass.custom.done").is_ok());
+        let output = model.into_runnable()?.run(tvec!(tensor1(&[1f32, 2.0, 3.0])))?;
+        assert_eq!(*output[0], tensor1(&[-1f32, -2.0, -3.0]));
+        assert!(Optimizer::declutter().without_pass("nope").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn registered_pass() -> TractResult<()> {
+        register_pass(Stage::Declutter, Rename);
+        assert!(Optimizer::declutter().pass_names().contains(&"Rename".to_string()));
+        assert!(!Optimizer::codegen().pass_names().contains(&"Rename".to_string()));
+        let model = model("registered_pass.global")?.into_decluttered()?;
+        assert!(model.node_by_name("registered_pass.global.done").is_ok());
+        Ok(())
+    }
+}
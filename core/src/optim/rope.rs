@@ -0,0 +1,260 @@
+use crate::internal::*;
+use crate::ops::array::{ConcatSlice, Slice, TypedConcat};
+use crate::ops::binary::{TypedBinOp, UnaryOp};
+use crate::ops::element_wise::ElementWiseOp;
+use crate::ops::math::{Add, Mul, Neg};
+use crate::ops::nn::{RopeLayout, RopeRotation, RotaryEmbedding};
+use crate::optim::OptimizerSession;
+
+/// Recognizes rotary position embeddings as exported from Llama or GPT-NeoX
+/// style models, once decluttered:
+///
+/// ```text
+/// rotate_half(x) = concat(-x[.., h..], x[.., ..h])
+/// rope(x) = x * cos + rotate_half(x) * sin
+/// ```
+///
+/// and their partial variant, concat(rope(x[.., ..r]), x[.., r..]) with r
+/// the half or the quarter of the last axis, and replaces them by a single
+/// `RotaryEmbedding`.
+#[derive(Clone, Debug)]
+pub struct FuseRotaryEmbedding;
+
+impl super::TypedPass for FuseRotaryEmbedding {
+    fn reset(&mut self) -> TractResult<()> {
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        _session: &mut OptimizerSession,
+        model: &TypedModel,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        for n in model.eval_order()? {
+            let node = model.node(n);
+            if let Some(patch) = fuse(model, node)? {
+                return Ok(Some(patch));
+            }
+        }
+        Ok(None)
+    }
+}
+
+enum Table {
+    Input(OutletId),
+    Const(Arc<Tensor>),
+}
+
+fn fuse(model: &TypedModel, node: &TypedNode) -> TractResult<Option<TypedModelPatch>> {
+    if !node.op_as::<TypedBinOp>().map(|op| op.0.is::<Add>()).unwrap_or(false)
+        || !model.outlet_fact(node.id.into())?.datum_type.is_float()
+    {
+        return Ok(None);
+    }
+    let (x, cos, sin) = if let Some(found) = rope(model, node) {
+        found
+    } else {
+        return Ok(None);
+    };
+    let (replaced, input, rotation) =
+        if let Some((concat, input, rotation)) = partial(model, node, x)? {
+            (concat, input, rotation)
+        } else {
+            (node, x, RopeRotation::Full)
+        };
+    let mut patch = TypedModelPatch::default();
+    let mut inputs = tvec!(patch.tap_model(model, input)?);
+    for (table, name) in [(cos, "cos"), (sin, "sin")] {
+        inputs.push(match table {
+            Table::Input(outlet) => patch.tap_model(model, outlet)?,
+            Table::Const(t) => patch.add_const(format!("{}.{}", replaced.name, name), t)?,
+        });
+    }
+    let op = RotaryEmbedding::new(rotation, RopeLayout::Halves);
+    let wire = patch.wire_node(&replaced.name, op, &inputs)?;
+    patch.shunt_outside(model, replaced.id.into(), wire[0])?;
+    Ok(Some(patch))
+}
+
+/// Input, cos and sin of the rotary embedding computed by the Add node.
+fn rope(model: &TypedModel, node: &TypedNode) -> Option<(OutletId, Table, Table)> {
+    for (p, q) in [(node.inputs[0], node.inputs[1]), (node.inputs[1], node.inputs[0])] {
+        for (x, cos) in factors(model, p) {
+            for (rotated, sin) in factors(model, q) {
+                if rotate_half(model, rotated) == Some(x) {
+                    return Some((x, cos, sin));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Concat node, input and rotation of a partial rotary embedding of x.
+fn partial<'m>(
+    model: &'m TypedModel,
+    node: &TypedNode,
+    x: OutletId,
+) -> TractResult<Option<(&'m TypedNode, OutletId, RopeRotation)>> {
+    let (input, rotated) = match slice_of(model, x)? {
+        Some((input, slice)) if slice.start == 0.to_dim() => (input, slice.end.clone()),
+        _ => return Ok(None),
+    };
+    let succ = &node.outputs[0].successors;
+    if succ.len() != 1 || !is_last_axis_concat(model, model.node(succ[0].node))? {
+        return Ok(None);
+    }
+    let concat = model.node(succ[0].node);
+    if concat.inputs[0] != node.id.into() {
+        return Ok(None);
+    }
+    let dim = model.outlet_fact(input)?.shape.last().unwrap().clone();
+    match slice_of(model, concat.inputs[1])? {
+        Some((pass, slice)) if pass == input && slice.start == rotated && slice.end == dim => (),
+        _ => return Ok(None),
+    }
+    let rotation = if rotated.clone() * 2 == dim {
+        RopeRotation::Half
+    } else if rotated * 4 == dim {
+        RopeRotation::Quarter
+    } else {
+        return Ok(None);
+    };
+    Ok(Some((concat, input, rotation)))
+}
+
+/// Factorizations (operand, factor) of the product computed at outlet.
+fn factors(model: &TypedModel, outlet: OutletId) -> TVec<(OutletId, Table)> {
+    let node = model.node(outlet.node);
+    if let Some(op) = node.op_as::<UnaryOp>() {
+        if op.mini_op.is::<Mul>() {
+            return tvec!((node.inputs[0], Table::Const(op.a.clone())));
+        }
+    }
+    if node.op_as::<TypedBinOp>().map(|op| op.0.is::<Mul>()).unwrap_or(false) {
+        return tvec!(
+            (node.inputs[0], Table::Input(node.inputs[1])),
+            (node.inputs[1], Table::Input(node.inputs[0]))
+        );
+    }
+    tvec!()
+}
+
+/// Input x of the rotate_half(x) computed at outlet.
+fn rotate_half(model: &TypedModel, outlet: OutletId) -> Option<OutletId> {
+    let node = model.node(outlet.node);
+    if !is_last_axis_concat(model, node).ok()? {
+        return None;
+    }
+    let (x, high) = slice_of(model, negated(model, node.inputs[0])?).ok()??;
+    let (low_x, low) = slice_of(model, node.inputs[1]).ok()??;
+    let dim = model.outlet_fact(x).ok()?.shape.last()?.clone();
+    if x == low_x
+        && low.start == 0.to_dim()
+        && low.end == high.start
+        && high.end == dim
+        && low.end.clone() * 2 == dim
+    {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+fn is_last_axis_concat(model: &TypedModel, node: &TypedNode) -> TractResult<bool> {
+    Ok(node
+        .op_as::<TypedConcat>()
+        .map(|op| {
+            op.slices.len() == 2
+                && op.slices.iter().all(|s| matches!(s, ConcatSlice::Var))
+                && op.axis + 1 == model.outlet_fact(node.id.into()).map(|f| f.rank()).unwrap_or(0)
+        })
+        .unwrap_or(false))
+}
+
+/// Input and Slice op of a slice of the last axis.
+fn slice_of(model: &TypedModel, outlet: OutletId) -> TractResult<Option<(OutletId, &Slice)>> {
+    let node = model.node(outlet.node);
+    if let Some(slice) = node.op_as::<Slice>() {
+        if slice.axis + 1 == model.outlet_fact(node.inputs[0])?.rank() {
+            return Ok(Some((node.inputs[0], slice)));
+        }
+    }
+    Ok(None)
+}
+
+/// x, from -x.
+fn negated(model: &TypedModel, outlet: OutletId) -> Option<OutletId> {
+    let node = model.node(outlet.node);
+    if node.op_as::<ElementWiseOp>().map(|op| op.0.is::<Neg>()).unwrap_or(false) {
+        return Some(node.inputs[0]);
+    }
+    let mul = node.op_as::<UnaryOp>().filter(|op| op.mini_op.is::<Mul>())?;
+    let minus_one =
+        mul.a.as_uniform().and_then(|t| t.cast_to_scalar::<f32>().ok()).map(|x| x == -1.0);
+    if minus_one == Some(true) {
+        Some(node.inputs[0])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::nn::RopeTables;
+    use crate::ops::{array, math};
+
+    /// rope(x[.., ..rotated]) concatenated to the rest of x, cos being an
+    /// input of the model.
+    fn decomposed(dim: usize, rotated: usize) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let input = model.add_source("x", f32::fact(&[2, 5, dim]))?;
+        let cos = model.add_source("cos", f32::fact(&[1, 5, rotated]))?;
+        let (_, sin) = RopeTables::new(rotated, RopeLayout::Halves).cos_sin(0..5)?;
+        let sin = model.add_const("sin", sin.into_shape(&[1, 5, rotated])?)?;
+        let slice = |model: &mut TypedModel, name: &str, x: OutletId, start: usize, end: usize| {
+            model.wire_node(name, array::Slice::new(2, start, end), &[x]).map(|w| w[0])
+        };
+        let x = if rotated < dim { slice(&mut model, "x.rot", input, 0, rotated)? } else { input };
+        let h = rotated / 2;
+        let low = slice(&mut model, "low", x, 0, h)?;
+        let high = slice(&mut model, "high", x, h, rotated)?;
+        let neg = model.wire_node("neg", math::neg(), &[high])?;
+        let half =
+            model.wire_node("rotate_half", TypedConcat::concat_vars(2, 2), &[neg[0], low])?;
+        let a = model.wire_node("x.cos", math::mul::bin_typed(), &[x, cos])?;
+        let b = model.wire_node("rotated.sin", math::mul::bin_typed(), &[half[0], sin])?;
+        let mut rope = model.wire_node("rope", math::add::bin_typed(), &[b[0], a[0]])?;
+        if rotated < dim {
+            let pass = slice(&mut model, "x.pass", input, rotated, dim)?;
+            rope = model.wire_node("concat", TypedConcat::concat_vars(2, 2), &[rope[0], pass])?;
+        }
+        model.set_output_outlets(&rope)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn fuse_rotary_embedding() -> TractResult<()> {
+        for (dim, rotated, rotation) in
+            [(8, 8, RopeRotation::Full), (8, 4, RopeRotation::Half), (16, 4, RopeRotation::Quarter)]
+        {
+            let model = decomposed(dim, rotated)?;
+            let x: Vec<f32> = (0..10 * dim).map(|i| ((i * 7) % 11) as f32 / 5.0 - 1.0).collect();
+            let x = tensor1(&x).into_shape(&[2, 5, dim])?;
+            let (cos, _) = RopeTables::new(rotated, RopeLayout::Halves).cos_sin(0..5)?;
+            let inputs = tvec!(x, cos.into_shape(&[1, 5, rotated])?);
+            let expected = model.clone().into_runnable()?.run(inputs.clone())?;
+            let decluttered = model.into_decluttered()?;
+            let output = decluttered.node(decluttered.output_outlets()?[0].node);
+            assert_eq!(
+                output.op_as::<RotaryEmbedding>(),
+                Some(&RotaryEmbedding::new(rotation, RopeLayout::Halves))
+            );
+            assert!(decluttered.nodes().iter().all(|n| !n.op_is::<TypedConcat>()));
+            let found = decluttered.into_runnable()?.run(inputs)?;
+            found[0].close_enough(&expected[0], true)?;
+        }
+        Ok(())
+    }
+}
@@ -4,7 +4,16 @@ use std::marker::PhantomData;
 
 use crate::internal::*;
 use crate::model::order::eval_order_for_nodes;
-use crate::model::{Fact, Graph, OutletId};
+use crate::model::{Fact, Graph, Outlet, OutletId};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use tract_linalg::multithread::{current_tract_executor, multithread_tract_scope, Executor};
+
+pub mod arena;
+pub mod profile;
+use arena::ArenaPlan;
+use tract_data::arena::Arena;
 
 #[derive(Default)]
 pub struct SessionState {
@@ -12,6 +21,7 @@ pub struct SessionState {
     pub resolved_symbols: SymbolValues,
     pub tensors: HashMap<String, Tensor>,
     pub cached_mmm_scratch_space: Option<Box<dyn tract_linalg::mmm::ScratchSpace>>,
+    pub opaque_evals: HashMap<String, crate::ops::opaque::OpaqueEval>,
 }
 
 impl Clone for SessionState {
@@ -21,6 +31,7 @@ impl Clone for SessionState {
             resolved_symbols: self.resolved_symbols.clone(),
             tensors: self.tensors.clone(),
             cached_mmm_scratch_space: None,
+            opaque_evals: self.opaque_evals.clone(),
         }
     }
 }
@@ -44,6 +55,21 @@ where
     pub order: Vec<usize>,
     pub flush_lists: Vec<TVec<usize>>,
     pub has_unresolved_symbols: bool,
+    /// Accuracy of the transcendental functions while running this plan,
+    /// the thread current one if None.
+    pub accuracy: Option<tract_linalg::Accuracy>,
+    /// Dependencies between nodes, on top of the ones from the wires.
+    pub more_dependencies: Vec<(usize, usize)>,
+    /// Executor running independent nodes concurrently, see
+    /// `with_inter_op_executor`.
+    #[educe(Hash(ignore))]
+    pub inter_op_executor: Option<Executor>,
+    /// Placement of the intermediate tensors in an arena, reused across runs.
+    pub arena: Option<ArenaPlan>,
+    /// Bitwise-reproducible runs, see `with_deterministic`.
+    pub deterministic: bool,
+    /// Refuse floating point values while running, see `with_integer_only`.
+    pub integer_only: bool,
     _casper: PhantomData<(F, O)>,
 }
 
@@ -69,6 +95,22 @@ where
         Self::new_for_outputs_and_deps(model, outputs, &[])
     }
 
+    /// This contructor returns a plan that will compute the outputs designated by their
+    /// names (outlet labels or node names), ignoring the nodes they do not depend on.
+    ///
+    /// Several such plans can share a single model (and its constants) by passing a
+    /// reference or an `Arc` of the model.
+    pub fn new_for_output_names(
+        model: M,
+        names: &[impl AsRef<str>],
+    ) -> TractResult<SimplePlan<F, O, M>> {
+        let outputs = names
+            .iter()
+            .map(|name| outlet_by_name(model.borrow(), name.as_ref()))
+            .collect::<TractResult<Vec<OutletId>>>()?;
+        Self::new_for_outputs(model, &outputs)
+    }
+
     pub fn new_for_outputs_and_deps(
         model: M,
         outputs: &[OutletId],
@@ -77,24 +119,11 @@ where
         let inputs = model.borrow().input_outlets()?.iter().map(|n| n.node).collect::<Vec<usize>>();
         let outputs_nodes = outputs.iter().map(|n| n.node).collect::<Vec<usize>>();
         let order = eval_order_for_nodes(model.borrow().nodes(), &inputs, &outputs_nodes, deps)?;
-        let mut values_needed_until_step = vec![0; model.borrow().nodes().len()];
-        for step in 0..order.len() {
-            for i in &model.borrow().node(order[step]).inputs {
-                values_needed_until_step[i.node] = step;
-            }
-        }
-        for o in outputs.iter() {
-            values_needed_until_step[o.node] = order.len();
-        }
-        let mut flush_lists: Vec<TVec<usize>> = vec![tvec!(); order.len() + 1];
-        for (node, &flush_at) in values_needed_until_step.iter().enumerate() {
-            if flush_at != 0 {
-                flush_lists[flush_at].push(node)
-            }
-        }
+        let (values_needed_until_step, flush_lists) = flush_lists(model.borrow(), &order, outputs);
+        let arena = ArenaPlan::new(model.borrow(), &order, outputs, &values_needed_until_step)?;
         let mut symbols: std::collections::HashSet<Symbol> = Default::default();
-        for node in &model.borrow().nodes {
-            for output in &node.outputs {
+        for &node in &order {
+            for output in &model.borrow().nodes[node].outputs {
                 if let Ok(fact) = output.fact.to_typed_fact() {
                     symbols.extend(fact.shape.iter().flat_map(|d| d.symbols()))
                 }
@@ -106,20 +135,383 @@ where
             flush_lists,
             outputs: outputs.to_vec(),
             has_unresolved_symbols: !symbols.is_empty(),
+            accuracy: None,
+            more_dependencies: deps.to_vec(),
+            inter_op_executor: None,
+            arena: Some(arena).filter(|a| !a.is_empty()),
+            deterministic: false,
+            integer_only: false,
             _casper: PhantomData,
         })
     }
 
+    /// Selects the accuracy of the transcendental functions (exp, erf,
+    /// sigmoid, tanh) for this plan.
+    pub fn with_accuracy(mut self, accuracy: tract_linalg::Accuracy) -> Self {
+        self.accuracy = Some(accuracy);
+        self
+    }
+
+    /// Runs the nodes on the workers of `executor` as soon as their inputs
+    /// are computed, instead of one after the other, so that independent
+    /// branches of the graph are computed concurrently.
+    ///
+    /// Each worker evaluates nodes with its own session state: this mode is
+    /// not used for sessions holding tensors (like TensorFlow variables),
+    /// which then run sequentially.
+    pub fn with_inter_op_executor(mut self, executor: Executor) -> Self {
+        self.inter_op_executor = Some(executor);
+        self
+    }
+
+    /// Makes the runs of this plan bitwise-reproducible: the nodes are run
+    /// one after the other, each on a single thread (ignoring the inter-op
+    /// and intra-op executors), and with the accuracy selected for the plan,
+    /// or the one selected on the thread when this is called, if any.
+    ///
+    /// Models whose convolution lowerings were selected by benchmarking (see
+    /// `AUTOTUNE_PROPERTY`) are refused, as the selection may change from
+    /// one process to another.
+    pub fn with_deterministic(mut self) -> TractResult<Self> {
+        use crate::ops::cnn::conv::AUTOTUNE_PROPERTY;
+        if let Some(prop) = self.model().properties.get(AUTOTUNE_PROPERTY) {
+            ensure!(
+                prop.cast_to_scalar::<i64>()? == 0,
+                "Deterministic plans require a model optimized without autotuning"
+            );
+        }
+        if self.accuracy.is_none() {
+            self.accuracy = tract_linalg::accuracy::current_accuracy();
+        }
+        self.deterministic = true;
+        Ok(self)
+    }
+
+    /// Restricts this plan to integer-only computations, for targets without
+    /// a floating point unit.
+    ///
+    /// Plans running a node using floating point values (see
+    /// `TypedModel::float_nodes`) are refused. While running, a node
+    /// producing a floating point tensor anyway fails the run.
+    pub fn with_integer_only(mut self) -> TractResult<Self> {
+        let model = self.model();
+        let mut float_nodes = vec![];
+        for &n in &self.order {
+            let node = model.node(n);
+            let op = node.op().as_typed().with_context(|| {
+                format!("Integer-only plans require a typed model, {} is not typed", node)
+            })?;
+            let inputs = node
+                .inputs
+                .iter()
+                .map(|i| Ok(model.outlet_fact(*i)?.to_typed_fact()?.into_owned()))
+                .collect::<TractResult<TVec<TypedFact>>>()?;
+            let outputs = node
+                .outputs
+                .iter()
+                .map(|o| Ok(o.fact.to_typed_fact()?.into_owned()))
+                .collect::<TractResult<TVec<TypedFact>>>()?;
+            let inputs: TVec<&TypedFact> = inputs.iter().collect();
+            let outputs: TVec<&TypedFact> = outputs.iter().collect();
+            if crate::model::typed::uses_float(op, &inputs, &outputs)? {
+                float_nodes.push(node.to_string());
+            }
+        }
+        ensure!(
+            float_nodes.is_empty(),
+            "Integer-only plan would run {} node(s) using floating point values: {}",
+            float_nodes.len(),
+            float_nodes.join(", ")
+        );
+        self.integer_only = true;
+        Ok(self)
+    }
+
     pub fn run(&self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
         let mut state = SimpleState::new(self)?;
         state.run(inputs)
     }
 
+    /// Run the plan with explicit symbol values, see
+    /// `SimpleState::run_with_symbols`.
+    pub fn run_with_symbols(
+        &self,
+        inputs: TVec<Tensor>,
+        symbols: &SymbolValues,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut state = SimpleState::new(self)?;
+        state.run_with_symbols(inputs, symbols)
+    }
+
+    /// Run the plan for some of its outputs only, see
+    /// `SimpleState::run_for_outputs`.
+    pub fn run_for_outputs(
+        &self,
+        inputs: TVec<Tensor>,
+        outputs: &[OutletId],
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut state = SimpleState::new(self)?;
+        state.run_for_outputs(inputs, outputs)
+    }
+
+    /// Run the plan with inputs and outputs designated by their names, see
+    /// `SimpleState::run_by_name`.
+    pub fn run_by_name(
+        &self,
+        inputs: HashMap<&str, Tensor>,
+    ) -> TractResult<HashMap<String, Arc<Tensor>>> {
+        let mut state = SimpleState::new(self)?;
+        state.run_by_name(inputs)
+    }
+
+    /// Run the plan, also returning some of its intermediate values, see
+    /// `SimpleState::run_capturing`.
+    pub fn run_capturing(
+        &self,
+        inputs: TVec<Tensor>,
+        names: &[impl AsRef<str>],
+    ) -> TractResult<(TVec<Arc<Tensor>>, HashMap<String, Arc<Tensor>>)> {
+        let mut state = SimpleState::new(self)?;
+        state.run_capturing(inputs, names)
+    }
+
+    /// Build a state and warm it up, see `SimpleState::warmup`.
+    pub fn warmup(&self, symbols: &SymbolValues) -> TractResult<SimpleState<F, O, M, &Self>> {
+        let mut state = SimpleState::new(self)?;
+        state.warmup(symbols)?;
+        Ok(state)
+    }
+
+    /// Peak size, in bytes, of the values alive at the same time when
+    /// running the plan with the given symbol values, the output of a node
+    /// being counted with the inputs it is computed from.
+    ///
+    /// Inputs and outputs of the model are included, but not the constants
+    /// nor the scratch buffers used internally by the operators.
+    pub fn peak_memory(&self, symbols: &SymbolValues) -> TractResult<usize> {
+        let model = self.model();
+        let mut live = 0;
+        let mut peak = 0;
+        let mut bytes = vec![0; model.nodes().len()];
+        for (step, &n) in self.order.iter().enumerate() {
+            let node = model.node(n);
+            if !node.op_is::<crate::ops::konst::Const>() {
+                for output in &node.outputs {
+                    let fact = output.fact.to_typed_fact()?;
+                    let shape = fact
+                        .shape
+                        .eval_to_usize(symbols)
+                        .with_context(|| format!("Resolving output shape of {}", node))?;
+                    bytes[n] += shape.iter().product::<usize>() * fact.datum_type.size_of();
+                }
+            }
+            peak = peak.max(live + bytes[n]);
+            live =
+                live + bytes[n] - self.flush_lists[step].iter().map(|f| bytes[*f]).sum::<usize>();
+        }
+        Ok(peak)
+    }
+
+    /// Peak memory of the plan for inputs of the given shapes, see
+    /// `peak_memory`. All the symbols must be deducible from the input
+    /// shapes.
+    pub fn peak_memory_for_input_shapes(&self, shapes: &[&[usize]]) -> TractResult<usize> {
+        let mut symbols = SymbolValues::default();
+        let inputs = self.model().input_outlets()?;
+        ensure!(
+            shapes.len() == inputs.len(),
+            "Expected {} input shapes, got {}",
+            inputs.len(),
+            shapes.len()
+        );
+        for (outlet, shape) in inputs.iter().zip(shapes) {
+            let fact = self.model().outlet_fact(*outlet)?.to_typed_fact()?;
+            ensure!(
+                fact.rank() == shape.len(),
+                "Expected a rank {} input shape for {:?}",
+                fact.rank(),
+                outlet
+            );
+            for (expected, provided) in fact.shape.iter().zip(shape.iter()) {
+                resolve(&mut symbols, &expected, *provided as i64);
+            }
+        }
+        self.peak_memory(&symbols)
+    }
+
     pub fn model(&self) -> &Graph<F, O> {
         self.model.borrow()
     }
 }
 
+/// A flag to cancel the runs of the states it is given to, from another
+/// thread. See `SimpleState::set_cancellation_token`.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Makes the current and next runs fail with `Cancelled::Token` before
+    /// evaluating their next node.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Error of a run stopped between two nodes, by its cancellation token or
+/// deadline. It can be told apart from other errors with
+/// `err.downcast_ref::<Cancelled>()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cancelled {
+    Token,
+    Deadline,
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cancelled::Token => write!(f, "Run cancelled"),
+            Cancelled::Deadline => write!(f, "Run deadline exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Cancellation token and deadline of the runs of a state.
+#[derive(Clone, Debug, Default)]
+struct Cancellation {
+    token: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    fn check(&self) -> TractResult<()> {
+        if self.token.as_ref().map(|t| t.is_cancelled()).unwrap_or(false) {
+            return Err(Cancelled::Token.into());
+        }
+        if self.deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+            return Err(Cancelled::Deadline.into());
+        }
+        Ok(())
+    }
+}
+
+/// Callbacks invoked by a `SimpleState` around the evaluation of each node,
+/// for logging, metrics, or to abort a run by returning an error.
+///
+/// Observers are shared, so they need interior mutability to record things.
+pub trait NodeObserver<F, O>: Debug + Send + Sync
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    /// Called before `node` is evaluated on `inputs`.
+    fn before_node(&self, _node: &Node<F, O>, _inputs: &[Arc<Tensor>]) -> TractResult<()> {
+        Ok(())
+    }
+
+    /// Called after `node` has computed `outputs`.
+    fn after_node(&self, _node: &Node<F, O>, _outputs: &[Arc<Tensor>]) -> TractResult<()> {
+        Ok(())
+    }
+}
+
+/// An observer checking the floating point outputs of every node, and
+/// aborting the run on the first one producing NaN or infinite values, with
+/// a summary of its inputs. See `SimpleState::check_non_finite`.
+#[derive(Debug, Default)]
+pub struct NonFiniteCheck {
+    inputs: Mutex<TVec<Arc<Tensor>>>,
+}
+
+impl<F, O> NodeObserver<F, O> for NonFiniteCheck
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    fn before_node(&self, _node: &Node<F, O>, inputs: &[Arc<Tensor>]) -> TractResult<()> {
+        *self.inputs.lock().unwrap() = inputs.into();
+        Ok(())
+    }
+
+    fn after_node(&self, node: &Node<F, O>, outputs: &[Arc<Tensor>]) -> TractResult<()> {
+        let inputs = std::mem::take(&mut *self.inputs.lock().unwrap());
+        for (ix, output) in outputs.iter().enumerate() {
+            let stats = FloatStats::of(output)?;
+            if let Some(stats) = stats.filter(|s| s.nan > 0 || s.inf > 0) {
+                let inputs = inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, i)| Ok(format!("\n  input #{}: {}", ix, summarize(i)?)))
+                    .collect::<TractResult<String>>()?;
+                bail!(
+                    "{} output #{} ({:?} {:?}) has {} NaN and {} infinite values out of {}.{}",
+                    node,
+                    ix,
+                    output.datum_type(),
+                    output.shape(),
+                    stats.nan,
+                    stats.inf,
+                    output.len(),
+                    inputs
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts of the non-finite values of a float tensor, and range of the
+/// finite ones.
+struct FloatStats {
+    nan: usize,
+    inf: usize,
+    min: f64,
+    max: f64,
+}
+
+impl FloatStats {
+    fn of(tensor: &Tensor) -> TractResult<Option<FloatStats>> {
+        if !tensor.datum_type().is_float() {
+            return Ok(None);
+        }
+        let values = tensor.cast_to::<f64>()?;
+        let mut stats = FloatStats { nan: 0, inf: 0, min: f64::INFINITY, max: f64::NEG_INFINITY };
+        for &v in values.as_slice::<f64>()? {
+            if v.is_nan() {
+                stats.nan += 1;
+            } else if v.is_infinite() {
+                stats.inf += 1;
+            } else {
+                stats.min = stats.min.min(v);
+                stats.max = stats.max.max(v);
+            }
+        }
+        Ok(Some(stats))
+    }
+}
+
+fn summarize(tensor: &Tensor) -> TractResult<String> {
+    let mut s = format!("{:?} {:?}", tensor.datum_type(), tensor.shape());
+    if let Some(stats) = FloatStats::of(tensor)? {
+        if stats.min <= stats.max {
+            s += &format!(", finite values in [{}, {}]", stats.min, stats.max);
+        }
+        if stats.nan > 0 || stats.inf > 0 {
+            s += &format!(", {} NaN, {} infinite", stats.nan, stats.inf);
+        }
+    }
+    Ok(s)
+}
+
 #[derive(Clone, Debug)]
 pub struct SimpleState<F, O, M, P>
 where
@@ -132,6 +524,15 @@ where
     pub states: Vec<Option<Box<dyn OpState>>>,
     pub session_state: SessionState,
     pub values: Vec<Option<TVec<Arc<Tensor>>>>,
+    /// Session states of the workers, when running with an inter-op executor.
+    worker_sessions: Vec<SessionState>,
+    /// Arena for the intermediate tensors, when the plan has one.
+    pub arena: Option<Arc<Arena>>,
+    /// Buffers offered to the outputs of some nodes during `run_into`, by
+    /// node id.
+    output_buffers: HashMap<usize, Arc<Arena>>,
+    observers: Vec<Arc<dyn NodeObserver<F, O>>>,
+    cancellation: Cancellation,
     _phantom: PhantomData<(M, F, O)>,
 }
 
@@ -151,7 +552,19 @@ where
             .iter()
             .map(|n: &Node<F, O>| n.op().state(&mut session, n.id))
             .collect::<TractResult<_>>()?;
-        Ok(SimpleState { plan, states, session_state: session, values, _phantom: PhantomData })
+        let arena = plan.borrow().arena.as_ref().map(|a| Arena::new(a.size)).transpose()?;
+        Ok(SimpleState {
+            plan,
+            states,
+            session_state: session,
+            values,
+            worker_sessions: vec![],
+            arena,
+            output_buffers: HashMap::default(),
+            observers: vec![],
+            cancellation: Cancellation::default(),
+            _phantom: PhantomData,
+        })
     }
 
     /// Reset wires state.
@@ -174,14 +587,403 @@ where
     }
 
     pub fn run(&mut self, inputs: TVec<Tensor>) -> TractResult<TVec<Arc<Tensor>>> {
+        if let Some(Executor::MultiThread(pool)) = &self.plan().inter_op_executor {
+            if self.session_state.tensors.is_empty()
+                && self.observers.is_empty()
+                && !self.plan().deterministic
+            {
+                let pool = pool.clone();
+                return self.run_inter_op(&pool, inputs);
+            }
+        }
         self.run_plan_with_eval(inputs, self::eval)
     }
 
+    /// Run the plan, with values for the symbols that can not be inferred
+    /// from the input shapes (like the ones only appearing in outputs or
+    /// inside the model). Symbols resolved by previous runs are forgotten,
+    /// and the input shapes must agree with the given values.
+    pub fn run_with_symbols(
+        &mut self,
+        inputs: TVec<Tensor>,
+        symbols: &SymbolValues,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        for (ix, (outlet, input)) in self.model().input_outlets()?.iter().zip(&inputs).enumerate() {
+            if let Ok(fact) = self.model().outlet_fact(*outlet)?.to_typed_fact() {
+                for (axis, (expected, provided)) in fact.shape.iter().zip(input.shape()).enumerate()
+                {
+                    let expected = expected.eval(symbols);
+                    if expected.to_usize().map(|e| e != *provided).unwrap_or(false) {
+                        bail!(
+                            "Input {} has {} on axis {}, expected {} from the symbol values",
+                            ix,
+                            provided,
+                            axis,
+                            expected
+                        );
+                    }
+                }
+            }
+        }
+        self.session_state.resolved_symbols = symbols.clone();
+        self.run(inputs)
+    }
+
+    /// Run the plan with inputs designated by their names (outlet labels or
+    /// node names), whatever their order in the model, and return the
+    /// outputs by name: their outlet label if any, or their node name.
+    pub fn run_by_name(
+        &mut self,
+        mut inputs: HashMap<&str, Tensor>,
+    ) -> TractResult<HashMap<String, Arc<Tensor>>> {
+        let model = self.model();
+        let ordered = model
+            .input_outlets()?
+            .iter()
+            .map(|&outlet| {
+                let name = outlet_name(model, outlet);
+                let tensor = inputs.remove(&*name).or_else(|| {
+                    let key = inputs
+                        .keys()
+                        .find(|k| outlet_by_name(model, k).ok() == Some(outlet))
+                        .copied();
+                    key.and_then(|k| inputs.remove(k))
+                });
+                tensor.ok_or_else(|| format_err!("No value given for input {}", name))
+            })
+            .collect::<TractResult<TVec<Tensor>>>()?;
+        if let Some(name) = inputs.keys().next() {
+            bail!("{} is not an input of the model", name);
+        }
+        let names: Vec<String> =
+            self.plan().outputs.iter().map(|&o| outlet_name(self.model(), o)).collect();
+        let outputs = self.run(ordered)?;
+        Ok(names.into_iter().zip(outputs).collect())
+    }
+
+    /// Run the plan to only compute `outputs`, skipping the nodes they do
+    /// not depend on. The outputs must be computed by the plan, but do not
+    /// have to be among its outputs. The nodes are run one after the other,
+    /// even with an inter-op executor.
+    pub fn run_for_outputs(
+        &mut self,
+        inputs: TVec<Tensor>,
+        outputs: &[OutletId],
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        let (order, flush_lists) = {
+            let plan = self.plan();
+            let model = plan.model();
+            let mut needed = vec![false; model.nodes().len()];
+            for o in outputs {
+                ensure!(
+                    plan.order.contains(&o.node) && o.slot < model.node(o.node).outputs.len(),
+                    "{:?} is not computed by this plan",
+                    o
+                );
+                needed[o.node] = true;
+            }
+            for &n in plan.order.iter().rev() {
+                if needed[n] {
+                    for i in &model.node(n).inputs {
+                        needed[i.node] = true;
+                    }
+                    for dep in plan.more_dependencies.iter().filter(|d| d.0 == n) {
+                        needed[dep.1] = true;
+                    }
+                }
+            }
+            let order: Vec<usize> = plan.order.iter().copied().filter(|&n| needed[n]).collect();
+            let (_, flush_lists) = flush_lists(model, &order, outputs);
+            (order, flush_lists)
+        };
+        self.set_inputs(inputs)?;
+        self.run_steps_with_eval(Some((&order, &flush_lists, outputs)), self::eval)
+    }
+
+    /// Run the plan, and return, alongside its outputs, the values of the
+    /// outlets designated by `names` (outlet labels or node names), so that
+    /// they can be compared to a reference without adding them to the model
+    /// outputs. The nodes are run one after the other, even with an inter-op
+    /// executor.
+    pub fn run_capturing(
+        &mut self,
+        inputs: TVec<Tensor>,
+        names: &[impl AsRef<str>],
+    ) -> TractResult<(TVec<Arc<Tensor>>, HashMap<String, Arc<Tensor>>)> {
+        let mut outlets = self.plan().outputs.clone();
+        for name in names {
+            outlets.push(outlet_by_name(self.model(), name.as_ref())?);
+        }
+        let mut values = self.run_for_outputs(inputs, &outlets)?;
+        let captured = values.drain(self.plan().outputs.len()..);
+        let captured = names.iter().map(|n| n.as_ref().to_string()).zip(captured).collect();
+        Ok((values, captured))
+    }
+
+    /// Run the plan, computing its outputs in the buffers of `outputs`.
+    ///
+    /// Each output tensor must be pre-allocated with the datum type and shape
+    /// of the corresponding plan output. The node computing it then writes
+    /// directly to its buffer, when the node computes no other output of the
+    /// plan and allocates its result first, or the result is copied to the
+    /// buffer otherwise. An output tensor not matching the computed one is
+    /// replaced. The nodes are run one after the other, even with an
+    /// inter-op executor.
+    pub fn run_into(&mut self, inputs: TVec<Tensor>, outputs: &mut [Tensor]) -> TractResult<()> {
+        let plan_outputs = self.plan().outputs.clone();
+        ensure!(
+            outputs.len() == plan_outputs.len(),
+            "Expected {} output tensors, got {}",
+            plan_outputs.len(),
+            outputs.len()
+        );
+        let mut buffers: TVec<Option<OutputBuffer>> = tvec!();
+        for (outlet, output) in plan_outputs.iter().zip(outputs.iter_mut()) {
+            let alone = self.model().node(outlet.node).outputs.len() == 1
+                && plan_outputs.iter().filter(|o| o.node == outlet.node).count() == 1;
+            let (dt, shape) = (output.datum_type(), output.shape().into());
+            let buffer = if alone { Arena::from_tensor(output) } else { None };
+            if let Some(buffer) = &buffer {
+                self.output_buffers.insert(outlet.node, buffer.clone());
+            }
+            buffers.push(buffer.map(|b| (b, dt, shape)));
+        }
+        let values = self.run_plan_with_eval(inputs, self::eval);
+        self.output_buffers.clear();
+        let values = match values {
+            Ok(values) => values,
+            Err(e) => {
+                for (buffer, output) in buffers.into_iter().zip(outputs) {
+                    if let Some(Ok(tensor)) = buffer.map(|b| b.0.into_tensor()) {
+                        *output = tensor;
+                    }
+                }
+                return Err(e);
+            }
+        };
+        for ((value, buffer), output) in values.into_iter().zip(buffers).zip(outputs) {
+            let value = Arc::try_unwrap(value).unwrap_or_else(|v| v.as_ref().clone());
+            if let Some((arena, dt, shape)) = buffer {
+                if arena.contains(unsafe { value.as_ptr_unchecked::<u8>() }) {
+                    if value.datum_type() == dt && value.shape() == &*shape {
+                        drop(value);
+                        *output = arena
+                            .into_tensor()
+                            .map_err(|_| format_err!("Output buffer is still in use"))?;
+                    } else {
+                        // computed in the buffer, but not with the expected shape
+                        *output = value;
+                    }
+                    continue;
+                }
+                if let Ok(tensor) = arena.into_tensor() {
+                    *output = tensor;
+                }
+            }
+            if output.datum_type() == value.datum_type()
+                && output.shape() == value.shape()
+                && output.datum_type().is_copy()
+                && output.len() > 0
+            {
+                unsafe { output.as_bytes_mut().copy_from_slice(value.as_bytes()) };
+            } else {
+                *output = value;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_inter_op(
+        &mut self,
+        pool: &rayon::ThreadPool,
+        inputs: TVec<Tensor>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        self.set_inputs(inputs)?;
+        let &mut SimpleState {
+            ref plan,
+            ref mut session_state,
+            ref mut states,
+            ref mut worker_sessions,
+            ref cancellation,
+            ..
+        } = self;
+        let plan = plan.borrow();
+        let model = plan.model();
+        for session in worker_sessions.iter_mut() {
+            session.inputs = session_state.inputs.clone();
+            session.opaque_evals = session_state.opaque_evals.clone();
+        }
+        let mut run = InterOpRun {
+            nodes: (0..model.nodes().len()).map(|_| None).collect(),
+            states: std::mem::take(states).into_iter().map(Mutex::new).collect(),
+            has_unresolved_symbols: plan.has_unresolved_symbols,
+            accuracy: plan.accuracy.or_else(tract_linalg::accuracy::current_accuracy),
+            intra_op_executor: current_tract_executor(),
+            cancellation: cancellation.clone(),
+            integer_only: plan.integer_only,
+            schedule: Mutex::new(InterOpSchedule {
+                values: vec![None; model.nodes().len()],
+                pending: vec![0; model.nodes().len()],
+                consumers: vec![0; model.nodes().len()],
+                resolved_symbols: session_state.resolved_symbols.clone(),
+                sessions: std::mem::take(worker_sessions),
+                session_template: session_state.clone(),
+                error: None,
+            }),
+        };
+        {
+            let schedule = run.schedule.get_mut().unwrap();
+            for &n in &plan.order {
+                let node = model.node(n);
+                let mut predecessors: TVec<usize> = node.inputs.iter().map(|i| i.node).collect();
+                predecessors
+                    .extend(plan.more_dependencies.iter().filter(|d| d.0 == n).map(|d| d.1));
+                predecessors.sort_unstable();
+                predecessors.dedup();
+                schedule.pending[n] = predecessors.len();
+                for &p in &predecessors {
+                    run.nodes[p]
+                        .get_or_insert_with(|| InterOpNode::new(model.node(p)))
+                        .successors
+                        .push(n);
+                }
+                for i in &node.inputs {
+                    schedule.consumers[i.node] += 1;
+                }
+                run.nodes[n].get_or_insert_with(|| InterOpNode::new(node));
+            }
+            for o in &plan.outputs {
+                // never flushed
+                schedule.consumers[o.node] += 1;
+            }
+        }
+        let ready: Vec<usize> = {
+            let schedule = run.schedule.get_mut().unwrap();
+            plan.order.iter().copied().filter(|n| schedule.pending[*n] == 0).collect()
+        };
+        pool.install(|| {
+            rayon::scope(|scope| {
+                for node in ready {
+                    let run = &run;
+                    scope.spawn(move |scope| run.run_node(scope, node));
+                }
+            })
+        });
+        *states = run.states.into_iter().map(|s| s.into_inner().unwrap()).collect();
+        let InterOpSchedule { values, resolved_symbols, sessions, error, .. } =
+            run.schedule.into_inner().unwrap();
+        *worker_sessions = sessions;
+        session_state.resolved_symbols = resolved_symbols;
+        if let Some(e) = error {
+            return Err(e);
+        }
+        plan.outputs
+            .iter()
+            .map(|o| {
+                let values = values[o.node]
+                    .as_ref()
+                    .with_context(|| format!("Output {:?} was not computed", o))?;
+                Ok(values[o.slot].clone())
+            })
+            .collect()
+    }
+
+    /// Provide the evaluation function for the opaque operators of a given kind.
+    pub fn register_opaque_eval(
+        &mut self,
+        kind: impl Into<String>,
+        eval: impl Fn(&crate::ops::opaque::OpaqueOp, TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.session_state.opaque_evals.insert(kind.into(), Arc::new(eval));
+    }
+
+    /// Adds an observer, called around the evaluation of each node by the
+    /// next runs. The nodes are then run one after the other, even with an
+    /// inter-op executor.
+    pub fn register_observer(&mut self, observer: Arc<dyn NodeObserver<F, O>>) {
+        self.observers.push(observer);
+    }
+
+    /// Makes the next runs check `token` before evaluating each node, and
+    /// fail with `Cancelled::Token` once it is cancelled. None removes the
+    /// token.
+    pub fn set_cancellation_token(&mut self, token: Option<CancellationToken>) {
+        self.cancellation.token = token;
+    }
+
+    /// Makes the next runs fail with `Cancelled::Deadline` if they are still
+    /// running at `deadline`. It is checked before evaluating each node, so
+    /// a run only stops once the node in progress is done. None removes the
+    /// deadline.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.cancellation.deadline = deadline;
+    }
+
+    /// Starts profiling the evaluation of the nodes in the next runs, and
+    /// returns the profiler to get the report from. The nodes are then run
+    /// one after the other, even with an inter-op executor.
+    pub fn profile(&mut self) -> Arc<profile::Profiler> {
+        let profiler = Arc::new(profile::Profiler::default());
+        self.register_observer(profiler.clone());
+        profiler
+    }
+
+    /// Makes the next runs fail on the first node computing NaN or infinite
+    /// values, reporting the node and a summary of its inputs. This is a
+    /// debugging mode: every float value is checked.
+    pub fn check_non_finite(&mut self) {
+        self.register_observer(Arc::new(NonFiniteCheck::default()));
+    }
+
+    /// Run the plan once on zero-filled inputs, so that the one-time work
+    /// (weights packing, scratch space allocation, lazy initializations) is
+    /// done before the first actual inference.
+    ///
+    /// `symbols` must give a value to every symbol appearing in the input
+    /// shapes. Op states are reset afterwards, so stateful models start from
+    /// scratch.
+    pub fn warmup(&mut self, symbols: &SymbolValues) -> TractResult<()> {
+        let mut inputs = tvec!();
+        for (ix, outlet) in self.model().input_outlets()?.iter().enumerate() {
+            let fact = self.model().outlet_fact(*outlet)?.to_typed_fact()?;
+            let shape = fact
+                .shape
+                .eval_to_usize(symbols)
+                .with_context(|| format!("Resolving shape of input {} for warmup", ix))?;
+            inputs.push(warmup_tensor(fact.datum_type, &shape)?);
+        }
+        self.run(inputs).context("Running warmup")?;
+        self.reset_op_states()
+    }
+
     pub fn run_plan_with_eval<Eval, E>(
         &mut self,
         inputs: TVec<Tensor>,
         mut eval: Eval,
     ) -> TractResult<TVec<Arc<Tensor>>>
+    where
+        Eval: for<'a, 'b, 'c> FnMut(
+            &'a mut SessionState,
+            Option<&'b mut (dyn OpState + 'static)>,
+            &'c Node<F, O>,
+            TVec<Arc<Tensor>>,
+        ) -> Result<TVec<Arc<Tensor>>, E>,
+        E: Into<anyhow::Error> + Send + Sync + 'static,
+    {
+        self.set_inputs(inputs)?;
+        self.run_steps_with_eval(None, eval)
+    }
+
+    /// Run the given steps (order, flush lists and outputs), or the ones of
+    /// the plan.
+    fn run_steps_with_eval<Eval, E>(
+        &mut self,
+        steps: Option<Steps>,
+        mut eval: Eval,
+    ) -> TractResult<TVec<Arc<Tensor>>>
     where
         Eval: for<'a, 'b, 'c> FnMut(
             &'a mut SessionState,
@@ -193,19 +995,28 @@ where
     {
         let mut result = tvec!();
         {
-            self.set_inputs(inputs)?;
             let &mut SimpleState {
                 ref plan,
                 ref mut session_state,
                 ref mut states,
                 ref mut values,
+                ref arena,
+                ref output_buffers,
+                ref observers,
+                ref cancellation,
                 ..
             } = self;
             let plan = plan.borrow();
             let model = plan.model().borrow();
-            for (step, n) in plan.order.iter().enumerate() {
+            let (order, flush_lists, outputs) =
+                steps.unwrap_or((&plan.order, &plan.flush_lists, &plan.outputs));
+            for (step, n) in order.iter().enumerate() {
                 let node = model.node(*n);
                 trace!("Running step {}, node {}", step, node);
+                if let Err(e) = cancellation.check() {
+                    values.iter_mut().for_each(|v| *v = None);
+                    return Err(e);
+                }
                 let mut inputs: TVec<Arc<Tensor>> = tvec![];
                 for i in &node.inputs {
                     trace!("  use input {:?}", i);
@@ -216,7 +1027,7 @@ where
                     inputs.push(prec[i.slot].clone().into())
                 }
 
-                for flush in &plan.flush_lists[step] {
+                for flush in &flush_lists[step] {
                     trace!("  Ran {} can now flush {}", node, model.node(*flush));
                     values[*flush] = None;
                 }
@@ -244,15 +1055,55 @@ where
                     }
                 }
 
-                let vs =
-                    eval(session_state, states[node.id].as_mut().map(|s| &mut **s), node, inputs)
-                        .map_err(|e| e.into())?;
+                for observer in observers {
+                    observer
+                        .before_node(node, &inputs)
+                        .with_context(|| format!("Observing {}", node))?;
+                }
+
+                let state = states[node.id].as_deref_mut();
+                let region = plan.arena.as_ref().and_then(|a| a.regions[node.id].clone());
+                let eval_node = || match (output_buffers.get(&node.id), arena, region) {
+                    (Some(buffer), _, _) => {
+                        tract_data::arena::offer(buffer, 0..buffer.len(), || {
+                            eval(session_state, state, node, inputs)
+                        })
+                    }
+                    (None, Some(arena), Some(region)) => {
+                        tract_data::arena::offer(arena, region, || {
+                            eval(session_state, state, node, inputs)
+                        })
+                    }
+                    _ => eval(session_state, state, node, inputs),
+                };
+                let eval_scoped = || {
+                    if let Some(accuracy) = plan.accuracy {
+                        tract_linalg::accuracy::accuracy_scope(accuracy, eval_node)
+                    } else {
+                        eval_node()
+                    }
+                };
+                let vs = if plan.deterministic {
+                    multithread_tract_scope(Executor::SingleThread, eval_scoped)
+                } else {
+                    eval_scoped()
+                }
+                .map_err(|e| e.into())?;
+                if plan.integer_only {
+                    check_integer_only(node, &vs)?;
+                }
+
+                for observer in observers {
+                    observer
+                        .after_node(node, &vs)
+                        .with_context(|| format!("Observing {}", node))?;
+                }
 
                 if plan.has_unresolved_symbols {
                     for (o, v) in node.outputs.iter().zip(vs.iter()) {
                         if let Ok(f) = o.fact.to_typed_fact() {
                             for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
-                                Self::resolve(
+                                resolve(
                                     &mut session_state.resolved_symbols,
                                     &dim_abstract,
                                     *dim_concrete as i64,
@@ -289,7 +1140,7 @@ where
 
                 values[node.id] = Some(vs);
             }
-            for output in &plan.outputs {
+            for output in outputs {
                 trace!("Extracting value {:?} ({})", output, model.node(output.node));
                 result.push(values[output.node].as_ref().unwrap()[output.slot].clone())
             }
@@ -305,14 +1156,6 @@ where
         Ok(())
     }
 
-    fn resolve(symbols: &mut SymbolValues, expected: &TDim, provided: i64) {
-        match expected {
-            TDim::Sym(s) => symbols[*s] = Some(provided),
-            TDim::MulInt(x, expr) => Self::resolve(symbols, expr, provided / *x),
-            _ => (),
-        }
-    }
-
     pub fn set_input(&mut self, input: usize, t: Tensor) -> TractResult<()> {
         let outlet: OutletId = *self
             .model()
@@ -324,7 +1167,7 @@ where
         let model = plan.model.borrow();
         if let Ok(fact) = model.outlet_fact(outlet)?.to_typed_fact() {
             for (expected, provided) in fact.shape.iter().zip(t.shape()) {
-                Self::resolve(&mut session_state.resolved_symbols, &expected, *provided as i64)
+                resolve(&mut session_state.resolved_symbols, &expected, *provided as i64)
             }
         }
         self.plan
@@ -340,7 +1183,7 @@ where
     pub fn take_outputs(&mut self) -> TractResult<Vec<Arc<Tensor>>> {
         let SimpleState { ref plan, ref mut values, .. } = self;
         let mut v = vec![];
-        for o in plan.borrow().model().output_outlets()?.iter() {
+        for o in plan.borrow().outputs.iter() {
             let vs = values[o.node].as_mut().ok_or_else(|| {
                 format_err!(
                     "Outputs of {:?} are not computed",
@@ -453,6 +1296,93 @@ where
     }
 }
 
+/// Evaluation order, flush lists and outputs of a run.
+type Steps<'s> = (&'s [usize], &'s [TVec<usize>], &'s [OutletId]);
+
+/// A buffer offered to an output, with the datum type and shape of its
+/// tensor.
+type OutputBuffer = (Arc<Arena>, DatumType, TVec<usize>);
+
+/// For each node, the step after which its value is no longer needed when
+/// running `order` to compute `outputs`, and the nodes to flush at each step.
+fn flush_lists<F, O>(
+    model: &Graph<F, O>,
+    order: &[usize],
+    outputs: &[OutletId],
+) -> (Vec<usize>, Vec<TVec<usize>>)
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    let mut values_needed_until_step = vec![0; model.nodes().len()];
+    for step in 0..order.len() {
+        for i in &model.node(order[step]).inputs {
+            values_needed_until_step[i.node] = step;
+        }
+    }
+    for o in outputs.iter() {
+        values_needed_until_step[o.node] = order.len();
+    }
+    let mut flush_lists: Vec<TVec<usize>> = vec![tvec!(); order.len() + 1];
+    for (node, &flush_at) in values_needed_until_step.iter().enumerate() {
+        if flush_at != 0 {
+            flush_lists[flush_at].push(node)
+        }
+    }
+    (values_needed_until_step, flush_lists)
+}
+
+fn resolve(symbols: &mut SymbolValues, expected: &TDim, provided: i64) {
+    match expected {
+        TDim::Sym(s) => symbols[*s] = Some(provided),
+        TDim::MulInt(x, expr) => resolve(symbols, expr, provided / *x),
+        _ => (),
+    }
+}
+
+fn warmup_tensor(dt: DatumType, shape: &[usize]) -> TractResult<Tensor> {
+    if dt == bool::datum_type() {
+        Ok(tract_ndarray::ArrayD::<bool>::default(shape).into_tensor())
+    } else if dt.is_copy() {
+        Tensor::zero_dt(dt, shape)
+    } else {
+        unsafe { Tensor::uninitialized_dt(dt, shape) }
+    }
+}
+
+fn outlet_by_name<F, O>(model: &Graph<F, O>, name: &str) -> TractResult<OutletId>
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    if let Some(outlet) = model.find_outlet_label(name) {
+        Ok(outlet)
+    } else {
+        Ok(model.node_by_name(name)?.id.into())
+    }
+}
+
+fn outlet_name<F, O>(model: &Graph<F, O>, outlet: OutletId) -> String
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    if let Some(label) = model.outlet_label(outlet) {
+        label.to_string()
+    } else if outlet.slot == 0 {
+        model.node(outlet.node).name.clone()
+    } else {
+        format!("{}:{}", model.node(outlet.node).name, outlet.slot)
+    }
+}
+
+fn check_integer_only(node: impl Display, values: &[Arc<Tensor>]) -> TractResult<()> {
+    if let Some(v) = values.iter().find(|v| v.datum_type().is_float()) {
+        bail!("Integer-only run: {} produced a {:?} tensor", node, v.datum_type());
+    }
+    Ok(())
+}
+
 pub fn eval<F, O>(
     session_state: &mut SessionState,
     mut state: Option<&mut (dyn OpState + 'static)>,
@@ -470,3 +1400,609 @@ where
     .with_context(|| format!("Evaluating {}", node));
     r
 }
+
+/// Node data the workers need during an inter-op run.
+struct InterOpNode<'m, F: Fact + Hash> {
+    op: &'m dyn Op,
+    name: &'m str,
+    inputs: &'m [OutletId],
+    outputs: &'m [Outlet<F>],
+    successors: TVec<usize>,
+}
+
+impl<'m, F: Fact + Hash> InterOpNode<'m, F> {
+    fn new<O>(node: &'m Node<F, O>) -> InterOpNode<'m, F>
+    where
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+    {
+        InterOpNode {
+            op: node.op(),
+            name: &node.name,
+            inputs: &node.inputs,
+            outputs: &node.outputs,
+            successors: tvec!(),
+        }
+    }
+}
+
+struct InterOpSchedule {
+    values: Vec<Option<TVec<Arc<Tensor>>>>,
+    /// Count of predecessors not computed yet, by node.
+    pending: Vec<usize>,
+    /// Count of consumers not started yet, by node: values are flushed when it
+    /// reaches zero.
+    consumers: Vec<usize>,
+    resolved_symbols: SymbolValues,
+    sessions: Vec<SessionState>,
+    session_template: SessionState,
+    error: Option<anyhow::Error>,
+}
+
+struct InterOpRun<'m, F: Fact + Hash> {
+    nodes: Vec<Option<InterOpNode<'m, F>>>,
+    states: Vec<Mutex<Option<Box<dyn OpState>>>>,
+    has_unresolved_symbols: bool,
+    accuracy: Option<tract_linalg::Accuracy>,
+    intra_op_executor: Executor,
+    cancellation: Cancellation,
+    integer_only: bool,
+    schedule: Mutex<InterOpSchedule>,
+}
+
+impl<'m, F: Fact + Hash> InterOpRun<'m, F> {
+    fn run_node<'s>(&'s self, scope: &rayon::Scope<'s>, id: usize) {
+        let node = self.nodes[id].as_ref().unwrap();
+        let (inputs, mut session) = {
+            let mut schedule = self.schedule.lock().unwrap();
+            if schedule.error.is_some() {
+                return;
+            }
+            if let Err(e) = self.cancellation.check() {
+                schedule.error = Some(e);
+                return;
+            }
+            let inputs: TVec<Arc<Tensor>> = node
+                .inputs
+                .iter()
+                .map(|i| schedule.values[i.node].as_ref().unwrap()[i.slot].clone())
+                .collect();
+            // flushing before the evaluation hands the last reference over, so
+            // the op can reuse the tensor
+            for i in node.inputs {
+                schedule.consumers[i.node] -= 1;
+                if schedule.consumers[i.node] == 0 {
+                    schedule.values[i.node] = None;
+                }
+            }
+            let mut session = match schedule.sessions.pop() {
+                Some(session) => session,
+                None => schedule.session_template.clone(),
+            };
+            session.resolved_symbols = schedule.resolved_symbols.clone();
+            (inputs, session)
+        };
+        let mut state = self.states[id].lock().unwrap();
+        let eval = || {
+            multithread_tract_scope(self.intra_op_executor.clone(), || match state.as_deref_mut() {
+                Some(state) => state.eval(&mut session, node.op, inputs),
+                None => node.op.eval(inputs),
+            })
+        };
+        let vs = if let Some(accuracy) = self.accuracy {
+            tract_linalg::accuracy::accuracy_scope(accuracy, eval)
+        } else {
+            eval()
+        }
+        .with_context(|| format!("Evaluating #{} \"{}\" {}", id, node.name, node.op.name()))
+        .and_then(|vs| {
+            if self.integer_only {
+                check_integer_only(format!("#{} \"{}\" {}", id, node.name, node.op.name()), &vs)?;
+            }
+            Ok(vs)
+        });
+        drop(state);
+        let mut schedule = self.schedule.lock().unwrap();
+        schedule.sessions.push(session);
+        let vs = match vs {
+            Ok(vs) => vs,
+            Err(e) => {
+                schedule.error.get_or_insert(e);
+                return;
+            }
+        };
+        if self.has_unresolved_symbols {
+            for (o, v) in node.outputs.iter().zip(vs.iter()) {
+                if let Ok(f) = o.fact.to_typed_fact() {
+                    for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
+                        resolve(
+                            &mut schedule.resolved_symbols,
+                            &dim_abstract,
+                            *dim_concrete as i64,
+                        );
+                    }
+                }
+            }
+        }
+        schedule.values[id] = Some(vs);
+        for &succ in &node.successors {
+            schedule.pending[succ] -= 1;
+            if schedule.pending[succ] == 0 {
+                scope.spawn(move |scope| self.run_node(scope, succ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model() -> TractResult<(TypedModel, Symbol)> {
+        let s = Symbol::new('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[s.to_dim(), 2.to_dim()]))?;
+        let relu = model.wire_node(
+            "relu",
+            crate::ops::math::max::unary(rctensor2(&[[0f32]])),
+            &[source],
+        )?;
+        model.set_output_outlets(&relu)?;
+        Ok((model.into_optimized()?, s))
+    }
+
+    #[test]
+    fn plan_for_some_outputs() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let relu =
+            model.wire_node("relu", crate::ops::math::max::unary(rctensor1(&[0f32])), &[source])?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        model.set_output_outlets(&[relu[0], neg[0]])?;
+        let model = Arc::new(model.into_decluttered()?);
+        let relu_plan = SimplePlan::new_for_output_names(model.clone(), &["relu"])?;
+        let neg_plan = SimplePlan::new_for_output_names(model.clone(), &["neg"])?;
+        let neg_id = model.node_by_name("neg")?.id;
+        assert!(!relu_plan.order.contains(&neg_id));
+        assert!(neg_plan.order.contains(&neg_id));
+        let input = tensor1(&[-1f32, 1.0]);
+        assert_eq!(*relu_plan.run(tvec!(input.clone()))?[0], tensor1(&[0f32, 1.0]));
+        assert_eq!(*neg_plan.run(tvec!(input))?[0], tensor1(&[1f32, -1.0]));
+        assert!(SimplePlan::new_for_output_names(model, &["missing"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn run_for_some_outputs() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let opaque = crate::ops::opaque::OpaqueOp {
+            kind: "custom".into(),
+            attributes: vec![],
+            output_facts: tvec!(f32::fact(&[2])),
+        };
+        let opaque = model.wire_node("opaque", opaque, &[source])?;
+        let relu =
+            model.wire_node("relu", crate::ops::math::max::unary(rctensor1(&[0f32])), &[source])?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &relu)?;
+        model.set_output_outlets(&[opaque[0], neg[0]])?;
+        let plan = SimplePlan::new(model)?;
+        let input = tensor1(&[-1f32, 1.0]);
+        // the opaque op has no evaluation function, so a full run fails
+        assert!(plan.run(tvec!(input.clone())).is_err());
+        let found = plan.run_for_outputs(tvec!(input.clone()), &[neg[0]])?;
+        assert_eq!(*found[0], tensor1(&[0f32, -1.0]));
+        let mut state = SimpleState::new(&plan)?;
+        let found = state.run_for_outputs(tvec!(input.clone()), &[relu[0], neg[0]])?;
+        assert_eq!(*found[0], tensor1(&[0f32, 1.0]));
+        assert_eq!(*found[1], tensor1(&[0f32, -1.0]));
+        assert!(state.run_for_outputs(tvec!(input), &[OutletId::new(neg[0].node, 1)]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn run_into_buffers() -> TractResult<()> {
+        use crate::ops::matmul::MatMulUnary;
+        let mut model = TypedModel::default();
+        let source = model.add_source("b", f32::fact(&[8, 3]))?;
+        let a: Vec<f32> = (0..64).map(|i| (i % 5) as f32 / 4.0 - 0.5).collect();
+        let a = tensor1(&a).into_shape(&[8, 8])?.into_arc_tensor();
+        let op = MatMulUnary { a, a_trans: false, b_trans: false, c_trans: false };
+        let mm = model.wire_node("mm", op, &[source])?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        model.set_output_outlets(&[mm[0], neg[0]])?;
+        let plan = SimplePlan::new(model.into_optimized()?)?;
+        let mut state = SimpleState::new(&plan)?;
+        let mut outputs = [Tensor::zero::<f32>(&[8, 3])?, Tensor::zero::<f32>(&[8, 3])?];
+        let buffers = [outputs[0].as_ptr::<f32>()?, outputs[1].as_ptr::<f32>()?];
+        for n in 0..3 {
+            let b: Vec<f32> = (0..24).map(|i| ((i + n) % 7) as f32 - 3.0).collect();
+            let b = tensor1(&b).into_shape(&[8, 3])?;
+            let expected = plan.run(tvec!(b.clone()))?;
+            state.run_into(tvec!(b), &mut outputs)?;
+            assert_eq!(outputs[0], *expected[0]);
+            assert_eq!(outputs[1], *expected[1]);
+            assert_eq!([outputs[0].as_ptr::<f32>()?, outputs[1].as_ptr::<f32>()?], buffers);
+        }
+        let mut outputs = [Tensor::zero::<f32>(&[8, 3])?, Tensor::zero::<f32>(&[3])?];
+        state.run_into(tvec!(Tensor::zero::<f32>(&[8, 3])?), &mut outputs)?;
+        assert_eq!(outputs[1], Tensor::zero::<f32>(&[8, 3])?);
+        assert!(state.run_into(tvec!(Tensor::zero::<f32>(&[8, 3])?), &mut outputs[..1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_input() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[4]))?;
+        let relu =
+            model.wire_node("relu", crate::ops::math::max::unary(rctensor1(&[0f32])), &[source])?;
+        model.set_output_outlets(&[source, relu[0]])?;
+        let plan = SimplePlan::new(model.into_optimized()?)?;
+        let frame: Arc<[f32]> = vec![-1f32, 2.0, -3.0, 4.0].into();
+        let input = Tensor::from_shared_slice(&[4], frame.clone())?;
+        let outputs = plan.run(tvec!(input))?;
+        // read as is, and copied before being written in place
+        assert_eq!(outputs[0].as_ptr::<f32>()?, frame.as_ptr());
+        assert_eq!(*outputs[1], tensor1(&[0f32, 2.0, 0.0, 4.0]));
+        assert_eq!(&*frame, &[-1f32, 2.0, -3.0, 4.0]);
+        Ok(())
+    }
+
+    #[test]
+    fn run_by_name() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let a = model.add_source("a", f32::fact(&[2]))?;
+        let b = model.add_source("b", f32::fact(&[2]))?;
+        let diff = model.wire_node("sub", crate::ops::math::sub::bin_typed(), &[a, b])?;
+        model.set_outlet_label(diff[0], "diff".to_string())?;
+        model.set_output_outlets(&[diff[0], b])?;
+        model.set_input_outlets(&[b, a])?;
+        let plan = SimplePlan::new(model)?;
+        let inputs: HashMap<&str, Tensor> =
+            vec![("a", tensor1(&[3f32, 4.0])), ("b", tensor1(&[1f32, 1.0]))].into_iter().collect();
+        let outputs = plan.run_by_name(inputs.clone())?;
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(*outputs["diff"], tensor1(&[2f32, 3.0]));
+        assert_eq!(*outputs["b"], tensor1(&[1f32, 1.0]));
+        let mut missing = inputs.clone();
+        missing.remove("a");
+        assert!(plan.run_by_name(missing).is_err());
+        let mut extra = inputs;
+        extra.insert("c", tensor1(&[0f32, 0.0]));
+        assert!(plan.run_by_name(extra).is_err());
+        Ok(())
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        nodes: Mutex<Vec<String>>,
+        abort_at: Option<String>,
+    }
+
+    impl NodeObserver<TypedFact, Box<dyn TypedOp>> for Recorder {
+        fn before_node(&self, node: &TypedNode, inputs: &[Arc<Tensor>]) -> TractResult<()> {
+            if Some(&node.name) == self.abort_at.as_ref() {
+                bail!("aborted with {} inputs", inputs.len());
+            }
+            Ok(())
+        }
+
+        fn after_node(&self, node: &TypedNode, outputs: &[Arc<Tensor>]) -> TractResult<()> {
+            self.nodes.lock().unwrap().push(format!("{}:{}", node.name, outputs[0].len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn observers() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &neg)?;
+        model.set_output_outlets(&exp)?;
+        let plan = SimplePlan::new(model)?;
+        let recorder = Arc::new(Recorder::default());
+        let mut state = SimpleState::new(&plan)?;
+        state.register_observer(recorder.clone());
+        state.run(tvec!(tensor1(&[1f32, 2.0, 3.0])))?;
+        assert_eq!(*recorder.nodes.lock().unwrap(), vec!["input:3", "neg:3", "exp:3"]);
+
+        let aborting = Arc::new(Recorder { abort_at: Some("exp".into()), ..Recorder::default() });
+        let mut state = SimpleState::new(&plan)?;
+        state.register_observer(aborting.clone());
+        let err = state.run(tvec!(tensor1(&[1f32, 2.0, 3.0]))).unwrap_err();
+        assert!(format!("{:?}", err).contains("aborted with 1 inputs"));
+        assert_eq!(*aborting.nodes.lock().unwrap(), vec!["input:3", "neg:3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn check_non_finite() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let ln = model.wire_node("ln", crate::ops::math::ln(), &neg)?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &ln)?;
+        model.set_output_outlets(&exp)?;
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        state.check_non_finite();
+        state.run(tvec!(tensor1(&[-1f32, -2.0, -3.0])))?;
+        let err = state.run(tvec!(tensor1(&[-1f32, 2.0, -3.0]))).unwrap_err();
+        let err = format!("{:?}", err);
+        assert!(err.contains("\"ln\""));
+        assert!(err.contains("has 1 NaN and 0 infinite values out of 3"));
+        assert!(err.contains("finite values in [-2, 3]"));
+        Ok(())
+    }
+
+    #[test]
+    fn run_capturing() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        model.set_outlet_label(neg[0], "negated".to_string())?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &neg)?;
+        model.set_output_outlets(&exp)?;
+        let plan = SimplePlan::new(model)?;
+        let (outputs, captured) =
+            plan.run_capturing(tvec!(tensor1(&[0f32, 1.0])), &["negated", "input"])?;
+        assert_eq!(*outputs[0], tensor1(&[1f32, (-1f32).exp()]));
+        assert_eq!(captured.len(), 2);
+        assert_eq!(*captured["negated"], tensor1(&[0f32, -1.0]));
+        assert_eq!(*captured["input"], tensor1(&[0f32, 1.0]));
+        assert!(plan.run_capturing(tvec!(tensor1(&[0f32, 1.0])), &["foo"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn peak_memory() -> TractResult<()> {
+        let s = Symbol::new('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[s.to_dim()]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &neg)?;
+        let bias = model.add_const("bias", tensor1(&[1f32]))?;
+        let add = model.wire_node("add", crate::ops::math::add::bin_typed(), &[exp[0], bias])?;
+        model.set_output_outlets(&[neg[0], add[0]])?;
+        let plan = SimplePlan::new(model)?;
+        // neg is an output: it stays alive while exp and add are computed
+        assert_eq!(plan.peak_memory(&SymbolValues::default().with(s, 10))?, 120);
+        assert_eq!(plan.peak_memory_for_input_shapes(&[&[10]])?, 120);
+        assert!(plan.peak_memory(&SymbolValues::default()).is_err());
+        assert!(plan.peak_memory_for_input_shapes(&[&[10, 2]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn warmup() -> TractResult<()> {
+        let (model, s) = model()?;
+        let plan = SimplePlan::new(model)?;
+        let mut state = plan.warmup(&SymbolValues::default().with(s, 3))?;
+        let output = state.run(tvec!(tensor2(&[[-1f32, 1.0]])))?;
+        assert_eq!(*output[0], tensor2(&[[0f32, 1.0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn warmup_requires_symbols() -> TractResult<()> {
+        let (model, _) = model()?;
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        assert!(state.warmup(&SymbolValues::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn inter_op() -> TractResult<()> {
+        use crate::ops::matmul::MatMulUnary;
+        let s = Symbol::new('S');
+        let mut model = TypedModel::default();
+        let source = model.add_source("b", f32::fact(&[8.to_dim(), s.to_dim()]))?;
+        let mut heads = tvec!();
+        for h in 0..4 {
+            let mut wire = source;
+            for l in 0..3 {
+                let a: Vec<f32> = (0..64).map(|i| ((i + h + l) % 5) as f32 / 4.0 - 0.5).collect();
+                let a = tensor1(&a).into_shape(&[8, 8])?.into_arc_tensor();
+                let op = MatMulUnary { a, a_trans: false, b_trans: false, c_trans: false };
+                wire = model.wire_node(format!("head.{}.{}", h, l), op, &[wire])?[0];
+            }
+            heads.push(wire);
+        }
+        let mut sum = heads[0];
+        for (ix, head) in heads[1..].iter().enumerate() {
+            sum = model.wire_node(
+                format!("sum.{}", ix),
+                crate::ops::math::add::bin_typed(),
+                &[sum, *head],
+            )?[0];
+        }
+        model.set_output_outlets(&[sum, heads[2]])?;
+        let model = Arc::new(model.into_optimized()?);
+        let sequential = SimplePlan::new(model.clone())?;
+        let parallel = SimplePlan::new(model)?.with_inter_op_executor(Executor::multithread(3)?);
+        let mut state = SimpleState::new(&parallel)?;
+        for n in &[5, 1, 17] {
+            let b: Vec<f32> = (0..8 * n).map(|i| (i % 7) as f32 - 3.0).collect();
+            let b = tensor1(&b).into_shape(&[8, *n])?;
+            let expected = sequential.run(tvec!(b.clone()))?;
+            let found = state.run(tvec!(b))?;
+            assert_eq!(found, expected);
+            assert_eq!(state.session_state.resolved_symbols[s], Some(*n as i64));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn run_with_symbols() -> TractResult<()> {
+        let s = Symbol::new('S');
+        let b = Symbol::new('B');
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[s.to_dim()]))?;
+        let slice = crate::ops::array::Slice::new(0, 0, b.to_dim());
+        let slice = model.wire_node("slice", slice, &[source])?;
+        model.set_output_outlets(&slice)?;
+        let plan = SimplePlan::new(model)?;
+        let input = tensor1(&[1f32, 2.0, 3.0, 4.0]);
+        let symbols: SymbolValues = vec![(b, 3), (s, 4)].into_iter().collect();
+        let output = plan.run_with_symbols(tvec!(input.clone()), &symbols)?;
+        assert_eq!(*output[0], tensor1(&[1f32, 2.0, 3.0]));
+        let mut state = SimpleState::new(&plan)?;
+        for n in 1..=2 {
+            let output =
+                state.run_with_symbols(tvec!(input.clone()), &symbols.clone().with(b, n))?;
+            assert_eq!(output[0].len(), n as usize);
+        }
+        assert!(state.run_with_symbols(tvec!(input), &symbols.with(s, 3)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn inter_op_error() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let opaque = crate::ops::opaque::OpaqueOp {
+            kind: "custom".into(),
+            attributes: vec![],
+            output_facts: tvec!(f32::fact(&[2])),
+        };
+        let opaque = model.wire_node("opaque", opaque, &[source])?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        model.set_output_outlets(&[opaque[0], neg[0]])?;
+        let plan = SimplePlan::new(model)?.with_inter_op_executor(Executor::multithread(2)?);
+        let mut state = SimpleState::new(&plan)?;
+        let err = state.run(tvec!(tensor1(&[1f32, 2.0]))).unwrap_err();
+        assert!(format!("{:?}", err).contains("No evaluation function registered"));
+        state.register_opaque_eval("custom", |_, inputs| Ok(inputs));
+        let outputs = state.run(tvec!(tensor1(&[1f32, 2.0])))?;
+        assert_eq!(*outputs[0], tensor1(&[1f32, 2.0]));
+        assert_eq!(*outputs[1], tensor1(&[-1f32, -2.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn deterministic() -> TractResult<()> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let opaque = crate::ops::opaque::OpaqueOp {
+            kind: "custom".into(),
+            attributes: vec![],
+            output_facts: tvec!(f32::fact(&[2])),
+        };
+        let opaque = model.wire_node("opaque", opaque, &[source])?;
+        model.set_output_outlets(&opaque)?;
+        let model = Arc::new(model);
+        let executor = Executor::multithread(2)?;
+        let run = |plan: &SimplePlan<_, _, _>| -> TractResult<usize> {
+            let threads = Arc::new(AtomicUsize::new(0));
+            let mut state = SimpleState::new(plan)?;
+            let recorded = threads.clone();
+            state.register_opaque_eval("custom", move |_, inputs| {
+                recorded.store(current_tract_executor().threads(), Ordering::SeqCst);
+                Ok(inputs)
+            });
+            multithread_tract_scope(executor.clone(), || state.run(tvec!(tensor1(&[1f32, 2.0]))))?;
+            Ok(threads.load(Ordering::SeqCst))
+        };
+        assert_eq!(run(&SimplePlan::new(model.clone())?)?, 2);
+        let plan = SimplePlan::new(model.clone())?
+            .with_inter_op_executor(executor.clone())
+            .with_deterministic()?;
+        assert_eq!(run(&plan)?, 1);
+        let pinned = tract_linalg::accuracy::accuracy_scope(tract_linalg::Accuracy::Fast, || {
+            SimplePlan::new(model.clone())?.with_deterministic()
+        })?;
+        assert_eq!(pinned.accuracy, Some(tract_linalg::Accuracy::Fast));
+
+        let mut autotuned = (*model).clone();
+        autotuned
+            .properties
+            .insert(crate::ops::cnn::conv::AUTOTUNE_PROPERTY.into(), rctensor0(1i64));
+        assert!(SimplePlan::new(autotuned)?.with_deterministic().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn integer_only() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", i8::fact(&[2]))?;
+        let opaque = crate::ops::opaque::OpaqueOp {
+            kind: "custom".into(),
+            attributes: vec![],
+            output_facts: tvec!(i8::fact(&[2])),
+        };
+        let opaque = model.wire_node("opaque", opaque, &[source])?;
+        let cast = model.wire_node("cast", crate::ops::cast::cast(f32::datum_type()), &opaque)?;
+        model.set_output_outlets(&cast)?;
+        assert!(SimplePlan::new(&model)?.with_integer_only().is_err());
+
+        let plan = SimplePlan::new_for_output(&model, opaque[0])?.with_integer_only()?;
+        let mut state = SimpleState::new(&plan)?;
+        state.register_opaque_eval("custom", |_, inputs| Ok(inputs));
+        state.run(tvec!(tensor1(&[1i8, 2])))?;
+        state.register_opaque_eval("custom", |_, _| Ok(tvec!(rctensor1(&[1f32, 2.0]))));
+        assert!(state.run(tvec!(tensor1(&[1i8, 2]))).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn cancellation() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let opaque = crate::ops::opaque::OpaqueOp {
+            kind: "custom".into(),
+            attributes: vec![],
+            output_facts: tvec!(f32::fact(&[2])),
+        };
+        let opaque = model.wire_node("opaque", opaque, &[source])?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &opaque)?;
+        model.set_output_outlets(&neg)?;
+        let model = Arc::new(model);
+        let sequential = SimplePlan::new(model.clone())?;
+        let parallel = SimplePlan::new(model)?.with_inter_op_executor(Executor::multithread(2)?);
+        for plan in &[sequential, parallel] {
+            let token = CancellationToken::new();
+            let mut state = SimpleState::new(plan)?;
+            let cancel = token.clone();
+            // cancels the run in progress, between opaque and neg
+            state.register_opaque_eval("custom", move |_, inputs| {
+                cancel.cancel();
+                Ok(inputs)
+            });
+            state.set_cancellation_token(Some(token));
+            let err = state.run(tvec!(tensor1(&[1f32, 2.0]))).unwrap_err();
+            assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Token));
+            state.set_cancellation_token(None);
+            state.set_deadline(Some(Instant::now()));
+            let err = state.run(tvec!(tensor1(&[1f32, 2.0]))).unwrap_err();
+            assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Deadline));
+            state.set_deadline(Some(Instant::now() + std::time::Duration::from_secs(3600)));
+            assert_eq!(*state.run(tvec!(tensor1(&[1f32, 2.0])))?[0], tensor1(&[-1f32, -2.0]));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn accuracy() -> TractResult<()> {
+        use tract_linalg::Accuracy;
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &[source])?;
+        model.set_output_outlets(&exp)?;
+        let model = Arc::new(model);
+        let input = tensor1(&[-3f32, 0.5, 7.0]);
+        for accuracy in &[Accuracy::Exact, Accuracy::Accurate, Accuracy::Fast] {
+            let plan = SimplePlan::new(model.clone())?.with_accuracy(*accuracy);
+            let found = plan.run(tvec!(input.clone()))?;
+            found[0].close_enough(&tensor1(&[(-3f32).exp(), 0.5f32.exp(), 7f32.exp()]), true)?;
+        }
+        let exact = SimplePlan::new(model.clone())?.with_accuracy(Accuracy::Exact);
+        let fast = SimplePlan::new(model.clone())?.with_accuracy(Accuracy::Fast);
+        assert_ne!(exact.run(tvec!(input.clone()))?, fast.run(tvec!(input.clone()))?);
+        let default = SimplePlan::new(model)?;
+        assert_eq!(exact.run(tvec!(input.clone()))?, default.run(tvec!(input))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+//! Placement of intermediate tensors in a pre-allocated buffer.
+//!
+//! Node outputs with a concrete shape get a region of an arena, computed from
+//! their liveness in the plan order: two values alive at the same step do not
+//! overlap. Regions are handed to the node evaluation through
+//! `tract_data::arena::offer`, so it only applies to outputs allocated with
+//! `Tensor::uninitialized*`, other ones still going to the heap.
+use std::fmt::{Debug, Display};
+use std::ops::Range;
+
+use crate::internal::*;
+use crate::model::{Fact, Graph, OutletId};
+use crate::ops::konst::Const;
+use tract_data::arena::ARENA_ALIGNMENT;
+
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct ArenaPlan {
+    /// Region of the arena for the output of each node, by node id.
+    pub regions: Vec<Option<Range<usize>>>,
+    /// Size of the arena, in bytes.
+    pub size: usize,
+}
+
+impl ArenaPlan {
+    /// Plans the arena for `order`. `last_use[node]` is the step at which
+    /// the outputs of node are used for the last time (0 if they are not
+    /// used).
+    pub fn new<F, O>(
+        model: &Graph<F, O>,
+        order: &[usize],
+        outputs: &[OutletId],
+        last_use: &[usize],
+    ) -> TractResult<ArenaPlan>
+    where
+        F: Fact + Hash + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+    {
+        let inputs = model.input_outlets()?;
+        // (node, bytes, first step, last step)
+        let mut values: Vec<(usize, usize, usize, usize)> = vec![];
+        let mut value_of: Vec<Option<usize>> = vec![None; model.nodes().len()];
+        // (node, node evaluated in place in it)
+        let mut aliases: Vec<(usize, usize)> = vec![];
+        for (step, &n) in order.iter().enumerate() {
+            let node = model.node(n);
+            if node.outputs.len() != 1
+                || outputs.iter().any(|o| o.node == n)
+                || inputs.iter().any(|i| i.node == n)
+                || node.op_is::<Const>()
+            {
+                continue;
+            }
+            let fact = if let Ok(fact) = node.outputs[0].fact.to_typed_fact() {
+                fact
+            } else {
+                continue;
+            };
+            let shape = if let Some(shape) = fact.shape.as_concrete() {
+                shape
+            } else {
+                continue;
+            };
+            if !fact.datum_type.is_copy() {
+                continue;
+            }
+            let bytes = shape.iter().product::<usize>() * fact.datum_type.size_of();
+            if bytes == 0 {
+                continue;
+            }
+            // an output written in its last use input extends the input liveness
+            let in_place = node.op().eval_in_place_input().and_then(|ix| node.inputs.get(ix));
+            let in_place = in_place.filter(|input| {
+                let input_fact =
+                    model.outlet_fact(**input).ok().and_then(|f| f.to_typed_fact().ok());
+                let same_fact =
+                    input_fact.map(|f| f.datum_type == fact.datum_type && f.shape == fact.shape);
+                last_use[input.node] == step
+                    && node.inputs.iter().filter(|i| i == input).count() == 1
+                    && same_fact == Some(true)
+            });
+            if let Some(k) = in_place.and_then(|input| value_of[input.node]) {
+                values[k].3 = values[k].3.max(last_use[n]);
+                value_of[n] = Some(k);
+                aliases.push((values[k].0, n));
+            } else {
+                value_of[n] = Some(values.len());
+                values.push((n, bytes, step, last_use[n].max(step)));
+            }
+        }
+        values.sort_by_key(|v| (std::cmp::Reverse(v.1), v.2));
+        let mut regions = vec![None; model.nodes().len()];
+        let mut placed: Vec<(Range<usize>, usize, usize)> = vec![];
+        let mut size = 0;
+        for (node, bytes, first, last) in values {
+            let mut conflicts: Vec<&Range<usize>> = placed
+                .iter()
+                .filter(|(_, f, l)| *f <= last && first <= *l)
+                .map(|(r, _, _)| r)
+                .collect();
+            conflicts.sort_by_key(|r| r.start);
+            let mut offset = 0;
+            for r in conflicts {
+                if offset + bytes <= r.start {
+                    break;
+                }
+                offset =
+                    offset.max((r.end + ARENA_ALIGNMENT - 1) / ARENA_ALIGNMENT * ARENA_ALIGNMENT);
+            }
+            size = size.max(offset + bytes);
+            regions[node] = Some(offset..offset + bytes);
+            placed.push((offset..offset + bytes, first, last));
+        }
+        for (root, node) in aliases {
+            regions[node] = regions[root].clone();
+        }
+        Ok(ArenaPlan { regions, size })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    #[test]
+    fn reuse_dead_values() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let mut wire = model.add_source("input", f32::fact(&[16]))?;
+        for i in 0..4 {
+            wire = model.wire_node(format!("add.{}", i), math::add::bin_typed(), &[wire, wire])?[0];
+        }
+        model.set_output_outlets(&[wire])?;
+        let plan = SimplePlan::new(&model)?;
+        let arena = plan.arena.as_ref().unwrap();
+        // add.0 to add.2 are planned, only two of them alive at a time
+        assert_eq!(arena.size, 64 + 64);
+        for i in 0..3 {
+            let node = model.node_by_name(format!("add.{}", i))?.id;
+            let next = model.node_by_name(format!("add.{}", i + 1))?.id;
+            assert!(arena.regions[node].is_some());
+            if i < 2 {
+                let (a, b) =
+                    (arena.regions[node].clone().unwrap(), arena.regions[next].clone().unwrap());
+                assert!(a.end <= b.start || b.end <= a.start);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn in_place_shares_region() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[16]))?;
+        let a = model.wire_node("a", math::add::bin_typed(), &[source, source])?;
+        let b = model.wire_node("b", math::neg(), &a)?;
+        let c = model.wire_node("c", math::add::bin_typed(), &[b[0], b[0]])?;
+        let d = model.wire_node("d", math::neg(), &c)?;
+        model.set_output_outlets(&d)?;
+        let plan = SimplePlan::new(&model)?;
+        let arena = plan.arena.as_ref().unwrap();
+        let region = |name: &str| -> TractResult<Option<Range<usize>>> {
+            Ok(arena.regions[model.node_by_name(name)?.id].clone())
+        };
+        // b is computed in a, c reads b twice so it can not be written in it
+        assert_eq!(region("a")?, region("b")?);
+        assert!(region("c")?.is_some());
+        assert_ne!(region("b")?, region("c")?);
+        assert_eq!(arena.size, 128);
+        Ok(())
+    }
+
+    #[test]
+    fn run_in_arena() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let a = model.wire_node("a", math::add::unary(rctensor1(&[1f32, 2.0, 3.0])), &[source])?;
+        let b = model.wire_node("b", math::mul::unary(rctensor1(&[2f32, 2.0, 2.0])), &a)?;
+        let c = model.wire_node("c", math::add::bin_typed(), &[a[0], b[0]])?;
+        model.set_output_outlets(&c)?;
+        let plan = SimplePlan::new(model)?;
+        assert!(plan.arena.is_some());
+        let mut state = SimpleState::new(&plan)?;
+        for _ in 0..3 {
+            let output = state.run(tvec!(tensor1(&[1f32, 0.0, -1.0])))?;
+            assert_eq!(*output[0], tensor1(&[6f32, 6.0, 6.0]));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn mat_mul_outputs_in_arena() -> TractResult<()> {
+        use crate::ops::matmul::MatMulUnary;
+        let mut model = TypedModel::default();
+        let mut wire = model.add_source("b", f32::fact(&[8, 3]))?;
+        for l in 0..3 {
+            let a: Vec<f32> = (0..64).map(|i| ((i + l) % 5) as f32 / 4.0 - 0.5).collect();
+            let a = tensor1(&a).into_shape(&[8, 8])?.into_arc_tensor();
+            let op = MatMulUnary { a, a_trans: false, b_trans: false, c_trans: false };
+            wire = model.wire_node(format!("mm.{}", l), op, &[wire])?[0];
+        }
+        model.set_output_outlets(&[wire])?;
+        let model = model.into_optimized()?;
+        let expected = SimplePlan::new(&model)?.run(tvec!(Tensor::zero::<f32>(&[8, 3])?))?;
+        let plan = SimplePlan::new(&model)?;
+        let mut state = SimpleState::new(&plan)?;
+        let arena = state.arena.clone().unwrap();
+        let mut leased = 0;
+        let found = state.run_plan_with_eval(
+            tvec!(Tensor::zero::<f32>(&[8, 3])?),
+            |session, op_state, node, inputs| {
+                let outputs = crate::plan::eval(session, op_state, node, inputs);
+                leased = leased.max(arena.leases());
+                outputs
+            },
+        )?;
+        assert_eq!(found, expected);
+        assert!(leased > 0);
+        assert_eq!(arena.leases(), 0);
+        Ok(())
+    }
+}
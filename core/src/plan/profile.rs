@@ -0,0 +1,171 @@
+//! Per-node profiling of the runs of a plan.
+//!
+//! A `Profiler` is a node observer: registered on a `SimpleState`, it
+//! accumulates, for every node, the time spent evaluating it, the number of
+//! evaluations and the bytes of the tensors it produced, over all the runs
+//! until it is reset.
+use std::fmt::{Debug, Display};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::NodeObserver;
+use crate::internal::*;
+use crate::model::Fact;
+
+/// Accumulated measures of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeProfile {
+    pub node: usize,
+    pub name: String,
+    pub op: String,
+    /// Number of evaluations of the node.
+    pub invocations: usize,
+    /// Wall time spent in the evaluations of the node.
+    pub time: Duration,
+    /// Bytes of the tensors produced by the node, not counting the outputs
+    /// sharing the buffer of one of its inputs (passed through or computed
+    /// in place).
+    pub allocated_bytes: usize,
+}
+
+/// Profile of the nodes evaluated since the profiler was created or reset,
+/// in their order of first evaluation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub nodes: Vec<NodeProfile>,
+}
+
+impl ProfileReport {
+    pub fn total_time(&self) -> Duration {
+        self.nodes.iter().map(|n| n.time).sum()
+    }
+
+    pub fn total_allocated_bytes(&self) -> usize {
+        self.nodes.iter().map(|n| n.allocated_bytes).sum()
+    }
+
+    /// Time spent per kind of operator, the most expensive first.
+    pub fn time_by_op(&self) -> Vec<(String, Duration)> {
+        let mut by_op: HashMap<&str, Duration> = HashMap::default();
+        for n in &self.nodes {
+            *by_op.entry(&n.op).or_default() += n.time;
+        }
+        let mut by_op: Vec<(String, Duration)> =
+            by_op.into_iter().map(|(op, time)| (op.to_string(), time)).collect();
+        by_op.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        by_op
+    }
+
+    /// The `n` nodes the evaluations took the longest, the slowest first.
+    pub fn slowest(&self, n: usize) -> Vec<&NodeProfile> {
+        let mut nodes: Vec<&NodeProfile> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| b.time.cmp(&a.time));
+        nodes.truncate(n);
+        nodes
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    /// Index in the report of each profiled node, by node id.
+    index: HashMap<usize, usize>,
+    report: ProfileReport,
+    /// Start of the evaluation in progress, with the addresses of the
+    /// buffers of its inputs. The inputs themselves are not retained, as
+    /// it would prevent in-place evaluations.
+    current: Option<(Instant, TVec<usize>)>,
+}
+
+/// Observer collecting a `ProfileReport`, see `SimpleState::profile`.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    state: Mutex<ProfilerState>,
+}
+
+impl Profiler {
+    /// The profile of the nodes evaluated so far.
+    pub fn report(&self) -> ProfileReport {
+        self.state.lock().unwrap().report.clone()
+    }
+
+    /// Forgets the measures collected so far.
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = ProfilerState::default();
+    }
+}
+
+impl<F, O> NodeObserver<F, O> for Profiler
+where
+    F: Fact + Hash + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static + Hash,
+{
+    fn before_node(&self, _node: &Node<F, O>, inputs: &[Arc<Tensor>]) -> TractResult<()> {
+        let inputs = inputs.iter().map(|i| buffer_address(i)).collect();
+        self.state.lock().unwrap().current = Some((Instant::now(), inputs));
+        Ok(())
+    }
+
+    fn after_node(&self, node: &Node<F, O>, outputs: &[Arc<Tensor>]) -> TractResult<()> {
+        let end = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let (start, inputs) =
+            state.current.take().with_context(|| format!("{} was not started", node))?;
+        let allocated_bytes = outputs
+            .iter()
+            .filter(|o| !inputs.contains(&buffer_address(o)))
+            .map(|o| o.len() * o.datum_type().size_of())
+            .sum::<usize>();
+        let ProfilerState { index, report, .. } = &mut *state;
+        let ix = *index.entry(node.id).or_insert_with(|| {
+            report.nodes.push(NodeProfile {
+                node: node.id,
+                name: node.name.clone(),
+                op: node.op().name().to_string(),
+                invocations: 0,
+                time: Duration::default(),
+                allocated_bytes: 0,
+            });
+            report.nodes.len() - 1
+        });
+        let profile = &mut report.nodes[ix];
+        profile.invocations += 1;
+        profile.time += end - start;
+        profile.allocated_bytes += allocated_bytes;
+        Ok(())
+    }
+}
+
+fn buffer_address(tensor: &Tensor) -> usize {
+    unsafe { tensor.as_ptr_unchecked::<u8>() as usize }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn profile_runs() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[4]))?;
+        let neg = model.wire_node("neg", crate::ops::math::neg(), &[source])?;
+        let exp = model.wire_node("exp", crate::ops::math::exp(), &neg)?;
+        model.set_output_outlets(&exp)?;
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        let profiler = state.profile();
+        for _ in 0..3 {
+            state.run(tvec!(tensor1(&[1f32, 2.0, 3.0, 4.0])))?;
+        }
+        let report = profiler.report();
+        let names: Vec<&str> = report.nodes.iter().map(|n| &*n.name).collect();
+        assert_eq!(names, vec!["input", "neg", "exp"]);
+        assert!(report.nodes.iter().all(|n| n.invocations == 3));
+        assert_eq!(report.nodes[0].allocated_bytes, 3 * 16);
+        assert_eq!(report.total_time(), report.nodes.iter().map(|n| n.time).sum());
+        assert_eq!(report.slowest(2).len(), 2);
+        assert_eq!(report.time_by_op().len(), 3);
+        profiler.reset();
+        assert!(profiler.report().nodes.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+use crate::f16::f16;
+use std::{fmt, ops};
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, PartialEq, PartialOrd, Debug)]
+pub struct bf16(pub half::bf16);
+
+macro_rules! binary_bf16 {
+    ($f:ident) => {
+        fn $f(self, other: bf16) -> bf16 {
+            (self.0).to_f32().$f((other.0).to_f32()).into()
+        }
+    };
+}
+
+macro_rules! unary_as_f32 {
+    ($f:ident) => {
+        fn $f(self) -> bf16 {
+            (self.0).to_f32().$f().into()
+        }
+    };
+}
+
+macro_rules! unary_bf16 {
+    ($f:ident, $t:ty) => {
+        fn $f(self) -> $t {
+            (self.0).$f()
+        }
+    };
+}
+
+macro_rules! const_bf16 {
+    ($f:ident, $c:ident) => {
+        fn $f() -> bf16 {
+            bf16(half::bf16::$c)
+        }
+    };
+}
+
+#[allow(deprecated)]
+impl num_traits::Float for bf16 {
+    unary_as_f32!(floor);
+    unary_as_f32!(ceil);
+    unary_as_f32!(round);
+    unary_as_f32!(trunc);
+    unary_as_f32!(fract);
+    unary_as_f32!(abs);
+    unary_as_f32!(recip);
+    unary_as_f32!(sqrt);
+    unary_as_f32!(exp);
+    unary_as_f32!(exp2);
+    unary_as_f32!(ln);
+    unary_as_f32!(log2);
+    unary_as_f32!(log10);
+    unary_as_f32!(cbrt);
+    unary_as_f32!(sin);
+    unary_as_f32!(cos);
+    unary_as_f32!(tan);
+    unary_as_f32!(sinh);
+    unary_as_f32!(cosh);
+    unary_as_f32!(tanh);
+    unary_as_f32!(asin);
+    unary_as_f32!(acos);
+    unary_as_f32!(atan);
+    unary_as_f32!(asinh);
+    unary_as_f32!(acosh);
+    unary_as_f32!(atanh);
+    unary_as_f32!(exp_m1);
+    unary_as_f32!(ln_1p);
+    unary_bf16!(classify, ::std::num::FpCategory);
+    unary_bf16!(is_nan, bool);
+    unary_bf16!(is_infinite, bool);
+    unary_bf16!(is_finite, bool);
+    unary_bf16!(is_normal, bool);
+    unary_bf16!(is_sign_positive, bool);
+    unary_bf16!(is_sign_negative, bool);
+    binary_bf16!(powf);
+    binary_bf16!(log);
+    binary_bf16!(max);
+    binary_bf16!(min);
+    binary_bf16!(abs_sub);
+    binary_bf16!(hypot);
+    binary_bf16!(atan2);
+    const_bf16!(nan, NAN);
+    const_bf16!(infinity, INFINITY);
+    const_bf16!(neg_infinity, NEG_INFINITY);
+    const_bf16!(neg_zero, NEG_ZERO);
+    const_bf16!(max_value, MAX);
+    const_bf16!(min_value, MIN);
+    const_bf16!(min_positive_value, MIN_POSITIVE);
+    fn signum(self) -> bf16 {
+        bf16(self.0.signum())
+    }
+    fn mul_add(self, a: bf16, b: bf16) -> bf16 {
+        (self.0).to_f32().mul_add((a.0).to_f32(), (b.0).to_f32()).into()
+    }
+    fn powi(self, i: i32) -> bf16 {
+        (self.0).to_f32().powi(i).into()
+    }
+    fn sin_cos(self) -> (bf16, bf16) {
+        let (s, c) = (self.0).to_f32().sin_cos();
+        (s.into(), c.into())
+    }
+    fn integer_decode(self) -> (u64, i16, i8) {
+        (self.0).to_f32().integer_decode()
+    }
+}
+
+impl num_traits::Num for bf16 {
+    type FromStrRadixErr = <f32 as num_traits::Num>::FromStrRadixErr;
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        f32::from_str_radix(str, radix).map(|it| it.into())
+    }
+}
+
+impl num_traits::Zero for bf16 {
+    fn is_zero(&self) -> bool {
+        f32::from(self.0).is_zero()
+    }
+    fn zero() -> bf16 {
+        0.0f32.into()
+    }
+}
+
+impl num_traits::One for bf16 {
+    fn one() -> bf16 {
+        1.0f32.into()
+    }
+}
+
+impl num_traits::ToPrimitive for bf16 {
+    fn to_i64(&self) -> Option<i64> {
+        f32::from(self.0).to_i64()
+    }
+    fn to_u64(&self) -> Option<u64> {
+        f32::from(self.0).to_u64()
+    }
+}
+
+impl num_traits::AsPrimitive<f32> for bf16 {
+    fn as_(self) -> f32 {
+        self.0.to_f32()
+    }
+}
+
+impl num_traits::AsPrimitive<bf16> for f32 {
+    fn as_(self) -> bf16 {
+        bf16(half::bf16::from_f32(self))
+    }
+}
+
+impl num_traits::AsPrimitive<f64> for bf16 {
+    fn as_(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+impl num_traits::AsPrimitive<bf16> for f64 {
+    fn as_(self) -> bf16 {
+        bf16(half::bf16::from_f64(self))
+    }
+}
+
+impl num_traits::NumCast for bf16 {
+    fn from<T: num_traits::ToPrimitive>(n: T) -> Option<Self> {
+        n.to_f32().map(|f| bf16(half::bf16::from_f32(f)))
+    }
+}
+
+impl num_traits::FromPrimitive for bf16 {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(bf16(half::bf16::from_f64(n as f64)))
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(bf16(half::bf16::from_f64(n as f64)))
+    }
+    fn from_f32(f: f32) -> Option<Self> {
+        Some(bf16(half::bf16::from_f32(f)))
+    }
+    fn from_f64(f: f64) -> Option<Self> {
+        Some(bf16(half::bf16::from_f64(f)))
+    }
+}
+
+impl num_traits::Bounded for bf16 {
+    fn min_value() -> bf16 {
+        bf16(half::bf16::MIN)
+    }
+    fn max_value() -> bf16 {
+        bf16(half::bf16::MAX)
+    }
+}
+
+impl ops::Neg for bf16 {
+    type Output = bf16;
+    fn neg(self) -> bf16 {
+        self.0.to_f32().neg().into()
+    }
+}
+
+impl num_traits::Signed for bf16 {
+    fn abs(&self) -> Self {
+        use std::ops::Neg;
+        if self.is_negative() {
+            (*self).neg()
+        } else {
+            *self
+        }
+    }
+
+    fn abs_sub(&self, other: &Self) -> Self {
+        (*self - *other).abs()
+    }
+
+    fn signum(&self) -> Self {
+        bf16(self.0.signum())
+    }
+
+    fn is_positive(&self) -> bool {
+        self.0.is_sign_positive()
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0.is_sign_negative()
+    }
+}
+
+impl From<f32> for bf16 {
+    fn from(f: f32) -> bf16 {
+        bf16(half::bf16::from_f32(f))
+    }
+}
+
+impl fmt::Display for bf16 {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl num_traits::AsPrimitive<bf16> for bf16 {
+    fn as_(self) -> bf16 {
+        self
+    }
+}
+
+macro_rules! as_prim {
+    ($t: ty) => {
+        impl num_traits::AsPrimitive<bf16> for $t {
+            fn as_(self) -> bf16 {
+                bf16(half::bf16::from_f64(self as f64))
+            }
+        }
+        impl num_traits::AsPrimitive<$t> for bf16 {
+            fn as_(self) -> $t {
+                self.0.to_f64() as _
+            }
+        }
+    };
+}
+
+as_prim!(isize);
+as_prim!(usize);
+as_prim!(i8);
+as_prim!(i16);
+as_prim!(i32);
+as_prim!(i64);
+as_prim!(u8);
+as_prim!(u16);
+as_prim!(u32);
+as_prim!(u64);
+
+impl ops::Add<bf16> for bf16 {
+    type Output = bf16;
+    fn add(self, other: bf16) -> bf16 {
+        (self.0.to_f32() + other.0.to_f32()).into()
+    }
+}
+
+impl ops::Add<&bf16> for bf16 {
+    type Output = bf16;
+    fn add(self, other: &bf16) -> bf16 {
+        (self.0.to_f32() + other.0.to_f32()).into()
+    }
+}
+
+impl ops::AddAssign<bf16> for bf16 {
+    fn add_assign(&mut self, other: bf16) {
+        *self = *self + other
+    }
+}
+
+impl ops::Sub<bf16> for bf16 {
+    type Output = bf16;
+    fn sub(self, other: bf16) -> bf16 {
+        (self.0.to_f32() - other.0.to_f32()).into()
+    }
+}
+
+impl ops::Sub<&bf16> for bf16 {
+    type Output = bf16;
+    fn sub(self, other: &bf16) -> bf16 {
+        (self.0.to_f32() - other.0.to_f32()).into()
+    }
+}
+
+impl ops::Mul<bf16> for bf16 {
+    type Output = bf16;
+    fn mul(self, other: bf16) -> bf16 {
+        (self.0.to_f32() * other.0.to_f32()).into()
+    }
+}
+
+impl ops::Mul<&bf16> for bf16 {
+    type Output = bf16;
+    fn mul(self, other: &bf16) -> bf16 {
+        (self.0.to_f32() * other.0.to_f32()).into()
+    }
+}
+
+impl ops::Div<bf16> for bf16 {
+    type Output = bf16;
+    fn div(self, other: bf16) -> bf16 {
+        (self.0.to_f32() / other.0.to_f32()).into()
+    }
+}
+
+impl ops::DivAssign<bf16> for bf16 {
+    fn div_assign(&mut self, other: bf16) {
+        self.0 = half::bf16::from_f32(self.0.to_f32() / other.0.to_f32())
+    }
+}
+
+impl ops::Div<&bf16> for bf16 {
+    type Output = bf16;
+    fn div(self, other: &bf16) -> bf16 {
+        (self.0.to_f32() / other.0.to_f32()).into()
+    }
+}
+
+impl ops::Rem<bf16> for bf16 {
+    type Output = bf16;
+    fn rem(self, other: bf16) -> bf16 {
+        (self.0.to_f32() % other.0.to_f32()).into()
+    }
+}
+
+impl ops::Rem<&bf16> for bf16 {
+    type Output = bf16;
+    fn rem(self, other: &bf16) -> bf16 {
+        (self.0.to_f32() % other.0.to_f32()).into()
+    }
+}
+
+impl std::iter::Sum for bf16 {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = bf16>,
+    {
+        iter.fold(0.0f32, |acc, i| acc + i.0.to_f32()).into()
+    }
+}
+
+impl<'a> std::iter::Sum<&'a bf16> for bf16 {
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = &'a bf16>,
+    {
+        iter.fold(0.0f32, |acc, i| acc + i.0.to_f32()).into()
+    }
+}
+
+impl std::str::FromStr for bf16 {
+    type Err = std::num::ParseFloatError;
+    fn from_str(s: &str) -> Result<bf16, Self::Err> {
+        s.parse::<f32>().map(|f| f.into())
+    }
+}
+
+impl num_traits::AsPrimitive<f16> for bf16 {
+    fn as_(self) -> f16 {
+        f16(half::f16::from_f32(self.0.to_f32()))
+    }
+}
+
+impl num_traits::AsPrimitive<bf16> for f16 {
+    fn as_(self) -> bf16 {
+        bf16(half::bf16::from_f32(self.0.to_f32()))
+    }
+}
+
+impl ndarray::ScalarOperand for bf16 {}
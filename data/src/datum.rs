@@ -1,6 +1,8 @@
 //! `Tensor` is the main data container for tract
+use crate::bf16::bf16;
 use crate::dim::TDim;
 use crate::f16::f16;
+use crate::f8::{f8e4m3, f8e5m2};
 use crate::tensor::litteral::*;
 use crate::tensor::Tensor;
 use crate::TVec;
@@ -106,8 +108,11 @@ pub enum DatumType {
     I32,
     I64,
     F16,
+    BF16,
     F32,
     F64,
+    F8E4M3,
+    F8E5M2,
     TDim,
     Blob,
     String,
@@ -125,7 +130,12 @@ pub enum DatumType {
 impl DatumType {
     pub fn super_types(&self) -> TVec<DatumType> {
         use DatumType::*;
-        if *self == String || *self == TDim || *self == Blob || *self == Bool || self.is_quantized()
+        if *self == String
+            || *self == TDim
+            || *self == Blob
+            || *self == Bool
+            || self.is_quantized()
+            || self.is_fp8()
         {
             tvec!(*self)
         } else if self.is_complex_float() {
@@ -140,6 +150,8 @@ impl DatumType {
                 .filter(|s| s.size_of() >= self.size_of())
                 .copied()
                 .collect()
+        } else if *self == BF16 {
+            tvec!(BF16, F32, F64)
         } else if self.is_float() {
             [F16, F32, F64].iter().filter(|s| s.size_of() >= self.size_of()).copied().collect()
         } else if self.is_signed() {
@@ -196,7 +208,11 @@ impl DatumType {
     }
 
     pub fn is_float(&self) -> bool {
-        matches!(self, DatumType::F16 | DatumType::F32 | DatumType::F64)
+        matches!(self, DatumType::F16 | DatumType::BF16 | DatumType::F32 | DatumType::F64)
+    }
+
+    pub fn is_fp8(&self) -> bool {
+        matches!(self, DatumType::F8E4M3 | DatumType::F8E5M2)
     }
 
     pub fn is_complex(&self) -> bool {
@@ -216,6 +232,7 @@ impl DatumType {
             || self.is_unsigned()
             || self.is_signed()
             || self.is_float()
+            || self.is_fp8()
             || self.is_complex()
     }
 
@@ -300,6 +317,7 @@ impl DatumType {
             DatumType::I32 => tensor0(i32::MIN),
             DatumType::I64 => tensor0(i64::MIN),
             DatumType::F16 => tensor0(f16(half::f16::MIN)),
+            DatumType::BF16 => tensor0(bf16(half::bf16::MIN)),
             DatumType::F32 => tensor0(f32::MIN),
             DatumType::F64 => tensor0(f64::MIN),
             _ => panic!("No min value for datum type {:?}", self),
@@ -317,6 +335,7 @@ impl DatumType {
             DatumType::I64 => tensor0(i64::MAX),
             DatumType::QI32(_) => tensor0(i32::MAX),
             DatumType::F16 => tensor0(f16(half::f16::MAX)),
+            DatumType::BF16 => tensor0(bf16(half::bf16::MAX)),
             DatumType::F32 => tensor0(f32::MAX),
             DatumType::F64 => tensor0(f64::MAX),
             _ => panic!("No max value for datum type {:?}", self),
@@ -338,8 +357,11 @@ impl std::str::FromStr for DatumType {
             "U32" | "u32" => Ok(DatumType::U32),
             "U64" | "u64" => Ok(DatumType::U64),
             "F16" | "f16" => Ok(DatumType::F16),
+            "BF16" | "bf16" => Ok(DatumType::BF16),
             "F32" | "f32" => Ok(DatumType::F32),
             "F64" | "f64" => Ok(DatumType::F64),
+            "F8E4M3" | "f8e4m3" => Ok(DatumType::F8E4M3),
+            "F8E5M2" | "f8e5m2" => Ok(DatumType::F8E5M2),
             "Bool" | "bool" => Ok(DatumType::Bool),
             "Blob" | "blob" => Ok(DatumType::Blob),
             "String" | "string" => Ok(DatumType::String),
@@ -431,8 +453,11 @@ macro_rules! datum {
 
 datum!(bool, Bool);
 datum!(f16, F16);
+datum!(bf16, BF16);
 datum!(f32, F32);
 datum!(f64, F64);
+datum!(f8e4m3, F8E4M3);
+datum!(f8e5m2, F8E5M2);
 datum!(i8, I8);
 datum!(i16, I16);
 datum!(i32, I32);
@@ -483,4 +508,43 @@ mod tests {
         let t_i64: Tensor = tensor1(&[0i64]);
         t_i64.cast_to::<bool>().unwrap();
     }
+
+    #[test]
+    fn test_cast_bf16() {
+        let t_f32: Tensor = tensor1(&[1f32, -2.5, 3.0e10]);
+        let t_bf16 = t_f32.cast_to::<bf16>().unwrap();
+        assert_eq!(t_bf16.datum_type(), DatumType::BF16);
+        assert_eq!(
+            t_bf16.cast_to::<f32>().unwrap().as_slice::<f32>().unwrap(),
+            &[1.0, -2.5, 30064771072.0]
+        );
+        assert_eq!(
+            *t_bf16.cast_to::<f16>().unwrap(),
+            tensor1(&[1f32, -2.5, f32::INFINITY]).cast_to::<f16>().unwrap().into_owned()
+        );
+    }
+
+    #[test]
+    fn test_bf16_super_type() {
+        assert_eq!(DatumType::BF16.common_super_type(DatumType::F16), Some(DatumType::F32));
+        assert_eq!(DatumType::BF16.common_super_type(DatumType::BF16), Some(DatumType::BF16));
+        assert_eq!(DatumType::F64.common_super_type(DatumType::BF16), Some(DatumType::F64));
+    }
+
+    #[test]
+    fn test_cast_fp8() {
+        let t_f32: Tensor = tensor1(&[1f32, -2.5, 0.3, 1000.0]);
+        let t_e4m3 = t_f32.cast_to::<f8e4m3>().unwrap();
+        assert_eq!(t_e4m3.datum_type(), DatumType::F8E4M3);
+        assert_eq!(
+            t_e4m3.cast_to::<f32>().unwrap().as_slice::<f32>().unwrap(),
+            &[1.0, -2.5, 0.3125, 448.0]
+        );
+        let t_e5m2 = t_e4m3.cast_to::<f16>().unwrap().cast_to::<f8e5m2>().unwrap().into_owned();
+        assert_eq!(
+            t_e5m2.cast_to::<f32>().unwrap().as_slice::<f32>().unwrap(),
+            &[1.0, -2.5, 0.3125, 448.0]
+        );
+        assert_eq!(t_e5m2.cast_to::<String>().unwrap().as_slice::<String>().unwrap()[1], "-2.5");
+    }
 }
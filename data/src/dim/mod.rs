@@ -5,7 +5,7 @@ use std::ops;
 
 mod tree;
 
-pub use self::tree::{Symbol, SymbolValues, TDim, UndeterminedSymbol};
+pub use self::tree::{Assumption, Assumptions, Symbol, SymbolValues, TDim, UndeterminedSymbol};
 use crate::{ TractError, TractResult };
 
 /// A super-trait for value acting as tensor dimensions in tract.
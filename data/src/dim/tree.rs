@@ -18,6 +18,8 @@ macro_rules! b( ($e:expr) => { Box::new($e) } );
 
 lazy_static::lazy_static! {
     static ref SYMBOL_TABLE: std::sync::Mutex<Vec<char>> = std::sync::Mutex::new(Vec::new());
+    static ref SYMBOL_ASSUMPTIONS: std::sync::Mutex<HashMap<Symbol, Assumptions>> =
+        std::sync::Mutex::new(HashMap::new());
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Debug)]
@@ -33,6 +35,93 @@ impl Symbol {
     pub fn as_char(&self) -> char {
         self.0
     }
+
+    /// Declares a fact about the values of the symbol, for the simplifier
+    /// to exploit. Assumptions hold for the whole process, and can only get
+    /// tighter.
+    pub fn assume(&self, assumption: Assumption) {
+        use num_integer::Integer;
+        let mut table = SYMBOL_ASSUMPTIONS.lock().unwrap();
+        let facts = table.entry(*self).or_default();
+        match assumption {
+            Assumption::AtLeast(v) => facts.min = facts.min.max(v),
+            Assumption::AtMost(v) => facts.max = Some(facts.max.map(|m| m.min(v)).unwrap_or(v)),
+            Assumption::MultipleOf(m) => {
+                assert!(m > 0, "A symbol can not be assumed a multiple of 0");
+                facts.multiple_of = facts.multiple_of.lcm(&m)
+            }
+        }
+    }
+
+    pub fn assumptions(&self) -> Assumptions {
+        SYMBOL_ASSUMPTIONS.lock().unwrap().get(self).copied().unwrap_or_default()
+    }
+}
+
+/// A fact about the values of a symbol.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Assumption {
+    AtLeast(i64),
+    AtMost(i64),
+    MultipleOf(u64),
+}
+
+impl Assumption {
+    /// Parses an assertion like `S >= 1`, `B <= 32` or `S % 8 == 0`.
+    pub fn parse(s: &str) -> anyhow::Result<(Symbol, Assumption)> {
+        let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let symbol = |sym: &str| -> anyhow::Result<Symbol> {
+            let mut chars = sym.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_alphabetic() => Ok(Symbol::from(c)),
+                _ => anyhow::bail!("Expected a symbol, got {:?}", sym),
+            }
+        };
+        if let Some((sym, rest)) = s.split_once('%') {
+            if let Some((m, "0")) = rest.split_once("==") {
+                let m: u64 = m.parse()?;
+                anyhow::ensure!(m > 0, "A symbol can not be assumed a multiple of 0");
+                return Ok((symbol(sym)?, Assumption::MultipleOf(m)));
+            }
+        } else {
+            for (op, at_least, offset) in
+                [(">=", true, 0), ("<=", false, 0), (">", true, 1), ("<", false, -1)]
+            {
+                if let Some((sym, v)) = s.split_once(op) {
+                    let v = v.parse::<i64>()? + offset;
+                    let sym = symbol(sym)?;
+                    let assumption =
+                        if at_least { Assumption::AtLeast(v) } else { Assumption::AtMost(v) };
+                    return Ok((sym, assumption));
+                }
+            }
+        }
+        anyhow::bail!("Can not parse assumption {:?} (expected S>=1, S<=32 or S%8==0)", s)
+    }
+}
+
+/// What is known about the values of a symbol. Symbols stand for
+/// dimensions, so they are non-negative by default.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Assumptions {
+    pub min: i64,
+    pub max: Option<i64>,
+    pub multiple_of: u64,
+}
+
+impl Default for Assumptions {
+    fn default() -> Assumptions {
+        Assumptions { min: 0, max: None, multiple_of: 1 }
+    }
+}
+
+impl Assumptions {
+    /// Checks a value is compatible with the assumptions.
+    pub fn allows(&self, v: i64) -> bool {
+        v >= self.min
+            && self.max.map(|m| v <= m).unwrap_or(true)
+            && v % self.multiple_of as i64 == 0
+    }
 }
 
 impl From<char> for Symbol {
@@ -63,6 +152,12 @@ impl SymbolValues {
     }
 }
 
+impl<S: Into<Symbol>> std::iter::FromIterator<(S, i64)> for SymbolValues {
+    fn from_iter<I: IntoIterator<Item = (S, i64)>>(iter: I) -> SymbolValues {
+        iter.into_iter().fold(SymbolValues::default(), |values, (s, v)| values.with(s.into(), v))
+    }
+}
+
 impl std::ops::Index<Symbol> for SymbolValues {
     type Output = Option<i64>;
     fn index(&self, index: Symbol) -> &Self::Output {
@@ -91,6 +186,8 @@ pub enum TDim {
     Mul(Vec<TDim>),
     MulInt(i64, Box<TDim>),
     Div(Box<TDim>, u64),
+    Min(Vec<TDim>),
+    Max(Vec<TDim>),
 }
 
 use TDim::*;
@@ -104,6 +201,8 @@ impl fmt::Display for TDim {
             Mul(it) => write!(fmt, "{}", it.iter().map(|x| format!("{}", x)).join("*")),
             MulInt(a, b) => write!(fmt, "{}*{}", a, b),
             Div(a, b) => write!(fmt, "({})/{}", a, b),
+            Min(it) => write!(fmt, "min({})", it.iter().map(|x| format!("{}", x)).join(",")),
+            Max(it) => write!(fmt, "max({})", it.iter().map(|x| format!("{}", x)).join(",")),
         }
     }
 }
@@ -129,6 +228,58 @@ impl TDim {
             Mul(terms) => terms.iter().fold(Val(1), |acc, it| -> TDim { acc * it.eval(values) }),
             Div(a, q) => a.eval(values) / *q as i64,
             MulInt(p, a) => a.eval(values) * *p,
+            Min(terms) => Min(terms.iter().map(|t| t.eval(values)).collect()).simplify(),
+            Max(terms) => Max(terms.iter().map(|t| t.eval(values)).collect()).simplify(),
+        }
+    }
+
+    /// The smallest of self and other.
+    pub fn mini(self, other: TDim) -> TDim {
+        Min(vec![self, other]).reduce()
+    }
+
+    /// The largest of self and other.
+    pub fn maxi(self, other: TDim) -> TDim {
+        Max(vec![self, other]).reduce()
+    }
+
+    /// A lower bound of the expression, if one can be established. Symbols,
+    /// standing for dimensions, are non-negative unless stated otherwise by
+    /// their assumptions.
+    pub fn low_bound(&self) -> Option<i64> {
+        match self {
+            Val(v) => Some(*v),
+            Sym(s) => Some(s.assumptions().min),
+            Add(terms) => terms.iter().map(|t| t.low_bound()).sum(),
+            Mul(terms) => terms.iter().map(|t| t.low_bound().filter(|b| *b >= 0)).product(),
+            MulInt(p, a) if *p >= 0 => a.low_bound().map(|b| b * p),
+            MulInt(p, a) => a.high_bound().map(|b| b * p),
+            Div(a, q) => a.low_bound().map(|b| b / *q as i64),
+            Min(terms) => terms
+                .iter()
+                .map(|t| t.low_bound())
+                .collect::<Option<Vec<_>>>()
+                .and_then(|b| b.into_iter().min()),
+            Max(terms) => terms.iter().filter_map(|t| t.low_bound()).max(),
+        }
+    }
+
+    /// An upper bound of the expression, if one can be established.
+    pub fn high_bound(&self) -> Option<i64> {
+        match self {
+            Val(v) => Some(*v),
+            Sym(s) => s.assumptions().max,
+            Add(terms) => terms.iter().map(|t| t.high_bound()).sum(),
+            Mul(_) => None,
+            MulInt(p, a) if *p >= 0 => a.high_bound().map(|b| b * p),
+            MulInt(p, a) => a.low_bound().map(|b| b * p),
+            Div(a, q) => a.high_bound().map(|b| b / *q as i64),
+            Min(terms) => terms.iter().filter_map(|t| t.high_bound()).min(),
+            Max(terms) => terms
+                .iter()
+                .map(|t| t.high_bound())
+                .collect::<Option<Vec<_>>>()
+                .and_then(|b| b.into_iter().max()),
         }
     }
 
@@ -151,13 +302,14 @@ impl TDim {
             Mul(terms) => 3 * terms.iter().map(TDim::cost).sum::<usize>(),
             Div(a, _) => 3 * a.cost(),
             MulInt(_, a) => 2 * a.cost(),
+            Min(terms) | Max(terms) => 2 * terms.iter().map(TDim::cost).sum::<usize>(),
         }
     }
 
     fn wiggle(&self) -> Vec<TDim> {
         use self::TDim::*;
         match self {
-            Sym(_) | Val(_) | Mul(_) => vec![self.clone()],
+            Sym(_) | Val(_) | Mul(_) | Min(_) | Max(_) => vec![self.clone()],
             Add(terms) => {
                 let mut forms = vec![];
                 let sub_wiggle = terms.iter().map(|e| e.wiggle()).multi_cartesian_product();
@@ -286,6 +438,14 @@ impl TDim {
                     Val(0)
                 } else if p == 1 {
                     a
+                } else if let Div(n, q) = &a {
+                    // exact division, when the numerator is a multiple of q
+                    let gcd = (p.abs() as u64).gcd(q);
+                    if gcd > 1 && n.gcd() % q == 0 {
+                        MulInt(p / gcd as i64, b!(Div(n.clone(), q / gcd))).simplify()
+                    } else {
+                        MulInt(p, b!(a))
+                    }
                 } else if let Add(terms) = &a {
                     Add(terms.clone().into_iter().map(|a| MulInt(p, b!(a)).simplify()).collect())
                 } else if let Val(p2) = a {
@@ -367,22 +527,70 @@ impl TDim {
                     Div(b!(a), q)
                 }
             }
+            Min(terms) => Self::simplify_min_max(terms, false),
+            Max(terms) => Self::simplify_min_max(terms, true),
             _ => self,
         }
     }
 
+    /// Flattens nested min (or max), folds constants, and drops the terms
+    /// another term is proven to dominate.
+    fn simplify_min_max(terms: Vec<TDim>, max: bool) -> TDim {
+        let mut flat: Vec<TDim> = vec![];
+        let mut todo = terms;
+        while let Some(term) = todo.pop() {
+            match (term.simplify(), max) {
+                (Max(terms), true) | (Min(terms), false) => todo.extend(terms),
+                (term, _) => flat.push(term),
+            }
+        }
+        let (vals, mut rest): (Vec<TDim>, Vec<TDim>) =
+            flat.into_iter().partition(|t| matches!(t, Val(_)));
+        let vals = vals.iter().map(|v| if let Val(v) = v { *v } else { unreachable!() });
+        if let Some(v) = if max { vals.max() } else { vals.min() } {
+            rest.push(Val(v));
+        }
+        rest.sort();
+        rest.dedup();
+        // a dominates b if a - b (max) or b - a (min) is non-negative
+        let dominates = |a: &TDim, b: &TDim| {
+            let diff = if max { a.clone() - b } else { b.clone() - a };
+            diff.low_bound().map(|b| b >= 0).unwrap_or(false)
+        };
+        let mut kept: Vec<TDim> = vec![];
+        for (ix, term) in rest.iter().enumerate() {
+            let dominated = rest.iter().enumerate().any(|(ix2, other)| {
+                ix2 != ix && dominates(other, term) && (ix2 < ix || !dominates(term, other))
+            });
+            if !dominated {
+                kept.push(term.clone());
+            }
+        }
+        if kept.len() == 1 {
+            kept.remove(0)
+        } else if max {
+            Max(kept)
+        } else {
+            Min(kept)
+        }
+    }
+
     fn gcd(&self) -> u64 {
         use self::TDim::*;
         use num_integer::Integer;
         match self {
             Val(v) => v.abs() as u64,
-            Sym(_) => 1,
+            Sym(s) => s.assumptions().multiple_of,
             Add(terms) => {
                 let (head, tail) = terms.split_first().unwrap();
                 tail.iter().fold(head.gcd(), |a, b| a.gcd(&b.gcd()))
             }
             MulInt(p, a) => a.gcd() * p.abs() as u64,
             Mul(_) => 1,
+            Min(terms) | Max(terms) => {
+                let (head, tail) = terms.split_first().unwrap();
+                tail.iter().fold(head.gcd(), |a, b| a.gcd(&b.gcd()))
+            }
             Div(a, q) => {
                 if a.gcd() % *q == 0 {
                     a.gcd() / *q
@@ -401,9 +609,8 @@ impl TDim {
         }
         match self {
             Val(v) => Val(v / d as i64),
-            Sym(_) => panic!(),
             Add(terms) => Add(terms.iter().map(|t| t.div(d)).collect()),
-            Mul(_) => Div(Box::new(self.clone()), d),
+            Sym(_) | Mul(_) | Min(_) | Max(_) => Div(Box::new(self.clone()), d),
             MulInt(p, a) => {
                 if *p == d as i64 {
                     (**a).clone()
@@ -441,6 +648,12 @@ impl TDim {
                     let (n, d) = slope_rec(a, sym);
                     (n, d * *q as i64)
                 }
+                // asymptotic slope, for large values of the symbol
+                Min(terms) | Max(terms) => {
+                    let slopes = terms.iter().map(|t| slope_rec(t, sym));
+                    let cmp = |a: &(i64, i64), b: &(i64, i64)| (a.0 * b.1).cmp(&(b.0 * a.1));
+                    if let Min(_) = d { slopes.min_by(cmp) } else { slopes.max_by(cmp) }.unwrap()
+                }
             }
         }
         let (p, q) = slope_rec(self, sym);
@@ -451,10 +664,12 @@ impl TDim {
         match self {
             Val(_) => maplit::hashset!(),
             Sym(s) => maplit::hashset!(*s),
-            Add(terms) | Mul(terms) => terms.iter().fold(maplit::hashset!(), |mut set, v| {
-                set.extend(v.symbols().into_iter());
-                set
-            }),
+            Add(terms) | Mul(terms) | Min(terms) | Max(terms) => {
+                terms.iter().fold(maplit::hashset!(), |mut set, v| {
+                    set.extend(v.symbols().into_iter());
+                    set
+                })
+            }
             MulInt(_, a) => a.symbols(),
             Div(a, _) => a.symbols(),
         }
@@ -918,6 +1133,90 @@ mod tests {
         assert_eq!(e, TDim::from(1));
     }
 
+    #[test]
+    fn min_max_vals() {
+        assert_eq!(TDim::from(3).mini(5.into()), 3.into());
+        assert_eq!(TDim::from(3).maxi(5.into()), 5.into());
+    }
+
+    #[test]
+    fn min_max_dominated() {
+        assert_eq!(s().maxi(s() - 2), s());
+        assert_eq!(s().mini(s() + 1), s());
+        assert_eq!(s().maxi(0.into()), s());
+        assert_eq!(s().mini(0.into()), 0.into());
+        assert_eq!((s() * 2).maxi(s()), s() * 2);
+    }
+
+    #[test]
+    fn min_max_symbolic() {
+        let e = (s() - 2).maxi(0.into());
+        assert_eq!(e, Max(vec![Val(0), s() - 2]));
+        assert_eq!(e.to_string(), "max(0,S+-2)");
+        assert_eq!(e.eval(&SymbolValues::default().with(*S, 1)), 0.into());
+        assert_eq!(e.eval(&SymbolValues::default().with(*S, 5)), 3.into());
+        assert_eq!(Max(vec![Val(3), mul(2, &s())]).slope(*S), (2, 1));
+        assert_eq!(Min(vec![Val(3), mul(2, &s())]).slope(*S), (0, 1));
+        assert_eq!(e.clone().maxi(e.clone()), e);
+        // nested maxes are flattened
+        assert_eq!(e.maxi(1.into()).maxi(s() - 1), Max(vec![Val(1), s() - 1]));
+    }
+
+    #[test]
+    fn min_max_nested() {
+        let e = s().mini(8.into()).mini(4.into());
+        assert_eq!(e, s().mini(4.into()));
+        assert_eq!(e.low_bound(), Some(0));
+        assert_eq!(e.high_bound(), Some(4));
+    }
+
+    #[test]
+    fn symbol_values_by_name() {
+        let values: SymbolValues = vec![('S', 128), ('B', 4)].into_iter().collect();
+        assert_eq!(values[Symbol::from('S')], Some(128));
+        assert_eq!(values[Symbol::from('B')], Some(4));
+    }
+
+    #[test]
+    fn assumptions_bounds() {
+        let b = Symbol::new('B');
+        b.assume(Assumption::AtLeast(1));
+        b.assume(Assumption::AtMost(32));
+        let b = TDim::from(b);
+        assert_eq!(b.low_bound(), Some(1));
+        assert_eq!(b.high_bound(), Some(32));
+        assert_eq!(b.clone().maxi(1.into()), b);
+        assert_eq!(b.clone().mini(64.into()), b);
+        assert_eq!(b.clone().mini(16.into()), Min(vec![b.clone(), Val(16)]));
+    }
+
+    #[test]
+    fn assumptions_multiple_of() {
+        let s = Symbol::new('S');
+        s.assume(Assumption::MultipleOf(8));
+        let s = TDim::from(s);
+        assert_eq!(s.clone() % 8, 0.into());
+        assert_eq!(s.clone() % 4, 0.into());
+        assert_eq!(s.clone().div_ceil(8) * 8, s);
+        assert_eq!(s.clone() / 4 * 2, s.clone() / 2);
+        assert_ne!(s.clone() % 16, 0.into());
+    }
+
+    #[test]
+    fn parse_assumptions() -> anyhow::Result<()> {
+        let s = Symbol::from('S');
+        assert_eq!(Assumption::parse("S >= 1")?, (s, Assumption::AtLeast(1)));
+        assert_eq!(Assumption::parse("S>0")?, (s, Assumption::AtLeast(1)));
+        assert_eq!(Assumption::parse("S<=32")?, (s, Assumption::AtMost(32)));
+        assert_eq!(Assumption::parse("S < 32")?, (s, Assumption::AtMost(31)));
+        assert_eq!(Assumption::parse("S % 8 == 0")?, (s, Assumption::MultipleOf(8)));
+        assert!(Assumption::parse("S % 8 == 1").is_err());
+        assert!(Assumption::parse("SS >= 1").is_err());
+        assert!(Assumptions { min: 1, max: Some(32), multiple_of: 8 }.allows(16));
+        assert!(!Assumptions { min: 1, max: Some(32), multiple_of: 8 }.allows(12));
+        Ok(())
+    }
+
     #[test]
     fn conv2d_ex_2() {
         let e = (s() - 3 + 1).div_ceil(1);
@@ -0,0 +1,175 @@
+//! 8-bit floating point storage types, as defined by the OCP FP8 formats.
+//!
+//! `f8e4m3` (E4M3FN) has no infinity, a single NaN encoding per sign, and
+//! saturates to its maximum (448) on overflow. `f8e5m2` follows IEEE
+//! conventions, with infinities, and a maximum of 57344.
+//!
+//! These types only support conversions: computations are done after a cast
+//! to a wider float type.
+use crate::datum::round_ties_to_even;
+use std::fmt;
+
+/// Encodes x with `m` mantissa bits, and the exponent bias, maximum finite
+/// code and overflow code of the format.
+fn encode(x: f32, m: u32, bias: i32, max: u8, overflow: u8, nan: u8) -> u8 {
+    let sign = if x.is_sign_negative() { 0x80 } else { 0 };
+    if x.is_nan() {
+        return sign | nan;
+    }
+    let abs = x.abs();
+    let min_normal = 2f32.powi(1 - bias);
+    let code = if abs < min_normal {
+        // subnormals, rounding up to the smallest normal when needed
+        round_ties_to_even(abs / min_normal * (1 << m) as f32) as u32
+    } else if abs.is_infinite() {
+        overflow as u32
+    } else {
+        let mut exp = ((abs.to_bits() >> 23) as i32) - 127;
+        let mantissa = abs / 2f32.powi(exp);
+        let mut q = round_ties_to_even((mantissa - 1.0) * (1 << m) as f32) as u32;
+        if q == 1 << m {
+            q = 0;
+            exp += 1;
+        }
+        let biased = exp + bias;
+        if biased > (max >> m) as i32 {
+            overflow as u32
+        } else {
+            (biased as u32) << m | q
+        }
+    };
+    sign | if code > max as u32 { overflow } else { code as u8 }
+}
+
+fn decode(code: u8, m: u32, bias: i32) -> f32 {
+    let sign = if code & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exp = ((code & 0x7F) >> m) as i32;
+    let mantissa = (code & ((1 << m) - 1)) as f32 / (1 << m) as f32;
+    if exp == 0 {
+        sign * mantissa * 2f32.powi(1 - bias)
+    } else {
+        sign * (1.0 + mantissa) * 2f32.powi(exp - bias)
+    }
+}
+
+/// E4M3FN float: 1 sign bit, 4 exponent bits, 3 mantissa bits.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct f8e4m3(pub u8);
+
+impl f8e4m3 {
+    pub const MAX: f32 = 448.0;
+
+    pub fn from_f32(x: f32) -> f8e4m3 {
+        f8e4m3(encode(x, 3, 7, 0x7E, 0x7E, 0x7F))
+    }
+
+    pub fn to_f32(self) -> f32 {
+        if self.0 & 0x7F == 0x7F {
+            f32::NAN
+        } else {
+            decode(self.0, 3, 7)
+        }
+    }
+}
+
+/// E5M2 float: 1 sign bit, 5 exponent bits, 2 mantissa bits.
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct f8e5m2(pub u8);
+
+impl f8e5m2 {
+    pub const MAX: f32 = 57344.0;
+
+    pub fn from_f32(x: f32) -> f8e5m2 {
+        f8e5m2(encode(x, 2, 15, 0x7B, 0x7C, 0x7E))
+    }
+
+    pub fn to_f32(self) -> f32 {
+        match self.0 & 0x7F {
+            0x7C => {
+                if self.0 & 0x80 != 0 {
+                    f32::NEG_INFINITY
+                } else {
+                    f32::INFINITY
+                }
+            }
+            0x7D..=0x7F => f32::NAN,
+            _ => decode(self.0, 2, 15),
+        }
+    }
+}
+
+macro_rules! f8_impls {
+    ($t: ident) => {
+        impl From<f32> for $t {
+            fn from(x: f32) -> $t {
+                $t::from_f32(x)
+            }
+        }
+
+        impl From<$t> for f32 {
+            fn from(x: $t) -> f32 {
+                x.to_f32()
+            }
+        }
+
+        impl fmt::Display for $t {
+            fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                self.to_f32().fmt(fmt)
+            }
+        }
+
+        impl std::str::FromStr for $t {
+            type Err = std::num::ParseFloatError;
+            fn from_str(s: &str) -> Result<$t, Self::Err> {
+                s.parse::<f32>().map(|f| f.into())
+            }
+        }
+    };
+}
+
+f8_impls!(f8e4m3);
+f8_impls!(f8e5m2);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn e4m3_round_trip() {
+        for code in 0..=255u8 {
+            let x = f8e4m3(code).to_f32();
+            if x.is_nan() {
+                assert!(f8e4m3::from_f32(x).to_f32().is_nan());
+            } else if code != 0x80 {
+                assert_eq!(f8e4m3::from_f32(x), f8e4m3(code), "{} {}", code, x);
+            }
+        }
+    }
+
+    #[test]
+    fn e5m2_round_trip() {
+        for code in 0..=255u8 {
+            let x = f8e5m2(code).to_f32();
+            if x.is_nan() {
+                assert!(f8e5m2::from_f32(x).to_f32().is_nan());
+            } else if code != 0x80 {
+                assert_eq!(f8e5m2::from_f32(x), f8e5m2(code), "{} {}", code, x);
+            }
+        }
+    }
+
+    #[test]
+    fn rounding_and_overflow() {
+        assert_eq!(f8e4m3::from_f32(1.0).0, 0x38);
+        assert_eq!(f8e4m3::from_f32(0.3).to_f32(), 0.3125);
+        assert_eq!(f8e4m3::from_f32(1000.0).to_f32(), f8e4m3::MAX);
+        assert_eq!(f8e4m3::from_f32(-f32::INFINITY).to_f32(), -f8e4m3::MAX);
+        assert_eq!(f8e4m3(0x01).to_f32(), 2f32.powi(-9));
+        assert_eq!(f8e5m2::from_f32(1.0).0, 0x3C);
+        assert_eq!(f8e5m2::from_f32(1e6).to_f32(), f32::INFINITY);
+        assert_eq!(f8e5m2::from_f32(f8e5m2::MAX).to_f32(), f8e5m2::MAX);
+        assert_eq!(f8e5m2(0x01).to_f32(), 2f32.powi(-16));
+    }
+}
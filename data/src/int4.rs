@@ -0,0 +1,128 @@
+//! Packed 4-bit integers, quantized by blocks.
+//!
+//! A block of `block_len` values is stored as a f16 scale, a zero point on
+//! one byte, then the 4-bit quants, two per byte, low nibble first. A value
+//! is `(q - zero_point) * scale`.
+use crate::TractResult;
+use half::f16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Int4Blocks {
+    block_len: usize,
+}
+
+impl Int4Blocks {
+    /// Supported block lengths.
+    pub const BLOCK_LENS: [usize; 3] = [32, 64, 128];
+
+    pub fn new(block_len: usize) -> TractResult<Int4Blocks> {
+        anyhow::ensure!(
+            Self::BLOCK_LENS.contains(&block_len),
+            "Unsupported int4 block length {}, expected one of {:?}",
+            block_len,
+            Self::BLOCK_LENS
+        );
+        Ok(Int4Blocks { block_len })
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Size of a block, in bytes.
+    pub fn block_bytes(&self) -> usize {
+        3 + self.block_len / 2
+    }
+
+    pub fn scale(&self, block: &[u8]) -> f32 {
+        f16::from_bits(u16::from_le_bytes([block[0], block[1]])).to_f32()
+    }
+
+    pub fn zero_point(&self, block: &[u8]) -> u8 {
+        block[2]
+    }
+
+    /// The packed quants of a block.
+    pub fn quants<'b>(&self, block: &'b [u8]) -> &'b [u8] {
+        &block[3..self.block_bytes()]
+    }
+
+    /// Quantizes a block of `block_len` values, with the scale and zero
+    /// point mapping their range, zero included, to [0, 15].
+    pub fn quant_block_f32(&self, values: &[f32], block: &mut [u8]) {
+        let min = values.iter().fold(0f32, |acc, &v| acc.min(v));
+        let max = values.iter().fold(0f32, |acc, &v| acc.max(v));
+        let scale = f16::from_f32((max - min) / 15.0);
+        let d = scale.to_f32();
+        let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+        let zero_point = (-min * id).round().min(15.0) as u8;
+        block[0..2].copy_from_slice(&scale.to_bits().to_le_bytes());
+        block[2] = zero_point;
+        let q = |v: f32| ((v * id).round() + zero_point as f32).max(0.0).min(15.0) as u8;
+        for (pair, byte) in values.chunks_exact(2).zip(block[3..].iter_mut()) {
+            *byte = q(pair[0]) | (q(pair[1]) << 4);
+        }
+    }
+
+    /// Dequantizes a block to `block_len` f32 values.
+    pub fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]) {
+        let d = self.scale(block);
+        let zp = self.zero_point(block) as i32;
+        for (pair, q) in values.chunks_exact_mut(2).zip(self.quants(block)) {
+            pair[0] = ((q & 0x0F) as i32 - zp) as f32 * d;
+            pair[1] = ((q >> 4) as i32 - zp) as f32 * d;
+        }
+    }
+
+    /// Quantizes values, whose length must be a multiple of the block
+    /// length.
+    pub fn quant_f32(&self, values: &[f32]) -> TractResult<Vec<u8>> {
+        anyhow::ensure!(
+            values.len() % self.block_len == 0,
+            "{} values are not a multiple of the block length {}",
+            values.len(),
+            self.block_len
+        );
+        let mut blocks = vec![0u8; values.len() / self.block_len * self.block_bytes()];
+        for (values, block) in
+            values.chunks_exact(self.block_len).zip(blocks.chunks_exact_mut(self.block_bytes()))
+        {
+            self.quant_block_f32(values, block);
+        }
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> TractResult<()> {
+        let format = Int4Blocks::new(32)?;
+        let values: Vec<f32> = (0..64).map(|i| (i % 16) as f32 * 0.5 - 2.0).collect();
+        let blocks = format.quant_f32(&values)?;
+        assert_eq!(blocks.len(), 2 * 19);
+        assert_eq!(format.scale(&blocks), 0.5);
+        assert_eq!(format.zero_point(&blocks), 4);
+        let mut found = vec![0f32; 64];
+        for (block, found) in blocks.chunks(19).zip(found.chunks_mut(32)) {
+            format.dequant_block_f32(block, found);
+        }
+        assert_eq!(found, values);
+        Ok(())
+    }
+
+    #[test]
+    fn positive_block() -> TractResult<()> {
+        let format = Int4Blocks::new(32)?;
+        let mut block = vec![0u8; format.block_bytes()];
+        format.quant_block_f32(&[3.0; 32], &mut block);
+        assert_eq!(format.zero_point(&block), 0);
+        let mut found = [0f32; 32];
+        format.dequant_block_f32(&block, &mut found);
+        assert!(found.iter().all(|v| (v - 3.0).abs() < 1e-2));
+        assert!(Int4Blocks::new(48).is_err());
+        Ok(())
+    }
+}
@@ -17,8 +17,11 @@ pub type TractResult<T> = anyhow::Result<T>;
 
 pub mod prelude {
     pub use crate::datum::{round_ties_to_even, Blob, Datum, DatumType, QParams};
-    pub use crate::dim::{Symbol, SymbolValues, TDim, ToDim};
+    pub use crate::dim::{Assumption, Assumptions, Symbol, SymbolValues, TDim, ToDim};
+    pub use crate::bf16::*;
     pub use crate::f16::*;
+    pub use crate::f8::*;
+    pub use crate::int4::Int4Blocks;
     pub use crate::tensor::litteral::*;
     pub use crate::tensor::{natural_strides, IntoArcTensor, IntoTensor, Tensor};
     pub use crate::tvec;
@@ -48,11 +51,15 @@ pub mod internal {
 
 pub use anyhow;
 pub use dim::UndeterminedSymbol;
+pub use tensor::arena;
 pub use half;
 
+mod bf16;
 mod datum;
 mod dim;
 mod f16;
+mod f8;
 pub mod hash;
+mod int4;
 mod scatter;
 mod tensor;
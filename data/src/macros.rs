@@ -33,8 +33,11 @@ macro_rules! dispatch_datum {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<f16>($($args),*),
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
+            DatumType::F8E4M3 => $($path)::*::<f8e4m3>($($args),*),
+            DatumType::F8E5M2 => $($path)::*::<f8e5m2>($($args),*),
             DatumType::Blob => $($path)::*::<Blob>($($args),*),
             DatumType::TDim => $($path)::*::<TDim>($($args),*),
             DatumType::String => $($path)::*::<String>($($args),*),
@@ -66,8 +69,11 @@ macro_rules! dispatch_datum_by_size {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<i16>($($args),*),
+            DatumType::BF16 => $($path)::*::<i16>($($args),*),
             DatumType::F32  => $($path)::*::<i32>($($args),*),
             DatumType::F64  => $($path)::*::<i64>($($args),*),
+            DatumType::F8E4M3 => $($path)::*::<i8>($($args),*),
+            DatumType::F8E5M2 => $($path)::*::<i8>($($args),*),
             DatumType::Blob => $($path)::*::<Blob>($($args),*),
             DatumType::TDim => $($path)::*::<TDim>($($args),*),
             DatumType::String => $($path)::*::<String>($($args),*),
@@ -99,8 +105,11 @@ macro_rules! dispatch_copy {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<f16>($($args),*),
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
+            DatumType::F8E4M3 => $($path)::*::<f8e4m3>($($args),*),
+            DatumType::F8E5M2 => $($path)::*::<f8e5m2>($($args),*),
             DatumType::QI8(_)  => $($path)::*::<i8>($($args),*),
             DatumType::QU8(_)  => $($path)::*::<u8>($($args),*),
             DatumType::QI32(_)  => $($path)::*::<u8>($($args),*),
@@ -130,8 +139,11 @@ macro_rules! dispatch_copy_by_size {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<i16>($($args),*),
+            DatumType::BF16 => $($path)::*::<i16>($($args),*),
             DatumType::F32  => $($path)::*::<i32>($($args),*),
             DatumType::F64  => $($path)::*::<i64>($($args),*),
+            DatumType::F8E4M3 => $($path)::*::<i8>($($args),*),
+            DatumType::F8E5M2 => $($path)::*::<i8>($($args),*),
             DatumType::QI8(_)  => $($path)::*::<i8>($($args),*),
             DatumType::QU8(_)  => $($path)::*::<u8>($($args),*),
             DatumType::QI32(_)  => $($path)::*::<i32>($($args),*),
@@ -159,6 +171,7 @@ macro_rules! dispatch_numbers {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<f16>($($args),*),
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
             DatumType::QI8(_)  => $($path)::*::<i8>($($args),*),
@@ -183,6 +196,7 @@ macro_rules! dispatch_zerolike {
             DatumType::I32  => $($path)::*::<i32>($($args),*),
             DatumType::I64  => $($path)::*::<i64>($($args),*),
             DatumType::F16  => $($path)::*::<f16>($($args),*),
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
             DatumType::QI8(_)  => $($path)::*::<i8>($($args),*),
@@ -204,6 +218,7 @@ macro_rules! dispatch_floatlike {
         use $crate::prelude::DatumType;
         match $dt {
             DatumType::F16  => $($path)::*::<f32>($($args),*), // FIXME !!!
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
             _ => $crate::anyhow::bail!("{:?} is not float-like", $dt)
@@ -217,6 +232,7 @@ macro_rules! dispatch_signed {
         use $crate::prelude::DatumType;
         match $dt {
             DatumType::F16  => $($path)::*::<f32>($($args),*), // FIXME !!!
+            DatumType::BF16 => $($path)::*::<bf16>($($args),*),
             DatumType::F32  => $($path)::*::<f32>($($args),*),
             DatumType::F64  => $($path)::*::<f64>($($args),*),
             DatumType::I8   => $($path)::*::<i8>($($args),*),
@@ -1,4 +1,6 @@
 //! `Tensor`, tract main data object of interest.
+use crate::bf16::bf16;
+use crate::f8::{f8e4m3, f8e5m2};
 use crate::datum::{round_ties_to_even, scale_by, Blob, ClampCast, Datum, DatumType, QParams};
 use crate::dim::TDim;
 use crate::f16::f16;
@@ -16,6 +18,7 @@ use std::mem::{align_of, size_of};
 use std::ops::Range;
 use std::sync::Arc;
 
+pub mod arena;
 pub mod litteral;
 pub mod view;
 
@@ -27,6 +30,8 @@ pub struct Tensor {
     len: usize,
     layout: alloc::Layout,
     data: *mut u8,
+    shared: Option<Box<dyn AsRef<[u8]> + Send + Sync>>,
+    arena: Option<arena::ArenaLease>,
 }
 
 unsafe impl Send for Tensor {}
@@ -50,8 +55,10 @@ impl Hash for Tensor {
                 U32 => self.as_slice_unchecked::<u32>().hash(state),
                 U64 => self.as_slice_unchecked::<u64>().hash(state),
                 F16 => self.as_slice_unchecked::<i16>().hash(state),
+                BF16 => self.as_slice_unchecked::<i16>().hash(state),
                 F32 => self.as_slice_unchecked::<i32>().hash(state),
                 F64 => self.as_slice_unchecked::<i64>().hash(state),
+                F8E4M3 | F8E5M2 => self.as_slice_unchecked::<u8>().hash(state),
                 TDim => self.as_slice_unchecked::<crate::dim::TDim>().hash(state),
                 String => self.as_slice_unchecked::<std::string::String>().hash(state),
                 Blob => self.as_slice_unchecked::<crate::datum::Blob>().hash(state),
@@ -107,7 +114,11 @@ impl Drop for Tensor {
                     .for_each(|s| std::ptr::drop_in_place(s as *mut TDim));
             }
         }
-        if !self.data.is_null() && self.layout.size() > 0 {
+        if self.shared.is_none()
+            && self.arena.is_none()
+            && !self.data.is_null()
+            && self.layout.size() > 0
+        {
             unsafe { alloc::dealloc(self.data, self.layout) }
         }
     }
@@ -148,14 +159,26 @@ impl Tensor {
         assert!(dt.is_copy());
         let bytes = shape.iter().cloned().product::<usize>() * dt.size_of();
         let layout = alloc::Layout::from_size_align(bytes, alignment)?;
+        let arena = if bytes == 0 { None } else { arena::take_offer(bytes, alignment) };
         let data = if bytes == 0 {
             std::ptr::null()
+        } else if let Some(lease) = &arena {
+            lease.ptr()
         } else {
             let ptr = alloc::alloc(layout);
             assert!(!ptr.is_null());
             ptr
         } as *mut u8;
-        let mut tensor = Tensor { strides: tvec!(), layout, dt, shape: shape.into(), data, len: 0 };
+        let mut tensor = Tensor {
+            strides: tvec!(),
+            layout,
+            dt,
+            shape: shape.into(),
+            data,
+            len: 0,
+            shared: None,
+            arena,
+        };
         #[cfg(debug_assertions)]
         {
             if dt == DatumType::F32 {
@@ -169,6 +192,95 @@ impl Tensor {
         Ok(tensor)
     }
 
+    /// Create a tensor over the bytes of a shared buffer, without copying them.
+    ///
+    /// The buffer is kept alive as long as the tensor, and its content is
+    /// copied to a private allocation on the first mutable access. The buffer
+    /// must be suitably aligned for the datum type, which must be copy (bool
+    /// excepted).
+    pub fn from_shared_bytes<B>(dt: DatumType, shape: &[usize], buffer: B) -> anyhow::Result<Tensor>
+    where
+        B: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        anyhow::ensure!(
+            dt.is_copy() && dt != DatumType::Bool,
+            "Can not build a shared tensor of {:?}",
+            dt
+        );
+        let buffer: Box<dyn AsRef<[u8]> + Send + Sync> = Box::new(buffer);
+        let bytes = (*buffer).as_ref();
+        let len = shape.iter().product::<usize>();
+        anyhow::ensure!(
+            bytes.len() == len * dt.size_of(),
+            "Shared buffer is {} bytes long, expected {} for {:?} of {:?}",
+            bytes.len(),
+            len * dt.size_of(),
+            shape,
+            dt
+        );
+        anyhow::ensure!(
+            bytes.as_ptr() as usize % dt.alignment() == 0,
+            "Shared buffer is misaligned for {:?}",
+            dt
+        );
+        let layout = alloc::Layout::from_size_align(bytes.len(), dt.alignment())?;
+        let data = bytes.as_ptr() as *mut u8;
+        let mut tensor = Tensor {
+            strides: tvec!(),
+            layout,
+            dt,
+            shape: shape.into(),
+            data,
+            len: 0,
+            shared: Some(buffer),
+            arena: None,
+        };
+        tensor.update_strides_and_len();
+        Ok(tensor)
+    }
+
+    /// Create a tensor over a shared slice of values, without copying them,
+    /// as `from_shared_bytes` does.
+    pub fn from_shared_slice<T, B>(shape: &[usize], buffer: B) -> anyhow::Result<Tensor>
+    where
+        T: Datum + Copy,
+        B: AsRef<[T]> + Send + Sync + 'static,
+    {
+        struct Bytes<T, B>(B, std::marker::PhantomData<fn() -> T>);
+        impl<T: Copy, B: AsRef<[T]>> AsRef<[u8]> for Bytes<T, B> {
+            fn as_ref(&self) -> &[u8] {
+                let values = self.0.as_ref();
+                unsafe {
+                    std::slice::from_raw_parts(
+                        values.as_ptr() as *const u8,
+                        std::mem::size_of_val(values),
+                    )
+                }
+            }
+        }
+        Self::from_shared_bytes(T::datum_type(), shape, Bytes(buffer, std::marker::PhantomData))
+    }
+
+    /// Is the tensor data still borrowed from a shared buffer?
+    pub fn is_shared(&self) -> bool {
+        self.shared.is_some()
+    }
+
+    /// Copy the data of a shared tensor to a private allocation.
+    fn unshare(&mut self) {
+        if self.shared.is_some() {
+            unsafe {
+                let mut owned =
+                    Tensor::uninitialized_aligned_dt(self.dt, &self.shape, self.layout.align())
+                        .unwrap();
+                if self.layout.size() > 0 {
+                    self.data.copy_to_nonoverlapping(owned.data, self.layout.size());
+                }
+                std::mem::swap(self, &mut owned);
+            }
+        }
+    }
+
     pub fn stack_tensors(
         axis: usize,
         tensors: &[impl std::borrow::Borrow<Tensor>],
@@ -582,6 +694,7 @@ impl Tensor {
         src_range: std::ops::Range<usize>,
         axis: usize,
     ) {
+        self.unshare();
         use ndarray::Slice;
         unsafe fn assign_slice_t<T: Datum>(
             to: &mut Tensor,
@@ -736,6 +849,7 @@ impl Tensor {
 
     /// Transform the data as a mutable `ndarray::Array`.
     pub unsafe fn to_array_view_mut_unchecked<'a, D: Datum>(&'a mut self) -> ArrayViewMutD<'a, D> {
+        self.unshare();
         if self.len() != 0 {
             ArrayViewMutD::from_shape_ptr(&*self.shape, self.data as *mut D)
         } else {
@@ -756,11 +870,13 @@ impl Tensor {
 
     /// Access the data as a pointer.
     pub unsafe fn as_ptr_mut_unchecked<D: Datum>(&mut self) -> *mut D {
+        self.unshare();
         self.data as *mut D
     }
 
     /// Access the data as a mutable pointer.
     pub fn as_ptr_mut<D: Datum>(&mut self) -> anyhow::Result<*mut D> {
+        self.unshare();
         self.as_ptr::<D>().map(|p| p as *mut D)
     }
 
@@ -781,6 +897,7 @@ impl Tensor {
 
     /// Access the data as a mutable slice.
     pub unsafe fn as_slice_mut_unchecked<D: Datum>(&mut self) -> &mut [D] {
+        self.unshare();
         std::slice::from_raw_parts_mut::<D>(self.data as *mut D, self.len())
     }
 
@@ -817,6 +934,7 @@ impl Tensor {
     }
 
     pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.unshare();
         std::slice::from_raw_parts_mut(self.data, self.layout.size())
     }
 
@@ -926,6 +1044,36 @@ impl Tensor {
                 }
                 return Ok(Cow::Owned(ints.cast_to_dt(dst_dt)?.into_owned()));
             }
+            // fp8 types only convert through f32
+            if self.dt.is_fp8() && dst_dt != DatumType::String {
+                let mut floats = Self::uninitialized::<f32>(&self.shape)?;
+                let floats_slice = floats.as_slice_mut_unchecked::<f32>();
+                if self.dt == DatumType::F8E4M3 {
+                    for (f, x) in floats_slice.iter_mut().zip(self.as_slice_unchecked::<f8e4m3>()) {
+                        *f = x.to_f32();
+                    }
+                } else {
+                    for (f, x) in floats_slice.iter_mut().zip(self.as_slice_unchecked::<f8e5m2>()) {
+                        *f = x.to_f32();
+                    }
+                }
+                return Ok(Cow::Owned(floats.cast_to_dt(dst_dt)?.into_owned()));
+            }
+            if dst_dt.is_fp8() && self.dt != DatumType::String {
+                let floats = self.cast_to::<f32>()?;
+                let floats = floats.as_slice_unchecked::<f32>();
+                let mut result = Self::uninitialized_dt(dst_dt, &self.shape)?;
+                if dst_dt == DatumType::F8E4M3 {
+                    for (x, f) in result.as_slice_mut_unchecked::<f8e4m3>().iter_mut().zip(floats) {
+                        *x = f8e4m3::from_f32(*f);
+                    }
+                } else {
+                    for (x, f) in result.as_slice_mut_unchecked::<f8e5m2>().iter_mut().zip(floats) {
+                        *x = f8e5m2::from_f32(*f);
+                    }
+                }
+                return Ok(Cow::Owned(result));
+            }
             let mut result = Self::uninitialized_dt(dst_dt, &self.shape)?;
             if self.dt == DatumType::String {
                 dispatch_datum!(Self::cast_from_string(dst_dt)(self, &mut result))?;
@@ -948,6 +1096,7 @@ impl Tensor {
                             DatumType::U32 => self.natural_cast::<$source, u32>(&mut result),
                             DatumType::U64 => self.natural_cast::<$source, u64>(&mut result),
                             DatumType::F16 => self.natural_cast::<$source, f16>(&mut result),
+                            DatumType::BF16 => self.natural_cast::<$source, bf16>(&mut result),
                             DatumType::F32 => self.natural_cast::<$source, f32>(&mut result),
                             DatumType::F64 => self.natural_cast::<$source, f64>(&mut result),
                             DatumType::TDim => {
@@ -976,6 +1125,7 @@ impl Tensor {
                 n!(i32);
                 n!(i64);
                 n!(f16);
+                n!(bf16);
                 n!(f32);
                 n!(f64);
             } else {
@@ -1134,8 +1284,16 @@ impl Tensor {
             let shape = it.shape().into();
             let vec = it.into_raw_vec().into_boxed_slice();
             let data = Box::into_raw(vec) as *mut u8;
-            let mut t =
-                Tensor { dt: T::datum_type(), shape, layout, data, strides: tvec!(), len: 0 };
+            let mut t = Tensor {
+                dt: T::datum_type(),
+                shape,
+                layout,
+                data,
+                strides: tvec!(),
+                len: 0,
+                shared: None,
+                arena: None,
+            };
             t.update_strides_and_len();
             return t;
         }
@@ -1180,6 +1338,8 @@ impl Tensor {
                 data: data.as_ptr() as *mut u8,
                 shape: self.shape.clone(),
                 strides: self.strides.clone(),
+                shared: None,
+                arena: None,
                 ..*self
             };
             std::mem::forget(data);
@@ -1190,6 +1350,8 @@ impl Tensor {
                 data: data.as_ptr() as *mut u8,
                 shape: self.shape.clone(),
                 strides: self.strides.clone(),
+                shared: None,
+                arena: None,
                 ..*self
             };
             std::mem::forget(data);
@@ -1455,4 +1617,38 @@ mod tests {
     fn t_2_2() {
         PermuteAxisProblem { shape: vec![2, 2], permutation: vec![1, 0] }.check().unwrap();
     }
+
+    struct F32Buffer(Vec<f32>);
+
+    impl AsRef<[u8]> for F32Buffer {
+        fn as_ref(&self) -> &[u8] {
+            unsafe { std::slice::from_raw_parts(self.0.as_ptr() as *const u8, self.0.len() * 4) }
+        }
+    }
+
+    #[test]
+    fn shared_bytes() -> anyhow::Result<()> {
+        let buffer = F32Buffer(vec![1.0, 2.0, 3.0, 4.0]);
+        let ptr = buffer.0.as_ptr();
+        let mut t = Tensor::from_shared_bytes(f32::datum_type(), &[2, 2], buffer)?;
+        assert!(t.is_shared());
+        assert_eq!(t.as_ptr::<f32>()?, ptr);
+        assert_eq!(t, super::litteral::tensor2(&[[1f32, 2.0], [3.0, 4.0]]));
+        t.as_slice_mut::<f32>()?[0] = 0.0;
+        assert!(!t.is_shared());
+        assert_eq!(t, super::litteral::tensor2(&[[0f32, 2.0], [3.0, 4.0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn shared_slice() -> anyhow::Result<()> {
+        let buffer: Arc<[i16]> = vec![1i16, 2, 3, 4, 5, 6].into();
+        let t = Tensor::from_shared_slice(&[3, 2], buffer.clone())?;
+        assert!(t.is_shared());
+        assert_eq!(t.as_ptr::<i16>()?, buffer.as_ptr());
+        assert_eq!(t, super::litteral::tensor2(&[[1i16, 2], [3, 4], [5, 6]]));
+        assert!(Tensor::from_shared_slice(&[4], buffer).is_err());
+        assert!(Tensor::from_shared_slice(&[1], vec![true]).is_err());
+        Ok(())
+    }
 }
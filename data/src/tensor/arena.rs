@@ -0,0 +1,205 @@
+//! Pre-allocated buffers for tensors with a planned lifetime.
+//!
+//! An `Arena` is a single allocation. A region of it can be offered, for the
+//! duration of a closure, to the next tensor allocated on the current
+//! thread with `offer`. Regions are leased by the tensors using them: a
+//! region overlapping a leased one is never handed out again before the
+//! tensor holding the lease is dropped. If the offer can not be honoured,
+//! tensors are allocated on the heap as usual.
+//!
+//! An arena can also be built over the buffer of a tensor, so that a
+//! computation writes its result directly in it.
+use crate::tensor::Tensor;
+use std::alloc;
+use std::cell::RefCell;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+/// Alignment of arenas, and of the regions offered by planners.
+pub const ARENA_ALIGNMENT: usize = 64;
+
+pub struct Arena {
+    data: *mut u8,
+    layout: alloc::Layout,
+    leased: Mutex<Vec<Range<usize>>>,
+    /// The tensor owning the buffer, for arenas built over one.
+    tensor: Option<Tensor>,
+}
+
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl std::fmt::Debug for Arena {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Arena({} bytes)", self.len())
+    }
+}
+
+impl Arena {
+    pub fn new(bytes: usize) -> anyhow::Result<Arc<Arena>> {
+        let layout = alloc::Layout::from_size_align(bytes.max(1), ARENA_ALIGNMENT)?;
+        let data = unsafe { alloc::alloc(layout) };
+        anyhow::ensure!(!data.is_null(), "Failed to allocate a {} bytes arena", bytes);
+        Ok(Arc::new(Arena { data, layout, leased: Mutex::new(vec![]), tensor: None }))
+    }
+
+    /// Builds an arena over the buffer of `tensor`, taking it, if it owns
+    /// non empty data of a copy datum type.
+    pub fn from_tensor(tensor: &mut Tensor) -> Option<Arc<Arena>> {
+        if !tensor.datum_type().is_copy() || tensor.is_shared() || tensor.len() == 0 {
+            return None;
+        }
+        let mut tensor = std::mem::take(tensor);
+        let bytes = unsafe { tensor.as_bytes_mut() };
+        let data = bytes.as_mut_ptr();
+        let layout = alloc::Layout::from_size_align(bytes.len(), 1).unwrap();
+        Some(Arc::new(Arena { data, layout, leased: Mutex::new(vec![]), tensor: Some(tensor) }))
+    }
+
+    /// Gives back the tensor an arena was built over, the arena being given
+    /// back if some tensor still uses it, or if it owns its buffer.
+    pub fn into_tensor(self: Arc<Self>) -> Result<Tensor, Arc<Arena>> {
+        match Arc::try_unwrap(self) {
+            Ok(mut arena) if arena.tensor.is_some() => {
+                arena.data = std::ptr::null_mut();
+                Ok(arena.tensor.take().unwrap())
+            }
+            Ok(arena) => Err(Arc::new(arena)),
+            Err(arena) => Err(arena),
+        }
+    }
+
+    /// Does `ptr` point in the arena?
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        (self.data as *const u8..unsafe { self.data.add(self.len()) } as *const u8).contains(&ptr)
+    }
+
+    pub fn len(&self) -> usize {
+        self.layout.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layout.size() == 0
+    }
+
+    /// Count of regions currently used by tensors.
+    pub fn leases(&self) -> usize {
+        self.leased.lock().unwrap().len()
+    }
+
+    fn lease(self: &Arc<Self>, range: Range<usize>) -> Option<ArenaLease> {
+        let mut leased = self.leased.lock().unwrap();
+        if leased.iter().any(|r| r.start < range.end && range.start < r.end) {
+            return None;
+        }
+        leased.push(range.clone());
+        Some(ArenaLease { arena: self.clone(), range })
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        if self.tensor.is_none() && !self.data.is_null() {
+            unsafe { alloc::dealloc(self.data, self.layout) }
+        }
+    }
+}
+
+/// A region of an arena, used by a tensor.
+#[derive(Debug)]
+pub(crate) struct ArenaLease {
+    arena: Arc<Arena>,
+    range: Range<usize>,
+}
+
+impl ArenaLease {
+    pub(crate) fn ptr(&self) -> *mut u8 {
+        unsafe { self.arena.data.add(self.range.start) }
+    }
+}
+
+impl Drop for ArenaLease {
+    fn drop(&mut self) {
+        let mut leased = self.arena.leased.lock().unwrap();
+        if let Some(ix) = leased.iter().position(|r| r == &self.range) {
+            leased.swap_remove(ix);
+        }
+    }
+}
+
+thread_local! {
+    static TLS_OFFER: RefCell<Option<(Arc<Arena>, Range<usize>)>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, the first tensor allocated by it on this thread using `range`
+/// of `arena` if it fits and the region is free.
+pub fn offer<R, F: FnOnce() -> R>(arena: &Arc<Arena>, range: Range<usize>, f: F) -> R {
+    debug_assert!(range.end <= arena.len());
+    let previous = TLS_OFFER.with(|o| o.replace(Some((arena.clone(), range))));
+    struct Restore(Option<(Arc<Arena>, Range<usize>)>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            TLS_OFFER.with(|o| *o.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Takes the region offered to this thread for a `bytes` long allocation.
+pub(crate) fn take_offer(bytes: usize, alignment: usize) -> Option<ArenaLease> {
+    TLS_OFFER.with(|o| {
+        let mut offer = o.borrow_mut();
+        let fits = offer.as_ref().map(|(arena, range)| {
+            range.len() >= bytes && (arena.data as usize + range.start) % alignment == 0
+        });
+        if fits != Some(true) {
+            return None;
+        }
+        let (arena, range) = offer.take().unwrap();
+        arena.lease(range.start..range.start + bytes)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn offered_region() -> anyhow::Result<()> {
+        let arena = Arena::new(256)?;
+        let a = offer(&arena, 0..128, || unsafe { Tensor::uninitialized::<f32>(&[32]) })?;
+        assert_eq!(a.as_ptr::<f32>()? as *const u8, arena.data as *const u8);
+        assert_eq!(arena.leases(), 1);
+        // overlapping a live tensor, or too small: on the heap
+        let b = offer(&arena, 64..192, || unsafe { Tensor::uninitialized::<f32>(&[16]) })?;
+        let c = offer(&arena, 128..192, || unsafe { Tensor::uninitialized::<f32>(&[32]) })?;
+        assert_eq!(arena.leases(), 1);
+        // only the first allocation of the closure gets the region
+        let (d, e) = offer(&arena, 128..256, || unsafe {
+            (Tensor::uninitialized::<f32>(&[32]), Tensor::uninitialized::<f32>(&[32]))
+        });
+        let d = d?;
+        assert_eq!(d.as_ptr::<f32>()? as *const u8, unsafe { arena.data.add(128) } as *const u8);
+        assert_eq!(arena.leases(), 2);
+        drop((a, b, c, d, e));
+        assert_eq!(arena.leases(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn tensor_arena() -> anyhow::Result<()> {
+        let arena = Arena::from_tensor(&mut tensor1(&[0f32; 4])).unwrap();
+        let mut a = offer(&arena, 0..16, || unsafe { Tensor::uninitialized::<f32>(&[4]) })?;
+        assert!(arena.contains(a.as_ptr::<f32>()? as *const u8));
+        a.as_slice_mut::<f32>()?.copy_from_slice(&[1.0, 2.0, 3.0, 4.0]);
+        let arena = arena.into_tensor().unwrap_err();
+        drop(a);
+        assert_eq!(arena.into_tensor().unwrap(), tensor1(&[1f32, 2.0, 3.0, 4.0]));
+        assert!(Arena::from_tensor(&mut tensor1(&["a".to_string()])).is_none());
+        assert!(Arena::new(16)?.into_tensor().is_err());
+        Ok(())
+    }
+}
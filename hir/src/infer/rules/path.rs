@@ -299,7 +299,7 @@ fn get_shape_path(shape: &ShapeFactoid, path: &[isize]) -> TractResult<Wrapped>
 fn get_value_path(value: &ValueFact, path: &[isize]) -> TractResult<Wrapped> {
     trace!("get_value_path path:{:?} value:{:?}", path, value);
     // Return the whole tensor.
-    if path == &[-1] || path == &[] {
+    if path == &[-1] || path.is_empty() {
         return Ok(value.clone().wrap());
     }
 
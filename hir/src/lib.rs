@@ -32,6 +32,7 @@ pub mod ops {
     pub use tract_core::ops::math;
     pub mod matmul;
     pub mod nn;
+    pub mod opaque;
     pub use tract_core::ops::quant;
     pub mod scan;
     pub mod source;
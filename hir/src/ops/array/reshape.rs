@@ -131,7 +131,7 @@ pub fn to_axis_ops(input_orig: &[TDim], output_spec: &[TDim]) -> TractResult<TVe
                         }
                     }
                 }
-                todo!()
+                bail!("Can not find a reshape from {:?} to {:?}", current_input, final_output)
             }
         } else {
             if final_output.len() > current_input.len() {
@@ -195,6 +195,35 @@ mod tests {
         assert_eq!(&*compute_shape(s!['s', 'b', 2, 128], s!(0, 0, -1)).unwrap(), s!['s', 'b', 256])
     }
 
+    #[test]
+    fn symbolic_shape_chain() -> TractResult<()> {
+        use crate::ops::array::{AddDims, Concat, Gather, Shape};
+        use tract_core::ops::math::{Div, Mul};
+        let mut model = InferenceModel::default();
+        let input = model.add_source("input", f32::fact(&[stream(), 4.into(), 8.into()]).into())?;
+        let shape = model.wire_node("shape", expand(Shape::new(i64::datum_type())), &[input])?;
+        let shape = model.wire_node("cast", crate::ops::cast::cast(i64::datum_type()), &shape)?;
+        let zero = model.add_const("zero", rctensor0(0i64))?;
+        let dim = model.wire_node("gather", expand(Gather::new(0)), &[shape[0], zero])?;
+        let eight = model.add_const("eight", rctensor0(8i64))?;
+        let dim = model.wire_node("mul", Mul.into_hir(), &[dim[0], eight])?;
+        let two = model.add_const("two", rctensor0(2i64))?;
+        let dim = model.wire_node("div", Div.into_hir(), &[dim[0], two])?;
+        let dim = model.wire_node("unsqueeze", expand(AddDims::new(vec![0])), &dim)?;
+        let minus_one = model.add_const("minus_one", rctensor1(&[-1i64]))?;
+        let target = model.wire_node("concat", expand(Concat::new(0)), &[dim[0], minus_one])?;
+        let output =
+            model.wire_node("reshape", expand(super::Reshape::new()), &[input, target[0]])?;
+        model.set_output_outlets(&output)?;
+        let typed = model.into_typed()?.into_decluttered()?;
+        assert_eq!(typed.nodes.len(), 2);
+        assert_eq!(
+            typed.output_fact(0)?.shape,
+            ShapeFact::from_dims(tvec!(stream() * 4, 8.into()))
+        );
+        Ok(())
+    }
+
     #[test]
     fn axis_op_rm_begin() {
         assert_eq!(&*to_axis_ops(s![1, 2, 3], s!(2, 3)).unwrap(), &[Rm(0)])
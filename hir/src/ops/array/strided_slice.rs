@@ -152,6 +152,10 @@ impl StridedSlice {
                 }
             }
         }
+        // a constant end may be past a symbolic dimension
+        if stride > 0 && dim.to_isize().is_err() && end.to_isize().is_ok() {
+            end = end.mini(dim.clone());
+        }
         Ok(Dim { begin, end, stride, shrink: false })
     }
 }
@@ -617,4 +621,17 @@ mod tests {
             Dim { begin: 3.to_dim(), end: -1.to_dim(), stride: -1, shrink: false }
         );
     }
+
+    #[test]
+    fn prep_symbolic_end() {
+        let op = strided_slice(0, 0, 0);
+        assert_eq!(
+            op.prepare_one_dim(0, &s(), &tensor1(&[1i64]), &tensor1(&[10i64]), &[1]).unwrap(),
+            Dim { begin: 1.to_dim(), end: 10.to_dim().mini(s()), stride: 1, shrink: false }
+        );
+        assert_eq!(
+            op.prepare_one_dim(0, &s(), &tensor1(&[0i64]), &tensor1(&[-2i64]), &[1]).unwrap(),
+            Dim { begin: 0.to_dim(), end: s() - 2, stride: 1, shrink: false }
+        );
+    }
 }
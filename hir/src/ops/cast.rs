@@ -14,10 +14,257 @@ impl InferenceRulesOp for Cast {
         check_input_arity(inputs, 1)?;
         check_output_arity(outputs, 1)?;
         s.equals(&inputs[0].shape, &outputs[0].shape)?;
-        s.equals(&outputs[0].datum_type, self.to)?;
-        Ok(())
+        s.given(&inputs[0].datum_type, move |s, dt| {
+            if dt == TDim::datum_type() && self.to.is_integer() {
+                s.given(&inputs[0].value, move |s, value| {
+                    if let Ok(casted) = value.cast_to_dt(self.to) {
+                        s.equals(&outputs[0].datum_type, self.to)?;
+                        s.equals(&outputs[0].value, casted.into_owned().into_arc_tensor())
+                    } else {
+                        // symbolic dims would only resolve at runtime: keep
+                        // them as TDim so shape computations can still be
+                        // folded
+                        s.equals(&outputs[0].datum_type, dt)?;
+                        s.equals(&outputs[0].value, value)
+                    }
+                })
+            } else {
+                s.equals(&outputs[0].datum_type, self.to)
+            }
+        })
+    }
+
+    fn incorporate(
+        &self,
+        model: &InferenceModel,
+        node: &InferenceNode,
+    ) -> TractResult<Option<InferenceModelPatch>> {
+        // symbolic dims kept as TDim are folded in shape computations, but
+        // must still be cast where they escape them: to the model outputs, or
+        // to ops evaluated at runtime that would compute TDim from them
+        let outputs = model.output_outlets()?;
+        let folded_tdim = |outlet: OutletId| -> TractResult<bool> {
+            let fact = model.outlet_fact(outlet)?;
+            Ok(fact.datum_type.concretize() == Some(TDim::datum_type()) && fact.value.is_concrete())
+        };
+        let dead = |node: &InferenceNode| {
+            (0..node.outputs.len()).all(|slot| {
+                node.outputs[slot].successors.is_empty()
+                    && !outputs.contains(&OutletId::new(node.id, slot))
+            })
+        };
+        if self.to == TDim::datum_type() || !folded_tdim(node.id.into())? {
+            return Ok(None);
+        }
+        let mut todo = tvec!(OutletId::from(node.id));
+        let mut visited = tvec!();
+        while let Some(outlet) = todo.pop() {
+            visited.push(outlet);
+            let mut at_runtime = tvec!();
+            for succ in &model.node(outlet.node).outputs[outlet.slot].successors {
+                let succ = model.node(succ.node);
+                if succ.op_is::<Cast>() || succ.op_is::<RuntimeCast>() || dead(succ) {
+                    continue;
+                }
+                if succ.outputs.iter().all(|o| o.fact.value.is_concrete()) {
+                    for slot in 0..succ.outputs.len() {
+                        let outlet = OutletId::new(succ.id, slot);
+                        if folded_tdim(outlet)? && !visited.contains(&outlet) {
+                            todo.push(outlet);
+                        }
+                    }
+                } else if succ
+                    .outputs
+                    .iter()
+                    .any(|o| o.fact.datum_type.concretize() == Some(TDim::datum_type()))
+                {
+                    at_runtime.push(succ.id);
+                }
+            }
+            let is_output = outputs.contains(&outlet);
+            if is_output || at_runtime.len() > 0 {
+                return cast_escaping(self, model, outlet, is_output, &at_runtime).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        target.wire_node(&*node.name, self.clone(), &[mapping[&node.inputs[0]]])
     }
 
     as_op!();
-    to_typed!();
+}
+
+/// Casts `outlet` for the `at_runtime` consumers, and for the model output if
+/// `is_output`. The nodes computed at runtime from it are re-created with
+/// relaxed facts, to be analysed again with the cast type; the other
+/// consumers still see the symbolic dims.
+fn cast_escaping(
+    cast: &Cast,
+    model: &InferenceModel,
+    outlet: OutletId,
+    is_output: bool,
+    at_runtime: &[usize],
+) -> TractResult<InferenceModelPatch> {
+    let mut runtime: TVec<usize> = at_runtime.into();
+    let mut ix = 0;
+    while ix < runtime.len() {
+        for output in &model.node(runtime[ix]).outputs {
+            for succ in &output.successors {
+                let succ = model.node(succ.node);
+                let tdim = succ.outputs.iter().any(|o| {
+                    o.fact.datum_type.concretize() == Some(TDim::datum_type())
+                        && !o.fact.value.is_concrete()
+                });
+                if tdim && !runtime.contains(&succ.id) {
+                    runtime.push(succ.id);
+                }
+            }
+        }
+        ix += 1;
+    }
+    let mut moved = runtime.clone();
+    if is_output {
+        for succ in model.outlet_successors(outlet) {
+            if !moved.contains(&succ.node) {
+                moved.push(succ.node);
+            }
+        }
+    }
+
+    let mut patch = InferenceModelPatch::default();
+    let tap = patch.tap_model(model, outlet)?;
+    let name = format!("{}.cast", model.node(outlet.node).name);
+    let fact = InferenceFact::shape(model.outlet_fact(outlet)?.shape.clone());
+    let casted = patch.add_node(name, RuntimeCast { to: cast.to }, tvec!(fact))?;
+    patch.add_edge(tap, InletId::new(casted, 0))?;
+    let casted = OutletId::new(casted, 0);
+    if is_output {
+        patch.shunt_outside(model, outlet, casted)?;
+    }
+    let mut mapping = HashMap::new();
+    for node in model.eval_order()? {
+        if !moved.contains(&node) {
+            continue;
+        }
+        let node = model.node(node);
+        let relax = runtime.contains(&node.id);
+        let facts = node
+            .outputs
+            .iter()
+            .map(
+                |o| if relax { InferenceFact::shape(o.fact.shape.clone()) } else { o.fact.clone() },
+            )
+            .collect();
+        let copy = patch.add_node(&*node.name, node.op.clone(), facts)?;
+        for (ix, &input) in node.inputs.iter().enumerate() {
+            let wire = if input == outlet {
+                if relax {
+                    casted
+                } else {
+                    tap
+                }
+            } else if let Some(wire) = mapping.get(&input) {
+                *wire
+            } else {
+                patch.tap_model(model, input)?
+            };
+            patch.add_edge(wire, InletId::new(copy, ix))?;
+        }
+        for slot in 0..node.outputs.len() {
+            mapping.insert(OutletId::new(node.id, slot), OutletId::new(copy, slot));
+            patch.shunt_outside(model, OutletId::new(node.id, slot), OutletId::new(copy, slot))?;
+        }
+    }
+    Ok(patch)
+}
+
+/// Cast of symbolic dims escaping shape computations, evaluated at runtime.
+///
+#[derive(Debug, Clone, Hash)]
+struct RuntimeCast {
+    to: DatumType,
+}
+
+impl_dyn_hash!(RuntimeCast);
+
+impl Op for RuntimeCast {
+    fn name(&self) -> Cow<str> {
+        "RuntimeCast".into()
+    }
+
+    op_hir!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for RuntimeCast {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+}
+
+impl InferenceRulesOp for RuntimeCast {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&outputs[0].datum_type, self.to)
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        target.wire_node(&*node.name, cast(self.to), &[mapping[&node.inputs[0]]])
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use super::cast;
+    use crate::internal::*;
+    use crate::ops::array::Shape;
+    use tract_core::ops::math::Add;
+
+    #[test]
+    fn symbolic_cast_output() -> TractResult<()> {
+        let mut model = InferenceModel::default();
+        let input =
+            model.add_source("input", f32::fact(&[Symbol::from('S').to_dim(), 4.into()]).into())?;
+        let offset = model.add_source("offset", i64::fact(&[2]).into())?;
+        let shape = model.wire_node("shape", expand(Shape::new(i64::datum_type())), &[input])?;
+        let shape = model.wire_node("cast", cast(i64::datum_type()), &shape)?;
+        let one = model.add_const("one", rctensor1(&[1i64, 1]))?;
+        let folded = model.wire_node("folded", Add.into_hir(), &[shape[0], one])?;
+        let runtime = model.wire_node("runtime", Add.into_hir(), &[shape[0], offset])?;
+        model.set_output_outlets(&[shape[0], folded[0], runtime[0]])?;
+        let typed = model.into_typed()?.into_decluttered()?;
+        for ix in 0..3 {
+            assert_eq!(typed.output_fact(ix)?.datum_type, i64::datum_type());
+        }
+        let outputs = typed
+            .into_runnable()?
+            .run(tvec!(Tensor::zero::<f32>(&[3, 4])?, tensor1(&[10i64, 20])))?;
+        assert_eq!(*outputs[0], tensor1(&[3i64, 4]));
+        assert_eq!(*outputs[1], tensor1(&[4i64, 5]));
+        assert_eq!(*outputs[2], tensor1(&[13i64, 24]));
+        Ok(())
+    }
 }
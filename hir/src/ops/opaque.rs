@@ -0,0 +1,77 @@
+use crate::infer::*;
+use crate::internal::*;
+
+pub use tract_core::ops::opaque::{OpaqueEval, OpaqueOp};
+
+/// Inference counterpart of `OpaqueOp`: there are no rules, so the output
+/// facts must be provided by the model (or by the user) before typing.
+#[derive(Debug, Clone, Hash)]
+pub struct InferenceOpaqueOp {
+    pub kind: String,
+    pub attributes: Vec<(String, Arc<Tensor>)>,
+    pub outputs: usize,
+}
+
+impl_dyn_hash!(InferenceOpaqueOp);
+
+impl Op for InferenceOpaqueOp {
+    fn name(&self) -> Cow<str> {
+        format!("Opaque({})", self.kind).into()
+    }
+
+    op_hir!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for InferenceOpaqueOp {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, _inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        bail!("Opaque operator {} can only run in a typed model", self.kind)
+    }
+}
+
+impl InferenceRulesOp for InferenceOpaqueOp {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        _s: &mut Solver<'r>,
+        _inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(&outputs, self.outputs)?;
+        Ok(())
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.outputs)
+    }
+
+    as_op!();
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        use std::convert::TryFrom;
+        let output_facts = node
+            .outputs
+            .iter()
+            .map(|o| TypedFact::try_from(&o.fact))
+            .collect::<TractResult<TVec<_>>>()
+            .with_context(|| {
+                format!(
+                    "Opaque operator {} without determined output facts. Help: provide explicit facts for its outputs.",
+                    self.kind
+                )
+            })?;
+        let op =
+            OpaqueOp { kind: self.kind.clone(), attributes: self.attributes.clone(), output_facts };
+        let inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        target.wire_node(&*node.name, op, &inputs)
+    }
+}
@@ -0,0 +1,134 @@
+//! Accuracy selection for the transcendental functions.
+//!
+//! Unless an accuracy is selected for the duration of a closure (on the
+//! calling thread) with `accuracy_scope`, each function runs at its default
+//! accuracy: exp is exact, erf, sigmoid and tanh are the vectorized kernels.
+use std::cell::Cell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accuracy {
+    /// Rust (libm) implementations, without vectorization.
+    Exact,
+    /// Vectorized approximations, within a few ulps (or 1e-6 absolute error
+    /// for erf, sigmoid and tanh) of the exact values.
+    Accurate,
+    /// Cheaper vectorized approximations, with a relative error around 1e-4
+    /// (1e-3 absolute error for erf).
+    Fast,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transcendental {
+    Exp,
+    Erf,
+    Sigmoid,
+    Tanh,
+}
+
+impl Transcendental {
+    /// Accuracy of the function when none is selected. Approximations of exp
+    /// are opt-in.
+    pub fn default_accuracy(self) -> Accuracy {
+        match self {
+            Transcendental::Exp => Accuracy::Exact,
+            _ => Accuracy::Accurate,
+        }
+    }
+}
+
+thread_local! {
+    static TLS_ACCURACY: Cell<Option<Accuracy>> = const { Cell::new(None) };
+}
+
+/// Accuracy selected for operations started from the current thread, if any.
+pub fn current_accuracy() -> Option<Accuracy> {
+    TLS_ACCURACY.with(|a| a.get())
+}
+
+/// Accuracy to run `function` at from the current thread.
+pub fn accuracy_for(function: Transcendental) -> Accuracy {
+    current_accuracy().unwrap_or_else(|| function.default_accuracy())
+}
+
+/// Runs `f` with `accuracy` overriding the default one on this thread.
+pub fn accuracy_scope<R, F: FnOnce() -> R>(accuracy: Accuracy, f: F) -> R {
+    let previous = TLS_ACCURACY.with(|a| a.replace(Some(accuracy)));
+    struct Restore(Option<Accuracy>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0;
+            TLS_ACCURACY.with(|a| a.set(previous));
+        }
+    }
+    let _restore = Restore(previous);
+    f()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::element_wise::ElementWise;
+    use crate::Ops;
+
+    fn run(ops: &Ops, f: Transcendental, accuracy: Accuracy, xs: &[f32]) -> Vec<f32> {
+        let mut xs = xs.to_vec();
+        ops.transcendental_f32(f, accuracy).run(&mut xs).unwrap();
+        xs
+    }
+
+    fn exact(f: Transcendental, x: f32) -> f64 {
+        let x = x as f64;
+        match f {
+            Transcendental::Exp => x.exp(),
+            Transcendental::Erf => libm::erf(x),
+            Transcendental::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Transcendental::Tanh => x.tanh(),
+        }
+    }
+
+    fn check(ops: &Ops, f: Transcendental, accuracy: Accuracy, rel: f64, abs: f64) {
+        let range = if f == Transcendental::Exp { -87.0..88.0 } else { -10.0..10.0 };
+        let xs: Vec<f32> = (0..2001)
+            .map(|i| range.start + (range.end - range.start) * i as f32 / 2000.0)
+            .chain([0.0, 1e-3, -1e-3, 0.5, -0.5].iter().copied())
+            .collect();
+        let found = run(ops, f, accuracy, &xs);
+        for (x, y) in xs.iter().zip(found.iter()) {
+            let e = exact(f, *x);
+            assert!(
+                (*y as f64 - e).abs() <= abs + rel * e.abs(),
+                "{:?} {:?} at {}: found {} expected {}",
+                f,
+                accuracy,
+                x,
+                y,
+                e
+            );
+        }
+    }
+
+    #[test]
+    fn accuracies() {
+        use Transcendental::*;
+        for ops in &[crate::generic(), crate::best()] {
+            for f in &[Exp, Erf, Sigmoid, Tanh] {
+                check(ops, *f, Accuracy::Exact, 2e-7, 1e-37);
+                check(ops, *f, Accuracy::Accurate, 1e-6, 1e-6);
+                check(ops, *f, Accuracy::Fast, 1e-4, 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn scope() {
+        assert_eq!(current_accuracy(), None);
+        assert_eq!(accuracy_for(Transcendental::Exp), Accuracy::Exact);
+        assert_eq!(accuracy_for(Transcendental::Tanh), Accuracy::Accurate);
+        let inner = accuracy_scope(Accuracy::Fast, || {
+            accuracy_scope(Accuracy::Exact, current_accuracy);
+            (current_accuracy(), accuracy_for(Transcendental::Exp))
+        });
+        assert_eq!(inner, (Some(Accuracy::Fast), Accuracy::Fast));
+        assert_eq!(current_accuracy(), None);
+    }
+}
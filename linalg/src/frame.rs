@@ -1,4 +1,11 @@
 #[macro_use]
+pub mod arg_min_max;
+#[macro_use]
+pub mod attention;
+pub mod block_quant;
+#[macro_use]
+pub mod depthwise;
+#[macro_use]
 pub mod element_wise;
 #[macro_use]
 pub mod lut;
@@ -8,10 +15,16 @@ pub mod pack;
 #[macro_use]
 pub mod sigmoid;
 #[macro_use]
+pub mod softmax;
+#[macro_use]
 pub mod tanh;
+#[macro_use]
+pub mod winograd;
 
 pub use pack::Packer;
 pub use pack::PackingWriter;
 
+pub use self::block_quant::BlockQuant;
 pub use self::element_wise::{ ElementWise, ElementWiseImpl};
 pub use self::mmm::{MatMatMul, MatMatMulImpl};
+pub use self::winograd::{Winograd, WinogradImpl};
@@ -0,0 +1,97 @@
+//! Index of the maximum or minimum of a buffer.
+//!
+//! The semantics are the ones of a sequential fold starting from the lowest
+//! (or greatest) value at index 0: NaNs are ignored, ties go to the first (or
+//! last) occurence of the extremum, and the result is 0 if no value beats the
+//! starting one.
+use std::fmt::Debug;
+
+pub trait ArgMinMax<T>: Send + Sync + Debug + dyn_clone::DynClone
+where
+    T: Copy + Debug + PartialEq + Send + Sync,
+{
+    fn name(&self) -> &'static str;
+    fn argmax(&self, vec: &[T], last: bool) -> usize;
+    fn argmin(&self, vec: &[T], last: bool) -> usize;
+}
+
+dyn_clone::clone_trait_object!(<T> ArgMinMax<T> where T: Copy);
+
+/// Merges a candidate found out of order: `b` replaces `a` if its value is
+/// better, or equal with a more suitable index.
+#[inline]
+pub fn better<T: PartialOrd>(max: bool, last: bool, a: (usize, T), b: (usize, T)) -> bool {
+    let strictly = if max { b.1 > a.1 } else { b.1 < a.1 };
+    strictly || (b.1 == a.1 && if last { b.0 > a.0 } else { b.0 < a.0 })
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseResult;
+
+    #[macro_export]
+    macro_rules! arg_min_max_frame_tests {
+        ($cond:expr, $ker:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn arg_min_max_prop(
+                    xs in crate::frame::arg_min_max::test::values(),
+                    last in proptest::bool::ANY
+                ) {
+                    if $cond {
+                        crate::frame::arg_min_max::test::check(&$ker, &xs, last)?
+                    }
+                }
+            }
+
+            #[test]
+            fn arg_min_max_extremes() {
+                if $cond {
+                    let extremes = [f32::NEG_INFINITY, f32::MIN, 3.0, f32::MAX, f32::INFINITY];
+                    let xs: Vec<f32> = extremes.iter().cycle().take(21).copied().collect();
+                    for last in &[false, true] {
+                        crate::frame::arg_min_max::test::check(&$ker, &xs, *last).unwrap();
+                        crate::frame::arg_min_max::test::check(&$ker, &xs[..2], *last).unwrap();
+                        crate::frame::arg_min_max::test::check(&$ker, &xs[..16], *last).unwrap();
+                        crate::frame::arg_min_max::test::check(&$ker, &[f32::MIN; 16], *last)
+                            .unwrap();
+                    }
+                }
+            }
+        };
+    }
+
+    /// Small integers, to get ties, and NaNs.
+    pub fn values() -> BoxedStrategy<Vec<f32>> {
+        proptest::collection::vec(
+            prop_oneof![9 => (-5i32..5).prop_map(|x| x as f32), 1 => Just(f32::NAN)],
+            0..100,
+        )
+        .boxed()
+    }
+
+    pub fn reference(xs: &[f32], max: bool, last: bool) -> usize {
+        let init = if max { f32::MIN } else { f32::MAX };
+        xs.iter()
+            .copied()
+            .enumerate()
+            .fold((0, init), |acc, x| {
+                let strictly = if max { x.1 > acc.1 } else { x.1 < acc.1 };
+                if strictly || (last && x.1 == acc.1) {
+                    x
+                } else {
+                    acc
+                }
+            })
+            .0
+    }
+
+    pub fn check<K: ArgMinMax<f32>>(ker: &K, xs: &[f32], last: bool) -> TestCaseResult {
+        prop_assert_eq!(ker.argmax(xs, last), reference(xs, true, last));
+        prop_assert_eq!(ker.argmin(xs, last), reference(xs, false, last));
+        Ok(())
+    }
+}
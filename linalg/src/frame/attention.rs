@@ -0,0 +1,200 @@
+//! Scaled dot product attention for a single head:
+//! out = softmax(scale * q.k^T + mask).v
+//!
+//! Kernels never materialize the q_len x kv_len scores: keys and values are
+//! consumed by blocks, with a running maximum and sum per query row (the
+//! "flash attention" online softmax).
+use std::fmt::Debug;
+use tract_data::anyhow;
+
+/// Operands of a single head attention, all contiguous and row-major.
+#[derive(Debug, Clone, Copy)]
+pub struct AttentionProblem<'a> {
+    /// q_len x depth
+    pub q: &'a [f32],
+    /// kv_len x depth
+    pub k: &'a [f32],
+    /// kv_len x value_depth
+    pub v: &'a [f32],
+    /// q_len x kv_len, added to the scaled scores.
+    pub mask: Option<&'a [f32]>,
+    pub scale: f32,
+    pub q_len: usize,
+    pub kv_len: usize,
+    pub depth: usize,
+    pub value_depth: usize,
+}
+
+impl<'a> AttentionProblem<'a> {
+    pub fn check(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(self.q.len() == self.q_len * self.depth, "Wrong q size");
+        anyhow::ensure!(self.k.len() == self.kv_len * self.depth, "Wrong k size");
+        anyhow::ensure!(self.v.len() == self.kv_len * self.value_depth, "Wrong v size");
+        if let Some(mask) = self.mask {
+            anyhow::ensure!(mask.len() == self.q_len * self.kv_len, "Wrong mask size");
+        }
+        Ok(())
+    }
+}
+
+pub trait Attention: Send + Sync + Debug + dyn_clone::DynClone {
+    fn name(&self) -> &'static str;
+
+    /// Writes the q_len x value_depth output to `out`.
+    fn run(&self, problem: &AttentionProblem, out: &mut [f32]) -> anyhow::Result<()>;
+}
+
+dyn_clone::clone_trait_object!(Attention);
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseResult;
+
+    #[macro_export]
+    macro_rules! attention_frame_tests {
+        ($cond:expr, $ker:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn attention_prop(pb in crate::frame::attention::test::problem()) {
+                    if $cond {
+                        pb.check(&$ker)?
+                    }
+                }
+            }
+
+            #[test]
+            fn attention_masked_out_keys() {
+                if $cond {
+                    let mut pb =
+                        crate::frame::attention::test::AttentionTestProblem::new(3, 70, 4, 5);
+                    let mask = pb.mask.insert(vec![0.0; 3 * 70]);
+                    mask.iter_mut().skip(2).step_by(3).for_each(|m| *m = f32::NEG_INFINITY);
+                    pb.check(&$ker).unwrap()
+                }
+            }
+
+            #[test]
+            fn attention_large_scores() {
+                if $cond {
+                    let mut pb =
+                        crate::frame::attention::test::AttentionTestProblem::new(2, 3, 1, 1);
+                    pb.q = vec![100.0, -100.0];
+                    pb.k = vec![10.0, 9.0, -10.0];
+                    pb.check(&$ker).unwrap()
+                }
+            }
+        };
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct AttentionTestProblem {
+        pub q: Vec<f32>,
+        pub k: Vec<f32>,
+        pub v: Vec<f32>,
+        pub mask: Option<Vec<f32>>,
+        pub scale: f32,
+        pub q_len: usize,
+        pub kv_len: usize,
+        pub depth: usize,
+        pub value_depth: usize,
+    }
+
+    pub fn problem() -> BoxedStrategy<AttentionTestProblem> {
+        (1usize..6, 1usize..80, 1usize..9, 1usize..9, proptest::bool::ANY)
+            .prop_flat_map(|(q_len, kv_len, depth, value_depth, masked)| {
+                let values = |n: usize| proptest::collection::vec(-2f32..2f32, n..=n);
+                (
+                    values(q_len * depth),
+                    values(kv_len * depth),
+                    values(kv_len * value_depth),
+                    values(if masked { q_len * kv_len } else { 0 }),
+                    0.1f32..2.0,
+                )
+                    .prop_map(move |(q, k, v, mask, scale)| AttentionTestProblem {
+                        q,
+                        k,
+                        v,
+                        mask: Some(mask).filter(|_| masked),
+                        scale,
+                        q_len,
+                        kv_len,
+                        depth,
+                        value_depth,
+                    })
+            })
+            .boxed()
+    }
+
+    impl AttentionTestProblem {
+        pub fn new(q_len: usize, kv_len: usize, depth: usize, value_depth: usize) -> Self {
+            let values = |n: usize| (0..n).map(|i| ((i * 7) % 11) as f32 / 5.0 - 1.0).collect();
+            AttentionTestProblem {
+                q: values(q_len * depth),
+                k: values(kv_len * depth),
+                v: values(kv_len * value_depth),
+                mask: None,
+                scale: 0.5,
+                q_len,
+                kv_len,
+                depth,
+                value_depth,
+            }
+        }
+
+        fn problem(&self) -> AttentionProblem {
+            AttentionProblem {
+                q: &self.q,
+                k: &self.k,
+                v: &self.v,
+                mask: self.mask.as_deref(),
+                scale: self.scale,
+                q_len: self.q_len,
+                kv_len: self.kv_len,
+                depth: self.depth,
+                value_depth: self.value_depth,
+            }
+        }
+
+        pub fn reference(&self) -> Vec<f32> {
+            let mut out = vec![0f32; self.q_len * self.value_depth];
+            for i in 0..self.q_len {
+                let scores: Vec<f64> = (0..self.kv_len)
+                    .map(|j| {
+                        let dot: f64 = (0..self.depth)
+                            .map(|d| {
+                                self.q[i * self.depth + d] as f64
+                                    * self.k[j * self.depth + d] as f64
+                            })
+                            .sum();
+                        let mask =
+                            self.mask.as_ref().map(|m| m[i * self.kv_len + j]).unwrap_or(0.0);
+                        dot * self.scale as f64 + mask as f64
+                    })
+                    .collect();
+                let max = scores.iter().fold(f64::MIN, |a, b| a.max(*b));
+                let exps: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+                let sum: f64 = exps.iter().sum();
+                for d in 0..self.value_depth {
+                    let acc: f64 = (0..self.kv_len)
+                        .map(|j| exps[j] * self.v[j * self.value_depth + d] as f64)
+                        .sum();
+                    out[i * self.value_depth + d] = (acc / sum) as f32;
+                }
+            }
+            out
+        }
+
+        pub fn check<K: Attention>(&self, ker: &K) -> TestCaseResult {
+            let mut found = vec![f32::NAN; self.q_len * self.value_depth];
+            ker.run(&self.problem(), &mut found).unwrap();
+            let expected = self.reference();
+            for (f, e) in found.iter().zip(expected.iter()) {
+                prop_assert!((f - e).abs() <= 1e-4 + e.abs() * 1e-4, "{:?} {:?}", found, expected);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! Block-quantized weight formats, as found in GGML / GGUF files.
+//!
+//! Values are stored in fixed-size blocks along the innermost axis, each
+//! block carrying its own scale (and minimum for the "K" formats). Matrices
+//! are stored row by row, each row being a sequence of blocks, so the
+//! inner dimension must be a multiple of the block length.
+//!
+//! The packed int4 blocks of tract-data, with a scale and a zero point per
+//! block, are a format too.
+use std::fmt::Debug;
+use tract_data::half::f16;
+use tract_data::prelude::Int4Blocks;
+
+pub trait BlockQuant: Send + Sync + Debug + dyn_clone::DynClone {
+    fn name(&self) -> &'static str;
+
+    /// Number of values in a block.
+    fn block_len(&self) -> usize;
+
+    /// Size of a block, in bytes.
+    fn block_bytes(&self) -> usize;
+
+    /// Dequantizes a single block to `block_len` f32 values.
+    fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]);
+
+    /// Dequantizes a sequence of blocks.
+    fn dequant_f32(&self, blocks: &[u8], values: &mut [f32]) {
+        debug_assert_eq!(blocks.len() / self.block_bytes() * self.block_len(), values.len());
+        for (block, values) in
+            blocks.chunks_exact(self.block_bytes()).zip(values.chunks_exact_mut(self.block_len()))
+        {
+            self.dequant_block_f32(block, values)
+        }
+    }
+
+    /// Computes c = a.bt, for a m x k block-quantized a, a n x k row-major b
+    /// and a n x m row-major c.
+    ///
+    /// `a` is consumed directly in its blocked layout, dequantizing one
+    /// block at a time.
+    fn matmul_bt_f32(&self, a: &[u8], m: usize, k: usize, b: &[f32], n: usize, c: &mut [f32]) {
+        let block_len = self.block_len();
+        let block_bytes = self.block_bytes();
+        debug_assert_eq!(k % block_len, 0);
+        debug_assert_eq!(a.len(), m * k / block_len * block_bytes);
+        debug_assert_eq!(b.len(), n * k);
+        debug_assert_eq!(c.len(), n * m);
+        c.iter_mut().for_each(|c| *c = 0.0);
+        let mut values = [0f32; 256];
+        let values = &mut values[..block_len];
+        for (row, a) in a.chunks_exact(k / block_len * block_bytes).enumerate() {
+            for (ix, block) in a.chunks_exact(block_bytes).enumerate() {
+                self.dequant_block_f32(block, values);
+                for j in 0..n {
+                    let b = &b[j * k + ix * block_len..][..block_len];
+                    c[j * m + row] += values.iter().zip(b.iter()).map(|(a, b)| a * b).sum::<f32>();
+                }
+            }
+        }
+    }
+}
+
+dyn_clone::clone_trait_object!(BlockQuant);
+
+impl std::hash::Hash for Box<dyn BlockQuant> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+
+impl PartialEq for Box<dyn BlockQuant> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+fn read_f16(bytes: &[u8]) -> f32 {
+    f16::from_bits(u16::from_le_bytes([bytes[0], bytes[1]])).to_f32()
+}
+
+fn write_f16(bytes: &mut [u8], value: f32) {
+    bytes[0..2].copy_from_slice(&f16::from_f32(value).to_bits().to_le_bytes())
+}
+
+/// 32 values, a f16 scale and 4-bit quants offset by 8.
+#[derive(Clone, Copy, Debug)]
+pub struct Q4_0;
+
+/// 32 values, a f16 scale and 8-bit quants.
+#[derive(Clone, Copy, Debug)]
+pub struct Q8_0;
+
+/// 256 values in 8 sub-blocks of 32, a f16 super-scale and super-minimum,
+/// 6-bit sub-block scales and minimums, and 4-bit quants.
+#[derive(Clone, Copy, Debug)]
+pub struct Q4K;
+
+impl Q4_0 {
+    /// Quantizes a block of 32 values.
+    pub fn quant_block_f32(&self, values: &[f32], block: &mut [u8]) {
+        let max = values.iter().fold(0f32, |acc, &v| if v.abs() > acc.abs() { v } else { acc });
+        let d = max / -8.0;
+        let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+        write_f16(block, d);
+        for j in 0..16 {
+            let q0 = ((values[j] * id + 8.5) as i32).min(15) as u8;
+            let q1 = ((values[j + 16] * id + 8.5) as i32).min(15) as u8;
+            block[2 + j] = q0 | (q1 << 4);
+        }
+    }
+}
+
+impl BlockQuant for Q4_0 {
+    fn name(&self) -> &'static str {
+        "Q4_0"
+    }
+
+    fn block_len(&self) -> usize {
+        32
+    }
+
+    fn block_bytes(&self) -> usize {
+        18
+    }
+
+    fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]) {
+        let d = read_f16(block);
+        for j in 0..16 {
+            values[j] = ((block[2 + j] & 0x0F) as i32 - 8) as f32 * d;
+            values[j + 16] = ((block[2 + j] >> 4) as i32 - 8) as f32 * d;
+        }
+    }
+}
+
+impl Q8_0 {
+    /// Quantizes a block of 32 values.
+    pub fn quant_block_f32(&self, values: &[f32], block: &mut [u8]) {
+        let amax = values.iter().fold(0f32, |acc, v| acc.max(v.abs()));
+        let d = amax / 127.0;
+        let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+        write_f16(block, d);
+        for j in 0..32 {
+            block[2 + j] = (values[j] * id).round() as i8 as u8;
+        }
+    }
+}
+
+impl BlockQuant for Q8_0 {
+    fn name(&self) -> &'static str {
+        "Q8_0"
+    }
+
+    fn block_len(&self) -> usize {
+        32
+    }
+
+    fn block_bytes(&self) -> usize {
+        34
+    }
+
+    fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]) {
+        let d = read_f16(block);
+        for j in 0..32 {
+            values[j] = block[2 + j] as i8 as f32 * d;
+        }
+    }
+}
+
+impl Q4K {
+    /// Scale and minimum of sub-block `j`, packed on 6 bits in `scales`.
+    fn scale_min(j: usize, scales: &[u8]) -> (u8, u8) {
+        if j < 4 {
+            (scales[j] & 63, scales[j + 4] & 63)
+        } else {
+            (
+                (scales[j + 4] & 0x0F) | ((scales[j - 4] >> 6) << 4),
+                (scales[j + 4] >> 4) | ((scales[j] >> 6) << 4),
+            )
+        }
+    }
+}
+
+impl BlockQuant for Q4K {
+    fn name(&self) -> &'static str {
+        "Q4_K"
+    }
+
+    fn block_len(&self) -> usize {
+        256
+    }
+
+    fn block_bytes(&self) -> usize {
+        144
+    }
+
+    fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]) {
+        let d = read_f16(&block[0..2]);
+        let dmin = read_f16(&block[2..4]);
+        let scales = &block[4..16];
+        let quants = &block[16..144];
+        for chunk in 0..4 {
+            let q = &quants[chunk * 32..][..32];
+            let (sc, m) = Self::scale_min(2 * chunk, scales);
+            let (d1, m1) = (d * sc as f32, dmin * m as f32);
+            let (sc, m) = Self::scale_min(2 * chunk + 1, scales);
+            let (d2, m2) = (d * sc as f32, dmin * m as f32);
+            let values = &mut values[chunk * 64..][..64];
+            for l in 0..32 {
+                values[l] = d1 * (q[l] & 0x0F) as f32 - m1;
+                values[l + 32] = d2 * (q[l] >> 4) as f32 - m2;
+            }
+        }
+    }
+}
+
+impl BlockQuant for Int4Blocks {
+    fn name(&self) -> &'static str {
+        match self.block_len() {
+            32 => "I4_32",
+            64 => "I4_64",
+            128 => "I4_128",
+            _ => unreachable!(),
+        }
+    }
+
+    fn block_len(&self) -> usize {
+        Int4Blocks::block_len(self)
+    }
+
+    fn block_bytes(&self) -> usize {
+        Int4Blocks::block_bytes(self)
+    }
+
+    fn dequant_block_f32(&self, block: &[u8], values: &mut [f32]) {
+        Int4Blocks::dequant_block_f32(self, block, values)
+    }
+
+    /// Accumulates the products of b by the packed quants, and only applies
+    /// the scale and zero point once per block: (q - zp) * d . b is
+    /// d * (q . b - zp * sum(b)).
+    fn matmul_bt_f32(&self, a: &[u8], m: usize, k: usize, b: &[f32], n: usize, c: &mut [f32]) {
+        let block_len = Int4Blocks::block_len(self);
+        let block_bytes = Int4Blocks::block_bytes(self);
+        debug_assert_eq!(k % block_len, 0);
+        debug_assert_eq!(a.len(), m * k / block_len * block_bytes);
+        debug_assert_eq!(b.len(), n * k);
+        debug_assert_eq!(c.len(), n * m);
+        c.iter_mut().for_each(|c| *c = 0.0);
+        let blocks_per_row = k / block_len;
+        let b_sums: Vec<f32> = b.chunks_exact(block_len).map(|b| b.iter().sum::<f32>()).collect();
+        for (row, a) in a.chunks_exact(blocks_per_row * block_bytes).enumerate() {
+            for (ix, block) in a.chunks_exact(block_bytes).enumerate() {
+                let d = self.scale(block);
+                let zp = self.zero_point(block) as f32;
+                let quants = self.quants(block);
+                for j in 0..n {
+                    let b = &b[j * k + ix * block_len..][..block_len];
+                    let mut dot = 0f32;
+                    for (q, b) in quants.iter().zip(b.chunks_exact(2)) {
+                        dot += (q & 0x0F) as f32 * b[0] + (q >> 4) as f32 * b[1];
+                    }
+                    c[j * m + row] += d * (dot - zp * b_sums[j * blocks_per_row + ix]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn values(len: usize) -> BoxedStrategy<Vec<f32>> {
+        proptest::collection::vec((-100i32..100).prop_map(|i| i as f32 / 100.0), len).boxed()
+    }
+
+    fn check_matmul(
+        bq: &dyn BlockQuant,
+        blocks: &[u8],
+        m: usize,
+        k: usize,
+        b: &[f32],
+    ) -> proptest::test_runner::TestCaseResult {
+        let n = b.len() / k;
+        let mut a = vec![0f32; m * k];
+        bq.dequant_f32(blocks, &mut a);
+        let mut expected = vec![0f32; n * m];
+        for j in 0..n {
+            for i in 0..m {
+                expected[j * m + i] = (0..k).map(|x| a[i * k + x] * b[j * k + x]).sum();
+            }
+        }
+        let mut found = vec![0f32; n * m];
+        bq.matmul_bt_f32(blocks, m, k, b, n, &mut found);
+        crate::check_close(&found, &expected)
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn q4_0_roundtrip(v in values(32)) {
+            let mut block = [0u8; 18];
+            Q4_0.quant_block_f32(&v, &mut block);
+            let mut found = [0f32; 32];
+            Q4_0.dequant_block_f32(&block, &mut found);
+            let amax = v.iter().fold(0f32, |acc, v| acc.max(v.abs()));
+            prop_assert!(v.iter().zip(found.iter()).all(|(a, b)| (a - b).abs() <= amax / 8.0 + 1e-3));
+        }
+
+        #[test]
+        fn q8_0_roundtrip(v in values(32)) {
+            let mut block = [0u8; 34];
+            Q8_0.quant_block_f32(&v, &mut block);
+            let mut found = [0f32; 32];
+            Q8_0.dequant_block_f32(&block, &mut found);
+            let amax = v.iter().fold(0f32, |acc, v| acc.max(v.abs()));
+            prop_assert!(v.iter().zip(found.iter()).all(|(a, b)| (a - b).abs() <= amax / 127.0));
+        }
+
+        #[test]
+        fn q8_0_matmul(a in values(3 * 64), b in values(2 * 64)) {
+            let mut blocks = vec![0u8; 3 * 2 * 34];
+            for (v, block) in a.chunks(32).zip(blocks.chunks_mut(34)) {
+                Q8_0.quant_block_f32(v, block);
+            }
+            check_matmul(&Q8_0, &blocks, 3, 64, &b)?
+        }
+
+        #[test]
+        fn int4_matmul(a in values(3 * 128), b in values(2 * 128), block_len in 0usize..3) {
+            let format = Int4Blocks::new(Int4Blocks::BLOCK_LENS[block_len]).unwrap();
+            let blocks = format.quant_f32(&a).unwrap();
+            check_matmul(&format, &blocks, 3, 128, &b)?
+        }
+
+        #[test]
+        fn q4_k_matmul(blocks in proptest::collection::vec(any::<u8>(), 2 * 144), b in values(3 * 256)) {
+            let mut blocks = blocks;
+            for block in blocks.chunks_mut(144) {
+                // keep scales small enough for check_close
+                write_f16(&mut block[0..2], 0.001);
+                write_f16(&mut block[2..4], 0.002);
+            }
+            check_matmul(&Q4K, &blocks, 2, 256, &b)?
+        }
+    }
+
+    #[test]
+    fn q4_k_dequant() {
+        let mut block = [0u8; 144];
+        write_f16(&mut block[0..2], 0.5);
+        write_f16(&mut block[2..4], 0.25);
+        // sub-block 0: scale 2, min 4. sub-block 5: scale 17, min 33.
+        block[4] = 2;
+        block[8] = 4;
+        block[4 + 9] = 0x01 | (0x01 << 4);
+        block[4 + 1] = 1 << 6;
+        block[4 + 5] = 2 << 6;
+        block[16] = 0x03;
+        block[16 + 2 * 32 + 1] = 0x50;
+        let mut values = [0f32; 256];
+        Q4K.dequant_block_f32(&block, &mut values);
+        assert_eq!(values[0], 0.5 * 2.0 * 3.0 - 0.25 * 4.0);
+        assert_eq!(values[1], -1.0);
+        assert_eq!(values[5 * 32 + 1], 0.5 * 17.0 * 5.0 - 0.25 * 33.0);
+    }
+}
@@ -0,0 +1,200 @@
+//! Depthwise 3x3 f32 convolution kernels.
+//!
+//! Two layouts are covered. In planar layouts (channels outside of the
+//! spatial axes) a kernel computes a run of outputs in a row, for strides
+//! 1 and 2. In interleaved layouts (channels innermost) a kernel computes
+//! all channels of an output pixel, whatever the stride. The caller deals
+//! with padding: kernels only see valid input positions.
+use std::fmt::Debug;
+use tract_data::internal::num_integer::Integer;
+
+/// Channels are grouped by blocks of this size in packed interleaved kernels.
+pub const INTERLEAVED_BLOCK: usize = 8;
+
+pub trait DepthWise3x3: Send + Sync + Debug + dyn_clone::DynClone {
+    fn name(&self) -> &'static str;
+
+    /// Computes `width` outputs of a planar row, with stride 1 or 2:
+    /// out[x] = bias + sum(kernel[3*ky+kx] * rows[ky][stride*x+kx]).
+    ///
+    /// Each row must hold at least (width - 1) * stride + 3 items.
+    unsafe fn planar_row(
+        &self,
+        stride: usize,
+        rows: [*const f32; 3],
+        kernel: &[f32; 9],
+        bias: f32,
+        out: *mut f32,
+        width: usize,
+    );
+
+    /// Computes an interleaved output pixel:
+    /// out[c] = bias[c] + sum(kernel[t][c] * inputs[t][c]).
+    ///
+    /// `kernel` comes from `pack_interleaved_kernel`.
+    unsafe fn interleaved_pixel(
+        &self,
+        inputs: &[*const f32; 9],
+        kernel: &[f32],
+        bias: *const f32,
+        out: *mut f32,
+        channels: usize,
+    );
+}
+
+dyn_clone::clone_trait_object!(DepthWise3x3);
+
+impl std::hash::Hash for Box<dyn DepthWise3x3> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+
+/// Packs a channels x 9 kernel for `interleaved_pixel`: by blocks of
+/// `INTERLEAVED_BLOCK` channels, each holding the 9 taps of its channels,
+/// tap-major, the last block being padded with zeros.
+pub fn pack_interleaved_kernel(kernel: &[f32], channels: usize) -> Vec<f32> {
+    debug_assert_eq!(kernel.len(), channels * 9);
+    let blocks = Integer::div_ceil(&channels, &INTERLEAVED_BLOCK);
+    let mut packed = vec![0f32; blocks * 9 * INTERLEAVED_BLOCK];
+    for c in 0..channels {
+        let (block, lane) = (c / INTERLEAVED_BLOCK, c % INTERLEAVED_BLOCK);
+        for t in 0..9 {
+            packed[(block * 9 + t) * INTERLEAVED_BLOCK + lane] = kernel[c * 9 + t];
+        }
+    }
+    packed
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[macro_export]
+    macro_rules! depthwise_frame_tests {
+        ($cond:expr, $ker:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn planar_row_prop(pb in crate::frame::depthwise::test::planar_problem()) {
+                    if $cond {
+                        crate::frame::depthwise::test::check_planar(&$ker, &pb)?
+                    }
+                }
+
+                #[test]
+                fn interleaved_pixel_prop(pb in crate::frame::depthwise::test::interleaved_problem()) {
+                    if $cond {
+                        crate::frame::depthwise::test::check_interleaved(&$ker, &pb)?
+                    }
+                }
+            }
+        };
+    }
+
+    fn values(len: usize) -> BoxedStrategy<Vec<f32>> {
+        proptest::collection::vec((-100i32..100).prop_map(|i| i as f32 / 100.0), len).boxed()
+    }
+
+    #[derive(Debug)]
+    pub struct PlanarProblem {
+        stride: usize,
+        width: usize,
+        rows: Vec<Vec<f32>>,
+        kernel: [f32; 9],
+        bias: f32,
+    }
+
+    pub fn planar_problem() -> BoxedStrategy<PlanarProblem> {
+        (1usize..=2, 1usize..40)
+            .prop_flat_map(|(stride, width)| {
+                let len = (width - 1) * stride + 3;
+                (Just(stride), Just(width), [values(len), values(len), values(len)], values(10))
+            })
+            .prop_map(|(stride, width, rows, kb)| {
+                let mut kernel = [0f32; 9];
+                kernel.copy_from_slice(&kb[..9]);
+                PlanarProblem { stride, width, rows: rows.to_vec(), kernel, bias: kb[9] }
+            })
+            .boxed()
+    }
+
+    pub fn check_planar(
+        ker: &dyn DepthWise3x3,
+        pb: &PlanarProblem,
+    ) -> proptest::test_runner::TestCaseResult {
+        let expected: Vec<f32> = (0..pb.width)
+            .map(|x| {
+                let mut sum = pb.bias;
+                for ky in 0..3 {
+                    for kx in 0..3 {
+                        sum += pb.kernel[3 * ky + kx] * pb.rows[ky][pb.stride * x + kx];
+                    }
+                }
+                sum
+            })
+            .collect();
+        let mut found = vec![0f32; pb.width];
+        unsafe {
+            ker.planar_row(
+                pb.stride,
+                [pb.rows[0].as_ptr(), pb.rows[1].as_ptr(), pb.rows[2].as_ptr()],
+                &pb.kernel,
+                pb.bias,
+                found.as_mut_ptr(),
+                pb.width,
+            );
+        }
+        crate::check_close(&found, &expected)
+    }
+
+    #[derive(Debug)]
+    pub struct InterleavedProblem {
+        channels: usize,
+        inputs: Vec<Vec<f32>>,
+        kernel: Vec<f32>,
+        bias: Vec<f32>,
+    }
+
+    pub fn interleaved_problem() -> BoxedStrategy<InterleavedProblem> {
+        (1usize..40)
+            .prop_flat_map(|c| {
+                (Just(c), proptest::collection::vec(values(c), 9), values(9 * c), values(c))
+            })
+            .prop_map(|(channels, inputs, kernel, bias)| InterleavedProblem {
+                channels,
+                inputs,
+                kernel,
+                bias,
+            })
+            .boxed()
+    }
+
+    pub fn check_interleaved(
+        ker: &dyn DepthWise3x3,
+        pb: &InterleavedProblem,
+    ) -> proptest::test_runner::TestCaseResult {
+        let expected: Vec<f32> = (0..pb.channels)
+            .map(|c| {
+                pb.bias[c] + (0..9).map(|t| pb.kernel[c * 9 + t] * pb.inputs[t][c]).sum::<f32>()
+            })
+            .collect();
+        let packed = pack_interleaved_kernel(&pb.kernel, pb.channels);
+        let mut inputs = [std::ptr::null(); 9];
+        for t in 0..9 {
+            inputs[t] = pb.inputs[t].as_ptr();
+        }
+        let mut found = vec![0f32; pb.channels];
+        unsafe {
+            ker.interleaved_pixel(
+                &inputs,
+                &packed,
+                pb.bias.as_ptr(),
+                found.as_mut_ptr(),
+                pb.channels,
+            );
+        }
+        crate::check_close(&found, &expected)
+    }
+}
@@ -9,6 +9,12 @@ pub trait Lut: fmt::Debug + dyn_clone::DynClone + Send + Sync {
 
 dyn_clone::clone_trait_object!(Lut);
 
+impl PartialEq for dyn Lut {
+    fn eq(&self, other: &dyn Lut) -> bool {
+        self.table() == other.table()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LutImpl<K>
 where
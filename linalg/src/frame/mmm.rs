@@ -1,3 +1,4 @@
+pub mod autotune;
 pub mod cost_model;
 #[macro_use]
 pub(crate) mod fuse;
@@ -12,6 +13,7 @@ mod storage;
 #[macro_use]
 pub mod tests;
 
+pub use autotune::*;
 pub use cost_model::*;
 pub use fuse::*;
 pub use input_store::*;
@@ -0,0 +1,213 @@
+//! Plan-time kernel selection by benchmarking.
+//!
+//! Candidates are timed on the actual product shape, and the winner is
+//! remembered for the whole shape class: architecture, datum type, and m, k
+//! and n rounded up to the next power of two. With a cache file, choices
+//! survive across processes.
+//!
+//! The global autotuner is opt-in: set `TRACT_AUTOTUNE=1`, or point
+//! `TRACT_AUTOTUNE_CACHE` to a file to also persist the choices.
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tract_data::anyhow::Context;
+use tract_data::internal::*;
+
+use super::{FusedSpec, MatMatMul};
+
+const BENCH_TIME_TARGET: Duration = Duration::from_millis(5);
+const BENCH_ROUNDS: usize = 3;
+
+lazy_static::lazy_static! {
+    static ref AUTOTUNER: Option<Autotuner> = {
+        if let Some(path) = std::env::var_os("TRACT_AUTOTUNE_CACHE") {
+            match Autotuner::with_cache(&path) {
+                Ok(tuner) => Some(tuner),
+                Err(e) => {
+                    log::warn!("Ignoring autotuner cache {:?}: {:?}", path, e);
+                    Some(Autotuner::new())
+                }
+            }
+        } else if std::env::var("TRACT_AUTOTUNE").map(|v| v != "0").unwrap_or(false) {
+            Some(Autotuner::new())
+        } else {
+            None
+        }
+    };
+}
+
+/// The process-wide autotuner, if enabled from the environment.
+pub fn autotuner() -> Option<&'static Autotuner> {
+    AUTOTUNER.as_ref()
+}
+
+#[derive(Debug, Default)]
+pub struct Autotuner {
+    cache: Mutex<HashMap<String, String>>,
+    path: Option<PathBuf>,
+}
+
+impl Autotuner {
+    pub fn new() -> Autotuner {
+        Autotuner::default()
+    }
+
+    /// An autotuner persisting its choices to `path`, loading the existing
+    /// ones if the file is already there.
+    pub fn with_cache(path: impl AsRef<Path>) -> TractResult<Autotuner> {
+        let path = path.as_ref().to_path_buf();
+        let mut cache = HashMap::new();
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Reading autotuner cache {:?}", path))?;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                let (key, kernel) = line
+                    .split_once('\t')
+                    .with_context(|| format!("Invalid autotuner cache line: {:?}", line))?;
+                cache.insert(key.to_string(), kernel.to_string());
+            }
+        }
+        Ok(Autotuner { cache: Mutex::new(cache), path: Some(path) })
+    }
+
+    pub fn key(dt: DatumType, m: usize, k: usize, n: usize) -> String {
+        format!(
+            "{}/{:?}/{}x{}x{}",
+            std::env::consts::ARCH,
+            dt,
+            m.next_power_of_two(),
+            k.next_power_of_two(),
+            n.next_power_of_two()
+        )
+    }
+
+    /// Kernel name chosen for a shape class, if it has been tuned.
+    pub fn cached(&self, dt: DatumType, m: usize, k: usize, n: usize) -> Option<String> {
+        self.cache.lock().unwrap().get(&Self::key(dt, m, k, n)).cloned()
+    }
+
+    /// Picks the fastest of `impls` for a m×k×n product, benchmarking them
+    /// unless the shape class is already in the cache.
+    pub fn pick(
+        &self,
+        impls: &[Box<dyn MatMatMul>],
+        m: usize,
+        k: usize,
+        n: usize,
+    ) -> TractResult<Box<dyn MatMatMul>> {
+        ensure!(!impls.is_empty(), "No kernel to pick from");
+        if impls.len() == 1 {
+            return Ok(impls[0].clone());
+        }
+        let dt = impls[0].internal_type();
+        let key = Self::key(dt, m, k, n);
+        let cached = self.cache.lock().unwrap().get(&key).cloned();
+        if let Some(mm) = cached.and_then(|c| impls.iter().find(|mm| mm.kernel_name() == c)) {
+            return Ok(mm.clone());
+        }
+        let mut best: Option<(f64, &Box<dyn MatMatMul>)> = None;
+        for mm in impls {
+            let time = unsafe { measure(&**mm, m, k, n)? };
+            log::debug!("autotuner: {} {} in {:.3e}s", key, mm.kernel_name(), time);
+            if best.map(|(t, _)| time < t).unwrap_or(true) {
+                best = Some((time, mm));
+            }
+        }
+        let best = best.unwrap().1;
+        log::info!("autotuner: {} picks {}", key, best.kernel_name());
+        let mut cache = self.cache.lock().unwrap();
+        cache.insert(key, best.kernel_name().to_string());
+        if let Some(path) = &self.path {
+            if let Err(e) = Self::save(path, &cache) {
+                log::warn!("Could not save autotuner cache {:?}: {:?}", path, e);
+            }
+        }
+        Ok(best.clone())
+    }
+
+    fn save(path: &Path, cache: &HashMap<String, String>) -> TractResult<()> {
+        let mut entries: Vec<_> = cache.iter().collect();
+        entries.sort();
+        let tmp = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        for (key, kernel) in entries {
+            writeln!(file, "{}\t{}", key, kernel)?;
+        }
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Best time per product over a few rounds, in seconds.
+unsafe fn measure(mm: &dyn MatMatMul, m: usize, k: usize, n: usize) -> TractResult<f64> {
+    let dt = mm.internal_type();
+    let a = Tensor::zero_aligned_dt(dt, &[mm.a_pack().len(k, m)], mm.a_pack().alignment())?;
+    let b = Tensor::zero_aligned_dt(dt, &[mm.b_pack().len(k, n)], mm.b_pack().alignment())?;
+    let c = Tensor::zero_dt(dt, &[m, n])?;
+    let spec = [
+        FusedSpec::AddMatMul {
+            a: mm.a_packed(dt.size_of(), k).wrap(&a.view()),
+            b: mm.b_packed(dt.size_of(), k).wrap(&b.view())?,
+            k,
+        },
+        FusedSpec::Store(mm.c_view(0, 1).wrap(&c.view())),
+    ];
+    let mut scratch = mm.allocate_scratch_space();
+    let start = Instant::now();
+    mm.run_with_scratch_space(m, n, &mut *scratch, &spec)?;
+    let once = start.elapsed().max(Duration::from_nanos(1));
+    let iters = (BENCH_TIME_TARGET.as_secs_f64() / once.as_secs_f64()).max(1.0) as usize;
+    let mut best = f64::MAX;
+    for _ in 0..BENCH_ROUNDS {
+        let start = Instant::now();
+        for _ in 0..iters {
+            mm.run_with_scratch_space(m, n, &mut *scratch, &spec)?;
+        }
+        best = best.min(start.elapsed().as_secs_f64() / iters as f64);
+    }
+    Ok(best)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::mmm::MatMatMulKer;
+    use crate::generic::{GenericMmm4x1, GenericMmm4x4};
+
+    fn impls() -> Vec<Box<dyn MatMatMul>> {
+        vec![GenericMmm4x4::<f32, f32, f32>::mmm(), GenericMmm4x1::<f32, f32, f32>::mmm()]
+    }
+
+    #[test]
+    fn shape_class() {
+        assert_eq!(
+            Autotuner::key(f32::datum_type(), 30, 64, 65),
+            Autotuner::key(f32::datum_type(), 32, 33, 128)
+        );
+        assert_ne!(
+            Autotuner::key(f32::datum_type(), 30, 64, 65),
+            Autotuner::key(i8::datum_type(), 30, 64, 65)
+        );
+    }
+
+    #[test]
+    fn persistent_cache() -> TractResult<()> {
+        let path = std::env::temp_dir().join(format!("tract-autotune-{}.txt", std::process::id()));
+        let tuner = Autotuner::with_cache(&path)?;
+        let picked = tuner.pick(&impls(), 16, 16, 16)?;
+        assert_eq!(
+            tuner.cached(f32::datum_type(), 16, 16, 16).as_deref(),
+            Some(picked.kernel_name())
+        );
+        let reloaded = Autotuner::with_cache(&path)?;
+        assert_eq!(
+            reloaded.cached(f32::datum_type(), 9, 10, 12).as_deref(),
+            Some(picked.kernel_name())
+        );
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -29,6 +29,7 @@ pub enum InputStoreSpec {
     Prepacked(PackedStoreSpec),
     LatePacking { packer: Packer, k_axis: usize, mn_axis: usize },
     VirtualPacking { packer: Packer, func: Box<dyn VirtualInputSpec>, k: usize },
+    Streaming { packer: Packer, k_axis: usize, mn_axis: usize },
 }
 
 #[derive(PartialEq, Clone, Copy, Debug, Hash)]
@@ -55,6 +56,29 @@ impl InputStoreSpec {
                 k_stride: tensor.strides()[*k_axis],
                 mn_stride: tensor.strides()[*mn_axis],
             }),
+            S::Streaming { packer, k_axis, mn_axis } => {
+                let ptr = tensor.as_ptr_unchecked::<u8>();
+                let (k, mn) = (tensor.shape()[*k_axis], tensor.shape()[*mn_axis]);
+                let (k_stride, mn_stride) = (tensor.strides()[*k_axis], tensor.strides()[*mn_axis]);
+                let r = packer.r as isize;
+                // B is read in place if it is already laid out as packed panels:
+                // without padding, either columns with a contiguous k, or the
+                // whole of B as k rows of exactly r items. Packed on the fly
+                // otherwise.
+                let in_place = packer.single_panel_len(k) == k * packer.r
+                    && ptr.align_offset(packer.alignment()) == 0
+                    && ((r == 1 && k_stride == 1)
+                        || (mn == packer.r && mn_stride == 1 && k_stride == r));
+                if in_place {
+                    Ok(Packed(PackedStore {
+                        ptr: ptr as _,
+                        panel_bytes: mn_stride * r * tensor.datum_type().size_of() as isize,
+                    }))
+                } else {
+                    S::LatePacking { packer: packer.clone(), k_axis: *k_axis, mn_axis: *mn_axis }
+                        .wrap(tensor)
+                }
+            }
             S::VirtualPacking { packer, func, k } => Ok(InputStore::VirtualPacking {
                 packer: packer.clone(),
                 input: func.wrap(tensor),
@@ -71,6 +95,7 @@ impl fmt::Display for InputStoreSpec {
             InputStoreSpec::Prepacked { .. } => write!(fmt, "Packed"),
             InputStoreSpec::LatePacking { .. } => write!(fmt, "LatePacking"),
             InputStoreSpec::VirtualPacking { .. } => write!(fmt, "VirtualPacking"),
+            InputStoreSpec::Streaming { .. } => write!(fmt, "Streaming"),
         }
     }
 }
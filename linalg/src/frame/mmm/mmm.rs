@@ -1,12 +1,16 @@
 use super::ScratchSpaceFusedNonLinear;
 use super::*;
 use crate::frame::Packer;
+use crate::multithread::{current_tract_executor, Executor};
 use crate::LADatum;
 use anyhow::Context;
+use rayon::prelude::*;
+use rayon::ThreadPool;
 use std::fmt;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use tract_data::anyhow;
+use tract_data::internal::num_integer::Integer;
 use tract_data::internal::*;
 
 pub trait MatMatMul:
@@ -29,6 +33,7 @@ pub trait MatMatMul:
     }
     unsafe fn b_late_packing_with_axes(&self, k_axis: usize, n_axis: usize) -> InputStoreSpec;
     unsafe fn b_virtual_input(&self, func: Box<dyn VirtualInputSpec>, k: usize) -> InputStoreSpec;
+    unsafe fn b_streaming_with_axes(&self, k_axis: usize, n_axis: usize) -> InputStoreSpec;
 
     unsafe fn c_view(&self, m_axis: usize, n_axis: usize) -> OutputStoreSpec;
     unsafe fn c_from_data_and_strides(
@@ -124,6 +129,72 @@ where
     pub fn new() -> MatMatMulImpl<K, TI> {
         MatMatMulImpl { phantom: PhantomData }
     }
+
+    #[inline(always)]
+    unsafe fn run_tile(
+        scratch: &mut ScratchSpaceFusedNonLinear<TI>,
+        non_linear: &[FusedSpec],
+        m: usize,
+        n: usize,
+        ia: usize,
+        ib: usize,
+    ) {
+        let mr = K::mr();
+        let nr = K::nr();
+        if (ia + 1) * mr <= m && (ib + 1) * nr <= n {
+            scratch.for_valid_tile::<K>(non_linear, ia, ib);
+            let err = K::kernel(scratch.uspecs());
+            debug_assert_eq!(err, 0, "Kernel return error {}", err);
+        } else {
+            scratch.for_border_tile::<K>(non_linear, ia, ib);
+            let err = K::kernel(scratch.uspecs());
+            debug_assert_eq!(err, 0, "Kernel return error {}", err);
+            scratch.postprocess_tile::<K>(
+                non_linear,
+                ia,
+                ib,
+                mr.min(m - ia * mr),
+                nr.min(n - ib * nr),
+            );
+        }
+    }
+
+    /// Splits the product over row panels (or column panels, when they are
+    /// more numerous or B is packed on the fly) and runs them on `pool`,
+    /// with a scratch space per worker.
+    unsafe fn run_multithread(
+        pool: &ThreadPool,
+        m: usize,
+        n: usize,
+        non_linear: &[FusedSpec],
+    ) -> anyhow::Result<()> {
+        // tiles write disjoint parts of the outputs, and only read the rest
+        struct Specs<'s, 't>(&'s [FusedSpec<'t>]);
+        unsafe impl Send for Specs<'_, '_> {}
+        unsafe impl Sync for Specs<'_, '_> {}
+        let specs = Specs(non_linear);
+        let row_panels = Integer::div_ceil(&m, &K::mr());
+        let col_panels = Integer::div_ceil(&n, &K::nr());
+        let col_outer = non_linear.iter().any(|f| f.prefer_col_outer()) || col_panels > row_panels;
+        let (outer, inner) =
+            if col_outer { (col_panels, row_panels) } else { (row_panels, col_panels) };
+        pool.install(|| {
+            (0..outer).into_par_iter().for_each_init(
+                || {
+                    let mut scratch = ScratchSpaceFusedNonLinear::<TI>::default();
+                    scratch.prepare::<K>(specs.0);
+                    scratch
+                },
+                |scratch, o| {
+                    for i in 0..inner {
+                        let (ia, ib) = if col_outer { (i, o) } else { (o, i) };
+                        Self::run_tile(scratch, specs.0, m, n, ia, ib);
+                    }
+                },
+            )
+        });
+        Ok(())
+    }
 }
 
 impl<K, TI> MatMatMul for MatMatMulImpl<K, TI>
@@ -172,6 +243,10 @@ where
         InputStoreSpec::VirtualPacking { packer: self.b_pack(), func, k }
     }
 
+    unsafe fn b_streaming_with_axes(&self, k_axis: usize, n_axis: usize) -> InputStoreSpec {
+        InputStoreSpec::Streaming { packer: self.b_pack(), k_axis, mn_axis: n_axis }
+    }
+
     unsafe fn c_view(&self, m_axis: usize, n_axis: usize) -> OutputStoreSpec {
         OutputStoreSpec::View { m_axis, n_axis, mr: K::mr(), nr: K::nr() }
     }
@@ -278,6 +353,11 @@ where
         if n == 1 && K::nr() == 1 {
             return self.run_with_scratch_space_vec(m, scratch, &non_linear);
         }
+        if let Executor::MultiThread(pool) = current_tract_executor() {
+            if m > mr || n > nr {
+                return Self::run_multithread(&pool, m, n, non_linear);
+            }
+        }
         if non_linear.iter().any(|f| f.prefer_col_outer()) {
             return self.run_with_scratch_space_col_outer(m, n, scratch, &non_linear);
         }
@@ -29,6 +29,13 @@ macro_rules! mmm_frame_tests {
                     }
                 }
 
+                #[test]
+                fn mat_mul_streaming_prop((m, k, n, ref a, ref b) in strat_mat_mat_mul::<$ta, $tb>()) {
+                    if $cond {
+                        test_mat_mat_mul_streaming::<$ker, $ta, $tb, $tc, $ti>(m, k, n, &a, &b)?
+                    }
+                }
+
                 #[test]
                 fn mat_vec_prepacked_prop((m, k, ref a, ref b) in strat_mat_vec_mul::<$ta, $tb>()) {
                     if $cond {
@@ -78,6 +85,18 @@ macro_rules! mmm_frame_tests {
                 }
             }
 
+            #[test]
+            fn mat_mul_streaming_single_panel() {
+                if $cond {
+                    let n = <$ker as $crate::frame::mmm::MatMatMulKer<$ti>>::nr();
+                    let a = tensor1(&(0..21).map(|i| i % 7 - 3).collect::<Vec<i32>>())
+                        .into_shape(&[3, 7]).unwrap().cast_to::<$ta>().unwrap().into_owned();
+                    let b = tensor1(&(0..7 * n as i32).map(|i| i % 5 - 2).collect::<Vec<i32>>())
+                        .into_shape(&[7, n]).unwrap().cast_to::<$tb>().unwrap().into_owned();
+                    test_mat_mat_mul_streaming::<$ker, $ta, $tb, $tc, $ti>(3, 7, n, &a, &b).unwrap()
+                }
+            }
+
             #[test]
             fn mat_vec_1() {
                 if $cond {
@@ -303,6 +322,55 @@ where
     }
 }
 
+pub fn test_mat_mat_mul_streaming<K: MatMatMulKer<TI> + 'static, TA, TB, TC, TI>(
+    m: usize,
+    k: usize,
+    n: usize,
+    a: &Tensor,
+    b: &Tensor,
+) -> Result<(), proptest::test_runner::TestCaseError>
+where
+    TA: LADatum + AsPrimitive<TI> + 'static,
+    TB: LADatum + AsPrimitive<TI> + 'static,
+    TC: LADatum + AsPrimitive<TI> + 'static,
+    TI: LADatum + AsPrimitive<TC> + 'static + Neg<Output = TI>,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    assert_eq!(a.datum_type(), TA::datum_type());
+    let op = MatMatMulImpl::<K, TI>::new();
+    // B as is, and stored as n rows of k so the k axis is contiguous
+    let b_t = b.clone().permute_axes(&[1, 0]).unwrap();
+    unsafe {
+        let mut packed_a =
+            Tensor::uninitialized_aligned::<TA>(&[op.a_pack().len(k, m)], op.a_pack().alignment())
+                .unwrap();
+        op.a_pack().pack(packed_a.view_mut(), a.view(), 1, 0);
+
+        for (b_view, k_axis, n_axis) in &[(b.view(), 0, 1), (b_t.view(), 1, 0)] {
+            fused_ops::<K, TA, TB, TC, TI, _>(
+                m,
+                n,
+                &[FusedSpec::AddMatMul {
+                    a: op.a_packed(TA::datum_type().size_of(), k).wrap(&packed_a.view()),
+                    b: op.b_streaming_with_axes(*k_axis, *n_axis).wrap(b_view).unwrap(),
+                    k,
+                }],
+                |r, c| {
+                    let mut v: TI = TI::zero();
+                    for i in 0..k {
+                        let a: TI = a.as_slice::<TA>().unwrap()[i + k * r].as_();
+                        let b: TI = b.as_slice::<TB>().unwrap()[c + i * n].as_();
+                        v = v + a * b;
+                    }
+                    v.as_()
+                },
+            )?
+        }
+        Ok(())
+    }
+}
+
 pub fn test_mat_vec_mul_prep<K: MatMatMulKer<TI> + 'static, TA, TB, TC, TI>(
     m: usize,
     k: usize,
@@ -0,0 +1,114 @@
+//! Softmax over a contiguous buffer: max, exp(x - max) and sum, then scale
+//! by the inverse of the sum, in three passes over the data.
+use std::fmt::Debug;
+use tract_data::anyhow;
+use tract_data::prelude::f16;
+
+pub trait Softmax<T>: Send + Sync + Debug + dyn_clone::DynClone
+where
+    T: Copy + Debug + PartialEq + Send + Sync,
+{
+    fn name(&self) -> &'static str;
+    fn run(&self, vec: &mut [T]) -> anyhow::Result<()>;
+}
+
+dyn_clone::clone_trait_object!(<T> Softmax<T> where T: Copy);
+
+std::thread_local! {
+    static F32_BUFFER: std::cell::RefCell<Vec<f32>> = const { std::cell::RefCell::new(vec![]) };
+}
+
+/// Runs f16 softmax through a f32 implementation, as the reductions need
+/// more precision than f16 offers anyway.
+#[derive(Debug, Clone, new)]
+pub struct F16ViaF32(Box<dyn Softmax<f32>>);
+
+impl Softmax<f16> for F16ViaF32 {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn run(&self, vec: &mut [f16]) -> anyhow::Result<()> {
+        F32_BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            buffer.clear();
+            buffer.extend(vec.iter().map(|x| x.0.to_f32()));
+            self.0.run(&mut buffer)?;
+            vec.iter_mut().zip(buffer.iter()).for_each(|(x, y)| *x = f16::from(*y));
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseResult;
+
+    #[macro_export]
+    macro_rules! softmax_frame_tests {
+        ($cond:expr, $ker:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn softmax_prop(xs in proptest::collection::vec(-50f32..50f32, 0..100)) {
+                    if $cond {
+                        crate::frame::softmax::test::check(&$ker, &xs)?
+                    }
+                }
+
+                #[test]
+                fn softmax_f16_prop(xs in proptest::collection::vec(-10f32..10f32, 0..100)) {
+                    if $cond {
+                        crate::frame::softmax::test::check_f16(&$ker, &xs)?
+                    }
+                }
+            }
+
+            #[test]
+            fn softmax_large_values() {
+                if $cond {
+                    crate::frame::softmax::test::check(&$ker, &[1000.0, 1000.0, 999.0, -1000.0])
+                        .unwrap()
+                }
+            }
+
+            #[test]
+            fn softmax_single() {
+                if $cond {
+                    crate::frame::softmax::test::check(&$ker, &[-12.0]).unwrap()
+                }
+            }
+        };
+    }
+
+    fn reference(values: &[f32]) -> Vec<f32> {
+        let max = values.iter().fold(f32::MIN, |a, b| a.max(*b));
+        let exps: Vec<f64> = values.iter().map(|x| ((x - max) as f64).exp()).collect();
+        let sum: f64 = exps.iter().sum();
+        exps.iter().map(|x| (x / sum) as f32).collect()
+    }
+
+    pub fn check<K: Softmax<f32>>(ker: &K, values: &[f32]) -> TestCaseResult {
+        let mut found = values.to_vec();
+        ker.run(&mut found).unwrap();
+        let expected = reference(values);
+        for (f, e) in found.iter().zip(expected.iter()) {
+            prop_assert!((f - e).abs() <= 1e-5 + e.abs() * 1e-4, "{:?} {:?}", found, expected);
+        }
+        Ok(())
+    }
+
+    pub fn check_f16<K: Softmax<f32> + Clone + 'static>(ker: &K, values: &[f32]) -> TestCaseResult {
+        let ker = F16ViaF32::new(Box::new(ker.clone()));
+        let values: Vec<f16> = values.iter().map(|x| f16::from(*x)).collect();
+        let mut found = values.clone();
+        ker.run(&mut found).unwrap();
+        let expected = reference(&values.iter().map(|x| x.0.to_f32()).collect::<Vec<_>>());
+        for (f, e) in found.iter().zip(expected.iter()) {
+            prop_assert!((f.0.to_f32() - e).abs() <= 1e-3 + e.abs() * 1e-2);
+        }
+        Ok(())
+    }
+}
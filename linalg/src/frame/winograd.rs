@@ -0,0 +1,168 @@
+//! Winograd minimal filtering transforms F(m x m, r x r).
+//!
+//! A convolution of a (m+r-1)x(m+r-1) input tile by a rxr kernel producing a
+//! m x m output tile is computed as At.[(G.g.Gt) * (Bt.d.B)].A, where `*` is
+//! the element-wise product. The kernel transform is done once, and the
+//! element-wise products over all channels are actually performed as
+//! (m+r-1)^2 independent matrix products by the caller.
+use std::fmt::Debug;
+
+pub trait Winograd: Send + Sync + Debug + dyn_clone::DynClone {
+    fn name(&self) -> &'static str;
+
+    /// Output tile size (m).
+    fn output_tile(&self) -> usize;
+
+    /// Kernel size (r).
+    fn kernel_size(&self) -> usize;
+
+    /// Input tile size (m + r - 1), also the size of the transformed tiles.
+    fn input_tile(&self) -> usize {
+        self.output_tile() + self.kernel_size() - 1
+    }
+
+    /// Computes G.g.Gt for a row-major rxr kernel.
+    fn transform_kernel(&self, kernel: &[f32], transformed: &mut [f32]);
+
+    /// Computes Bt.d.B for a row-major input tile.
+    fn transform_input(&self, tile: &[f32], transformed: &mut [f32]);
+
+    /// Computes At.M.A, returning a row-major m x m output tile.
+    fn transform_output(&self, tile: &[f32], output: &mut [f32]);
+}
+
+dyn_clone::clone_trait_object!(Winograd);
+
+impl std::hash::Hash for Box<dyn Winograd> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name().hash(state)
+    }
+}
+
+/// Winograd transforms driven by their three matrices.
+#[derive(Debug, Clone)]
+pub struct WinogradImpl {
+    pub name: &'static str,
+    pub m: usize,
+    pub r: usize,
+    /// (m+r-1) x r kernel transform matrix
+    pub g: &'static [f32],
+    /// (m+r-1) x (m+r-1) input transform matrix
+    pub bt: &'static [f32],
+    /// m x (m+r-1) output transform matrix
+    pub at: &'static [f32],
+}
+
+impl WinogradImpl {
+    /// Computes L.X.Lt, for a rows x inner L, and a inner x inner X.
+    #[inline]
+    fn sandwich(l: &[f32], rows: usize, inner: usize, x: &[f32], out: &mut [f32]) {
+        debug_assert_eq!(l.len(), rows * inner);
+        debug_assert_eq!(x.len(), inner * inner);
+        debug_assert_eq!(out.len(), rows * rows);
+        let mut tmp = [0f32; 64];
+        for i in 0..rows {
+            for j in 0..inner {
+                let mut acc = 0f32;
+                for k in 0..inner {
+                    acc += l[i * inner + k] * x[k * inner + j];
+                }
+                tmp[i * inner + j] = acc;
+            }
+        }
+        for i in 0..rows {
+            for j in 0..rows {
+                let mut acc = 0f32;
+                for k in 0..inner {
+                    acc += tmp[i * inner + k] * l[j * inner + k];
+                }
+                out[i * rows + j] = acc;
+            }
+        }
+    }
+}
+
+impl Winograd for WinogradImpl {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn output_tile(&self) -> usize {
+        self.m
+    }
+
+    fn kernel_size(&self) -> usize {
+        self.r
+    }
+
+    fn transform_kernel(&self, kernel: &[f32], transformed: &mut [f32]) {
+        Self::sandwich(self.g, self.input_tile(), self.r, kernel, transformed)
+    }
+
+    fn transform_input(&self, tile: &[f32], transformed: &mut [f32]) {
+        Self::sandwich(self.bt, self.input_tile(), self.input_tile(), tile, transformed)
+    }
+
+    fn transform_output(&self, tile: &[f32], output: &mut [f32]) {
+        Self::sandwich(self.at, self.m, self.input_tile(), tile, output)
+    }
+}
+
+#[cfg(test)]
+#[macro_use]
+pub mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[macro_export]
+    macro_rules! winograd_frame_tests {
+        ($cond:expr, $wino:expr) => {
+            proptest::proptest! {
+                #[test]
+                fn winograd_prop((input, kernel) in crate::frame::winograd::test::problem(&$wino)) {
+                    if $cond {
+                        crate::frame::winograd::test::check(&$wino, &input, &kernel)?
+                    }
+                }
+            }
+        };
+    }
+
+    pub fn problem(wino: &dyn Winograd) -> BoxedStrategy<(Vec<f32>, Vec<f32>)> {
+        let alpha = wino.input_tile();
+        let r = wino.kernel_size();
+        let value = (-100i32..100).prop_map(|i| i as f32 / 100.0);
+        (
+            proptest::collection::vec(value.clone(), alpha * alpha),
+            proptest::collection::vec(value, r * r),
+        )
+            .boxed()
+    }
+
+    pub fn check(
+        wino: &dyn Winograd,
+        input: &[f32],
+        kernel: &[f32],
+    ) -> proptest::test_runner::TestCaseResult {
+        let (m, r, alpha) = (wino.output_tile(), wino.kernel_size(), wino.input_tile());
+        let mut expected = vec![0f32; m * m];
+        for y in 0..m {
+            for x in 0..m {
+                for ky in 0..r {
+                    for kx in 0..r {
+                        expected[y * m + x] +=
+                            input[(y + ky) * alpha + x + kx] * kernel[ky * r + kx];
+                    }
+                }
+            }
+        }
+        let mut u = vec![0f32; alpha * alpha];
+        let mut v = vec![0f32; alpha * alpha];
+        wino.transform_kernel(kernel, &mut u);
+        wino.transform_input(input, &mut v);
+        let product: Vec<f32> = u.iter().zip(v.iter()).map(|(u, v)| u * v).collect();
+        let mut found = vec![0f32; m * m];
+        wino.transform_output(&product, &mut found);
+        crate::check_close(&found, &expected)
+    }
+}
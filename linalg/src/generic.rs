@@ -1,8 +1,14 @@
+pub mod arg_min_max;
+pub mod attention;
+pub mod depthwise;
 pub mod lut;
 pub mod mmm;
 pub mod rounding;
 pub mod sigmoid;
+pub mod softmax;
 pub mod tanh;
+pub mod transcendental;
+pub mod winograd;
 
 pub use self::lut::GenericLut8;
 pub use self::mmm::GenericMmm4x1;
@@ -0,0 +1,47 @@
+use crate::frame::arg_min_max::ArgMinMax;
+use num_traits::Bounded;
+use std::fmt::Debug;
+
+/// Sequential fold over `vec[range]`, from `acc`.
+#[inline]
+pub fn fold<T: Copy + PartialOrd>(
+    vec: &[T],
+    range: std::ops::Range<usize>,
+    max: bool,
+    last: bool,
+    mut acc: (usize, T),
+) -> (usize, T) {
+    for ix in range {
+        let x = vec[ix];
+        let strictly = if max { x > acc.1 } else { x < acc.1 };
+        if strictly || (last && x == acc.1) {
+            acc = (ix, x);
+        }
+    }
+    acc
+}
+
+#[derive(Clone, Debug)]
+pub struct GenericArgMinMax;
+
+impl<T> ArgMinMax<T> for GenericArgMinMax
+where
+    T: Copy + Debug + PartialEq + PartialOrd + Bounded + Send + Sync,
+{
+    fn name(&self) -> &'static str {
+        "generic"
+    }
+
+    fn argmax(&self, vec: &[T], last: bool) -> usize {
+        fold(vec, 0..vec.len(), true, last, (0, T::min_value())).0
+    }
+
+    fn argmin(&self, vec: &[T], last: bool) -> usize {
+        fold(vec, 0..vec.len(), false, last, (0, T::max_value())).0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    arg_min_max_frame_tests!(true, crate::generic::arg_min_max::GenericArgMinMax);
+}
@@ -0,0 +1,81 @@
+use crate::frame::attention::{Attention, AttentionProblem};
+use crate::generic::transcendental::sexp;
+use tract_data::anyhow;
+
+/// Query rows processed together: each block of keys and values is loaded
+/// once for all of them.
+const Q_BLOCK: usize = 8;
+/// Keys and values consumed at a time.
+const KV_BLOCK: usize = 64;
+
+#[derive(Clone, Debug)]
+pub struct GenericFlashAttention;
+
+impl Attention for GenericFlashAttention {
+    fn name(&self) -> &'static str {
+        "generic"
+    }
+
+    fn run(&self, pb: &AttentionProblem, out: &mut [f32]) -> anyhow::Result<()> {
+        pb.check()?;
+        anyhow::ensure!(out.len() == pb.q_len * pb.value_depth, "Wrong output size");
+        let (d, dv) = (pb.depth, pb.value_depth);
+        let mut scores = [0f32; Q_BLOCK * KV_BLOCK];
+        let mut max = [f32::NEG_INFINITY; Q_BLOCK];
+        let mut sum = [0f32; Q_BLOCK];
+        for q0 in (0..pb.q_len).step_by(Q_BLOCK) {
+            let rows = Q_BLOCK.min(pb.q_len - q0);
+            let out = &mut out[q0 * dv..][..rows * dv];
+            out.iter_mut().for_each(|o| *o = 0.0);
+            max.iter_mut().for_each(|m| *m = f32::NEG_INFINITY);
+            sum.iter_mut().for_each(|s| *s = 0.0);
+            for k0 in (0..pb.kv_len).step_by(KV_BLOCK) {
+                let cols = KV_BLOCK.min(pb.kv_len - k0);
+                for r in 0..rows {
+                    let q = &pb.q[(q0 + r) * d..][..d];
+                    let scores = &mut scores[r * KV_BLOCK..][..cols];
+                    for (c, s) in scores.iter_mut().enumerate() {
+                        let k = &pb.k[(k0 + c) * d..][..d];
+                        *s = q.iter().zip(k).map(|(a, b)| a * b).sum::<f32>() * pb.scale;
+                    }
+                    if let Some(mask) = pb.mask {
+                        let mask = &mask[(q0 + r) * pb.kv_len + k0..][..cols];
+                        scores.iter_mut().zip(mask).for_each(|(s, m)| *s += m);
+                    }
+                    let block_max = scores.iter().fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+                    if block_max == f32::NEG_INFINITY {
+                        scores.iter_mut().for_each(|s| *s = 0.0);
+                        continue;
+                    }
+                    let new_max = max[r].max(block_max);
+                    let correction = sexp(max[r] - new_max);
+                    max[r] = new_max;
+                    let mut block_sum = 0.0;
+                    for s in scores.iter_mut() {
+                        *s = sexp(*s - new_max);
+                        block_sum += *s;
+                    }
+                    sum[r] = sum[r] * correction + block_sum;
+                    out[r * dv..][..dv].iter_mut().for_each(|o| *o *= correction);
+                }
+                for c in 0..cols {
+                    let v = &pb.v[(k0 + c) * dv..][..dv];
+                    for r in 0..rows {
+                        let p = scores[r * KV_BLOCK + c];
+                        out[r * dv..][..dv].iter_mut().zip(v).for_each(|(o, v)| *o += p * v);
+                    }
+                }
+            }
+            for r in 0..rows {
+                let inv = 1.0 / sum[r];
+                out[r * dv..][..dv].iter_mut().for_each(|o| *o *= inv);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    attention_frame_tests!(true, crate::generic::attention::GenericFlashAttention);
+}
@@ -0,0 +1,77 @@
+use crate::frame::depthwise::{DepthWise3x3, INTERLEAVED_BLOCK};
+
+/// Planar row outputs in `range`, see `DepthWise3x3::planar_row`.
+pub unsafe fn planar_row(
+    stride: usize,
+    rows: [*const f32; 3],
+    kernel: &[f32; 9],
+    bias: f32,
+    out: *mut f32,
+    range: std::ops::Range<usize>,
+) {
+    for x in range {
+        let mut sum = bias;
+        for ky in 0..3 {
+            let row = rows[ky].add(stride * x);
+            sum += kernel[3 * ky] * *row
+                + kernel[3 * ky + 1] * *row.add(1)
+                + kernel[3 * ky + 2] * *row.add(2);
+        }
+        *out.add(x) = sum;
+    }
+}
+
+/// Interleaved pixel outputs in `range`, see `DepthWise3x3::interleaved_pixel`.
+pub unsafe fn interleaved_pixel(
+    inputs: &[*const f32; 9],
+    kernel: &[f32],
+    bias: *const f32,
+    out: *mut f32,
+    range: std::ops::Range<usize>,
+) {
+    for c in range {
+        let (block, lane) = (c / INTERLEAVED_BLOCK, c % INTERLEAVED_BLOCK);
+        let mut sum = *bias.add(c);
+        for t in 0..9 {
+            sum += kernel[(block * 9 + t) * INTERLEAVED_BLOCK + lane] * *inputs[t].add(c);
+        }
+        *out.add(c) = sum;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenericDepthWise3x3;
+
+impl DepthWise3x3 for GenericDepthWise3x3 {
+    fn name(&self) -> &'static str {
+        "generic"
+    }
+
+    unsafe fn planar_row(
+        &self,
+        stride: usize,
+        rows: [*const f32; 3],
+        kernel: &[f32; 9],
+        bias: f32,
+        out: *mut f32,
+        width: usize,
+    ) {
+        planar_row(stride, rows, kernel, bias, out, 0..width)
+    }
+
+    unsafe fn interleaved_pixel(
+        &self,
+        inputs: &[*const f32; 9],
+        kernel: &[f32],
+        bias: *const f32,
+        out: *mut f32,
+        channels: usize,
+    ) {
+        interleaved_pixel(inputs, kernel, bias, out, 0..channels)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    depthwise_frame_tests!(true, crate::generic::depthwise::GenericDepthWise3x3);
+}
@@ -0,0 +1,45 @@
+use crate::frame::softmax::Softmax;
+use crate::generic::transcendental::sexp;
+use tract_data::anyhow;
+
+#[derive(Clone, Debug)]
+pub struct GenericSoftmaxF32;
+
+impl Softmax<f32> for GenericSoftmaxF32 {
+    fn name(&self) -> &'static str {
+        "generic"
+    }
+
+    fn run(&self, vec: &mut [f32]) -> anyhow::Result<()> {
+        let max = vec.iter().fold(f32::MIN, |a, b| a.max(*b));
+        let mut sum = 0f32;
+        for x in vec.iter_mut() {
+            *x = sexp(*x - max);
+            sum += *x;
+        }
+        let inv = 1.0 / sum;
+        vec.iter_mut().for_each(|x| *x *= inv);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    softmax_frame_tests!(true, crate::generic::softmax::GenericSoftmaxF32);
+
+    #[test]
+    fn exp() {
+        for i in -1000..=0 {
+            let x = i as f32 / 10.0;
+            let expected = x.exp();
+            let found = super::sexp(x);
+            assert!(
+                (found - expected).abs() <= expected * 2e-7 + 1e-37,
+                "{} {} {}",
+                x,
+                found,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::frame::element_wise::ElementWiseKer;
+
+const EXP_LOW: f32 = -87.336_55;
+const EXP_HIGH: f32 = 88.376_26;
+const LOG2_E: f32 = std::f32::consts::LOG2_E;
+const LN_2_HI: f32 = 0.693_359_4;
+const LN_2_LO: f32 = -2.121_944_4e-4;
+
+/// Rounds to the nearest integer in a way that vectorizes without SSE4.1.
+#[inline(always)]
+fn round(x: f32) -> f32 {
+    (x + 12_582_912.0) - 12_582_912.0
+}
+
+#[inline(always)]
+fn pow2i(n: f32) -> f32 {
+    f32::from_bits(((n as i32 + 127) as u32) << 23)
+}
+
+/// Cephes-like exp: x = n.ln(2) + r, exp(x) = 2^n.p(r), within the normal
+/// f32 range. The SIMD kernels use the same constants.
+#[inline(always)]
+pub fn sexp(x: f32) -> f32 {
+    let x = x.clamp(EXP_LOW, EXP_HIGH);
+    let n = round(x * LOG2_E);
+    let r = x - n * LN_2_HI - n * LN_2_LO;
+    let p = 1.987_569_1e-4;
+    let p = p * r + 1.398_199_9e-3;
+    let p = p * r + 8.333_452e-3;
+    let p = p * r + 4.166_579_6e-2;
+    let p = p * r + 0.166_666_65;
+    let p = p * r + 0.5;
+    (p * r * r + r + 1.0) * pow2i(n)
+}
+
+/// exp with a degree 4 polynomial.
+#[inline(always)]
+pub fn sexp_fast(x: f32) -> f32 {
+    let x = x.clamp(EXP_LOW, EXP_HIGH);
+    let n = round(x * LOG2_E);
+    let r = x - n * std::f32::consts::LN_2;
+    let p = 1.0 / 24.0;
+    let p = p * r + 1.0 / 6.0;
+    let p = p * r + 0.5;
+    (p * r * r + r + 1.0) * pow2i(n)
+}
+
+/// erf as x.P(x^2)/Q(x^2), with x clamped to [-4, 4].
+#[inline(always)]
+pub fn serf(x: f32) -> f32 {
+    let x = x.clamp(-4.0, 4.0);
+    let x2 = x * x;
+    let p = -2.726_142_3e-10;
+    let p = p * x2 + 2.770_681_4e-8;
+    let p = p * x2 + -2.101_024e-6;
+    let p = p * x2 + -5.692_506_4e-5;
+    let p = p * x2 + -7.349_906e-4;
+    let p = p * x2 + -2.954_6e-3;
+    let p = p * x2 + -1.609_603_3e-2;
+    let q = -1.456_607_2e-5;
+    let q = q * x2 + -2.133_740_6e-4;
+    let q = q * x2 + -1.682_827e-3;
+    let q = q * x2 + -7.373_329e-3;
+    let q = q * x2 + -1.426_473_9e-2;
+    x * p / q
+}
+
+/// erf from Abramowitz and Stegun 7.1.27.
+#[inline(always)]
+pub fn serf_fast(x: f32) -> f32 {
+    let a = x.abs();
+    let d = 1.0 + a * (0.278_393 + a * (0.230_389 + a * (0.000_972 + a * 0.078_108)));
+    let d2 = d * d;
+    (1.0 - 1.0 / (d2 * d2)).copysign(x)
+}
+
+#[inline(always)]
+pub fn ssigmoid_exact(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[inline(always)]
+pub fn ssigmoid_fast(x: f32) -> f32 {
+    1.0 / (1.0 + sexp_fast(-x))
+}
+
+#[inline(always)]
+pub fn stanh_fast(x: f32) -> f32 {
+    2.0 / (1.0 + sexp_fast(-2.0 * x)) - 1.0
+}
+
+macro_rules! generic_kernel {
+    ($name: ident, $label: expr, $f: expr) => {
+        #[derive(Clone, Debug)]
+        pub struct $name;
+
+        impl ElementWiseKer<f32> for $name {
+            fn name() -> &'static str {
+                $label
+            }
+
+            fn alignment_bytes() -> usize {
+                16
+            }
+
+            fn alignment_items() -> usize {
+                4
+            }
+
+            fn nr() -> usize {
+                4
+            }
+
+            fn run(x: &mut [f32]) {
+                debug_assert!(x.len() % Self::nr() == 0);
+                x.iter_mut().for_each(|px| *px = $f(*px))
+            }
+        }
+    };
+}
+
+generic_kernel!(SExp4, "generic", sexp);
+generic_kernel!(SErf4, "generic", serf);
+generic_kernel!(SExpExact, "exact", f32::exp);
+generic_kernel!(SErfExact, "exact", libm::erff);
+generic_kernel!(SSigmoidExact, "exact", ssigmoid_exact);
+generic_kernel!(STanhExact, "exact", f32::tanh);
+generic_kernel!(SExpFast4, "fast", sexp_fast);
+generic_kernel!(SErfFast4, "fast", serf_fast);
+generic_kernel!(SSigmoidFast4, "fast", ssigmoid_fast);
+generic_kernel!(STanhFast4, "fast", stanh_fast);
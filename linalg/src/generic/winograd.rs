@@ -0,0 +1,200 @@
+use crate::frame::winograd::{Winograd, WinogradImpl};
+
+#[rustfmt::skip]
+static F2X2_3X3_G: [f32; 12] = [
+    1.0,  0.0, 0.0,
+    0.5,  0.5, 0.5,
+    0.5, -0.5, 0.5,
+    0.0,  0.0, 1.0,
+];
+
+#[rustfmt::skip]
+static F2X2_3X3_BT: [f32; 16] = [
+    1.0,  0.0, -1.0,  0.0,
+    0.0,  1.0,  1.0,  0.0,
+    0.0, -1.0,  1.0,  0.0,
+    0.0,  1.0,  0.0, -1.0,
+];
+
+#[rustfmt::skip]
+static F2X2_3X3_AT: [f32; 8] = [
+    1.0, 1.0,  1.0,  0.0,
+    0.0, 1.0, -1.0, -1.0,
+];
+
+#[rustfmt::skip]
+static F4X4_3X3_G: [f32; 18] = [
+     1.0 / 4.0,         0.0,        0.0,
+    -1.0 / 6.0, -1.0 /  6.0, -1.0 / 6.0,
+    -1.0 / 6.0,  1.0 /  6.0, -1.0 / 6.0,
+     1.0 / 24.0, 1.0 / 12.0,  1.0 / 6.0,
+     1.0 / 24.0,-1.0 / 12.0,  1.0 / 6.0,
+           0.0,         0.0,        1.0,
+];
+
+#[rustfmt::skip]
+static F4X4_3X3_BT: [f32; 36] = [
+    4.0,  0.0, -5.0,  0.0, 1.0, 0.0,
+    0.0, -4.0, -4.0,  1.0, 1.0, 0.0,
+    0.0,  4.0, -4.0, -1.0, 1.0, 0.0,
+    0.0, -2.0, -1.0,  2.0, 1.0, 0.0,
+    0.0,  2.0, -1.0, -2.0, 1.0, 0.0,
+    0.0,  4.0,  0.0, -5.0, 0.0, 1.0,
+];
+
+#[rustfmt::skip]
+static F4X4_3X3_AT: [f32; 24] = [
+    1.0, 1.0,  1.0, 1.0,  1.0, 0.0,
+    0.0, 1.0, -1.0, 2.0, -2.0, 0.0,
+    0.0, 1.0,  1.0, 4.0,  4.0, 0.0,
+    0.0, 1.0, -1.0, 8.0, -8.0, 1.0,
+];
+
+/// F(2x2, 3x3): 16 multiplications per output tile instead of 36.
+pub fn f2x2_3x3() -> WinogradImpl {
+    WinogradImpl {
+        name: "generic_f2x2_3x3",
+        m: 2,
+        r: 3,
+        g: &F2X2_3X3_G,
+        bt: &F2X2_3X3_BT,
+        at: &F2X2_3X3_AT,
+    }
+}
+
+/// F(4x4, 3x3): 36 multiplications per output tile instead of 144, at the
+/// price of a wider dynamic range in the transformed values.
+pub fn f4x4_3x3() -> WinogradImpl {
+    WinogradImpl {
+        name: "generic_f4x4_3x3",
+        m: 4,
+        r: 3,
+        g: &F4X4_3X3_G,
+        bt: &F4X4_3X3_BT,
+        at: &F4X4_3X3_AT,
+    }
+}
+
+/// Computes F.X.Ft for a I x I X, with the 1-D transform `f` from columns of
+/// size I to columns of size O.
+#[inline(always)]
+fn separable<const I: usize, const O: usize>(
+    f: impl Fn([f32; I]) -> [f32; O],
+    x: &[f32],
+    out: &mut [f32],
+) {
+    let mut tmp = [[0f32; I]; O];
+    for j in 0..I {
+        let mut col = [0f32; I];
+        for i in 0..I {
+            col[i] = x[i * I + j];
+        }
+        let col = f(col);
+        for o in 0..O {
+            tmp[o][j] = col[o];
+        }
+    }
+    for o in 0..O {
+        out[o * O..][..O].copy_from_slice(&f(tmp[o]));
+    }
+}
+
+/// F(2x2, 3x3) with unrolled input and output transforms.
+#[derive(Debug, Clone)]
+pub struct F2x2_3x3;
+
+impl Winograd for F2x2_3x3 {
+    fn name(&self) -> &'static str {
+        "generic_f2x2_3x3_unrolled"
+    }
+
+    fn output_tile(&self) -> usize {
+        2
+    }
+
+    fn kernel_size(&self) -> usize {
+        3
+    }
+
+    fn transform_kernel(&self, kernel: &[f32], transformed: &mut [f32]) {
+        f2x2_3x3().transform_kernel(kernel, transformed)
+    }
+
+    fn transform_input(&self, tile: &[f32], transformed: &mut [f32]) {
+        separable(|[x0, x1, x2, x3]| [x0 - x2, x1 + x2, x2 - x1, x1 - x3], tile, transformed)
+    }
+
+    fn transform_output(&self, tile: &[f32], output: &mut [f32]) {
+        separable(|[x0, x1, x2, x3]| [x0 + x1 + x2, x1 - x2 - x3], tile, output)
+    }
+}
+
+/// F(4x4, 3x3) with unrolled input and output transforms.
+#[derive(Debug, Clone)]
+pub struct F4x4_3x3;
+
+impl Winograd for F4x4_3x3 {
+    fn name(&self) -> &'static str {
+        "generic_f4x4_3x3_unrolled"
+    }
+
+    fn output_tile(&self) -> usize {
+        4
+    }
+
+    fn kernel_size(&self) -> usize {
+        3
+    }
+
+    fn transform_kernel(&self, kernel: &[f32], transformed: &mut [f32]) {
+        f4x4_3x3().transform_kernel(kernel, transformed)
+    }
+
+    fn transform_input(&self, tile: &[f32], transformed: &mut [f32]) {
+        separable(
+            |[x0, x1, x2, x3, x4, x5]| {
+                [
+                    4.0 * x0 - 5.0 * x2 + x4,
+                    x3 + x4 - 4.0 * (x1 + x2),
+                    x4 - x3 + 4.0 * (x1 - x2),
+                    x4 - x2 + 2.0 * (x3 - x1),
+                    x4 - x2 + 2.0 * (x1 - x3),
+                    4.0 * x1 - 5.0 * x3 + x5,
+                ]
+            },
+            tile,
+            transformed,
+        )
+    }
+
+    fn transform_output(&self, tile: &[f32], output: &mut [f32]) {
+        separable(
+            |[x0, x1, x2, x3, x4, x5]| {
+                let (a, b) = (x1 + x2, x1 - x2);
+                let (c, d) = (x3 + x4, x3 - x4);
+                [x0 + a + c, b + 2.0 * d, a + 4.0 * c, b + 8.0 * d + x5]
+            },
+            tile,
+            output,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    mod f2x2_3x3 {
+        winograd_frame_tests!(true, crate::generic::winograd::f2x2_3x3());
+    }
+
+    mod f4x4_3x3 {
+        winograd_frame_tests!(true, crate::generic::winograd::f4x4_3x3());
+    }
+
+    mod f2x2_3x3_unrolled {
+        winograd_frame_tests!(true, crate::generic::winograd::F2x2_3x3);
+    }
+
+    mod f4x4_3x3_unrolled {
+        winograd_frame_tests!(true, crate::generic::winograd::F4x4_3x3);
+    }
+}
@@ -4,6 +4,7 @@ extern crate derive_new;
 extern crate educe;
 extern crate lazy_static;
 extern crate libc;
+extern crate libm;
 extern crate log;
 extern crate num_traits;
 #[macro_use]
@@ -13,9 +14,12 @@ extern crate proptest;
 
 include!(concat!(env!("OUT_DIR"), "/extern_kernel_macro.rs"));
 
+pub mod accuracy;
 #[macro_use]
 pub mod frame;
 pub mod generic;
+pub mod multithread;
+pub use accuracy::{Accuracy, Transcendental};
 use frame::MatMatMul;
 pub use generic::{ScaleShiftAndRound, Scaler};
 #[cfg(target_arch = "x86_64")]
@@ -27,7 +31,9 @@ pub mod arm64;
 #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
 pub mod arm32;
 
-pub use self::frame::{element_wise, lut, mmm};
+pub use self::frame::{
+    arg_min_max, attention, block_quant, depthwise, element_wise, lut, mmm, softmax, winograd,
+};
 
 use crate::frame::mmm::kernel::MatMatMulKer;
 use tract_data::prelude::*;
@@ -40,6 +46,12 @@ pub struct Ops {
             + Sync,
     >,
     mmv_f32: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
+    /// Kernels for products with only a few columns, if any fits `n`.
+    mmm_f32_skinny: Box<
+        dyn Fn(Option<usize>, Option<usize>, usize) -> Option<Box<dyn mmm::MatMatMul>>
+            + Send
+            + Sync,
+    >,
     qmmm_i32: Box<
         dyn Fn(Option<usize>, Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul>
             + Send
@@ -48,7 +60,16 @@ pub struct Ops {
     qmmv_i32: Box<dyn Fn(Option<usize>, Option<usize>) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     pub sigmoid_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
     pub tanh_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
+    pub exp_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
+    pub erf_f32: Box<dyn Fn() -> Box<dyn element_wise::ElementWise<f32>> + Send + Sync>,
+    pub softmax_f32: Box<dyn Fn() -> Box<dyn softmax::Softmax<f32>> + Send + Sync>,
+    pub softmax_f16: Box<dyn Fn() -> Box<dyn softmax::Softmax<f16>> + Send + Sync>,
+    pub arg_min_max_f32: Box<dyn Fn() -> Box<dyn arg_min_max::ArgMinMax<f32>> + Send + Sync>,
     pub lut_u8: Box<dyn Fn(&[u8]) -> Box<dyn lut::Lut> + Send + Sync>,
+    /// Winograd 3x3 transforms for a given output tile size.
+    pub winograd_3x3_f32: Box<dyn Fn(usize) -> Option<Box<dyn winograd::Winograd>> + Send + Sync>,
+    pub depthwise_3x3_f32: Box<dyn Fn() -> Box<dyn depthwise::DepthWise3x3> + Send + Sync>,
+    pub attention_f32: Box<dyn Fn() -> Box<dyn attention::Attention> + Send + Sync>,
 }
 
 impl Ops {
@@ -56,6 +77,50 @@ impl Ops {
         &self.mmm_f32_impls
     }
 
+    fn autotuned_f32(
+        &self,
+        m: Option<usize>,
+        k: Option<usize>,
+        n: Option<usize>,
+    ) -> Option<Box<dyn mmm::MatMatMul>> {
+        let tuner = mmm::autotuner()?;
+        let (m, k, n) = (m?, k?, n?);
+        match tuner.pick(&self.mmm_f32_impls, m, k, n) {
+            Ok(mm) => Some(mm),
+            Err(e) => {
+                log::warn!("Autotuning failed for {}x{}x{}: {:?}", m, k, n, e);
+                None
+            }
+        }
+    }
+
+    /// `function` at the given `accuracy`: the exact variants run the libm
+    /// functions, the accurate ones are the `*_f32` kernels.
+    pub fn transcendental_f32(
+        &self,
+        function: Transcendental,
+        accuracy: Accuracy,
+    ) -> Box<dyn element_wise::ElementWise<f32>> {
+        use element_wise::ElementWiseImpl as E;
+        use generic::transcendental::*;
+        use Accuracy::*;
+        use Transcendental::*;
+        match (accuracy, function) {
+            (Exact, Exp) => Box::new(E::<SExpExact, f32>::new()),
+            (Exact, Erf) => Box::new(E::<SErfExact, f32>::new()),
+            (Exact, Sigmoid) => Box::new(E::<SSigmoidExact, f32>::new()),
+            (Exact, Tanh) => Box::new(E::<STanhExact, f32>::new()),
+            (Accurate, Exp) => (self.exp_f32)(),
+            (Accurate, Erf) => (self.erf_f32)(),
+            (Accurate, Sigmoid) => (self.sigmoid_f32)(),
+            (Accurate, Tanh) => (self.tanh_f32)(),
+            (Fast, Exp) => Box::new(E::<SExpFast4, f32>::new()),
+            (Fast, Erf) => Box::new(E::<SErfFast4, f32>::new()),
+            (Fast, Sigmoid) => Box::new(E::<SSigmoidFast4, f32>::new()),
+            (Fast, Tanh) => Box::new(E::<STanhFast4, f32>::new()),
+        }
+    }
+
     pub fn mmm(
         &self,
         a: DatumType,
@@ -67,9 +132,15 @@ impl Ops {
     ) -> Option<Box<dyn mmm::MatMatMul>> {
         use DatumType::*;
         match (a.unquantized(), b.unquantized(), c.unquantized()) {
-            (F32, F32, F32) => {
-                Some(if n == Some(1) { (self.mmv_f32)(m, k) } else { (self.mmm_f32)(m, k, n) })
-            }
+            (F32, F32, F32) => Some(if n == Some(1) {
+                (self.mmv_f32)(m, k)
+            } else if let Some(mm) = n.and_then(|n| (self.mmm_f32_skinny)(m, k, n)) {
+                mm
+            } else if let Some(mm) = self.autotuned_f32(m, k, n) {
+                mm
+            } else {
+                (self.mmm_f32)(m, k, n)
+            }),
             (I8, I8, I32) => {
                 Some(if n == Some(1) { (self.qmmv_i32)(m, k) } else { (self.qmmm_i32)(m, k, n) })
             }
@@ -86,6 +157,7 @@ pub fn generic() -> Ops {
         mmm_f32_impls: vec![generic::GenericMmm4x4::<f32, f32, f32>::mmm()],
         mmm_f32: Box::new(|_, _, _| generic::GenericMmm4x4::<f32, f32, f32>::mmm()),
         mmv_f32: Box::new(|_, _| generic::GenericMmm4x1::<f32, f32, f32>::mmm()),
+        mmm_f32_skinny: Box::new(|_, _, _| None),
         qmmm_i32: Box::new(|_, _, _| generic::GenericMmm4x4::<i8, i8, i32>::mmm()),
         qmmv_i32: Box::new(|_, _| generic::GenericMmm4x1::<i8, i8, i32>::mmm()),
         sigmoid_f32: Box::new(|| {
@@ -94,7 +166,25 @@ pub fn generic() -> Ops {
         tanh_f32: Box::new(|| {
             Box::new(element_wise::ElementWiseImpl::<generic::STanh4, f32>::new())
         }),
+        exp_f32: Box::new(|| {
+            Box::new(element_wise::ElementWiseImpl::<generic::transcendental::SExp4, f32>::new())
+        }),
+        erf_f32: Box::new(|| {
+            Box::new(element_wise::ElementWiseImpl::<generic::transcendental::SErf4, f32>::new())
+        }),
+        softmax_f32: Box::new(|| Box::new(generic::softmax::GenericSoftmaxF32)),
+        softmax_f16: Box::new(|| {
+            Box::new(softmax::F16ViaF32::new(Box::new(generic::softmax::GenericSoftmaxF32)))
+        }),
+        arg_min_max_f32: Box::new(|| Box::new(generic::arg_min_max::GenericArgMinMax)),
         lut_u8: Box::new(|table: &[u8]| Box::new(lut::LutImpl::<generic::GenericLut8>::new(table))),
+        winograd_3x3_f32: Box::new(|tile| match tile {
+            2 => Some(Box::new(generic::winograd::F2x2_3x3)),
+            4 => Some(Box::new(generic::winograd::F4x4_3x3)),
+            _ => None,
+        }),
+        depthwise_3x3_f32: Box::new(|| Box::new(generic::depthwise::GenericDepthWise3x3)),
+        attention_f32: Box::new(|| Box::new(generic::attention::GenericFlashAttention)),
     }
 }
 
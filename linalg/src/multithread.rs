@@ -0,0 +1,211 @@
+//! Executor selection for parallel operations.
+//!
+//! tract runs single threaded unless told otherwise. A multithreaded
+//! executor can be set for the whole process with `set_default_executor`, or
+//! for the duration of a closure (on the calling thread) with
+//! `multithread_tract_scope`.
+//!
+//! Executors can run on a pool created by tract (optionally with workers
+//! pinned to cores) or on a rayon pool supplied by the application.
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tract_data::internal::*;
+
+#[derive(Debug, Clone)]
+pub enum Executor {
+    SingleThread,
+    MultiThread(Arc<ThreadPool>),
+}
+
+impl Executor {
+    /// A multithreaded executor running on a new pool of `threads` workers.
+    pub fn multithread(threads: usize) -> TractResult<Executor> {
+        let pool = ThreadPoolBuilder::new()
+            .thread_name(|ix| format!("tract-worker-{}", ix))
+            .num_threads(threads)
+            .build()?;
+        Ok(Executor::MultiThread(Arc::new(pool)))
+    }
+
+    /// A multithreaded executor with one worker per core in `cores`, each
+    /// worker being pinned to its core. Only supported on Linux and Android.
+    pub fn multithread_pinned(cores: &[usize]) -> TractResult<Executor> {
+        ensure!(!cores.is_empty(), "Need at least one core to pin workers to");
+        let available = affinity::available_cores()?;
+        if let Some(core) = cores.iter().find(|c| !available.contains(c)) {
+            bail!("Core {} is not available to this process (available: {:?})", core, available);
+        }
+        let cores = cores.to_vec();
+        let pool = ThreadPoolBuilder::new()
+            .thread_name(|ix| format!("tract-worker-{}", ix))
+            .num_threads(cores.len())
+            .start_handler(move |ix| {
+                if let Err(e) = affinity::pin_current_thread(cores[ix]) {
+                    log::warn!("Failed to pin tract worker {} to core {}: {}", ix, cores[ix], e);
+                }
+            })
+            .build()?;
+        Ok(Executor::MultiThread(Arc::new(pool)))
+    }
+
+    /// A multithreaded executor running on a pool owned by the caller, for
+    /// instance shared with the rest of the application.
+    pub fn from_pool(pool: Arc<ThreadPool>) -> Executor {
+        Executor::MultiThread(pool)
+    }
+
+    pub fn threads(&self) -> usize {
+        match self {
+            Executor::SingleThread => 1,
+            Executor::MultiThread(pool) => pool.current_num_threads(),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod affinity {
+    use tract_data::internal::*;
+
+    /// Cores the current thread is allowed to run on.
+    pub fn available_cores() -> TractResult<Vec<usize>> {
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+                bail!("sched_getaffinity failed: {}", std::io::Error::last_os_error());
+            }
+            Ok((0..libc::CPU_SETSIZE as usize).filter(|c| libc::CPU_ISSET(*c, &set)).collect())
+        }
+    }
+
+    pub fn pin_current_thread(core: usize) -> std::io::Result<()> {
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_SET(core, &mut set);
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod affinity {
+    use tract_data::internal::*;
+
+    pub fn available_cores() -> TractResult<Vec<usize>> {
+        bail!("Pinning threads to cores is only supported on Linux and Android")
+    }
+
+    pub fn pin_current_thread(_core: usize) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_EXECUTOR: Mutex<Executor> = Mutex::new(Executor::SingleThread);
+}
+
+thread_local! {
+    static TLS_EXECUTOR_OVERRIDE: RefCell<Option<Executor>> = const { RefCell::new(None) };
+}
+
+pub fn set_default_executor(executor: Executor) {
+    *DEFAULT_EXECUTOR.lock().unwrap() = executor;
+}
+
+/// Executor for operations started from the current thread.
+pub fn current_tract_executor() -> Executor {
+    if let Some(executor) = TLS_EXECUTOR_OVERRIDE.with(|e| e.borrow().clone()) {
+        executor
+    } else {
+        DEFAULT_EXECUTOR.lock().unwrap().clone()
+    }
+}
+
+/// Runs `f` with `executor` overriding the default one on this thread.
+pub fn multithread_tract_scope<R, F: FnOnce() -> R>(executor: Executor, f: F) -> R {
+    let previous = TLS_EXECUTOR_OVERRIDE.with(|e| e.replace(Some(executor)));
+    struct Restore(Option<Executor>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            TLS_EXECUTOR_OVERRIDE.with(|e| *e.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(previous);
+    f()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::mmm::tests::{test_mat_mat_mul_late, test_mat_mat_mul_prep};
+    use crate::generic::GenericMmm4x4;
+
+    type Ker = GenericMmm4x4<f32, f32, f32>;
+
+    fn operands(m: usize, k: usize, n: usize) -> (Tensor, Tensor) {
+        let a = tensor1(&(0..m * k).map(|i| (i % 7) as f32 - 3.0).collect::<Vec<_>>());
+        let b = tensor1(&(0..k * n).map(|i| (i % 5) as f32 - 2.0).collect::<Vec<_>>());
+        (a.into_shape(&[m, k]).unwrap(), b.into_shape(&[k, n]).unwrap())
+    }
+
+    #[test]
+    fn scope_overrides_default() -> TractResult<()> {
+        assert_eq!(current_tract_executor().threads(), 1);
+        multithread_tract_scope(Executor::multithread(3)?, || {
+            assert_eq!(current_tract_executor().threads(), 3)
+        });
+        assert_eq!(current_tract_executor().threads(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn external_pool() -> TractResult<()> {
+        let pool = Arc::new(ThreadPoolBuilder::new().num_threads(2).build()?);
+        let (a, b) = operands(23, 9, 17);
+        multithread_tract_scope(Executor::from_pool(pool), || {
+            assert_eq!(current_tract_executor().threads(), 2);
+            test_mat_mat_mul_prep::<Ker, f32, f32, f32, f32>(23, 9, 17, &a, &b)
+        })
+        .unwrap();
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn pinned_workers() -> TractResult<()> {
+        let core = affinity::available_cores()?[0];
+        let executor = Executor::multithread_pinned(&[core, core])?;
+        assert_eq!(executor.threads(), 2);
+        if let Executor::MultiThread(pool) = &executor {
+            assert_eq!(pool.install(affinity::available_cores)?, vec![core]);
+        }
+        assert!(Executor::multithread_pinned(&[]).is_err());
+        assert!(Executor::multithread_pinned(&[usize::MAX]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn multithread_mat_mul_prepacked() -> TractResult<()> {
+        let (a, b) = operands(23, 9, 17);
+        multithread_tract_scope(Executor::multithread(4)?, || {
+            test_mat_mat_mul_prep::<Ker, f32, f32, f32, f32>(23, 9, 17, &a, &b)
+        })
+        .unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn multithread_mat_mul_late_packing() -> TractResult<()> {
+        let (a, b) = operands(6, 5, 31);
+        multithread_tract_scope(Executor::multithread(4)?, || {
+            test_mat_mat_mul_late::<Ker, f32, f32, f32, f32>(6, 5, 31, &a, &b)
+        })
+        .unwrap();
+        Ok(())
+    }
+}
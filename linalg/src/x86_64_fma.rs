@@ -1,9 +1,15 @@
 use crate::frame::mmm::kernel::MatMatMulKer;
+use crate::frame::softmax::F16ViaF32;
 use crate::frame::ElementWiseImpl;
 use crate::Ops;
 
+pub mod arg_min_max;
+pub mod depthwise;
+pub mod erf;
+pub mod exp;
 pub mod mmm;
 pub mod sigmoid;
+pub mod softmax;
 pub mod tanh;
 
 mod intel;
@@ -11,15 +17,42 @@ mod intel;
 pub fn plug(ops: &mut Ops) {
     if is_x86_feature_detected!("fma") {
         ops.mmv_f32 = Box::new(|_, _| mmm::fma_mmm_f32_64x1::mmm());
+        ops.mmm_f32_skinny = Box::new(|_, _, n| match n {
+            2 => Some(mmm::fma_mmm_f32_32x2::mmm()),
+            3 | 4 => Some(mmm::fma_mmm_f32_24x4::mmm()),
+            _ => None,
+        });
         ops.mmm_f32_impls.push(mmm::fma_mmm_f32_16x6::mmm());
         ops.mmm_f32 = Box::new(|_,_,_| mmm::fma_mmm_f32_16x6::mmm());
         ops.mmm_f32_impls.push(mmm::fma_mmm_f32_8x8::mmm());
         ops.sigmoid_f32 = Box::new(|| Box::new(ElementWiseImpl::<sigmoid::SigmoidF32, f32>::new()));
         ops.tanh_f32 = Box::new(|| Box::new(ElementWiseImpl::<tanh::TanhF32, f32>::new()));
-        log::info!("mmm_f32, sigmoid_f32, tanh_f32: x86_64/fma activated");
+        ops.depthwise_3x3_f32 = Box::new(|| Box::new(depthwise::FmaDepthWise3x3));
+        ops.erf_f32 = Box::new(|| Box::new(ElementWiseImpl::<erf::ErfF32, f32>::new()));
+        log::info!(
+            "mmm_f32, sigmoid_f32, tanh_f32, erf_f32, depthwise_3x3_f32: x86_64/fma activated"
+        );
+    }
+    if is_x86_feature_detected!("avx512f") {
+        ops.mmm_f32_impls.push(mmm::avx512_mmm_f32_32x12::mmm());
+        ops.mmm_f32 = Box::new(|_, _, _| mmm::avx512_mmm_f32_32x12::mmm());
+        log::info!("mmm_f32: x86_64/avx512f activated");
     }
     if is_x86_feature_detected!("avx2") {
         ops.qmmm_i32 = Box::new(|_, _, _| mmm::avx2_mmm_i32_8x8::mmm());
         log::info!("mmm_i8_i8 and mmm_i8_i32: x86_64/avx2 activated");
     }
+    if is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2") {
+        ops.arg_min_max_f32 = Box::new(|| Box::new(arg_min_max::FmaArgMinMaxF32));
+        ops.exp_f32 = Box::new(|| Box::new(ElementWiseImpl::<exp::ExpF32, f32>::new()));
+        ops.softmax_f32 = Box::new(|| Box::new(softmax::FmaSoftmaxF32));
+        ops.softmax_f16 = Box::new(|| Box::new(F16ViaF32::new(Box::new(softmax::FmaSoftmaxF32))));
+        log::info!(
+            "arg_min_max_f32, exp_f32, softmax_f32, softmax_f16: x86_64/fma activated"
+        );
+    }
+    if is_x86_feature_detected!("avx512vnni") && is_x86_feature_detected!("avx512vl") {
+        ops.qmmm_i32 = Box::new(|_, _, _| mmm::avx512vnni_mmm_i32_8x8::mmm());
+        log::info!("mmm_i8_i8 and mmm_i8_i32: x86_64/avx512vnni activated");
+    }
 }
@@ -0,0 +1,72 @@
+use crate::frame::arg_min_max::{better, ArgMinMax};
+use crate::generic::arg_min_max::fold;
+
+#[repr(C)]
+struct ArgSpec {
+    ptr: *const f32,
+    len: usize,
+    last: usize,
+    values: *mut f32,
+    indices: *mut u32,
+}
+
+extern_kernel!(fn fma_argmax_f32(spec: *const ArgSpec) -> ());
+extern_kernel!(fn fma_argmin_f32(spec: *const ArgSpec) -> ());
+
+#[derive(Clone, Debug)]
+pub struct FmaArgMinMaxF32;
+
+impl FmaArgMinMaxF32 {
+    fn run(&self, vec: &[f32], max: bool, last: bool) -> usize {
+        let init = if max { f32::MIN } else { f32::MAX };
+        let vectorized = if vec.len() <= u32::MAX as usize { vec.len() / 8 * 8 } else { 0 };
+        let mut acc = (0, init);
+        if vectorized > 0 {
+            let mut values = [0f32; 8];
+            let mut indices = [0u32; 8];
+            let spec = ArgSpec {
+                ptr: vec.as_ptr(),
+                len: vectorized,
+                last: last as usize,
+                values: values.as_mut_ptr(),
+                indices: indices.as_mut_ptr(),
+            };
+            unsafe {
+                if max {
+                    fma_argmax_f32(&spec)
+                } else {
+                    fma_argmin_f32(&spec)
+                }
+            }
+            for lane in 0..8 {
+                let candidate = (indices[lane] as usize, values[lane]);
+                if better(max, last, acc, candidate) {
+                    acc = candidate;
+                }
+            }
+        }
+        fold(vec, vectorized..vec.len(), max, last, acc).0
+    }
+}
+
+impl ArgMinMax<f32> for FmaArgMinMaxF32 {
+    fn name(&self) -> &'static str {
+        "fma"
+    }
+
+    fn argmax(&self, vec: &[f32], last: bool) -> usize {
+        self.run(vec, true, last)
+    }
+
+    fn argmin(&self, vec: &[f32], last: bool) -> usize {
+        self.run(vec, false, last)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    arg_min_max_frame_tests!(
+        is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2"),
+        crate::x86_64_fma::arg_min_max::FmaArgMinMaxF32
+    );
+}
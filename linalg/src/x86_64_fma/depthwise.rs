@@ -0,0 +1,90 @@
+use crate::frame::depthwise::DepthWise3x3;
+use crate::generic::depthwise::{interleaved_pixel, planar_row};
+
+#[repr(C)]
+struct PlanarSpec {
+    rows: [*const f32; 3],
+    kernel: *const f32,
+    bias: *const f32,
+    out: *mut f32,
+    width: usize,
+}
+
+#[repr(C)]
+struct InterleavedSpec {
+    inputs: *const *const f32,
+    kernel: *const f32,
+    bias: *const f32,
+    out: *mut f32,
+    channels: usize,
+}
+
+extern_kernel!(fn fma_dw3x3_planar_s1_f32(spec: *const PlanarSpec) -> ());
+extern_kernel!(fn fma_dw3x3_planar_s2_f32(spec: *const PlanarSpec) -> ());
+extern_kernel!(fn fma_dw3x3_interleaved_f32(spec: *const InterleavedSpec) -> ());
+
+#[derive(Debug, Clone)]
+pub struct FmaDepthWise3x3;
+
+impl DepthWise3x3 for FmaDepthWise3x3 {
+    fn name(&self) -> &'static str {
+        "fma"
+    }
+
+    unsafe fn planar_row(
+        &self,
+        stride: usize,
+        rows: [*const f32; 3],
+        kernel: &[f32; 9],
+        bias: f32,
+        out: *mut f32,
+        width: usize,
+    ) {
+        // with stride 2, the kernel reads one item past the last block inputs
+        let vectorized = match stride {
+            1 => width / 8 * 8,
+            2 => width.saturating_sub(1) / 8 * 8,
+            _ => 0,
+        };
+        if vectorized > 0 {
+            let spec =
+                PlanarSpec { rows, kernel: kernel.as_ptr(), bias: &bias, out, width: vectorized };
+            if stride == 1 {
+                fma_dw3x3_planar_s1_f32(&spec)
+            } else {
+                fma_dw3x3_planar_s2_f32(&spec)
+            }
+        }
+        planar_row(stride, rows, kernel, bias, out, vectorized..width)
+    }
+
+    unsafe fn interleaved_pixel(
+        &self,
+        inputs: &[*const f32; 9],
+        kernel: &[f32],
+        bias: *const f32,
+        out: *mut f32,
+        channels: usize,
+    ) {
+        let vectorized = channels / 8 * 8;
+        if vectorized > 0 {
+            let spec = InterleavedSpec {
+                inputs: inputs.as_ptr(),
+                kernel: kernel.as_ptr(),
+                bias,
+                out,
+                channels: vectorized,
+            };
+            fma_dw3x3_interleaved_f32(&spec)
+        }
+        interleaved_pixel(inputs, kernel, bias, out, vectorized..channels)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    depthwise_frame_tests!(
+        is_x86_feature_detected!("fma"),
+        crate::x86_64_fma::depthwise::FmaDepthWise3x3
+    );
+}
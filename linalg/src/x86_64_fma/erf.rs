@@ -0,0 +1,29 @@
+use crate::element_wise::ElementWiseKer;
+
+extern_kernel!(fn fma_erf_f32(ptr: *mut f32, count: usize) -> ());
+
+#[derive(Copy, Clone, Debug)]
+pub struct ErfF32;
+
+impl ElementWiseKer<f32> for ErfF32 {
+    #[inline(always)]
+    fn name() -> &'static str {
+        "fma"
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_items() -> usize {
+        8
+    }
+    #[inline(always)]
+    fn alignment_bytes() -> usize {
+        32
+    }
+    #[inline(never)]
+    fn run(buf: &mut [f32]) {
+        unsafe { fma_erf_f32(buf.as_mut_ptr(), buf.len()) }
+    }
+}
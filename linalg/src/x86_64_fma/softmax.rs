@@ -0,0 +1,28 @@
+use crate::frame::softmax::Softmax;
+use tract_data::anyhow;
+
+extern_kernel!(fn fma_softmax_f32(ptr: *mut f32, count: usize) -> ());
+
+#[derive(Clone, Debug)]
+pub struct FmaSoftmaxF32;
+
+impl Softmax<f32> for FmaSoftmaxF32 {
+    fn name(&self) -> &'static str {
+        "fma"
+    }
+
+    fn run(&self, vec: &mut [f32]) -> anyhow::Result<()> {
+        if !vec.is_empty() {
+            unsafe { fma_softmax_f32(vec.as_mut_ptr(), vec.len()) }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    softmax_frame_tests!(
+        is_x86_feature_detected!("fma") && is_x86_feature_detected!("avx2"),
+        crate::x86_64_fma::softmax::FmaSoftmaxF32
+    );
+}
@@ -54,9 +54,11 @@ fn version(i: &str) -> IResult<&str, NumericLiteral> {
     delimited(stag("version"), numeric_literal, stag(";"))(i)
 }
 
-// <extension> ::= "extension" <identifier>+ ";"
+// <extension> ::= "extension" <identifier> ([","] <identifier>)* ";"
 fn extension(i: &str) -> IResult<&str, Vec<String>> {
-    delimited(stag("extension"), many1(spaced(identifier)), stag(";"))(i)
+    delimited(stag("extension"), many1(terminated(spaced(identifier), opt(stag(",")))), stag(";"))(
+        i,
+    )
 }
 
 // FRAGMENT
@@ -335,9 +337,10 @@ fn rvalue(i: &str) -> IResult<&str, RValue> {
 fn comprehension_expr(i: &str) -> IResult<&str, Comprehension> {
     delimited(
         pair(stag("["), stag("for")),
-        map(separated_pair(loop_iters, stag("yield"), rvalue), |(loop_iters, yields)| {
-            Comprehension { loop_iters, filter: None, yields }
-        }),
+        map(
+            tuple((loop_iters, opt(preceded(stag("if"), rvalue)), preceded(stag("yield"), rvalue))),
+            |(loop_iters, filter, yields)| Comprehension { loop_iters, filter, yields },
+        ),
         stag("]"),
     )(i)
 }
@@ -0,0 +1,171 @@
+//! On-disk cache of optimized models.
+//!
+//! Translating and decluttering a large model can take a long time, and is
+//! done again at every process start. A cache file is a plain NNEF tar
+//! archive of the decluttered model, with the tract_core operators, starting
+//! with a `fingerprint` entry. Codegen and weight packing depend on the
+//! machine and are redone at load time: loading a cache only runs the
+//! (idempotent) declutter passes on a graph that needs none, then codegen.
+//!
+//! The fingerprint identifies what the cache was built from, and the tract
+//! version: a cache with another fingerprint is ignored and rebuilt.
+use crate::ast::ProtoModel;
+use crate::internal::*;
+use std::hash::Hasher;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const FINGERPRINT_ENTRY: &str = "fingerprint";
+
+/// Fingerprint of a model source (typically the bytes of the original model
+/// file, or `TypedModel::signature`) for this version of tract.
+pub fn fingerprint<H: Hash + ?Sized>(source: &H) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    source.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+impl Nnef {
+    /// Write the decluttered `model` to a cache file at `path`.
+    pub fn write_cache(
+        &self,
+        model: &TypedModel,
+        fingerprint: &str,
+        path: impl AsRef<Path>,
+    ) -> TractResult<()> {
+        let path = path.as_ref();
+        // written aside and renamed so a concurrent reader never sees a partial file
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        let file = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+        let mut ar = tar::Builder::new(file);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(fingerprint.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        ar.append_data(&mut header, FINGERPRINT_ENTRY, fingerprint.as_bytes())?;
+        self.append_to_tar(model, &mut ar)?;
+        ar.into_inner()?.flush()?;
+        std::fs::rename(&tmp, path).with_context(|| format!("Moving cache file to {:?}", path))?;
+        Ok(())
+    }
+
+    /// Read the decluttered model of a cache file, if it exists and has the
+    /// expected fingerprint.
+    pub fn read_cache(
+        &self,
+        path: impl AsRef<Path>,
+        fingerprint: &str,
+    ) -> TractResult<Option<TypedModel>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut ar = tar::Archive::new(std::io::BufReader::new(std::fs::File::open(path)?));
+        let mut entries = ar.entries()?;
+        match entries.next() {
+            Some(entry) => {
+                let mut entry = entry?;
+                if entry.path()?.to_str() != Some(FINGERPRINT_ENTRY) {
+                    bail!("{:?} is not a model cache", path);
+                }
+                let mut found = String::new();
+                entry.read_to_string(&mut found)?;
+                if found != fingerprint {
+                    debug!(
+                        "Ignoring {:?}, fingerprint is {}, expected {}",
+                        path, found, fingerprint
+                    );
+                    return Ok(None);
+                }
+            }
+            None => bail!("{:?} is empty", path),
+        }
+        let mut text: Option<String> = None;
+        let mut tensors: Vec<(String, Arc<Tensor>)> = Default::default();
+        let mut quantization = None;
+        for entry in entries {
+            let mut entry = entry?;
+            let path = entry.path()?.to_path_buf();
+            crate::framework::read_stream(
+                &path,
+                &mut entry,
+                &mut text,
+                &mut tensors,
+                &mut quantization,
+            )?;
+        }
+        let text = text.ok_or_else(|| format_err!("Cache must contain graph.nnef"))?;
+        let doc = crate::ast::parse::parse_document(&text)?;
+        let proto = ProtoModel { doc, tensors, quantization };
+        proto.validate()?;
+        Ok(Some(self.model_for_proto_model(&proto)?))
+    }
+
+    /// Optimized model, from the cache file at `path` if it is valid, or
+    /// from the decluttered model returned by `build`, which is then cached.
+    /// A model that can not be cached is still returned.
+    pub fn cached_model(
+        &self,
+        path: impl AsRef<Path>,
+        fingerprint: &str,
+        build: impl FnOnce() -> TractResult<TypedModel>,
+    ) -> TractResult<TypedModel> {
+        let path = path.as_ref();
+        match self.read_cache(path, fingerprint) {
+            Ok(Some(model)) => return model.into_optimized(),
+            Ok(None) => (),
+            Err(e) => warn!("Ignoring invalid model cache {:?}: {:?}", path, e),
+        }
+        let model = build()?;
+        if let Err(e) = self.write_cache(&model, fingerprint, path) {
+            warn!("Failed to write model cache {:?}: {:?}", path, e);
+        }
+        model.into_optimized()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let a = model.wire_node(
+            "a",
+            tract_core::ops::math::add::unary(rctensor1(&[1f32, 2.0, 3.0])),
+            &[source],
+        )?;
+        let b = model.wire_node("b", tract_core::ops::math::neg(), &a)?;
+        model.set_output_outlets(&b)?;
+        model.into_decluttered()
+    }
+
+    #[test]
+    fn cache_round_trip() -> TractResult<()> {
+        let dir = std::env::temp_dir().join(format!("tract-nnef-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("model.nnef.tar");
+        let nnef = crate::nnef().with_tract_core();
+        let print = fingerprint("model".as_bytes());
+        let mut builds = 0;
+        for _ in 0..2 {
+            let model = nnef.cached_model(&path, &print, || {
+                builds += 1;
+                model()
+            })?;
+            let output = model.into_runnable()?.run(tvec!(tensor1(&[1f32, 0.0, -1.0])))?;
+            assert_eq!(*output[0], tensor1(&[-2f32, -2.0, -2.0]));
+        }
+        assert_eq!(builds, 1);
+        assert!(nnef.read_cache(&path, &fingerprint("other".as_bytes()))?.is_none());
+        nnef.cached_model(&path, &fingerprint("other".as_bytes()), || {
+            builds += 1;
+            model()
+        })?;
+        assert_eq!(builds, 2);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -134,14 +134,22 @@ impl<'mb> ModelBuilder<'mb> {
                 })
                 .collect::<Vec<_>>();
             self.naming_scopes.push(identifiers[0].to_string());
+            let first_new_node = self.model.nodes().len();
             let mut values = if identifiers.len() == 1 {
-                let value: OutletId = assignment
-                    .right
-                    .resolve(self, &datum_types)
-                    .and_then(|v| v.to(self))
-                    .with_context(|| {
-                        format!("Plugging in assignement for {:?}", identifiers.join(", "))
-                    })?;
+                let value = assignment.right.resolve(self, &datum_types).with_context(|| {
+                    format!("Plugging in assignement for {:?}", identifiers.join(", "))
+                })?;
+                // arrays of tensors (like in fragment bodies) are kept as such
+                if let Value::Array(items) = &value {
+                    if items.iter().any(|item| matches!(item, Value::Wire(_))) {
+                        self.scopes.last_mut().unwrap().insert(identifiers[0].to_string(), value);
+                        self.naming_scopes.pop();
+                        continue;
+                    }
+                }
+                let value: OutletId = value.to(self).with_context(|| {
+                    format!("Plugging in assignement for {:?}", identifiers.join(", "))
+                })?;
                 tvec!(value)
             } else {
                 let values: TVec<OutletId> = assignment
@@ -173,7 +181,11 @@ impl<'mb> ModelBuilder<'mb> {
                     }
                 }
             }
-            self.model.node_mut(values[0].node).name = format!("{}", self.naming_scopes.join("."));
+            // do not rename nodes wired before this assignment (like a fragment parameter
+            // passed through)
+            if values[0].node >= first_new_node {
+                self.model.node_mut(values[0].node).name = self.naming_scopes.join(".");
+            }
             for (id, outlet) in identifiers.iter().zip(values.iter()) {
                 self.scopes.last_mut().unwrap().insert(id.to_string(), Value::Wire(*outlet));
             }
@@ -463,8 +475,111 @@ impl RValue {
                     .map(|(i, dt)| RValue::Literal(i.clone()).resolve(builder, &[*dt]))
                     .collect::<TractResult<_>>()?,
             )),
-            _ => panic!("{:?}", self),
+            RValue::Literal(Literal::Tuple(items)) => Ok(Value::Tuple(
+                items
+                    .iter()
+                    .map(|i| RValue::Literal(i.clone()).resolve(builder, &[]))
+                    .collect::<TractResult<_>>()?,
+            )),
+            RValue::Unary(op, rv) => match (&**op, rv.resolve(builder, dt)?) {
+                ("+", value) => Ok(value),
+                ("-", Value::Scalar(f)) => Ok(Value::Scalar(-f)),
+                ("-", Value::Dim(d)) => Ok(Value::Dim(-d)),
+                ("!", Value::Bool(b)) => Ok(Value::Bool(!b)),
+                (op, value) => {
+                    let input = value.to::<OutletId>(builder)?;
+                    let outlets = match op {
+                        "-" => builder.wire(tract_core::ops::math::neg(), &[input])?,
+                        "!" => builder.wire(tract_core::ops::logic::not(), &[input])?,
+                        op => bail!("Unknown unary operator: {}", op),
+                    };
+                    Ok(Value::Wire(outlets[0]))
+                }
+            },
+            RValue::Subscript(rv, subscript) => {
+                let items = match rv.resolve(builder, dt)? {
+                    Value::Array(items) | Value::Tuple(items) => items,
+                    value => bail!("Only arrays and tuples can be subscripted, got {:?}", value),
+                };
+                match &**subscript {
+                    Subscript::Single(ix) => {
+                        let ix: usize = ix.resolve(builder, &[])?.to(builder)?;
+                        items.get(ix).cloned().with_context(|| {
+                            format!("Index {} out of bounds for {} item(s)", ix, items.len())
+                        })
+                    }
+                    Subscript::Range(start, end) => {
+                        let start: usize = start
+                            .as_ref()
+                            .map(|rv| rv.resolve(builder, &[])?.to(builder))
+                            .transpose()?
+                            .unwrap_or(0);
+                        let end: usize = end
+                            .as_ref()
+                            .map(|rv| rv.resolve(builder, &[])?.to(builder))
+                            .transpose()?
+                            .unwrap_or(items.len());
+                        ensure!(
+                            start <= end && end <= items.len(),
+                            "Invalid range {}:{} for {} item(s)",
+                            start,
+                            end,
+                            items.len()
+                        );
+                        Ok(Value::Array(items[start..end].to_vec()))
+                    }
+                }
+            }
+            RValue::IfThenElse(ite) => {
+                let cond: bool = ite.cond.resolve(builder, &[])?.to(builder)?;
+                if cond {
+                    ite.then.resolve(builder, dt)
+                } else {
+                    ite.otherwise.resolve(builder, dt)
+                }
+            }
+            RValue::Comprehension(comp) => {
+                let iters = comp
+                    .loop_iters
+                    .iter()
+                    .map(|(id, rv)| Ok((id, rv.resolve(builder, &[])?.to::<TVec<Value>>(builder)?)))
+                    .collect::<TractResult<Vec<_>>>()?;
+                let len = iters.get(0).map(|(_, values)| values.len()).unwrap_or(0);
+                ensure!(
+                    iters.iter().all(|(_, values)| values.len() == len),
+                    "Comprehension iterates over arrays of different lengths"
+                );
+                let mut items = vec![];
+                for ix in 0..len {
+                    let mut scope = builder.scopes.last().unwrap().clone();
+                    for (id, values) in &iters {
+                        scope.insert(id.to_string(), values[ix].clone());
+                    }
+                    builder.scopes.push(scope);
+                    let item = comp.resolve_item(builder, dt);
+                    builder.scopes.pop();
+                    if let Some(item) = item? {
+                        items.push(item);
+                    }
+                }
+                Ok(Value::Array(items))
+            }
+        }
+    }
+}
+
+impl Comprehension {
+    fn resolve_item(
+        &self,
+        builder: &mut ModelBuilder,
+        dt: &[Option<DatumType>],
+    ) -> TractResult<Option<Value>> {
+        if let Some(filter) = &self.filter {
+            if !filter.resolve(builder, &[])?.to::<bool>(builder)? {
+                return Ok(None);
+            }
         }
+        self.yields.resolve(builder, &dt.get(0).map(|dt| tvec!(*dt)).unwrap_or_default()).map(Some)
     }
 }
 
@@ -504,7 +619,7 @@ impl CoerceFrom<Value> for Value {
 impl CoerceFrom<Value> for Arc<Tensor> {
     fn coerce(builder: &mut ModelBuilder, from: &Value) -> TractResult<Self> {
         match from {
-            Value::Dim(t) => Ok(rctensor0(t.to_i32()?)),
+            Value::Dim(t) => Ok(t.to_i32().map(rctensor0).unwrap_or_else(|_| rctensor0(t.clone()))),
             Value::Tensor(t) => Ok(t.clone()),
             Value::Tuple(t) if t.len() == 1 => t[0].to(builder),
             Value::Scalar(f) => Ok(rctensor0(*f)),
@@ -523,6 +638,11 @@ impl CoerceFrom<Value> for Arc<Tensor> {
                     tensor.insert_axis(0)?;
                     tensors.push(tensor);
                 }
+                if tensors.iter().any(|t| t.datum_type() == TDim::datum_type()) {
+                    for t in &mut tensors {
+                        *t = t.cast_to::<TDim>()?.into_owned();
+                    }
+                }
                 let tensor = Tensor::stack_tensors(0, &tensors)?;
                 Ok(tensor.into_arc_tensor())
             }
@@ -573,9 +693,11 @@ impl CoerceFrom<Value> for OutletId {
 }
 
 impl CoerceFrom<Value> for i64 {
-    fn coerce(_builder: &mut ModelBuilder, from: &Value) -> TractResult<Self> {
+    fn coerce(builder: &mut ModelBuilder, from: &Value) -> TractResult<Self> {
         match from {
             Value::Dim(d) => d.to_i64(),
+            Value::Tensor(t) => t.cast_to_scalar::<i64>(),
+            Value::Wire(_) => from.to::<Arc<Tensor>>(builder)?.cast_to_scalar::<i64>(),
             _ => bail!("Can not build a i64 from {:?}", from),
         }
     }
@@ -603,11 +725,12 @@ impl CoerceFrom<Value> for String {
 }
 
 impl CoerceFrom<Value> for bool {
-    fn coerce(_builder: &mut ModelBuilder, from: &Value) -> TractResult<Self> {
-        if let Value::Bool(b) = from {
-            Ok(*b)
-        } else {
-            bail!("Can not build a boolean from {:?}", from)
+    fn coerce(builder: &mut ModelBuilder, from: &Value) -> TractResult<Self> {
+        match from {
+            Value::Bool(b) => Ok(*b),
+            Value::Tensor(t) => t.cast_to_scalar::<bool>(),
+            Value::Wire(_) => from.to::<Arc<Tensor>>(builder)?.cast_to_scalar::<bool>(),
+            _ => bail!("Can not build a boolean from {:?}", from),
         }
     }
 }
@@ -42,9 +42,24 @@ impl Nnef {
         Ok(())
     }
 
+    /// Serialize the model to an in-memory tar archive.
+    pub fn write_to_bytes(&self, model: &TypedModel) -> TractResult<Vec<u8>> {
+        self.write_to_tar(model, vec![])
+    }
+
     pub fn write_to_tar<W: std::io::Write>(&self, model: &TypedModel, w: W) -> TractResult<W> {
-        let proto_model = crate::ser::to_proto_model(&self, model).context("Translating model to proto_model")?;
         let mut ar = tar::Builder::new(w);
+        self.append_to_tar(model, &mut ar)?;
+        Ok(ar.into_inner()?)
+    }
+
+    /// Append the model files to a tar archive being built.
+    pub fn append_to_tar<W: std::io::Write>(
+        &self,
+        model: &TypedModel,
+        ar: &mut tar::Builder<W>,
+    ) -> TractResult<()> {
+        let proto_model = crate::ser::to_proto_model(&self, model).context("Translating model to proto_model")?;
         let mut graph_data = vec![];
         crate::ast::dump::Dumper::new(&mut graph_data)
             .document(&proto_model.doc)
@@ -90,7 +105,45 @@ impl Nnef {
 
             ar.append_data(&mut header, &*filename, &mut &*data)?;
         }
-        Ok(ar.into_inner()?)
+        Ok(())
+    }
+
+    /// Write the model to a tar archive at `path`, compressing it according
+    /// to the file extension: gzip for `.tgz` and `.gz`, zstd for `.tzst` and
+    /// `.zst`, no compression otherwise.
+    pub fn write_to_path(
+        &self,
+        model: &TypedModel,
+        path: impl AsRef<std::path::Path>,
+    ) -> TractResult<()> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        match extension {
+            "tgz" | "gz" => {
+                #[cfg(feature = "flate2")]
+                {
+                    let encoder =
+                        flate2::write::GzEncoder::new(file, flate2::Compression::default());
+                    self.write_to_tar(model, encoder)?.finish()?.into_inner()?;
+                }
+                #[cfg(not(feature = "flate2"))]
+                bail!("Cannot write gzip file without flate2 enabled.");
+            }
+            "tzst" | "zst" => {
+                #[cfg(feature = "zstd")]
+                {
+                    let encoder = zstd::stream::write::Encoder::new(file, 0)?;
+                    self.write_to_tar(model, encoder)?.finish()?.into_inner()?;
+                }
+                #[cfg(not(feature = "zstd"))]
+                bail!("Cannot write zstd file without zstd enabled.");
+            }
+            _ => {
+                self.write_to_tar(model, file)?.into_inner()?;
+            }
+        }
+        Ok(())
     }
 
     pub fn write_to_dir(
@@ -162,11 +215,11 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
         let mut text: Option<String> = None;
         let mut tensors: Vec<(String, Arc<Tensor>)> = Default::default();
         let mut quantization = None;
-        let mut buffer = vec![0u8; 2];
+        let mut buffer = vec![0u8; 4];
         reader.read_exact(&mut buffer)?;
         let header = std::io::Cursor::new(buffer.clone());
         let stream = header.chain(reader);
-        let mut tar = if buffer == [0x1f, 0x8b] {
+        let mut tar = if buffer[0..2] == [0x1f, 0x8b] {
             #[cfg(feature = "flate2")]
             {
                 let f = flate2::read::GzDecoder::new(stream);
@@ -174,6 +227,14 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
             }
             #[cfg(not(feature = "flate2"))]
             bail!("Cannot read gzip file without flate2 enabled.");
+        } else if buffer == [0x28, 0xb5, 0x2f, 0xfd] {
+            #[cfg(feature = "zstd")]
+            {
+                let f = zstd::stream::read::Decoder::new(stream)?;
+                tar::Archive::new(Box::new(f) as Box<dyn Read>)
+            }
+            #[cfg(not(feature = "zstd"))]
+            bail!("Cannot read zstd file without zstd enabled.");
         } else {
             tar::Archive::new(Box::new(stream) as Box<dyn Read>)
         };
@@ -194,7 +255,7 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
     }
 }
 
-fn read_stream<R: std::io::Read>(
+pub(crate) fn read_stream<R: std::io::Read>(
     path: &std::path::Path,
     reader: &mut R,
     text: &mut Option<String>,
@@ -230,3 +291,22 @@ fn read_stream<R: std::io::Read>(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[2]))?;
+        let neg = model.wire_node("neg", tract_core::ops::math::neg(), &[source])?;
+        model.set_output_outlets(&neg)?;
+        let nnef = crate::nnef();
+        let bytes = nnef.write_to_bytes(&model)?;
+        let reloaded = nnef.model_for_bytes(&bytes)?;
+        let output = reloaded.into_runnable()?.run(tvec!(tensor1(&[1f32, -2.0])))?;
+        assert_eq!(*output[0], tensor1(&[-1f32, 2.0]));
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@
 extern crate log;
 
 pub mod ast;
+pub mod cache;
 pub mod deser;
 pub mod framework;
 pub mod ops;
@@ -1,20 +1,27 @@
 use crate::internal::*;
 use tract_core::ops;
 
+mod attention;
 mod broadcast;
 mod cast;
 mod downsample;
 mod gather;
+mod kv_cache;
+mod layer_norm;
 mod one_hot;
 mod qconv;
 mod qmatmul;
 mod reduce;
+mod rope;
 mod scan;
 mod scatter;
 mod source;
 
 pub fn register(registry: &mut Registry) {
     registry.register_unit_element_wise("tract_core_round_even", &ops::math::RoundHalfToEven {});
+    registry.register_unit_element_wise("tract_core_silu", &ops::nn::Silu {});
+    registry.register_unit_element_wise("tract_core_gelu", &ops::nn::Gelu {});
+    registry.register_unit_element_wise("tract_core_gelu_tanh", &ops::nn::GeluTanh {});
 
     registry.register_binary("tract_core_xor", &ops::logic::Xor {});
 
@@ -28,14 +35,18 @@ pub fn register(registry: &mut Registry) {
         &ops::math::ShiftRight,
         &ops::math::FlippedShiftRight,
     );
+    attention::register(registry);
     broadcast::register(registry);
     cast::register(registry);
     downsample::register(registry);
     gather::register(registry);
+    kv_cache::register(registry);
+    layer_norm::register(registry);
     one_hot::register(registry);
     qconv::register(registry);
     qmatmul::register(registry);
     reduce::register(registry);
+    rope::register(registry);
     scatter::register(registry);
     scan::register(registry);
     source::register(registry);
@@ -0,0 +1,52 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::ScaledDotProductAttention;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<ScaledDotProductAttention>(), attention_dump);
+    registry.register_primitive(
+        "tract_core_scaled_dot_product_attention",
+        &[
+            TypeName::Scalar.tensor().named("q"),
+            TypeName::Scalar.tensor().named("k"),
+            TypeName::Scalar.tensor().named("v"),
+            TypeName::Scalar.tensor().named("mask").default(0),
+            TypeName::Scalar.named("scale"),
+            TypeName::Logical.named("k_trans").default(false),
+        ],
+        attention_load,
+    );
+}
+
+fn attention_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<ScaledDotProductAttention>().unwrap();
+    let inputs: TVec<Arc<RValue>> = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect();
+    let mut named = vec![("scale", numeric(op.scale)), ("k_trans", logical(op.k_trans))];
+    if let Some(mask) = inputs.get(3) {
+        named.push(("mask", mask.as_ref().clone()));
+    }
+    Ok(Some(invocation("tract_core_scaled_dot_product_attention", &inputs[0..3], &named)))
+}
+
+fn attention_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let mut inputs: TVec<OutletId> = tvec!(
+        invocation.named_arg_as(builder, "q")?,
+        invocation.named_arg_as(builder, "k")?,
+        invocation.named_arg_as(builder, "v")?,
+    );
+    let mask: OutletId = invocation.named_arg_as(builder, "mask")?;
+    // an all-zero mask is the default, for attention without mask
+    let konst = builder.model.outlet_fact(mask)?.konst.clone();
+    let is_zero = |k: &Tensor| -> TractResult<bool> {
+        Ok(k.is_uniform() && k.cast_to_scalar::<f32>()? == 0.0)
+    };
+    if !konst.map(|k| is_zero(&k)).transpose()?.unwrap_or(false) {
+        inputs.push(mask);
+    }
+    let scale = invocation.named_arg_as(builder, "scale")?;
+    let k_trans = invocation.named_arg_as(builder, "k_trans")?;
+    builder.wire(ScaledDotProductAttention { scale, k_trans }, &inputs)
+}
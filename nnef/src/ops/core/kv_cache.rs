@@ -0,0 +1,47 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::array::KvCache;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<KvCache>(), kv_cache_dump);
+    registry.register_primitive(
+        "tract_core_kv_cache",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Integer.named("axis"),
+            TypeName::Integer.named("max_len"),
+            TypeName::String.named("len"),
+        ],
+        kv_cache_load,
+    );
+}
+
+fn kv_cache_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<KvCache>().unwrap();
+    let input = ast.mapping[&node.inputs[0]].clone();
+    ast.ensure_symbol(&op.len)?;
+    Ok(Some(invocation(
+        "tract_core_kv_cache",
+        &[input],
+        &[
+            ("axis", numeric(op.axis)),
+            ("max_len", numeric(op.max_len)),
+            ("len", string(op.len.as_char().to_string())),
+        ],
+    )))
+}
+
+fn kv_cache_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let axis = invocation.named_arg_as(builder, "axis")?;
+    let max_len = invocation.named_arg_as(builder, "max_len")?;
+    let len: String = invocation.named_arg_as(builder, "len")?;
+    if len.chars().count() != 1 {
+        bail!("KV cache length must be a one letter symbol, found {}", len);
+    }
+    let len = Symbol::from(len.chars().next().unwrap());
+    builder.wire(KvCache { axis, max_len, len }, &[input])
+}
@@ -40,7 +40,7 @@ fn qconv_unary_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<A
     let mut named_args = make_conv_named_args(node, &op.pool_spec, op.group, false, None)?;
 
     let [a0, a_scale, b0, b_scale, c0, c_scale] =
-        qparams_to_rvalues(&op.q_params.as_ref().unwrap().1, &node.inputs, &ast.mapping)?;
+        qparams_to_rvalues(ast, node, &op.q_params.as_ref().unwrap().1)?;
     macro_rules! push {
         ($a: ident) => {
             if let Some($a) = $a {
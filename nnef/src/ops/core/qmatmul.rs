@@ -38,18 +38,25 @@ fn qmatmul_parameters() -> Vec<Parameter> {
     ]
 }
 
+/// Translates quantization parameters to rvalues.
+///
+/// Per-channel parameters (non-uniform tensors) are dumped as variables, so that
+/// they survive a round-trip.
 pub fn qparams_to_rvalues(
+    ast: &mut IntoAst,
+    node: &TypedNode,
     params: &MatMulQParams,
-    node_inputs: &[OutletId],
-    ast_mapping: &HashMap<OutletId, Arc<RValue>>,
 ) -> TractResult<[Option<RValue>; 6]> {
     macro_rules! attr_to_rvalue {
         ($a:ident, $typ:ty) => {
             match &params.$a {
+                QParamKind::Attr(t) if t.is_uniform() => Some(numeric(t.cast_to_scalar::<$typ>()?)),
                 QParamKind::Attr(t) => {
-                    Some(numeric(t.cast_to_dt(<$typ>::datum_type())?.to_scalar::<$typ>()?))
+                    let t = t.cast_to_dt(<$typ>::datum_type())?.into_owned().into_arc_tensor();
+                    let name = format!("{}.{}", node.name, stringify!($a));
+                    Some(ast.konst_variable(name, &t)?.as_ref().clone())
                 }
-                QParamKind::FromInput(i) => Some((*ast_mapping[&node_inputs[*i]]).clone()),
+                QParamKind::FromInput(i) => Some((*ast.mapping[&node.inputs[*i]]).clone()),
                 QParamKind::FromQType => None,
             }
         };
@@ -74,8 +81,7 @@ fn qmatmul_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<R
     let b = ast.mapping[&node.inputs[1]].clone();
     let bias = ast.mapping[&node.inputs[2]].clone();
 
-    let [a0, a_scale, b0, b_scale, c0, c_scale] =
-        qparams_to_rvalues(&op.params, &node.inputs, &ast.mapping)?;
+    let [a0, a_scale, b0, b_scale, c0, c_scale] = qparams_to_rvalues(ast, node, &op.params)?;
     let mut named_args = vec![
         ("A", (*a).clone()),
         ("B", (*b).clone()),
@@ -106,8 +112,7 @@ fn qmatmul_unary_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option
     let a = ast.konst_variable(format!("{}.a", node.name), &op.a)?;
     let b = ast.mapping[&node.inputs[0]].clone();
 
-    let [a0, a_scale, b0, b_scale, c0, c_scale] =
-        qparams_to_rvalues(&op.params, &node.inputs, &ast.mapping)?;
+    let [a0, a_scale, b0, b_scale, c0, c_scale] = qparams_to_rvalues(ast, node, &op.params)?;
 
     let mut named_args = vec![
         ("A", (*a).clone()),
@@ -197,3 +202,43 @@ fn qmatmul_load(
     let params = values_to_qparams(a0, a_scale, b0, b_scale, c0, c_scale, &mut inputs, builder)?;
     builder.wire(QMatMul { a_trans, b_trans, c_trans, output_type, params }, &inputs)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn per_channel_round_trip() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let b = model.add_source("b", i8::fact(&[3, 2]))?;
+        let op = QMatMulUnary {
+            a: tensor2(&[[1i8, 2, 3], [4, 5, 6]]).into_arc_tensor(),
+            bias: Some(tensor2(&[[1i32], [2]]).into_arc_tensor()),
+            a_trans: false,
+            b_trans: false,
+            c_trans: false,
+            output_type: i8::datum_type(),
+            params: MatMulQParams {
+                a0: QParamKind::Attr(rctensor0(0i32)),
+                a_scale: QParamKind::Attr(tensor2(&[[0.5f32], [0.25]]).into_arc_tensor()),
+                b0: QParamKind::Attr(rctensor0(0i32)),
+                b_scale: QParamKind::Attr(rctensor0(1f32)),
+                c0: QParamKind::Attr(rctensor0(0i32)),
+                c_scale: QParamKind::Attr(rctensor0(1f32)),
+            },
+        };
+        let c = model.wire_node("qmm", op, &[b])?;
+        model.set_output_outlets(&c)?;
+
+        let nnef = crate::nnef().with_tract_core();
+        let mut buffer = vec![];
+        nnef.write(&model, &mut buffer)?;
+        let reloaded = nnef.model_for_read(&mut &*buffer)?;
+
+        let input = tensor2(&[[1i8, 0], [0, 1], [1, 1]]);
+        let expected = model.into_runnable()?.run(tvec!(input.clone()))?;
+        let found = reloaded.into_runnable()?.run(tvec!(input))?;
+        assert_eq!(expected[0], found[0]);
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::internal::*;
+use crate::ser::*;
+use tract_core::ops::nn::{RopeLayout, RopeRotation, RotaryEmbedding};
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(TypeId::of::<RotaryEmbedding>(), rope_dump);
+    registry.register_primitive(
+        "tract_core_rotary_embedding",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::Scalar.tensor().named("cos"),
+            TypeName::Scalar.tensor().named("sin"),
+            TypeName::String.named("rotation").default("full"),
+            TypeName::String.named("layout").default("halves"),
+        ],
+        rope_load,
+    );
+}
+
+fn rope_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
+    let op = node.op_as::<RotaryEmbedding>().unwrap();
+    let inputs: TVec<Arc<RValue>> = node.inputs.iter().map(|i| ast.mapping[i].clone()).collect();
+    let rotation = match op.rotation {
+        RopeRotation::Full => "full",
+        RopeRotation::Half => "half",
+        RopeRotation::Quarter => "quarter",
+    };
+    let layout = match op.layout {
+        RopeLayout::Halves => "halves",
+        RopeLayout::Interleaved => "interleaved",
+    };
+    Ok(Some(invocation(
+        "tract_core_rotary_embedding",
+        &inputs,
+        &[("rotation", string(rotation)), ("layout", string(layout))],
+    )))
+}
+
+fn rope_load(
+    builder: &mut ModelBuilder,
+    invocation: &ResolvedInvocation,
+) -> TractResult<TVec<OutletId>> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let cos = invocation.named_arg_as(builder, "cos")?;
+    let sin = invocation.named_arg_as(builder, "sin")?;
+    let rotation: String = invocation.named_arg_as(builder, "rotation")?;
+    let rotation = match &*rotation {
+        "full" => RopeRotation::Full,
+        "half" => RopeRotation::Half,
+        "quarter" => RopeRotation::Quarter,
+        s => bail!("Unsupported rotary embedding rotation {}", s),
+    };
+    let layout: String = invocation.named_arg_as(builder, "layout")?;
+    let layout = match &*layout {
+        "halves" => RopeLayout::Halves,
+        "interleaved" => RopeLayout::Interleaved,
+        s => bail!("Unsupported rotary embedding layout {}", s),
+    };
+    builder.wire(RotaryEmbedding { rotation, layout }, &[input, cos, sin])
+}
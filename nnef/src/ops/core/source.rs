@@ -7,42 +7,9 @@ pub fn register(registry: &mut Registry) {
     registry.register_primitive("tract_core_external", &external_parameters(), external_load);
 }
 
-fn ser_tdim(dim: &TDim) -> TractResult<RValue> {
-    Ok(match dim {
-        TDim::Val(x) => numeric(x),
-        TDim::Sym(s) => ident(format!("{}", s.as_char())),
-        TDim::Add(terms) => {
-            let terms = terms.iter().map(|x| ser_tdim(x)).collect::<TractResult<Vec<_>>>()?;
-            terms
-                .into_iter()
-                .reduce(|x, y| RValue::Binary(x.boxed(), "+".to_string(), y.boxed()))
-                .unwrap()
-        }
-        TDim::Mul(terms) => {
-            let terms = terms.iter().map(|x| ser_tdim(x)).collect::<TractResult<Vec<_>>>()?;
-            terms
-                .into_iter()
-                .reduce(|x, y| RValue::Binary(x.boxed(), "*".to_string(), y.boxed()))
-                .unwrap()
-        }
-        TDim::MulInt(x, y) => {
-            RValue::Binary(numeric(x).boxed(), "*".to_string(), ser_tdim(y)?.boxed())
-        }
-        TDim::Div(x, y) => {
-            RValue::Binary(ser_tdim(&x)?.boxed(), "/".to_string(), numeric(y).boxed())
-        }
-    })
-}
-
 fn external_dump(ast: &mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>> {
     let op = node.op_as::<TypedSource>().unwrap();
-    for dim in op.fact.shape.iter() {
-        for sym in dim.symbols() {
-            ast.ensure_symbol(&sym)?;
-        }
-    }
-    let shape =
-        RValue::Array(op.fact.shape.iter().map(|d| ser_tdim(&d)).collect::<TractResult<Vec<_>>>()?);
+    let shape = ast.dims(&op.fact.shape)?;
     Ok(Some(invocation(
         "tract_core_external",
         &[],
@@ -146,22 +146,16 @@ pub fn slice(
     let wire = tvec!(invocation.named_arg_as(builder, "input")?);
     let input_fact = builder.model.outlet_fact(wire[0])?.clone();
     let axes: TVec<usize> = invocation.named_arg_as(builder, "axes")?;
-    let begins: TVec<i64> = invocation.named_arg_as(builder, "begin")?;
-    let begins = begins.into_iter().enumerate().map(|(ix, b)| -> TDim {
-        if b < 0 {
-            input_fact.shape[ix].clone() + b
-        } else {
-            b.into()
-        }
-    });
-    let ends: TVec<i64> = invocation.named_arg_as(builder, "end")?;
-    let ends = ends.into_iter().enumerate().map(|(ix, b)| -> TDim {
-        if b < 0 {
-            input_fact.shape[ix].clone() + b
-        } else {
-            b.into()
+    let from_end = |axis: usize, b: TDim| -> TDim {
+        match b.to_i64() {
+            Ok(b) if b < 0 => input_fact.shape[axis].clone() + b,
+            _ => b,
         }
-    });
+    };
+    let begins: TVec<TDim> = invocation.named_arg_as(builder, "begin")?;
+    let begins = axes.iter().zip(begins).map(|(axis, b)| from_end(*axis, b)).collect::<TVec<_>>();
+    let ends: TVec<TDim> = invocation.named_arg_as(builder, "end")?;
+    let ends = axes.iter().zip(ends).map(|(axis, e)| from_end(*axis, e)).collect::<TVec<_>>();
     izip!(axes, begins, ends).try_fold(wire, |wire, (axis, start, end)| {
         builder.wire(tract_core::ops::array::Slice { axis, start, end }, &wire)
     })
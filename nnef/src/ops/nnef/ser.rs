@@ -54,12 +54,12 @@ pub fn slice(
     op: &ops::array::Slice,
 ) -> TractResult<Option<Arc<RValue>>> {
     let wire = ast.mapping[&node.inputs[0]].clone();
-    let start = op.start.to_usize()?;
-    let end = op.end.to_usize()?;
+    let start = ast.dims(&[op.start.clone()])?;
+    let end = ast.dims(&[op.end.clone()])?;
     Ok(Some(invocation(
         "slice",
         &[wire],
-        &[("axes", ints(&[op.axis])), ("begin", ints(&[start])), ("end", ints(&[end]))],
+        &[("axes", ints(&[op.axis])), ("begin", start), ("end", end)],
     )))
 }
 
@@ -390,7 +390,7 @@ pub fn axis_op(
             "reshape",
             &[wire],
             &[
-                ("shape", ints(&*to.iter().map(|d| d.to_usize().unwrap()).collect::<Vec<_>>())),
+                ("shape", ast.dims(to)?),
                 ("axis_start", numeric(start)),
                 ("axis_count", numeric(from.len())),
             ],
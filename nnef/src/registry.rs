@@ -10,16 +10,21 @@ use tract_core::ops::binary::*;
 
 pub type ToTract = fn(&mut ModelBuilder, &ResolvedInvocation) -> TractResult<TVec<OutletId>>;
 pub type FromTract = fn(&mut IntoAst, node: &TypedNode) -> TractResult<Option<Arc<RValue>>>;
+pub type BoxedToTract = Box<
+    dyn Fn(&mut ModelBuilder, &ResolvedInvocation) -> TractResult<TVec<OutletId>> + Send + Sync,
+>;
+pub type BoxedFromTract =
+    Box<dyn Fn(&mut IntoAst, &TypedNode) -> TractResult<Option<Arc<RValue>>> + Send + Sync>;
 
 pub struct Registry {
     pub id: String,
     pub aliases: Vec<String>,
     pub fragments: HashMap<String, FragmentDef>,
-    pub primitives: HashMap<String, (Vec<ast::Parameter>, ToTract)>,
+    pub primitives: HashMap<String, (Vec<ast::Parameter>, BoxedToTract)>,
     pub unit_element_wise_ops: Vec<(String, Box<dyn ElementWiseMiniOp>)>,
     pub element_wise_ops: Vec<(String, TypeId, FromTract, Vec<ast::Parameter>, ToTract)>,
     pub binary_ops: Vec<(String, Box<dyn BinMiniOp>, Option<Box<dyn BinMiniOp>>)>,
-    pub from_tract: HashMap<TypeId, FromTract>,
+    pub from_tract: HashMap<TypeId, BoxedFromTract>,
     pub extensions:
         Vec<Box<dyn Fn(&mut crate::deser::ModelBuilder, &[String]) -> TractResult<ControlFlow<(), ()>>>>,
 }
@@ -39,12 +44,53 @@ impl Registry {
         }
     }
 
-    pub fn register_dumper(&mut self, id: TypeId, func: FromTract) {
-        self.from_tract.insert(id, func);
+    pub fn register_dumper(
+        &mut self,
+        id: TypeId,
+        func: impl Fn(&mut IntoAst, &TypedNode) -> TractResult<Option<Arc<RValue>>>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.from_tract.insert(id, Box::new(func));
+    }
+
+    pub fn register_primitive(
+        &mut self,
+        id: &str,
+        decl: &[ast::Parameter],
+        func: impl Fn(&mut ModelBuilder, &ResolvedInvocation) -> TractResult<TVec<OutletId>>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.primitives.insert(id.to_string(), (decl.to_vec(), Box::new(func)));
     }
 
-    pub fn register_primitive(&mut self, id: &str, decl: &[ast::Parameter], func: ToTract) {
-        self.primitives.insert(id.to_string(), (decl.to_vec(), func));
+    /// Register a custom operator, so that models using it can be dumped to
+    /// and loaded from NNEF.
+    ///
+    /// `dumper` is called on the nodes wrapping an `O` and translates them to
+    /// an invocation of `id`, while `loader` wires back the invocations of `id`
+    /// in a model being loaded. `parameters` is the declaration of `id`
+    /// (see `parse_parameters`), used to resolve default values.
+    pub fn register_op<O: TypedOp>(
+        &mut self,
+        id: &str,
+        parameters: &[ast::Parameter],
+        dumper: impl Fn(&mut IntoAst, &TypedNode, &O) -> TractResult<Option<Arc<RValue>>>
+            + Send
+            + Sync
+            + 'static,
+        loader: impl Fn(&mut ModelBuilder, &ResolvedInvocation) -> TractResult<TVec<OutletId>>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.register_dumper(TypeId::of::<O>(), move |ast, node| {
+            dumper(ast, node, node.op_as::<O>().unwrap())
+        });
+        self.register_primitive(id, parameters, loader);
     }
 
     pub fn register_fragment(&mut self, def: FragmentDef) {
@@ -77,6 +77,18 @@ impl<'a> IntoAst<'a> {
         Ok(())
     }
 
+    /// Serialize a dimension, declaring the symbols it uses.
+    pub fn dim(&mut self, dim: &TDim) -> TractResult<RValue> {
+        for sym in dim.symbols() {
+            self.ensure_symbol(&sym)?;
+        }
+        Ok(tdim(dim))
+    }
+
+    pub fn dims(&mut self, dims: &[TDim]) -> TractResult<RValue> {
+        Ok(RValue::Array(dims.iter().map(|d| self.dim(d)).collect::<TractResult<_>>()?))
+    }
+
     fn extract_prefix(model: &TypedModel) -> Option<String> {
         let names = model
             .nodes()
@@ -268,7 +280,11 @@ impl<'a> IntoAst<'a> {
             }
         }
         if required_registries.is_empty() {
-            bail!("No serializer found for node {}", node);
+            bail!(
+                "No serializer found for node {}, consider registering one for {} (see Registry::register_op)",
+                node,
+                node.op().name()
+                );
         } else if required_registries.len() == 1 {
             bail!(
                 "Registry {} required, consider allowing it on the NNEF framework.",
@@ -345,6 +361,14 @@ impl<'a> IntoAst<'a> {
         tensor: &Arc<Tensor>,
         force_variable: bool,
         ) -> TractResult<Arc<RValue>> {
+        if tensor.datum_type() == TDim::datum_type() && tensor.rank() <= 1 {
+            let dims = tensor.as_slice::<TDim>()?;
+            if dims.iter().any(|d| d.to_i64().is_err()) {
+                let dims = dims.iter().map(|d| self.dim(d)).collect::<TractResult<Vec<_>>>()?;
+                let rv = if tensor.rank() == 0 { dims[0].clone() } else { RValue::Array(dims) };
+                return Ok(rv.into());
+            }
+        }
         if !force_variable && tensor.is_uniform() && tensor.len() > 0 {
             if tensor.datum_type() == String::datum_type() {
                 return Ok(string(tensor.to_scalar::<String>().unwrap()).into());
@@ -396,6 +420,35 @@ pub fn ints(shape: &[usize]) -> RValue {
     RValue::Array(shape.iter().map(|s| RValue::Literal(Literal::Numeric(s.to_string()))).collect())
 }
 
+pub fn tdim(dim: &TDim) -> RValue {
+    match dim {
+        TDim::Val(x) => numeric(x),
+        TDim::Sym(s) => ident(format!("{}", s.as_char())),
+        TDim::Add(terms) => terms
+            .iter()
+            .map(tdim)
+            .reduce(|x, y| RValue::Binary(x.boxed(), "+".to_string(), y.boxed()))
+            .unwrap(),
+        TDim::Mul(terms) => terms
+            .iter()
+            .map(tdim)
+            .reduce(|x, y| RValue::Binary(x.boxed(), "*".to_string(), y.boxed()))
+            .unwrap(),
+        TDim::MulInt(x, y) => RValue::Binary(numeric(x).boxed(), "*".to_string(), tdim(y).boxed()),
+        TDim::Div(x, y) => RValue::Binary(tdim(x).boxed(), "/".to_string(), numeric(y).boxed()),
+        TDim::Min(terms) => terms
+            .iter()
+            .map(tdim)
+            .reduce(|x, y| invocation("min", &[x.into(), y.into()], &[]).as_ref().clone())
+            .unwrap(),
+        TDim::Max(terms) => terms
+            .iter()
+            .map(tdim)
+            .reduce(|x, y| invocation("max", &[x.into(), y.into()], &[]).as_ref().clone())
+            .unwrap(),
+    }
+}
+
 pub fn string(s: impl Into<String>) -> RValue {
     RValue::Literal(Literal::String(s.into()))
 }
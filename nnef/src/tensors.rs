@@ -62,6 +62,9 @@ pub fn read_tensor<R: std::io::Read>(mut reader: R) -> TractResult<Tensor> {
             (0, 4, 32) => DatumType::I32,
             (0, 4, 64) => DatumType::I64,
             (TRACT_ITEM_TYPE_VENDOR, 0x1000, 0xFFFF) => DatumType::String,
+            (TRACT_ITEM_TYPE_VENDOR, 0x1001, 16) => DatumType::BF16,
+            (TRACT_ITEM_TYPE_VENDOR, 0x1002, 8) => DatumType::F8E4M3,
+            (TRACT_ITEM_TYPE_VENDOR, 0x1003, 8) => DatumType::F8E5M2,
             _ => bail!(
                 "Unsupported type in tensor type:{} bits_per_item:{}",
                 header.item_type,
@@ -108,7 +111,16 @@ pub fn write_tensor<W: std::io::Write>(w: &mut W, tensor: &Tensor) -> TractResul
         }
         header.data_size_bytes = (tensor.len() * tensor.datum_type().size_of()) as u32;
         header.bits_per_item = (tensor.datum_type().size_of() * 8) as u32;
-        header.item_type = if tensor.datum_type().is_float() {
+        header.item_type = if tensor.datum_type() == DatumType::BF16 {
+            header.item_type_vendor = TRACT_ITEM_TYPE_VENDOR;
+            0x1001
+        } else if tensor.datum_type() == DatumType::F8E4M3 {
+            header.item_type_vendor = TRACT_ITEM_TYPE_VENDOR;
+            0x1002
+        } else if tensor.datum_type() == DatumType::F8E5M2 {
+            header.item_type_vendor = TRACT_ITEM_TYPE_VENDOR;
+            0x1003
+        } else if tensor.datum_type().is_float() {
             0
         } else if tensor.datum_type().is_signed() {
             4
@@ -143,4 +155,24 @@ mod test {
     fn header_is_128_bytes() {
         assert_eq!(std::mem::size_of::<Header>(), 128);
     }
+
+    #[test]
+    fn bf16_round_trip() -> TractResult<()> {
+        let tensor = tensor1(&[1f32, -2.5, 0.125]).cast_to::<bf16>()?.into_owned();
+        let mut buffer = vec![];
+        write_tensor(&mut buffer, &tensor)?;
+        assert_eq!(read_tensor(&*buffer)?, tensor);
+        Ok(())
+    }
+
+    #[test]
+    fn fp8_round_trip() -> TractResult<()> {
+        let floats = tensor1(&[1f32, -2.5, 0.125]);
+        for tensor in [floats.cast_to::<f8e4m3>()?, floats.cast_to::<f8e5m2>()?] {
+            let mut buffer = vec![];
+            write_tensor(&mut buffer, &tensor)?;
+            assert_eq!(read_tensor(&*buffer)?, *tensor);
+        }
+        Ok(())
+    }
 }
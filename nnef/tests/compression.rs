@@ -0,0 +1,43 @@
+use tract_nnef::internal::*;
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let source = model.add_source("input", f32::fact(&[3]))?;
+    let add = model.wire_node(
+        "add",
+        tract_core::ops::math::add::unary(rctensor1(&[1f32, 2.0, 3.0])),
+        &[source],
+    )?;
+    model.set_output_outlets(&add)?;
+    Ok(model)
+}
+
+fn round_trip(filename: &str) -> TractResult<()> {
+    let dir = std::env::temp_dir().join(format!("tract-nnef-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(filename);
+    let nnef = tract_nnef::nnef();
+    nnef.write_to_path(&model()?, &path)?;
+    let reloaded = nnef.model_for_path(&path)?;
+    std::fs::remove_file(&path)?;
+    let output = reloaded.into_runnable()?.run(tvec!(tensor1(&[1f32, 1.0, 1.0])))?;
+    assert_eq!(*output[0], tensor1(&[2f32, 3.0, 4.0]));
+    Ok(())
+}
+
+#[test]
+fn tar() -> TractResult<()> {
+    round_trip("model.nnef.tar")
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn tgz() -> TractResult<()> {
+    round_trip("model.nnef.tgz")
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd() -> TractResult<()> {
+    round_trip("model.nnef.tar.zst")
+}
@@ -0,0 +1,86 @@
+use tract_nnef::internal::*;
+
+#[derive(Debug, Clone, Hash)]
+struct Shift {
+    amount: i64,
+}
+
+impl_dyn_hash!(Shift);
+
+impl Op for Shift {
+    fn name(&self) -> Cow<str> {
+        "Shift".into()
+    }
+
+    fn op_families(&self) -> &'static [&'static str] {
+        &["acme"]
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Shift {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let input = args_1!(inputs);
+        let output = input.to_array_view::<i64>()?.mapv(|x| x + self.amount);
+        Ok(tvec!(output.into_arc_tensor()))
+    }
+}
+
+impl TypedOp for Shift {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    as_op!();
+}
+
+fn registry() -> Registry {
+    let mut registry = Registry::new("acme");
+    registry.register_op::<Shift>(
+        "acme_shift",
+        &parse_parameters("input: tensor<integer>, amount: integer = 1").unwrap(),
+        |ast, node, op| {
+            let input = ast.mapping[&node.inputs[0]].clone();
+            Ok(Some(invocation("acme_shift", &[input], &[("amount", numeric(op.amount))])))
+        },
+        |builder, invocation| {
+            let input = invocation.named_arg_as(builder, "input")?;
+            let amount = invocation.named_arg_as(builder, "amount")?;
+            builder.wire(Shift { amount }, &[input])
+        },
+    );
+    registry
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let source = model.add_source("input", i64::fact(&[3]))?;
+    let shift = model.wire_node("shift", Shift { amount: 3 }, &[source])?;
+    model.set_output_outlets(&shift)?;
+    Ok(model)
+}
+
+#[test]
+fn custom_op_requires_registry() -> TractResult<()> {
+    let mut buffer = vec![];
+    assert!(tract_nnef::nnef().with_tract_core().write(&model()?, &mut buffer).is_err());
+    Ok(())
+}
+
+#[test]
+fn custom_op_round_trip() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_tract_core().with_registry(registry());
+    let mut buffer = vec![];
+    nnef.write(&model()?, &mut buffer)?;
+    let reloaded = nnef.model_for_read(&mut &*buffer)?;
+    let shift = reloaded.node(reloaded.output_outlets()?[0].node).op_as::<Shift>().unwrap();
+    assert_eq!(shift.amount, 3);
+    let output = reloaded.into_runnable()?.run(tvec!(tensor1(&[1i64, 2, 3])))?;
+    assert_eq!(*output[0], tensor1(&[4i64, 5, 6]));
+    Ok(())
+}
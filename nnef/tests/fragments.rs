@@ -0,0 +1,110 @@
+use tract_nnef::ast::ProtoModel;
+use tract_nnef::internal::*;
+
+fn load(graph: &str) -> TractResult<TypedModel> {
+    let doc = tract_nnef::ast::parse::parse_document(graph)?;
+    let proto = ProtoModel { doc, tensors: vec![], quantization: None };
+    proto.validate()?;
+    tract_nnef::nnef().with_tract_core().model_for_proto_model(&proto)
+}
+
+fn run(model: TypedModel, input: Tensor) -> TractResult<Arc<Tensor>> {
+    Ok(model.into_runnable()?.run(tvec!(input))?.remove(0))
+}
+
+#[test]
+fn simple_fragment() -> TractResult<()> {
+    let model = load(
+        "version 1.0;
+
+        fragment shifted_relu( input: tensor<scalar>, shift: scalar = 1.0 ) -> ( output: tensor<scalar> )
+        {
+            output = max(input - shift, 0.0);
+        }
+
+        graph g( input ) -> ( output )
+        {
+            input = external<scalar>(shape = [3]);
+            output = shifted_relu(input, shift = 2.0);
+        }",
+    )?;
+    let output = run(model, tensor1(&[1f32, 2.0, 3.0]))?;
+    assert_eq!(*output, tensor1(&[0f32, 0.0, 1.0]));
+    Ok(())
+}
+
+#[test]
+fn fragment_with_expressions() -> TractResult<()> {
+    let model = load(
+        "version 1.0;
+        extension KHR_enable_fragment_definitions, KHR_enable_operator_expressions;
+
+        fragment scaled<? = scalar>(
+            input: tensor<?>,
+            scales: scalar[] = [1.0, 2.0, 3.0],
+            pick: integer = 1,
+            negate: logical = false
+        ) -> ( output: tensor<?>, other: tensor<?> )
+        {
+            s = scales[pick];
+            t = -s if negate else s;
+            output = input * t;
+            other = -input;
+        }
+
+        graph g( input ) -> ( output, other )
+        {
+            input = external<scalar>(shape = [3]);
+            output, other = scaled(input, negate = true);
+        }",
+    )?;
+    let outputs = model.into_runnable()?.run(tvec!(tensor1(&[1f32, 2.0, 3.0])))?;
+    assert_eq!(*outputs[0], tensor1(&[-2f32, -4.0, -6.0]));
+    assert_eq!(*outputs[1], tensor1(&[-1f32, -2.0, -3.0]));
+    Ok(())
+}
+
+#[test]
+fn fragment_with_comprehension() -> TractResult<()> {
+    let model = load(
+        "version 1.0;
+
+        fragment sum_of( input: tensor<scalar>, factors: scalar[] ) -> ( output: tensor<scalar> )
+        {
+            scaled = [for f in factors if f > 0.0 yield input * f];
+            picked = scaled[1:3];
+            output = picked[0] + picked[1];
+        }
+
+        graph g( input ) -> ( output )
+        {
+            input = external<scalar>(shape = [2]);
+            output = sum_of(input, factors = [1.0, -1.0, 2.0, 4.0]);
+        }",
+    )?;
+    let output = run(model, tensor1(&[1f32, 2.0]))?;
+    assert_eq!(*output, tensor1(&[6f32, 12.0]));
+    Ok(())
+}
+
+#[test]
+fn pass_through_fragment() -> TractResult<()> {
+    let model = load(
+        "version 1.0;
+
+        fragment identity( input: tensor<scalar> ) -> ( output: tensor<scalar> )
+        {
+            output = input;
+        }
+
+        graph g( input ) -> ( output )
+        {
+            input = external<scalar>(shape = [2]);
+            output = identity(input);
+        }",
+    )?;
+    assert_eq!(model.node(model.input_outlets()?[0].node).name, "input");
+    let output = run(model, tensor1(&[1f32, 2.0]))?;
+    assert_eq!(*output, tensor1(&[1f32, 2.0]));
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use tract_core::ops::array::{MultiBroadcastTo, Slice};
+use tract_nnef::internal::*;
+
+fn round_trip(model: &TypedModel) -> TractResult<TypedModel> {
+    let nnef = tract_nnef::nnef().with_tract_core();
+    let mut buffer = vec![];
+    nnef.write(model, &mut buffer)?;
+    nnef.model_for_read(&mut &*buffer)
+}
+
+#[test]
+fn symbolic_shapes() -> TractResult<()> {
+    let s = Symbol::from('S');
+    let mut model = TypedModel::default();
+    let source = model.add_source("input", f32::fact(&[s.to_dim(), 3.to_dim()]))?;
+    let slice = model.wire_node("slice", Slice::new(0, 1, s.to_dim()), &[source])?;
+    let reshape = model.wire_node(
+        "reshape",
+        AxisOp::Reshape(0, tvec!(s.to_dim() - 1, 3.to_dim()), tvec!((s.to_dim() - 1) * 3)),
+        &slice,
+    )?;
+    model.set_output_outlets(&reshape)?;
+    let reloaded = round_trip(&model)?;
+    assert_eq!(reloaded.input_fact(0)?, model.input_fact(0)?);
+    assert_eq!(
+        reloaded.output_fact(0)?.shape[0].clone().simplify(),
+        ((s.to_dim() - 1) * 3).simplify()
+    );
+    let input = Tensor::from_shape(&[3, 3], &(0..9).map(|i| i as f32).collect::<Vec<_>>())?;
+    let concrete = reloaded.concretize_dims(&SymbolValues::default().with(s, 3))?;
+    let output = concrete.into_runnable()?.run(tvec!(input))?;
+    assert_eq!(*output[0], tensor1(&[3f32, 4., 5., 6., 7., 8.]));
+    Ok(())
+}
+
+#[test]
+fn symbolic_broadcast() -> TractResult<()> {
+    let s = Symbol::from('S');
+    let mut model = TypedModel::default();
+    let source = model.add_source("input", f32::fact(&[s.to_dim(), 1.to_dim()]))?;
+    let shape = tvec!(s.to_dim(), 2.to_dim());
+    let bc = model.wire_node("bc", MultiBroadcastTo { shape: shape.into() }, &[source])?;
+    model.set_output_outlets(&bc)?;
+    let reloaded = round_trip(&model)?;
+    assert_eq!(reloaded.output_fact(0)?.shape, model.output_fact(0)?.shape);
+    Ok(())
+}
+
+#[test]
+fn symbolic_constant() -> TractResult<()> {
+    let s = Symbol::from('S');
+    let mut model = TypedModel::default();
+    let source = model.add_source("input", f32::fact(&[s.to_dim()]))?;
+    let konst = model.add_const("shape", tensor1(&[s.to_dim() * 2, 3.to_dim()]))?;
+    model.set_output_outlets(&[source, konst])?;
+    let reloaded = round_trip(&model)?;
+    assert_eq!(
+        reloaded.output_fact(1)?.konst.as_deref(),
+        Some(&tensor1(&[s.to_dim() * 2, 3.to_dim()]))
+    );
+    Ok(())
+}
@@ -5,6 +5,7 @@ fn main() {
     let _ = fs::create_dir_all(&workdir);
     prost_build::Config::new()
         .out_dir(workdir)
+        .bytes([".onnx.TensorProto.raw_data"])
         .compile_protos(&["protos/onnx/onnx.proto3"], &["protos/"])
         .unwrap();
 }
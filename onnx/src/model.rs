@@ -57,8 +57,15 @@ impl<'a> ParsingContext<'a> {
         let mut initializers: HashMap<&str, Tensor> = graph
             .initializer
             .iter()
-            .map(|init| Ok((&*init.name, init.try_into()?)))
+            .map(|init| Ok((&*init.name, self.framework.load_initializer(init)?)))
             .collect::<TractResult<_>>()?;
+        for sparse in graph.sparse_initializer.iter() {
+            let name = &*sparse.values.as_ref().context("Sparse initializer without values")?.name;
+            let tensor = sparse
+                .try_into()
+                .with_context(|| format!("Densifying sparse initializer {}", name))?;
+            initializers.insert(name, tensor);
+        }
         for (k, v) in initializers.iter() {
             trace!("Initializer: {} {:?}", k, v);
         }
@@ -109,6 +116,9 @@ impl<'a> ParsingContext<'a> {
                 Some(builder) => (builder)(&ctx, pbnode).with_context(|| {
                     format!("Building node {} ({})", pbnode.name, pbnode.op_type)
                 })?,
+                None if self.framework.opaque_unknown_ops => {
+                    (opaque_op(pbnode).with_context(|| format!("Building node {}", name))?, vec![])
+                }
                 None => (
                     tract_hir::ops::unimpl::UnimplementedOp::new(
                         pbnode.output.len(),
@@ -150,6 +160,22 @@ impl<'a> ParsingContext<'a> {
             let ix = model.nodes()[id].inputs.len();
             model.add_edge(outlet, InletId::new(id, ix))?;
         }
+        if self.framework.opaque_unknown_ops {
+            for info in graph.value_info.iter() {
+                let outlet = match outlets_by_name.get(&*info.name) {
+                    Some(outlet) => *outlet,
+                    None => continue,
+                };
+                if !model.node(outlet.node).op_is::<tract_hir::ops::opaque::InferenceOpaqueOp>() {
+                    continue;
+                }
+                if let Some(pb::type_proto::Value::TensorType(f)) =
+                    info.r#type.as_ref().and_then(|t| t.value.as_ref())
+                {
+                    model.set_outlet_fact(outlet, f.try_into()?)?;
+                }
+            }
+        }
         let mut outputs = vec![];
         for output in graph.output.iter() {
             let mut fact = InferenceFact::default();
@@ -170,6 +196,36 @@ impl<'a> ParsingContext<'a> {
     }
 }
 
+fn opaque_op(pbnode: &pb::NodeProto) -> TractResult<Box<dyn InferenceOp>> {
+    use pb::attribute_proto::AttributeType;
+    let mut attributes = vec![];
+    for attr in &pbnode.attribute {
+        let string = |s: &[u8]| String::from_utf8_lossy(s).into_owned();
+        let value = match AttributeType::from_i32(attr.r#type) {
+            Some(AttributeType::Float) => tensor0(attr.f),
+            Some(AttributeType::Int) => tensor0(attr.i),
+            Some(AttributeType::String) => tensor0(string(&attr.s)),
+            Some(AttributeType::Tensor) => {
+                attr.t.as_ref().context("Tensor attribute without tensor")?.try_into()?
+            }
+            Some(AttributeType::Floats) => tensor1(&attr.floats),
+            Some(AttributeType::Ints) => tensor1(&attr.ints),
+            Some(AttributeType::Strings) => {
+                tensor1(&attr.strings.iter().map(|s| string(s)).collect::<Vec<_>>())
+            }
+            _ => {
+                bail!("Unsupported attribute {} for opaque operator {}", attr.name, pbnode.op_type)
+            }
+        };
+        attributes.push((attr.name.clone(), value.into_arc_tensor()));
+    }
+    Ok(Box::new(tract_hir::ops::opaque::InferenceOpaqueOp {
+        kind: pbnode.op_type.clone(),
+        attributes,
+        outputs: pbnode.output.iter().filter(|s| !s.is_empty()).count(),
+    }))
+}
+
 #[derive(Clone, Default)]
 pub struct OnnxOpRegister(
     pub  HashMap<
@@ -198,6 +254,9 @@ impl OnnxOpRegister {
 pub struct Onnx {
     pub op_register: OnnxOpRegister,
     pub ignore_output_shapes: bool,
+    /// Minimum size in bytes of the initializers sharing the model buffer.
+    pub shared_initializers: Option<usize>,
+    pub opaque_unknown_ops: bool,
 }
 
 impl Onnx {
@@ -224,21 +283,60 @@ impl Onnx {
             parent_graphs: vec![],
             onnx_operator_set_version,
         };
-        ctx.parse_graph(graph)
+        let mut result = ctx.parse_graph(graph)?;
+        for prop in &proto.metadata_props {
+            result
+                .model
+                .properties
+                .insert(format!("onnx.metadata.{}", prop.key), rctensor0(prop.value.clone()));
+        }
+        input_ranges_from_metadata(&mut result.model, &proto.metadata_props)?;
+        Ok(result)
     }
 
     pub fn with_ignore_output_shapes(self, ignore: bool) -> Onnx {
         Self { ignore_output_shapes: ignore, ..self }
     }
+
+    /// Let initializers of at least `min_bytes` borrow their data from the
+    /// model buffer instead of copying it.
+    ///
+    /// When the model is loaded from a path, the buffer is a private memory
+    /// map of the file, so these constants are paged in lazily and never
+    /// duplicated on the heap. They are copied on the first mutable access.
+    pub fn with_shared_initializers(self, min_bytes: Option<usize>) -> Onnx {
+        Self { shared_initializers: min_bytes, ..self }
+    }
+
+    /// Translate operators tract does not know about to `OpaqueOp`, instead of
+    /// `UnimplementedOp`.
+    ///
+    /// Their evaluation is then provided by the application, with
+    /// `SimpleState::register_opaque_eval`. As there is no way to infer their
+    /// output facts, they must be present in the ONNX graph (as graph outputs
+    /// or value_info) or set on the model before typing it.
+    pub fn with_opaque_unknown_ops(self, opaque: bool) -> Onnx {
+        Self { opaque_unknown_ops: opaque, ..self }
+    }
+
+    fn load_initializer(&self, init: &pb::TensorProto) -> TractResult<Tensor> {
+        if self.shared_initializers.map(|min| init.raw_data.len() >= min).unwrap_or(false) {
+            if let Some(tensor) = crate::tensor::shared_from_proto(init)? {
+                return Ok(tensor);
+            }
+            debug!("Initializer {} can not be shared, copying it", init.name);
+        }
+        init.try_into()
+    }
 }
 
 impl Framework<pb::ModelProto, InferenceModel> for Onnx {
     fn proto_model_for_path(&self, p: impl AsRef<path::Path>) -> TractResult<pb::ModelProto> {
         #[cfg(not(target_arch = "wasm32"))]
-        let map = unsafe { mapr::Mmap::map(&fs::File::open(p)?)? };
+        let map = unsafe { mapr::MmapOptions::new().map_copy(&fs::File::open(p)?)? };
         #[cfg(target_arch = "wasm32")]
         let map = fs::read(p)?;
-        Ok(crate::pb::ModelProto::decode(&*map)?)
+        Ok(crate::pb::ModelProto::decode(bytes::Bytes::from_owner(map))?)
     }
 
     fn proto_model_for_read(&self, r: &mut dyn std::io::Read) -> TractResult<pb::ModelProto> {
@@ -256,3 +354,137 @@ impl Framework<pb::ModelProto, InferenceModel> for Onnx {
         Ok(model)
     }
 }
+
+/// Declares input ranges from the model metadata.
+///
+/// For an input named `x`, `x.min` and `x.max` give its range. Failing that,
+/// normalization parameters `x.mean` and `x.std` give a range of three standard
+/// deviations around the mean.
+fn input_ranges_from_metadata(
+    model: &mut InferenceModel,
+    metadata: &[pb::StringStringEntryProto],
+) -> TractResult<()> {
+    let get = |key: String| -> Option<f32> {
+        let prop = metadata.iter().find(|prop| prop.key == key)?;
+        let value = prop.value.trim().parse::<f32>();
+        if value.is_err() {
+            warn!("Ignoring metadata {}: {:?} is not a number", key, prop.value);
+        }
+        value.ok()
+    };
+    for ix in 0..model.inputs.len() {
+        let name = model.node(model.inputs[ix].node).name.clone();
+        let range = if let (Some(min), Some(max)) =
+            (get(format!("{}.min", name)), get(format!("{}.max", name)))
+        {
+            (min, max)
+        } else if let (Some(mean), Some(std)) =
+            (get(format!("{}.mean", name)), get(format!("{}.std", name)))
+        {
+            (mean - 3.0 * std.abs(), mean + 3.0 * std.abs())
+        } else {
+            continue;
+        };
+        debug!("Input {} range from metadata: {:?}", name, range);
+        model
+            .set_input_range(ix, range.0, range.1)
+            .with_context(|| format!("Input range for {} from metadata", name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_info(name: &str) -> pb::ValueInfoProto {
+        use pb::tensor_shape_proto::{dimension::Value, Dimension};
+        let shape = pb::TensorShapeProto {
+            dim: vec![Dimension { value: Some(Value::DimValue(2)), ..Dimension::default() }],
+        };
+        let tensor = pb::type_proto::Tensor {
+            elem_type: pb::tensor_proto::DataType::Float as i32,
+            shape: Some(shape),
+        };
+        pb::ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(pb::TypeProto {
+                value: Some(pb::type_proto::Value::TensorType(tensor)),
+                ..pb::TypeProto::default()
+            }),
+            ..pb::ValueInfoProto::default()
+        }
+    }
+
+    #[test]
+    fn opaque_unknown_op() -> TractResult<()> {
+        let node = pb::NodeProto {
+            op_type: "AcmeScale".to_string(),
+            input: vec!["x".to_string()],
+            output: vec!["y".to_string()],
+            attribute: vec![pb::AttributeProto {
+                name: "factor".to_string(),
+                r#type: pb::attribute_proto::AttributeType::Float as i32,
+                f: 3.0,
+                ..pb::AttributeProto::default()
+            }],
+            ..pb::NodeProto::default()
+        };
+        let graph = pb::GraphProto {
+            node: vec![node],
+            input: vec![value_info("x")],
+            output: vec![value_info("y")],
+            ..pb::GraphProto::default()
+        };
+        let proto = pb::ModelProto { graph: Some(graph), ..pb::ModelProto::default() };
+        let onnx = crate::onnx().with_opaque_unknown_ops(true);
+        let model = onnx.model_for_proto_model(&proto)?.into_optimized()?;
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(&plan)?;
+        state.register_opaque_eval("AcmeScale", |op, inputs| {
+            let factor = *op.attribute("factor").unwrap().to_scalar::<f32>()?;
+            Ok(tvec!(inputs[0].to_array_view::<f32>()?.mapv(|x| x * factor).into_arc_tensor()))
+        });
+        let output = state.run(tvec!(tensor1(&[1f32, 2.0])))?;
+        assert_eq!(*output[0], tensor1(&[3f32, 6.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn input_ranges_from_metadata() -> TractResult<()> {
+        let node = pb::NodeProto {
+            op_type: "Add".to_string(),
+            input: vec!["x".to_string(), "y".to_string()],
+            output: vec!["z".to_string()],
+            ..pb::NodeProto::default()
+        };
+        let graph = pb::GraphProto {
+            node: vec![node],
+            input: vec![value_info("x"), value_info("y")],
+            output: vec![value_info("z")],
+            ..pb::GraphProto::default()
+        };
+        let metadata = |key: &str, value: &str| pb::StringStringEntryProto {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        let proto = pb::ModelProto {
+            graph: Some(graph),
+            metadata_props: vec![
+                metadata("x.min", "0"),
+                metadata("x.max", "255"),
+                metadata("y.mean", "0.5"),
+                metadata("y.std", "0.25"),
+                metadata("author", "acme"),
+            ],
+            ..pb::ModelProto::default()
+        };
+        let model = crate::onnx().model_for_proto_model(&proto)?;
+        assert_eq!(model.input_range(0)?, Some((0.0, 255.0)));
+        assert_eq!(model.input_range(1)?, Some((-0.25, 1.25)));
+        assert_eq!(*model.properties["onnx.metadata.author"], tensor0("acme".to_string()));
+        let typed = model.into_typed()?;
+        assert_eq!(typed.input_range(0)?, Some((0.0, 255.0)));
+        Ok(())
+    }
+}
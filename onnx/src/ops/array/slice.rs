@@ -66,18 +66,15 @@ impl Expansion for Slice1 {
                 } else {
                     Some((self.starts[axis].into(), self.ends[axis].into()))
                 };
-                if let Some((mut b, mut e)) = spec {
-                    if let Ok(d) = d.to_i64() {
-                        if b > d {
-                            b = d.into();
+                if let Some((b, e)) = spec {
+                    let clamp = |x: i64| {
+                        if x >= 0 {
+                            x.to_dim().mini(d.clone())
+                        } else {
+                            (d.clone() + x).maxi(0.to_dim())
                         }
-                        if e > d {
-                            e = d.into();
-                        }
-                    }
-                    let b = if b < 0 { d.bex() + TDim::from(b) } else { TDim::from(b).bex() };
-                    let e = if e < 0 { d.bex() + TDim::from(e) } else { TDim::from(e).bex() };
-                    s.equals(&outputs[0].shape[axis], e - b)
+                    };
+                    s.equals(&outputs[0].shape[axis], clamp(e) - clamp(b))
                 } else {
                     s.equals(&outputs[0].shape[axis], &shape[axis])
                 }
@@ -97,18 +94,20 @@ impl Expansion for Slice1 {
         for (ix, (&b, &e)) in self.starts.iter().zip(self.ends.iter()).enumerate() {
             let axis = self.axes.as_ref().map(|axes| axes[ix]).unwrap_or(ix);
             let dim = &input.shape[axis];
-            if let Ok(dim) = dim.to_i64() {
-                let b = (if b >= 0 { b.min(dim) } else { dim + b }) as usize;
-                let e = (if e >= 0 { e.min(dim) } else { dim + e }) as usize;
-                if b > 0 || e < dim as usize {
-                    wire = target.wire_node(
-                        format!("{}.axis-{}", prefix, axis),
-                        tract_hir::ops::array::Slice::new(axis, b, e),
-                        [wire].as_ref(),
-                    )?[0];
+            let clamp = |x: i64| {
+                if x >= 0 {
+                    x.to_dim().mini(dim.clone())
+                } else {
+                    (dim.clone() + x).maxi(0.to_dim())
                 }
-            } else {
-                bail!("Can't translate slice: axis={} dim={} b={} e={}", axis, dim, b, e)
+            };
+            let (b, e) = (clamp(b), clamp(e));
+            if b != 0.to_dim() || &e != dim {
+                wire = target.wire_node(
+                    format!("{}.axis-{}", prefix, axis),
+                    tract_hir::ops::array::Slice::new(axis, b, e),
+                    [wire].as_ref(),
+                )?[0];
             }
         }
         target.rename_node(wire.node, &*prefix)?;
@@ -14,7 +14,8 @@ fn quantize_linear(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let op = QuantizeLinear::new(Some(2).filter(|_| node.input.len() == 3));
+    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
+    let op = QuantizeLinear::new(Some(2).filter(|_| node.input.len() == 3), axis);
     Ok((expand(op), vec![]))
 }
 
@@ -22,7 +23,8 @@ fn dequantize_linear(
     _ctx: &ParsingContext,
     node: &NodeProto,
 ) -> TractResult<(Box<dyn InferenceOp>, Vec<String>)> {
-    let op = DequantizeLinear::new(Some(2).filter(|_| node.input.len() == 3));
+    let axis = node.get_attr_opt("axis")?.unwrap_or(1);
+    let op = DequantizeLinear::new(Some(2).filter(|_| node.input.len() == 3), axis);
     Ok((expand(op), vec![]))
 }
 
@@ -37,6 +39,7 @@ fn dynamic_quantize_linear(
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct QuantizeLinear {
     optional_zero_point_input: Option<usize>,
+    axis: i64,
 }
 
 impl_dyn_hash!(QuantizeLinear);
@@ -75,13 +78,8 @@ impl Expansion for QuantizeLinear {
         inputs: &[OutletId],
     ) -> TractResult<TVec<OutletId>> {
         use tract_hir::ops::quant::*;
-        let scale = target
-            .outlet_fact(inputs[1])?
-            .konst
-            .as_ref()
-            .context("y_scale must be a const")?
-            .as_slice::<f32>()?[0]
-            .recip();
+        let scale =
+            target.outlet_fact(inputs[1])?.konst.clone().context("y_scale must be a const")?;
         let zero_point = if self.optional_zero_point_input.is_some() {
             target
                 .outlet_fact(inputs[2])?
@@ -92,6 +90,17 @@ impl Expansion for QuantizeLinear {
         } else {
             rctensor0(0u8)
         };
+        if scale.len() > 1 || zero_point.len() > 1 {
+            return wire_quantize_per_axis(
+                prefix,
+                target,
+                inputs[0],
+                self.axis,
+                &scale,
+                &zero_point,
+            );
+        }
+        let scale = scale.as_slice::<f32>()?[0].recip();
         let op: Box<dyn TypedOp> = if zero_point.datum_type() == u8::datum_type() {
             Box::new(quantize_linear_u8(scale, zero_point.as_slice::<u8>()?[0]))
         } else {
@@ -104,6 +113,7 @@ impl Expansion for QuantizeLinear {
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct DequantizeLinear {
     optional_zero_point_input: Option<usize>,
+    axis: i64,
 }
 
 impl_dyn_hash!(DequantizeLinear);
@@ -140,12 +150,8 @@ impl Expansion for DequantizeLinear {
         target: &mut TypedModel,
         inputs: &[OutletId],
     ) -> TractResult<TVec<OutletId>> {
-        let scale = target
-            .outlet_fact(inputs[1])?
-            .konst
-            .as_ref()
-            .context("y_scale must be a const")?
-            .as_slice::<f32>()?[0];
+        let scale =
+            target.outlet_fact(inputs[1])?.konst.clone().context("y_scale must be a const")?;
         let zero_point = if self.optional_zero_point_input.is_some() {
             target
                 .outlet_fact(inputs[2])?
@@ -156,6 +162,17 @@ impl Expansion for DequantizeLinear {
         } else {
             rctensor0(0u8)
         };
+        if scale.len() > 1 || zero_point.len() > 1 {
+            return wire_dequantize_per_axis(
+                prefix,
+                target,
+                inputs[0],
+                self.axis,
+                &scale,
+                &zero_point,
+            );
+        }
+        let scale = scale.as_slice::<f32>()?[0];
         let op: Box<dyn TypedOp> = if zero_point.datum_type() == u8::datum_type() {
             Box::new(DequantizeLinearF32::new(scale, zero_point.as_slice::<u8>()?[0] as i32))
         } else if zero_point.datum_type() == i8::datum_type() {
@@ -167,6 +184,84 @@ impl Expansion for DequantizeLinear {
     }
 }
 
+/// Per-axis parameter shaped to broadcast along `axis` of `input`, as f32.
+fn per_axis(
+    target: &TypedModel,
+    input: OutletId,
+    axis: i64,
+    param: &Tensor,
+) -> TractResult<Arc<Tensor>> {
+    let rank = target.outlet_fact(input)?.rank();
+    let axis = if axis < 0 { axis + rank as i64 } else { axis } as usize;
+    ensure!(axis < rank, "Invalid quantization axis {} for a rank {} input", axis, rank);
+    let mut shape = tvec!(1; rank);
+    shape[axis] = param.len();
+    Ok(param.cast_to::<f32>()?.into_owned().into_shape(&shape)?.into_arc_tensor())
+}
+
+fn wire_quantize_per_axis(
+    prefix: &str,
+    target: &mut TypedModel,
+    input: OutletId,
+    axis: i64,
+    scale: &Tensor,
+    zero_point: &Tensor,
+) -> TractResult<TVec<OutletId>> {
+    use tract_hir::ops::math;
+    let dt = zero_point.datum_type();
+    let rank = target.outlet_fact(input)?.rank();
+    let recip = per_axis(target, input, axis, scale)?.into_tensor();
+    let recip = recip.to_array_view::<f32>()?.mapv(|x| x.recip()).into_arc_tensor();
+    let zero_point = per_axis(target, input, axis, zero_point)?;
+    let min = dt.min_value().cast_to::<f32>()?.into_owned().broadcast_into_rank(rank)?;
+    let max = dt.max_value().cast_to::<f32>()?.into_owned().broadcast_into_rank(rank)?;
+    let mut wire = target.wire_node(
+        format!("{}.as_f32", prefix),
+        tract_hir::ops::cast::cast(f32::datum_type()),
+        &[input],
+    )?;
+    wire = target.wire_node(format!("{}.scale", prefix), math::mul::unary(recip), &wire)?;
+    wire = target.wire_node(format!("{}.round", prefix), math::round_half_to_even(), &wire)?;
+    wire =
+        target.wire_node(format!("{}.zero_point", prefix), math::add::unary(zero_point), &wire)?;
+    wire = target.wire_node(
+        format!("{}.min", prefix),
+        math::min::unary(max.into_arc_tensor()),
+        &wire,
+    )?;
+    wire = target.wire_node(
+        format!("{}.max", prefix),
+        math::max::unary(min.into_arc_tensor()),
+        &wire,
+    )?;
+    target.wire_node(prefix, tract_hir::ops::cast::cast(dt), &wire)
+}
+
+fn wire_dequantize_per_axis(
+    prefix: &str,
+    target: &mut TypedModel,
+    input: OutletId,
+    axis: i64,
+    scale: &Tensor,
+    zero_point: &Tensor,
+) -> TractResult<TVec<OutletId>> {
+    use tract_hir::ops::math;
+    let scale = per_axis(target, input, axis, scale)?;
+    let zero_point = per_axis(target, input, axis, zero_point)?.into_tensor();
+    let minus_zero_point = zero_point.to_array_view::<f32>()?.mapv(|x| -x).into_arc_tensor();
+    let mut wire = target.wire_node(
+        format!("{}.as_f32", prefix),
+        tract_hir::ops::cast::cast(f32::datum_type()),
+        &[input],
+    )?;
+    wire = target.wire_node(
+        format!("{}.zero_point", prefix),
+        math::add::unary(minus_zero_point),
+        &wire,
+    )?;
+    target.wire_node(prefix, math::mul::unary(scale), &wire)
+}
+
 #[derive(Debug, Clone, new, Default, Hash)]
 pub struct DynamicQuantizeLinear {}
 
@@ -365,4 +460,24 @@ mod tests {
             assert_eq!(quantized.as_slice().unwrap(), *quantized_ok);
         }
     }
+
+    #[test]
+    fn test_quantize_dequantize_per_axis() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[2, 3]))?;
+        let scale = model.add_const("scale", tensor1(&[0.5f32, 2.0, 0.1]))?;
+        let zero_point = model.add_const("zero_point", tensor1(&[0u8, 10, 128]))?;
+        let q = QuantizeLinear::new(Some(2), 1).wire("q", &mut model, &[x, scale, zero_point])?;
+        let dq = DequantizeLinear::new(Some(2), -1).wire(
+            "dq",
+            &mut model,
+            &[q[0], scale, zero_point],
+        )?;
+        model.set_output_outlets(&[q[0], dq[0]])?;
+        let x = tensor2(&[[1.25f32, -4.0, 3.0], [-1.0, 600.0, -20.0]]);
+        let outputs = model.into_runnable()?.run(tvec!(x))?;
+        assert_eq!(*outputs[0], tensor2(&[[2u8, 8, 158], [0, 255, 0]]));
+        outputs[1].close_enough(&tensor2(&[[1.0f32, -4.0, 3.0], [0.0, 490.0, -12.8]]), true)?;
+        Ok(())
+    }
 }
@@ -138,6 +138,87 @@ impl<'a> TryFrom<&'a TensorProto> for Tensor {
     }
 }
 
+impl<'a> TryFrom<&'a SparseTensorProto> for Tensor {
+    type Error = TractError;
+    fn try_from(t: &SparseTensorProto) -> TractResult<Tensor> {
+        fn densify<T: Datum>(
+            shape: &[usize],
+            values: &Tensor,
+            indices: &[usize],
+        ) -> TractResult<Tensor> {
+            let mut dense = tract_ndarray::ArrayD::<T>::default(shape);
+            let slice = dense.as_slice_mut().unwrap();
+            for (&ix, v) in indices.iter().zip(values.as_slice::<T>()?.iter()) {
+                *slice.get_mut(ix).context("Sparse tensor index out of bounds")? = v.clone();
+            }
+            Ok(dense.into_tensor())
+        }
+        let values: Tensor =
+            t.values.as_ref().context("Sparse tensor without values")?.try_into()?;
+        let indices: Tensor =
+            t.indices.as_ref().context("Sparse tensor without indices")?.try_into()?;
+        let indices = indices.cast_to::<i64>()?;
+        let shape: TVec<usize> = t.dims.iter().map(|&i| i as usize).collect();
+        let nnz = values.len();
+        let linear: Vec<usize> = if indices.rank() == 1 {
+            indices.as_slice::<i64>()?.iter().map(|&i| i as usize).collect()
+        } else if indices.rank() == 2 && indices.shape()[1] == shape.len() {
+            let strides = natural_strides(&shape);
+            let indices =
+                indices.to_array_view::<i64>()?.into_dimensionality::<tract_ndarray::Ix2>()?;
+            indices
+                .outer_iter()
+                .map(|coords| {
+                    let mut ix = 0;
+                    for (axis, &c) in coords.iter().enumerate() {
+                        ensure!(
+                            c >= 0 && (c as usize) < shape[axis],
+                            "Sparse tensor coordinates {:?} out of bounds for shape {:?}",
+                            coords,
+                            shape
+                        );
+                        ix += c as usize * strides[axis] as usize;
+                    }
+                    Ok(ix)
+                })
+                .collect::<TractResult<_>>()?
+        } else {
+            bail!("Sparse tensor indices shape {:?} invalid for shape {:?}", indices.shape(), shape)
+        };
+        ensure!(
+            linear.len() == nnz,
+            "Sparse tensor has {} indices for {} values",
+            linear.len(),
+            nnz
+        );
+        dispatch_datum!(densify(values.datum_type())(&shape, &values, &linear))
+    }
+}
+
+impl TryFrom<SparseTensorProto> for Tensor {
+    type Error = TractError;
+    fn try_from(t: SparseTensorProto) -> TractResult<Tensor> {
+        (&t).try_into()
+    }
+}
+
+/// Build a tensor sharing the raw data buffer of the proto instead of copying it.
+///
+/// Returns None if the tensor can not be shared: no raw data, unsupported
+/// datum type, or data misaligned for the datum type.
+pub fn shared_from_proto(t: &TensorProto) -> TractResult<Option<Tensor>> {
+    if t.raw_data.len() == 0 {
+        return Ok(None);
+    }
+    let dt: DatumType = DataType::from_i32(t.data_type).unwrap().try_into()?;
+    if !dt.is_copy() || dt == DatumType::Bool || t.raw_data.as_ptr() as usize % dt.alignment() != 0
+    {
+        return Ok(None);
+    }
+    let shape: Vec<usize> = t.dims.iter().map(|&i| i as usize).collect();
+    Ok(Some(Tensor::from_shared_bytes(dt, &shape, t.raw_data.clone())?))
+}
+
 impl TryFrom<TensorProto> for Tensor {
     type Error = TractError;
     fn try_from(t: TensorProto) -> TractResult<Tensor> {
@@ -155,3 +236,96 @@ pub fn proto_from_reader<R: ::std::io::Read>(mut r: R) -> TractResult<TensorProt
 pub fn from_reader<R: ::std::io::Read>(r: R) -> TractResult<Tensor> {
     proto_from_reader(r)?.try_into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proto(raw_data: bytes::Bytes, dims: Vec<i64>) -> TensorProto {
+        TensorProto { dims, data_type: DataType::Float as i32, raw_data, ..TensorProto::default() }
+    }
+
+    #[test]
+    fn shared_initializer() -> TractResult<()> {
+        let buffer: Vec<u8> = [1f32, 2.0, 3.0, 4.0].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let buffer = bytes::Bytes::from(buffer);
+        let pb = proto(buffer.clone(), vec![2, 2]);
+        let shared = shared_from_proto(&pb)?.unwrap();
+        assert!(shared.is_shared());
+        assert_eq!(shared.as_ptr::<f32>()? as *const u8, buffer.as_ptr());
+        assert_eq!(shared, Tensor::try_from(&pb)?);
+        Ok(())
+    }
+
+    #[test]
+    fn misaligned_initializer() -> TractResult<()> {
+        let buffer = bytes::Bytes::from(vec![0u8; 17]);
+        assert!(shared_from_proto(&proto(buffer.slice(1..), vec![4]))?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_linear_indices() -> TractResult<()> {
+        let sparse = SparseTensorProto {
+            values: Some(TensorProto {
+                dims: vec![2],
+                data_type: DataType::Float as i32,
+                float_data: vec![1.0, 2.0],
+                ..TensorProto::default()
+            }),
+            indices: Some(TensorProto {
+                dims: vec![2],
+                data_type: DataType::Int64 as i32,
+                int64_data: vec![1, 4],
+                ..TensorProto::default()
+            }),
+            dims: vec![2, 3],
+        };
+        assert_eq!(Tensor::try_from(&sparse)?, tensor2(&[[0f32, 1.0, 0.0], [0.0, 2.0, 0.0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_coords_indices() -> TractResult<()> {
+        let sparse = SparseTensorProto {
+            values: Some(TensorProto {
+                dims: vec![2],
+                data_type: DataType::Int32 as i32,
+                int32_data: vec![3, 5],
+                ..TensorProto::default()
+            }),
+            indices: Some(TensorProto {
+                dims: vec![2, 2],
+                data_type: DataType::Int64 as i32,
+                int64_data: vec![0, 2, 1, 0],
+                ..TensorProto::default()
+            }),
+            dims: vec![2, 3],
+        };
+        assert_eq!(Tensor::try_from(&sparse)?, tensor2(&[[0i32, 0, 3], [5, 0, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_coords_out_of_bounds() -> TractResult<()> {
+        for coords in &[vec![0, 3], vec![-1, 0], vec![2, 0]] {
+            let sparse = SparseTensorProto {
+                values: Some(TensorProto {
+                    dims: vec![1],
+                    data_type: DataType::Int32 as i32,
+                    int32_data: vec![3],
+                    ..TensorProto::default()
+                }),
+                indices: Some(TensorProto {
+                    dims: vec![1, 2],
+                    data_type: DataType::Int64 as i32,
+                    int64_data: coords.clone(),
+                    ..TensorProto::default()
+                }),
+                dims: vec![2, 3],
+            };
+            assert!(Tensor::try_from(&sparse).is_err());
+        }
+        Ok(())
+    }
+}
@@ -151,7 +151,8 @@ impl TypedOp for Delay {
     as_op!();
 
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        let mut fact = inputs[0].clone();
+        // the buffer starts zeroed, out of the input range
+        let mut fact = inputs[0].clone().with_range(None);
         fact.shape.set(self.axis, fact.shape[self.axis].clone() + self.overlap.to_dim());
         Ok(tvec!(fact))
     }
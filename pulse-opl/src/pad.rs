@@ -189,7 +189,7 @@ impl EvalOp for PulsePad {
 
 impl TypedOp for PulsePad {
     fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
-        Ok(tvec!(inputs[0].clone()))
+        Ok(tvec!(inputs[0].clone().with_range(None)))
     }
 
     as_op!();
@@ -0,0 +1,254 @@
+//! Functions from a GraphDef library, as produced by `tf.function`.
+//!
+//! Calls (PartitionedCall, StatefulPartitionedCall, or nodes whose op is a
+//! library function) are inlined, the function nodes being prefixed by the
+//! call node name. Functional control flow ops (StatelessWhile, StatelessIf,
+//! ...) keep their branches and bodies as separate models, built by
+//! `Tensorflow::model_for_function`.
+//!
+//! Function bodies refer to node outputs by argument name ("node:arg:index").
+//! Without the op definitions, these names are resolved with a table of the
+//! common multi-output ops, and by assuming the first argument otherwise.
+use std::convert::TryInto;
+use tract_hir::internal::*;
+
+use crate::model::Tensorflow;
+use crate::tfpb::tensorflow::{FunctionDef, GraphDef, NodeDef};
+
+const CALL_OPS: &[&str] = &["PartitionedCall", "StatefulPartitionedCall"];
+const MAX_INLINING_DEPTH: usize = 64;
+
+/// Output arguments of multi-output ops, for resolving "node:arg:index".
+const OUTPUT_ARGS: &[(&str, &[&str])] = &[
+    (
+        "FusedBatchNorm",
+        &["y", "batch_mean", "batch_variance", "reserve_space_1", "reserve_space_2"],
+    ),
+    (
+        "FusedBatchNormV3",
+        &[
+            "y",
+            "batch_mean",
+            "batch_variance",
+            "reserve_space_1",
+            "reserve_space_2",
+            "reserve_space_3",
+        ],
+    ),
+    ("Merge", &["output", "value_index"]),
+    ("Switch", &["output_false", "output_true"]),
+    ("TopKV2", &["values", "indices"]),
+    ("Unique", &["y", "idx"]),
+];
+
+/// Splits "node:slot", "node" or "^node" references.
+pub fn parse_ref(name: &str) -> TractResult<(&str, usize)> {
+    let name = name.trim_start_matches('^');
+    if let Some((node, slot)) = name.rsplit_once(':') {
+        Ok((node, slot.parse()?))
+    } else {
+        Ok((name, 0))
+    }
+}
+
+/// Follows references to inlined calls until they reach a regular node.
+pub fn resolve(calls: &HashMap<String, Vec<String>>, name: &str) -> String {
+    let mut name = name.to_string();
+    for _ in 0..MAX_INLINING_DEPTH {
+        let (node, slot) = match parse_ref(&name) {
+            Ok(r) => r,
+            Err(_) => break,
+        };
+        if let Some(resolved) = calls.get(node).and_then(|outputs| outputs.get(slot)) {
+            name = resolved.clone();
+        } else {
+            break;
+        }
+    }
+    name
+}
+
+pub fn function<'g>(graph: &'g GraphDef, name: &str) -> TractResult<&'g FunctionDef> {
+    graph
+        .library
+        .iter()
+        .flat_map(|l| l.function.iter())
+        .find(|f| f.signature.as_ref().map(|s| s.name == name).unwrap_or(false))
+        .with_context(|| format!("Function {} not found", name))
+}
+
+pub fn has_calls(graph: &GraphDef) -> bool {
+    graph.library.as_ref().map(|l| !l.function.is_empty()).unwrap_or(false)
+        && graph.node.iter().any(|n| CALL_OPS.contains(&&*n.op) || function(graph, &n.op).is_ok())
+}
+
+/// Replaces all function call nodes by their bodies, returning the call
+/// outputs as references to the inlined nodes.
+pub fn inline_calls(graph: &mut GraphDef) -> TractResult<HashMap<String, Vec<String>>> {
+    let mut calls = HashMap::new();
+    for _ in 0..MAX_INLINING_DEPTH {
+        let mut nodes = vec![];
+        let mut inlined = false;
+        for node in std::mem::take(&mut graph.node) {
+            let function = if CALL_OPS.contains(&&*node.op) {
+                Some(function(graph, node.get_attr_func("f")?)?)
+            } else {
+                function(graph, &node.op).ok()
+            };
+            if let Some(function) = function {
+                let (body, outputs) = inline_call(&node, function)
+                    .with_context(|| format!("Inlining call {}", node.name))?;
+                nodes.extend(body);
+                calls.insert(node.name, outputs);
+                inlined = true;
+            } else {
+                nodes.push(node);
+            }
+        }
+        for node in &mut nodes {
+            node.input = node
+                .input
+                .iter()
+                .filter(|i| !(i.starts_with('^') && calls.contains_key(&i[1..])))
+                .map(|i| resolve(&calls, i))
+                .collect();
+        }
+        graph.node = nodes;
+        if !inlined {
+            return Ok(calls);
+        }
+    }
+    bail!("Function calls nested more than {} levels deep", MAX_INLINING_DEPTH)
+}
+
+fn inline_call(call: &NodeDef, function: &FunctionDef) -> TractResult<(Vec<NodeDef>, Vec<String>)> {
+    let signature = function.signature.as_ref().unwrap();
+    let call_inputs: Vec<&str> =
+        call.input.iter().filter(|i| !i.starts_with('^')).map(|i| &**i).collect();
+    ensure!(
+        call_inputs.len() == signature.input_arg.len(),
+        "Expected {} inputs, got {}",
+        signature.input_arg.len(),
+        call_inputs.len()
+    );
+    instantiate(function, Some(&call.name), &call_inputs)
+}
+
+/// Function nodes and outputs, with arguments replaced by `args`, and node
+/// names prefixed by `prefix`.
+fn instantiate(
+    function: &FunctionDef,
+    prefix: Option<&str>,
+    args: &[&str],
+) -> TractResult<(Vec<NodeDef>, Vec<String>)> {
+    let signature = function.signature.as_ref().unwrap();
+    let args: HashMap<&str, &str> = signature
+        .input_arg
+        .iter()
+        .zip(args.iter())
+        .map(|(arg, input)| (&*arg.name, *input))
+        .collect();
+    let ops: HashMap<&str, &str> = function.node_def.iter().map(|n| (&*n.name, &*n.op)).collect();
+    let prefixed = |name: &str| {
+        if let Some(prefix) = prefix {
+            format!("{}/{}", prefix, name)
+        } else {
+            name.to_string()
+        }
+    };
+    let rename = |input: &str| -> TractResult<Option<String>> {
+        if let Some(control) = input.strip_prefix('^') {
+            return Ok(if args.contains_key(control) {
+                None
+            } else {
+                Some(format!("^{}", prefixed(control)))
+            });
+        }
+        let parts: Vec<&str> = input.split(':').collect();
+        if let Some(arg) = args.get(parts[0]) {
+            return Ok(Some(arg.to_string()));
+        }
+        let slot = match parts.len() {
+            1 => 0,
+            2 => parts[1].parse()?,
+            _ => {
+                let index: usize = parts[2].parse()?;
+                let op = ops.get(parts[0]).with_context(|| format!("No node for {}", input))?;
+                OUTPUT_ARGS
+                    .iter()
+                    .find(|(o, _)| o == op)
+                    .and_then(|(_, args)| args.iter().position(|a| *a == parts[1]))
+                    .unwrap_or(0)
+                    + index
+            }
+        };
+        Ok(Some(format!("{}:{}", prefixed(parts[0]), slot)))
+    };
+    let mut body = vec![];
+    for node in &function.node_def {
+        let mut node = node.clone();
+        let mut inputs = vec![];
+        for input in &node.input {
+            inputs.extend(rename(input)?);
+        }
+        node.input = inputs;
+        node.name = prefixed(&node.name);
+        body.push(node);
+    }
+    let outputs = signature
+        .output_arg
+        .iter()
+        .map(|arg| {
+            let ret = function.ret.get(&arg.name).context("Missing function output")?;
+            rename(ret)?.context("Control output")
+        })
+        .collect::<TractResult<_>>()?;
+    Ok((body, outputs))
+}
+
+impl Tensorflow {
+    /// Builds a model computing a function of `graph` library, for the given
+    /// input types.
+    pub fn model_for_function(
+        &self,
+        graph: &GraphDef,
+        name: &str,
+        input_types: &[DatumType],
+    ) -> TractResult<InferenceModel> {
+        let function = function(graph, name)?;
+        let signature = function.signature.as_ref().unwrap();
+        ensure!(
+            signature.input_arg.len() == input_types.len(),
+            "Function {} expects {} inputs, got {} types",
+            name,
+            signature.input_arg.len(),
+            input_types.len()
+        );
+        let args: Vec<&str> = signature.input_arg.iter().map(|a| &*a.name).collect();
+        let (mut nodes, outputs) = instantiate(function, None, &args)?;
+        for (arg, dt) in args.iter().zip(input_types.iter()) {
+            let dt: crate::tfpb::tensorflow::DataType = (*dt).try_into()?;
+            nodes.push(crate::tfpb::node().name(arg).op("Placeholder").attr("dtype", dt));
+        }
+        let mut body =
+            GraphDef { node: nodes, library: graph.library.clone(), ..GraphDef::default() };
+        let calls = inline_calls(&mut body)?;
+        let mut model =
+            self.parse_graph(&body).with_context(|| format!("Parsing function {}", name))?.0;
+        let inputs = args
+            .iter()
+            .map(|a| Ok(OutletId::new(model.node_id_by_name(a)?, 0)))
+            .collect::<TractResult<TVec<_>>>()?;
+        model.set_input_outlets(&inputs)?;
+        let outputs = outputs
+            .iter()
+            .map(|o| {
+                let o = resolve(&calls, o);
+                let (node, slot) = parse_ref(&o)?;
+                Ok(OutletId::new(model.node_id_by_name(node)?, slot))
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        model.set_output_outlets(&outputs)?;
+        Ok(model)
+    }
+}
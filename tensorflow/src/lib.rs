@@ -51,8 +51,10 @@ pub extern crate tract_hir;
 #[cfg(feature = "conform")]
 pub mod conform;
 
+pub mod function;
 pub mod model;
 pub mod ops;
+pub mod saved_model;
 pub mod tensor;
 pub mod tfpb;
 
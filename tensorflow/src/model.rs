@@ -118,6 +118,12 @@ impl Tensorflow {
     pub fn parse_graph(&self, graph: &GraphDef) -> TractResult<TfModelAndExtensions> {
         use crate::ops::control_flow as cf;
 
+        if crate::function::has_calls(graph) {
+            let mut graph = graph.clone();
+            crate::function::inline_calls(&mut graph)?;
+            return self.parse_graph(&graph);
+        }
+
         let mut model = InferenceModel::default();
         let mut inputs = tvec!();
         let mut context = ParsingContext::default();
@@ -144,9 +150,12 @@ impl Tensorflow {
                 continue;
             }
 
-            let op = match self.op_register.0.get(&pbnode.op) {
-                Some(builder) => (builder)(&context, pbnode)?,
-                None => tract_hir::ops::unimpl::UnimplementedOp::new(
+            let functional = crate::ops::functional::build(self, graph, pbnode)
+                .with_context(|| format!("Building {} ({})", name, pbnode.op))?;
+            let op = match (functional, self.op_register.0.get(&pbnode.op)) {
+                (Some(op), _) => op,
+                (None, Some(builder)) => (builder)(&context, pbnode)?,
+                (None, None) => tract_hir::ops::unimpl::UnimplementedOp::new(
                     context.node_output_arities.get(name).cloned().unwrap_or(1),
                     &pbnode.op,
                     format!("{:?}", pbnode),
@@ -0,0 +1,669 @@
+//! Functional control flow: While, StatelessWhile, If and StatelessIf.
+//!
+//! The loop condition and body, or the branches, are library functions. They
+//! are built as separate models, so these ops can not be registered in the
+//! TfOpRegister as the other ones: the GraphDef parser calls `build` on each
+//! node first.
+use tract_hir::internal::*;
+
+use crate::model::Tensorflow;
+use crate::tfpb::tensorflow::{GraphDef, NodeDef};
+
+pub fn build(
+    tf: &Tensorflow,
+    graph: &GraphDef,
+    node: &NodeDef,
+) -> TractResult<Option<Box<dyn InferenceOp>>> {
+    match &*node.op {
+        "While" | "StatelessWhile" => {
+            let types = node.get_attr_list_datum_type("T")?;
+            let cond = tf.model_for_function(graph, node.get_attr_func("cond")?, &types)?;
+            let body = tf.model_for_function(graph, node.get_attr_func("body")?, &types)?;
+            ensure!(
+                body.output_outlets()?.len() == types.len(),
+                "While body must return its {} loop variables",
+                types.len()
+            );
+            Ok(Some(Box::new(While { cond, body })))
+        }
+        "If" | "StatelessIf" => {
+            let types = node.get_attr_list_datum_type("Tin")?;
+            let then_branch =
+                tf.model_for_function(graph, node.get_attr_func("then_branch")?, &types)?;
+            let else_branch =
+                tf.model_for_function(graph, node.get_attr_func("else_branch")?, &types)?;
+            ensure!(
+                then_branch.output_outlets()?.len() == else_branch.output_outlets()?.len(),
+                "If branches must have the same number of outputs"
+            );
+            Ok(Some(Box::new(If { then_branch, else_branch })))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// TensorFlow truth value of a tensor: the scalar value for scalars,
+/// non-emptiness otherwise.
+fn is_true(t: &Tensor) -> TractResult<bool> {
+    if t.rank() == 0 {
+        t.cast_to_scalar::<bool>()
+    } else {
+        Ok(t.len() > 0)
+    }
+}
+
+/// Unifies datum types and shapes of facts, leaving their values alone.
+fn unify_type_and_shape(facts: &mut [InferenceFact]) -> TractResult<bool> {
+    let mut datum_type = TypeFactoid::default();
+    let mut shape = ShapeFactoid::default();
+    for fact in facts.iter() {
+        datum_type = datum_type.unify(&fact.datum_type)?;
+        shape = shape.unify(&fact.shape)?;
+    }
+    let mut changed = false;
+    for fact in facts {
+        if fact.datum_type != datum_type || fact.shape != shape {
+            fact.datum_type = datum_type;
+            fact.shape = shape.clone();
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+fn into_typed(model: &InferenceModel, what: &str) -> TractResult<TypedModel> {
+    model.clone().into_typed().with_context(|| format!("Translating {} to typed", what))
+}
+
+/// Loop on `body` while `cond` is true. Loop variables must keep the same
+/// shape across iterations.
+#[derive(Debug, Clone, Hash)]
+pub struct While {
+    pub cond: InferenceModel,
+    pub body: InferenceModel,
+}
+
+impl_dyn_hash!(While);
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for While {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = TypedWhile::new(into_typed(&self.cond, "cond")?, into_typed(&self.body, "body")?)?;
+        WhileState::new(&op)?.run(inputs)
+    }
+}
+
+impl InferenceOp for While {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let vars = self.body.input_outlets()?.len();
+        ensure!(inputs.len() == vars, "While expects {} inputs, got {}", vars, inputs.len());
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for ix in 0..vars {
+                let mut facts = [
+                    inputs[ix].clone(),
+                    outputs[ix].clone(),
+                    self.cond.input_fact(ix)?.clone(),
+                    self.body.input_fact(ix)?.clone(),
+                    self.body.output_fact(ix)?.clone(),
+                ];
+                if unify_type_and_shape(&mut facts)? {
+                    changed = true;
+                    let [input, output, cond_input, body_input, body_output] = facts;
+                    inputs[ix] = input;
+                    outputs[ix] = output;
+                    self.cond.set_input_fact(ix, cond_input)?;
+                    self.body.set_input_fact(ix, body_input)?;
+                    self.body.set_output_fact(ix, body_output)?;
+                }
+            }
+            changed |= self.cond.analyse(false).context("analysing loop condition")?;
+            changed |= self.body.analyse(false).context("analysing loop body")?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let op = TypedWhile::new(into_typed(&self.cond, "cond")?, into_typed(&self.body, "body")?)?;
+        let inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+/// While with typed condition and body, planned once.
+#[derive(Debug, Clone, Hash)]
+pub struct TypedWhile {
+    pub cond: Arc<TypedSimplePlan<TypedModel>>,
+    pub body: Arc<TypedSimplePlan<TypedModel>>,
+}
+
+impl_dyn_hash!(TypedWhile);
+
+impl TypedWhile {
+    pub fn new(cond: TypedModel, body: TypedModel) -> TractResult<TypedWhile> {
+        Ok(TypedWhile {
+            cond: Arc::new(SimplePlan::new(cond)?),
+            body: Arc::new(SimplePlan::new(body)?),
+        })
+    }
+}
+
+impl Op for TypedWhile {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_tf!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for TypedWhile {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(WhileState::new(self)?)))
+    }
+}
+
+impl TypedOp for TypedWhile {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let body = self.body.model();
+        ensure!(
+            inputs.len() == body.input_outlets()?.len(),
+            "While expects {} inputs, got {}",
+            body.input_outlets()?.len(),
+            inputs.len()
+        );
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(ix, input)| {
+                let mut fact = body.output_fact(ix)?.without_value();
+                ensure!(
+                    fact.rank() == input.rank(),
+                    "While body changes the rank of loop variable #{}",
+                    ix
+                );
+                // loop variables may change shape across iterations: axes
+                // where the body output and the input disagree are unknown
+                for axis in 0..fact.rank() {
+                    if fact.shape[axis] != input.shape[axis] {
+                        fact.shape.set(axis, Symbol::new('w').into());
+                    }
+                }
+                Ok(fact)
+            })
+            .collect()
+    }
+
+    as_op!();
+}
+
+/// States of the condition and body plans, kept across evaluations.
+#[derive(Debug, Clone)]
+struct WhileState {
+    cond: TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+    body: TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+}
+
+impl WhileState {
+    fn new(op: &TypedWhile) -> TractResult<WhileState> {
+        Ok(WhileState {
+            cond: SimpleState::new(Arc::clone(&op.cond))?,
+            body: SimpleState::new(Arc::clone(&op.body))?,
+        })
+    }
+
+    fn run(&mut self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let mut vars = inputs;
+        loop {
+            let proceed = self.cond.run(vars.iter().map(|v| v.clone().into_tensor()).collect())?;
+            ensure!(
+                proceed[0].rank() == 0,
+                "While condition must be a scalar, got {:?}",
+                proceed[0]
+            );
+            if !proceed[0].cast_to_scalar::<bool>()? {
+                break;
+            }
+            vars = self.body.run(vars.into_iter().map(|v| v.into_tensor()).collect())?;
+        }
+        Ok(vars)
+    }
+}
+
+impl OpState for WhileState {
+    fn eval(
+        &mut self,
+        _session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<Arc<Tensor>>,
+    ) -> TractResult<TVec<Arc<Tensor>>> {
+        self.run(inputs)
+    }
+}
+
+/// Runs `then_branch` or `else_branch` on inputs 1.. depending on input 0.
+#[derive(Debug, Clone, Hash)]
+pub struct If {
+    pub then_branch: InferenceModel,
+    pub else_branch: InferenceModel,
+}
+
+impl_dyn_hash!(If);
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_tf!();
+    not_a_typed_op!();
+}
+
+impl EvalOp for If {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let op = TypedIf {
+            then_branch: into_typed(&self.then_branch, "then branch")?,
+            else_branch: into_typed(&self.else_branch, "else branch")?,
+        };
+        op.eval(inputs)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        _observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let args = self.then_branch.input_outlets()?.len();
+        ensure!(inputs.len() == args + 1, "If expects {} inputs, got {}", args + 1, inputs.len());
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for ix in 0..args {
+                let mut facts = [
+                    inputs[ix + 1].clone(),
+                    self.then_branch.input_fact(ix)?.clone(),
+                    self.else_branch.input_fact(ix)?.clone(),
+                ];
+                if unify_type_and_shape(&mut facts)? {
+                    changed = true;
+                    let [input, then_input, else_input] = facts;
+                    inputs[ix + 1] = input;
+                    self.then_branch.set_input_fact(ix, then_input)?;
+                    self.else_branch.set_input_fact(ix, else_input)?;
+                }
+            }
+            for ix in 0..outputs.len() {
+                let mut facts = [
+                    outputs[ix].clone(),
+                    self.then_branch.output_fact(ix)?.clone(),
+                    self.else_branch.output_fact(ix)?.clone(),
+                ];
+                if unify_type_and_shape(&mut facts)? {
+                    changed = true;
+                    let [output, then_output, else_output] = facts;
+                    outputs[ix] = output;
+                    self.then_branch.set_output_fact(ix, then_output)?;
+                    self.else_branch.set_output_fact(ix, else_output)?;
+                }
+            }
+            changed |= self.then_branch.analyse(false).context("analysing then branch")?;
+            changed |= self.else_branch.analyse(false).context("analysing else branch")?;
+            if !changed {
+                break;
+            }
+        }
+        Ok((inputs, outputs, tvec!()))
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let op = TypedIf {
+            then_branch: into_typed(&self.then_branch, "then branch")?,
+            else_branch: into_typed(&self.else_branch, "else branch")?,
+        };
+        let inputs = node.inputs.iter().map(|i| mapping[i]).collect::<TVec<_>>();
+        target.wire_node(&*node.name, op, &inputs)
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.then_branch.output_outlets()?.len())
+    }
+
+    as_op!();
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct TypedIf {
+    pub then_branch: TypedModel,
+    pub else_branch: TypedModel,
+}
+
+impl_dyn_hash!(TypedIf);
+
+impl Op for TypedIf {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    op_tf!();
+    op_as_typed_op!();
+}
+
+impl EvalOp for TypedIf {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<Arc<Tensor>>) -> TractResult<TVec<Arc<Tensor>>> {
+        let cond = inputs.remove(0);
+        let branch = if is_true(&cond)? { &self.then_branch } else { &self.else_branch };
+        SimplePlan::new(branch)?.run(inputs.into_iter().map(|i| i.into_tensor()).collect())
+    }
+}
+
+impl TypedOp for TypedIf {
+    fn output_facts(&self, _inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        self.then_branch
+            .output_outlets()?
+            .iter()
+            .map(|o| Ok(self.then_branch.outlet_fact(*o)?.without_value()))
+            .collect()
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let cond = if let Some(cond) = &model.outlet_fact(node.inputs[0])?.konst {
+            is_true(cond)?
+        } else {
+            return Ok(None);
+        };
+        let branch = if cond { &self.then_branch } else { &self.else_branch };
+        let mut patch = TypedModelPatch::default();
+        let mut wires: HashMap<OutletId, OutletId> = HashMap::new();
+        for (ix, input) in branch.input_outlets()?.iter().enumerate() {
+            wires.insert(*input, patch.tap_model(model, node.inputs[ix + 1])?);
+        }
+        for n in branch.eval_order()? {
+            if wires.contains_key(&OutletId::new(n, 0)) {
+                continue;
+            }
+            let inner = branch.node(n);
+            let inputs = inner.inputs.iter().map(|i| wires[i]).collect::<TVec<_>>();
+            let outputs = patch.wire_node(
+                format!("{}.{}", node.name, inner.name),
+                inner.op.clone(),
+                &inputs,
+            )?;
+            for (slot, outlet) in outputs.into_iter().enumerate() {
+                wires.insert(OutletId::new(n, slot), outlet);
+            }
+        }
+        for (ix, output) in branch.output_outlets()?.iter().enumerate() {
+            patch.shunt_outside(model, OutletId::new(node.id, ix), wires[output])?;
+        }
+        Ok(Some(patch))
+    }
+
+    as_op!();
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryInto;
+
+    use crate::tfpb::node;
+    use crate::tfpb::tensorflow::attr_value::{ListValue, Value};
+    use crate::tfpb::tensorflow::op_def::ArgDef;
+    use crate::tfpb::tensorflow::{
+        AttrValue, DataType, FunctionDef, FunctionDefLibrary, GraphDef, NameAttrList, NodeDef,
+        OpDef, TensorProto,
+    };
+    use tract_hir::internal::*;
+
+    fn func(name: &str) -> AttrValue {
+        AttrValue {
+            value: Some(Value::Func(NameAttrList { name: name.to_string(), attr: HashMap::new() })),
+        }
+    }
+
+    fn types(types: &[DataType]) -> AttrValue {
+        AttrValue {
+            value: Some(Value::List(ListValue {
+                r#type: types.iter().map(|t| *t as i32).collect(),
+                ..ListValue::default()
+            })),
+        }
+    }
+
+    fn konst(name: &str, value: Tensor) -> NodeDef {
+        let dt: DataType = value.datum_type().try_into().unwrap();
+        let value: TensorProto = (&value).try_into().unwrap();
+        node().name(name).op("Const").attr("dtype", dt).attr("value", value)
+    }
+
+    fn function(
+        name: &str,
+        inputs: &[(&str, DataType)],
+        outputs: &[(&str, &str)],
+        nodes: Vec<NodeDef>,
+    ) -> FunctionDef {
+        let arg = |name: &str, dt: DataType| ArgDef {
+            name: name.to_string(),
+            r#type: dt as i32,
+            ..ArgDef::default()
+        };
+        FunctionDef {
+            signature: Some(OpDef {
+                name: name.to_string(),
+                input_arg: inputs.iter().map(|(n, dt)| arg(n, *dt)).collect(),
+                output_arg: outputs.iter().map(|(n, _)| arg(n, DataType::DtFloat)).collect(),
+                ..OpDef::default()
+            }),
+            node_def: nodes,
+            ret: outputs.iter().map(|(n, r)| (n.to_string(), r.to_string())).collect(),
+            ..FunctionDef::default()
+        }
+    }
+
+    fn run(graph: GraphDef, input: Tensor) -> TractResult<TVec<Arc<Tensor>>> {
+        let tf = crate::tensorflow();
+        let mut model = tf.parse_graph(&graph)?.0;
+        model.set_input_fact(0, InferenceFact::dt_shape(input.datum_type(), input.shape()))?;
+        let model = model.into_optimized()?;
+        SimplePlan::new(&model)?.run(tvec!(input))
+    }
+
+    #[test]
+    fn stateless_while() -> TractResult<()> {
+        // doubles x until it reaches 100
+        let cond = function(
+            "cond",
+            &[("x", DataType::DtFloat)],
+            &[("lt", "less:z:0")],
+            vec![
+                konst("limit", tensor0(100f32)),
+                node().name("less").op("Less").input("x").input("limit:output:0"),
+            ],
+        );
+        let body = function(
+            "body",
+            &[("x", DataType::DtFloat)],
+            &[("y", "double:z:0")],
+            vec![node().name("double").op("AddV2").input("x").input("x")],
+        );
+        let graph = GraphDef {
+            node: vec![
+                node().name("input").op("Placeholder").attr("dtype", DataType::DtFloat),
+                node()
+                    .name("loop")
+                    .op("StatelessWhile")
+                    .input("input")
+                    .attr("T", types(&[DataType::DtFloat]))
+                    .attr("cond", func("cond"))
+                    .attr("body", func("body")),
+            ],
+            library: Some(FunctionDefLibrary { function: vec![cond, body], ..Default::default() }),
+            ..GraphDef::default()
+        };
+        let outputs = run(graph, tensor0(3f32))?;
+        assert_eq!(&*outputs[0], &tensor0(192f32));
+        Ok(())
+    }
+
+    #[test]
+    fn while_facts_follow_body() -> TractResult<()> {
+        use tract_hir::tract_core::ops::array::TypedConcat;
+        let mut cond = TypedModel::default();
+        cond.add_source("x", f32::fact(&[2]))?;
+        cond.add_source("y", f32::fact(&[2]))?;
+        let stop = cond.add_const("stop", rctensor0(false))?;
+        cond.set_output_outlets(&[stop])?;
+        let mut body = TypedModel::default();
+        let x = body.add_source("x", f32::fact(&[2]))?;
+        let y = body.add_source("y", f32::fact(&[2]))?;
+        let same = body.wire_node("same", TypedConcat::concat_vars(0, 1), &[x])?;
+        let grown = body.wire_node("grown", TypedConcat::concat_vars(0, 2), &[y, y])?;
+        body.set_output_outlets(&[same[0], grown[0]])?;
+        let op = super::TypedWhile::new(cond, body)?;
+        let input = f32::fact(&[2]);
+        let facts = op.output_facts(&[&input, &input])?;
+        assert_eq!(facts[0].shape, ShapeFact::from_dims(tvec!(2.to_dim())));
+        assert!(facts[1].shape.as_concrete().is_none());
+
+        let mut model = TypedModel::default();
+        let a = model.add_source("a", f32::fact(&[2]))?;
+        let b = model.add_source("b", f32::fact(&[2]))?;
+        let outputs = model.wire_node("loop", op, &[a, b])?;
+        model.set_output_outlets(&outputs)?;
+        let mut state = SimpleState::new(SimplePlan::new(model)?)?;
+        for _ in 0..2 {
+            let outputs = state.run(tvec!(tensor1(&[1f32, 2.0]), tensor1(&[3f32, 4.0])))?;
+            assert_eq!(&*outputs[1], &tensor1(&[3f32, 4.0]));
+        }
+        Ok(())
+    }
+
+    fn if_graph() -> GraphDef {
+        let then_branch = function(
+            "then",
+            &[("x", DataType::DtFloat)],
+            &[("y", "neg:y:0")],
+            vec![node().name("neg").op("Neg").input("x")],
+        );
+        let else_branch = function("else", &[("x", DataType::DtFloat)], &[("y", "x")], vec![]);
+        GraphDef {
+            node: vec![
+                node().name("input").op("Placeholder").attr("dtype", DataType::DtFloat),
+                konst("zero", tensor0(0f32)),
+                konst("axis", tensor1(&[0i32])),
+                node()
+                    .name("min")
+                    .op("Min")
+                    .input("input")
+                    .input("axis")
+                    .attr("T", DataType::DtFloat)
+                    .attr("Tidx", DataType::DtInt32)
+                    .attr("keep_dims", false),
+                node().name("positive").op("Greater").input("min").input("zero"),
+                node()
+                    .name("if")
+                    .op("StatelessIf")
+                    .input("positive")
+                    .input("input")
+                    .attr("Tin", types(&[DataType::DtFloat]))
+                    .attr("Tout", types(&[DataType::DtFloat]))
+                    .attr("then_branch", func("then"))
+                    .attr("else_branch", func("else")),
+            ],
+            library: Some(FunctionDefLibrary {
+                function: vec![then_branch, else_branch],
+                ..Default::default()
+            }),
+            ..GraphDef::default()
+        }
+    }
+
+    #[test]
+    fn stateless_if() -> TractResult<()> {
+        let outputs = run(if_graph(), tensor1(&[1f32, 2.0]))?;
+        assert_eq!(&*outputs[0], &tensor1(&[-1f32, -2.0]));
+        let outputs = run(if_graph(), tensor1(&[-1f32, 2.0]))?;
+        assert_eq!(&*outputs[0], &tensor1(&[-1f32, 2.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn constant_if_is_inlined() -> TractResult<()> {
+        let mut graph = if_graph();
+        graph.node.push(konst("one", tensor0(1f32)));
+        graph.node.push(node().name("true").op("Greater").input("one").input("zero"));
+        graph.node.iter_mut().find(|n| n.name == "if").unwrap().input[0] = "true".to_string();
+        let tf = crate::tensorflow();
+        let mut model = tf.parse_graph(&graph)?.0;
+        model.set_input_fact(0, InferenceFact::dt_shape(f32::datum_type(), [2]))?;
+        model.set_output_names(["if"])?;
+        let model = model.into_optimized()?;
+        assert!(model.nodes().iter().all(|n| n.op_as::<super::TypedIf>().is_none()));
+        let outputs = SimplePlan::new(&model)?.run(tvec!(tensor1(&[1f32, -2.0])))?;
+        assert_eq!(&*outputs[0], &tensor1(&[-1f32, 2.0]));
+        Ok(())
+    }
+}
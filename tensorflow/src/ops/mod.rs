@@ -14,6 +14,7 @@ macro_rules! op_tf {
 
 pub mod array;
 pub mod control_flow;
+pub mod functional;
 pub mod logic;
 pub mod math;
 pub mod nn;
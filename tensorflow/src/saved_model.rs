@@ -0,0 +1,597 @@
+//! SavedModel directories, as exported by TensorFlow 2.
+//!
+//! A SavedModel directory contains a `saved_model.pb` with one or more
+//! MetaGraphs, and a `variables/` checkpoint holding the variable values.
+//! Loading a model for a signature:
+//!
+//! * inlines the function calls (PartitionedCall and StatefulPartitionedCall)
+//!   TF2 exports are made of,
+//! * restores variables into constants, following the RestoreV2 ops of the
+//!   graph to map checkpoint keys to variables,
+//! * prunes the graph to what the signature outputs need, and uses the
+//!   signature inputs and outputs as the model ones.
+use std::collections::HashSet;
+use std::convert::{TryFrom, TryInto};
+use std::path::Path;
+use std::{fs, path};
+
+use prost::Message;
+use tract_hir::internal::*;
+
+use crate::function::{inline_calls, parse_ref, resolve};
+use crate::model::Tensorflow;
+use crate::tfpb::tensorflow::bundle_header_proto::Endianness;
+use crate::tfpb::tensorflow::tensor_info::Encoding;
+use crate::tfpb::tensorflow::{
+    BundleEntryProto, BundleHeaderProto, DataType, GraphDef, MetaGraphDef, NodeDef, SavedModel,
+    SignatureDef, TensorInfo,
+};
+
+pub const DEFAULT_SIGNATURE: &str = "serving_default";
+pub const SERVE_TAG: &str = "serve";
+
+const VARIABLE_OPS: &[&str] = &["VarHandleOp", "VariableV2"];
+
+/// A MetaGraph from a SavedModel directory, with its variable values.
+#[derive(Clone, Debug)]
+pub struct TfSavedModel {
+    pub meta_graph: MetaGraphDef,
+    /// Variable values, by checkpoint key.
+    pub variables: HashMap<String, Tensor>,
+}
+
+impl TfSavedModel {
+    /// Signature names, in alphabetical order.
+    pub fn signature_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.meta_graph.signature_def.keys().map(|s| &**s).collect();
+        names.sort();
+        names
+    }
+
+    pub fn signature(&self, name: &str) -> TractResult<&SignatureDef> {
+        self.meta_graph
+            .signature_def
+            .get(name)
+            .with_context(|| format!("No signature {}, found: {:?}", name, self.signature_names()))
+    }
+}
+
+impl Tensorflow {
+    /// Reads the MetaGraph matching all `tags` from a SavedModel directory.
+    pub fn open_saved_model_dir(
+        &self,
+        dir: impl AsRef<path::Path>,
+        tags: &[&str],
+    ) -> TractResult<TfSavedModel> {
+        let dir = dir.as_ref();
+        let pb = dir.join("saved_model.pb");
+        let saved = fs::read(&pb).with_context(|| format!("Reading {:?}", pb))?;
+        let saved = SavedModel::decode(&*saved)?;
+        let meta_graph = saved
+            .meta_graphs
+            .into_iter()
+            .find(|mg| {
+                let found = mg.meta_info_def.as_ref().map(|m| &*m.tags).unwrap_or(&[]);
+                tags.iter().all(|t| found.iter().any(|f| f == t))
+            })
+            .with_context(|| format!("No MetaGraph tagged with {:?} in {:?}", tags, dir))?;
+        let prefix = dir.join("variables").join("variables");
+        let variables = if path::Path::new(&format!("{}.index", prefix.display())).exists() {
+            read_bundle(&prefix)?
+        } else {
+            HashMap::new()
+        };
+        Ok(TfSavedModel { meta_graph, variables })
+    }
+
+    /// Loads the model for a signature of the serving MetaGraph.
+    pub fn model_for_saved_model_dir(
+        &self,
+        dir: impl AsRef<path::Path>,
+        signature: &str,
+    ) -> TractResult<InferenceModel> {
+        let saved = self.open_saved_model_dir(dir, &[SERVE_TAG])?;
+        self.model_for_saved_model(&saved, signature)
+    }
+
+    pub fn model_for_saved_model(
+        &self,
+        saved: &TfSavedModel,
+        signature: &str,
+    ) -> TractResult<InferenceModel> {
+        let signature = saved.signature(signature)?;
+        let mut graph =
+            saved.meta_graph.graph_def.clone().context("MetaGraph without a GraphDef")?;
+        let calls = inline_calls(&mut graph)?;
+        let keys = variable_keys(&graph)?;
+
+        let inputs = signature_tensors(&calls, &signature.inputs)?;
+        let outputs = signature_tensors(&calls, &signature.outputs)?;
+        prune(&mut graph, outputs.iter().map(|(_, o)| &**o));
+
+        let mut model = self.parse_graph(&graph)?.0;
+        for node in &graph.node {
+            if VARIABLE_OPS.contains(&&*node.op) {
+                let key = keys.get(&node.name).cloned().unwrap_or_else(|| {
+                    node.get_attr_opt_str("shared_name")
+                        .ok()
+                        .flatten()
+                        .filter(|s| !s.is_empty())
+                        .unwrap_or_else(|| node.name.clone())
+                });
+                let value = saved.variables.get(&key).with_context(|| {
+                    format!("No value for variable {} (checkpoint key {})", node.name, key)
+                })?;
+                let id = model.node_id_by_name(&node.name)?;
+                model.node_mut(id).op =
+                    Box::new(tract_hir::ops::konst::Const(value.clone().into_arc_tensor()));
+            }
+        }
+        let inputs = inputs
+            .iter()
+            .map(|(_, name)| Ok(OutletId::new(model.node_id_by_name(parse_ref(name)?.0)?, 0)))
+            .collect::<TractResult<TVec<_>>>()?;
+        model.set_input_outlets(&inputs)?;
+        let outlets = outputs
+            .iter()
+            .map(|(key, name)| {
+                let (node, slot) = parse_ref(name)?;
+                let outlet = OutletId::new(model.node_id_by_name(node)?, slot);
+                model.set_outlet_label(outlet, key.to_string())?;
+                Ok(outlet)
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        model.set_output_outlets(&outlets)?;
+        Ok(model)
+    }
+}
+
+/// Signature tensors sorted by key, with their names resolved through the
+/// inlined calls.
+fn signature_tensors<'s>(
+    calls: &HashMap<String, Vec<String>>,
+    infos: &'s HashMap<String, TensorInfo>,
+) -> TractResult<Vec<(&'s String, String)>> {
+    let mut tensors = infos
+        .iter()
+        .map(|(key, info)| match &info.encoding {
+            Some(Encoding::Name(name)) => Ok((key, resolve(calls, name))),
+            _ => bail!("Only dense tensors are supported in signatures, got {:?}", info),
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    tensors.sort();
+    Ok(tensors)
+}
+
+/// Maps variable node names to their checkpoint keys, by following the
+/// values assigned by the restore ops.
+fn variable_keys(graph: &GraphDef) -> TractResult<HashMap<String, String>> {
+    let nodes: HashMap<&str, &NodeDef> = graph.node.iter().map(|n| (&*n.name, n)).collect();
+    let mut keys = HashMap::new();
+    for node in &graph.node {
+        if (node.op != "AssignVariableOp" && node.op != "Assign") || node.input.len() < 2 {
+            continue;
+        }
+        let (mut source, mut slot) = parse_ref(&node.input[1])?;
+        while let Some(n) = nodes.get(source).filter(|n| n.op == "Identity") {
+            let r = parse_ref(&n.input[0])?;
+            source = r.0;
+            slot = r.1;
+        }
+        let restore = match nodes.get(source).filter(|n| n.op == "RestoreV2") {
+            Some(restore) => restore,
+            None => continue,
+        };
+        let names = parse_ref(&restore.input[1])?.0;
+        let names = nodes
+            .get(names)
+            .filter(|n| n.op == "Const")
+            .with_context(|| format!("RestoreV2 {} tensor names are not a constant", source))?
+            .get_attr_tensor("value")?;
+        let key = names.as_slice::<Blob>()?.get(slot).context("RestoreV2 output out of range")?;
+        let variable = parse_ref(&node.input[0])?.0;
+        keys.insert(variable.to_string(), String::from_utf8(key.to_vec())?);
+    }
+    Ok(keys)
+}
+
+/// Keeps only the nodes the outputs depend on.
+fn prune<'a>(graph: &mut GraphDef, outputs: impl Iterator<Item = &'a str>) {
+    let nodes: HashMap<&str, &NodeDef> = graph.node.iter().map(|n| (&*n.name, n)).collect();
+    let mut kept: HashSet<String> = HashSet::new();
+    let mut todo: Vec<&str> =
+        outputs.filter_map(|o| parse_ref(o).ok()).map(|(node, _)| node).collect();
+    while let Some(name) = todo.pop() {
+        if kept.insert(name.to_string()) {
+            if let Some(node) = nodes.get(name) {
+                for input in node.input.iter().filter(|i| !i.starts_with('^')) {
+                    if let Ok((input, _)) = parse_ref(input) {
+                        todo.push(input)
+                    }
+                }
+            }
+        }
+    }
+    graph.node.retain(|n| kept.contains(&n.name));
+    for node in &mut graph.node {
+        node.input.retain(|i| !i.starts_with('^') || kept.contains(&i[1..]));
+    }
+}
+
+/// Reads a tensor bundle (a TF checkpoint), skipping non-numeric tensors.
+fn read_bundle(prefix: &Path) -> TractResult<HashMap<String, Tensor>> {
+    let index = format!("{}.index", prefix.display());
+    let index = fs::read(&index).with_context(|| format!("Reading {}", index))?;
+    let entries = read_table(&index).context("Reading checkpoint index")?;
+    let header = entries.iter().find(|(k, _)| k.is_empty()).context("No checkpoint header")?;
+    let header = BundleHeaderProto::decode(&*header.1)?;
+    ensure!(
+        header.endianness == Endianness::Little as i32,
+        "Big endian checkpoints are not supported"
+    );
+    let shards = (0..header.num_shards)
+        .map(|shard| {
+            let path =
+                format!("{}.data-{:05}-of-{:05}", prefix.display(), shard, header.num_shards);
+            fs::read(&path).with_context(|| format!("Reading {}", path))
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let mut tensors = HashMap::new();
+    for (key, value) in entries.iter().filter(|(k, _)| !k.is_empty()) {
+        let key = String::from_utf8(key.to_vec())?;
+        let entry = BundleEntryProto::decode(&**value)?;
+        let dt = match DataType::from_i32(entry.dtype).and_then(|dt| DatumType::try_from(dt).ok()) {
+            Some(dt) if dt != DatumType::Blob => dt,
+            _ => continue,
+        };
+        let shape: TVec<usize> = entry.shape.as_ref().context("Missing shape")?.try_into()?;
+        let shard = shards.get(entry.shard_id as usize).context("Invalid shard")?;
+        let bytes = shard
+            .get(entry.offset as usize..(entry.offset + entry.size) as usize)
+            .with_context(|| format!("Truncated data for {}", key))?;
+        ensure!(
+            bytes.len() == shape.iter().product::<usize>() * dt.size_of(),
+            "Inconsistent size for {}",
+            key
+        );
+        tensors.insert(key, unsafe { Tensor::from_raw_dt(dt, &shape, bytes)? });
+    }
+    Ok(tensors)
+}
+
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_LEN: usize = 48;
+
+/// Reads all the entries of an uncompressed LevelDB-style table, as used for
+/// checkpoint indexes.
+fn read_table(bytes: &[u8]) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    ensure!(bytes.len() >= FOOTER_LEN, "Truncated table");
+    let footer = &bytes[bytes.len() - FOOTER_LEN..];
+    ensure!(
+        u64::from_le_bytes(footer[40..48].try_into().unwrap()) == TABLE_MAGIC,
+        "Not a table (wrong magic number)"
+    );
+    let mut pos = 0;
+    let _metaindex = (varint(footer, &mut pos)?, varint(footer, &mut pos)?);
+    let index = (varint(footer, &mut pos)?, varint(footer, &mut pos)?);
+    let mut entries = vec![];
+    for (_, handle) in read_block(bytes, index)? {
+        let mut pos = 0;
+        let handle = (varint(&handle, &mut pos)?, varint(&handle, &mut pos)?);
+        entries.extend(read_block(bytes, handle)?);
+    }
+    Ok(entries)
+}
+
+fn read_block(bytes: &[u8], (offset, size): (u64, u64)) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    let (offset, size) = (offset as usize, size as usize);
+    let block = bytes.get(offset..offset + size).context("Truncated table block")?;
+    let compression = *bytes.get(offset + size).context("Truncated table block")?;
+    ensure!(compression == 0, "Compressed tables are not supported");
+    ensure!(block.len() >= 4, "Truncated table block");
+    let restarts = u32::from_le_bytes(block[block.len() - 4..].try_into().unwrap()) as usize;
+    let end = block.len().checked_sub(4 * (restarts + 1)).context("Invalid table block")?;
+    let mut entries = vec![];
+    let mut key: Vec<u8> = vec![];
+    let mut pos = 0;
+    while pos < end {
+        let shared = varint(block, &mut pos)? as usize;
+        let non_shared = varint(block, &mut pos)? as usize;
+        let value_len = varint(block, &mut pos)? as usize;
+        ensure!(shared <= key.len() && pos + non_shared + value_len <= end, "Invalid table entry");
+        key.truncate(shared);
+        key.extend_from_slice(&block[pos..pos + non_shared]);
+        pos += non_shared;
+        entries.push((key.clone(), block[pos..pos + value_len].to_vec()));
+        pos += value_len;
+    }
+    Ok(entries)
+}
+
+fn varint(bytes: &[u8], pos: &mut usize) -> TractResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos).context("Truncated varint")?;
+        *pos += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Invalid varint")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tfpb::node;
+    use crate::tfpb::tensorflow::attr_value::Value;
+    use crate::tfpb::tensorflow::meta_graph_def::MetaInfoDef;
+    use crate::tfpb::tensorflow::op_def::ArgDef;
+    use crate::tfpb::tensorflow::tensor_shape_proto::Dim;
+    use crate::tfpb::tensorflow::{
+        AttrValue, FunctionDef, FunctionDefLibrary, NameAttrList, OpDef, TensorProto,
+        TensorShapeProto,
+    };
+
+    const KERNEL_KEY: &str = "layer_with_weights-0/kernel/.ATTRIBUTES/VARIABLE_VALUE";
+
+    fn shape(dims: &[i64]) -> TensorShapeProto {
+        TensorShapeProto {
+            dim: dims.iter().map(|&size| Dim { size, name: String::new() }).collect(),
+            unknown_rank: false,
+        }
+    }
+
+    fn call(name: &str, f: &str, inputs: &[&str]) -> NodeDef {
+        let func = NameAttrList { name: f.to_string(), attr: HashMap::new() };
+        let mut n = node().name(name).op("StatefulPartitionedCall");
+        n.attr.insert("f".to_string(), AttrValue { value: Some(Value::Func(func)) });
+        inputs.iter().fold(n, |n, i| n.input(i))
+    }
+
+    fn function(
+        name: &str,
+        inputs: &[&str],
+        nodes: Vec<NodeDef>,
+        ret: &[(&str, &str)],
+    ) -> FunctionDef {
+        let arg = |name: &str| ArgDef { name: name.to_string(), ..ArgDef::default() };
+        FunctionDef {
+            signature: Some(OpDef {
+                name: name.to_string(),
+                input_arg: inputs.iter().map(|i| arg(i)).collect(),
+                output_arg: ret.iter().map(|(o, _)| arg(o)).collect(),
+                ..OpDef::default()
+            }),
+            node_def: nodes,
+            ret: ret.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            attr: HashMap::new(),
+        }
+    }
+
+    /// The graph of a TF2 export of a single bias-less dense layer: the
+    /// signature function calls the layer function, and the variable is
+    /// restored by a restore function.
+    fn graph() -> GraphDef {
+        let wrapper = function(
+            "__inference_signature_wrapper_10",
+            &["x", "unknown"],
+            vec![call("dense", "__inference_dense_5", &["x", "unknown"])],
+            &[("output_0", "dense:output:0")],
+        );
+        let dense = function(
+            "__inference_dense_5",
+            &["inputs", "matmul_readvariableop_resource"],
+            vec![
+                node()
+                    .name("MatMul/ReadVariableOp")
+                    .op("ReadVariableOp")
+                    .input("matmul_readvariableop_resource"),
+                node()
+                    .name("MatMul")
+                    .op("MatMul")
+                    .input("inputs")
+                    .input("MatMul/ReadVariableOp:value:0")
+                    .attr("transpose_a", false)
+                    .attr("transpose_b", false),
+                node().name("Identity").op("Identity").input("MatMul:product:0"),
+            ],
+            &[("identity", "Identity:output:0")],
+        );
+        let names = TensorProto {
+            dtype: DataType::DtString.into(),
+            tensor_shape: Some(shape(&[1])),
+            string_val: vec![KERNEL_KEY.as_bytes().to_vec()],
+            ..TensorProto::default()
+        };
+        let restore = function(
+            "__inference__traced_restore_20",
+            &["file_prefix", "assignvariableop_resource"],
+            vec![
+                node().name("RestoreV2/tensor_names").op("Const").attr("value", names),
+                node()
+                    .name("RestoreV2")
+                    .op("RestoreV2")
+                    .input("file_prefix")
+                    .input("RestoreV2/tensor_names:output:0"),
+                node().name("Identity").op("Identity").input("RestoreV2:tensors:0"),
+                node()
+                    .name("AssignVariableOp")
+                    .op("AssignVariableOp")
+                    .input("assignvariableop_resource")
+                    .input("Identity:output:0"),
+            ],
+            &[],
+        );
+        GraphDef {
+            node: vec![
+                node()
+                    .name("serving_default_x")
+                    .op("Placeholder")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[1, 2])),
+                node()
+                    .name("dense/kernel")
+                    .op("VarHandleOp")
+                    .attr("dtype", DataType::DtFloat)
+                    .attr("shape", shape(&[2, 2]))
+                    .attr("shared_name", "dense/kernel"),
+                call(
+                    "StatefulPartitionedCall",
+                    "__inference_signature_wrapper_10",
+                    &["serving_default_x", "dense/kernel"],
+                ),
+                node().name("saver_filename").op("Placeholder").attr("dtype", DataType::DtString),
+                call(
+                    "StatefulPartitionedCall_1",
+                    "__inference__traced_restore_20",
+                    &["saver_filename", "dense/kernel"],
+                ),
+            ],
+            library: Some(FunctionDefLibrary {
+                function: vec![wrapper, dense, restore],
+                gradient: vec![],
+            }),
+            ..GraphDef::default()
+        }
+    }
+
+    fn put_varint(buf: &mut Vec<u8>, v: usize) {
+        prost::encoding::encode_varint(v as u64, buf)
+    }
+
+    fn block(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut block = vec![];
+        let mut previous: &[u8] = &[];
+        for (key, value) in entries {
+            let shared = key.iter().zip(previous.iter()).take_while(|(a, b)| a == b).count();
+            put_varint(&mut block, shared);
+            put_varint(&mut block, key.len() - shared);
+            put_varint(&mut block, value.len());
+            block.extend(&key[shared..]);
+            block.extend(value);
+            previous = key;
+        }
+        block.extend(0u32.to_le_bytes());
+        block.extend(1u32.to_le_bytes());
+        block
+    }
+
+    fn handle(table: &mut Vec<u8>, block: Vec<u8>) -> Vec<u8> {
+        let mut handle = vec![];
+        put_varint(&mut handle, table.len());
+        put_varint(&mut handle, block.len());
+        table.extend(block);
+        table.extend([0u8; 5]);
+        handle
+    }
+
+    fn table(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut table = vec![];
+        let data = handle(&mut table, block(entries));
+        let metaindex = handle(&mut table, block(&[]));
+        let index = handle(&mut table, block(&[(entries.last().unwrap().0.clone(), data)]));
+        let mut footer = [metaindex, index].concat();
+        footer.resize(40, 0);
+        footer.extend(TABLE_MAGIC.to_le_bytes());
+        table.extend(footer);
+        table
+    }
+
+    fn encode(m: &impl Message) -> Vec<u8> {
+        let mut buf = vec![];
+        m.encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn saved_model_dir(name: &str) -> TractResult<path::PathBuf> {
+        let dir =
+            std::env::temp_dir().join(format!("tract-saved-model-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("variables"))?;
+        let tensor_info = |name: &str| TensorInfo {
+            encoding: Some(Encoding::Name(name.to_string())),
+            ..TensorInfo::default()
+        };
+        let signature = SignatureDef {
+            inputs: vec![("x".to_string(), tensor_info("serving_default_x:0"))]
+                .into_iter()
+                .collect(),
+            outputs: vec![("output_0".to_string(), tensor_info("StatefulPartitionedCall:0"))]
+                .into_iter()
+                .collect(),
+            method_name: "tensorflow/serving/predict".to_string(),
+        };
+        let meta_graph = MetaGraphDef {
+            meta_info_def: Some(MetaInfoDef {
+                tags: vec![SERVE_TAG.to_string()],
+                ..MetaInfoDef::default()
+            }),
+            graph_def: Some(graph()),
+            signature_def: vec![(DEFAULT_SIGNATURE.to_string(), signature)].into_iter().collect(),
+            ..MetaGraphDef::default()
+        };
+        let saved = SavedModel { saved_model_schema_version: 1, meta_graphs: vec![meta_graph] };
+        fs::write(dir.join("saved_model.pb"), encode(&saved))?;
+
+        let kernel: Vec<u8> = [1f32, 2.0, 3.0, 4.0].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let header = BundleHeaderProto { num_shards: 1, ..BundleHeaderProto::default() };
+        let object_graph = BundleEntryProto {
+            dtype: DataType::DtString.into(),
+            shape: Some(shape(&[])),
+            offset: 16,
+            size: 4,
+            ..BundleEntryProto::default()
+        };
+        let kernel_entry = BundleEntryProto {
+            dtype: DataType::DtFloat.into(),
+            shape: Some(shape(&[2, 2])),
+            size: 16,
+            ..BundleEntryProto::default()
+        };
+        let index = table(&[
+            (vec![], encode(&header)),
+            (b"_CHECKPOINTABLE_OBJECT_GRAPH".to_vec(), encode(&object_graph)),
+            (KERNEL_KEY.as_bytes().to_vec(), encode(&kernel_entry)),
+        ]);
+        fs::write(dir.join("variables/variables.index"), index)?;
+        fs::write(
+            dir.join("variables/variables.data-00000-of-00001"),
+            [kernel, vec![0; 4]].concat(),
+        )?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn table_roundtrip() -> TractResult<()> {
+        let entries = vec![
+            (b"".to_vec(), b"header".to_vec()),
+            (b"abc".to_vec(), b"1".to_vec()),
+            (b"abd".to_vec(), b"".to_vec()),
+            (b"b".to_vec(), b"3".to_vec()),
+        ];
+        assert_eq!(read_table(&table(&entries))?, entries);
+        Ok(())
+    }
+
+    #[test]
+    fn load_dense() -> TractResult<()> {
+        let dir = saved_model_dir("dense")?;
+        let tf = crate::tensorflow();
+        let saved = tf.open_saved_model_dir(&dir, &[SERVE_TAG])?;
+        assert_eq!(saved.signature_names(), vec![DEFAULT_SIGNATURE]);
+        assert_eq!(saved.variables.len(), 1);
+        assert!(tf.open_saved_model_dir(&dir, &["train"]).is_err());
+
+        let model = tf.model_for_saved_model(&saved, DEFAULT_SIGNATURE)?;
+        assert_eq!(model.input_outlets()?.len(), 1);
+        assert_eq!(model.nodes().len(), 5);
+        let model = model.into_optimized()?;
+        let output = model.into_runnable()?.run(tvec!(tensor2(&[[1f32, 1.0]])))?;
+        assert_eq!(*output[0], tensor2(&[[4f32, 6.0]]));
+        assert!(tf.model_for_saved_model(&saved, "other").is_err());
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
@@ -222,6 +222,40 @@ impl NodeDef {
         };
         Ok(None)
     }
+    pub fn get_attr_list_datum_type(&self, name: &str) -> TractResult<Vec<DatumType>> {
+        Ok(self.get_attr_opt_list_datum_type(name)?.with_context(|| {
+            format!("Node {} ({}) expected list<type> attribute '{}'", self.name, self.op, name)
+        })?)
+    }
+
+    pub fn get_attr_opt_list_datum_type(&self, name: &str) -> TractResult<Option<Vec<DatumType>>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::List(list) = a.value.as_ref().unwrap() {
+                return Ok(Some(
+                    list.r#type
+                        .iter()
+                        .map(|&t| DataType::from_i32(t).unwrap().try_into())
+                        .collect::<TractResult<_>>()?,
+                ));
+            }
+        };
+        Ok(None)
+    }
+
+    pub fn get_attr_func(&self, name: &str) -> TractResult<&str> {
+        Ok(self.get_attr_opt_func(name)?.with_context(|| {
+            format!("Node {} ({}) expected func attribute '{}'", self.name, self.op, name)
+        })?)
+    }
+
+    pub fn get_attr_opt_func(&self, name: &str) -> TractResult<Option<&str>> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::Func(f) = a.value.as_ref().unwrap() {
+                return Ok(Some(&f.name));
+            }
+        };
+        Ok(None)
+    }
 }
 
 impl From<DataType> for AttrValue {
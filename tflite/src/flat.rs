@@ -0,0 +1,343 @@
+//! A minimal reader and writer for the flatbuffers binary format.
+//!
+//! Only what is needed to walk a TFLite model is implemented: tables, scalar
+//! fields, strings, and vectors of scalars or tables. Every access is bounds
+//! and alignment checked, so a corrupted file results in an error, not a
+//! panic.
+use std::convert::TryInto;
+use tract_hir::internal::*;
+
+pub trait Scalar: Copy {
+    const SIZE: usize;
+    fn read(bytes: &[u8]) -> Self;
+    fn write(self, out: &mut Vec<u8>);
+}
+
+macro_rules! scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                fn read(bytes: &[u8]) -> $t {
+                    <$t>::from_le_bytes(bytes[..Self::SIZE].try_into().unwrap())
+                }
+                fn write(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+scalar!(u8, i8, u16, i16, u32, i32, u64, i64, f32);
+
+impl Scalar for bool {
+    const SIZE: usize = 1;
+    fn read(bytes: &[u8]) -> bool {
+        bytes[0] != 0
+    }
+    fn write(self, out: &mut Vec<u8>) {
+        out.push(self as u8)
+    }
+}
+
+fn slice(buf: &[u8], pos: usize, len: usize) -> TractResult<&[u8]> {
+    pos.checked_add(len).and_then(|end| buf.get(pos..end)).with_context(|| {
+        format!("Reading {} bytes at {} out of a {} bytes buffer", len, pos, buf.len())
+    })
+}
+
+/// Like the flatbuffers verifier, refuses values not aligned to their size.
+fn aligned<T: Scalar>(pos: usize) -> TractResult<()> {
+    ensure!(pos % T::SIZE == 0, "Misaligned {} bytes value at {}", T::SIZE, pos);
+    Ok(())
+}
+
+fn read<T: Scalar>(buf: &[u8], pos: usize) -> TractResult<T> {
+    aligned::<T>(pos)?;
+    Ok(T::read(slice(buf, pos, T::SIZE)?))
+}
+
+/// Follows the unsigned offset stored at `pos`.
+fn follow(buf: &[u8], pos: usize) -> TractResult<usize> {
+    let offset = read::<u32>(buf, pos)? as usize;
+    pos.checked_add(offset).context("Offset overflow")
+}
+
+/// Position of the first element and length of the vector referenced at `pos`.
+fn vector_at(buf: &[u8], pos: usize) -> TractResult<(usize, usize)> {
+    let start = follow(buf, pos)?;
+    let len = read::<u32>(buf, start)? as usize;
+    Ok((start + 4, len))
+}
+
+#[derive(Clone, Copy)]
+pub struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> std::fmt::Debug for Table<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Table@{}", self.pos)
+    }
+}
+
+impl<'a> Table<'a> {
+    /// The root table of a buffer.
+    pub fn root(buf: &'a [u8]) -> TractResult<Table<'a>> {
+        Ok(Table { buf, pos: follow(buf, 0)? })
+    }
+
+    fn field_pos(&self, field: usize) -> TractResult<Option<usize>> {
+        let vtable = self.pos as i64 - read::<i32>(self.buf, self.pos)? as i64;
+        ensure!(vtable >= 0, "Invalid vtable offset for table at {}", self.pos);
+        let vtable = vtable as usize;
+        let vtable_size = read::<u16>(self.buf, vtable)? as usize;
+        let entry = 4 + 2 * field;
+        if entry + 2 > vtable_size {
+            return Ok(None);
+        }
+        let offset = read::<u16>(self.buf, vtable + entry)? as usize;
+        Ok(Some(self.pos + offset).filter(|_| offset != 0))
+    }
+
+    pub fn scalar<T: Scalar>(&self, field: usize, default: T) -> TractResult<T> {
+        if let Some(pos) = self.field_pos(field)? {
+            read(self.buf, pos)
+        } else {
+            Ok(default)
+        }
+    }
+
+    pub fn table(&self, field: usize) -> TractResult<Option<Table<'a>>> {
+        if let Some(pos) = self.field_pos(field)? {
+            Ok(Some(Table { buf: self.buf, pos: follow(self.buf, pos)? }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A vector of bytes, empty if the field is absent.
+    pub fn bytes(&self, field: usize) -> TractResult<&'a [u8]> {
+        if let Some(pos) = self.field_pos(field)? {
+            let (start, len) = vector_at(self.buf, pos)?;
+            slice(self.buf, start, len)
+        } else {
+            Ok(&[])
+        }
+    }
+
+    pub fn string(&self, field: usize) -> TractResult<Option<&'a str>> {
+        if self.field_pos(field)?.is_some() {
+            Ok(Some(std::str::from_utf8(self.bytes(field)?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A vector of scalars, empty if the field is absent.
+    pub fn vector<T: Scalar>(&self, field: usize) -> TractResult<Vec<T>> {
+        if let Some(pos) = self.field_pos(field)? {
+            let (start, len) = vector_at(self.buf, pos)?;
+            aligned::<T>(start)?;
+            let data = slice(self.buf, start, len.checked_mul(T::SIZE).context("Overflow")?)?;
+            Ok(data.chunks(T::SIZE).map(T::read).collect())
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// A vector of tables, empty if the field is absent.
+    pub fn tables(&self, field: usize) -> TractResult<Vec<Table<'a>>> {
+        if let Some(pos) = self.field_pos(field)? {
+            let (start, len) = vector_at(self.buf, pos)?;
+            (0..len)
+                .map(|ix| Ok(Table { buf: self.buf, pos: follow(self.buf, start + 4 * ix)? }))
+                .collect()
+        } else {
+            Ok(vec![])
+        }
+    }
+}
+
+/// Serializes flatbuffers.
+///
+/// Tables are described as a list of fields, in field id order, then written
+/// recursively: vtable first, table next, and children after the table. As
+/// flatc does, table fields are laid out largest first, each aligned to its
+/// size.
+pub mod builder {
+    use super::Scalar;
+
+    pub enum Field {
+        Absent,
+        Scalar(Vec<u8>),
+        String(String),
+        /// element size and raw little endian elements
+        Vector(usize, Vec<u8>),
+        Table(Obj),
+        Tables(Vec<Obj>),
+    }
+
+    pub struct Obj(pub Vec<Field>);
+
+    pub fn scalar<T: Scalar>(t: T) -> Field {
+        let mut bytes = vec![];
+        t.write(&mut bytes);
+        Field::Scalar(bytes)
+    }
+
+    pub fn vector<T: Scalar>(ts: &[T]) -> Field {
+        let mut bytes = vec![];
+        for t in ts {
+            t.write(&mut bytes);
+        }
+        Field::Vector(T::SIZE, bytes)
+    }
+
+    pub fn string(s: &str) -> Field {
+        Field::String(s.to_string())
+    }
+
+    fn patch(buf: &mut [u8], at: usize, target: usize) {
+        buf[at..at + 4].copy_from_slice(&((target - at) as u32).to_le_bytes())
+    }
+
+    fn align(buf: &mut Vec<u8>, alignment: usize) {
+        while buf.len() % alignment != 0 {
+            buf.push(0)
+        }
+    }
+
+    fn write_vector(buf: &mut Vec<u8>, len: usize, bytes: &[u8]) -> usize {
+        // vector content is 16-byte aligned, so that runtimes can use constant
+        // buffers in place
+        while (buf.len() + 4) % 16 != 0 {
+            buf.push(0)
+        }
+        let pos = buf.len();
+        (len as u32).write(buf);
+        buf.extend_from_slice(bytes);
+        pos
+    }
+
+    fn write_table(buf: &mut Vec<u8>, obj: &Obj) -> usize {
+        // scalars are aligned to their size, offsets to 4 bytes
+        let field_size = |field: &Field| match field {
+            Field::Absent => 0,
+            Field::Scalar(bytes) => bytes.len(),
+            _ => 4,
+        };
+        let mut by_size: Vec<usize> = (0..obj.0.len()).collect();
+        by_size.sort_by_key(|&ix| std::cmp::Reverse(field_size(&obj.0[ix])));
+        let mut offsets = vec![0; obj.0.len()];
+        let mut size = 4;
+        for &ix in &by_size {
+            let field_size = field_size(&obj.0[ix]);
+            if field_size > 0 {
+                size = (size + field_size - 1) / field_size * field_size;
+                offsets[ix] = size;
+                size += field_size;
+            }
+        }
+        let alignment = obj.0.iter().map(field_size).max().unwrap_or(0).max(4);
+        align(buf, 2);
+        let vtable = buf.len();
+        ((4 + 2 * offsets.len()) as u16).write(buf);
+        (size as u16).write(buf);
+        for offset in &offsets {
+            (*offset as u16).write(buf);
+        }
+        align(buf, alignment);
+        let table = buf.len();
+        ((table - vtable) as i32).write(buf);
+        buf.resize(table + size, 0);
+        let mut deferred = vec![];
+        for (field, offset) in obj.0.iter().zip(offsets.iter()) {
+            let at = table + offset;
+            match field {
+                Field::Absent => (),
+                Field::Scalar(bytes) => buf[at..at + bytes.len()].copy_from_slice(bytes),
+                other => deferred.push((at, other)),
+            }
+        }
+        for (at, field) in deferred {
+            let target = match field {
+                Field::String(s) => {
+                    let mut bytes = s.as_bytes().to_vec();
+                    bytes.push(0);
+                    write_vector(buf, s.len(), &bytes)
+                }
+                Field::Vector(size, bytes) => write_vector(buf, bytes.len() / size, bytes),
+                Field::Table(obj) => write_table(buf, obj),
+                Field::Tables(objs) => {
+                    let pos = write_vector(buf, objs.len(), &vec![0u8; 4 * objs.len()]);
+                    for (ix, obj) in objs.iter().enumerate() {
+                        let child = write_table(buf, obj);
+                        patch(buf, pos + 4 + 4 * ix, child);
+                    }
+                    pos
+                }
+                _ => unreachable!(),
+            };
+            patch(buf, at, target);
+        }
+        table
+    }
+
+    /// Serializes a root table with the given file identifier.
+    pub fn finish(root: &Obj, identifier: &[u8; 4]) -> Vec<u8> {
+        let mut buf = vec![0u8; 4];
+        buf.extend_from_slice(identifier);
+        let table = write_table(&mut buf, root);
+        patch(&mut buf, 0, table);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::builder::*;
+    use super::*;
+
+    #[test]
+    fn aligned_fields() -> TractResult<()> {
+        let obj = Obj(vec![
+            scalar(1i8),
+            scalar(2i32),
+            string("name"),
+            scalar(3u16),
+            scalar(4i64),
+            Field::Absent,
+            scalar(5u32),
+        ]);
+        let buf = finish(&obj, b"TEST");
+        let table = Table::root(&buf)?;
+        assert_eq!(table.scalar(0, 0i8)?, 1);
+        assert_eq!(table.scalar(1, 0i32)?, 2);
+        assert_eq!(table.string(2)?, Some("name"));
+        assert_eq!(table.scalar(3, 0u16)?, 3);
+        assert_eq!(table.scalar(4, 0i64)?, 4);
+        assert_eq!(table.scalar(5, 7i32)?, 7);
+        assert_eq!(table.scalar(6, 0u32)?, 5);
+        for (field, size) in &[(0, 1), (1, 4), (2, 4), (3, 2), (4, 8), (6, 4)] {
+            assert_eq!(table.field_pos(*field)?.unwrap() % size, 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn misaligned_field() -> TractResult<()> {
+        let buf = finish(&Obj(vec![scalar(1i8), scalar(2i32)]), b"TEST");
+        let table = Table::root(&buf)?;
+        let pos = table.field_pos(1)?.unwrap();
+        let mut shifted = buf.clone();
+        // point field 1 one byte off
+        let vtable = table.pos - read::<i32>(&buf, table.pos)? as usize;
+        shifted[vtable + 6..vtable + 8]
+            .copy_from_slice(&((pos - table.pos + 1) as u16).to_le_bytes());
+        assert!(Table::root(&shifted)?.scalar(1, 0i32).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+#[macro_use]
+extern crate educe;
+#[allow(unused_imports)]
+#[macro_use]
+extern crate log;
+
+pub mod flat;
+pub mod model;
+pub mod ops;
+pub mod schema;
+pub mod ser;
+pub mod tensor;
+
+pub use model::Tflite;
+pub use model::TfliteProtoModel;
+
+pub use tract_hir::tract_core;
+
+pub mod prelude {
+    pub use crate::tflite;
+    pub use tract_hir::prelude::*;
+}
+
+pub fn tflite() -> Tflite {
+    let mut tflite = Tflite::default();
+    ops::register_all_ops(&mut tflite.op_register);
+    tflite
+}
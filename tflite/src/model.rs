@@ -0,0 +1,434 @@
+use tract_hir::internal::*;
+
+use crate::schema;
+use crate::tensor;
+
+/// A TFLite model, kept as its flatbuffer bytes.
+#[derive(Clone, Debug)]
+pub struct TfliteProtoModel {
+    pub bytes: Vec<u8>,
+}
+
+impl TfliteProtoModel {
+    pub fn new(bytes: Vec<u8>) -> TractResult<TfliteProtoModel> {
+        let proto = TfliteProtoModel { bytes };
+        proto.model()?;
+        Ok(proto)
+    }
+
+    pub fn model(&self) -> TractResult<schema::Model> {
+        schema::Model::root(&self.bytes)
+    }
+}
+
+pub struct ParsingContext<'a> {
+    pub model: schema::Model<'a>,
+    pub subgraph: schema::SubGraph<'a>,
+    pub tensors: Vec<schema::Tensor<'a>>,
+    pub buffers: Vec<schema::Buffer<'a>>,
+}
+
+impl<'a> ParsingContext<'a> {
+    pub fn tensor(&self, ix: i32) -> TractResult<&schema::Tensor<'a>> {
+        self.tensors.get(ix as usize).with_context(|| format!("No tensor {} in subgraph", ix))
+    }
+
+    /// Datum type of the tensor of an operator output.
+    pub fn output_datum_type(&self, op: &schema::Operator, slot: usize) -> TractResult<DatumType> {
+        let outputs = op.outputs()?;
+        let ix = outputs.get(slot).with_context(|| format!("Operator has no output {}", slot))?;
+        tensor::datum_type(self.tensor(*ix)?)
+    }
+
+    /// Value of an operator input, if it is a constant.
+    pub fn input_konst(&self, op: &schema::Operator, slot: usize) -> TractResult<Option<Tensor>> {
+        let inputs = op.inputs()?;
+        let ix = inputs.get(slot).with_context(|| format!("Operator has no input {}", slot))?;
+        let t = self.tensor(*ix)?;
+        let name = t.name()?;
+        let data = self
+            .buffers
+            .get(t.buffer()? as usize)
+            .with_context(|| format!("Invalid buffer index for {}", name))?
+            .data()?;
+        if data.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(tensor::konst(t, data)?))
+        }
+    }
+
+    /// Shape of the tensor of an operator output.
+    pub fn output_shape(&self, op: &schema::Operator, slot: usize) -> TractResult<TVec<usize>> {
+        let outputs = op.outputs()?;
+        let ix = outputs.get(slot).with_context(|| format!("Operator has no output {}", slot))?;
+        tensor::shape(self.tensor(*ix)?)
+    }
+}
+
+pub type TfliteOpBuilder =
+    fn(&ParsingContext, &schema::Operator) -> TractResult<Box<dyn InferenceOp>>;
+
+#[derive(Clone, Default)]
+pub struct TfliteOpRegister(pub HashMap<i32, TfliteOpBuilder>);
+
+impl TfliteOpRegister {
+    pub fn insert(&mut self, code: i32, builder: TfliteOpBuilder) {
+        self.0.insert(code, builder);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Tflite {
+    pub op_register: TfliteOpRegister,
+}
+
+impl Tflite {
+    /// Serialize a decluttered TypedModel as a TFLite flatbuffer.
+    pub fn write(&self, model: &TypedModel, mut w: impl std::io::Write) -> TractResult<()> {
+        w.write_all(&self.write_to_bytes(model)?)?;
+        Ok(())
+    }
+
+    pub fn write_to_bytes(&self, model: &TypedModel) -> TractResult<Vec<u8>> {
+        crate::ser::to_tflite(model)
+    }
+}
+
+impl Framework<TfliteProtoModel, InferenceModel> for Tflite {
+    fn proto_model_for_read(&self, r: &mut dyn std::io::Read) -> TractResult<TfliteProtoModel> {
+        let mut v = vec![];
+        r.read_to_end(&mut v)?;
+        TfliteProtoModel::new(v)
+    }
+
+    fn model_for_proto_model(&self, proto: &TfliteProtoModel) -> TractResult<InferenceModel> {
+        let model = proto.model()?;
+        let subgraph = *model.subgraphs()?.get(0).context("Model has no subgraph")?;
+        let codes = model.operator_codes()?;
+        let ctx = ParsingContext {
+            model,
+            subgraph,
+            tensors: subgraph.tensors()?,
+            buffers: model.buffers()?,
+        };
+
+        let mut target = InferenceModel::default();
+        let mut outlets = HashMap::<i32, OutletId>::new();
+        for input in subgraph.inputs()? {
+            let t = ctx.tensor(input)?;
+            let id = target.add_source(t.name()?, tensor::fact(t)?)?;
+            outlets.insert(input, id);
+        }
+        for (ix, op) in subgraph.operators()?.iter().enumerate() {
+            let code = codes
+                .get(op.opcode_index()? as usize)
+                .with_context(|| format!("Invalid opcode index for operator #{}", ix))?;
+            let builtin = code.builtin_code()?;
+            let outputs = op.outputs()?;
+            let name = if let Some(first) = outputs.get(0) {
+                ctx.tensor(*first)?.name()?.to_string()
+            } else {
+                format!("operator_{}", ix)
+            };
+            let op_name = if let Some(custom) = code.custom_code()? {
+                custom.to_string()
+            } else {
+                schema::builtin::name(builtin)
+            };
+            let inference_op = if let Some(builder) = self.op_register.0.get(&builtin) {
+                (builder)(&ctx, op)
+                    .with_context(|| format!("Translating {} ({})", name, op_name))?
+            } else {
+                Box::new(tract_hir::ops::unimpl::UnimplementedOp::new(
+                    outputs.len(),
+                    op_name,
+                    format!("{:?}", op),
+                ))
+            };
+            let facts = outputs
+                .iter()
+                .map(|o| tensor::fact(ctx.tensor(*o)?))
+                .collect::<TractResult<TVec<_>>>()?;
+            let id = target.add_node(&*name, inference_op, facts)?;
+            let inputs = op.inputs()?;
+            let present = inputs.iter().enumerate().filter(|(_, i)| **i >= 0);
+            for (inlet, (slot, input)) in present.enumerate() {
+                let outlet = if let Some(outlet) = outlets.get(input) {
+                    *outlet
+                } else {
+                    let name = ctx.tensor(*input)?.name()?;
+                    let konst = ctx.input_konst(op, slot)?.with_context(|| {
+                        format!("Tensor {} is neither computed nor constant", name)
+                    })?;
+                    let outlet = target.add_const(name, konst)?;
+                    outlets.insert(*input, outlet);
+                    outlet
+                };
+                target.add_edge(outlet, InletId::new(id, inlet))?;
+            }
+            for (slot, output) in outputs.iter().enumerate() {
+                let outlet = OutletId::new(id, slot);
+                target.set_outlet_label(outlet, ctx.tensor(*output)?.name()?.to_string())?;
+                outlets.insert(*output, outlet);
+            }
+        }
+        let outputs = subgraph
+            .outputs()?
+            .iter()
+            .map(|o| {
+                outlets.get(o).cloned().with_context(|| format!("Output {} is not computed", o))
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        target.set_output_outlets(&outputs)?;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::flat::builder::*;
+    use crate::schema::{activation, builtin, padding, tensor_type};
+
+    /// Describes a tensor of a test model. Constant tensors get their own buffer.
+    pub struct T {
+        pub name: &'static str,
+        pub shape: Vec<i32>,
+        pub dt: i8,
+        pub q: Option<(f32, i64)>,
+        pub data: Option<Vec<u8>>,
+    }
+
+    pub fn t(name: &'static str, shape: &[i32]) -> T {
+        T { name, shape: shape.to_vec(), dt: tensor_type::FLOAT32, q: None, data: None }
+    }
+
+    impl T {
+        pub fn q(self, dt: i8, scale: f32, zero_point: i64) -> T {
+            T { dt, q: Some((scale, zero_point)), ..self }
+        }
+
+        pub fn data(self, dt: i8, data: Vec<u8>) -> T {
+            T { dt, data: Some(data), ..self }
+        }
+
+        pub fn f32s(self, data: &[f32]) -> T {
+            self.data(tensor_type::FLOAT32, data.iter().flat_map(|f| f.to_le_bytes()).collect())
+        }
+    }
+
+    /// An operator: builtin code, input and output tensor indices, options.
+    pub struct O(pub i32, pub Vec<i32>, pub Vec<i32>, pub Option<Obj>);
+
+    pub fn build(tensors: Vec<T>, ops: Vec<O>, inputs: &[i32], outputs: &[i32]) -> Vec<u8> {
+        let mut codes = vec![];
+        let mut buffers = vec![Obj(vec![])];
+        let mut operators = vec![];
+        for O(code, ins, outs, options) in ops {
+            let ix = codes.iter().position(|c| *c == code).unwrap_or_else(|| {
+                codes.push(code);
+                codes.len() - 1
+            });
+            operators.push(Obj(vec![
+                scalar(ix as u32),
+                vector(&ins),
+                vector(&outs),
+                Field::Absent,
+                options.map(Field::Table).unwrap_or(Field::Absent),
+            ]))
+        }
+        let tensors = tensors
+            .into_iter()
+            .map(|t| {
+                let buffer = if let Some(data) = t.data {
+                    buffers.push(Obj(vec![vector(&data)]));
+                    buffers.len() - 1
+                } else {
+                    0
+                };
+                let quant =
+                    t.q.map(|(s, z)| {
+                        Field::Table(Obj(vec![
+                            Field::Absent,
+                            Field::Absent,
+                            vector(&[s]),
+                            vector(&[z]),
+                        ]))
+                    })
+                    .unwrap_or(Field::Absent);
+                Obj(vec![
+                    vector(&t.shape),
+                    scalar(t.dt),
+                    scalar(buffer as u32),
+                    string(t.name),
+                    quant,
+                ])
+            })
+            .collect();
+        let codes = codes
+            .into_iter()
+            .map(|c| Obj(vec![scalar(c.min(127) as i8), Field::Absent, scalar(1i32), scalar(c)]))
+            .collect();
+        let subgraph = Obj(vec![
+            Field::Tables(tensors),
+            vector(inputs),
+            vector(outputs),
+            Field::Tables(operators),
+        ]);
+        let model = Obj(vec![
+            scalar(3u32),
+            Field::Tables(codes),
+            Field::Tables(vec![subgraph]),
+            Field::Absent,
+            Field::Tables(buffers),
+        ]);
+        finish(&model, schema::FILE_IDENTIFIER)
+    }
+
+    pub fn run(bytes: &[u8], input: Tensor) -> TractResult<Arc<Tensor>> {
+        let model = crate::tflite().model_for_bytes(bytes)?.into_optimized()?;
+        let mut outputs = model.into_runnable()?.run(tvec!(input))?;
+        Ok(outputs.remove(0))
+    }
+
+    fn conv_options(act: i8) -> Obj {
+        Obj(vec![scalar(padding::VALID), scalar(1i32), scalar(1i32), scalar(act)])
+    }
+
+    #[test]
+    fn not_tflite() {
+        assert!(crate::tflite().model_for_bytes(b"not a model").is_err());
+    }
+
+    #[test]
+    fn conv() -> TractResult<()> {
+        let bytes = build(
+            vec![
+                t("input", &[1, 3, 3, 1]),
+                t("kernel", &[1, 3, 3, 1]).f32s(&[1.0; 9]),
+                t("bias", &[1]).f32s(&[0.5]),
+                t("output", &[1, 1, 1, 1]),
+            ],
+            vec![O(builtin::CONV_2D, vec![0, 1, 2], vec![3], Some(conv_options(activation::RELU)))],
+            &[0],
+            &[3],
+        );
+        let input =
+            tensor1(&(1..10).map(|i| i as f32).collect::<Vec<_>>()).into_shape(&[1, 3, 3, 1])?;
+        let output = run(&bytes, input)?;
+        assert_eq!(*output, tensor1(&[45.5f32]).into_shape(&[1, 1, 1, 1])?);
+        Ok(())
+    }
+
+    #[test]
+    fn depthwise_conv() -> TractResult<()> {
+        let options = Obj(vec![
+            scalar(padding::VALID),
+            scalar(1i32),
+            scalar(1i32),
+            scalar(2i32),
+            scalar(activation::NONE),
+        ]);
+        // two input channels, multiplier 2: kernel is [1, 1, 2, 4]
+        let bytes = build(
+            vec![
+                t("input", &[1, 1, 2, 2]),
+                t("kernel", &[1, 1, 2, 4]).f32s(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
+                t("output", &[1, 1, 1, 4]),
+            ],
+            vec![O(builtin::DEPTHWISE_CONV_2D, vec![0, 1, -1], vec![2], Some(options))],
+            &[0],
+            &[2],
+        );
+        let input = tensor1(&[1f32, 10.0, 100.0, 1000.0]).into_shape(&[1, 1, 2, 2])?;
+        let output = run(&bytes, input)?;
+        // channel c, multiplier m uses input channel c / 2
+        let expected = [
+            1f32 + 5.0 * 100.0,
+            2.0 + 6.0 * 100.0,
+            3.0 * 10.0 + 7.0 * 1000.0,
+            4.0 * 10.0 + 8.0 * 1000.0,
+        ];
+        assert_eq!(*output, tensor1(&expected).into_shape(&[1, 1, 1, 4])?);
+        Ok(())
+    }
+
+    #[test]
+    fn fully_connected() -> TractResult<()> {
+        let bytes = build(
+            vec![
+                t("input", &[1, 3]),
+                t("weights", &[2, 3]).f32s(&[1.0, 2.0, 3.0, 0.0, 1.0, 0.0]),
+                t("bias", &[2]).f32s(&[0.5, -0.5]),
+                t("output", &[1, 2]),
+            ],
+            vec![O(builtin::FULLY_CONNECTED, vec![0, 1, 2], vec![3], None)],
+            &[0],
+            &[3],
+        );
+        let output = run(&bytes, tensor2(&[[1f32, 2.0, 3.0]]))?;
+        assert_eq!(*output, tensor2(&[[14.5f32, 1.5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn quantized_conv() -> TractResult<()> {
+        let bytes = build(
+            vec![
+                t("input", &[1, 3, 3, 1]).q(tensor_type::UINT8, 1.0, 0),
+                t("kernel", &[1, 3, 3, 1]).data(tensor_type::UINT8, vec![1; 9]).q(
+                    tensor_type::UINT8,
+                    1.0,
+                    0,
+                ),
+                t("output", &[1, 1, 1, 1]).q(tensor_type::UINT8, 1.0, 0),
+            ],
+            vec![O(
+                builtin::CONV_2D,
+                vec![0, 1, -1],
+                vec![2],
+                Some(conv_options(activation::NONE)),
+            )],
+            &[0],
+            &[2],
+        );
+        let qu8 = DatumType::QU8(QParams::ZpScale { zero_point: 0, scale: 1.0 });
+        let input = tensor1(&(1..10).map(|i| i as u8).collect::<Vec<_>>())
+            .into_shape(&[1, 3, 3, 1])?
+            .cast_to_dt(qu8)?
+            .into_owned();
+        let output = run(&bytes, input)?;
+        assert_eq!(output.cast_to::<u8>()?.as_slice::<u8>()?, &[45]);
+        Ok(())
+    }
+
+    #[test]
+    fn add_reshape_softmax() -> TractResult<()> {
+        let bytes = build(
+            vec![
+                t("input", &[1, 2, 2]),
+                t("offset", &[2]).f32s(&[0.0, 1.0]),
+                t("sum", &[1, 2, 2]),
+                t("shape", &[2]).data(
+                    tensor_type::INT32,
+                    [1i32, 4].iter().flat_map(|i| i.to_le_bytes()).collect(),
+                ),
+                t("flat", &[1, 4]),
+                t("output", &[1, 4]),
+            ],
+            vec![
+                O(builtin::ADD, vec![0, 1], vec![2], None),
+                O(builtin::RESHAPE, vec![2, 3], vec![4], None),
+                O(builtin::SOFTMAX, vec![4], vec![5], Some(Obj(vec![scalar(1f32)]))),
+            ],
+            &[0],
+            &[5],
+        );
+        let output = run(&bytes, tensor3(&[[[0f32, 0.0], [1.0, 1.0]]]))?;
+        let e = [1f32, 1f32.exp(), 1f32.exp(), 2f32.exp()];
+        let sum: f32 = e.iter().sum();
+        let expected = tensor2(&[[e[0] / sum, e[1] / sum, e[2] / sum, e[3] / sum]]);
+        output.close_enough(&expected, true)
+    }
+}
@@ -0,0 +1,169 @@
+use crate::model::{ParsingContext, TfliteOpRegister};
+use crate::ops::{requantize, Activation};
+use crate::schema::{self, builtin};
+use tract_hir::internal::*;
+use tract_hir::ops::array::PermuteAxes;
+use tract_hir::tract_core::ops::array::TypedConcat;
+use tract_hir::tract_core::ops::change_axes::AxisOp;
+
+pub fn register_all_ops(reg: &mut TfliteOpRegister) {
+    reg.insert(builtin::CONCATENATION, concatenation);
+    reg.insert(builtin::RESHAPE, reshape);
+    reg.insert(builtin::SQUEEZE, reshape);
+    reg.insert(builtin::TRANSPOSE, transpose);
+}
+
+fn concatenation(ctx: &ParsingContext, op: &schema::Operator) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::ConcatenationOptions::of(op)?;
+    Ok(expand(Concat {
+        axis: options.axis()? as i64,
+        activation: Activation::fused(options.fused_activation_function()?)?,
+        output_dt: ctx.output_datum_type(op, 0)?,
+    }))
+}
+
+/// RESHAPE and SQUEEZE both take the output shape declared in the model,
+/// ignoring the shape input and the squeeze options.
+fn reshape(ctx: &ParsingContext, op: &schema::Operator) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(expand(Reshape { shape: ctx.output_shape(op, 0)? }))
+}
+
+fn transpose(ctx: &ParsingContext, op: &schema::Operator) -> TractResult<Box<dyn InferenceOp>> {
+    let perm = ctx.input_konst(op, 1)?.context("Permutation must be const")?;
+    let perm = perm.cast_to::<i64>()?.as_slice::<i64>()?.iter().map(|&a| a as usize).collect();
+    Ok(expand(Transpose { perm }))
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct Reshape {
+    pub shape: TVec<usize>,
+}
+
+impl_dyn_hash!(Reshape);
+
+impl Expansion for Reshape {
+    fn name(&self) -> Cow<str> {
+        "Reshape".into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.is_empty() || inputs.len() > 2 {
+            bail!("Wrong number of inputs. Expected 1 or 2, got {}", inputs.len())
+        }
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&outputs[0].shape, ShapeFactoid::from(&*self.shape))?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input_shape = model.outlet_fact(inputs[0])?.shape.to_tvec();
+        let shape = self.shape.iter().map(|d| d.to_dim()).collect::<TVec<_>>();
+        if input_shape.iter().product::<TDim>() != shape.iter().product::<TDim>() {
+            bail!("Can not reshape {:?} to {:?}", input_shape, shape)
+        }
+        model.wire_node(prefix, AxisOp::Reshape(0, input_shape, shape), &inputs[0..1])
+    }
+}
+
+/// CONCATENATION, inputs being requantized to the output type if needed.
+#[derive(Debug, Clone, Hash)]
+pub struct Concat {
+    pub axis: i64,
+    pub activation: Activation,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(Concat);
+
+impl Expansion for Concat {
+    fn name(&self) -> Cow<str> {
+        "Concatenation".into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, self.output_dt)?;
+        s.equals_all(inputs.iter().map(|i| i.rank.bex()).collect())?;
+        s.equals(&outputs[0].rank, &inputs[0].rank)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank() as i64;
+        let axis = if self.axis < 0 { self.axis + rank } else { self.axis } as usize;
+        let inputs = inputs
+            .iter()
+            .enumerate()
+            .map(|(ix, i)| {
+                requantize(&format!("{}.input-{}", prefix, ix), model, *i, self.output_dt)
+            })
+            .collect::<TractResult<TVec<_>>>()?;
+        let wire =
+            model.wire_node(prefix, TypedConcat::concat_vars(axis, inputs.len()), &inputs)?[0];
+        Ok(tvec!(self.activation.wire(&format!("{}.activation", prefix), model, wire)?))
+    }
+}
+
+/// TRANSPOSE, the permutation input being a constant.
+#[derive(Debug, Clone, Hash)]
+pub struct Transpose {
+    pub perm: TVec<usize>,
+}
+
+impl_dyn_hash!(Transpose);
+
+impl Expansion for Transpose {
+    fn name(&self) -> Cow<str> {
+        "Transpose".into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].rank, self.perm.len() as i64)?;
+        s.equals(&outputs[0].rank, self.perm.len() as i64)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        PermuteAxes::new(Some(self.perm.clone())).wire(prefix, model, &inputs[0..1])
+    }
+}
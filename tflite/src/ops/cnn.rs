@@ -0,0 +1,212 @@
+use crate::model::{ParsingContext, TfliteOpRegister};
+use crate::ops::{dequantize, requantize, Activation};
+use crate::schema::{self, builtin, padding};
+use tract_hir::internal::*;
+use tract_hir::ops::cnn::PaddingSpec;
+use tract_hir::ops::nn::DataFormat;
+use tract_hir::tract_core::ops::cnn::{ConvUnary, KernelFormat, MaxPool, PoolSpec, SumPool};
+use tract_hir::tract_core::ops::matmul::MatMulQParams;
+
+pub fn register_all_ops(reg: &mut TfliteOpRegister) {
+    reg.insert(builtin::CONV_2D, conv2d);
+    reg.insert(builtin::DEPTHWISE_CONV_2D, depthwise_conv2d);
+    reg.insert(builtin::AVERAGE_POOL_2D, |ctx, op| pool2d(ctx, op, false));
+    reg.insert(builtin::MAX_POOL_2D, |ctx, op| pool2d(ctx, op, true));
+}
+
+fn padding(code: i8) -> TractResult<PaddingSpec> {
+    Ok(match code {
+        padding::SAME => PaddingSpec::SameUpper,
+        padding::VALID => PaddingSpec::Valid,
+        other => bail!("Unsupported padding {}", other),
+    })
+}
+
+fn hw(h: i32, w: i32) -> TVec<usize> {
+    tvec!(h as usize, w as usize)
+}
+
+fn conv2d(ctx: &ParsingContext, op: &schema::Operator) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::Conv2DOptions::of(op)?;
+    Ok(expand(Conv2D {
+        padding: padding(options.padding()?)?,
+        strides: hw(options.stride_h()?, options.stride_w()?),
+        dilations: hw(options.dilation_h_factor()?, options.dilation_w_factor()?),
+        depthwise: false,
+        activation: Activation::fused(options.fused_activation_function()?)?,
+        output_dt: ctx.output_datum_type(op, 0)?,
+    }))
+}
+
+fn depthwise_conv2d(
+    ctx: &ParsingContext,
+    op: &schema::Operator,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::DepthwiseConv2DOptions::of(op)?;
+    Ok(expand(Conv2D {
+        padding: padding(options.padding()?)?,
+        strides: hw(options.stride_h()?, options.stride_w()?),
+        dilations: hw(options.dilation_h_factor()?, options.dilation_w_factor()?),
+        depthwise: true,
+        activation: Activation::fused(options.fused_activation_function()?)?,
+        output_dt: ctx.output_datum_type(op, 0)?,
+    }))
+}
+
+fn pool2d(
+    _ctx: &ParsingContext,
+    op: &schema::Operator,
+    max: bool,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::Pool2DOptions::of(op)?;
+    Ok(expand(Pool2D {
+        max,
+        kernel_shape: hw(options.filter_height()?, options.filter_width()?),
+        padding: padding(options.padding()?)?,
+        strides: hw(options.stride_h()?, options.stride_w()?),
+        activation: Activation::fused(options.fused_activation_function()?)?,
+    }))
+}
+
+/// CONV_2D and DEPTHWISE_CONV_2D, over NHWC inputs.
+///
+/// Kernels are OHWI for regular convolutions, and 1HW(I*M) for depthwise
+/// ones, M being the depth multiplier.
+#[derive(Debug, Clone, Hash)]
+pub struct Conv2D {
+    pub padding: PaddingSpec,
+    pub strides: TVec<usize>,
+    pub dilations: TVec<usize>,
+    pub depthwise: bool,
+    pub activation: Activation,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(Conv2D);
+
+impl Expansion for Conv2D {
+    fn name(&self) -> Cow<str> {
+        if self.depthwise { "DepthwiseConv2D" } else { "Conv2D" }.into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.len() < 2 || inputs.len() > 3 {
+            bail!("Wrong number of inputs. Expected 2 or 3, got {}", inputs.len())
+        }
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].rank, 4)?;
+        s.equals(&inputs[1].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&outputs[0].datum_type, self.output_dt)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = model.outlet_fact(inputs[0])?.clone();
+        let kernel = model.outlet_fact(inputs[1])?.konst.clone().context("Kernel must be const")?;
+        let (kernel, group) = if self.depthwise {
+            (kernel.into_tensor().permute_axes(&[3, 0, 1, 2])?, input.shape[3].to_usize()?)
+        } else {
+            (kernel.into_tensor().permute_axes(&[0, 3, 1, 2])?, 1)
+        };
+        let bias = if let Some(bias) = inputs.get(2) {
+            Some(model.outlet_fact(*bias)?.konst.clone().context("Bias must be const")?)
+        } else {
+            None
+        };
+        let q_params = if input.datum_type.is_quantized() {
+            Some((self.output_dt, MatMulQParams::all_from_qtype()))
+        } else {
+            None
+        };
+        let pool_spec = PoolSpec::new(
+            DataFormat::NHWC,
+            kernel.shape()[2..].into(),
+            self.padding.clone(),
+            Some(self.dilations.clone()),
+            Some(self.strides.clone()),
+            Some(kernel.shape()[0]),
+        );
+        let conv = ConvUnary::new(
+            pool_spec,
+            KernelFormat::OIHW,
+            kernel.into_arc_tensor(),
+            group,
+            bias,
+            q_params,
+        );
+        let wire = model.wire_node(prefix, conv, &inputs[0..1])?[0];
+        Ok(tvec!(self.activation.wire(&format!("{}.activation", prefix), model, wire)?))
+    }
+}
+
+/// AVERAGE_POOL_2D and MAX_POOL_2D, over NHWC inputs.
+#[derive(Debug, Clone, Hash)]
+pub struct Pool2D {
+    pub max: bool,
+    pub kernel_shape: TVec<usize>,
+    pub padding: PaddingSpec,
+    pub strides: TVec<usize>,
+    pub activation: Activation,
+}
+
+impl_dyn_hash!(Pool2D);
+
+impl Expansion for Pool2D {
+    fn name(&self) -> Cow<str> {
+        if self.max { "MaxPool2D" } else { "AveragePool2D" }.into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let pool_spec = PoolSpec::new(
+            DataFormat::NHWC,
+            self.kernel_shape.clone(),
+            self.padding.clone(),
+            None,
+            Some(self.strides.clone()),
+            None,
+        );
+        let wire = if self.max {
+            model.wire_node(prefix, MaxPool::new(pool_spec, None), inputs)?[0]
+        } else {
+            let dt = model.outlet_fact(inputs[0])?.datum_type;
+            let wire = dequantize(prefix, model, inputs)?[0];
+            let wire = model.wire_node(prefix, SumPool::new(pool_spec, false, true), &[wire])?[0];
+            requantize(prefix, model, wire, dt)?
+        };
+        Ok(tvec!(self.activation.wire(&format!("{}.activation", prefix), model, wire)?))
+    }
+}
@@ -0,0 +1,141 @@
+use crate::model::{ParsingContext, TfliteOpRegister};
+use crate::ops::{dequantize, requantize, Activation};
+use crate::schema::{self, builtin};
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::binary::{wire_with_rank_broadcast, BinMiniOp, TypedBinOp};
+use tract_hir::tract_core::ops::math;
+
+pub fn register_all_ops(reg: &mut TfliteOpRegister) {
+    reg.insert(builtin::ADD, |ctx, op| binary(ctx, op, Box::new(math::Add)));
+    reg.insert(builtin::SUB, |ctx, op| binary(ctx, op, Box::new(math::Sub)));
+    reg.insert(builtin::MUL, |ctx, op| binary(ctx, op, Box::new(math::Mul)));
+    reg.insert(builtin::DIV, |ctx, op| binary(ctx, op, Box::new(math::Div)));
+    reg.insert(builtin::MAXIMUM, |ctx, op| binary(ctx, op, Box::new(math::Max)));
+    reg.insert(builtin::MINIMUM, |ctx, op| binary(ctx, op, Box::new(math::Min)));
+    reg.insert(builtin::RELU, |ctx, op| unary(ctx, op, Activation::Relu));
+    reg.insert(builtin::RELU6, |ctx, op| unary(ctx, op, Activation::Relu6));
+    reg.insert(builtin::TANH, |ctx, op| unary(ctx, op, Activation::Tanh));
+    reg.insert(builtin::LOGISTIC, |ctx, op| unary(ctx, op, Activation::Logistic));
+}
+
+fn binary(
+    ctx: &ParsingContext,
+    op: &schema::Operator,
+    mini_op: Box<dyn BinMiniOp>,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::ArithmeticOptions::of(op)?;
+    Ok(expand(Binary {
+        mini_op,
+        activation: Activation::fused(options.fused_activation_function()?)?,
+        output_dt: ctx.output_datum_type(op, 0)?,
+    }))
+}
+
+fn unary(
+    ctx: &ParsingContext,
+    op: &schema::Operator,
+    activation: Activation,
+) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(expand(Unary { activation, output_dt: ctx.output_datum_type(op, 0)? }))
+}
+
+/// ADD, SUB, MUL, DIV, MAXIMUM and MINIMUM, with numpy-style broadcasting.
+///
+/// Quantized operands, which may all have different quantization
+/// parameters, are computed in f32.
+#[derive(Debug, Clone, Hash)]
+pub struct Binary {
+    pub mini_op: Box<dyn BinMiniOp>,
+    pub activation: Activation,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(Binary);
+
+impl Expansion for Binary {
+    fn name(&self) -> Cow<str> {
+        self.mini_op.name().into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        let output_dt = self.output_dt;
+        tract_hir::ops::binary::rules(s, inputs, outputs, move |_, _| Ok(output_dt))
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let quantized = self.output_dt.is_quantized()
+            || inputs
+                .iter()
+                .map(|i| Ok(model.outlet_fact(*i)?.datum_type.is_quantized()))
+                .collect::<TractResult<Vec<bool>>>()?
+                .into_iter()
+                .any(|q| q);
+        let op = TypedBinOp(self.mini_op.clone());
+        let activation = format!("{}.activation", prefix);
+        if quantized {
+            let inputs = dequantize(prefix, model, inputs)?;
+            let wire = wire_with_rank_broadcast(prefix, model, op, &inputs)?[0];
+            let wire = self.activation.wire_float(&activation, model, wire)?;
+            Ok(tvec!(requantize(prefix, model, wire, self.output_dt)?))
+        } else {
+            let wire = wire_with_rank_broadcast(prefix, model, op, inputs)?[0];
+            Ok(tvec!(self.activation.wire(&activation, model, wire)?))
+        }
+    }
+}
+
+/// Standalone activations: RELU, RELU6, TANH and LOGISTIC.
+#[derive(Debug, Clone, Hash)]
+pub struct Unary {
+    pub activation: Activation,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(Unary);
+
+impl Expansion for Unary {
+    fn name(&self) -> Cow<str> {
+        format!("{:?}", self.activation).into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&outputs[0].datum_type, self.output_dt)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        if model.outlet_fact(inputs[0])?.datum_type == self.output_dt {
+            return Ok(tvec!(self.activation.wire(prefix, model, inputs[0])?));
+        }
+        let wire = dequantize(prefix, model, inputs)?[0];
+        let wire = self.activation.wire_float(prefix, model, wire)?;
+        Ok(tvec!(requantize(prefix, model, wire, self.output_dt)?))
+    }
+}
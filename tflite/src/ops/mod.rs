@@ -0,0 +1,167 @@
+use crate::model::TfliteOpRegister;
+use crate::schema::{activation, builtin};
+use tract_hir::internal::*;
+use tract_hir::ops::cast::cast;
+
+#[macro_export]
+macro_rules! op_tflite {
+    () => {
+        fn op_families(&self) -> &'static [&'static str] {
+            &["tflite"]
+        }
+    };
+}
+
+pub mod array;
+pub mod cnn;
+pub mod math;
+pub mod nn;
+
+pub fn register_all_ops(reg: &mut TfliteOpRegister) {
+    array::register_all_ops(reg);
+    cnn::register_all_ops(reg);
+    math::register_all_ops(reg);
+    nn::register_all_ops(reg);
+    reg.insert(builtin::QUANTIZE, |ctx, op| Ok(Box::new(cast(ctx.output_datum_type(op, 0)?))));
+    reg.insert(builtin::DEQUANTIZE, |ctx, op| Ok(Box::new(cast(ctx.output_datum_type(op, 0)?))));
+}
+
+/// Activations, either fused in an operator or standalone.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Activation {
+    None,
+    Relu,
+    ReluN1To1,
+    Relu6,
+    Tanh,
+    Logistic,
+}
+
+impl Activation {
+    pub fn fused(code: i8) -> TractResult<Activation> {
+        Ok(match code {
+            activation::NONE => Activation::None,
+            activation::RELU => Activation::Relu,
+            activation::RELU_N1_TO_1 => Activation::ReluN1To1,
+            activation::RELU6 => Activation::Relu6,
+            activation::TANH => Activation::Tanh,
+            other => bail!("Unsupported fused activation function {}", other),
+        })
+    }
+
+    fn clamp(&self) -> Option<(Option<f32>, Option<f32>)> {
+        match self {
+            Activation::Relu => Some((Some(0.0), None)),
+            Activation::ReluN1To1 => Some((Some(-1.0), Some(1.0))),
+            Activation::Relu6 => Some((Some(0.0), Some(6.0))),
+            _ => None,
+        }
+    }
+
+    /// Wires the activation over a float wire.
+    pub fn wire_float(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        wire: OutletId,
+    ) -> TractResult<OutletId> {
+        let fact = model.outlet_fact(wire)?.clone();
+        let mut wire = wire;
+        if let Some((low, high)) = self.clamp() {
+            if let Some(low) = low {
+                let low = tensor0(low).cast_to_dt(fact.datum_type)?.into_owned();
+                let low = low.broadcast_into_rank(fact.rank())?.into_arc_tensor();
+                wire = model.wire_node(
+                    format!("{}.low", prefix),
+                    tract_core::ops::math::max::unary(low),
+                    &[wire],
+                )?[0];
+            }
+            if let Some(high) = high {
+                let high = tensor0(high).cast_to_dt(fact.datum_type)?.into_owned();
+                let high = high.broadcast_into_rank(fact.rank())?.into_arc_tensor();
+                wire = model.wire_node(
+                    format!("{}.high", prefix),
+                    tract_core::ops::math::min::unary(high),
+                    &[wire],
+                )?[0];
+            }
+        } else if *self == Activation::Tanh {
+            wire = model.wire_node(prefix, tract_core::ops::math::tanh(), &[wire])?[0];
+        } else if *self == Activation::Logistic {
+            wire = model.wire_node(prefix, tract_core::ops::nn::sigmoid(), &[wire])?[0];
+        }
+        Ok(wire)
+    }
+
+    /// Wires the activation, preserving the wire datum type.
+    ///
+    /// On quantized wires, clamping activations are skipped when the type can
+    /// only represent values inside the clamped range anyway, which is what
+    /// converters produce most of the time. Otherwise, the activation is
+    /// computed in f32.
+    pub fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        wire: OutletId,
+    ) -> TractResult<OutletId> {
+        if *self == Activation::None {
+            return Ok(wire);
+        }
+        let dt = model.outlet_fact(wire)?.datum_type;
+        if !dt.is_quantized() {
+            return self.wire_float(prefix, model, wire);
+        }
+        if let Some((low, high)) = self.clamp() {
+            let (zero_point, scale) = dt.zp_scale();
+            let (qmin, qmax) = if dt.unquantized() == u8::datum_type() {
+                (u8::MIN as f32, u8::MAX as f32)
+            } else {
+                (i8::MIN as f32, i8::MAX as f32)
+            };
+            let min = (qmin - zero_point as f32) * scale;
+            let max = (qmax - zero_point as f32) * scale;
+            if low.map(|l| l <= min).unwrap_or(true) && high.map(|h| h >= max).unwrap_or(true) {
+                return Ok(wire);
+            }
+        }
+        let wire = dequantize(prefix, model, &[wire])?[0];
+        let wire = self.wire_float(prefix, model, wire)?;
+        requantize(prefix, model, wire, dt)
+    }
+}
+
+/// Casts quantized wires to f32, leaving the other ones untouched.
+pub fn dequantize(
+    prefix: &str,
+    model: &mut TypedModel,
+    inputs: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(ix, &wire)| {
+            if model.outlet_fact(wire)?.datum_type.is_quantized() {
+                let name = format!("{}.dequantize-{}", prefix, ix);
+                Ok(model.wire_node(name, cast(f32::datum_type()), &[wire])?[0])
+            } else {
+                Ok(wire)
+            }
+        })
+        .collect()
+}
+
+/// Casts a wire to `dt`, if it is not already of this type.
+pub fn requantize(
+    prefix: &str,
+    model: &mut TypedModel,
+    wire: OutletId,
+    dt: DatumType,
+) -> TractResult<OutletId> {
+    if model.outlet_fact(wire)?.datum_type == dt {
+        Ok(wire)
+    } else {
+        Ok(model.wire_node(format!("{}.requantize", prefix), cast(dt), &[wire])?[0])
+    }
+}
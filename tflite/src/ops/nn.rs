@@ -0,0 +1,179 @@
+use crate::model::{ParsingContext, TfliteOpRegister};
+use crate::ops::{dequantize, requantize, Activation};
+use crate::schema::{self, builtin};
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::change_axes::AxisOp;
+use tract_hir::tract_core::ops::matmul::mir_quant_unary::QMatMulUnary;
+use tract_hir::tract_core::ops::matmul::{MatMulQParams, MatMulUnary};
+
+pub fn register_all_ops(reg: &mut TfliteOpRegister) {
+    reg.insert(builtin::FULLY_CONNECTED, fully_connected);
+    reg.insert(builtin::SOFTMAX, softmax);
+}
+
+fn fully_connected(
+    ctx: &ParsingContext,
+    op: &schema::Operator,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::FullyConnectedOptions::of(op)?;
+    if options.weights_format()? != 0 {
+        bail!("Only default weights format is supported for FULLY_CONNECTED")
+    }
+    Ok(expand(FullyConnected {
+        activation: Activation::fused(options.fused_activation_function()?)?,
+        keep_num_dims: options.keep_num_dims()?,
+        output_dt: ctx.output_datum_type(op, 0)?,
+    }))
+}
+
+fn softmax(ctx: &ParsingContext, op: &schema::Operator) -> TractResult<Box<dyn InferenceOp>> {
+    let options = schema::SoftmaxOptions::of(op)?;
+    Ok(expand(Softmax { beta: options.beta()?, output_dt: ctx.output_datum_type(op, 0)? }))
+}
+
+/// FULLY_CONNECTED: input is flattened to [N, I], weights are [O, I].
+#[derive(Debug, Clone, Hash)]
+pub struct FullyConnected {
+    pub activation: Activation,
+    pub keep_num_dims: bool,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(FullyConnected);
+
+impl Expansion for FullyConnected {
+    fn name(&self) -> Cow<str> {
+        "FullyConnected".into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        if inputs.len() < 2 || inputs.len() > 3 {
+            bail!("Wrong number of inputs. Expected 2 or 3, got {}", inputs.len())
+        }
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[1].rank, 2)?;
+        s.equals(&outputs[0].datum_type, self.output_dt)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let input = model.outlet_fact(inputs[0])?.clone();
+        let weights =
+            model.outlet_fact(inputs[1])?.konst.clone().context("Weights must be const")?;
+        let (outputs, features) = (weights.shape()[0], weights.shape()[1]);
+        let mut wire = inputs[0];
+        if input.rank() != 2 {
+            let batch = input.shape.iter().product::<TDim>() / features;
+            wire = model.wire_node(
+                format!("{}.flatten", prefix),
+                AxisOp::Reshape(0, input.shape.to_tvec(), tvec!(batch, features.to_dim())),
+                &[wire],
+            )?[0];
+        }
+        let bias = if let Some(bias) = inputs.get(2) {
+            let bias = model.outlet_fact(*bias)?.konst.clone().context("Bias must be const")?;
+            Some(bias.into_tensor().into_shape(&[1, outputs])?.into_arc_tensor())
+        } else {
+            None
+        };
+        if input.datum_type.is_quantized() {
+            let op = QMatMulUnary::new(
+                weights,
+                bias,
+                false,
+                true,
+                true,
+                self.output_dt,
+                MatMulQParams::all_from_qtype(),
+            );
+            wire = model.wire_node(prefix, op, &[wire])?[0];
+        } else {
+            wire =
+                model.wire_node(prefix, MatMulUnary::new(weights, false, true, true), &[wire])?[0];
+            if let Some(bias) = bias {
+                wire = model.wire_node(
+                    format!("{}.bias", prefix),
+                    tract_hir::tract_core::ops::math::add::unary(bias),
+                    &[wire],
+                )?[0];
+            }
+        }
+        if self.keep_num_dims && input.rank() != 2 {
+            let batch = model.outlet_fact(wire)?.shape[0].clone();
+            let mut shape = input.shape.to_tvec();
+            shape[input.rank() - 1] = outputs.to_dim();
+            wire = model.wire_node(
+                format!("{}.unflatten", prefix),
+                AxisOp::Reshape(0, tvec!(batch, outputs.to_dim()), shape),
+                &[wire],
+            )?[0];
+        }
+        Ok(tvec!(self.activation.wire(&format!("{}.activation", prefix), model, wire)?))
+    }
+}
+
+/// SOFTMAX over the last axis, with inputs scaled by beta.
+#[derive(Debug, Clone, Educe)]
+#[educe(Hash)]
+pub struct Softmax {
+    #[educe(Hash(method = "hash_f32"))]
+    pub beta: f32,
+    pub output_dt: DatumType,
+}
+
+impl_dyn_hash!(Softmax);
+
+impl Expansion for Softmax {
+    fn name(&self) -> Cow<str> {
+        "Softmax".into()
+    }
+
+    op_tflite!();
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&outputs[0].datum_type, self.output_dt)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let rank = model.outlet_fact(inputs[0])?.rank();
+        let mut wire = dequantize(prefix, model, inputs)?[0];
+        if self.beta != 1.0 {
+            let beta = tensor0(self.beta).broadcast_into_rank(rank)?.into_arc_tensor();
+            wire = model.wire_node(
+                format!("{}.beta", prefix),
+                tract_hir::tract_core::ops::math::mul::unary(beta),
+                &[wire],
+            )?[0];
+        }
+        let softmax =
+            tract_hir::tract_core::ops::nn::Softmax::new(tvec!(rank - 1), f32::datum_type());
+        wire = model.wire_node(prefix, softmax, &[wire])?[0];
+        Ok(tvec!(requantize(prefix, model, wire, self.output_dt)?))
+    }
+}
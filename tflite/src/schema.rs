@@ -0,0 +1,332 @@
+//! Accessors for the parts of the TFLite schema tract understands.
+//!
+//! Field ids and enum values follow tensorflow/lite/schema/schema.fbs.
+use crate::flat::Table;
+use tract_hir::internal::*;
+
+pub const FILE_IDENTIFIER: &[u8; 4] = b"TFL3";
+
+macro_rules! table {
+    ($name:ident) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<'a>(pub Table<'a>);
+    };
+}
+
+table!(Model);
+table!(OperatorCode);
+table!(SubGraph);
+table!(Tensor);
+table!(QuantizationParameters);
+table!(Operator);
+table!(Buffer);
+
+impl<'a> Model<'a> {
+    pub fn root(buf: &'a [u8]) -> TractResult<Model<'a>> {
+        if buf.get(4..8) != Some(&FILE_IDENTIFIER[..]) {
+            bail!("Not a TFLite model (missing TFL3 file identifier)")
+        }
+        Ok(Model(Table::root(buf)?))
+    }
+
+    pub fn version(&self) -> TractResult<u32> {
+        self.0.scalar(0, 0)
+    }
+
+    pub fn operator_codes(&self) -> TractResult<Vec<OperatorCode<'a>>> {
+        Ok(self.0.tables(1)?.into_iter().map(OperatorCode).collect())
+    }
+
+    pub fn subgraphs(&self) -> TractResult<Vec<SubGraph<'a>>> {
+        Ok(self.0.tables(2)?.into_iter().map(SubGraph).collect())
+    }
+
+    pub fn description(&self) -> TractResult<Option<&'a str>> {
+        self.0.string(3)
+    }
+
+    pub fn buffers(&self) -> TractResult<Vec<Buffer<'a>>> {
+        Ok(self.0.tables(4)?.into_iter().map(Buffer).collect())
+    }
+}
+
+impl<'a> OperatorCode<'a> {
+    /// The builtin operator code.
+    ///
+    /// Codes above 127 only live in the newer i32 field, older files only
+    /// fill the deprecated i8 one: the effective code is the max of both.
+    pub fn builtin_code(&self) -> TractResult<i32> {
+        let deprecated = self.0.scalar::<i8>(0, 0)? as i32;
+        let code = self.0.scalar::<i32>(3, 0)?;
+        Ok(deprecated.max(code))
+    }
+
+    pub fn custom_code(&self) -> TractResult<Option<&'a str>> {
+        self.0.string(1)
+    }
+
+    pub fn version(&self) -> TractResult<i32> {
+        self.0.scalar(2, 1)
+    }
+}
+
+impl<'a> SubGraph<'a> {
+    pub fn tensors(&self) -> TractResult<Vec<Tensor<'a>>> {
+        Ok(self.0.tables(0)?.into_iter().map(Tensor).collect())
+    }
+
+    pub fn inputs(&self) -> TractResult<Vec<i32>> {
+        self.0.vector(1)
+    }
+
+    pub fn outputs(&self) -> TractResult<Vec<i32>> {
+        self.0.vector(2)
+    }
+
+    pub fn operators(&self) -> TractResult<Vec<Operator<'a>>> {
+        Ok(self.0.tables(3)?.into_iter().map(Operator).collect())
+    }
+
+    pub fn name(&self) -> TractResult<Option<&'a str>> {
+        self.0.string(4)
+    }
+}
+
+impl<'a> Tensor<'a> {
+    pub fn shape(&self) -> TractResult<Vec<i32>> {
+        self.0.vector(0)
+    }
+
+    pub fn tensor_type(&self) -> TractResult<i8> {
+        self.0.scalar(1, tensor_type::FLOAT32)
+    }
+
+    pub fn buffer(&self) -> TractResult<u32> {
+        self.0.scalar(2, 0)
+    }
+
+    pub fn name(&self) -> TractResult<&'a str> {
+        Ok(self.0.string(3)?.unwrap_or(""))
+    }
+
+    pub fn quantization(&self) -> TractResult<Option<QuantizationParameters<'a>>> {
+        Ok(self.0.table(4)?.map(QuantizationParameters))
+    }
+}
+
+impl<'a> QuantizationParameters<'a> {
+    pub fn min(&self) -> TractResult<Vec<f32>> {
+        self.0.vector(0)
+    }
+
+    pub fn max(&self) -> TractResult<Vec<f32>> {
+        self.0.vector(1)
+    }
+
+    pub fn scale(&self) -> TractResult<Vec<f32>> {
+        self.0.vector(2)
+    }
+
+    pub fn zero_point(&self) -> TractResult<Vec<i64>> {
+        self.0.vector(3)
+    }
+
+    pub fn quantized_dimension(&self) -> TractResult<i32> {
+        self.0.scalar(6, 0)
+    }
+}
+
+impl<'a> Operator<'a> {
+    pub fn opcode_index(&self) -> TractResult<u32> {
+        self.0.scalar(0, 0)
+    }
+
+    /// Input tensor indices, -1 denoting an omitted optional input.
+    pub fn inputs(&self) -> TractResult<Vec<i32>> {
+        self.0.vector(1)
+    }
+
+    pub fn outputs(&self) -> TractResult<Vec<i32>> {
+        self.0.vector(2)
+    }
+
+    pub fn builtin_options_type(&self) -> TractResult<u8> {
+        self.0.scalar(3, 0)
+    }
+
+    pub fn builtin_options(&self) -> TractResult<Option<Table<'a>>> {
+        self.0.table(4)
+    }
+}
+
+impl<'a> Buffer<'a> {
+    pub fn data(&self) -> TractResult<&'a [u8]> {
+        self.0.bytes(0)
+    }
+}
+
+/// Builtin options tables. All fields are scalars, and fall back to the
+/// schema defaults when the table or the field is missing.
+macro_rules! options {
+    ($name:ident { $($field:ident: $t:ty = $id:expr, $default:expr;)* }) => {
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name<'a>(pub Option<Table<'a>>);
+
+        impl<'a> $name<'a> {
+            pub fn of(op: &Operator<'a>) -> TractResult<$name<'a>> {
+                Ok($name(op.builtin_options()?))
+            }
+
+            $(
+                pub fn $field(&self) -> TractResult<$t> {
+                    self.0.map(|t| t.scalar($id, $default)).unwrap_or(Ok($default))
+                }
+            )*
+        }
+    };
+}
+
+options!(Conv2DOptions {
+    padding: i8 = 0, padding::SAME;
+    stride_w: i32 = 1, 0;
+    stride_h: i32 = 2, 0;
+    fused_activation_function: i8 = 3, activation::NONE;
+    dilation_w_factor: i32 = 4, 1;
+    dilation_h_factor: i32 = 5, 1;
+});
+
+options!(DepthwiseConv2DOptions {
+    padding: i8 = 0, padding::SAME;
+    stride_w: i32 = 1, 0;
+    stride_h: i32 = 2, 0;
+    depth_multiplier: i32 = 3, 0;
+    fused_activation_function: i8 = 4, activation::NONE;
+    dilation_w_factor: i32 = 5, 1;
+    dilation_h_factor: i32 = 6, 1;
+});
+
+options!(Pool2DOptions {
+    padding: i8 = 0, padding::SAME;
+    stride_w: i32 = 1, 0;
+    stride_h: i32 = 2, 0;
+    filter_width: i32 = 3, 0;
+    filter_height: i32 = 4, 0;
+    fused_activation_function: i8 = 5, activation::NONE;
+});
+
+options!(FullyConnectedOptions {
+    fused_activation_function: i8 = 0, activation::NONE;
+    weights_format: i8 = 1, 0;
+    keep_num_dims: bool = 2, false;
+});
+
+options!(SoftmaxOptions {
+    beta: f32 = 0, 0.0;
+});
+
+options!(ConcatenationOptions {
+    axis: i32 = 0, 0;
+    fused_activation_function: i8 = 1, activation::NONE;
+});
+
+// AddOptions, SubOptions and MulOptions all start with the activation.
+options!(ArithmeticOptions {
+    fused_activation_function: i8 = 0, activation::NONE;
+});
+
+pub mod tensor_type {
+    pub const FLOAT32: i8 = 0;
+    pub const FLOAT16: i8 = 1;
+    pub const INT32: i8 = 2;
+    pub const UINT8: i8 = 3;
+    pub const INT64: i8 = 4;
+    pub const STRING: i8 = 5;
+    pub const BOOL: i8 = 6;
+    pub const INT16: i8 = 7;
+    pub const INT8: i8 = 9;
+    pub const FLOAT64: i8 = 10;
+}
+
+pub mod padding {
+    pub const SAME: i8 = 0;
+    pub const VALID: i8 = 1;
+}
+
+pub mod activation {
+    pub const NONE: i8 = 0;
+    pub const RELU: i8 = 1;
+    pub const RELU_N1_TO_1: i8 = 2;
+    pub const RELU6: i8 = 3;
+    pub const TANH: i8 = 4;
+}
+
+/// Tags of the BuiltinOptions union.
+pub mod options_type {
+    pub const CONV_2D: u8 = 1;
+    pub const DEPTHWISE_CONV_2D: u8 = 2;
+    pub const POOL_2D: u8 = 5;
+    pub const FULLY_CONNECTED: u8 = 8;
+    pub const SOFTMAX: u8 = 9;
+    pub const CONCATENATION: u8 = 10;
+    pub const ADD: u8 = 11;
+    pub const RESHAPE: u8 = 17;
+    pub const MUL: u8 = 21;
+    pub const TRANSPOSE: u8 = 26;
+    pub const SUB: u8 = 28;
+    pub const DIV: u8 = 29;
+    pub const MAXIMUM_MINIMUM: u8 = 39;
+}
+
+pub mod builtin {
+    pub const ADD: i32 = 0;
+    pub const AVERAGE_POOL_2D: i32 = 1;
+    pub const CONCATENATION: i32 = 2;
+    pub const CONV_2D: i32 = 3;
+    pub const DEPTHWISE_CONV_2D: i32 = 4;
+    pub const DEQUANTIZE: i32 = 6;
+    pub const FULLY_CONNECTED: i32 = 9;
+    pub const LOGISTIC: i32 = 14;
+    pub const MAX_POOL_2D: i32 = 17;
+    pub const MUL: i32 = 18;
+    pub const RELU: i32 = 19;
+    pub const RELU6: i32 = 21;
+    pub const RESHAPE: i32 = 22;
+    pub const SOFTMAX: i32 = 25;
+    pub const TANH: i32 = 28;
+    pub const TRANSPOSE: i32 = 39;
+    pub const SUB: i32 = 41;
+    pub const DIV: i32 = 42;
+    pub const SQUEEZE: i32 = 43;
+    pub const MAXIMUM: i32 = 55;
+    pub const MINIMUM: i32 = 57;
+    pub const QUANTIZE: i32 = 114;
+
+    pub fn name(code: i32) -> String {
+        let name = match code {
+            ADD => "ADD",
+            AVERAGE_POOL_2D => "AVERAGE_POOL_2D",
+            CONCATENATION => "CONCATENATION",
+            CONV_2D => "CONV_2D",
+            DEPTHWISE_CONV_2D => "DEPTHWISE_CONV_2D",
+            DEQUANTIZE => "DEQUANTIZE",
+            FULLY_CONNECTED => "FULLY_CONNECTED",
+            LOGISTIC => "LOGISTIC",
+            MAX_POOL_2D => "MAX_POOL_2D",
+            MUL => "MUL",
+            RELU => "RELU",
+            RELU6 => "RELU6",
+            RESHAPE => "RESHAPE",
+            SOFTMAX => "SOFTMAX",
+            TANH => "TANH",
+            TRANSPOSE => "TRANSPOSE",
+            SUB => "SUB",
+            DIV => "DIV",
+            SQUEEZE => "SQUEEZE",
+            MAXIMUM => "MAXIMUM",
+            MINIMUM => "MINIMUM",
+            QUANTIZE => "QUANTIZE",
+            _ => return format!("BUILTIN_{}", code),
+        };
+        name.to_string()
+    }
+}
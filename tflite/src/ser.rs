@@ -0,0 +1,526 @@
+//! Translation of a TypedModel to a TFLite flatbuffer.
+//!
+//! This works on decluttered models, mapping core operators back to the
+//! TFLite builtins: convolutions and pools must be 2D and NHWC, matrix
+//! products must be fully-connected-like. Anything else is an error, naming
+//! the offending node.
+use crate::flat::builder::*;
+use crate::schema::{self, builtin, options_type, padding, tensor_type};
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::array::{ConcatSlice, TypedConcat};
+use tract_hir::tract_core::ops::binary::{BinMiniOp, TypedBinOp, UnaryOp};
+use tract_hir::tract_core::ops::cast::Cast;
+use tract_hir::tract_core::ops::change_axes::AxisOp;
+use tract_hir::tract_core::ops::cnn::{ConvUnary, MaxPool, PaddingSpec, PoolSpec, SumPool};
+use tract_hir::tract_core::ops::element_wise::ElementWiseOp;
+use tract_hir::tract_core::ops::konst::Const;
+use tract_hir::tract_core::ops::math;
+use tract_hir::tract_core::ops::matmul::mir_quant_unary::QMatMulUnary;
+use tract_hir::tract_core::ops::matmul::{MatMulQParams, MatMulUnary};
+use tract_hir::tract_core::ops::nn::{DataFormat, Sigmoid, Softmax};
+use tract_hir::tract_core::ops::source::TypedSource;
+
+pub fn to_tflite(model: &TypedModel) -> TractResult<Vec<u8>> {
+    let mut ser = Serializer { buffers: vec![Obj(vec![])], ..Serializer::default() };
+    for node in model.eval_order()? {
+        let node = &model.nodes()[node];
+        ser.node(model, node).with_context(|| format!("Translating node {}", node))?;
+    }
+    let inputs =
+        model.input_outlets()?.iter().map(|o| ser.outlet(*o)).collect::<TractResult<Vec<_>>>()?;
+    let outputs =
+        model.output_outlets()?.iter().map(|o| ser.outlet(*o)).collect::<TractResult<Vec<_>>>()?;
+    Ok(ser.finish(&inputs, &outputs))
+}
+
+#[derive(Default)]
+struct Serializer {
+    tensors: Vec<Obj>,
+    buffers: Vec<Obj>,
+    operators: Vec<Obj>,
+    codes: Vec<i32>,
+    outlets: HashMap<OutletId, i32>,
+}
+
+fn outlet_name(model: &TypedModel, outlet: OutletId) -> String {
+    if let Some(label) = model.outlet_label(outlet) {
+        label.to_string()
+    } else if outlet.slot == 0 {
+        model.node(outlet.node).name.clone()
+    } else {
+        format!("{}.{}", model.node(outlet.node).name, outlet.slot)
+    }
+}
+
+fn padding(spec: &PoolSpec) -> TractResult<i8> {
+    Ok(match &spec.padding {
+        PaddingSpec::Valid => padding::VALID,
+        PaddingSpec::SameUpper => padding::SAME,
+        PaddingSpec::Explicit(before, after, _)
+            if before.iter().chain(after.iter()).all(|p| *p == 0) =>
+        {
+            padding::VALID
+        }
+        other => bail!("{:?} padding can not be exported", other),
+    })
+}
+
+fn check_2d_nhwc(spec: &PoolSpec) -> TractResult<()> {
+    ensure!(
+        spec.data_format == DataFormat::NHWC && spec.rank() == 2,
+        "Only 2D NHWC operators can be exported (got {:?}, {}D)",
+        spec.data_format,
+        spec.rank()
+    );
+    Ok(())
+}
+
+fn no_activation() -> Field {
+    scalar(schema::activation::NONE)
+}
+
+fn binary(mini_op: &dyn BinMiniOp) -> TractResult<(i32, Option<(u8, Obj)>)> {
+    let arithmetic = |tag| Some((tag, Obj(vec![no_activation()])));
+    Ok(if mini_op.is::<math::Add>() {
+        (builtin::ADD, arithmetic(options_type::ADD))
+    } else if mini_op.is::<math::Sub>() {
+        (builtin::SUB, arithmetic(options_type::SUB))
+    } else if mini_op.is::<math::Mul>() {
+        (builtin::MUL, arithmetic(options_type::MUL))
+    } else if mini_op.is::<math::Div>() {
+        (builtin::DIV, arithmetic(options_type::DIV))
+    } else if mini_op.is::<math::Max>() {
+        (builtin::MAXIMUM, Some((options_type::MAXIMUM_MINIMUM, Obj(vec![]))))
+    } else if mini_op.is::<math::Min>() {
+        (builtin::MINIMUM, Some((options_type::MAXIMUM_MINIMUM, Obj(vec![]))))
+    } else {
+        bail!("{} can not be exported", mini_op.name())
+    })
+}
+
+impl Serializer {
+    fn tensor(
+        &mut self,
+        name: &str,
+        dt: DatumType,
+        shape: &[usize],
+        data: Option<&Tensor>,
+    ) -> TractResult<i32> {
+        let tensor_type = match dt.unquantized() {
+            DatumType::F32 => tensor_type::FLOAT32,
+            DatumType::F16 => tensor_type::FLOAT16,
+            DatumType::F64 => tensor_type::FLOAT64,
+            DatumType::I32 => tensor_type::INT32,
+            DatumType::I64 => tensor_type::INT64,
+            DatumType::I16 => tensor_type::INT16,
+            DatumType::U8 => tensor_type::UINT8,
+            DatumType::I8 => tensor_type::INT8,
+            DatumType::Bool => tensor_type::BOOL,
+            other => bail!("{:?} tensors can not be exported", other),
+        };
+        let quantization = if dt.is_quantized() {
+            let (zero_point, scale) = dt.zp_scale();
+            Field::Table(Obj(vec![
+                Field::Absent,
+                Field::Absent,
+                vector(&[scale]),
+                vector(&[zero_point as i64]),
+            ]))
+        } else {
+            Field::Absent
+        };
+        let buffer = if let Some(data) = data {
+            self.buffers.push(Obj(vec![vector(unsafe { data.as_bytes() })]));
+            self.buffers.len() - 1
+        } else {
+            0
+        };
+        let shape = shape.iter().map(|d| *d as i32).collect::<Vec<_>>();
+        self.tensors.push(Obj(vec![
+            vector(&shape),
+            scalar(tensor_type),
+            scalar(buffer as u32),
+            string(name),
+            quantization,
+        ]));
+        Ok(self.tensors.len() as i32 - 1)
+    }
+
+    fn konst(&mut self, name: &str, tensor: &Tensor) -> TractResult<i32> {
+        self.tensor(name, tensor.datum_type(), tensor.shape(), Some(tensor))
+    }
+
+    fn outlet(&self, outlet: OutletId) -> TractResult<i32> {
+        self.outlets.get(&outlet).cloned().with_context(|| format!("No tensor for {:?}", outlet))
+    }
+
+    fn operator(&mut self, code: i32, inputs: &[i32], outputs: &[i32], options: Option<(u8, Obj)>) {
+        let index = self.codes.iter().position(|c| *c == code).unwrap_or_else(|| {
+            self.codes.push(code);
+            self.codes.len() - 1
+        });
+        let (options_type, options) = if let Some((tag, options)) = options {
+            (scalar(tag), Field::Table(options))
+        } else {
+            (Field::Absent, Field::Absent)
+        };
+        self.operators.push(Obj(vec![
+            scalar(index as u32),
+            vector(inputs),
+            vector(outputs),
+            options_type,
+            options,
+        ]))
+    }
+
+    fn node(&mut self, model: &TypedModel, node: &TypedNode) -> TractResult<()> {
+        let mut outputs = vec![];
+        for (slot, output) in node.outputs.iter().enumerate() {
+            let outlet = OutletId::new(node.id, slot);
+            let name = outlet_name(model, outlet);
+            let shape = output
+                .fact
+                .shape
+                .as_concrete()
+                .with_context(|| format!("Symbolic shape {:?}", output.fact.shape))?;
+            let id = if let Some(k) = node.op_as::<Const>() {
+                self.konst(&name, &k.0)?
+            } else {
+                self.tensor(&name, output.fact.datum_type, shape, None)?
+            };
+            self.outlets.insert(outlet, id);
+            outputs.push(id);
+        }
+        if node.op_is::<TypedSource>() || node.op_is::<Const>() {
+            return Ok(());
+        }
+        let inputs =
+            node.inputs.iter().map(|i| self.outlet(*i)).collect::<TractResult<Vec<_>>>()?;
+        let input_facts = model.node_input_facts(node.id)?;
+        let name = &node.name;
+        if let Some(conv) = node.op_as::<ConvUnary>() {
+            let spec = &conv.pool_spec;
+            check_2d_nhwc(spec)?;
+            let quantized = if let Some((_, qp)) = &conv.q_params {
+                ensure!(
+                    *qp == MatMulQParams::all_from_qtype(),
+                    "Only convolutions quantized by their datum types can be exported"
+                );
+                true
+            } else {
+                false
+            };
+            let kernel = conv.kernel_as_group_o_ihw()?;
+            let (h, w) = (spec.kernel_shape[0], spec.kernel_shape[1]);
+            let output_channels = kernel.shape()[0] * kernel.shape()[1];
+            let input_channels = kernel.shape()[2] / (h * w) * conv.group;
+            let kernel = kernel.into_tensor().into_shape(&[
+                output_channels,
+                input_channels / conv.group,
+                h,
+                w,
+            ])?;
+            let (strides, dilations) = (spec.strides(), spec.dilations());
+            let (code, kernel, options) = if conv.group == 1 {
+                let options = Obj(vec![
+                    scalar(padding(spec)?),
+                    scalar(strides[1] as i32),
+                    scalar(strides[0] as i32),
+                    no_activation(),
+                    scalar(dilations[1] as i32),
+                    scalar(dilations[0] as i32),
+                ]);
+                (
+                    builtin::CONV_2D,
+                    kernel.permute_axes(&[0, 2, 3, 1])?,
+                    (options_type::CONV_2D, options),
+                )
+            } else if conv.group == input_channels {
+                let options = Obj(vec![
+                    scalar(padding(spec)?),
+                    scalar(strides[1] as i32),
+                    scalar(strides[0] as i32),
+                    scalar((output_channels / input_channels) as i32),
+                    no_activation(),
+                    scalar(dilations[1] as i32),
+                    scalar(dilations[0] as i32),
+                ]);
+                (
+                    builtin::DEPTHWISE_CONV_2D,
+                    kernel.permute_axes(&[1, 2, 3, 0])?,
+                    (options_type::DEPTHWISE_CONV_2D, options),
+                )
+            } else {
+                bail!("Grouped convolutions (other than depthwise) can not be exported")
+            };
+            let bias_dt = if quantized { i32::datum_type() } else { kernel.datum_type() };
+            let bias = if let Some(bias) = &conv.bias {
+                if bias.len() == output_channels {
+                    bias.clone().into_tensor().into_shape(&[output_channels])?
+                } else {
+                    bias.broadcast_scalar_to_shape(&[output_channels])?
+                }
+            } else {
+                Tensor::zero_dt(bias_dt, &[output_channels])?
+            };
+            let kernel = self.konst(&format!("{}.kernel", name), &kernel)?;
+            let bias = self.konst(&format!("{}.bias", name), &bias)?;
+            self.operator(code, &[inputs[0], kernel, bias], &outputs, Some(options));
+        } else if let Some(mm) = node.op_as::<MatMulUnary>() {
+            ensure!(
+                input_facts[0].rank() == 2 && mm.a.rank() == 2 && mm.b_trans && mm.c_trans,
+                "Only fully connected matrix products can be exported"
+            );
+            let weights = if mm.a_trans {
+                mm.a.clone().into_tensor().permute_axes(&[1, 0])?
+            } else {
+                mm.a.clone().into_tensor()
+            };
+            let weights = self.konst(&format!("{}.weights", name), &weights)?;
+            let options = Obj(vec![no_activation()]);
+            self.operator(
+                builtin::FULLY_CONNECTED,
+                &[inputs[0], weights, -1],
+                &outputs,
+                Some((options_type::FULLY_CONNECTED, options)),
+            );
+        } else if let Some(mm) = node.op_as::<QMatMulUnary>() {
+            ensure!(
+                input_facts[0].rank() == 2
+                    && mm.a.rank() == 2
+                    && !mm.a_trans
+                    && mm.b_trans
+                    && mm.c_trans
+                    && mm.params == MatMulQParams::all_from_qtype(),
+                "Only fully connected matrix products quantized by their datum types can be exported"
+            );
+            let weights = self.konst(&format!("{}.weights", name), &mm.a)?;
+            let bias = if let Some(bias) = &mm.bias {
+                let bias = bias.clone().into_tensor();
+                let len = bias.len();
+                self.konst(&format!("{}.bias", name), &bias.into_shape(&[len])?)?
+            } else {
+                -1
+            };
+            let options = Obj(vec![no_activation()]);
+            self.operator(
+                builtin::FULLY_CONNECTED,
+                &[inputs[0], weights, bias],
+                &outputs,
+                Some((options_type::FULLY_CONNECTED, options)),
+            );
+        } else if let Some(op) = node.op_as::<UnaryOp>() {
+            let (code, options) = binary(&*op.mini_op)?;
+            let a = self.konst(&format!("{}.a", name), &op.a)?;
+            self.operator(code, &[a, inputs[0]], &outputs, options);
+        } else if let Some(op) = node.op_as::<TypedBinOp>() {
+            let (code, options) = binary(&*op.0)?;
+            self.operator(code, &inputs, &outputs, options);
+        } else if let Some(op) = node.op_as::<ElementWiseOp>() {
+            let code = if op.0.is::<math::Tanh>() {
+                builtin::TANH
+            } else if op.0.is::<Sigmoid>() {
+                builtin::LOGISTIC
+            } else {
+                bail!("{} can not be exported", op.0.name())
+            };
+            self.operator(code, &inputs, &outputs, None);
+        } else if let Some(op) = node.op_as::<Softmax>() {
+            let rank = input_facts[0].rank();
+            ensure!(&*op.axes == &[rank - 1], "Only softmax over the last axis can be exported");
+            let options = Obj(vec![scalar(1f32)]);
+            self.operator(
+                builtin::SOFTMAX,
+                &inputs,
+                &outputs,
+                Some((options_type::SOFTMAX, options)),
+            );
+        } else if let Some(op) = node.op_as::<AxisOp>() {
+            if let AxisOp::Move(from, to) = op {
+                let mut perm = (0..input_facts[0].rank() as i32).collect::<Vec<_>>();
+                let axis = perm.remove(*from);
+                perm.insert(*to, axis);
+                let perm = self.konst(&format!("{}.perm", name), &tensor1(&perm))?;
+                let options = Obj(vec![]);
+                self.operator(
+                    builtin::TRANSPOSE,
+                    &[inputs[0], perm],
+                    &outputs,
+                    Some((options_type::TRANSPOSE, options)),
+                );
+            } else {
+                let shape = node.outputs[0]
+                    .fact
+                    .shape
+                    .as_concrete()
+                    .context("Symbolic shape")?
+                    .iter()
+                    .map(|d| *d as i32)
+                    .collect::<Vec<_>>();
+                let shape_input = self.konst(&format!("{}.shape", name), &tensor1(&shape))?;
+                let options = Obj(vec![vector(&shape)]);
+                self.operator(
+                    builtin::RESHAPE,
+                    &[inputs[0], shape_input],
+                    &outputs,
+                    Some((options_type::RESHAPE, options)),
+                );
+            }
+        } else if let Some(op) = node.op_as::<TypedConcat>() {
+            ensure!(
+                op.slices.iter().all(|s| matches!(s, ConcatSlice::Var)),
+                "Concatenation with constant slices can not be exported"
+            );
+            let options = Obj(vec![scalar(op.axis as i32), no_activation()]);
+            self.operator(
+                builtin::CONCATENATION,
+                &inputs,
+                &outputs,
+                Some((options_type::CONCATENATION, options)),
+            );
+        } else if let Some((spec, code)) = node
+            .op_as::<MaxPool>()
+            .filter(|mp| mp.with_index_outputs.is_none())
+            .map(|mp| (&mp.pool_spec, builtin::MAX_POOL_2D))
+            .or_else(|| {
+                node.op_as::<SumPool>()
+                    .filter(|sp| sp.normalize && !sp.count_include_pad)
+                    .map(|sp| (&sp.pool_spec, builtin::AVERAGE_POOL_2D))
+            })
+        {
+            check_2d_nhwc(spec)?;
+            ensure!(spec.dilations().iter().all(|d| *d == 1), "Dilated pools can not be exported");
+            let strides = spec.strides();
+            let options = Obj(vec![
+                scalar(padding(spec)?),
+                scalar(strides[1] as i32),
+                scalar(strides[0] as i32),
+                scalar(spec.kernel_shape[1] as i32),
+                scalar(spec.kernel_shape[0] as i32),
+                no_activation(),
+            ]);
+            self.operator(code, &inputs, &outputs, Some((options_type::POOL_2D, options)));
+        } else if let Some(cast) = node.op_as::<Cast>() {
+            let code = if cast.to.is_quantized() && input_facts[0].datum_type.is_float() {
+                builtin::QUANTIZE
+            } else if input_facts[0].datum_type.is_quantized() && cast.to.is_float() {
+                builtin::DEQUANTIZE
+            } else {
+                bail!(
+                    "Cast from {:?} to {:?} can not be exported",
+                    input_facts[0].datum_type,
+                    cast.to
+                )
+            };
+            self.operator(code, &inputs, &outputs, None);
+        } else {
+            bail!("{} can not be exported to TFLite", node.op.name())
+        }
+        Ok(())
+    }
+
+    fn finish(self, inputs: &[i32], outputs: &[i32]) -> Vec<u8> {
+        let codes = self
+            .codes
+            .iter()
+            .map(|c| Obj(vec![scalar(*c.min(&127) as i8), Field::Absent, scalar(1i32), scalar(*c)]))
+            .collect();
+        let subgraph = Obj(vec![
+            Field::Tables(self.tensors),
+            vector(inputs),
+            vector(outputs),
+            Field::Tables(self.operators),
+            string("main"),
+        ]);
+        let model = Obj(vec![
+            scalar(3u32),
+            Field::Tables(codes),
+            Field::Tables(vec![subgraph]),
+            string("tract"),
+            Field::Tables(self.buffers),
+        ]);
+        finish(&model, schema::FILE_IDENTIFIER)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tract_hir::tract_core::ops::cnn::KernelFormat;
+
+    fn check(model: TypedModel, input: Tensor) -> TractResult<()> {
+        let model = model.into_decluttered()?;
+        let expected = model.clone().into_runnable()?.run(tvec!(input.clone()))?;
+        let bytes = crate::tflite().write_to_bytes(&model)?;
+        let reloaded = crate::tflite().model_for_bytes(&bytes)?.into_optimized()?;
+        let found = reloaded.into_runnable()?.run(tvec!(input))?;
+        found[0].close_enough(&expected[0], true)
+    }
+
+    fn data(shape: &[usize]) -> TractResult<Tensor> {
+        let len = shape.iter().product::<usize>();
+        Ok(tensor1(&(0..len).map(|i| ((i * 7) % 11) as f32 / 11.0 - 0.5).collect::<Vec<_>>())
+            .into_shape(shape)?)
+    }
+
+    fn conv(group: usize, ci: usize, co: usize, padding: PaddingSpec) -> TractResult<ConvUnary> {
+        Ok(ConvUnary::new(
+            PoolSpec::new(DataFormat::NHWC, tvec!(3, 3), padding, None, None, Some(co)),
+            KernelFormat::OIHW,
+            data(&[co, ci / group, 3, 3])?.into_arc_tensor(),
+            group,
+            Some(data(&[co])?.into_arc_tensor()),
+            None,
+        ))
+    }
+
+    #[test]
+    fn conv_fc_softmax() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[1, 5, 5, 2]))?;
+        let wire = model.wire_node("conv", conv(1, 2, 3, PaddingSpec::Valid)?, &[source])?;
+        let zero = tensor0(0f32).broadcast_into_rank(4)?.into_arc_tensor();
+        let wire = model.wire_node("relu", math::max::unary(zero), &wire)?;
+        let reshape =
+            AxisOp::Reshape(1, tvec!(3.to_dim(), 3.to_dim(), 3.to_dim()), tvec!(27.to_dim()));
+        let wire = model.wire_node("flatten", reshape, &wire)?;
+        let fc = MatMulUnary::new(data(&[4, 27])?.into_arc_tensor(), false, true, true);
+        let wire = model.wire_node("fc", fc, &wire)?;
+        let wire = model.wire_node("softmax", Softmax::new(tvec!(1), f32::datum_type()), &wire)?;
+        model.set_output_outlets(&wire)?;
+        check(model, data(&[1, 5, 5, 2])?)
+    }
+
+    #[test]
+    fn depthwise_and_pools() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[1, 6, 6, 2]))?;
+        let wire = model.wire_node("dw", conv(2, 2, 4, PaddingSpec::SameUpper)?, &[source])?;
+        let spec = PoolSpec::new(
+            DataFormat::NHWC,
+            tvec!(2, 2),
+            PaddingSpec::Valid,
+            None,
+            Some(tvec!(2, 2)),
+            None,
+        );
+        let wire = model.wire_node("max", MaxPool::new(spec, None), &wire)?;
+        let spec =
+            PoolSpec::new(DataFormat::NHWC, tvec!(3, 3), PaddingSpec::Valid, None, None, None);
+        let wire = model.wire_node("avg", SumPool::new(spec, false, true), &wire)?;
+        let wire = model.wire_node("tanh", math::tanh(), &wire)?;
+        model.set_output_outlets(&wire)?;
+        check(model, data(&[1, 6, 6, 2])?)
+    }
+
+    #[test]
+    fn unsupported_op() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let source = model.add_source("input", f32::fact(&[3]))?;
+        let wire = model.wire_node("exp", math::exp(), &[source])?;
+        model.set_output_outlets(&wire)?;
+        let err = crate::tflite().write_to_bytes(&model).unwrap_err();
+        assert!(format!("{:?}", err).contains("exp"));
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::schema;
+use crate::schema::tensor_type;
+use tract_hir::internal::*;
+
+/// Datum type of a tensor, quantized types carrying their zero point and scale.
+///
+/// Only 8-bit tensors are mapped to quantized types: int32 biases in
+/// quantized models also have quantization parameters, but tract expects them
+/// as plain i32.
+pub fn datum_type(tensor: &schema::Tensor) -> TractResult<DatumType> {
+    let dt = match tensor.tensor_type()? {
+        tensor_type::FLOAT32 => f32::datum_type(),
+        tensor_type::FLOAT16 => f16::datum_type(),
+        tensor_type::FLOAT64 => f64::datum_type(),
+        tensor_type::INT32 => i32::datum_type(),
+        tensor_type::INT64 => i64::datum_type(),
+        tensor_type::INT16 => i16::datum_type(),
+        tensor_type::UINT8 => u8::datum_type(),
+        tensor_type::INT8 => i8::datum_type(),
+        tensor_type::BOOL => bool::datum_type(),
+        tensor_type::STRING => String::datum_type(),
+        other => bail!("Unsupported tensor type {} for {}", other, tensor.name()?),
+    };
+    if dt != u8::datum_type() && dt != i8::datum_type() {
+        return Ok(dt);
+    }
+    if let Some(quant) = tensor.quantization()? {
+        let scales = quant.scale()?;
+        let zero_points = quant.zero_point()?;
+        if scales.is_empty() {
+            return Ok(dt);
+        }
+        if scales.iter().any(|s| *s != scales[0])
+            || zero_points.iter().any(|z| *z != zero_points[0])
+        {
+            bail!("Per-channel quantization is not supported (on {})", tensor.name()?)
+        }
+        let qp = QParams::ZpScale {
+            zero_point: zero_points.get(0).cloned().unwrap_or(0) as i32,
+            scale: scales[0],
+        };
+        Ok(if dt == u8::datum_type() { DatumType::QU8(qp) } else { DatumType::QI8(qp) })
+    } else {
+        Ok(dt)
+    }
+}
+
+pub fn shape(tensor: &schema::Tensor) -> TractResult<TVec<usize>> {
+    tensor
+        .shape()?
+        .iter()
+        .map(|d| {
+            if *d < 0 {
+                bail!("Negative dimension in {}", tensor.name()?)
+            }
+            Ok(*d as usize)
+        })
+        .collect()
+}
+
+pub fn fact(tensor: &schema::Tensor) -> TractResult<InferenceFact> {
+    Ok(InferenceFact::dt_shape(datum_type(tensor)?, &*shape(tensor)?))
+}
+
+/// Builds a constant tensor from the content of its buffer.
+pub fn konst(tensor: &schema::Tensor, data: &[u8]) -> TractResult<Tensor> {
+    let dt = datum_type(tensor)?;
+    if dt == String::datum_type() {
+        bail!("String constants are not supported ({})", tensor.name()?)
+    }
+    let shape = shape(tensor)?;
+    let len = shape.iter().product::<usize>();
+    if data.len() != len * dt.size_of() {
+        bail!(
+            "Buffer for {} is {} bytes long, expected {} for {:?} {:?}",
+            tensor.name()?,
+            data.len(),
+            len * dt.size_of(),
+            dt,
+            shape
+        )
+    }
+    unsafe { Tensor::from_raw_dt(dt, &shape, data) }
+}